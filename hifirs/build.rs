@@ -1,5 +1,44 @@
+use std::{env, fs, path::Path};
+
 // generated by `sqlx migrate build-script`
 fn main() {
     // trigger recompilation when a new migration is added
     println!("cargo:rerun-if-changed=migrations");
+    println!("cargo:rerun-if-changed=CHANGELOG.md");
+
+    release_notes();
+}
+
+/// Turn CHANGELOG.md into a list of releases that is included in the binary.
+fn release_notes() {
+    let changelog = fs::read_to_string("CHANGELOG.md").expect("failed to read CHANGELOG.md");
+    let mut releases: Vec<(String, Vec<String>)> = Vec::new();
+
+    for line in changelog.lines() {
+        if let Some(version) = line.strip_prefix("## ") {
+            releases.push((version.trim().to_string(), Vec::new()));
+        } else if let Some(note) = line.strip_prefix("- ") {
+            if let Some((_, notes)) = releases.last_mut() {
+                notes.push(note.trim().to_string());
+            }
+        } else if line.starts_with("  ") && !line.trim().is_empty() {
+            if let Some(note) = releases.last_mut().and_then(|(_, notes)| notes.last_mut()) {
+                note.push(' ');
+                note.push_str(line.trim());
+            }
+        }
+    }
+
+    let mut code = String::from("pub static RELEASES: &[Release] = &[\n");
+
+    for (version, notes) in releases {
+        code.push_str(&format!(
+            "    Release {{ version: {version:?}, notes: &{notes:?} }},\n"
+        ));
+    }
+
+    code.push_str("];\n");
+
+    let out = Path::new(&env::var("OUT_DIR").unwrap()).join("release_notes.rs");
+    fs::write(out, code).expect("failed to write release notes");
 }
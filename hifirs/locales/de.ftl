@@ -0,0 +1,246 @@
+## Screens and menus
+now-playing = Aktuelle Wiedergabe
+my-playlists = Meine Playlists
+search = Suche
+enter-url = URL eingeben
+remote-clients = Entfernte Clients
+remote-clients-panel = entfernte Clients
+no-remote-clients = keine entfernten Clients verbunden
+favorites = Favoriten
+favorites-panel = Favoriten
+favorites-keys = Enter zum Abspielen, a zum Einreihen in die Warteschlange, r zum Entfernen aus den Favoriten
+playlist-keys = Enter zum Abspielen, d zum Entfernen aus der Playlist, K und J zum Verschieben nach oben und unten
+personal-weekly = Weekly Q
+personal-daily = Tägliche Entdeckungen
+made-for-you = für dich
+personal-playlist-unavailable = Diese Playlist konnte nicht geladen werden.
+browse = Entdecken
+discover = Empfehlungen
+featured-playlists = ausgewählte Playlists
+cached-tracks = Zwischengespeicherte Titel
+cached-tracks-panel = zwischengespeicherte Titel
+release-calendar = Veröffentlichungskalender
+release-calendar-panel = Veröffentlichungen gefolgter Künstler
+history = Verlauf
+saved-queues = Gespeicherte Warteschlangen
+history-recent-panel = zuletzt gespielt
+history-most-played-panel = am häufigsten gespielt
+
+## Panels
+player-panel = Player
+my-playlists-panel = meine Playlists
+search-panel = Suche
+results-panel = Ergebnisse
+track-count-separator = von
+shuffle-indicator = Zufallswiedergabe
+autoplay-indicator = Autoplay
+volume-indicator = Lautstärke { $volume } %
+list-progress = Titel { $track }/{ $total } – { $elapsed } von { $duration }
+
+## Search
+search-albums = Alben
+search-artists = Künstler
+search-tracks = Titel
+search-playlists = Playlists
+search-keys = Enter zum Abspielen, a zum Einreihen in die Warteschlange, F zum Hinzufügen zu den Favoriten, f zum Filtern
+added-to-queue = Zur Warteschlange hinzugefügt.
+favorite-added = Zu den Favoriten hinzugefügt.
+favorite-removed = Aus den Favoriten entfernt.
+search-filter = Ergebnisse filtern
+filter-min-year = Erschienen ab
+filter-genre = Genre
+filter-since = ab { $year }
+search-filtered = Gefiltert: { $filters }
+apply-filter = Anwenden
+clear-filter = Zurücksetzen
+invalid-year = { $year } ist kein Jahr.
+
+## Favorites
+sort-recently-added = Zuletzt hinzugefügt
+sort-artist = Künstler
+sort-release-year = Erscheinungsjahr
+filter-hires = nur Hi-Res
+filter-unplayed = nur ungehörte
+
+## Browse
+browse-new-releases = Neuerscheinungen
+browse-most-streamed = Meistgestreamt
+browse-press-awards = Auszeichnungen der Presse
+browse-editor-picks = Empfehlungen der Redaktion
+browse-best-sellers = Bestseller
+browse-page = Seite { $page } von { $pages }
+all-genres = Alle Genres
+
+## Release calendar
+calendar-hint = Enter spielt eine erschienene Veröffentlichung ab oder beobachtet eine kommende.
+calendar-refresh = Aktualisieren
+calendar-empty = Keine kommenden oder neuen Veröffentlichungen der Künstler, denen du folgst.
+calendar-refresh-failed = Die Veröffentlichungen konnten nicht abgerufen werden, versuche es später noch einmal.
+
+## History
+history-empty = Es wurde noch nichts gespielt.
+
+## Cache
+filter-cached-only = nur zwischengespeicherte Titel abspielen
+play-all = Alle abspielen
+offline-indicator = offline, Wiedergabe aus dem Zwischenspeicher
+service-outage = Qobuz ist nicht erreichbar ({ $status }), neuer Versuch in { $seconds } s. Die Warteschlange bleibt erhalten.
+account-limited = dein Konto ist auf { $quality } beschränkt · hifi-rs config set default-quality { $quality }
+quality-fallback = ↓ { $quality }
+stats-bitrate = { $kbps } kbit/s
+stats-line = { $bitrate } · Puffer { $buffer }% · { $rate } kB/s · Titel { $track } · Sitzung { $session }
+lyrics = Songtext
+lyrics-instrumental = ♪ instrumental
+
+## Event log
+event-log-panel = Ereignisprotokoll
+event-filter = Filter:
+event-dump = exportieren
+event-dump-saved = { $count } Ereignisse in { $path } gespeichert
+event-dump-failed = Ereignisprotokoll konnte nicht gespeichert werden: { $error }
+gapless-stats = lückenlos: { $prepared } vorbereitet, { $retried } nach erneutem Versuch, { $failed } mit Pause, { $prefetched } mit vorab geholter URL
+api-latency = { $endpoint }: { $calls } Aufrufe, { $errors } fehlgeschlagen, p50 { $p50 } ms, p95 { $p95 } ms, max { $max } ms
+
+## Playlists
+select-playlist = Playlist auswählen
+playlist-tracks = { $count } Titel
+play = abspielen
+total-tracks = Titel insgesamt: { $count }
+track-playlists = Playlists mit { $title }
+track-playlist-remove = Aus { $title } entfernen
+track-playlist-add = Zu { $title } hinzufügen
+track-playlist-updated = Die Playlist wurde aktualisiert.
+new-playlist = neu
+delete-playlist = löschen
+playlist-name = Name:
+playlist-public = Öffentlich
+create = Erstellen
+delete = Löschen
+delete-playlist-prompt = { $title } löschen? Das kann nicht rückgängig gemacht werden.
+playlist-created = Die Playlist { $name } ({ $id }) wurde erstellt.
+playlist-create-failed = Die Playlist { $name } konnte nicht erstellt werden.
+playlist-deleted = Die Playlist { $id } wurde gelöscht.
+playlist-delete-failed = Die Playlist { $id } konnte nicht gelöscht werden.
+search-failed = Die Suche nach { $query } ist fehlgeschlagen.
+playlist-not-found = Es gibt keine Playlist { $id }.
+
+## Dialogs
+quit-prompt = Möchtest du das Programm beenden?
+yes = Ja
+no = Nein
+cancel = Abbrechen
+track-or-album = Titel oder Album?
+track = Titel
+album = Album
+album-from-track = Album ab hier
+switch-quality = Qualität wechseln
+next-quality = Qualität der nächsten Wiedergabe
+next-quality-help = Gilt für das nächste Album, die nächste Playlist oder den nächsten Titel, die Sitzungsqualität bleibt unverändert.
+session-quality = Sitzungsqualität
+output-profiles = Ausgabeprofile
+no-output-profiles = Keine Ausgabeprofile gespeichert. Füge eines mit `hifi-rs profile add` hinzu.
+output-devices = Ausgabegerät
+no-output-devices = Keine Audioausgänge gefunden.
+system-default = Systemstandard
+reload-config = Konfiguration neu laden
+config-unchanged = Die Konfiguration hat sich nicht geändert.
+config-reloaded = Neu geladen: { $changed }
+config-needs-restart = Starte hifi-rs neu, um Folgendes zu übernehmen: { $changed }
+config-reload-failed = Die Konfiguration konnte nicht neu geladen werden: { $error }
+close = Schließen
+no-description = Keine Beschreibung verfügbar.
+watch-release-prompt = { $title } ist noch nicht verfügbar. Möchtest du benachrichtigt werden, sobald es gestreamt werden kann?
+release-available = { $title } von { $artist } ist jetzt verfügbar.
+track-abandoned = { $title } wurde nach { $attempts } fehlgeschlagenen Versuchen übersprungen. { $errors } Fehler und { $abandoned } übersprungene Titel in dieser Sitzung.
+track-unavailable = { $title } ist beim Dienst nicht mehr verfügbar und wurde übersprungen.
+track-blocked = { $title } kann hier gerade nicht gestreamt werden und wurde übersprungen.
+
+## Lists
+by = von
+available-on = verfügbar ab { $date }
+
+## Command line
+username-prompt = Benutzername / E-Mail eingeben
+username-saved = Benutzername gespeichert.
+password-prompt = Passwort eingeben (verborgen)
+password-saved = Passwort gespeichert.
+token-prompt = Gib dein Authentifizierungstoken ein (verborgen)
+token-saved = Token gespeichert.
+quality-saved = Standardqualität gespeichert.
+config-saved = { $key } gespeichert.
+language-saved = Sprache gespeichert.
+clear-prompt = Dies löscht die Konfiguration in der Datenbank.
+    Möchtest du fortfahren?
+database-cleared = Datenbank gelöscht.
+profile-saved = Ausgabeprofil { $name } gespeichert.
+profile-removed = Ausgabeprofil { $name } entfernt.
+profile-active = Wiedergabe über { $name }.
+output-device-active = Wiedergabe ab jetzt über { $device }.
+output-device-default = Wiedergabe ab jetzt über den Standardausgang des Systems.
+profile-not-found = Es gibt kein Ausgabeprofil namens { $name }.
+queue-name = Warteschlange speichern als
+save-queue = Speichern
+queue-saved = Warteschlange als { $name } gespeichert.
+queue-removed = Gespeicherte Warteschlange { $name } entfernt.
+queue-not-found = Es gibt keine gespeicherte Warteschlange namens { $name }.
+no-saved-queues = Noch keine Warteschlangen gespeichert.
+profile-invalid-volume = Die Lautstärke muss zwischen 0.0 und 1.0 liegen.
+no-trims = Keine Kürzungen gespeichert. Füge eine mit `hifi-rs trim set` hinzu.
+trim-saved = Kürzung für { $kind } { $id } gespeichert.
+trim-removed = Kürzung für { $kind } { $id } entfernt.
+trim-not-found = Es gibt keine Kürzung für { $kind } { $id }.
+trim-empty = Gib --start, --end oder beides an.
+trim-unknown = Es gibt kein { $kind } { $id } beim Dienst.
+trim-too-long = --start und --end zusammen schneiden { $title } ganz weg, es ist { $duration } Sekunden lang.
+no-schedules = Keine Zeitpläne gespeichert. Füge einen mit `hifi-rs schedule add` hinzu.
+schedule-saved = Zeitplan { $name } gespeichert, nächster Start { $next }.
+schedule-removed = Zeitplan { $name } entfernt.
+schedule-not-found = Es gibt keinen Zeitplan namens { $name }.
+schedule-invalid = { $spec } ist kein gültiger Zeitplan: { $error }
+cache-purged = { $count } Titel aus dem Zwischenspeicher entfernt, { $size } MB frei.
+ctl-unreachable = Unter { $address } hat kein Player geantwortet. Starte ihn mit --web oder gib --address an.
+ctl-no-answer = Der Player hat nicht geantwortet.
+ctl-invalid-seek = { $value } ist keine Position. Verwende +30, -10, 90 oder 50%.
+ctl-nothing-playing = Es wird nichts abgespielt.
+ctl-status = { $state }: { $title } von { $artist } · { $position } / { $duration } · Lautstärke { $volume } %
+session-summary = Diese Sitzung: { $tracks } Titel, { $time } gehört, etwa { $data } gestreamt, { $rebuffers } Pufferungen.
+tls-generated = Zertifikat unter { $cert } und Schlüssel unter { $key } gespeichert. Starte den Player neu, um HTTPS zu verwenden.
+tls-disabled = Der Webserver verwendet nach einem Neustart wieder einfaches HTTP.
+scrobble-api-key-prompt = Last.fm-API-Schlüssel
+scrobble-api-secret-prompt = Last.fm-API-Secret
+scrobble-token-prompt = ListenBrainz-Benutzertoken
+scrobble-authorize = Erlaube hifi-rs, an dein Konto zu scrobbeln: { $url }
+scrobble-authorized-prompt = Erlaubt?
+scrobble-linked = { $service } als { $user } verknüpft. Ab dem nächsten Start wird gescrobbelt.
+scrobble-unlinked = Es wird nicht mehr an { $service } gescrobbelt.
+scrobble-status-linked = { $service }: verknüpft
+scrobble-status-unlinked = { $service }: nicht verknüpft
+changelog-empty = Keine Versionshinweise für diese Versionen.
+whats-new = Was ist neu
+
+## Party mode
+party-mode = Partymodus
+party-mode-indicator = Partymodus
+party-mode-prompt = Partymodus einschalten? Bis er mit der PIN ausgeschaltet wird, funktionieren nur Wiedergabe, Pause, Weiter und die Lautstärke.
+party-mode-pin = PIN eingeben
+party-mode-locked = Der Partymodus ist eingeschaltet.
+
+## Simple mode
+simple-mode-playlists = Playlists
+simple-mode-keys = Leertaste Wiedergabe/Pause · N weiter · U entsperren
+simple-mode-locked = Der einfache Modus ist eingeschaltet. Mit U und der PIN wird er verlassen.
+
+## Audition
+audition = probehören
+audition-indicator = probehören · { $count } zu entfernen · k behalten · x entfernen
+audition-confirm = Diese { $count } Titel aus der Playlist entfernen?
+audition-remove = Entfernen
+audition-removed = { $count } Titel aus der Playlist entfernt.
+audition-all-kept = Alle Titel wurden behalten.
+
+## Library
+library-search = Bibliothek durchsuchen
+library-no-results = Nichts in Favoriten, Playlists oder Verlauf passt.
+library-album = Album
+library-track = Titel
+library-playlist = Playlist
@@ -0,0 +1,248 @@
+# English strings. This is the fallback catalog, every key must exist here.
+
+## Screens and menus
+now-playing = Now Playing
+my-playlists = My Playlists
+search = Search
+enter-url = Enter URL
+remote-clients = Remote Clients
+remote-clients-panel = remote clients
+no-remote-clients = no remote clients connected
+favorites = Favorites
+favorites-panel = favorites
+favorites-keys = enter to play, a to add to the queue, r to remove from your favorites
+playlist-keys = enter to play, d to remove from the playlist, K and J to move up and down
+personal-weekly = Weekly Q
+personal-daily = Daily discovery
+made-for-you = made for you
+personal-playlist-unavailable = This playlist could not be loaded.
+browse = Browse
+discover = Discover
+featured-playlists = featured playlists
+cached-tracks = Cached Tracks
+cached-tracks-panel = cached tracks
+release-calendar = Release Calendar
+release-calendar-panel = releases by artists you follow
+history = History
+saved-queues = Saved queues
+history-recent-panel = played last
+history-most-played-panel = played most
+
+## Panels
+player-panel = player
+my-playlists-panel = my playlists
+search-panel = search
+results-panel = results
+track-count-separator = of
+shuffle-indicator = shuffle
+autoplay-indicator = autoplay
+volume-indicator = volume { $volume }%
+list-progress = track { $track }/{ $total } – { $elapsed } of { $duration }
+
+## Search
+search-albums = Albums
+search-artists = Artists
+search-tracks = Tracks
+search-playlists = Playlists
+search-keys = enter to play, a to add to the queue, F to add to your favorites, f to filter
+added-to-queue = Added to the queue.
+favorite-added = Added to your favorites.
+favorite-removed = Removed from your favorites.
+search-filter = Filter results
+filter-min-year = Released in or after
+filter-genre = Genre
+filter-since = since { $year }
+search-filtered = Filtered: { $filters }
+apply-filter = Apply
+clear-filter = Clear
+invalid-year = { $year } is not a year.
+
+## Favorites
+sort-recently-added = Recently added
+sort-artist = Artist
+sort-release-year = Release year
+filter-hires = hi-res only
+filter-unplayed = unplayed only
+
+## Browse
+browse-new-releases = New releases
+browse-most-streamed = Most streamed
+browse-press-awards = Press awards
+browse-editor-picks = Editor's picks
+browse-best-sellers = Best sellers
+browse-page = page { $page } of { $pages }
+all-genres = All genres
+
+## Release calendar
+calendar-hint = Enter plays a release that is out, or watches one that is upcoming.
+calendar-refresh = Refresh
+calendar-empty = No upcoming or recent releases by the artists you follow.
+calendar-refresh-failed = Could not fetch the releases, try again later.
+
+## History
+history-empty = Nothing has been played yet.
+
+## Cache
+filter-cached-only = play cached tracks only
+play-all = Play all
+offline-indicator = offline, playing from the cache
+service-outage = Qobuz is unavailable ({ $status }), checking again in { $seconds }s. The queue is kept.
+account-limited = your account is limited to { $quality } · hifi-rs config set default-quality { $quality }
+quality-fallback = ↓ { $quality }
+stats-bitrate = { $kbps } kbps
+stats-line = { $bitrate } · buffer { $buffer }% · { $rate } kB/s · track { $track } · session { $session }
+lyrics = Lyrics
+lyrics-instrumental = ♪ instrumental
+
+## Event log
+event-log-panel = event log
+event-filter = filter:
+event-dump = dump
+event-dump-saved = Saved { $count } events to { $path }
+event-dump-failed = Failed to save the event log: { $error }
+gapless-stats = gapless: { $prepared } prepared, { $retried } after a retry, { $failed } fell back to a gap, { $prefetched } started from a prefetched url
+api-latency = { $endpoint }: { $calls } calls, { $errors } failed, p50 { $p50 } ms, p95 { $p95 } ms, max { $max } ms
+
+## Playlists
+select-playlist = Select Playlist
+playlist-tracks = { $count } tracks
+play = play
+total-tracks = total tracks: { $count }
+track-playlists = Playlists with { $title }
+track-playlist-remove = Remove from { $title }
+track-playlist-add = Add to { $title }
+track-playlist-updated = The playlist was updated.
+new-playlist = new
+delete-playlist = delete
+playlist-name = Name:
+playlist-public = Public
+create = Create
+delete = Delete
+delete-playlist-prompt = Delete { $title }? This can't be undone.
+playlist-created = Created the playlist { $name } ({ $id }).
+playlist-create-failed = Could not create the playlist { $name }.
+playlist-deleted = Deleted the playlist { $id }.
+playlist-delete-failed = Could not delete the playlist { $id }.
+search-failed = The search for { $query } failed.
+playlist-not-found = There is no playlist { $id }.
+
+## Dialogs
+quit-prompt = Do you want to quit?
+yes = Yes
+no = No
+cancel = Cancel
+track-or-album = Track or album?
+track = Track
+album = Album
+album-from-track = Album from here
+switch-quality = Switch quality
+next-quality = Quality of the next play
+next-quality-help = Used for the next album, playlist or track you play, the session quality stays as it is.
+session-quality = Session quality
+output-profiles = Output profiles
+no-output-profiles = No output profiles saved. Add one with `hifi-rs profile add`.
+output-devices = Output device
+no-output-devices = No audio outputs found.
+system-default = System default
+reload-config = Reload config
+config-unchanged = The config has not changed.
+config-reloaded = Reloaded: { $changed }
+config-needs-restart = Restart hifi-rs to apply: { $changed }
+config-reload-failed = Could not reload the config: { $error }
+close = Close
+no-description = No description available.
+watch-release-prompt = { $title } is not available yet. Do you want to be notified when it can be streamed?
+release-available = { $title } by { $artist } is now available.
+track-abandoned = Skipped { $title } after { $attempts } failed attempts. { $errors } errors and { $abandoned } skipped tracks this session.
+track-unavailable = { $title } is no longer available on the service and was skipped.
+track-blocked = { $title } can't be streamed here right now and was skipped.
+
+## Lists
+by = by
+available-on = available on { $date }
+
+## Command line
+username-prompt = Enter your username / email
+username-saved = Username saved.
+password-prompt = Enter your password (hidden)
+password-saved = Password saved.
+token-prompt = Enter your user auth token (hidden)
+token-saved = Token saved.
+quality-saved = Default quality saved.
+config-saved = { $key } saved.
+language-saved = Language saved.
+clear-prompt = This will clear the configuration in the database.
+    Do you want to continue?
+database-cleared = Database cleared.
+profile-saved = Output profile { $name } saved.
+profile-removed = Output profile { $name } removed.
+profile-active = Now playing through { $name }.
+output-device-active = Playing through { $device } from now on.
+output-device-default = Playing through the system default output from now on.
+profile-not-found = There is no output profile named { $name }.
+queue-name = Save the queue as
+save-queue = Save
+queue-saved = Queue saved as { $name }.
+queue-removed = Saved queue { $name } removed.
+queue-not-found = There is no saved queue named { $name }.
+no-saved-queues = No queues saved yet.
+profile-invalid-volume = The volume must be between 0.0 and 1.0.
+no-trims = No trims saved. Add one with `hifi-rs trim set`.
+trim-saved = Trim for { $kind } { $id } saved.
+trim-removed = Trim for { $kind } { $id } removed.
+trim-not-found = There is no trim for { $kind } { $id }.
+trim-empty = Set --start, --end or both.
+trim-unknown = There is no { $kind } { $id } on the service.
+trim-too-long = --start and --end together trim all of { $title }, which is { $duration } seconds long.
+no-schedules = No schedules saved. Add one with `hifi-rs schedule add`.
+schedule-saved = Schedule { $name } saved, next start { $next }.
+schedule-removed = Schedule { $name } removed.
+schedule-not-found = There is no schedule named { $name }.
+schedule-invalid = { $spec } is not a valid schedule: { $error }
+cache-purged = Removed { $count } tracks from the cache, { $size } MB freed.
+ctl-unreachable = No player answered at { $address }. Start it with --web, or pass --address.
+ctl-no-answer = The player did not answer.
+ctl-invalid-seek = { $value } is not a position. Use +30, -10, 90 or 50%.
+ctl-nothing-playing = Nothing is playing.
+ctl-status = { $state }: { $title } by { $artist } · { $position } / { $duration } · volume { $volume }%
+session-summary = This session: { $tracks } tracks, { $time } listened, about { $data } streamed, { $rebuffers } rebuffers.
+tls-generated = Certificate saved to { $cert } and key to { $key }. Restart the player to serve over HTTPS.
+tls-disabled = The web server is back to plain HTTP after a restart.
+scrobble-api-key-prompt = Last.fm API key
+scrobble-api-secret-prompt = Last.fm API shared secret
+scrobble-token-prompt = ListenBrainz user token
+scrobble-authorize = Allow hifi-rs to scrobble to your account: { $url }
+scrobble-authorized-prompt = Allowed it?
+scrobble-linked = Linked { $service } as { $user }. Plays are scrobbled from the next start.
+scrobble-unlinked = No longer scrobbling to { $service }.
+scrobble-status-linked = { $service }: linked
+scrobble-status-unlinked = { $service }: not linked
+changelog-empty = No release notes for these versions.
+whats-new = What's new
+
+## Party mode
+party-mode = Party mode
+party-mode-indicator = party mode
+party-mode-prompt = Turn on party mode? Only play, pause, next and the volume will work until it is turned off with the PIN.
+party-mode-pin = Enter the PIN
+party-mode-locked = Party mode is on.
+
+## Simple mode
+simple-mode-playlists = Playlists
+simple-mode-keys = space play/pause · N next · U unlock
+simple-mode-locked = Simple mode is on. Press U and enter the PIN to leave it.
+
+## Audition
+audition = audition
+audition-indicator = auditioning · { $count } to remove · k keep · x remove
+audition-confirm = Remove these { $count } tracks from the playlist?
+audition-remove = Remove
+audition-removed = Removed { $count } tracks from the playlist.
+audition-all-kept = Every track was kept.
+
+## Library
+library-search = Search your library
+library-no-results = Nothing in your favorites, playlists or history matches.
+library-album = album
+library-track = track
+library-playlist = playlist
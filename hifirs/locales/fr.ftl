@@ -0,0 +1,246 @@
+## Screens and menus
+now-playing = En cours de lecture
+my-playlists = Mes playlists
+search = Recherche
+enter-url = Saisir une URL
+remote-clients = Clients distants
+remote-clients-panel = clients distants
+no-remote-clients = aucun client distant connecté
+favorites = Favoris
+favorites-panel = favoris
+favorites-keys = entrée pour lire, a pour ajouter à la file d'attente, r pour retirer des favoris
+playlist-keys = entrée pour lire, d pour retirer de la playlist, K et J pour monter et descendre
+personal-weekly = Weekly Q
+personal-daily = Découvertes du jour
+made-for-you = pour vous
+personal-playlist-unavailable = Cette playlist n'a pas pu être chargée.
+browse = Explorer
+discover = Découvrir
+featured-playlists = playlists à la une
+cached-tracks = Pistes en cache
+cached-tracks-panel = pistes en cache
+release-calendar = Calendrier des sorties
+release-calendar-panel = sorties des artistes suivis
+history = Historique
+saved-queues = Files d'attente enregistrées
+history-recent-panel = écoutés récemment
+history-most-played-panel = les plus écoutés
+
+## Panels
+player-panel = lecteur
+my-playlists-panel = mes playlists
+search-panel = recherche
+results-panel = résultats
+track-count-separator = sur
+shuffle-indicator = aléatoire
+autoplay-indicator = lecture auto
+volume-indicator = volume { $volume } %
+list-progress = titre { $track }/{ $total } – { $elapsed } sur { $duration }
+
+## Search
+search-albums = Albums
+search-artists = Artistes
+search-tracks = Titres
+search-playlists = Playlists
+search-keys = entrée pour lire, a pour ajouter à la file d'attente, F pour ajouter aux favoris, f pour filtrer
+added-to-queue = Ajouté à la file d'attente.
+favorite-added = Ajouté à vos favoris.
+favorite-removed = Retiré de vos favoris.
+search-filter = Filtrer les résultats
+filter-min-year = Sorti à partir de
+filter-genre = Genre
+filter-since = depuis { $year }
+search-filtered = Filtré : { $filters }
+apply-filter = Appliquer
+clear-filter = Effacer
+invalid-year = { $year } n'est pas une année.
+
+## Favorites
+sort-recently-added = Ajoutés récemment
+sort-artist = Artiste
+sort-release-year = Année de sortie
+filter-hires = hi-res uniquement
+filter-unplayed = non écoutés uniquement
+
+## Browse
+browse-new-releases = Nouveautés
+browse-most-streamed = Les plus écoutés
+browse-press-awards = Distinctions de la presse
+browse-editor-picks = Sélection de la rédaction
+browse-best-sellers = Meilleures ventes
+browse-page = page { $page } sur { $pages }
+all-genres = Tous les genres
+
+## Release calendar
+calendar-hint = Entrée lit une sortie disponible ou surveille une sortie à venir.
+calendar-refresh = Actualiser
+calendar-empty = Aucune sortie récente ou à venir pour les artistes suivis.
+calendar-refresh-failed = Impossible de récupérer les sorties, réessayez plus tard.
+
+## History
+history-empty = Rien n'a encore été écouté.
+
+## Cache
+filter-cached-only = lire uniquement les pistes en cache
+play-all = Tout lire
+offline-indicator = hors ligne, lecture depuis le cache
+service-outage = Qobuz est indisponible ({ $status }), nouvel essai dans { $seconds } s. La file d'attente est conservée.
+account-limited = votre compte est limité à { $quality } · hifi-rs config set default-quality { $quality }
+quality-fallback = ↓ { $quality }
+stats-bitrate = { $kbps } kbit/s
+stats-line = { $bitrate } · tampon { $buffer } % · { $rate } ko/s · piste { $track } · session { $session }
+lyrics = Paroles
+lyrics-instrumental = ♪ instrumental
+
+## Event log
+event-log-panel = journal des événements
+event-filter = filtre :
+event-dump = exporter
+event-dump-saved = { $count } événements enregistrés dans { $path }
+event-dump-failed = Impossible d'enregistrer le journal : { $error }
+gapless-stats = sans coupure : { $prepared } préparées, { $retried } après une nouvelle tentative, { $failed } avec une pause, { $prefetched } depuis une URL préchargée
+api-latency = { $endpoint } : { $calls } appels, { $errors } en échec, p50 { $p50 } ms, p95 { $p95 } ms, max { $max } ms
+
+## Playlists
+select-playlist = Choisir une playlist
+playlist-tracks = { $count } titres
+play = lire
+total-tracks = nombre de titres : { $count }
+track-playlists = Playlists avec { $title }
+track-playlist-remove = Retirer de { $title }
+track-playlist-add = Ajouter à { $title }
+track-playlist-updated = La playlist a été mise à jour.
+new-playlist = nouvelle
+delete-playlist = supprimer
+playlist-name = Nom :
+playlist-public = Publique
+create = Créer
+delete = Supprimer
+delete-playlist-prompt = Supprimer { $title } ? C'est définitif.
+playlist-created = La playlist { $name } ({ $id }) a été créée.
+playlist-create-failed = Impossible de créer la playlist { $name }.
+playlist-deleted = La playlist { $id } a été supprimée.
+playlist-delete-failed = Impossible de supprimer la playlist { $id }.
+search-failed = La recherche de { $query } a échoué.
+playlist-not-found = Il n'y a pas de playlist { $id }.
+
+## Dialogs
+quit-prompt = Voulez-vous quitter ?
+yes = Oui
+no = Non
+cancel = Annuler
+track-or-album = Titre ou album ?
+track = Titre
+album = Album
+album-from-track = Album à partir d'ici
+switch-quality = Changer de qualité
+next-quality = Qualité de la prochaine lecture
+next-quality-help = S'applique au prochain album, playlist ou titre lu, la qualité de la session ne change pas.
+session-quality = Qualité de la session
+output-profiles = Profils de sortie
+no-output-profiles = Aucun profil de sortie enregistré. Ajoutez-en un avec `hifi-rs profile add`.
+output-devices = Périphérique de sortie
+no-output-devices = Aucune sortie audio trouvée.
+system-default = Sortie par défaut
+reload-config = Recharger la configuration
+config-unchanged = La configuration n'a pas changé.
+config-reloaded = Rechargé : { $changed }
+config-needs-restart = Redémarrez hifi-rs pour appliquer : { $changed }
+config-reload-failed = Impossible de recharger la configuration : { $error }
+close = Fermer
+no-description = Aucune description disponible.
+watch-release-prompt = { $title } n'est pas encore disponible. Voulez-vous être averti lorsqu'il pourra être écouté ?
+release-available = { $title } de { $artist } est maintenant disponible.
+track-abandoned = { $title } a été ignoré après { $attempts } tentatives échouées. { $errors } erreurs et { $abandoned } titres ignorés pendant cette session.
+track-unavailable = { $title } n'est plus disponible sur le service et a été ignoré.
+track-blocked = { $title } ne peut pas être écouté ici pour le moment et a été ignoré.
+
+## Lists
+by = par
+available-on = disponible le { $date }
+
+## Command line
+username-prompt = Saisissez votre identifiant / e-mail
+username-saved = Identifiant enregistré.
+password-prompt = Saisissez votre mot de passe (masqué)
+password-saved = Mot de passe enregistré.
+token-prompt = Entrez votre jeton d'authentification (masqué)
+token-saved = Jeton enregistré.
+quality-saved = Qualité par défaut enregistrée.
+config-saved = { $key } enregistré.
+language-saved = Langue enregistrée.
+clear-prompt = Ceci effacera la configuration enregistrée dans la base de données.
+    Voulez-vous continuer ?
+database-cleared = Base de données effacée.
+profile-saved = Profil de sortie { $name } enregistré.
+profile-removed = Profil de sortie { $name } supprimé.
+profile-active = Lecture via { $name }.
+output-device-active = Lecture via { $device } désormais.
+output-device-default = Lecture via la sortie par défaut du système désormais.
+profile-not-found = Aucun profil de sortie nommé { $name }.
+queue-name = Enregistrer la file d'attente sous
+save-queue = Enregistrer
+queue-saved = File d'attente enregistrée sous { $name }.
+queue-removed = File d'attente { $name } supprimée.
+queue-not-found = Aucune file d'attente enregistrée nommée { $name }.
+no-saved-queues = Aucune file d'attente enregistrée pour l'instant.
+profile-invalid-volume = Le volume doit être compris entre 0.0 et 1.0.
+no-trims = Aucune coupe enregistrée. Ajoutez-en une avec `hifi-rs trim set`.
+trim-saved = Coupe pour { $kind } { $id } enregistrée.
+trim-removed = Coupe pour { $kind } { $id } supprimée.
+trim-not-found = Aucune coupe pour { $kind } { $id }.
+trim-empty = Indiquez --start, --end ou les deux.
+trim-unknown = Il n'y a pas de { $kind } { $id } sur le service.
+trim-too-long = --start et --end ensemble coupent tout { $title }, qui dure { $duration } secondes.
+no-schedules = Aucune programmation enregistrée. Ajoutez-en une avec `hifi-rs schedule add`.
+schedule-saved = Programmation { $name } enregistrée, prochain démarrage { $next }.
+schedule-removed = Programmation { $name } supprimée.
+schedule-not-found = Il n'y a pas de programmation nommée { $name }.
+schedule-invalid = { $spec } n'est pas une programmation valide : { $error }
+cache-purged = { $count } pistes supprimées du cache, { $size } Mo libérés.
+ctl-unreachable = Aucun lecteur n'a répondu à { $address }. Lancez-le avec --web, ou indiquez --address.
+ctl-no-answer = Le lecteur n'a pas répondu.
+ctl-invalid-seek = { $value } n'est pas une position. Utilisez +30, -10, 90 ou 50%.
+ctl-nothing-playing = Rien n'est en lecture.
+ctl-status = { $state } : { $title } par { $artist } · { $position } / { $duration } · volume { $volume } %
+session-summary = Cette session : { $tracks } titres, { $time } d'écoute, environ { $data } diffusés, { $rebuffers } mises en mémoire tampon.
+tls-generated = Certificat enregistré dans { $cert } et clé dans { $key }. Redémarrez le lecteur pour servir en HTTPS.
+tls-disabled = Le serveur web repassera en HTTP simple au prochain démarrage.
+scrobble-api-key-prompt = Clé d'API Last.fm
+scrobble-api-secret-prompt = Secret partagé de l'API Last.fm
+scrobble-token-prompt = Jeton utilisateur ListenBrainz
+scrobble-authorize = Autorisez hifi-rs à scrobbler sur votre compte : { $url }
+scrobble-authorized-prompt = Autorisé ?
+scrobble-linked = { $service } lié en tant que { $user }. Les écoutes seront scrobblées au prochain démarrage.
+scrobble-unlinked = Plus de scrobbles vers { $service }.
+scrobble-status-linked = { $service } : lié
+scrobble-status-unlinked = { $service } : non lié
+changelog-empty = Aucune note de version pour ces versions.
+whats-new = Nouveautés
+
+## Party mode
+party-mode = Mode fête
+party-mode-indicator = mode fête
+party-mode-prompt = Activer le mode fête ? Seuls lecture, pause, suivant et le volume fonctionneront jusqu'à sa désactivation avec le code PIN.
+party-mode-pin = Saisissez le code PIN
+party-mode-locked = Le mode fête est activé.
+
+## Simple mode
+simple-mode-playlists = Playlists
+simple-mode-keys = espace lecture/pause · N suivant · U déverrouiller
+simple-mode-locked = Le mode simple est activé. Appuyez sur U et saisissez le code PIN pour le quitter.
+
+## Audition
+audition = audition
+audition-indicator = audition · { $count } à retirer · k garder · x retirer
+audition-confirm = Retirer ces { $count } titres de la playlist ?
+audition-remove = Retirer
+audition-removed = { $count } titres retirés de la playlist.
+audition-all-kept = Tous les titres ont été gardés.
+
+## Library
+library-search = Chercher dans votre bibliothèque
+library-no-results = Rien ne correspond dans vos favoris, playlists ou votre historique.
+library-album = album
+library-track = titre
+library-playlist = playlist
@@ -0,0 +1,3 @@
+ALTER TABLE "config" DROP COLUMN "theme";
+ALTER TABLE "config" DROP COLUMN "web_port";
+ALTER TABLE "config" DROP COLUMN "resume";
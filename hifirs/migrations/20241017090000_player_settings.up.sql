@@ -0,0 +1,3 @@
+ALTER TABLE "config" ADD COLUMN "resume" INTEGER;
+ALTER TABLE "config" ADD COLUMN "web_port" INTEGER;
+ALTER TABLE "config" ADD COLUMN "theme" TEXT;
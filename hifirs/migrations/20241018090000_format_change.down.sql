@@ -0,0 +1,2 @@
+ALTER TABLE "config" DROP COLUMN "resample_rate";
+ALTER TABLE "config" DROP COLUMN "format_change";
@@ -0,0 +1,2 @@
+ALTER TABLE "config" ADD COLUMN "format_change" TEXT;
+ALTER TABLE "config" ADD COLUMN "resample_rate" INTEGER;
@@ -0,0 +1,6 @@
+CREATE TABLE IF NOT EXISTS "favorite_albums" (
+ "album_id" TEXT NOT NULL UNIQUE,
+ "position" INTEGER NOT NULL,
+ "album" TEXT NOT NULL,
+ PRIMARY KEY("album_id")
+);
@@ -0,0 +1,7 @@
+CREATE TABLE IF NOT EXISTS "release_watch" (
+ "album_id" TEXT NOT NULL UNIQUE,
+ "title" TEXT NOT NULL,
+ "artist" TEXT NOT NULL,
+ "streamable_at" INTEGER,
+ PRIMARY KEY("album_id")
+);
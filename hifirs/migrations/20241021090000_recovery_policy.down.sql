@@ -0,0 +1,2 @@
+ALTER TABLE "config" DROP COLUMN "retry_backoff";
+ALTER TABLE "config" DROP COLUMN "max_retries";
@@ -0,0 +1,2 @@
+ALTER TABLE "config" ADD COLUMN "max_retries" INTEGER;
+ALTER TABLE "config" ADD COLUMN "retry_backoff" INTEGER;
@@ -0,0 +1 @@
+ALTER TABLE "config" ADD COLUMN "play_log" TEXT;
@@ -0,0 +1,2 @@
+ALTER TABLE "config" DROP COLUMN "output_profile";
+DROP TABLE IF EXISTS "output_profiles";
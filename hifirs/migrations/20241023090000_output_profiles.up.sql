@@ -0,0 +1,9 @@
+CREATE TABLE IF NOT EXISTS "output_profiles" (
+ "name" TEXT NOT NULL UNIQUE,
+ "device" TEXT,
+ "bit_perfect" INTEGER NOT NULL DEFAULT 0,
+ "volume" REAL NOT NULL DEFAULT 1.0,
+ "eq_preset" TEXT NOT NULL DEFAULT 'flat',
+ PRIMARY KEY("name")
+);
+ALTER TABLE "config" ADD COLUMN "output_profile" TEXT;
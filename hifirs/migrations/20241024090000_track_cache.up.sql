@@ -0,0 +1,9 @@
+CREATE TABLE IF NOT EXISTS "cached_tracks" (
+ "track_id" INTEGER NOT NULL,
+ "quality" INTEGER NOT NULL,
+ "path" TEXT NOT NULL,
+ "track" TEXT NOT NULL,
+ "size" INTEGER NOT NULL DEFAULT 0,
+ "cached_at" TEXT NOT NULL,
+ PRIMARY KEY("track_id", "quality")
+);
@@ -0,0 +1,7 @@
+CREATE TABLE IF NOT EXISTS "trims" (
+ "kind" TEXT NOT NULL,
+ "id" TEXT NOT NULL,
+ "start_secs" INTEGER NOT NULL DEFAULT 0,
+ "end_secs" INTEGER NOT NULL DEFAULT 0,
+ PRIMARY KEY("kind", "id")
+);
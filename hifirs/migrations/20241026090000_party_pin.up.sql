@@ -0,0 +1 @@
+ALTER TABLE "config" ADD COLUMN "party_pin" TEXT;
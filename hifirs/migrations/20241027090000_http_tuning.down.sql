@@ -0,0 +1,5 @@
+ALTER TABLE "config" DROP COLUMN "http_keep_alive";
+ALTER TABLE "config" DROP COLUMN "http_timeout";
+ALTER TABLE "config" DROP COLUMN "http_connect_timeout";
+ALTER TABLE "config" DROP COLUMN "http_pool_size";
+ALTER TABLE "config" DROP COLUMN "http2";
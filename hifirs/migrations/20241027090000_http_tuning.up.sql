@@ -0,0 +1,5 @@
+ALTER TABLE "config" ADD COLUMN "http2" BOOLEAN;
+ALTER TABLE "config" ADD COLUMN "http_pool_size" INTEGER;
+ALTER TABLE "config" ADD COLUMN "http_connect_timeout" INTEGER;
+ALTER TABLE "config" ADD COLUMN "http_timeout" INTEGER;
+ALTER TABLE "config" ADD COLUMN "http_keep_alive" INTEGER;
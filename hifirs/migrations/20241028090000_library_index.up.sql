@@ -0,0 +1,11 @@
+CREATE VIRTUAL TABLE IF NOT EXISTS "library_index" USING fts5(
+ "kind" UNINDEXED,
+ "entity_id" UNINDEXED,
+ "source" UNINDEXED,
+ "source_id" UNINDEXED,
+ "album_id" UNINDEXED,
+ "title",
+ "artist",
+ "album",
+ tokenize = 'trigram'
+);
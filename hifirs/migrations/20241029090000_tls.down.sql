@@ -0,0 +1,2 @@
+ALTER TABLE "config" DROP COLUMN "tls_key";
+ALTER TABLE "config" DROP COLUMN "tls_cert";
@@ -0,0 +1,2 @@
+ALTER TABLE "config" ADD COLUMN "tls_cert" TEXT;
+ALTER TABLE "config" ADD COLUMN "tls_key" TEXT;
@@ -0,0 +1,2 @@
+ALTER TABLE "config" DROP COLUMN "media_key_map";
+ALTER TABLE "config" DROP COLUMN "media_keys";
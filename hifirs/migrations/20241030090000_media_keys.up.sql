@@ -0,0 +1,2 @@
+ALTER TABLE "config" ADD COLUMN "media_keys" BOOLEAN;
+ALTER TABLE "config" ADD COLUMN "media_key_map" TEXT;
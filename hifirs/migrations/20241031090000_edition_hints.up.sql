@@ -0,0 +1 @@
+ALTER TABLE "config" ADD COLUMN "edition_hints" BOOLEAN;
@@ -0,0 +1,3 @@
+DROP TABLE IF EXISTS "loudness";
+
+ALTER TABLE "config" DROP COLUMN "normalization";
@@ -0,0 +1,7 @@
+ALTER TABLE "config" ADD COLUMN "normalization" BOOLEAN;
+
+CREATE TABLE IF NOT EXISTS "loudness" (
+ "track_id" INTEGER PRIMARY KEY NOT NULL,
+ "gain" REAL NOT NULL,
+ "peak" REAL NOT NULL
+);
@@ -0,0 +1,5 @@
+CREATE TABLE IF NOT EXISTS "release_calendar" (
+ "album_id" TEXT PRIMARY KEY NOT NULL,
+ "date" INTEGER NOT NULL,
+ "album" TEXT NOT NULL
+);
@@ -0,0 +1 @@
+ALTER TABLE "config" DROP COLUMN "api_slow_threshold";
@@ -0,0 +1 @@
+ALTER TABLE "config" ADD COLUMN "api_slow_threshold" INTEGER;
@@ -0,0 +1,2 @@
+ALTER TABLE "config" DROP COLUMN "simple_mode";
+ALTER TABLE "config" DROP COLUMN "simple_playlists";
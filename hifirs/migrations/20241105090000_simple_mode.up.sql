@@ -0,0 +1,2 @@
+ALTER TABLE "config" ADD COLUMN "simple_mode" INTEGER;
+ALTER TABLE "config" ADD COLUMN "simple_playlists" TEXT;
@@ -0,0 +1,3 @@
+DROP INDEX IF EXISTS "history_track_id";
+DROP INDEX IF EXISTS "history_played_at";
+DROP TABLE IF EXISTS "history";
@@ -0,0 +1,13 @@
+CREATE TABLE IF NOT EXISTS "history" (
+ "id" INTEGER PRIMARY KEY AUTOINCREMENT,
+ "track_id" INTEGER NOT NULL,
+ "title" TEXT NOT NULL,
+ "artist" TEXT,
+ "album" TEXT,
+ "album_id" TEXT,
+ "played_at" INTEGER NOT NULL,
+ "duration_played" INTEGER NOT NULL,
+ "duration" INTEGER NOT NULL
+);
+CREATE INDEX IF NOT EXISTS "history_played_at" ON "history" ("played_at");
+CREATE INDEX IF NOT EXISTS "history_track_id" ON "history" ("track_id");
@@ -0,0 +1,2 @@
+ALTER TABLE "config" DROP COLUMN "music_dir";
+DROP TABLE IF EXISTS "local_tracks";
@@ -0,0 +1,5 @@
+CREATE TABLE IF NOT EXISTS "local_tracks" (
+ "id" INTEGER PRIMARY KEY AUTOINCREMENT,
+ "path" TEXT NOT NULL UNIQUE
+);
+ALTER TABLE "config" ADD COLUMN "music_dir" TEXT;
@@ -0,0 +1 @@
+ALTER TABLE "config" ADD COLUMN "output_device" TEXT;
@@ -0,0 +1,4 @@
+ALTER TABLE "config" DROP COLUMN "lastfm_api_key";
+ALTER TABLE "config" DROP COLUMN "lastfm_api_secret";
+ALTER TABLE "config" DROP COLUMN "lastfm_session";
+ALTER TABLE "config" DROP COLUMN "listenbrainz_token";
@@ -0,0 +1,4 @@
+ALTER TABLE "config" ADD COLUMN "lastfm_api_key" TEXT;
+ALTER TABLE "config" ADD COLUMN "lastfm_api_secret" TEXT;
+ALTER TABLE "config" ADD COLUMN "lastfm_session" TEXT;
+ALTER TABLE "config" ADD COLUMN "listenbrainz_token" TEXT;
@@ -0,0 +1,7 @@
+CREATE TABLE IF NOT EXISTS "queue_snapshots" (
+ "name" TEXT NOT NULL UNIQUE,
+ "tracklist" TEXT NOT NULL,
+ "position" INTEGER NOT NULL DEFAULT 0,
+ "saved_at" INTEGER NOT NULL,
+ PRIMARY KEY("name")
+);
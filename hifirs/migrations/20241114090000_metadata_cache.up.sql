@@ -0,0 +1,7 @@
+CREATE TABLE IF NOT EXISTS "metadata_cache" (
+ "kind" TEXT NOT NULL,
+ "id" TEXT NOT NULL,
+ "data" TEXT NOT NULL,
+ "fetched_at" INTEGER NOT NULL,
+ PRIMARY KEY("kind", "id")
+);
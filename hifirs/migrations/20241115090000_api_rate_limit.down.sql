@@ -0,0 +1,2 @@
+ALTER TABLE "config" DROP COLUMN "api_retries";
+ALTER TABLE "config" DROP COLUMN "api_rate_limit";
@@ -0,0 +1,2 @@
+ALTER TABLE "config" ADD COLUMN "api_rate_limit" INTEGER;
+ALTER TABLE "config" ADD COLUMN "api_retries" INTEGER;
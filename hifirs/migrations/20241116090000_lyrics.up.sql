@@ -0,0 +1,10 @@
+CREATE TABLE IF NOT EXISTS "lyrics" (
+ "artist" TEXT NOT NULL,
+ "title" TEXT NOT NULL,
+ "duration" INTEGER NOT NULL,
+ "synced" TEXT,
+ "plain" TEXT,
+ "instrumental" BOOLEAN NOT NULL,
+ "fetched_at" INTEGER NOT NULL,
+ PRIMARY KEY("artist", "title", "duration")
+);
@@ -0,0 +1,7 @@
+CREATE TABLE IF NOT EXISTS "schedules" (
+ "name" TEXT PRIMARY KEY NOT NULL,
+ "spec" TEXT NOT NULL,
+ "kind" TEXT NOT NULL,
+ "id" TEXT NOT NULL,
+ "last_run" INTEGER
+);
@@ -0,0 +1,281 @@
+use base64::{engine::general_purpose::STANDARD, Engine};
+use clap::ValueEnum;
+use image::{imageops::FilterType, RgbImage};
+use once_cell::sync::OnceCell;
+use serde::{Deserialize, Serialize};
+use std::{fmt::Write, path::PathBuf};
+
+/// Pixels a terminal cell is assumed to take up when a cover is scaled for a graphics protocol.
+pub const CELL_PIXELS: (u32, u32) = (10, 20);
+/// The most base64 a single kitty graphics escape may carry.
+const KITTY_CHUNK: usize = 4096;
+/// Sixel colors are picked from a 6x6x6 color cube.
+const SIXEL_LEVELS: usize = 6;
+
+/// How album covers are drawn in the terminal.
+#[derive(Default, Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, ValueEnum)]
+#[serde(rename_all = "camelCase")]
+pub enum Protocol {
+    /// The best one the terminal is known to support.
+    #[default]
+    Auto,
+    /// The kitty graphics protocol, also spoken by WezTerm and Ghostty.
+    Kitty,
+    Sixel,
+    /// Two pixels per cell drawn with `▀`, works in any terminal with true color.
+    Halfblocks,
+    /// Don't show covers.
+    None,
+}
+
+static PROTOCOL: OnceCell<Protocol> = OnceCell::new();
+
+pub fn set_protocol(protocol: Protocol) {
+    let protocol = match protocol {
+        Protocol::Auto => detect(),
+        protocol => protocol,
+    };
+
+    debug!(?protocol, "artwork protocol");
+    PROTOCOL.set(protocol).ok();
+}
+
+/// The protocol covers are drawn with, never `Auto`.
+pub fn protocol() -> Protocol {
+    *PROTOCOL.get_or_init(detect)
+}
+
+/// Terminals can't be asked once the TUI reads the input, so this goes by the environment.
+fn detect() -> Protocol {
+    let var = |name: &str| std::env::var(name).unwrap_or_default();
+    let term = var("TERM");
+    let program = var("TERM_PROGRAM");
+
+    // tmux and screen only pass graphics through when told to.
+    if !var("TMUX").is_empty() || term.starts_with("screen") || term.starts_with("tmux") {
+        Protocol::Halfblocks
+    } else if !var("KITTY_WINDOW_ID").is_empty()
+        || term == "xterm-kitty"
+        || term == "xterm-ghostty"
+        || program == "WezTerm"
+        || program == "ghostty"
+    {
+        Protocol::Kitty
+    } else if term.contains("sixel")
+        || term.starts_with("foot")
+        || term == "mlterm"
+        || program == "iTerm.app"
+    {
+        Protocol::Sixel
+    } else {
+        Protocol::Halfblocks
+    }
+}
+
+/// Where downloaded covers are kept.
+pub fn cache_dir() -> Option<PathBuf> {
+    let mut path = dirs::data_local_dir()?;
+    path.push("hifi-rs");
+    path.push("artwork");
+
+    Some(path)
+}
+
+/// The cover at `url`, read from the disk cache when it was downloaded before.
+pub async fn load(url: &str) -> Option<RgbImage> {
+    let bytes = match url::Url::parse(url) {
+        Ok(file) if file.scheme() == "file" => {
+            tokio::fs::read(file.to_file_path().ok()?).await.ok()?
+        }
+        _ => cached_or_download(url).await?,
+    };
+
+    let decoded =
+        tokio::task::spawn_blocking(move || image::load_from_memory(&bytes).map(|i| i.to_rgb8()))
+            .await
+            .ok()?;
+
+    match decoded {
+        Ok(image) => Some(image),
+        Err(error) => {
+            debug!(?error, url, "failed to decode cover");
+            None
+        }
+    }
+}
+
+async fn cached_or_download(url: &str) -> Option<Vec<u8>> {
+    let dir = cache_dir()?;
+    let path = dir.join(format!("{:x}", md5::compute(url)));
+
+    if let Ok(bytes) = tokio::fs::read(&path).await {
+        return Some(bytes);
+    }
+
+    let bytes = match download(url).await {
+        Ok(bytes) => bytes,
+        Err(error) => {
+            debug!(?error, url, "failed to download cover");
+            return None;
+        }
+    };
+
+    if let Err(error) = tokio::fs::create_dir_all(&dir).await {
+        debug!(?error, "failed to create the artwork cache");
+    } else if let Err(error) = tokio::fs::write(&path, &bytes).await {
+        debug!(?error, "failed to cache cover");
+    }
+
+    Some(bytes)
+}
+
+async fn download(url: &str) -> reqwest::Result<Vec<u8>> {
+    let response = reqwest::get(url).await?.error_for_status()?;
+
+    Ok(response.bytes().await?.to_vec())
+}
+
+/// The top and bottom color of every cell when `image` is drawn over `columns` by `rows` cells.
+pub fn halfblocks(image: &RgbImage, columns: usize, rows: usize) -> Vec<Vec<([u8; 3], [u8; 3])>> {
+    let scaled =
+        image::imageops::resize(image, columns as u32, rows as u32 * 2, FilterType::Triangle);
+
+    (0..rows as u32)
+        .map(|row| {
+            (0..columns as u32)
+                .map(|column| {
+                    (
+                        scaled.get_pixel(column, row * 2).0,
+                        scaled.get_pixel(column, row * 2 + 1).0,
+                    )
+                })
+                .collect()
+        })
+        .collect()
+}
+
+fn scale_to_cells(image: &RgbImage, columns: usize, rows: usize) -> RgbImage {
+    image::imageops::resize(
+        image,
+        columns as u32 * CELL_PIXELS.0,
+        rows as u32 * CELL_PIXELS.1,
+        FilterType::Triangle,
+    )
+}
+
+/// Escapes that show `image` over `columns` by `rows` cells at the cursor, replacing whatever
+/// was shown with the same `id`. The cursor doesn't move and the terminal doesn't answer.
+pub fn kitty(image: &RgbImage, id: u32, columns: usize, rows: usize) -> String {
+    let scaled = scale_to_cells(image, columns, rows);
+    let data = STANDARD.encode(scaled.as_raw());
+    let chunks = data.as_bytes().chunks(KITTY_CHUNK).collect::<Vec<&[u8]>>();
+    let mut escapes = String::new();
+
+    for (index, chunk) in chunks.iter().enumerate() {
+        let more = u8::from(index + 1 < chunks.len());
+        let chunk = std::str::from_utf8(chunk).unwrap_or_default();
+
+        if index == 0 {
+            let _ = write!(
+                escapes,
+                "\x1b_Ga=T,f=24,s={},v={},i={id},c={columns},r={rows},C=1,q=2,m={more};{chunk}\x1b\\",
+                scaled.width(),
+                scaled.height(),
+            );
+        } else {
+            let _ = write!(escapes, "\x1b_Gm={more};{chunk}\x1b\\");
+        }
+    }
+
+    escapes
+}
+
+/// Removes the image shown with `id` and frees its data.
+pub fn kitty_delete(id: u32) -> String {
+    format!("\x1b_Ga=d,d=I,i={id},q=2\x1b\\")
+}
+
+/// A sixel image of `image` covering `columns` by `rows` cells from the cursor.
+pub fn sixel(image: &RgbImage, columns: usize, rows: usize) -> String {
+    let scaled = scale_to_cells(image, columns, rows);
+    let (width, height) = (scaled.width() as usize, scaled.height() as usize);
+    let level = |value: u8| (value as usize * (SIXEL_LEVELS - 1) + 127) / 255;
+    let colors = scaled
+        .pixels()
+        .map(|p| (level(p[0]) * SIXEL_LEVELS + level(p[1])) * SIXEL_LEVELS + level(p[2]))
+        .collect::<Vec<usize>>();
+
+    // Leave the pixels no color is drawn in as they were, and give the size up front.
+    let mut escapes = format!("\x1bP0;1;0q\"1;1;{width};{height}");
+
+    for color in 0..SIXEL_LEVELS.pow(3) {
+        let percent = |level: usize| level * 100 / (SIXEL_LEVELS - 1);
+        let (red, green, blue) = (
+            color / (SIXEL_LEVELS * SIXEL_LEVELS),
+            color / SIXEL_LEVELS % SIXEL_LEVELS,
+            color % SIXEL_LEVELS,
+        );
+
+        let _ = write!(
+            escapes,
+            "#{color};2;{};{};{}",
+            percent(red),
+            percent(green),
+            percent(blue)
+        );
+    }
+
+    for top in (0..height).step_by(6) {
+        let band = top..(top + 6).min(height);
+        let mut used = vec![false; SIXEL_LEVELS.pow(3)];
+
+        for y in band.clone() {
+            for &color in &colors[y * width..(y + 1) * width] {
+                used[color] = true;
+            }
+        }
+
+        for color in (0..used.len()).filter(|c| used[*c]) {
+            let _ = write!(escapes, "#{color}");
+            let mut run: Option<(char, usize)> = None;
+
+            for x in 0..width {
+                let bits = band
+                    .clone()
+                    .enumerate()
+                    .filter(|(_, y)| colors[y * width + x] == color)
+                    .fold(0u8, |bits, (row, _)| bits | 1 << row);
+                let sixel = char::from(63 + bits);
+
+                run = match run {
+                    Some((previous, count)) if previous == sixel => Some((sixel, count + 1)),
+                    Some((previous, count)) => {
+                        push_run(&mut escapes, previous, count);
+                        Some((sixel, 1))
+                    }
+                    None => Some((sixel, 1)),
+                };
+            }
+
+            if let Some((sixel, count)) = run {
+                push_run(&mut escapes, sixel, count);
+            }
+
+            // Back to the start of the band for the next color.
+            escapes.push('$');
+        }
+
+        escapes.push('-');
+    }
+
+    escapes.push_str("\x1b\\");
+    escapes
+}
+
+fn push_run(escapes: &mut String, sixel: char, count: usize) {
+    if count > 3 {
+        let _ = write!(escapes, "!{count}{sixel}");
+    } else {
+        escapes.extend(std::iter::repeat(sixel).take(count));
+    }
+}
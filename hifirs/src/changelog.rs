@@ -0,0 +1,59 @@
+use crate::sql::db;
+
+/// The notes for one version, from CHANGELOG.md.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Release {
+    pub version: &'static str,
+    pub notes: &'static [&'static str],
+}
+
+include!(concat!(env!("OUT_DIR"), "/release_notes.rs"));
+
+pub const VERSION: &str = env!("CARGO_PKG_VERSION");
+/// Stands in for the version of a database from before the last version was kept, older
+/// than every release.
+const UNTRACKED_VERSION: &str = "0";
+
+/// `0.3.10` as `[0, 3, 10]` so versions compare by number. Anything after a `-` is ignored.
+fn version_parts(version: &str) -> Vec<u32> {
+    version
+        .split('-')
+        .next()
+        .unwrap_or_default()
+        .split('.')
+        .map(|part| part.trim().parse::<u32>().unwrap_or_default())
+        .collect()
+}
+
+/// Releases newer than `version` up to the running one, newest first.
+pub fn since(version: &str) -> Vec<&'static Release> {
+    let after = version_parts(version);
+    let current = version_parts(VERSION);
+
+    RELEASES
+        .iter()
+        .filter(|r| {
+            let parts = version_parts(r.version);
+            parts > after && parts <= current
+        })
+        .collect()
+}
+
+/// The releases the user hasn't seen yet, remembering that they have now. Nothing is
+/// returned the first time hifi-rs runs, a new install has nothing to catch up on. A
+/// database without a version is from before versions were kept, all the notes are new to it.
+pub async fn take_unseen() -> Vec<&'static Release> {
+    let last = db::get_last_version().await;
+
+    if last.as_deref() == Some(VERSION) {
+        return Vec::new();
+    }
+
+    db::set_last_version(VERSION).await;
+
+    match last {
+        Some(last) => since(&last),
+        None if db::is_new() => Vec::new(),
+        None => since(UNTRACKED_VERSION),
+    }
+}
@@ -1,18 +1,44 @@
-use std::net::SocketAddr;
+use std::{
+    collections::BTreeMap,
+    net::{Ipv4Addr, SocketAddr},
+    sync::Arc,
+};
 
+#[cfg(any(target_os = "windows", target_os = "macos"))]
+use crate::media_controls;
 #[cfg(target_os = "linux")]
 use crate::mpris;
 use crate::{
+    artwork::{self, Protocol},
+    changelog,
+    config::{self, ConfigKey},
+    ctl::{self, PlayerStatus, SeekTarget},
     cursive::{self, CursiveUI},
-    player::{self},
+    history,
+    i18n::{self, Language},
+    library, local, network, play_log,
+    player::{
+        self,
+        actions::Action,
+        autoplay, cache, event_log, loudness,
+        output::{self, EqPreset, OutputProfile},
+        schedule::{self, CronSpec, Schedule, ScheduleKind},
+        stats, sync,
+        trim::{Trim, TrimKind},
+        visualization::Visualization,
+    },
     qobuz::{self},
+    releases,
+    scrobble::{self, LastFm, ListenBrainz, ScrobbleService, Scrobblers},
+    service::{self, BrowseCategory, MusicService, SearchFilter, SearchLimits, SearchType},
     sql::db::{self},
-    wait, websocket,
+    t, tls, wait, websocket,
 };
-use clap::{Parser, Subcommand};
+use clap::{Parser, Subcommand, ValueEnum};
 use comfy_table::{presets::UTF8_FULL, Table};
 use dialoguer::{Confirm, Input, Password};
 use hifirs_qobuz_api::client::{api::OutputFormat, AudioQuality};
+use serde::Serialize;
 use snafu::prelude::*;
 use tokio::task::JoinHandle;
 use tracing_subscriber::EnvFilter;
@@ -29,6 +55,15 @@ struct Cli {
     /// Provide a password. (overrides any database value)
     pub password: Option<String>,
 
+    #[clap(long)]
+    /// Provide a user auth token instead of a username and password. (overrides any database value)
+    pub token: Option<String>,
+
+    #[clap(long, default_value = service::DEFAULT_SERVICE)]
+    /// The music service to play from. A uri with the scheme of another registered
+    /// service switches to it.
+    pub service: String,
+
     #[clap(short, long, default_value_t = false)]
     /// Quit after done playing
     pub quit_when_done: bool,
@@ -41,14 +76,58 @@ struct Cli {
     /// Start web server with websocket API and embedded UI.
     pub web: bool,
 
-    #[clap(long, default_value = "0.0.0.0:9888")]
+    #[clap(long)]
     /// Specify a different interface and port for the web server to listen on.
-    pub interface: SocketAddr,
+    /// Defaults to 0.0.0.0 and the `web-port` config value (9888).
+    pub interface: Option<SocketAddr>,
+
+    #[clap(long, value_enum, default_value_t = Visualization::None)]
+    /// Show a VU meter or spectrum in the now playing panel.
+    pub visualization: Visualization,
+
+    #[clap(long, default_value_t = 100)]
+    /// How often, in milliseconds, the visualization is updated.
+    pub visualization_interval: u64,
+
+    #[clap(long, value_enum, default_value_t = Protocol::Auto)]
+    /// How the album cover is drawn in the now playing panel.
+    pub artwork: Protocol,
+
+    #[clap(long)]
+    /// Use this output profile instead of the saved one.
+    pub profile: Option<String>,
+
+    #[clap(long)]
+    /// Play through this device instead of the saved one, by its name in
+    /// `hifi-rs profile devices` or as a GStreamer sink like "alsasink device=hw:1,0".
+    pub output_device: Option<String>,
+
+    #[clap(long, default_value_t = false, conflicts_with = "follow")]
+    /// Lead multi-room playback: other players started with `--follow` play in step with this one.
+    pub lead: bool,
+
+    #[clap(long)]
+    /// Play in step with the player at this host, started with `--lead`.
+    pub follow: Option<String>,
+
+    #[clap(long, default_value_t = sync::DEFAULT_SYNC_PORT)]
+    /// The port multi-room playback syncs on. The clock is shared on the port after it.
+    pub sync_port: u16,
 
     #[clap(subcommand)]
     pub command: Commands,
 }
 
+impl Cli {
+    fn credentials(&self) -> service::Credentials {
+        service::Credentials {
+            username: self.username.clone(),
+            password: self.password.clone(),
+            token: self.token.clone(),
+        }
+    }
+}
+
 #[derive(Subcommand)]
 enum Commands {
     /// Open the player
@@ -62,11 +141,17 @@ enum Commands {
     StreamTrack {
         #[clap(value_parser)]
         track_id: i32,
+        /// Stream it at this quality instead of the default one.
+        #[clap(long)]
+        quality: Option<AudioQuality>,
     },
     /// Stream a full album by its ID.
     StreamAlbum {
         #[clap(value_parser)]
         album_id: String,
+        /// Stream it at this quality instead of the default one.
+        #[clap(long)]
+        quality: Option<AudioQuality>,
     },
     /// Retreive data from the Qobuz API
     Api {
@@ -80,6 +165,111 @@ enum Commands {
         #[clap(subcommand)]
         command: ConfigCommands,
     },
+    /// Manage output profiles and devices
+    Profile {
+        #[clap(subcommand)]
+        command: ProfileCommands,
+    },
+    /// Skip the start or end of tracks
+    Trim {
+        #[clap(subcommand)]
+        command: TrimCommands,
+    },
+    /// Control a player that was started with --web
+    Ctl {
+        /// The player's web server. Defaults to 127.0.0.1 and the `web-port` config value (9888).
+        #[clap(long)]
+        address: Option<SocketAddr>,
+        #[clap(subcommand)]
+        command: CtlCommands,
+    },
+    /// Save and switch between queues
+    Queue {
+        /// The player's web server, for saving and loading. Defaults to 127.0.0.1 and the
+        /// `web-port` config value (9888).
+        #[clap(long)]
+        address: Option<SocketAddr>,
+        #[clap(subcommand)]
+        command: QueueCommands,
+    },
+    /// Serve the web UI and websocket over HTTPS
+    Tls {
+        #[clap(subcommand)]
+        command: TlsCommands,
+    },
+    /// Show what changed in each release
+    Changelog {
+        /// Only show releases newer than this version.
+        #[clap(long)]
+        since: Option<String>,
+    },
+    /// Show upcoming and recent releases by the artists you follow
+    Calendar {
+        /// Fetch the releases again instead of showing the ones from the last refresh.
+        #[clap(long)]
+        refresh: bool,
+    },
+    /// Manage the cache of streamed tracks
+    Cache {
+        #[clap(subcommand)]
+        command: CacheCommands,
+    },
+    /// Create and delete your playlists
+    Playlist {
+        #[clap(subcommand)]
+        command: PlaylistCommands,
+    },
+    /// Show the tracks that were played last, or the ones played most
+    History {
+        /// List the most played tracks instead.
+        #[clap(long)]
+        most_played: bool,
+        /// How many tracks to show.
+        #[clap(long, short, default_value_t = history::DEFAULT_LIMIT)]
+        limit: u32,
+    },
+    /// Search the music service and print the results
+    Search {
+        #[clap(value_parser)]
+        query: String,
+        /// Only search for one kind of result.
+        #[clap(long = "type", value_enum, default_value_t = SearchType::All)]
+        search_type: SearchType,
+        /// How many results of each kind to show.
+        #[clap(long, short, default_value_t = 10)]
+        limit: u32,
+        /// Only show albums and tracks available in hi-res.
+        #[clap(long, default_value_t = false)]
+        hires: bool,
+        /// Only show albums and tracks released in or after this year.
+        #[clap(long)]
+        since: Option<u32>,
+        /// Only show albums and tracks whose genre contains this.
+        #[clap(long)]
+        genre: Option<String>,
+        /// Print the results as JSON.
+        #[clap(long, default_value_t = false)]
+        json: bool,
+    },
+    /// Show new releases, press awards, editor's picks and featured playlists
+    Discover {
+        /// How many albums or playlists of each section to show.
+        #[clap(long, short, default_value_t = 10)]
+        limit: u32,
+        /// Print the sections as JSON.
+        #[clap(long, default_value_t = false)]
+        json: bool,
+    },
+    /// Submit plays to Last.fm or ListenBrainz
+    Scrobble {
+        #[clap(subcommand)]
+        command: ScrobbleCommands,
+    },
+    /// Start an album or playlist at set times, e.g. as an alarm
+    Schedule {
+        #[clap(subcommand)]
+        command: ScheduleCommands,
+    },
 }
 
 #[derive(Subcommand)]
@@ -146,6 +336,9 @@ pub enum ConfigCommands {
     /// Save password to database.
     #[clap(value_parser)]
     Password {},
+    /// Save a user auth token to database instead of a username and password.
+    #[clap(value_parser)]
+    Token {},
     /// Clear saved username and password.
     Clear {},
     /// Target this quality when playing audio.
@@ -153,6 +346,252 @@ pub enum ConfigCommands {
         #[clap(value_enum)]
         quality: AudioQuality,
     },
+    /// Language used for the interface and Qobuz metadata.
+    Language {
+        #[clap(value_enum)]
+        language: Language,
+    },
+    /// Print the value of a config key, or all keys if none is given.
+    Get {
+        #[clap(value_enum)]
+        key: Option<ConfigKey>,
+        /// Print the output as JSON.
+        #[clap(long, default_value_t = false)]
+        json: bool,
+    },
+    /// Validate and save a config value.
+    Set {
+        #[clap(value_enum)]
+        key: ConfigKey,
+        #[clap(value_parser)]
+        value: String,
+    },
+}
+
+#[derive(Subcommand)]
+pub enum QueueCommands {
+    /// Save the queue of a player started with --web, replacing a queue saved under the
+    /// same name.
+    Save {
+        #[clap(value_parser)]
+        name: String,
+    },
+    /// Replace the queue of a player started with --web with a saved one.
+    Load {
+        #[clap(value_parser)]
+        name: String,
+    },
+    /// List the saved queues.
+    List {
+        /// Print the output as JSON.
+        #[clap(long, default_value_t = false)]
+        json: bool,
+    },
+    /// Remove a saved queue.
+    Delete {
+        #[clap(value_parser)]
+        name: String,
+    },
+}
+
+#[derive(Subcommand)]
+pub enum ProfileCommands {
+    /// List the saved output profiles.
+    List {},
+    /// Save an output profile, replacing any profile with the same name.
+    Add {
+        #[clap(value_parser)]
+        name: String,
+        /// GStreamer sink to play through, e.g. "alsasink device=hw:1,0".
+        /// Defaults to the system output.
+        #[clap(long)]
+        device: Option<String>,
+        /// Send audio to the device untouched: no resampling, EQ or volume changes.
+        #[clap(long, default_value_t = false)]
+        bit_perfect: bool,
+        /// Volume between 0.0 and 1.0.
+        #[clap(long, default_value_t = 1.0)]
+        volume: f64,
+        #[clap(long, value_enum, default_value_t = EqPreset::Flat)]
+        eq: EqPreset,
+    },
+    /// Remove an output profile.
+    Remove {
+        #[clap(value_parser)]
+        name: String,
+    },
+    /// Play through this output profile from now on.
+    Use {
+        #[clap(value_parser)]
+        name: String,
+    },
+    /// List the audio outputs that can be played through.
+    Devices {},
+    /// Play through this device from now on, by its name or as a GStreamer sink.
+    /// Leave it out to go back to the system default.
+    Device {
+        #[clap(value_parser)]
+        name: Option<String>,
+    },
+}
+
+#[derive(Subcommand)]
+pub enum CacheCommands {
+    /// Remove every track from the cache.
+    Purge,
+}
+
+#[derive(Subcommand)]
+pub enum PlaylistCommands {
+    /// Create a new playlist and print its id.
+    Create {
+        name: String,
+        /// Let everyone find and play the playlist.
+        #[clap(long)]
+        public: bool,
+        #[clap(long)]
+        description: Option<String>,
+    },
+    /// Delete one of your playlists.
+    Delete { id: i64 },
+    /// List your playlists.
+    List {
+        /// Print the playlists as JSON.
+        #[clap(long, default_value_t = false)]
+        json: bool,
+    },
+    /// List the tracks of a playlist.
+    Show {
+        id: i64,
+        /// Print the playlist as JSON.
+        #[clap(long, default_value_t = false)]
+        json: bool,
+    },
+}
+
+#[derive(Subcommand)]
+pub enum TrimCommands {
+    /// List the saved trims.
+    List {},
+    /// Trim a track, or every track on an album. A track's own trim wins over its album's.
+    Set {
+        #[clap(value_enum)]
+        kind: TrimKind,
+        /// The track or album id.
+        #[clap(value_parser)]
+        id: String,
+        /// Seconds to skip at the start.
+        #[clap(long, default_value_t = 0)]
+        start: u64,
+        /// Seconds to cut from the end.
+        #[clap(long, default_value_t = 0)]
+        end: u64,
+    },
+    /// Remove a trim.
+    Remove {
+        #[clap(value_enum)]
+        kind: TrimKind,
+        #[clap(value_parser)]
+        id: String,
+    },
+}
+
+#[derive(Subcommand)]
+pub enum ScheduleCommands {
+    /// List the schedules and when each starts next.
+    List {
+        /// Print the output as JSON.
+        #[clap(long, default_value_t = false)]
+        json: bool,
+    },
+    /// Play an album or playlist whenever the time matches a cron spec, replacing a
+    /// schedule with the same name. Only runs while the player is open.
+    Add {
+        #[clap(value_parser)]
+        name: String,
+        /// Minute, hour, day of month, month and day of week, e.g. "30 7 * * 1-5" for
+        /// 7:30 on weekdays.
+        #[clap(value_parser)]
+        spec: String,
+        #[clap(value_enum)]
+        kind: ScheduleKind,
+        /// The album or playlist id.
+        #[clap(value_parser)]
+        id: String,
+    },
+    /// Remove a schedule.
+    Remove {
+        #[clap(value_parser)]
+        name: String,
+    },
+}
+
+#[derive(Subcommand)]
+pub enum TlsCommands {
+    /// Generate a self-signed certificate and use it for the web server.
+    Generate {
+        /// A host name or address the certificate is valid for, can be repeated.
+        /// Defaults to localhost and this machine's addresses.
+        #[clap(long = "host")]
+        hosts: Vec<String>,
+    },
+    /// Go back to plain HTTP.
+    Disable,
+}
+
+#[derive(Subcommand)]
+pub enum ScrobbleCommands {
+    /// Link an account, plays are scrobbled to it from the next start.
+    Link {
+        #[clap(value_enum)]
+        service: ScrobbleService,
+        /// The key of your Last.fm API account, see https://www.last.fm/api/account/create.
+        #[clap(long)]
+        api_key: Option<String>,
+        /// The shared secret of your Last.fm API account.
+        #[clap(long)]
+        api_secret: Option<String>,
+        /// Your ListenBrainz user token, see https://listenbrainz.org/settings/.
+        #[clap(long)]
+        token: Option<String>,
+    },
+    /// Stop scrobbling to an account.
+    Unlink {
+        #[clap(value_enum)]
+        service: ScrobbleService,
+    },
+    /// Show the linked accounts.
+    Status,
+}
+
+#[derive(Subcommand)]
+pub enum CtlCommands {
+    /// Play if paused, pause if playing.
+    PlayPause,
+    Play,
+    Pause,
+    Next,
+    Previous,
+    Stop,
+    /// Seek in the current track: +30 or -10 seconds from here, 90 for 1:30 or 50% for
+    /// half way.
+    Seek {
+        #[clap(allow_hyphen_values = true, value_parser = ctl::parse_seek)]
+        position: SeekTarget,
+    },
+    /// Set the volume, from 0 to 100.
+    Volume {
+        #[clap(value_parser = clap::value_parser!(u8).range(0..=100))]
+        volume: u8,
+    },
+    /// Turn shuffle on or off.
+    Shuffle,
+    /// Show what is playing.
+    Status {
+        /// Print the output as JSON.
+        #[clap(long, default_value_t = false)]
+        json: bool,
+    },
 }
 
 #[derive(Debug, Snafu)]
@@ -163,6 +602,16 @@ pub enum Error {
     PlayerError { error: String },
     #[snafu(display("{error}"))]
     TerminalError { error: String },
+    #[snafu(display("{error}"))]
+    ConfigError { error: String },
+}
+
+impl From<config::Error> for Error {
+    fn from(error: config::Error) -> Self {
+        Error::ConfigError {
+            error: error.to_string(),
+        }
+    }
 }
 
 impl From<hifirs_qobuz_api::Error> for Error {
@@ -173,6 +622,38 @@ impl From<hifirs_qobuz_api::Error> for Error {
     }
 }
 
+impl From<ctl::Error> for Error {
+    fn from(error: ctl::Error) -> Self {
+        Error::PlayerError {
+            error: error.to_string(),
+        }
+    }
+}
+
+impl From<service::Error> for Error {
+    fn from(error: service::Error) -> Self {
+        Error::ClientError {
+            error: error.to_string(),
+        }
+    }
+}
+
+impl From<scrobble::Error> for Error {
+    fn from(error: scrobble::Error) -> Self {
+        Error::ClientError {
+            error: error.to_string(),
+        }
+    }
+}
+
+impl From<tls::Error> for Error {
+    fn from(error: tls::Error) -> Self {
+        Error::ConfigError {
+            error: error.to_string(),
+        }
+    }
+}
+
 impl From<player::error::Error> for Error {
     fn from(error: player::error::Error) -> Self {
         Error::PlayerError {
@@ -181,19 +662,54 @@ impl From<player::error::Error> for Error {
     }
 }
 
-async fn setup_player(
-    quit_when_done: bool,
-    resume: bool,
-    web: bool,
-    interface: SocketAddr,
-    username: Option<&str>,
-    password: Option<&str>,
-) -> Result<Vec<JoinHandle<()>>, Error> {
-    player::init(username, password, quit_when_done).await?;
+async fn setup_player(cli: &Cli, resume: bool) -> Result<Vec<JoinHandle<()>>, Error> {
+    let settings = config::settings().await;
+    let signed_in = player::init(&cli.service, cli.credentials(), cli.quit_when_done).await?;
+    player::set_visualization(cli.visualization, cli.visualization_interval);
+    artwork::set_protocol(cli.artwork);
+    player::set_format_change_policy(settings.format_change, settings.resample_rate);
+    player::set_recovery_policy(settings.recovery);
+    service::set_edition_hints(settings.edition_hints);
+    player::set_normalization(settings.normalization);
+    player::set_autoplay(settings.autoplay);
+    player::set_crossfade(settings.crossfade);
+    cache::set_size_limit(settings.cache_size);
+
+    player::set_settings(settings.clone());
+
+    // A device asked for on the command line has to be there, a saved one may have been
+    // unplugged since and the default plays instead.
+    if let Some(device) = cli.output_device.as_ref() {
+        player::set_output_device(Some(output::resolve_device(device)?)).await?;
+    } else if let Some(device) = settings.output_device.as_ref() {
+        match output::resolve_device(device) {
+            Ok(sink) => player::set_output_device(Some(sink)).await?,
+            Err(error) => warn!(%error, "playing through the default output"),
+        }
+    }
+
+    // Applied after the settings, which would otherwise put the saved profile back in place
+    // of one picked with --profile.
+    if let Some(name) = cli.profile.as_ref().or(settings.output_profile.as_ref()) {
+        match db::get_output_profile(name).await {
+            Some(profile) => player::set_output_profile(profile).await?,
+            None => {
+                return Err(Error::ConfigError {
+                    error: t!("profile-not-found", name = name),
+                })
+            }
+        }
+    }
+
+    // Simple mode locks the output profile, so it's only turned on once that is set up.
+    player::set_simple_mode(settings.simple_mode, settings.simple_playlists.clone());
 
     let mut handles: Vec<JoinHandle<()>> = Vec::new();
 
-    if resume {
+    #[cfg(unix)]
+    handles.push(tokio::spawn(async { reload_on_hangup().await }));
+
+    if resume && settings.resume {
         handles.push(tokio::spawn(async move {
             match player::resume(false).await {
                 Ok(_) => debug!("resume success"),
@@ -211,12 +727,80 @@ async fn setup_player(
         }));
     }
 
-    if web {
-        handles.push(tokio::spawn(
-            async move { websocket::init(interface).await },
-        ));
+    #[cfg(any(target_os = "windows", target_os = "macos"))]
+    handles.push(tokio::spawn(async {
+        media_controls::receive_notifications().await
+    }));
+
+    if cli.web {
+        let interface = cli
+            .interface
+            .unwrap_or_else(|| SocketAddr::from((Ipv4Addr::UNSPECIFIED, settings.web_port)));
+
+        let tls = settings.tls();
+
+        handles.push(tokio::spawn(async move {
+            websocket::init(interface, tls).await
+        }));
+    }
+
+    if settings.media_keys {
+        #[cfg(all(target_os = "linux", feature = "media-keys"))]
+        {
+            let map = settings.media_key_map.clone();
+
+            handles.push(tokio::spawn(async move {
+                crate::media_keys::listen_loop(map).await
+            }));
+        }
+
+        #[cfg(not(all(target_os = "linux", feature = "media-keys")))]
+        warn!("media-keys is on, but this build doesn't include the media-keys feature");
+    }
+
+    handles.push(tokio::spawn(async { releases::watch_loop().await }));
+    handles.push(tokio::spawn(async { network::watch_loop().await }));
+    handles.push(tokio::spawn(async { network::outage_loop().await }));
+    handles.push(tokio::spawn(async { library::index_loop().await }));
+    handles.push(tokio::spawn(async { loudness::scan_loop().await }));
+    handles.push(tokio::spawn(async { autoplay::watch_loop().await }));
+    handles.push(tokio::spawn(async { stats::stats_loop().await }));
+    handles.push(tokio::spawn(async { schedule::run_loop().await }));
+
+    if let Some(dir) = settings.music_dir.clone() {
+        let library = local::open(dir);
+
+        handles.push(tokio::spawn(async move { library.scan_once().await }));
+    }
+
+    if !signed_in {
+        let (service, credentials) = (cli.service.clone(), cli.credentials());
+
+        handles.push(tokio::spawn(async move {
+            network::login_loop(service, credentials).await
+        }));
+    }
+
+    if cli.lead {
+        let port = cli.sync_port;
+        handles.push(tokio::spawn(async move { sync::lead(port).await }));
+    } else if let Some(host) = cli.follow.clone() {
+        let port = cli.sync_port;
+        handles.push(tokio::spawn(async move { sync::follow(host, port).await }));
     }
 
+    handles.push(tokio::spawn(async { history::record_loop().await }));
+    handles.push(tokio::spawn(async { scrobble::scrobble_loop().await }));
+    handles.push(tokio::spawn(async { cache::save_loop().await }));
+
+    let play_log = settings.play_log;
+    handles.push(tokio::spawn(async move {
+        play_log::record_loop(play_log).await
+    }));
+    handles.push(tokio::spawn(async {
+        event_log::record_notifications().await
+    }));
+
     handles.push(tokio::spawn(async {
         match player::player_loop().await {
             Ok(_) => debug!("player loop exited successfully"),
@@ -227,6 +811,104 @@ async fn setup_player(
     Ok(handles)
 }
 
+/// The service picked with `--service`, signed in for one-off queries.
+async fn query_service(cli: &Cli) -> Result<Arc<dyn MusicService>, Error> {
+    let (service, _) = service::build(&cli.service, cli.credentials()).await?;
+
+    Ok(service)
+}
+
+/// Print `value` for scripts, as pretty JSON on stdout.
+fn print_json<T: Serialize>(value: &T) {
+    println!(
+        "{}",
+        serde_json::to_string_pretty(value).expect("failed to serialize")
+    );
+}
+
+/// Reload the config whenever the process receives SIGHUP.
+#[cfg(unix)]
+async fn reload_on_hangup() {
+    use futures::StreamExt;
+    use player::notification::Notification;
+    use tokio::signal::unix::{signal, SignalKind};
+
+    let mut hangup = match signal(SignalKind::hangup()) {
+        Ok(hangup) => hangup,
+        Err(error) => {
+            debug!(?error, "failed to listen for SIGHUP");
+            return;
+        }
+    };
+
+    let mut receiver = player::notify_receiver();
+
+    loop {
+        tokio::select! {
+            Some(_) = hangup.recv() => {
+                debug!("received SIGHUP, reloading config");
+
+                if let Err(error) = player::reload_config().await {
+                    debug!(?error, "failed to reload config");
+                }
+            }
+            Some(notification) = receiver.next() => {
+                if notification == Notification::Quit {
+                    break;
+                }
+            }
+            else => break,
+        }
+    }
+}
+
+/// Printed once the TUI has given the terminal back.
+fn print_session_summary() {
+    let stats = player::session::stats();
+
+    if stats.tracks_played == 0 {
+        return;
+    }
+
+    println!(
+        "{}",
+        t!(
+            "session-summary",
+            tracks = stats.tracks_played,
+            time = service::format_seconds(stats.listening_seconds),
+            data = player::session::format_bytes(stats.bytes_streamed),
+            rebuffers = stats.rebuffers
+        )
+    );
+}
+
+fn print_status(status: &PlayerStatus) {
+    let Some(track) = &status.track else {
+        println!("{}", t!("ctl-nothing-playing"));
+        return;
+    };
+
+    let artist = track
+        .artist
+        .as_ref()
+        .or(track.album.as_ref().map(|a| &a.artist))
+        .map(|a| a.name.clone())
+        .unwrap_or_default();
+
+    println!(
+        "{}",
+        t!(
+            "ctl-status",
+            state = format!("{:?}", status.state).to_lowercase(),
+            title = track.title,
+            artist = artist,
+            position = service::format_seconds(status.position_seconds),
+            duration = service::format_seconds(status.duration_seconds),
+            volume = (status.volume * 100.0).round()
+        )
+    );
+}
+
 pub async fn run() -> Result<(), Error> {
     tracing_subscriber::registry()
         .with(
@@ -244,69 +926,49 @@ pub async fn run() -> Result<(), Error> {
     // INIT DB
     db::init().await;
 
+    // INIT TRANSLATIONS
+    i18n::init(
+        db::get_config()
+            .await
+            .and_then(|c| c.language)
+            .and_then(|l| l.parse::<Language>().ok()),
+    );
+
     // CLI COMMANDS
-    match cli.command {
+    match &cli.command {
         Commands::Open {} => {
-            let mut handles = setup_player(
-                cli.quit_when_done,
-                true,
-                cli.web,
-                cli.interface,
-                cli.username.as_deref(),
-                cli.password.as_deref(),
-            )
-            .await?;
+            let mut handles = setup_player(&cli, true).await?;
 
             wait!(mut handles, cli.disable_tui);
 
             Ok(())
         }
         Commands::Play { url } => {
-            let mut handles = setup_player(
-                cli.quit_when_done,
-                false,
-                cli.web,
-                cli.interface,
-                cli.username.as_deref(),
-                cli.password.as_deref(),
-            )
-            .await?;
-
-            player::play_uri(&url).await?;
+            let mut handles = setup_player(&cli, false).await?;
+
+            player::play_uri(url).await?;
 
             wait!(mut handles, cli.disable_tui);
 
             Ok(())
         }
-        Commands::StreamTrack { track_id } => {
-            let mut handles = setup_player(
-                cli.quit_when_done,
-                false,
-                cli.web,
-                cli.interface,
-                cli.username.as_deref(),
-                cli.password.as_deref(),
-            )
-            .await?;
-
-            player::play_track(track_id).await?;
+        Commands::StreamTrack { track_id, quality } => {
+            let mut handles = setup_player(&cli, false).await?;
+
+            player::set_next_quality(quality.clone()).await?;
+            player::play_track(*track_id).await?;
 
             wait!(mut handles, cli.disable_tui);
 
             Ok(())
         }
-        Commands::StreamAlbum { album_id } => {
-            let mut handles = setup_player(
-                cli.quit_when_done,
-                false,
-                cli.web,
-                cli.interface,
-                cli.username.as_deref(),
-                cli.password.as_deref(),
-            )
-            .await?;
-
-            player::play_album(&album_id).await?;
+        Commands::StreamAlbum { album_id, quality } => {
+            let mut handles = setup_player(&cli, false).await?;
+
+            match quality {
+                Some(quality) => player::play_album_with_quality(album_id, quality.clone()).await?,
+                None => player::play_album(album_id).await?,
+            }
 
             wait!(mut handles, cli.disable_tui);
 
@@ -318,9 +980,13 @@ pub async fn run() -> Result<(), Error> {
                 limit,
                 output_format,
             } => {
-                let client =
-                    qobuz::make_client(cli.username.as_deref(), cli.password.as_deref()).await?;
-                let results = client.search_all(&query, limit.unwrap_or_default()).await?;
+                let client = qobuz::make_client(
+                    cli.username.as_deref(),
+                    cli.password.as_deref(),
+                    cli.token.as_deref(),
+                )
+                .await?;
+                let results = client.search_all(query, limit.unwrap_or_default()).await?;
 
                 output!(results, output_format);
 
@@ -331,9 +997,13 @@ pub async fn run() -> Result<(), Error> {
                 limit,
                 output_format,
             } => {
-                let client =
-                    qobuz::make_client(cli.username.as_deref(), cli.password.as_deref()).await?;
-                let results = client.search_albums(&query, limit).await?;
+                let client = qobuz::make_client(
+                    cli.username.as_deref(),
+                    cli.password.as_deref(),
+                    cli.token.as_deref(),
+                )
+                .await?;
+                let results = client.search_albums(query, *limit).await?;
 
                 output!(results, output_format);
 
@@ -344,93 +1014,1083 @@ pub async fn run() -> Result<(), Error> {
                 limit,
                 output_format,
             } => {
-                let client =
-                    qobuz::make_client(cli.username.as_deref(), cli.password.as_deref()).await?;
-                let results = client.search_artists(&query, limit).await?;
+                let client = qobuz::make_client(
+                    cli.username.as_deref(),
+                    cli.password.as_deref(),
+                    cli.token.as_deref(),
+                )
+                .await?;
+                let results = client.search_artists(query, *limit).await?;
 
                 output!(results, output_format);
 
                 Ok(())
             }
             ApiCommands::Playlist { id, output_format } => {
-                let client =
-                    qobuz::make_client(cli.username.as_deref(), cli.password.as_deref()).await?;
-
-                let results = client.playlist(id).await?;
+                let client = qobuz::make_client(
+                    cli.username.as_deref(),
+                    cli.password.as_deref(),
+                    cli.token.as_deref(),
+                )
+                .await?;
+
+                let results = client.playlist(*id).await?;
                 output!(results, output_format);
                 Ok(())
             }
             ApiCommands::Album { id, output_format } => {
-                let client =
-                    qobuz::make_client(cli.username.as_deref(), cli.password.as_deref()).await?;
-
-                let results = client.album(&id).await?;
+                let client = qobuz::make_client(
+                    cli.username.as_deref(),
+                    cli.password.as_deref(),
+                    cli.token.as_deref(),
+                )
+                .await?;
+
+                let results = client.album(id).await?;
                 output!(results, output_format);
                 Ok(())
             }
             ApiCommands::Artist { id, output_format } => {
-                let client =
-                    qobuz::make_client(cli.username.as_deref(), cli.password.as_deref()).await?;
-
-                let results = client.artist(id, Some(500)).await?;
+                let client = qobuz::make_client(
+                    cli.username.as_deref(),
+                    cli.password.as_deref(),
+                    cli.token.as_deref(),
+                )
+                .await?;
+
+                let results = client.artist(*id, Some(500)).await?;
                 output!(results, output_format);
                 Ok(())
             }
             ApiCommands::Track { id, output_format } => {
-                let client =
-                    qobuz::make_client(cli.username.as_deref(), cli.password.as_deref()).await?;
-
-                let results = client.track(id).await?;
+                let client = qobuz::make_client(
+                    cli.username.as_deref(),
+                    cli.password.as_deref(),
+                    cli.token.as_deref(),
+                )
+                .await?;
+
+                let results = client.track(*id).await?;
                 output!(results, output_format);
                 Ok(())
             }
         },
-        Commands::Reset => {
-            db::clear_state().await;
-            Ok(())
-        }
-        Commands::Config { command } => match command {
-            ConfigCommands::Username {} => {
-                if let Ok(username) = Input::new()
-                    .with_prompt("Enter your username / email")
-                    .interact_text()
-                {
-                    db::set_username(username).await;
+        Commands::Ctl { address, command } => {
+            let settings = config::settings().await;
+            let address = match address {
+                Some(address) => *address,
+                None => SocketAddr::from((Ipv4Addr::LOCALHOST, settings.web_port)),
+            };
+            let tls = settings.tls().map(|(cert, _)| cert);
+
+            let action = match command {
+                CtlCommands::PlayPause => Action::PlayPause,
+                CtlCommands::Play => Action::Play,
+                CtlCommands::Pause => Action::Pause,
+                CtlCommands::Next => Action::Next,
+                CtlCommands::Previous => Action::Previous,
+                CtlCommands::Stop => Action::Stop,
+                CtlCommands::Seek { position } => match *position {
+                    SeekTarget::Seconds { seconds, relative } => Action::Seek { seconds, relative },
+                    SeekTarget::Percent(percent) => Action::SeekPercent(percent),
+                },
+                CtlCommands::Volume { volume } => Action::SetVolume {
+                    volume: *volume as f64 / 100.0,
+                },
+                CtlCommands::Shuffle => Action::ToggleShuffle,
+                CtlCommands::Status { json } => {
+                    let status = ctl::status(address, tls.as_deref()).await?;
+
+                    if *json {
+                        print_json(&status);
+                    } else {
+                        print_status(&status);
+                    }
 
-                    println!("Username saved.");
+                    return Ok(());
                 }
-                Ok(())
-            }
-            ConfigCommands::Password {} => {
-                if let Ok(password) = Password::new()
-                    .with_prompt("Enter your password (hidden)")
-                    .interact()
-                {
-                    let md5_pw = format!("{:x}", md5::compute(password));
+            };
 
-                    debug!("saving password to database: {}", md5_pw);
+            ctl::send(address, tls.as_deref(), action).await?;
 
-                    db::set_password(md5_pw).await;
+            Ok(())
+        }
+        Commands::Queue { address, command } => {
+            let settings = config::settings().await;
+            let address = match address {
+                Some(address) => *address,
+                None => SocketAddr::from((Ipv4Addr::LOCALHOST, settings.web_port)),
+            };
+            let tls = settings.tls().map(|(cert, _)| cert);
+
+            let action = match command {
+                QueueCommands::Save { name } => Action::SaveQueue { name: name.clone() },
+                QueueCommands::Load { name } => {
+                    if db::get_queue_snapshot(name).await.is_none() {
+                        return Err(Error::ConfigError {
+                            error: t!("queue-not-found", name = name),
+                        });
+                    }
 
-                    println!("Password saved.");
+                    Action::LoadQueue { name: name.clone() }
                 }
-                Ok(())
-            }
-            ConfigCommands::DefaultQuality { quality } => {
-                db::set_default_quality(quality).await;
+                QueueCommands::List { json } => {
+                    let snapshots = db::get_queue_snapshots().await;
 
-                println!("Default quality saved.");
+                    if *json {
+                        print_json(&snapshots);
+                        return Ok(());
+                    }
 
-                Ok(())
-            }
-            ConfigCommands::Clear {} => {
-                if let Ok(ok) = Confirm::new()
-                    .with_prompt("This will clear the configuration in the database.\nDo you want to continue?")
+                    if snapshots.is_empty() {
+                        println!("{}", t!("no-saved-queues"));
+                        return Ok(());
+                    }
+
+                    let mut table = Table::new();
+                    table.load_preset(UTF8_FULL);
+                    table.set_header(vec!["name", "tracks", "current track", "saved"]);
+
+                    for snapshot in snapshots {
+                        table.add_row(vec![
+                            snapshot.name.clone(),
+                            snapshot.tracklist.queue.len().to_string(),
+                            snapshot.current_title(),
+                            snapshot.saved_at_label(),
+                        ]);
+                    }
+
+                    println!("{table}");
+
+                    return Ok(());
+                }
+                QueueCommands::Delete { name } => {
+                    if !db::remove_queue_snapshot(name).await {
+                        return Err(Error::ConfigError {
+                            error: t!("queue-not-found", name = name),
+                        });
+                    }
+
+                    println!("{}", t!("queue-removed", name = name));
+
+                    return Ok(());
+                }
+            };
+
+            ctl::send(address, tls.as_deref(), action).await?;
+
+            Ok(())
+        }
+        Commands::Reset => {
+            db::clear_state().await;
+            Ok(())
+        }
+        Commands::Profile { command } => match command {
+            ProfileCommands::List {} => {
+                let profiles = db::get_output_profiles().await;
+
+                if profiles.is_empty() {
+                    println!("{}", t!("no-output-profiles"));
+                    return Ok(());
+                }
+
+                let active = config::settings().await.output_profile;
+
+                let mut table = Table::new();
+                table.load_preset(UTF8_FULL);
+                table.set_header(vec!["", "name", "device", "bit-perfect", "volume", "eq"]);
+
+                for profile in profiles {
+                    let marker = if active.as_ref() == Some(&profile.name) {
+                        "*"
+                    } else {
+                        ""
+                    };
+
+                    table.add_row(vec![
+                        marker.to_string(),
+                        profile.name,
+                        profile.device.unwrap_or_default(),
+                        profile.bit_perfect.to_string(),
+                        profile.volume.to_string(),
+                        profile.eq_preset.to_string(),
+                    ]);
+                }
+
+                println!("{table}");
+
+                Ok(())
+            }
+            ProfileCommands::Add {
+                name,
+                device,
+                bit_perfect,
+                volume,
+                eq,
+            } => {
+                if !(0.0..=1.0).contains(volume) {
+                    return Err(Error::ConfigError {
+                        error: t!("profile-invalid-volume"),
+                    });
+                }
+
+                db::save_output_profile(&OutputProfile {
+                    name: name.trim().to_string(),
+                    device: device.clone(),
+                    bit_perfect: *bit_perfect,
+                    volume: *volume,
+                    eq_preset: *eq,
+                })
+                .await;
+
+                println!("{}", t!("profile-saved", name = name));
+
+                Ok(())
+            }
+            ProfileCommands::Remove { name } => {
+                if !db::remove_output_profile(name).await {
+                    return Err(Error::ConfigError {
+                        error: t!("profile-not-found", name = name),
+                    });
+                }
+
+                if config::settings().await.output_profile.as_ref() == Some(name) {
+                    db::set_config_value(ConfigKey::OutputProfile, None).await;
+                }
+
+                println!("{}", t!("profile-removed", name = name));
+
+                Ok(())
+            }
+            ProfileCommands::Use { name } => {
+                if db::get_output_profile(name).await.is_none() {
+                    return Err(Error::ConfigError {
+                        error: t!("profile-not-found", name = name),
+                    });
+                }
+
+                db::set_config_value(ConfigKey::OutputProfile, Some(name.clone())).await;
+
+                println!("{}", t!("profile-active", name = name));
+
+                Ok(())
+            }
+            ProfileCommands::Devices {} => {
+                let devices = output::devices();
+
+                if devices.is_empty() {
+                    println!("{}", t!("no-output-devices"));
+                    return Ok(());
+                }
+
+                let active = config::settings().await.output_device;
+
+                let mut table = Table::new();
+                table.load_preset(UTF8_FULL);
+                table.set_header(vec!["", "name", "sink"]);
+
+                for device in devices {
+                    let marker = if active.as_ref() == Some(&device.sink) {
+                        "*"
+                    } else {
+                        ""
+                    };
+
+                    table.add_row(vec![marker.to_string(), device.name, device.sink]);
+                }
+
+                println!("{table}");
+
+                Ok(())
+            }
+            ProfileCommands::Device { name } => {
+                let sink = name.as_deref().map(output::resolve_device).transpose()?;
+
+                db::set_config_value(ConfigKey::OutputDevice, sink.clone()).await;
+
+                match sink {
+                    Some(sink) => println!("{}", t!("output-device-active", device = sink)),
+                    None => println!("{}", t!("output-device-default")),
+                }
+
+                Ok(())
+            }
+        },
+        Commands::Schedule { command } => match command {
+            ScheduleCommands::List { json } => {
+                let schedules = db::get_schedules().await;
+
+                if *json {
+                    print_json(&schedules);
+                    return Ok(());
+                }
+
+                if schedules.is_empty() {
+                    println!("{}", t!("no-schedules"));
+                    return Ok(());
+                }
+
+                let mut table = Table::new();
+                table.load_preset(UTF8_FULL);
+                table.set_header(vec!["name", "spec", "kind", "id", "next"]);
+
+                for schedule in schedules {
+                    let next = schedule
+                        .next_run()
+                        .map(|next| next.format("%Y-%m-%d %H:%M").to_string())
+                        .unwrap_or_default();
+
+                    table.add_row(vec![
+                        schedule.name,
+                        schedule.spec,
+                        schedule.kind.to_string(),
+                        schedule.id,
+                        next,
+                    ]);
+                }
+
+                println!("{table}");
+
+                Ok(())
+            }
+            ScheduleCommands::Add {
+                name,
+                spec,
+                kind,
+                id,
+            } => {
+                if let Err(error) = spec.parse::<CronSpec>() {
+                    return Err(Error::ConfigError {
+                        error: t!("schedule-invalid", spec = spec, error = error),
+                    });
+                }
+
+                let schedule = Schedule {
+                    name: name.trim().to_string(),
+                    spec: spec.trim().to_string(),
+                    kind: *kind,
+                    id: id.trim().to_string(),
+                    last_run: None,
+                };
+
+                db::save_schedule(&schedule).await;
+
+                let next = schedule
+                    .next_run()
+                    .map(|next| next.format("%Y-%m-%d %H:%M").to_string())
+                    .unwrap_or_default();
+
+                println!("{}", t!("schedule-saved", name = name, next = next));
+
+                Ok(())
+            }
+            ScheduleCommands::Remove { name } => {
+                if !db::remove_schedule(name).await {
+                    return Err(Error::ConfigError {
+                        error: t!("schedule-not-found", name = name),
+                    });
+                }
+
+                println!("{}", t!("schedule-removed", name = name));
+
+                Ok(())
+            }
+        },
+        Commands::Trim { command } => match command {
+            TrimCommands::List {} => {
+                let trims = db::get_trims().await;
+
+                if trims.is_empty() {
+                    println!("{}", t!("no-trims"));
+                    return Ok(());
+                }
+
+                let mut table = Table::new();
+                table.load_preset(UTF8_FULL);
+                table.set_header(vec!["kind", "id", "start", "end"]);
+
+                for trim in trims {
+                    table.add_row(vec![
+                        trim.kind.to_string(),
+                        trim.id,
+                        trim.start_secs.to_string(),
+                        trim.end_secs.to_string(),
+                    ]);
+                }
+
+                println!("{table}");
+
+                Ok(())
+            }
+            TrimCommands::Set {
+                kind,
+                id,
+                start,
+                end,
+            } => {
+                if *start == 0 && *end == 0 {
+                    return Err(Error::ConfigError {
+                        error: t!("trim-empty"),
+                    });
+                }
+
+                let id = id.trim();
+                let service = query_service(&cli).await?;
+                let tracks = match kind {
+                    TrimKind::Track => match id.parse() {
+                        Ok(track_id) => service.track(track_id).await.into_iter().collect(),
+                        Err(_) => vec![],
+                    },
+                    TrimKind::Album => service
+                        .album(id)
+                        .await
+                        .map(|album| album.tracks.into_values().collect())
+                        .unwrap_or_default(),
+                };
+
+                if tracks.is_empty() {
+                    return Err(Error::ConfigError {
+                        error: t!("trim-unknown", kind = kind, id = id),
+                    });
+                }
+
+                // Every track of an album is trimmed, the shortest one has to keep something.
+                if let Some(track) = tracks
+                    .iter()
+                    .find(|track| start + end >= track.duration_seconds as u64)
+                {
+                    return Err(Error::ConfigError {
+                        error: t!(
+                            "trim-too-long",
+                            title = track.title,
+                            duration = track.duration_seconds
+                        ),
+                    });
+                }
+
+                db::save_trim(&Trim {
+                    kind: *kind,
+                    id: id.to_string(),
+                    start_secs: *start,
+                    end_secs: *end,
+                })
+                .await;
+
+                println!("{}", t!("trim-saved", kind = kind, id = id));
+
+                Ok(())
+            }
+            TrimCommands::Remove { kind, id } => {
+                if !db::remove_trim(*kind, id).await {
+                    return Err(Error::ConfigError {
+                        error: t!("trim-not-found", kind = kind, id = id),
+                    });
+                }
+
+                println!("{}", t!("trim-removed", kind = kind, id = id));
+
+                Ok(())
+            }
+        },
+        Commands::Tls { command } => match command {
+            TlsCommands::Generate { hosts } => {
+                let hosts = if hosts.is_empty() {
+                    tls::default_hosts()
+                } else {
+                    hosts.clone()
+                };
+
+                let (cert, key) = tls::generate(hosts)?;
+
+                db::set_config_value(ConfigKey::TlsCert, Some(cert.display().to_string())).await;
+                db::set_config_value(ConfigKey::TlsKey, Some(key.display().to_string())).await;
+
+                println!(
+                    "{}",
+                    t!("tls-generated", cert = cert.display(), key = key.display())
+                );
+
+                Ok(())
+            }
+            TlsCommands::Disable => {
+                db::set_config_value(ConfigKey::TlsCert, None).await;
+                db::set_config_value(ConfigKey::TlsKey, None).await;
+
+                println!("{}", t!("tls-disabled"));
+
+                Ok(())
+            }
+        },
+        Commands::Cache { command } => match command {
+            CacheCommands::Purge => {
+                let (count, freed) = cache::purge().await;
+
+                println!(
+                    "{}",
+                    t!("cache-purged", count = count, size = freed / 1024 / 1024)
+                );
+
+                Ok(())
+            }
+        },
+        Commands::Discover { limit, json } => {
+            let service = query_service(&cli).await?;
+            let sections = [
+                BrowseCategory::NewReleases,
+                BrowseCategory::PressAwards,
+                BrowseCategory::EditorPicks,
+            ];
+
+            let mut albums = Vec::new();
+
+            for category in sections {
+                if let Some(page) = service.browse(category, None, 0, *limit).await {
+                    albums.push(page);
+                }
+            }
+
+            let mut playlists = service.featured_playlists().await.unwrap_or_default();
+            playlists.truncate(*limit as usize);
+
+            if *json {
+                print_json(&serde_json::json!({ "albums": albums, "playlists": playlists }));
+                return Ok(());
+            }
+
+            let mut table = Table::new();
+            table.load_preset(UTF8_FULL);
+            table.set_header(vec!["section", "title", "artist", "id"]);
+
+            for page in albums {
+                for album in page.albums {
+                    table.add_row(vec![
+                        page.category.title(),
+                        album.display_title(),
+                        album.artist.name,
+                        album.id,
+                    ]);
+                }
+            }
+            for playlist in playlists {
+                table.add_row(vec![
+                    t!("featured-playlists"),
+                    playlist.title,
+                    String::new(),
+                    playlist.id.to_string(),
+                ]);
+            }
+
+            println!("{table}");
+
+            Ok(())
+        }
+        Commands::Search {
+            query,
+            search_type,
+            limit,
+            hires,
+            since,
+            genre,
+            json,
+        } => {
+            let service = query_service(&cli).await?;
+            let mut results = service
+                .search(query, SearchLimits::only(*search_type, *limit))
+                .await
+                .ok_or_else(|| Error::ClientError {
+                    error: t!("search-failed", query = query),
+                })?;
+
+            SearchFilter {
+                hires_only: *hires,
+                min_year: *since,
+                genre: genre.clone(),
+            }
+            .apply(&mut results);
+
+            if *json {
+                print_json(&results);
+                return Ok(());
+            }
+
+            let mut table = Table::new();
+            table.load_preset(UTF8_FULL);
+            table.set_header(vec!["type", "title", "artist", "id"]);
+
+            for album in results.albums {
+                table.add_row(vec![
+                    "album".to_string(),
+                    album.display_title(),
+                    album.artist.name,
+                    album.id,
+                ]);
+            }
+            for track in results.tracks {
+                table.add_row(vec![
+                    "track".to_string(),
+                    track.display_title(),
+                    track.artist.map(|a| a.name).unwrap_or_default(),
+                    track.id.to_string(),
+                ]);
+            }
+            for artist in results.artists {
+                table.add_row(vec![
+                    "artist".to_string(),
+                    artist.name,
+                    String::new(),
+                    artist.id.to_string(),
+                ]);
+            }
+            for playlist in results.playlists {
+                table.add_row(vec![
+                    "playlist".to_string(),
+                    playlist.title,
+                    String::new(),
+                    playlist.id.to_string(),
+                ]);
+            }
+
+            println!("{table}");
+
+            Ok(())
+        }
+        Commands::Playlist { command } => {
+            match command {
+                PlaylistCommands::List { json } => {
+                    let service = query_service(&cli).await?;
+                    let playlists = service.user_playlists().await.unwrap_or_default();
+
+                    if *json {
+                        print_json(&playlists);
+                        return Ok(());
+                    }
+
+                    let mut table = Table::new();
+                    table.load_preset(UTF8_FULL);
+                    table.set_header(vec!["title", "tracks", "duration", "id"]);
+
+                    for playlist in playlists {
+                        table.add_row(vec![
+                            playlist.title,
+                            playlist.tracks_count.to_string(),
+                            service::format_seconds(playlist.duration_seconds as u64),
+                            playlist.id.to_string(),
+                        ]);
+                    }
+
+                    println!("{table}");
+                }
+                PlaylistCommands::Show { id, json } => {
+                    let service = query_service(&cli).await?;
+                    let playlist =
+                        service
+                            .playlist(*id)
+                            .await
+                            .ok_or_else(|| Error::ClientError {
+                                error: t!("playlist-not-found", id = id),
+                            })?;
+
+                    if *json {
+                        print_json(&playlist);
+                        return Ok(());
+                    }
+
+                    let mut table = Table::new();
+                    table.load_preset(UTF8_FULL);
+                    table.set_header(vec!["#", "artist", "title", "album", "duration", "id"]);
+
+                    for track in playlist.tracks.values() {
+                        table.add_row(vec![
+                            track.position.to_string(),
+                            track
+                                .artist
+                                .as_ref()
+                                .map(|a| a.name.clone())
+                                .unwrap_or_default(),
+                            track.display_title(),
+                            track
+                                .album
+                                .as_ref()
+                                .map(|a| a.title.clone())
+                                .unwrap_or_default(),
+                            service::format_seconds(track.duration_seconds as u64),
+                            track.id.to_string(),
+                        ]);
+                    }
+
+                    println!("{table}");
+                }
+                PlaylistCommands::Create {
+                    name,
+                    public,
+                    description,
+                } => {
+                    let service = query_service(&cli).await?;
+                    let playlist = service
+                        .create_playlist(name, *public, description.as_deref())
+                        .await
+                        .ok_or_else(|| Error::ClientError {
+                            error: t!("playlist-create-failed", name = name),
+                        })?;
+
+                    println!(
+                        "{}",
+                        t!("playlist-created", name = playlist.name, id = playlist.id)
+                    );
+                }
+                PlaylistCommands::Delete { id } => {
+                    let service = query_service(&cli).await?;
+
+                    if !service.delete_playlist(*id as u32).await {
+                        return Err(Error::ClientError {
+                            error: t!("playlist-delete-failed", id = id),
+                        });
+                    }
+
+                    println!("{}", t!("playlist-deleted", id = id));
+                }
+            }
+
+            Ok(())
+        }
+        Commands::Changelog { since } => {
+            let releases = match since {
+                Some(version) => changelog::since(version),
+                None => changelog::since("0"),
+            };
+
+            if releases.is_empty() {
+                println!("{}", t!("changelog-empty"));
+            }
+
+            for release in releases {
+                println!("{}", release.version);
+
+                for note in release.notes {
+                    println!("  - {note}");
+                }
+
+                println!();
+            }
+
+            Ok(())
+        }
+        Commands::Calendar { refresh } => {
+            let albums = if *refresh {
+                let client = qobuz::make_client(
+                    cli.username.as_deref(),
+                    cli.password.as_deref(),
+                    cli.token.as_deref(),
+                )
+                .await?;
+
+                releases::refresh_calendar(&client)
+                    .await
+                    .ok_or_else(|| Error::ClientError {
+                        error: t!("calendar-refresh-failed"),
+                    })?
+            } else {
+                releases::calendar().await
+            };
+
+            if albums.is_empty() {
+                println!("{}", t!("calendar-empty"));
+                return Ok(());
+            }
+
+            let mut table = Table::new();
+            table.load_preset(UTF8_FULL);
+            table.set_header(vec!["date", "artist", "album", "id", ""]);
+
+            for album in albums {
+                let date = releases::release_timestamp(&album)
+                    .map(releases::format_date)
+                    .unwrap_or_default();
+                let upcoming = if album.available { "" } else { "upcoming" };
+
+                table.add_row(vec![
+                    date,
+                    album.artist.name.clone(),
+                    album.display_title(),
+                    album.id,
+                    upcoming.to_string(),
+                ]);
+            }
+
+            println!("{table}");
+
+            Ok(())
+        }
+        Commands::History { most_played, limit } => {
+            let mut table = Table::new();
+            table.load_preset(UTF8_FULL);
+
+            if *most_played {
+                let tracks = history::most_played(*limit).await;
+
+                if tracks.is_empty() {
+                    println!("{}", t!("history-empty"));
+                    return Ok(());
+                }
+
+                table.set_header(vec!["plays", "artist", "title", "album", "played", "id"]);
+
+                for track in tracks {
+                    table.add_row(vec![
+                        track.plays.to_string(),
+                        track.artist.unwrap_or_default(),
+                        track.title,
+                        track.album.unwrap_or_default(),
+                        service::format_seconds(track.seconds_played as u64),
+                        track.track_id.to_string(),
+                    ]);
+                }
+            } else {
+                let tracks = history::recent(*limit).await;
+
+                if tracks.is_empty() {
+                    println!("{}", t!("history-empty"));
+                    return Ok(());
+                }
+
+                table.set_header(vec![
+                    "played at",
+                    "artist",
+                    "title",
+                    "album",
+                    "played",
+                    "id",
+                ]);
+
+                for track in tracks {
+                    table.add_row(vec![
+                        track.played_at_label(),
+                        track.artist.unwrap_or_default(),
+                        track.title,
+                        track.album.unwrap_or_default(),
+                        format!(
+                            "{} / {}",
+                            service::format_seconds(track.duration_played as u64),
+                            service::format_seconds(track.duration as u64)
+                        ),
+                        track.track_id.to_string(),
+                    ]);
+                }
+            }
+
+            println!("{table}");
+
+            Ok(())
+        }
+        Commands::Scrobble { command } => match command {
+            ScrobbleCommands::Link {
+                service: ScrobbleService::Lastfm,
+                api_key,
+                api_secret,
+                ..
+            } => {
+                let api_key = match api_key.clone() {
+                    Some(api_key) => api_key,
+                    None => match db::get_config_value(ConfigKey::LastfmApiKey).await {
+                        Some(api_key) => api_key,
+                        None => Input::new()
+                            .with_prompt(t!("scrobble-api-key-prompt"))
+                            .interact_text()
+                            .map_err(|error| Error::TerminalError {
+                                error: error.to_string(),
+                            })?,
+                    },
+                };
+                let api_secret = match api_secret.clone() {
+                    Some(api_secret) => api_secret,
+                    None => match db::get_config_value(ConfigKey::LastfmApiSecret).await {
+                        Some(api_secret) => api_secret,
+                        None => Password::new()
+                            .with_prompt(t!("scrobble-api-secret-prompt"))
+                            .interact()
+                            .map_err(|error| Error::TerminalError {
+                                error: error.to_string(),
+                            })?,
+                    },
+                };
+
+                let mut lastfm =
+                    LastFm::new(api_key.trim().to_string(), api_secret.trim().to_string());
+                let token = lastfm.token().await?;
+
+                println!(
+                    "{}",
+                    t!("scrobble-authorize", url = lastfm.auth_url(&token))
+                );
+
+                if !Confirm::new()
+                    .with_prompt(t!("scrobble-authorized-prompt"))
                     .interact()
+                    .unwrap_or(false)
                 {
+                    return Ok(());
+                }
+
+                let user = lastfm.session(&token).await?;
+                scrobble::link_lastfm(&lastfm).await;
+
+                println!(
+                    "{}",
+                    t!(
+                        "scrobble-linked",
+                        service = ScrobbleService::Lastfm,
+                        user = user
+                    )
+                );
+
+                Ok(())
+            }
+            ScrobbleCommands::Link {
+                service: ScrobbleService::Listenbrainz,
+                token,
+                ..
+            } => {
+                let token = match token.clone() {
+                    Some(token) => token,
+                    None => Password::new()
+                        .with_prompt(t!("scrobble-token-prompt"))
+                        .interact()
+                        .map_err(|error| Error::TerminalError {
+                            error: error.to_string(),
+                        })?,
+                };
+
+                let listenbrainz = ListenBrainz::new(token.trim().to_string());
+                let user = listenbrainz.validate().await?;
+                scrobble::link_listenbrainz(&listenbrainz).await;
+
+                println!(
+                    "{}",
+                    t!(
+                        "scrobble-linked",
+                        service = ScrobbleService::Listenbrainz,
+                        user = user
+                    )
+                );
+
+                Ok(())
+            }
+            ScrobbleCommands::Unlink { service } => {
+                scrobble::unlink(*service).await;
+
+                println!("{}", t!("scrobble-unlinked", service = service));
+
+                Ok(())
+            }
+            ScrobbleCommands::Status => {
+                let scrobblers = Scrobblers::load().await;
+
+                for service in ScrobbleService::value_variants() {
+                    if scrobblers.linked(*service) {
+                        println!("{}", t!("scrobble-status-linked", service = service));
+                    } else {
+                        println!("{}", t!("scrobble-status-unlinked", service = service));
+                    }
+                }
+
+                Ok(())
+            }
+        },
+        Commands::Config { command } => match command {
+            ConfigCommands::Username {} => {
+                if let Ok(username) = Input::new()
+                    .with_prompt(t!("username-prompt"))
+                    .interact_text()
+                {
+                    db::set_username(username).await;
+
+                    println!("{}", t!("username-saved"));
+                }
+                Ok(())
+            }
+            ConfigCommands::Password {} => {
+                if let Ok(password) = Password::new()
+                    .with_prompt(t!("password-prompt"))
+                    .interact()
+                {
+                    let md5_pw = format!("{:x}", md5::compute(password));
+
+                    debug!("saving password to database: {}", md5_pw);
+
+                    db::set_password(md5_pw).await;
+
+                    println!("{}", t!("password-saved"));
+                }
+                Ok(())
+            }
+            ConfigCommands::Token {} => {
+                if let Ok(token) = Password::new().with_prompt(t!("token-prompt")).interact() {
+                    let token = token.trim().to_string();
+
+                    qobuz::make_client(None, None, Some(&token)).await?;
+                    db::set_user_token(&token).await;
+
+                    println!("{}", t!("token-saved"));
+                }
+                Ok(())
+            }
+            ConfigCommands::DefaultQuality { quality } => {
+                db::set_default_quality(quality.clone()).await;
+
+                println!("{}", t!("quality-saved"));
+
+                Ok(())
+            }
+            ConfigCommands::Language { language } => {
+                db::set_language(language.to_string()).await;
+
+                println!("{}", t!("language-saved"));
+
+                Ok(())
+            }
+            ConfigCommands::Get { key, json } => {
+                let keys = match key {
+                    Some(key) => vec![*key],
+                    None => ConfigKey::all().to_vec(),
+                };
+
+                let mut values = BTreeMap::new();
+
+                for key in keys {
+                    let value = db::get_config_value(key).await.map(|v| key.display(&v));
+
+                    values.insert(key.to_string(), value);
+                }
+
+                if *json {
+                    let json =
+                        serde_json::to_string(&values).expect("failed to convert config to json");
+                    println!("{json}");
+                } else if let (Some(key), 1) = (key, values.len()) {
+                    if let Some(Some(value)) = values.get(&key.to_string()) {
+                        println!("{value}");
+                    }
+                } else {
+                    for (key, value) in values {
+                        println!("{key}\t{}", value.unwrap_or_default());
+                    }
+                }
+
+                Ok(())
+            }
+            ConfigCommands::Set { key, value } => {
+                let value = key.parse(value)?;
+                db::set_config_value(*key, Some(value)).await;
+
+                println!("{}", t!("config-saved", key = key));
+
+                Ok(())
+            }
+            ConfigCommands::Clear {} => {
+                if let Ok(ok) = Confirm::new().with_prompt(t!("clear-prompt")).interact() {
                     if ok {
                         db::clear_state().await;
-                        println!("Database cleared.");
+                        println!("{}", t!("database-cleared"));
                     }
                 }
                 Ok(())
@@ -444,6 +2104,7 @@ macro_rules! wait {
     (mut $handles: expr, $disable_tui: expr) => {
         if !$disable_tui {
             let mut tui = CursiveUI::new();
+            tui.set_theme(config::settings().await.theme);
 
             $handles.push(tokio::spawn(async {
                 cursive::receive_notifications().await
@@ -453,6 +2114,7 @@ macro_rules! wait {
 
             debug!("tui exited, quitting");
             player::quit().await?;
+            print_session_summary();
 
             for h in $handles {
                 match h.await {
@@ -468,6 +2130,7 @@ macro_rules! wait {
 
             debug!("ctrlc received, quitting");
             player::quit().await?;
+            print_session_summary();
 
             for h in $handles {
                 match h.await {
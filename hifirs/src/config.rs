@@ -0,0 +1,596 @@
+use crate::{
+    cursive::Theme,
+    i18n::Language,
+    media_keys::KeyMap,
+    play_log::PlayLogFormat,
+    player::{
+        cache::DEFAULT_CACHE_SIZE_MB,
+        crossfade::MAX_CROSSFADE_SECS,
+        output,
+        recovery::{RecoveryPolicy, DEFAULT_MAX_RETRIES, DEFAULT_RETRY_BACKOFF_MS},
+        transition::FormatChangePolicy,
+    },
+    sql::db,
+};
+use clap::ValueEnum;
+use hifirs_qobuz_api::client::AudioQuality;
+use serde::{Deserialize, Serialize};
+use snafu::prelude::*;
+use std::{
+    fmt::Display,
+    path::{Path, PathBuf},
+};
+
+pub const DEFAULT_WEB_PORT: u16 = 9888;
+pub const DEFAULT_RESAMPLE_RATE: u32 = 96000;
+
+/// Settings stored in the config table that can be read and written from the command line.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, ValueEnum)]
+#[serde(rename_all = "camelCase")]
+pub enum ConfigKey {
+    DefaultQuality,
+    Resume,
+    WebPort,
+    Theme,
+    Language,
+    FormatChange,
+    ResampleRate,
+    MaxRetries,
+    RetryBackoff,
+    PlayLog,
+    OutputProfile,
+    PartyPin,
+    Http2,
+    HttpPoolSize,
+    HttpConnectTimeout,
+    HttpTimeout,
+    HttpKeepAlive,
+    ApiSlowThreshold,
+    ApiRateLimit,
+    ApiRetries,
+    TlsCert,
+    TlsKey,
+    MediaKeys,
+    MediaKeyMap,
+    EditionHints,
+    Normalization,
+    SimpleMode,
+    SimplePlaylists,
+    Crossfade,
+    CacheSize,
+    MusicDir,
+    OutputDevice,
+    LastfmApiKey,
+    LastfmApiSecret,
+    LastfmSession,
+    ListenbrainzToken,
+    Autoplay,
+}
+
+#[derive(Debug, Snafu)]
+pub enum Error {
+    #[snafu(display("invalid value for {key}: {message}"))]
+    InvalidValue { key: ConfigKey, message: String },
+}
+
+impl Display for ConfigKey {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self.to_possible_value() {
+            Some(value) => f.write_str(value.get_name()),
+            None => f.write_str(self.column()),
+        }
+    }
+}
+
+impl ConfigKey {
+    pub fn all() -> &'static [ConfigKey] {
+        ConfigKey::value_variants()
+    }
+
+    /// The column in the config table backing this key.
+    pub fn column(&self) -> &'static str {
+        match self {
+            ConfigKey::DefaultQuality => "default_quality",
+            ConfigKey::Resume => "resume",
+            ConfigKey::WebPort => "web_port",
+            ConfigKey::Theme => "theme",
+            ConfigKey::Language => "language",
+            ConfigKey::FormatChange => "format_change",
+            ConfigKey::ResampleRate => "resample_rate",
+            ConfigKey::MaxRetries => "max_retries",
+            ConfigKey::RetryBackoff => "retry_backoff",
+            ConfigKey::PlayLog => "play_log",
+            ConfigKey::OutputProfile => "output_profile",
+            ConfigKey::PartyPin => "party_pin",
+            ConfigKey::Http2 => "http2",
+            ConfigKey::HttpPoolSize => "http_pool_size",
+            ConfigKey::HttpConnectTimeout => "http_connect_timeout",
+            ConfigKey::HttpTimeout => "http_timeout",
+            ConfigKey::HttpKeepAlive => "http_keep_alive",
+            ConfigKey::ApiSlowThreshold => "api_slow_threshold",
+            ConfigKey::ApiRateLimit => "api_rate_limit",
+            ConfigKey::ApiRetries => "api_retries",
+            ConfigKey::TlsCert => "tls_cert",
+            ConfigKey::TlsKey => "tls_key",
+            ConfigKey::MediaKeys => "media_keys",
+            ConfigKey::MediaKeyMap => "media_key_map",
+            ConfigKey::EditionHints => "edition_hints",
+            ConfigKey::Normalization => "normalization",
+            ConfigKey::SimpleMode => "simple_mode",
+            ConfigKey::SimplePlaylists => "simple_playlists",
+            ConfigKey::Crossfade => "crossfade",
+            ConfigKey::CacheSize => "cache_size",
+            ConfigKey::MusicDir => "music_dir",
+            ConfigKey::OutputDevice => "output_device",
+            ConfigKey::LastfmApiKey => "lastfm_api_key",
+            ConfigKey::LastfmApiSecret => "lastfm_api_secret",
+            ConfigKey::LastfmSession => "lastfm_session",
+            ConfigKey::ListenbrainzToken => "listenbrainz_token",
+            ConfigKey::Autoplay => "autoplay",
+        }
+    }
+
+    /// Validate a user provided value and convert it to the form stored in the database.
+    pub fn parse(&self, value: &str) -> Result<String, Error> {
+        let invalid = |message: String| Error::InvalidValue {
+            key: *self,
+            message,
+        };
+
+        match self {
+            ConfigKey::DefaultQuality => AudioQuality::from_str(value, true)
+                .map(|q| (q as i64).to_string())
+                .map_err(invalid),
+            ConfigKey::Resume
+            | ConfigKey::Http2
+            | ConfigKey::MediaKeys
+            | ConfigKey::EditionHints
+            | ConfigKey::Normalization
+            | ConfigKey::SimpleMode
+            | ConfigKey::Autoplay => match value.to_lowercase().as_str() {
+                "true" | "yes" | "on" | "1" => Ok("1".to_string()),
+                "false" | "no" | "off" | "0" => Ok("0".to_string()),
+                _ => Err(invalid("expected true or false".to_string())),
+            },
+            ConfigKey::WebPort => match value.parse::<u16>() {
+                Ok(port) if port > 0 => Ok(port.to_string()),
+                _ => Err(invalid("expected a port between 1 and 65535".to_string())),
+            },
+            ConfigKey::Theme => Theme::from_str(value, true)
+                .map(|t| t.to_string())
+                .map_err(invalid),
+            ConfigKey::Language => Language::from_str(value, true)
+                .map(|l| l.to_string())
+                .map_err(invalid),
+            ConfigKey::FormatChange => FormatChangePolicy::from_str(value, true)
+                .map(|p| p.to_string())
+                .map_err(invalid),
+            ConfigKey::ResampleRate => match value.parse::<u32>() {
+                Ok(rate) if (8000..=768000).contains(&rate) => Ok(rate.to_string()),
+                _ => Err(invalid(
+                    "expected a sample rate between 8000 and 768000".to_string(),
+                )),
+            },
+            ConfigKey::MaxRetries => match value.parse::<u32>() {
+                Ok(retries) if retries <= 10 => Ok(retries.to_string()),
+                _ => Err(invalid("expected a number between 0 and 10".to_string())),
+            },
+            ConfigKey::RetryBackoff => match value.parse::<u64>() {
+                Ok(ms) if ms <= 60000 => Ok(ms.to_string()),
+                _ => Err(invalid(
+                    "expected a delay in milliseconds up to 60000".to_string(),
+                )),
+            },
+            ConfigKey::PlayLog => PlayLogFormat::from_str(value, true)
+                .map(|f| f.to_string())
+                .map_err(invalid),
+            ConfigKey::OutputProfile => match value.trim() {
+                "" => Err(invalid(
+                    "expected the name of an output profile".to_string(),
+                )),
+                name => Ok(name.to_string()),
+            },
+            ConfigKey::OutputDevice => match value.trim() {
+                "" => Err(invalid(
+                    "expected a device name or a sink like \"alsasink device=hw:1,0\"".to_string(),
+                )),
+                name => output::resolve_device(name).map_err(|error| invalid(error.to_string())),
+            },
+            ConfigKey::LastfmApiKey
+            | ConfigKey::LastfmApiSecret
+            | ConfigKey::LastfmSession
+            | ConfigKey::ListenbrainzToken => match value.trim() {
+                "" => Err(invalid("expected a key".to_string())),
+                key => Ok(key.to_string()),
+            },
+            ConfigKey::PartyPin => match value.trim() {
+                pin if (4..=8).contains(&pin.len()) && pin.chars().all(|c| c.is_ascii_digit()) => {
+                    Ok(pin.to_string())
+                }
+                _ => Err(invalid("expected a PIN of 4 to 8 digits".to_string())),
+            },
+            ConfigKey::HttpPoolSize => match value.parse::<u32>() {
+                Ok(size) if size <= 64 => Ok(size.to_string()),
+                _ => Err(invalid("expected a number between 0 and 64".to_string())),
+            },
+            ConfigKey::HttpConnectTimeout | ConfigKey::HttpTimeout | ConfigKey::HttpKeepAlive => {
+                match value.parse::<u32>() {
+                    Ok(secs) if (1..=600).contains(&secs) => Ok(secs.to_string()),
+                    _ => Err(invalid(
+                        "expected a number of seconds between 1 and 600".to_string(),
+                    )),
+                }
+            }
+            ConfigKey::ApiSlowThreshold => match value.parse::<u32>() {
+                Ok(ms) if (1..=60000).contains(&ms) => Ok(ms.to_string()),
+                _ => Err(invalid(
+                    "expected a number of milliseconds between 1 and 60000".to_string(),
+                )),
+            },
+            ConfigKey::ApiRateLimit => match value.parse::<u32>() {
+                Ok(rate) if rate <= 100 => Ok(rate.to_string()),
+                _ => Err(invalid(
+                    "expected calls per second between 0 and 100, 0 for no limit".to_string(),
+                )),
+            },
+            ConfigKey::ApiRetries => match value.parse::<u32>() {
+                Ok(retries) if retries <= 10 => Ok(retries.to_string()),
+                _ => Err(invalid("expected a number between 0 and 10".to_string())),
+            },
+            ConfigKey::TlsCert | ConfigKey::TlsKey => {
+                match Path::new(value.trim()).canonicalize() {
+                    Ok(path) if path.is_file() => Ok(path.to_string_lossy().to_string()),
+                    _ => Err(invalid("expected the path to a PEM file".to_string())),
+                }
+            }
+            ConfigKey::MediaKeyMap => value
+                .parse::<KeyMap>()
+                .map(|m| m.to_string())
+                .map_err(invalid),
+            ConfigKey::Crossfade => match value.parse::<u32>() {
+                Ok(secs) if secs <= MAX_CROSSFADE_SECS => Ok(secs.to_string()),
+                _ => Err(invalid(format!(
+                    "expected a number of seconds between 0 and {MAX_CROSSFADE_SECS}"
+                ))),
+            },
+            ConfigKey::CacheSize => value
+                .parse::<u64>()
+                .map(|megabytes| megabytes.to_string())
+                .map_err(|_| {
+                    invalid("expected a size in megabytes, 0 to turn caching off".to_string())
+                }),
+            ConfigKey::MusicDir => match Path::new(value.trim()).canonicalize() {
+                Ok(path) if path.is_dir() => Ok(path.to_string_lossy().to_string()),
+                _ => Err(invalid("expected the path to a directory".to_string())),
+            },
+            ConfigKey::SimplePlaylists => parse_playlist_ids(value)
+                .map(|ids| {
+                    ids.iter()
+                        .map(|id| id.to_string())
+                        .collect::<Vec<String>>()
+                        .join(",")
+                })
+                .ok_or_else(|| invalid("expected playlist ids separated by commas".to_string())),
+        }
+    }
+
+    /// Convert a stored value back into the form a user would type.
+    pub fn display(&self, stored: &str) -> String {
+        match self {
+            ConfigKey::DefaultQuality => stored
+                .parse::<i64>()
+                .ok()
+                .map(AudioQuality::from)
+                .and_then(|q| q.to_possible_value())
+                .map(|v| v.get_name().to_string())
+                .unwrap_or_else(|| stored.to_string()),
+            ConfigKey::Resume
+            | ConfigKey::Http2
+            | ConfigKey::MediaKeys
+            | ConfigKey::EditionHints
+            | ConfigKey::Normalization
+            | ConfigKey::SimpleMode
+            | ConfigKey::Autoplay => (stored == "1").to_string(),
+            ConfigKey::PartyPin
+            | ConfigKey::LastfmApiSecret
+            | ConfigKey::LastfmSession
+            | ConfigKey::ListenbrainzToken => "*".repeat(stored.len()),
+            _ => stored.to_string(),
+        }
+    }
+
+    /// True for settings that are only read when hifi-rs starts.
+    pub fn needs_restart(&self) -> bool {
+        matches!(
+            self,
+            ConfigKey::WebPort
+                | ConfigKey::Language
+                | ConfigKey::PlayLog
+                | ConfigKey::Http2
+                | ConfigKey::HttpPoolSize
+                | ConfigKey::HttpConnectTimeout
+                | ConfigKey::HttpTimeout
+                | ConfigKey::HttpKeepAlive
+                | ConfigKey::ApiSlowThreshold
+                | ConfigKey::ApiRateLimit
+                | ConfigKey::ApiRetries
+                | ConfigKey::TlsCert
+                | ConfigKey::TlsKey
+                | ConfigKey::MediaKeys
+                | ConfigKey::MediaKeyMap
+                | ConfigKey::SimpleMode
+                | ConfigKey::MusicDir
+                | ConfigKey::LastfmApiKey
+                | ConfigKey::LastfmApiSecret
+                | ConfigKey::LastfmSession
+                | ConfigKey::ListenbrainzToken
+        )
+    }
+}
+
+/// Parse a list of playlist ids like `123,456`. `None` if any of them isn't a number.
+fn parse_playlist_ids(value: &str) -> Option<Vec<i64>> {
+    value
+        .split(',')
+        .map(str::trim)
+        .filter(|id| !id.is_empty())
+        .map(|id| id.parse::<i64>().ok())
+        .collect()
+}
+
+/// Player settings with defaults applied.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Settings {
+    pub resume: bool,
+    pub web_port: u16,
+    pub theme: Theme,
+    pub format_change: FormatChangePolicy,
+    pub resample_rate: u32,
+    pub recovery: RecoveryPolicy,
+    pub play_log: PlayLogFormat,
+    pub output_profile: Option<String>,
+    /// The sink to play through, a GStreamer sink description.
+    pub output_device: Option<String>,
+    pub tls_cert: Option<PathBuf>,
+    pub tls_key: Option<PathBuf>,
+    /// Listen for media keys on the input devices, for builds with the `media-keys` feature.
+    pub media_keys: bool,
+    pub media_key_map: KeyMap,
+    /// Show editions like `(2019 Remaster)` after album and track titles.
+    pub edition_hints: bool,
+    /// Even out the loudness of tracks, scanning the ones without ReplayGain tags.
+    pub normalization: bool,
+    /// Start in the simplified TUI, unlocked with the party mode PIN.
+    pub simple_mode: bool,
+    /// The playlists that can be started in simple mode.
+    pub simple_playlists: Vec<i64>,
+    /// Seconds the end of a track overlaps with the next one, 0 for none.
+    pub crossfade: u32,
+    /// Megabytes of streamed tracks kept in the track cache, 0 to keep none.
+    pub cache_size: u64,
+    /// Where local music files are, scanned when hifi-rs starts.
+    pub music_dir: Option<PathBuf>,
+    /// Add tracks by similar artists when the queue is about to run out.
+    pub autoplay: bool,
+}
+
+impl Default for Settings {
+    fn default() -> Self {
+        Self {
+            resume: true,
+            web_port: DEFAULT_WEB_PORT,
+            theme: Theme::default(),
+            format_change: FormatChangePolicy::default(),
+            resample_rate: DEFAULT_RESAMPLE_RATE,
+            recovery: RecoveryPolicy::default(),
+            play_log: PlayLogFormat::default(),
+            output_profile: None,
+            output_device: None,
+            tls_cert: None,
+            tls_key: None,
+            media_keys: false,
+            media_key_map: KeyMap::default(),
+            edition_hints: true,
+            normalization: false,
+            simple_mode: false,
+            simple_playlists: Vec::new(),
+            crossfade: 0,
+            cache_size: DEFAULT_CACHE_SIZE_MB,
+            music_dir: None,
+            autoplay: false,
+        }
+    }
+}
+
+impl Settings {
+    /// The keys whose values differ from `other`.
+    pub fn changed(&self, other: &Settings) -> Vec<ConfigKey> {
+        let mut changed = Vec::new();
+
+        if self.resume != other.resume {
+            changed.push(ConfigKey::Resume);
+        }
+        if self.web_port != other.web_port {
+            changed.push(ConfigKey::WebPort);
+        }
+        if self.theme != other.theme {
+            changed.push(ConfigKey::Theme);
+        }
+        if self.format_change != other.format_change {
+            changed.push(ConfigKey::FormatChange);
+        }
+        if self.resample_rate != other.resample_rate {
+            changed.push(ConfigKey::ResampleRate);
+        }
+        if self.recovery.max_retries != other.recovery.max_retries {
+            changed.push(ConfigKey::MaxRetries);
+        }
+        if self.recovery.backoff_ms != other.recovery.backoff_ms {
+            changed.push(ConfigKey::RetryBackoff);
+        }
+        if self.play_log != other.play_log {
+            changed.push(ConfigKey::PlayLog);
+        }
+        if self.output_profile != other.output_profile {
+            changed.push(ConfigKey::OutputProfile);
+        }
+        if self.output_device != other.output_device {
+            changed.push(ConfigKey::OutputDevice);
+        }
+        if self.tls_cert != other.tls_cert {
+            changed.push(ConfigKey::TlsCert);
+        }
+        if self.tls_key != other.tls_key {
+            changed.push(ConfigKey::TlsKey);
+        }
+        if self.media_keys != other.media_keys {
+            changed.push(ConfigKey::MediaKeys);
+        }
+        if self.media_key_map != other.media_key_map {
+            changed.push(ConfigKey::MediaKeyMap);
+        }
+        if self.edition_hints != other.edition_hints {
+            changed.push(ConfigKey::EditionHints);
+        }
+        if self.normalization != other.normalization {
+            changed.push(ConfigKey::Normalization);
+        }
+        if self.simple_mode != other.simple_mode {
+            changed.push(ConfigKey::SimpleMode);
+        }
+        if self.simple_playlists != other.simple_playlists {
+            changed.push(ConfigKey::SimplePlaylists);
+        }
+        if self.crossfade != other.crossfade {
+            changed.push(ConfigKey::Crossfade);
+        }
+        if self.cache_size != other.cache_size {
+            changed.push(ConfigKey::CacheSize);
+        }
+        if self.music_dir != other.music_dir {
+            changed.push(ConfigKey::MusicDir);
+        }
+        if self.autoplay != other.autoplay {
+            changed.push(ConfigKey::Autoplay);
+        }
+
+        changed
+    }
+
+    /// The certificate and key the web server uses, when both are set.
+    pub fn tls(&self) -> Option<(PathBuf, PathBuf)> {
+        Some((self.tls_cert.clone()?, self.tls_key.clone()?))
+    }
+}
+
+/// Load the settings from the database.
+pub async fn settings() -> Settings {
+    let defaults = Settings::default();
+
+    let resume = db::get_config_value(ConfigKey::Resume)
+        .await
+        .map(|v| v == "1")
+        .unwrap_or(defaults.resume);
+    let web_port = db::get_config_value(ConfigKey::WebPort)
+        .await
+        .and_then(|v| v.parse::<u16>().ok())
+        .unwrap_or(defaults.web_port);
+    let theme = db::get_config_value(ConfigKey::Theme)
+        .await
+        .and_then(|v| Theme::from_str(&v, true).ok())
+        .unwrap_or(defaults.theme);
+    let format_change = db::get_config_value(ConfigKey::FormatChange)
+        .await
+        .and_then(|v| FormatChangePolicy::from_str(&v, true).ok())
+        .unwrap_or(defaults.format_change);
+    let resample_rate = db::get_config_value(ConfigKey::ResampleRate)
+        .await
+        .and_then(|v| v.parse::<u32>().ok())
+        .unwrap_or(defaults.resample_rate);
+    let max_retries = db::get_config_value(ConfigKey::MaxRetries)
+        .await
+        .and_then(|v| v.parse::<u32>().ok())
+        .unwrap_or(DEFAULT_MAX_RETRIES);
+    let backoff_ms = db::get_config_value(ConfigKey::RetryBackoff)
+        .await
+        .and_then(|v| v.parse::<u64>().ok())
+        .unwrap_or(DEFAULT_RETRY_BACKOFF_MS);
+    let play_log = db::get_config_value(ConfigKey::PlayLog)
+        .await
+        .and_then(|v| PlayLogFormat::from_str(&v, true).ok())
+        .unwrap_or(defaults.play_log);
+    let output_profile = db::get_config_value(ConfigKey::OutputProfile).await;
+    let output_device = db::get_config_value(ConfigKey::OutputDevice).await;
+    let tls_cert = db::get_config_value(ConfigKey::TlsCert)
+        .await
+        .map(PathBuf::from);
+    let tls_key = db::get_config_value(ConfigKey::TlsKey)
+        .await
+        .map(PathBuf::from);
+    let media_keys = db::get_config_value(ConfigKey::MediaKeys)
+        .await
+        .map(|v| v == "1")
+        .unwrap_or(defaults.media_keys);
+    let media_key_map = db::get_config_value(ConfigKey::MediaKeyMap)
+        .await
+        .and_then(|v| v.parse::<KeyMap>().ok())
+        .unwrap_or(defaults.media_key_map);
+    let edition_hints = db::get_config_value(ConfigKey::EditionHints)
+        .await
+        .map(|v| v == "1")
+        .unwrap_or(defaults.edition_hints);
+    let normalization = db::get_config_value(ConfigKey::Normalization)
+        .await
+        .map(|v| v == "1")
+        .unwrap_or(defaults.normalization);
+    let simple_mode = db::get_config_value(ConfigKey::SimpleMode)
+        .await
+        .map(|v| v == "1")
+        .unwrap_or(defaults.simple_mode);
+    let simple_playlists = db::get_config_value(ConfigKey::SimplePlaylists)
+        .await
+        .and_then(|v| parse_playlist_ids(&v))
+        .unwrap_or(defaults.simple_playlists);
+    let crossfade = db::get_config_value(ConfigKey::Crossfade)
+        .await
+        .and_then(|v| v.parse::<u32>().ok())
+        .unwrap_or(defaults.crossfade);
+    let cache_size = db::get_config_value(ConfigKey::CacheSize)
+        .await
+        .and_then(|v| v.parse::<u64>().ok())
+        .unwrap_or(defaults.cache_size);
+    let music_dir = db::get_config_value(ConfigKey::MusicDir)
+        .await
+        .map(PathBuf::from);
+    let autoplay = db::get_config_value(ConfigKey::Autoplay)
+        .await
+        .map(|v| v == "1")
+        .unwrap_or(defaults.autoplay);
+
+    Settings {
+        resume,
+        web_port,
+        theme,
+        format_change,
+        resample_rate,
+        recovery: RecoveryPolicy {
+            max_retries,
+            backoff_ms,
+        },
+        play_log,
+        output_profile,
+        output_device,
+        tls_cert,
+        tls_key,
+        media_keys,
+        media_key_map,
+        edition_hints,
+        normalization,
+        simple_mode,
+        simple_playlists,
+        crossfade,
+        cache_size,
+        music_dir,
+        autoplay,
+    }
+}
@@ -0,0 +1,173 @@
+use crate::{
+    player::{self, actions::Action},
+    service::Track,
+    t, tls,
+};
+use futures::{SinkExt, StreamExt};
+use gstreamer::State as GstState;
+use serde::{Deserialize, Serialize};
+use snafu::prelude::*;
+use std::{net::SocketAddr, path::Path, sync::Arc, time::Duration};
+use tokio::net::TcpStream;
+use tokio_tungstenite::{
+    connect_async, connect_async_tls_with_config, tungstenite::Message, Connector, MaybeTlsStream,
+    WebSocketStream,
+};
+
+/// How long to wait for the running player to connect or answer.
+const TIMEOUT: Duration = Duration::from_secs(5);
+
+type Socket = WebSocketStream<MaybeTlsStream<TcpStream>>;
+
+#[derive(Debug, Snafu)]
+pub enum Error {
+    #[snafu(display("{}", t!("ctl-unreachable", address = address)))]
+    Unreachable { address: SocketAddr },
+    #[snafu(display("{}", t!("ctl-no-answer")))]
+    NoAnswer,
+    #[snafu(display("{error}"))]
+    Tls { error: tls::Error },
+}
+
+pub type Result<T, E = Error> = std::result::Result<T, E>;
+
+/// What the player is doing, as answered to a `status` action.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PlayerStatus {
+    pub state: GstState,
+    pub track: Option<Track>,
+    pub position_seconds: u64,
+    pub duration_seconds: u64,
+    /// Between 0.0 and 1.0.
+    pub volume: f64,
+    #[serde(default)]
+    pub shuffle: bool,
+}
+
+impl PlayerStatus {
+    pub async fn current() -> Self {
+        Self {
+            state: player::current_state(),
+            track: player::current_track().await,
+            position_seconds: player::position().map(|p| p.seconds()).unwrap_or_default(),
+            duration_seconds: player::duration().map(|d| d.seconds()).unwrap_or_default(),
+            volume: player::volume(),
+            shuffle: player::is_shuffled().await,
+        }
+    }
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct StatusAnswer {
+    player_status: PlayerStatus,
+}
+
+/// A seek target from the command line: `+30` and `-10` are relative to the current
+/// position, `90` is an absolute position and `50%` is half way through the track.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum SeekTarget {
+    Seconds { seconds: i64, relative: bool },
+    Percent(f32),
+}
+
+pub fn parse_seek(value: &str) -> std::result::Result<SeekTarget, String> {
+    let value = value.trim();
+    let invalid = || t!("ctl-invalid-seek", value = value);
+
+    if let Some(percent) = value.strip_suffix('%') {
+        return match percent.trim().parse::<f32>() {
+            Ok(percent) if (0.0..=100.0).contains(&percent) => Ok(SeekTarget::Percent(percent)),
+            _ => Err(invalid()),
+        };
+    }
+
+    let relative = value.starts_with('+') || value.starts_with('-');
+
+    value
+        .parse::<i64>()
+        .map(|seconds| SeekTarget::Seconds { seconds, relative })
+        .map_err(|_| invalid())
+}
+
+/// Connects to the websocket of the player running at `address`. Only the quit
+/// notification is subscribed to, the answers to actions are always sent.
+/// With `cert`, the connection uses TLS and trusts only that certificate.
+async fn connect(address: SocketAddr, cert: Option<&Path>) -> Result<Socket> {
+    let path = "/ws?name=hifi-rs%20ctl&topics=quit";
+
+    let connecting = match cert {
+        Some(cert) => {
+            let config = tls::client_config(cert).map_err(|error| Error::Tls { error })?;
+            let connector = Connector::Rustls(Arc::new(config));
+
+            tokio::time::timeout(
+                TIMEOUT,
+                connect_async_tls_with_config(
+                    format!("wss://{address}{path}"),
+                    None,
+                    false,
+                    Some(connector),
+                ),
+            )
+            .await
+        }
+        None => tokio::time::timeout(TIMEOUT, connect_async(format!("ws://{address}{path}"))).await,
+    };
+
+    match connecting {
+        Ok(Ok((socket, _))) => Ok(socket),
+        _ => Err(Error::Unreachable { address }),
+    }
+}
+
+async fn send_action(socket: &mut Socket, address: SocketAddr, action: &Action) -> Result<()> {
+    let json = serde_json::to_string(action).expect("failed to serialize action");
+
+    socket
+        .send(Message::Text(json))
+        .await
+        .map_err(|_| Error::Unreachable { address })
+}
+
+/// Send an action to the player running at `address`.
+pub async fn send(address: SocketAddr, cert: Option<&Path>, action: Action) -> Result<()> {
+    let mut socket = connect(address, cert).await?;
+
+    send_action(&mut socket, address, &action).await?;
+    socket.close(None).await.ok();
+
+    Ok(())
+}
+
+/// Ask the player running at `address` what it is doing.
+pub async fn status(address: SocketAddr, cert: Option<&Path>) -> Result<PlayerStatus> {
+    let mut socket = connect(address, cert).await?;
+
+    send_action(&mut socket, address, &Action::Status).await?;
+
+    // The player first sends its current state to every new connection, skip ahead to the answer.
+    let answer = tokio::time::timeout(TIMEOUT, async {
+        while let Some(message) = socket.next().await {
+            match message {
+                Ok(Message::Text(text)) => {
+                    if let Ok(answer) = serde_json::from_str::<StatusAnswer>(&text) {
+                        return Some(answer.player_status);
+                    }
+                }
+                Ok(_) => {}
+                Err(_) => break,
+            }
+        }
+
+        None
+    })
+    .await
+    .ok()
+    .flatten();
+
+    socket.close(None).await.ok();
+
+    answer.context(NoAnswerSnafu)
+}
@@ -0,0 +1,193 @@
+use crate::artwork::{self, Protocol};
+use cursive::{
+    theme::{Color, ColorStyle},
+    Cursive, Printer, Vec2, View,
+};
+use image::RgbImage;
+use once_cell::sync::Lazy;
+use std::{
+    io::Write,
+    sync::{Arc, Mutex},
+};
+
+/// Columns taken up by the cover, twice its rows as cells are about twice as tall as wide.
+pub const COVER_COLUMNS: usize = 16;
+pub const COVER_ROWS: usize = 8;
+/// The kitty image id covers are shown with, so a new one replaces the last.
+const KITTY_IMAGE_ID: u32 = 4817;
+
+static COVER: Lazy<Mutex<Cover>> = Lazy::new(|| Mutex::new(Cover::default()));
+
+/// The cover of the current track and where it was drawn. Graphics protocols write to the
+/// terminal after a frame, so the view only notes where the cover goes.
+#[derive(Default)]
+struct Cover {
+    url: Option<String>,
+    image: Option<Arc<RgbImage>>,
+    /// Bumped whenever the image changes.
+    version: u64,
+    /// Screen position and size of the view in the last frame.
+    drawn: Option<(Vec2, Vec2)>,
+    /// What the terminal shows now, as version, position and size.
+    shown: Option<(u64, Vec2, Vec2)>,
+    /// The last sixel image, which has to be sent after every frame.
+    sixel: Option<(u64, Vec2, String)>,
+}
+
+/// The url of the cover being shown or loaded.
+pub fn url() -> Option<String> {
+    COVER.lock().unwrap().url.clone()
+}
+
+/// Start showing the cover at `url`, `image` is filled in once it has loaded.
+pub fn set(url: Option<String>, image: Option<RgbImage>) {
+    let mut cover = COVER.lock().unwrap();
+
+    cover.url = url;
+    cover.image = image.map(Arc::new);
+    cover.version += 1;
+}
+
+pub fn is_loaded() -> bool {
+    COVER.lock().unwrap().image.is_some()
+}
+
+/// Write the cover with the kitty or sixel protocol, once the frame it was laid out in is on
+/// the terminal. A popup over the player hides it.
+pub fn present(siv: &mut Cursive) {
+    let protocol = artwork::protocol();
+
+    if !matches!(protocol, Protocol::Kitty | Protocol::Sixel) {
+        return;
+    }
+
+    let mut cover = COVER.lock().unwrap();
+    let drawn = cover.drawn.take().filter(|_| siv.screen().len() == 1);
+    let mut escapes = String::new();
+
+    match (drawn, cover.image.clone()) {
+        (Some((position, size)), Some(image)) if size.x > 0 && size.y > 0 => {
+            let version = cover.version;
+
+            let image = match protocol {
+                Protocol::Kitty if cover.shown != Some((version, position, size)) => {
+                    Some(artwork::kitty(&image, KITTY_IMAGE_ID, size.x, size.y))
+                }
+                Protocol::Sixel => match &cover.sixel {
+                    Some((v, s, sixel)) if *v == version && *s == size => Some(sixel.clone()),
+                    _ => {
+                        let sixel = artwork::sixel(&image, size.x, size.y);
+                        cover.sixel = Some((version, size, sixel.clone()));
+                        Some(sixel)
+                    }
+                },
+                _ => None,
+            };
+
+            if let Some(image) = image {
+                if protocol == Protocol::Kitty && cover.shown.is_some() {
+                    escapes.push_str(&artwork::kitty_delete(KITTY_IMAGE_ID));
+                }
+
+                // Save the cursor, draw at the view and put the cursor back.
+                escapes.push_str(&format!(
+                    "\x1b7\x1b[{};{}H{image}\x1b8",
+                    position.y + 1,
+                    position.x + 1
+                ));
+                cover.shown = Some((version, position, size));
+            }
+        }
+        _ => {
+            if cover.shown.take().is_some() && protocol == Protocol::Kitty {
+                escapes.push_str(&artwork::kitty_delete(KITTY_IMAGE_ID));
+            }
+        }
+    }
+
+    if !escapes.is_empty() {
+        let mut stdout = std::io::stdout();
+
+        if let Err(error) = stdout
+            .write_all(escapes.as_bytes())
+            .and_then(|_| stdout.flush())
+        {
+            debug!(?error, "failed to draw cover");
+        }
+    }
+}
+
+/// Take the cover off the terminal when the TUI quits.
+pub fn clear() {
+    if artwork::protocol() == Protocol::Kitty && COVER.lock().unwrap().shown.take().is_some() {
+        print!("{}", artwork::kitty_delete(KITTY_IMAGE_ID));
+        std::io::stdout().flush().ok();
+    }
+}
+
+/// Draws the cover with half blocks, or marks where a graphics protocol should draw it.
+#[derive(Default)]
+pub struct CoverView {
+    /// Half block colors for the cover version and size they were made for.
+    cells: Option<(u64, Vec2, Vec<Vec<([u8; 3], [u8; 3])>>)>,
+}
+
+impl CoverView {
+    pub fn new() -> Self {
+        Self::default()
+    }
+}
+
+impl View for CoverView {
+    fn required_size(&mut self, _constraint: Vec2) -> Vec2 {
+        Vec2::new(COVER_COLUMNS, COVER_ROWS)
+    }
+
+    fn layout(&mut self, size: Vec2) {
+        if artwork::protocol() != Protocol::Halfblocks {
+            return;
+        }
+
+        let cover = COVER.lock().unwrap();
+
+        let stale = match &self.cells {
+            Some((version, cells_size, _)) => *version != cover.version || *cells_size != size,
+            None => true,
+        };
+
+        if stale {
+            self.cells = cover.image.as_ref().map(|image| {
+                (
+                    cover.version,
+                    size,
+                    artwork::halfblocks(image, size.x, size.y),
+                )
+            });
+        }
+    }
+
+    fn draw(&self, printer: &Printer) {
+        match artwork::protocol() {
+            Protocol::Halfblocks => {
+                let Some((_, _, cells)) = &self.cells else {
+                    return;
+                };
+
+                for (y, row) in cells.iter().enumerate() {
+                    for (x, (top, bottom)) in row.iter().enumerate() {
+                        let style = ColorStyle::new(
+                            Color::Rgb(top[0], top[1], top[2]),
+                            Color::Rgb(bottom[0], bottom[1], bottom[2]),
+                        );
+
+                        printer.with_color(style, |p| p.print((x, y), "▀"));
+                    }
+                }
+            }
+            Protocol::Kitty | Protocol::Sixel => {
+                COVER.lock().unwrap().drawn = Some((printer.offset, printer.output_size));
+            }
+            _ => {}
+        }
+    }
+}
@@ -0,0 +1,133 @@
+use cursive::{
+    direction::Orientation,
+    view::{IntoBoxedView, Resizable, SizeConstraint},
+    views::{BoxedView, LinearLayout},
+};
+use std::sync::atomic::{AtomicUsize, Ordering};
+use unicode_width::{UnicodeWidthChar, UnicodeWidthStr};
+
+/// Columns taken up by panel borders and the scrollbar around a list.
+const LIST_CHROME: usize = 4;
+
+static SCREEN_WIDTH: AtomicUsize = AtomicUsize::new(80);
+
+/// Width of the terminal as of the last resize.
+pub fn screen_width() -> usize {
+    SCREEN_WIDTH.load(Ordering::Relaxed)
+}
+
+pub fn set_screen_width(width: usize) {
+    SCREEN_WIDTH.store(width, Ordering::Relaxed);
+}
+
+/// Columns left for the flexible part of a list row once `fixed`
+/// columns have been handed out to the other parts of the row.
+pub fn column_width(fixed: usize) -> usize {
+    screen_width().saturating_sub(fixed + LIST_CHROME).max(1)
+}
+
+/// Pad or truncate `text` so it takes up exactly `width` columns. Wide characters, as in
+/// CJK titles, take two columns and combining marks none.
+pub fn fit(text: &str, width: usize) -> String {
+    let length = text.width();
+
+    if length <= width {
+        return format!("{text}{}", " ".repeat(width - length));
+    }
+
+    if width == 0 {
+        return String::new();
+    }
+
+    let mut truncated = String::new();
+    let mut taken = 0;
+
+    for c in text.chars() {
+        let columns = c.width().unwrap_or(0);
+
+        if taken + columns > width - 1 {
+            break;
+        }
+
+        truncated.push(c);
+        taken += columns;
+    }
+
+    truncated.push('…');
+    // A wide character that didn't fit leaves a column to pad.
+    truncated.push_str(&" ".repeat(width - 1 - taken));
+    truncated
+}
+
+/// The layout used by the player panel: a header made of a fixed width
+/// column on each side of a column that takes up the remaining width,
+/// followed by full width rows.
+pub struct PanelLayout {
+    left: Option<(BoxedView, usize)>,
+    center: BoxedView,
+    right: Option<(BoxedView, usize)>,
+    rows: Vec<BoxedView>,
+}
+
+impl PanelLayout {
+    pub fn new<V: IntoBoxedView>(center: V) -> Self {
+        Self {
+            left: None,
+            center: BoxedView::new(center.into_boxed_view()),
+            right: None,
+            rows: Vec::new(),
+        }
+    }
+
+    /// Add a column of `width` to the left of the header.
+    pub fn left<V: IntoBoxedView>(mut self, view: V, width: usize) -> Self {
+        self.left = Some((BoxedView::new(view.into_boxed_view()), width));
+        self
+    }
+
+    /// Add a column of `width` to the right of the header.
+    pub fn right<V: IntoBoxedView>(mut self, view: V, width: usize) -> Self {
+        self.right = Some((BoxedView::new(view.into_boxed_view()), width));
+        self
+    }
+
+    /// Add a row below the header.
+    pub fn row<V: IntoBoxedView>(mut self, view: V) -> Self {
+        self.rows.push(BoxedView::new(view.into_boxed_view()));
+        self
+    }
+
+    pub fn build(self) -> LinearLayout {
+        let mut header = LinearLayout::new(Orientation::Horizontal);
+
+        if let Some((view, width)) = self.left {
+            header.add_child(view.fixed_width(width));
+        }
+
+        header.add_child(
+            self.center
+                .resized(SizeConstraint::Full, SizeConstraint::Free),
+        );
+
+        if let Some((view, width)) = self.right {
+            header.add_child(view.fixed_width(width));
+        }
+
+        let mut container = LinearLayout::new(Orientation::Vertical).child(header);
+
+        for row in self.rows {
+            container.add_child(row);
+        }
+
+        container
+    }
+}
+
+#[test]
+fn fits_wide_characters_by_columns() {
+    assert_eq!(fit("abc", 5), "abc  ");
+    assert_eq!(fit("abcdef", 4), "abc…");
+    assert_eq!(fit("日本語", 6), "日本語");
+    assert_eq!(fit("日本語", 4), "日… ");
+    assert_eq!(fit("日本語", 3), "日…");
+}
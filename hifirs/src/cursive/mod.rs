@@ -1,42 +1,110 @@
 use std::{
+    fmt::Display,
     rc::Rc,
     sync::{
-        atomic::{AtomicBool, Ordering},
-        Arc,
+        atomic::{AtomicBool, AtomicU64, Ordering},
+        Arc, Mutex,
     },
+    time::{Duration, Instant},
 };
 
 use crate::{
-    player::{self, notification::Notification, queue::TrackListType},
-    service::{SearchResults, Track, TrackStatus},
+    artwork::{self, Protocol},
+    changelog::{self, Release},
+    config::{self, ConfigKey},
+    favorites::{self, FavoriteFilter, FavoriteSort, Favorites},
+    history,
+    library::{self, LibraryEntry, LibraryKind},
+    lyrics::{self, Lyrics},
+    player::{
+        self,
+        audition::{self, Audition},
+        autoplay, cache, event_log,
+        notification::{Notification, RemoteClient},
+        output::{self, OutputProfile},
+        party,
+        queue::{TrackListType, TrackListValue},
+        session, simple,
+        stats::{self, StreamStats},
+        transition,
+    },
+    releases,
+    service::{
+        self, Album, BrowseCategory, BrowsePage, Favorite, PersonalPlaylist, Playlist,
+        SearchFilter, SearchLimits, SearchResults, SearchType, Track, TrackStatus,
+    },
+    sql::db,
+    t, websocket,
 };
+use clap::ValueEnum;
 use cursive::{
     align::HAlign,
     direction::Orientation,
-    event::{Event, Key},
+    event::{Event, EventResult, Key},
     reexports::crossbeam_channel::Sender,
     theme::{BorderStyle, ColorStyle, Effect, Palette, Style},
     utils::{markup::StyledString, Counter},
-    view::{Nameable, Position, Resizable, Scrollable, SizeConstraint},
+    view::{Nameable, Position, Resizable, ScrollStrategy, Scrollable, SizeConstraint},
     views::{
-        Button, Dialog, EditView, HideableView, Layer, LinearLayout, MenuPopup, NamedView,
-        OnEventView, PaddedView, Panel, ProgressBar, ResizedView, ScreensView, ScrollView,
-        SelectView, TextView,
+        BoxedView, Button, Checkbox, Dialog, EditView, HideableView, Layer, LayerPosition,
+        LinearLayout, MenuPopup, NamedView, OnEventView, PaddedView, Panel, ProgressBar,
+        ResizedView, ScreensView, ScrollView, SelectView, TextView,
     },
-    CbSink, Cursive, CursiveRunnable, With,
+    CbSink, Cursive, CursiveRunnable, View, With,
 };
-use futures::executor::block_on;
 use gstreamer::{ClockTime, State as GstState};
-use once_cell::sync::OnceCell;
+use hifirs_qobuz_api::client::{metrics, AudioQuality};
+use once_cell::sync::{Lazy, OnceCell};
+use serde::{Deserialize, Serialize};
 use tokio::select;
 use tokio_stream::StreamExt;
 
+use cover::CoverView;
+use layout::PanelLayout;
+use scrubber::{Preview, Scrubber};
+
+pub mod cover;
+pub mod layout;
+pub mod scrubber;
+pub mod snapshot;
+
 type CursiveSender = Sender<Box<dyn FnOnce(&mut Cursive) + Send>>;
 
 static SINK: OnceCell<CursiveSender> = OnceCell::new();
 
 static UNSTREAMABLE: &str = "UNSTREAMABLE";
 static ENTER_URL_OPEN: AtomicBool = AtomicBool::new(false);
+static SHOW_VISUALIZATION: AtomicBool = AtomicBool::new(true);
+static SHOW_STATS: AtomicBool = AtomicBool::new(false);
+/// The track lyrics were last looked up for, and what was found.
+static LYRICS: Lazy<Mutex<(Option<u32>, Option<Lyrics>)>> = Lazy::new(Default::default);
+static SHUFFLE: AtomicBool = AtomicBool::new(false);
+/// The version of the queue the track list was last filled from.
+static QUEUE_GENERATION: AtomicU64 = AtomicU64::new(0);
+static SPECTRUM_BLOCKS: &[char] = &[' ', '▁', '▂', '▃', '▄', '▅', '▆', '▇', '█'];
+static EVENT_LOG_SCREEN: usize = 5;
+static CACHED_TRACKS_SCREEN: usize = 7;
+static RELEASE_CALENDAR_SCREEN: usize = 8;
+static HISTORY_SCREEN: usize = 9;
+static DISCOVER_SCREEN: usize = 10;
+static SIMPLE_SCREEN: usize = 11;
+/// Endpoints listed under the gapless stats on the event log screen.
+static API_LATENCY_ROWS: usize = 5;
+/// Descriptions are scrolled once they are taller than this.
+static DESCRIPTION_HEIGHT: usize = 8;
+static DESCRIPTION_WIDTH: usize = 72;
+/// How many of an artist's albums are fetched ahead when their menu opens.
+static ARTIST_PREFETCH: usize = 24;
+static BROWSE: Lazy<Mutex<BrowsePage>> = Lazy::new(|| Mutex::new(BrowsePage::default()));
+static FAVORITES: Lazy<Mutex<Favorites>> = Lazy::new(|| Mutex::new(Favorites::default()));
+static CALENDAR: Lazy<Mutex<Vec<Album>>> = Lazy::new(|| Mutex::new(Vec::new()));
+/// The featured album sections shown on the Discover screen.
+static DISCOVER_SECTIONS: [BrowseCategory; 3] = [
+    BrowseCategory::NewReleases,
+    BrowseCategory::PressAwards,
+    BrowseCategory::EditorPicks,
+];
+static SEARCH_FILTER: Lazy<Mutex<SearchFilter>> = Lazy::new(|| Mutex::new(SearchFilter::default()));
 
 pub struct CursiveUI {
     root: CursiveRunnable,
@@ -48,46 +116,16 @@ impl CursiveUI {
 
         SINK.set(siv.cb_sink().clone()).expect("error setting sink");
 
-        siv.set_theme(cursive::theme::Theme {
-            shadow: false,
-            borders: BorderStyle::Simple,
-            palette: Palette::terminal_default().with(|palette| {
-                use cursive::theme::BaseColor::*;
-
-                {
-                    use cursive::theme::Color::TerminalDefault;
-                    use cursive::theme::PaletteColor::*;
-
-                    palette[Background] = TerminalDefault;
-                    palette[View] = TerminalDefault;
-                    palette[Primary] = White.dark();
-                    palette[Highlight] = Cyan.dark();
-                    palette[HighlightInactive] = Black.dark();
-                    palette[HighlightText] = Black.dark();
-                }
-
-                {
-                    use cursive::theme::Color::TerminalDefault;
-                    use cursive::theme::Effect::*;
-                    use cursive::theme::PaletteStyle::*;
-
-                    palette[Highlight] = Style::from(Cyan.dark())
-                        .combine(Underline)
-                        .combine(Reverse)
-                        .combine(Bold);
-                    palette[HighlightInactive] = Style::from(TerminalDefault).combine(Reverse);
-                    palette[TitlePrimary] = Style::from(Cyan.dark()).combine(Bold);
-                }
-            }),
-        });
+        siv.set_theme(build_theme(Theme::default()));
 
         Self { root: siv }
     }
 
-    pub fn player(&self) -> LinearLayout {
-        let mut container = LinearLayout::new(Orientation::Vertical);
-        let mut track_info = LinearLayout::new(Orientation::Horizontal);
+    pub fn set_theme(&mut self, theme: Theme) {
+        self.root.set_theme(build_theme(theme));
+    }
 
+    pub fn player(&self) -> LinearLayout {
         let meta = PaddedView::lrtb(
             1,
             1,
@@ -100,7 +138,8 @@ impl CursiveUI {
                         .with_name("current_track_title")
                         .scrollable()
                         .show_scrollbars(false)
-                        .scroll_x(true),
+                        .scroll_x(true)
+                        .with_name("current_track_title_scroll"),
                 )
                 .child(TextView::new("").with_name("artist_name"))
                 .child(
@@ -108,10 +147,10 @@ impl CursiveUI {
                         .with_name("entity_title")
                         .scrollable()
                         .show_scrollbars(false)
-                        .scroll_x(true),
+                        .scroll_x(true)
+                        .with_name("entity_title_scroll"),
                 ),
-        )
-        .resized(SizeConstraint::Full, SizeConstraint::Free);
+        );
 
         let track_num = LinearLayout::new(Orientation::Vertical)
             .child(
@@ -119,17 +158,16 @@ impl CursiveUI {
                     .h_align(HAlign::Left)
                     .with_name("current_track_number"),
             )
-            .child(TextView::new("of").h_align(HAlign::Center))
+            .child(TextView::new(t!("track-count-separator")).h_align(HAlign::Center))
             .child(
                 TextView::new("000")
                     .h_align(HAlign::Left)
                     .with_name("total_tracks"),
-            )
-            .fixed_width(3);
+            );
 
         let player_status = LinearLayout::new(Orientation::Vertical)
             .child(
-                TextView::new(format!(" {}", '\u{23f9}'))
+                TextView::new(get_state_icon(snapshot::status()))
                     .h_align(HAlign::Center)
                     .with_name("player_status"),
             )
@@ -143,111 +181,187 @@ impl CursiveUI {
                     .h_align(HAlign::Right)
                     .with_name("sample_rate"),
             )
-            .fixed_width(8);
+            .child(
+                HideableView::new(
+                    TextView::new("")
+                        .style(Style::from(Effect::Dim))
+                        .h_align(HAlign::Right)
+                        .with_name("fallback_quality"),
+                )
+                .visible(false)
+                .with_name("fallback_quality_row"),
+            );
 
         let counter = Counter::new(0);
-        let progress = ProgressBar::new()
-            .with_value(counter)
-            .with_label(|value, (_, max)| {
-                let position =
-                    ClockTime::from_seconds(value as u64).to_string().as_str()[2..7].to_string();
-                let duration =
-                    ClockTime::from_seconds(max as u64).to_string().as_str()[2..7].to_string();
-
-                format!("{position} / {duration}")
-            })
-            .with_name("progress");
+        let preview = Preview::default();
+        let label = preview.clone();
+        let progress = Scrubber::new(
+            ProgressBar::new()
+                .with_value(counter)
+                .with_label(move |value, (_, max)| label.label(value, max))
+                .with_name("progress"),
+            preview,
+        );
 
-        track_info.add_child(track_num);
-        track_info.add_child(meta);
-        track_info.add_child(player_status);
+        let list_progress = HideableView::new(
+            TextView::new("")
+                .style(Style::from(Effect::Dim))
+                .h_align(HAlign::Center)
+                .with_name("list_progress"),
+        )
+        .visible(false)
+        .with_name("list_progress_row");
+
+        let modes_label = modes_label();
+        let modes = HideableView::new(
+            TextView::new(modes_label.clone().unwrap_or_default())
+                .style(Style::from(Effect::Dim))
+                .h_align(HAlign::Right)
+                .with_name("modes"),
+        )
+        .visible(modes_label.is_some())
+        .with_name("modes_row");
+
+        let visualization =
+            HideableView::new(TextView::new("").no_wrap().with_name("visualization"))
+                .visible(false)
+                .with_name("visualization_row");
+
+        let stats = HideableView::new(
+            TextView::new("")
+                .style(Style::from(Effect::Dim))
+                .h_align(HAlign::Center)
+                .with_name("stats"),
+        )
+        .visible(false)
+        .with_name("stats_row");
+
+        let offline = HideableView::new(
+            TextView::new(t!("offline-indicator"))
+                .style(Style::highlight().combine(Effect::Bold))
+                .h_align(HAlign::Center),
+        )
+        .visible(cache::is_offline())
+        .with_name("offline_row");
+
+        let outage = HideableView::new(
+            TextView::new("")
+                .style(Style::highlight().combine(Effect::Bold))
+                .h_align(HAlign::Center)
+                .with_name("outage"),
+        )
+        .visible(false)
+        .with_name("outage_row");
 
-        container.add_child(track_info);
-        container.add_child(progress);
+        let party = HideableView::new(
+            TextView::new(t!("party-mode-indicator"))
+                .style(Style::highlight().combine(Effect::Bold))
+                .h_align(HAlign::Center),
+        )
+        .visible(false)
+        .with_name("party_row");
+
+        let account = HideableView::new(
+            TextView::new("")
+                .style(Style::highlight().combine(Effect::Bold))
+                .h_align(HAlign::Center)
+                .with_name("account"),
+        )
+        .visible(false)
+        .with_name("account_row");
+
+        let audition = HideableView::new(
+            TextView::new("")
+                .style(Style::highlight().combine(Effect::Bold))
+                .h_align(HAlign::Center)
+                .with_name("audition"),
+        )
+        .visible(false)
+        .with_name("audition_row");
+
+        let container = PanelLayout::new(meta)
+            .left(track_num, 3)
+            .right(player_status, 8)
+            .row(progress)
+            .row(list_progress)
+            .row(modes)
+            .row(visualization)
+            .row(stats)
+            .row(offline)
+            .row(outage)
+            .row(account)
+            .row(party)
+            .row(audition)
+            .build();
 
         let mut track_list: SelectView<usize> = SelectView::new();
 
         track_list.set_on_submit(move |_s, item| {
             let i = item.to_owned();
-            tokio::spawn(async move { player::skip(i as u32, true).await });
+            let generation = QUEUE_GENERATION.load(Ordering::Relaxed);
+
+            tokio::spawn(async move {
+                if let Err(error) = player::skip_to(i as u32, generation).await {
+                    debug!(?error);
+                }
+            });
         });
 
+        let cover = HideableView::new(PaddedView::lrtb(0, 1, 0, 0, CoverView::new()))
+            .visible(false)
+            .with_name("cover_row");
+
         let mut layout = LinearLayout::new(Orientation::Vertical).child(
-            Panel::new(container)
-                .title("player")
-                .with_name("player_panel"),
+            Panel::new(
+                LinearLayout::new(Orientation::Horizontal)
+                    .child(cover)
+                    .child(container),
+            )
+            .title(t!("player-panel"))
+            .with_name("player_panel"),
         );
 
-        layout.add_child(Panel::new(
-            HideableView::new(
-                track_list
+        let lyrics = HideableView::new(
+            Panel::new(
+                SelectView::<usize>::new()
+                    .with_name("lyrics")
                     .scrollable()
                     .scroll_y(true)
                     .scroll_x(true)
-                    .with_name("current_track_list"),
+                    .with_name("lyrics_scroll"),
             )
-            .visible(true),
-        ));
+            .title(t!("lyrics")),
+        )
+        .visible(false)
+        .with_name("lyrics_panel");
+
+        layout.add_child(
+            LinearLayout::new(Orientation::Horizontal)
+                .child(Panel::new(
+                    HideableView::new(
+                        track_list
+                            .scrollable()
+                            .scroll_y(true)
+                            .scroll_x(true)
+                            .with_name("current_track_list"),
+                    )
+                    .visible(true),
+                ))
+                .child(lyrics),
+        );
 
         layout
     }
 
     pub fn global_events(&mut self) {
-        self.root.clear_global_callbacks(Event::CtrlChar('c'));
-
-        self.root.set_on_pre_event(Event::CtrlChar('c'), move |s| {
-            let dialog = Dialog::text("Do you want to quit?")
-                .button("Yes", move |s: &mut Cursive| {
-                    s.quit();
-                })
-                .dismiss_button("No");
-
-            s.add_layer(dialog);
-        });
-
-        self.root.add_global_callback('1', move |s| {
-            s.set_screen(0);
-        });
-
-        self.root.add_global_callback('2', move |s| {
-            s.set_screen(1);
-        });
-
-        self.root.add_global_callback('3', move |s| {
-            s.set_screen(2);
-        });
-
-        self.root.add_global_callback(' ', move |_| {
-            block_on(async { player::play_pause().await.expect("") });
-        });
-
-        self.root.add_global_callback('N', move |_| {
-            block_on(async { player::next().await.expect("") });
-        });
-
-        self.root.add_global_callback('P', move |_| {
-            block_on(async { player::previous().await.expect("") });
-        });
-
-        self.root.add_global_callback('l', move |_| {
-            block_on(async { player::jump_forward().await.expect("") });
-        });
-
-        self.root.add_global_callback('h', move |_| {
-            block_on(async { player::jump_backward().await.expect("") });
-        });
+        install_global_events(&mut self.root);
     }
 
     pub async fn my_playlists(&self) -> NamedView<LinearLayout> {
         let mut list_layout = LinearLayout::new(Orientation::Vertical);
 
         let mut user_playlists = SelectView::new().popup();
-        user_playlists.add_item("Select Playlist", 0);
-
-        let my_playlists = player::user_playlists().await;
-        my_playlists.iter().for_each(|p| {
-            user_playlists.add_item(p.title.clone(), p.id);
-        });
+        fill_user_playlists(&mut user_playlists, player::user_playlists().await);
 
         user_playlists.set_on_submit(move |s: &mut Cursive, item: &u32| {
             if item == &0 {
@@ -258,224 +372,636 @@ impl CursiveUI {
                 return;
             }
 
-            let layout = submit_playlist(s, *item).wrap_with(Panel::new);
+            let playlist_id = *item;
 
-            s.call_on_name("user_playlist_layout", |l: &mut LinearLayout| {
-                l.remove_child(1);
-                l.add_child(layout);
-            });
+            submit_playlist(playlist_id, move |s, layout| {
+                let layout = layout
+                    .wrap_with(Panel::new)
+                    .with_name(user_playlist_name(playlist_id));
+
+                s.call_on_name("user_playlist_layout", |l: &mut LinearLayout| {
+                    l.remove_child(1);
+                    l.add_child(layout);
+                });
 
-            s.call_on_name("play_button", |button: &mut Button| {
-                button.enable();
+                s.call_on_name("play_button", |button: &mut Button| {
+                    button.enable();
+                });
             });
         });
 
-        list_layout.add_child(
-            Panel::new(
+        let controls = LinearLayout::horizontal()
+            .child(
                 user_playlists
                     .with_name("user_playlists")
                     .scrollable()
                     .scroll_y(true)
                     .resized(SizeConstraint::Full, SizeConstraint::Free),
             )
-            .title("my playlists"),
-        );
+            .child(Button::new(t!("new-playlist"), new_playlist))
+            .child(Button::new(t!("delete-playlist"), delete_playlist));
 
-        list_layout.with_name("user_playlist_layout")
-    }
+        let mut playlists = LinearLayout::vertical();
+        let personal = player::personal_playlists().await;
 
-    fn search(&mut self) -> LinearLayout {
-        let mut layout = LinearLayout::new(Orientation::Vertical);
+        if !personal.is_empty() {
+            let mut personal_playlists = SelectView::new();
 
-        let on_submit = move |s: &mut Cursive, item: &String| {
-            load_search_results(item, s);
-        };
+            for kind in personal {
+                let mut item = StyledString::styled(
+                    kind.title(),
+                    Style::from(ColorStyle::title_primary()).combine(Effect::Bold),
+                );
+                item.append_plain("  ");
+                item.append_styled(t!("made-for-you"), Effect::Dim);
 
-        let search_type = SelectView::new()
-            .item_str("Albums")
-            .item_str("Artists")
-            .item_str("Tracks")
-            .item_str("Playlists")
-            .on_submit(on_submit)
-            .popup()
-            .with_name("search_type")
-            .wrap_with(Panel::new);
+                personal_playlists.add_item(item, kind);
+            }
 
-        let search_form = EditView::new()
-            .on_submit_mut(move |_, item| {
-                let item = item.to_string();
+            personal_playlists.set_on_submit(|_s: &mut Cursive, kind: &PersonalPlaylist| {
+                let kind = *kind;
 
                 tokio::spawn(async move {
-                    let results = player::search(&item).await;
+                    let playlist = player::personal_playlist(kind).await;
 
                     SINK.get()
                         .unwrap()
                         .send(Box::new(move |s| {
-                            s.set_user_data(results);
+                            let layout = personal_playlist_layout(kind, playlist);
 
-                            if let Some(view) = s.find_name::<SelectView>("search_type") {
-                                if let Some(value) = view.selection() {
-                                    load_search_results(&value, s);
-                                }
-                            }
+                            s.call_on_name("user_playlist_layout", |l: &mut LinearLayout| {
+                                l.remove_child(1);
+                                l.add_child(layout.wrap_with(Panel::new));
+                            });
                         }))
                         .expect("failed to send update");
                 });
-            })
+            });
+
+            playlists.add_child(personal_playlists);
+        }
+
+        playlists.add_child(controls);
+
+        list_layout.add_child(Panel::new(playlists).title(t!("my-playlists-panel")));
+
+        list_layout.with_name("user_playlist_layout")
+    }
+
+    fn search(&mut self) -> LinearLayout {
+        let mut layout = LinearLayout::new(Orientation::Vertical);
+
+        let search_type = SelectView::new()
+            .item(t!("search-albums"), SearchType::Albums)
+            .item(t!("search-artists"), SearchType::Artists)
+            .item(t!("search-tracks"), SearchType::Tracks)
+            .item(t!("search-playlists"), SearchType::Playlists)
+            .on_submit(|s: &mut Cursive, _: &SearchType| run_search(s))
+            .popup()
+            .with_name("search_type")
+            .wrap_with(Panel::new);
+
+        let search_form = EditView::new()
+            .on_submit(|s, _| run_search(s))
+            .with_name("search_query")
             .wrap_with(Panel::new);
 
         let search_results: SelectView<String> = SelectView::new();
 
-        layout.add_child(search_form.title("search"));
+        layout.add_child(search_form.title(t!("search-panel")));
         layout.add_child(search_type);
 
         layout.add_child(
             Panel::new(
                 search_results
                     .with_name("search_results")
+                    .wrap_with(OnEventView::new)
+                    .on_event('a', enqueue_search_result)
+                    .on_event('f', search_filter)
+                    .on_event('F', favorite_search_result)
                     .scrollable()
                     .scroll_y(true)
                     .scroll_x(true)
                     .resized(SizeConstraint::Free, SizeConstraint::Full),
             )
-            .title("results"),
+            .title(t!("results-panel")),
         );
+        layout.add_child(TextView::new(search_filter_label()).with_name("search_filter"));
+        layout.add_child(TextView::new(t!("search-keys")));
 
         layout
     }
 
-    fn results_list(name: &str) -> ResultsPanel {
-        let panel: ResultsPanel = SelectView::new()
-            .with_name(name)
-            .scrollable()
-            .scroll_y(true)
-            .scroll_x(true);
+    fn remote_clients(&self) -> LinearLayout {
+        let mut layout = LinearLayout::new(Orientation::Vertical);
 
-        panel
+        layout.add_child(
+            Panel::new(
+                TextView::new(t!("no-remote-clients"))
+                    .with_name("remote_clients")
+                    .scrollable()
+                    .scroll_y(true)
+                    .resized(SizeConstraint::Full, SizeConstraint::Full),
+            )
+            .title(t!("remote-clients-panel")),
+        );
+
+        layout
     }
 
-    fn enter_url<F>(callback: F) -> NamedView<OnEventView<ResizedView<Panel<EditView>>>>
-    where
-        F: Fn(&mut Cursive, &str) + 'static,
-    {
-        let mut input = EditView::new();
+    async fn favorites(&self) -> LinearLayout {
+        let mut layout = LinearLayout::new(Orientation::Vertical);
 
-        input.set_on_submit(callback);
+        *FAVORITES.lock().unwrap() = favorites::cached().await;
+
+        let favorites_type = SelectView::new()
+            .item(t!("search-albums"), SearchType::Albums)
+            .item(t!("search-tracks"), SearchType::Tracks)
+            .item(t!("search-artists"), SearchType::Artists)
+            .on_submit(|s: &mut Cursive, _: &SearchType| load_favorites(s))
+            .popup()
+            .with_name("favorites_type");
 
-        let panel = OnEventView::new(Panel::new(input).title("Enter URL").full_width());
+        let sort = SelectView::new()
+            .item(t!("sort-recently-added"), FavoriteSort::RecentlyAdded)
+            .item(t!("sort-artist"), FavoriteSort::Artist)
+            .item(t!("sort-release-year"), FavoriteSort::ReleaseYear)
+            .on_submit(|s: &mut Cursive, _: &FavoriteSort| load_favorites(s))
+            .popup()
+            .with_name("favorites_sort");
 
-        panel.with_name("event_url")
+        let filters = LinearLayout::horizontal()
+            .child(favorites_type)
+            .child(TextView::new("  "))
+            .child(sort)
+            .child(TextView::new("  "))
+            .child(
+                Checkbox::new()
+                    .on_change(|s, _| load_favorites(s))
+                    .with_name("favorites_hires"),
+            )
+            .child(TextView::new(format!(" {}  ", t!("filter-hires"))))
+            .child(
+                Checkbox::new()
+                    .on_change(|s, _| load_favorites(s))
+                    .with_name("favorites_unplayed"),
+            )
+            .child(TextView::new(format!(" {}", t!("filter-unplayed"))));
+
+        let mut favorites_list: SelectView<String> = SelectView::new();
+        favorites_list.set_on_submit(submit_favorite);
+
+        layout.add_child(Panel::new(filters));
+        layout.add_child(
+            Panel::new(
+                favorites_list
+                    .with_name("favorites_list")
+                    .wrap_with(OnEventView::new)
+                    .on_event('a', |s| {
+                        if let Some((search_type, item)) = selected_favorite(s)
+                            .filter(|(search_type, item)| favorite_available(*search_type, item))
+                        {
+                            enqueue(search_type, item);
+                        }
+                    })
+                    .on_event('r', |s| {
+                        if let Some(item) =
+                            selected_favorite(s).and_then(|(t, item)| favorite_item(t, &item))
+                        {
+                            set_favorite(item, false);
+                        }
+                    })
+                    .scrollable()
+                    .scroll_y(true)
+                    .scroll_x(true)
+                    .resized(SizeConstraint::Full, SizeConstraint::Full),
+            )
+            .title(t!("favorites-panel")),
+        );
+        layout.add_child(TextView::new(t!("favorites-keys")));
+
+        layout
     }
 
-    pub fn menubar(&mut self) {
-        self.root.set_autohide_menu(false);
+    async fn browse(&self) -> LinearLayout {
+        let mut layout = LinearLayout::new(Orientation::Vertical);
+        let mut tabs = LinearLayout::horizontal();
 
-        let open = Arc::new(move |s: &mut Cursive| {
-            let mut panel = CursiveUI::enter_url(move |s, url| {
-                let u = url.to_string();
-                tokio::spawn(async move { player::play_uri(&u).await });
-                s.pop_layer();
-                ENTER_URL_OPEN.store(false, Ordering::Relaxed);
+        let genres = player::genres().await;
+
+        if !genres.is_empty() {
+            let mut genre = SelectView::new().popup();
+            genre.add_item(t!("all-genres"), None);
+
+            for g in genres {
+                genre.add_item(g.name, Some(g.id));
+            }
+
+            genre.set_on_submit(|_, genre: &Option<u32>| {
+                let category = BROWSE.lock().unwrap().category;
+                load_browse_page(category, *genre, 0);
             });
 
-            panel
-                .get_mut()
-                .set_on_pre_event(Event::Key(Key::Esc), move |s| {
-                    s.pop_layer();
-                    ENTER_URL_OPEN.store(false, Ordering::Relaxed);
-                });
+            tabs.add_child(genre.with_name("browse_genre"));
+            tabs.add_child(TextView::new("  "));
+        }
 
-            let bg = Layer::with_color(
-                PaddedView::lrtb(
-                    2,
-                    2,
-                    2,
-                    2,
-                    panel.resized(SizeConstraint::Full, SizeConstraint::Fixed(3)),
-                )
-                .full_width(),
-                ColorStyle::highlight_inactive(),
-            )
-            .full_width();
+        for category in player::browse_categories().await {
+            tabs.add_child(Button::new(category.title(), move |_| {
+                let genre = BROWSE.lock().unwrap().genre;
+                load_browse_page(category, genre, 0);
+            }));
+        }
 
-            s.screen_mut().add_layer_at(Position::parent((0, 3)), bg);
+        let mut album_list: SelectView<String> = SelectView::new();
+        album_list.set_on_submit(move |s: &mut Cursive, item: &String| {
+            if item != UNSTREAMABLE {
+                let item = item.clone();
+                tokio::spawn(async move { player::play_album(&item).await });
 
-            ENTER_URL_OPEN.store(true, Ordering::Relaxed);
+                s.set_screen(0);
+            }
         });
 
-        let o = open.clone();
-        self.root
-            .menubar()
-            .add_leaf("Now Playing", move |s| {
-                if ENTER_URL_OPEN.load(Ordering::Relaxed) {
-                    s.pop_layer();
-                    ENTER_URL_OPEN.store(false, Ordering::Relaxed);
-                }
+        let pager = LinearLayout::horizontal()
+            .child(Button::new("<", |_| {
+                let page = BROWSE.lock().unwrap().clone();
 
-                s.set_screen(0);
-            })
-            .add_delimiter()
-            .add_leaf("My Playlists", move |s| {
-                if ENTER_URL_OPEN.load(Ordering::Relaxed) {
-                    s.pop_layer();
-                    ENTER_URL_OPEN.store(false, Ordering::Relaxed);
+                if page.offset > 0 {
+                    load_browse_page(
+                        page.category,
+                        page.genre,
+                        page.offset.saturating_sub(player::BROWSE_PAGE_SIZE),
+                    );
                 }
+            }))
+            .child(
+                TextView::new("")
+                    .h_align(HAlign::Center)
+                    .with_name("browse_page")
+                    .full_width(),
+            )
+            .child(Button::new(">", |_| {
+                let page = BROWSE.lock().unwrap().clone();
+                let next = page.offset + player::BROWSE_PAGE_SIZE;
 
-                s.set_screen(1);
-            })
-            .add_delimiter()
-            .add_leaf("Search", move |s| {
-                if ENTER_URL_OPEN.load(Ordering::Relaxed) {
-                    s.pop_layer();
-                    ENTER_URL_OPEN.store(false, Ordering::Relaxed);
+                if next < page.total {
+                    load_browse_page(page.category, page.genre, next);
                 }
+            }));
 
-                s.set_screen(2);
-            })
-            .add_delimiter()
-            .add_leaf("Enter URL", move |s| {
-                if !ENTER_URL_OPEN.load(Ordering::Relaxed) {
-                    o(s);
-                }
-            });
+        layout.add_child(Panel::new(tabs));
+        layout.add_child(
+            Panel::new(
+                album_list
+                    .with_name("browse_albums")
+                    .scrollable()
+                    .scroll_y(true)
+                    .scroll_x(true)
+                    .resized(SizeConstraint::Full, SizeConstraint::Full),
+            )
+            .title(BrowseCategory::default().title())
+            .with_name("browse_panel"),
+        );
+        layout.add_child(pager);
+
+        layout
+    }
+
+    fn release_calendar(&self) -> LinearLayout {
+        let mut layout = LinearLayout::new(Orientation::Vertical);
 
-        let o = open.clone();
-        self.root.add_global_callback('4', move |s| {
-            o(s);
+        let controls = LinearLayout::horizontal()
+            .child(TextView::new(t!("calendar-hint")).full_width())
+            .child(Button::new(t!("calendar-refresh"), |_| {
+                refresh_release_calendar()
+            }));
+
+        let mut album_list: SelectView<String> = SelectView::new();
+        album_list.set_on_submit(move |s: &mut Cursive, item: &String| {
+            let album = CALENDAR
+                .lock()
+                .unwrap()
+                .iter()
+                .find(|a| &a.id == item)
+                .cloned();
+
+            match album {
+                Some(album) if album.available => {
+                    tokio::spawn(async move { player::play_album(&album.id).await });
+                    s.set_screen(0);
+                }
+                Some(album) => watch_release(s, album),
+                None => {}
+            }
         });
 
-        self.root.add_global_callback('1', move |s| {
-            if ENTER_URL_OPEN.load(Ordering::Relaxed) {
-                s.pop_layer();
-                ENTER_URL_OPEN.store(false, Ordering::Relaxed);
+        layout.add_child(Panel::new(controls));
+        layout.add_child(
+            Panel::new(
+                album_list
+                    .with_name("release_calendar")
+                    .scrollable()
+                    .scroll_y(true)
+                    .scroll_x(true)
+                    .resized(SizeConstraint::Full, SizeConstraint::Full),
+            )
+            .title(t!("release-calendar-panel")),
+        );
+
+        layout
+    }
+
+    fn history(&self) -> LinearLayout {
+        let mut layout = LinearLayout::new(Orientation::Vertical);
+
+        let play = |s: &mut Cursive, item: &i32| {
+            if *item < 0 {
+                return;
             }
 
+            let item = *item;
+            tokio::spawn(async move { player::play_track(item).await });
+
             s.set_screen(0);
-        });
+        };
 
-        self.root.add_global_callback('2', move |s| {
-            if ENTER_URL_OPEN.load(Ordering::Relaxed) {
-                s.pop_layer();
-                ENTER_URL_OPEN.store(false, Ordering::Relaxed);
-            }
+        let mut recent: SelectView<i32> = SelectView::new();
+        recent.set_on_submit(play);
 
-            s.set_screen(1);
-        });
+        let mut most_played: SelectView<i32> = SelectView::new();
+        most_played.set_on_submit(play);
 
-        self.root.add_global_callback('3', move |s| {
-            if ENTER_URL_OPEN.load(Ordering::Relaxed) {
-                s.pop_layer();
-                ENTER_URL_OPEN.store(false, Ordering::Relaxed);
-            }
+        layout.add_child(
+            Panel::new(
+                recent
+                    .with_name("history_recent")
+                    .scrollable()
+                    .scroll_y(true)
+                    .scroll_x(true)
+                    .resized(SizeConstraint::Full, SizeConstraint::Full),
+            )
+            .title(t!("history-recent-panel")),
+        );
+        layout.add_child(
+            Panel::new(
+                most_played
+                    .with_name("history_most_played")
+                    .scrollable()
+                    .scroll_y(true)
+                    .scroll_x(true)
+                    .resized(SizeConstraint::Full, SizeConstraint::Full),
+            )
+            .title(t!("history-most-played-panel")),
+        );
 
-            s.set_screen(2);
-        });
+        layout
     }
 
-    pub async fn run(&mut self) {
-        let player = self.player();
-        let search = self.search();
-        let my_playlists = self.my_playlists().await;
+    fn discover(&self) -> LinearLayout {
+        let mut tabs = LinearLayout::horizontal();
+
+        for category in DISCOVER_SECTIONS {
+            tabs.add_child(Button::new(category.title(), move |_| {
+                load_discover_albums(category);
+            }));
+        }
+
+        let mut albums: SelectView<String> = SelectView::new();
+        albums.set_on_submit(move |s: &mut Cursive, item: &String| {
+            if item != UNSTREAMABLE {
+                let item = item.clone();
+                tokio::spawn(async move { player::play_album(&item).await });
+
+                s.set_screen(0);
+            }
+        });
+
+        let mut playlists: SelectView<u32> = SelectView::new();
+        playlists.set_on_submit(move |_s: &mut Cursive, item: &u32| {
+            submit_playlist(*item, |s, layout| {
+                let event_panel =
+                    OnEventView::new(layout).on_event(Event::Key(Key::Esc), move |s| {
+                        s.screen_mut().pop_layer();
+                    });
+
+                s.screen_mut().add_layer(Panel::new(event_panel));
+            });
+        });
+
+        let albums = LinearLayout::vertical().child(Panel::new(tabs)).child(
+            Panel::new(
+                albums
+                    .with_name("discover_albums")
+                    .scrollable()
+                    .scroll_y(true)
+                    .scroll_x(true)
+                    .resized(SizeConstraint::Full, SizeConstraint::Full),
+            )
+            .title(DISCOVER_SECTIONS[0].title())
+            .with_name("discover_albums_panel"),
+        );
+
+        LinearLayout::horizontal().child(albums.full_width()).child(
+            Panel::new(
+                playlists
+                    .with_name("discover_playlists")
+                    .scrollable()
+                    .scroll_y(true)
+                    .scroll_x(true)
+                    .resized(SizeConstraint::Full, SizeConstraint::Full),
+            )
+            .title(t!("featured-playlists"))
+            .full_width(),
+        )
+    }
+
+    fn cached_tracks(&self) -> LinearLayout {
+        let mut layout = LinearLayout::new(Orientation::Vertical);
+
+        let controls = LinearLayout::horizontal()
+            .child(
+                Checkbox::new()
+                    .on_change(|_, enabled| {
+                        tokio::spawn(async move { player::set_cached_only(enabled).await });
+                    })
+                    .with_name("cached_only"),
+            )
+            .child(TextView::new(format!(" {}", t!("filter-cached-only"))).full_width())
+            .child(Button::new(t!("play-all"), |s| {
+                tokio::spawn(async { player::play_cached().await });
+                s.set_screen(0);
+            }));
+
+        let mut track_list: SelectView<i32> = SelectView::new();
+        track_list.set_on_submit(move |s: &mut Cursive, item: &i32| {
+            let item = *item;
+            tokio::spawn(async move { player::play_track(item).await });
+
+            s.set_screen(0);
+        });
+
+        layout.add_child(Panel::new(controls));
+        layout.add_child(
+            Panel::new(
+                track_list
+                    .with_name("cached_tracks")
+                    .scrollable()
+                    .scroll_y(true)
+                    .scroll_x(true)
+                    .resized(SizeConstraint::Full, SizeConstraint::Full),
+            )
+            .title(t!("cached-tracks-panel")),
+        );
+
+        layout
+    }
+
+    fn event_log(&self) -> LinearLayout {
+        let mut layout = LinearLayout::new(Orientation::Vertical);
+
+        let controls = LinearLayout::horizontal()
+            .child(TextView::new(format!("{} ", t!("event-filter"))))
+            .child(
+                EditView::new()
+                    .on_edit(|s, _, _| load_event_log(s))
+                    .with_name("event_filter")
+                    .full_width(),
+            )
+            .child(Button::new(t!("event-dump"), dump_event_log));
+
+        layout.add_child(Panel::new(controls));
+        layout.add_child(PaddedView::lrtb(
+            1,
+            1,
+            0,
+            0,
+            TextView::new("").with_name("gapless_stats"),
+        ));
+        layout.add_child(PaddedView::lrtb(
+            1,
+            1,
+            0,
+            0,
+            TextView::new("").no_wrap().with_name("api_latency"),
+        ));
+        layout.add_child(
+            Panel::new(
+                TextView::new("")
+                    .no_wrap()
+                    .with_name("event_log")
+                    .scrollable()
+                    .scroll_x(true)
+                    .scroll_y(true)
+                    .scroll_strategy(ScrollStrategy::StickToBottom)
+                    .resized(SizeConstraint::Full, SizeConstraint::Full),
+            )
+            .title(t!("event-log-panel")),
+        );
+
+        layout
+    }
+
+    fn results_list(name: &str) -> ResultsPanel {
+        let panel: ResultsPanel = SelectView::new()
+            .with_name(name)
+            .scrollable()
+            .scroll_y(true)
+            .scroll_x(true);
+
+        panel
+    }
+
+    fn enter_url<F>(callback: F) -> NamedView<OnEventView<ResizedView<Panel<EditView>>>>
+    where
+        F: Fn(&mut Cursive, &str) + 'static,
+    {
+        let mut input = EditView::new();
+
+        input.set_on_submit(callback);
+
+        let panel = OnEventView::new(Panel::new(input).title(t!("enter-url")).full_width());
+
+        panel.with_name("event_url")
+    }
+
+    pub fn menubar(&mut self) {
+        install_menubar(&mut self.root);
+    }
+
+    /// The only screen in simple mode: what's playing and the playlists that may be started.
+    async fn simple(&self) -> LinearLayout {
+        let mut playlists = SelectView::new();
+
+        for id in simple::playlists() {
+            let title = match player::playlist(id).await {
+                Some(playlist) => playlist.title,
+                None => id.to_string(),
+            };
+
+            playlists.add_item(title, id);
+        }
+
+        playlists.set_on_submit(|_s: &mut Cursive, id: &i64| {
+            let id = *id;
+
+            tokio::spawn(async move {
+                if let Err(error) = player::play_playlist(id).await {
+                    debug!(?error);
+                }
+            });
+        });
+
+        let now_playing = PaddedView::lrtb(
+            2,
+            2,
+            2,
+            2,
+            LinearLayout::new(Orientation::Vertical)
+                .child(
+                    TextView::new("")
+                        .style(Style::highlight().combine(Effect::Bold))
+                        .h_align(HAlign::Center)
+                        .with_name("simple_title"),
+                )
+                .child(
+                    TextView::new("")
+                        .h_align(HAlign::Center)
+                        .with_name("simple_artist"),
+                )
+                .child(
+                    TextView::new(get_state_icon(snapshot::status()))
+                        .h_align(HAlign::Center)
+                        .with_name("simple_status"),
+                ),
+        );
+
+        LinearLayout::new(Orientation::Vertical)
+            .child(Panel::new(now_playing).title(t!("now-playing")))
+            .child(Panel::new(playlists.scrollable()).title(t!("simple-mode-playlists")))
+            .child(
+                TextView::new(t!("simple-mode-keys"))
+                    .style(Style::from(Effect::Dim))
+                    .h_align(HAlign::Center),
+            )
+    }
+
+    pub async fn run(&mut self) {
+        snapshot::init().await;
+
+        let unseen = changelog::take_unseen().await;
+        let player = self.player();
+        let search = self.search();
+        let my_playlists = self.my_playlists().await;
+        let remote_clients = self.remote_clients();
+        let favorites = self.favorites().await;
+        let event_log = self.event_log();
+        let browse = self.browse().await;
+        let cached_tracks = self.cached_tracks();
+        let release_calendar = self.release_calendar();
+        let history = self.history();
+        let discover = self.discover();
+        let simple_mode = simple::is_enabled();
 
         self.root
             .screen_mut()
@@ -509,186 +1035,2780 @@ impl CursiveUI {
                 search.resized(SizeConstraint::Full, SizeConstraint::Free),
             ));
 
-        self.root.set_screen(0);
+        self.root.add_active_screen();
+        self.root
+            .screen_mut()
+            .add_fullscreen_layer(PaddedView::lrtb(
+                0,
+                0,
+                1,
+                0,
+                remote_clients.resized(SizeConstraint::Full, SizeConstraint::Free),
+            ));
+
+        self.root.add_active_screen();
+        self.root
+            .screen_mut()
+            .add_fullscreen_layer(PaddedView::lrtb(
+                0,
+                0,
+                1,
+                0,
+                favorites.resized(SizeConstraint::Full, SizeConstraint::Free),
+            ));
+
+        self.root.add_active_screen();
+        self.root
+            .screen_mut()
+            .add_fullscreen_layer(PaddedView::lrtb(
+                0,
+                0,
+                1,
+                0,
+                event_log.resized(SizeConstraint::Full, SizeConstraint::Free),
+            ));
+
+        self.root.add_active_screen();
+        self.root
+            .screen_mut()
+            .add_fullscreen_layer(PaddedView::lrtb(
+                0,
+                0,
+                1,
+                0,
+                browse.resized(SizeConstraint::Full, SizeConstraint::Free),
+            ));
+
+        self.root.add_active_screen();
+        self.root
+            .screen_mut()
+            .add_fullscreen_layer(PaddedView::lrtb(
+                0,
+                0,
+                1,
+                0,
+                cached_tracks.resized(SizeConstraint::Full, SizeConstraint::Free),
+            ));
+
+        self.root.add_active_screen();
+        self.root
+            .screen_mut()
+            .add_fullscreen_layer(PaddedView::lrtb(
+                0,
+                0,
+                1,
+                0,
+                release_calendar.resized(SizeConstraint::Full, SizeConstraint::Free),
+            ));
+
+        self.root.add_active_screen();
+        self.root
+            .screen_mut()
+            .add_fullscreen_layer(PaddedView::lrtb(
+                0,
+                0,
+                1,
+                0,
+                history.resized(SizeConstraint::Full, SizeConstraint::Free),
+            ));
+
+        self.root.add_active_screen();
+        self.root
+            .screen_mut()
+            .add_fullscreen_layer(PaddedView::lrtb(
+                0,
+                0,
+                1,
+                0,
+                discover.resized(SizeConstraint::Full, SizeConstraint::Free),
+            ));
+
+        if simple_mode {
+            let simple = self.simple().await;
+
+            self.root.add_active_screen();
+            self.root
+                .screen_mut()
+                .add_fullscreen_layer(PaddedView::lrtb(
+                    0,
+                    0,
+                    1,
+                    0,
+                    simple.resized(SizeConstraint::Full, SizeConstraint::Free),
+                ));
+        }
+
+        self.root
+            .set_screen(if simple_mode { SIMPLE_SCREEN } else { 0 });
+        let list = snapshot::track_list();
+        fill_track_list(&mut self.root, &list);
+
+        if let Some(track) = list.current_track() {
+            set_current_track(&mut self.root, track, list.list_type());
+        }
+        load_favorites(&mut self.root);
+        refresh_favorites();
+        tail_event_log();
+        load_browse_page(BrowseCategory::default(), None, 0);
+
+        if !unseen.is_empty() {
+            show_whats_new(&mut self.root, &unseen);
+        }
+
+        if simple_mode {
+            install_simple_events(&mut self.root);
+        } else {
+            self.menubar();
+            self.global_events();
+        }
+
+        // Covers drawn with a graphics protocol go out after each frame.
+        let mut runner = self.root.runner();
+        runner.refresh();
+        cover::present(&mut runner);
+
+        while runner.is_running() {
+            if runner.step() {
+                cover::present(&mut runner);
+            }
+        }
+
+        cover::clear();
+    }
+
+    pub async fn sink(&self) -> &CbSink {
+        self.root.cb_sink()
+    }
+}
+
+impl Default for CursiveUI {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// The color scheme used by the TUI.
+#[derive(Default, Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, ValueEnum)]
+#[serde(rename_all = "lowercase")]
+pub enum Theme {
+    /// Uses the terminal's colors with cyan highlights.
+    #[default]
+    Terminal,
+    /// No colors, highlights are shown in reverse video.
+    Monochrome,
+}
+
+impl Display for Theme {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Theme::Terminal => f.write_str("terminal"),
+            Theme::Monochrome => f.write_str("monochrome"),
+        }
+    }
+}
+
+/// Keys for the full TUI.
+fn install_global_events(siv: &mut Cursive) {
+    siv.clear_global_callbacks(Event::CtrlChar('c'));
+
+    siv.set_on_pre_event(Event::CtrlChar('c'), move |s| {
+        if party::is_enabled() {
+            s.add_layer(Dialog::text(t!("party-mode-locked")).dismiss_button(t!("close")));
+            return;
+        }
+
+        let dialog = Dialog::text(t!("quit-prompt"))
+            .button(t!("yes"), move |s: &mut Cursive| {
+                s.quit();
+            })
+            .dismiss_button(t!("no"));
+
+        s.add_layer(dialog);
+    });
+
+    siv.clear_global_callbacks(Event::WindowResize);
+    siv.set_on_pre_event(Event::WindowResize, on_resize);
+    layout::set_screen_width(siv.screen_size().x);
+
+    siv.add_global_callback('1', move |s| {
+        s.set_screen(0);
+    });
+
+    siv.add_global_callback('2', move |s| {
+        s.set_screen(1);
+    });
+
+    siv.add_global_callback('3', move |s| {
+        s.set_screen(2);
+    });
+
+    siv.add_global_callback(' ', move |_| {
+        tokio::spawn(async {
+            if let Err(error) = player::play_pause().await {
+                debug!(?error);
+            }
+        });
+    });
+
+    siv.add_global_callback('N', move |_| {
+        tokio::spawn(async {
+            if let Err(error) = player::next().await {
+                debug!(?error);
+            }
+        });
+    });
+
+    siv.add_global_callback('P', move |_| {
+        tokio::spawn(async {
+            if let Err(error) = player::previous().await {
+                debug!(?error);
+            }
+        });
+    });
+
+    siv.add_global_callback('S', move |_| {
+        tokio::spawn(async {
+            if let Err(error) = player::start_over().await {
+                debug!(?error);
+            }
+        });
+    });
+
+    siv.add_global_callback('R', move |_| {
+        tokio::spawn(async {
+            if let Err(error) = player::restart_list().await {
+                debug!(?error);
+            }
+        });
+    });
+
+    siv.add_global_callback('l', move |_| {
+        tokio::spawn(async {
+            if let Err(error) = player::jump_forward().await {
+                debug!(?error);
+            }
+        });
+    });
+
+    siv.add_global_callback('h', move |_| {
+        tokio::spawn(async {
+            if let Err(error) = player::jump_backward().await {
+                debug!(?error);
+            }
+        });
+    });
+
+    siv.add_global_callback('+', move |_| change_volume(player::VOLUME_STEP));
+    siv.add_global_callback('=', move |_| change_volume(player::VOLUME_STEP));
+    siv.add_global_callback('-', move |_| change_volume(-player::VOLUME_STEP));
+
+    siv.add_global_callback('s', move |_| toggle_shuffle());
+
+    siv.add_global_callback('Q', move |s| {
+        switch_quality(s);
+    });
+
+    siv.add_global_callback('q', pick_next_quality);
+
+    siv.add_global_callback('i', move |_| {
+        show_description();
+    });
+
+    siv.add_global_callback('I', move |s| {
+        let enabled = !SHOW_STATS.load(Ordering::Relaxed);
+        SHOW_STATS.store(enabled, Ordering::Relaxed);
+
+        // Filled right away, the download rate follows with the next stats.
+        s.call_on_name("stats", |view: &mut TextView| {
+            view.set_content(stats_line(&stats::stats()));
+        });
+        s.call_on_name(
+            "stats_row",
+            |view: &mut HideableView<NamedView<TextView>>| {
+                view.set_visible(enabled);
+            },
+        );
+    });
+
+    siv.add_global_callback('O', move |_| {
+        tokio::spawn(async { output_profiles().await });
+    });
+
+    siv.add_global_callback('p', track_playlists);
+    siv.add_global_callback('L', party_mode);
+    siv.add_global_callback('/', library_search);
+
+    siv.add_global_callback('k', move |_| audition_decide(true));
+    siv.add_global_callback('x', move |_| audition_decide(false));
+
+    siv.add_global_callback('v', move |s| {
+        let enabled = player::toggle_visualization();
+        SHOW_VISUALIZATION.store(enabled, Ordering::Relaxed);
+
+        s.call_on_name(
+            "visualization_row",
+            |view: &mut HideableView<NamedView<TextView>>| {
+                view.set_visible(enabled);
+            },
+        );
+    });
+}
+
+/// The menu bar and the keys that go with it.
+fn install_menubar(siv: &mut Cursive) {
+    siv.set_autohide_menu(false);
+
+    let open = Arc::new(move |s: &mut Cursive| {
+        let mut panel = CursiveUI::enter_url(move |s, url| {
+            let u = url.to_string();
+            tokio::spawn(async move { player::play_uri(&u).await });
+            s.pop_layer();
+            ENTER_URL_OPEN.store(false, Ordering::Relaxed);
+        });
+
+        panel
+            .get_mut()
+            .set_on_pre_event(Event::Key(Key::Esc), move |s| {
+                s.pop_layer();
+                ENTER_URL_OPEN.store(false, Ordering::Relaxed);
+            });
+
+        let bg = Layer::with_color(
+            PaddedView::lrtb(
+                2,
+                2,
+                2,
+                2,
+                panel.resized(SizeConstraint::Full, SizeConstraint::Fixed(3)),
+            )
+            .full_width(),
+            ColorStyle::highlight_inactive(),
+        )
+        .full_width();
+
+        s.screen_mut().add_layer_at(Position::parent((0, 3)), bg);
+
+        ENTER_URL_OPEN.store(true, Ordering::Relaxed);
+    });
+
+    let o = open.clone();
+    siv.menubar()
+        .add_leaf(t!("now-playing"), move |s| {
+            if ENTER_URL_OPEN.load(Ordering::Relaxed) {
+                s.pop_layer();
+                ENTER_URL_OPEN.store(false, Ordering::Relaxed);
+            }
+
+            s.set_screen(0);
+        })
+        .add_delimiter()
+        .add_leaf(t!("my-playlists"), move |s| {
+            if ENTER_URL_OPEN.load(Ordering::Relaxed) {
+                s.pop_layer();
+                ENTER_URL_OPEN.store(false, Ordering::Relaxed);
+            }
+
+            s.set_screen(1);
+        })
+        .add_delimiter()
+        .add_leaf(t!("search"), move |s| {
+            if ENTER_URL_OPEN.load(Ordering::Relaxed) {
+                s.pop_layer();
+                ENTER_URL_OPEN.store(false, Ordering::Relaxed);
+            }
+
+            s.set_screen(2);
+        })
+        .add_delimiter()
+        .add_leaf(t!("enter-url"), move |s| {
+            if !ENTER_URL_OPEN.load(Ordering::Relaxed) {
+                o(s);
+            }
+        })
+        .add_delimiter()
+        .add_leaf(t!("favorites"), move |s| {
+            if ENTER_URL_OPEN.load(Ordering::Relaxed) {
+                s.pop_layer();
+                ENTER_URL_OPEN.store(false, Ordering::Relaxed);
+            }
+
+            show_favorites(s);
+        })
+        .add_delimiter()
+        .add_leaf(t!("remote-clients"), move |s| {
+            if ENTER_URL_OPEN.load(Ordering::Relaxed) {
+                s.pop_layer();
+                ENTER_URL_OPEN.store(false, Ordering::Relaxed);
+            }
+
+            s.set_screen(3);
+        })
+        .add_delimiter()
+        .add_leaf(t!("browse"), move |s| {
+            if ENTER_URL_OPEN.load(Ordering::Relaxed) {
+                s.pop_layer();
+                ENTER_URL_OPEN.store(false, Ordering::Relaxed);
+            }
+
+            s.set_screen(6);
+        })
+        .add_delimiter()
+        .add_leaf(t!("discover"), move |s| {
+            if ENTER_URL_OPEN.load(Ordering::Relaxed) {
+                s.pop_layer();
+                ENTER_URL_OPEN.store(false, Ordering::Relaxed);
+            }
+
+            show_discover(s);
+        })
+        .add_delimiter()
+        .add_leaf(t!("cached-tracks"), move |s| {
+            if ENTER_URL_OPEN.load(Ordering::Relaxed) {
+                s.pop_layer();
+                ENTER_URL_OPEN.store(false, Ordering::Relaxed);
+            }
+
+            show_cached_tracks(s);
+        })
+        .add_delimiter()
+        .add_leaf(t!("release-calendar"), move |s| {
+            if ENTER_URL_OPEN.load(Ordering::Relaxed) {
+                s.pop_layer();
+                ENTER_URL_OPEN.store(false, Ordering::Relaxed);
+            }
+
+            show_release_calendar(s);
+        })
+        .add_delimiter()
+        .add_leaf(t!("history"), move |s| {
+            if ENTER_URL_OPEN.load(Ordering::Relaxed) {
+                s.pop_layer();
+                ENTER_URL_OPEN.store(false, Ordering::Relaxed);
+            }
+
+            show_history(s);
+        })
+        .add_leaf(t!("saved-queues"), move |s| {
+            if ENTER_URL_OPEN.load(Ordering::Relaxed) {
+                s.pop_layer();
+                ENTER_URL_OPEN.store(false, Ordering::Relaxed);
+            }
+
+            tokio::spawn(async { saved_queues().await });
+        })
+        .add_delimiter()
+        .add_leaf(t!("output-profiles"), move |s| {
+            if ENTER_URL_OPEN.load(Ordering::Relaxed) {
+                s.pop_layer();
+                ENTER_URL_OPEN.store(false, Ordering::Relaxed);
+            }
+
+            tokio::spawn(async { output_profiles().await });
+        })
+        .add_leaf(t!("output-devices"), move |s| {
+            if ENTER_URL_OPEN.load(Ordering::Relaxed) {
+                s.pop_layer();
+                ENTER_URL_OPEN.store(false, Ordering::Relaxed);
+            }
+
+            tokio::spawn(async { output_devices().await });
+        })
+        .add_delimiter()
+        .add_leaf(t!("reload-config"), move |s| {
+            if ENTER_URL_OPEN.load(Ordering::Relaxed) {
+                s.pop_layer();
+                ENTER_URL_OPEN.store(false, Ordering::Relaxed);
+            }
+
+            tokio::spawn(async { reload_config().await });
+        })
+        .add_delimiter()
+        .add_leaf(t!("party-mode"), move |s| {
+            if ENTER_URL_OPEN.load(Ordering::Relaxed) {
+                s.pop_layer();
+                ENTER_URL_OPEN.store(false, Ordering::Relaxed);
+            }
+
+            party_mode(s);
+        });
+
+    let o = open.clone();
+    siv.add_global_callback('4', move |s| {
+        o(s);
+    });
+
+    siv.add_global_callback('1', move |s| {
+        if ENTER_URL_OPEN.load(Ordering::Relaxed) {
+            s.pop_layer();
+            ENTER_URL_OPEN.store(false, Ordering::Relaxed);
+        }
+
+        s.set_screen(0);
+    });
+
+    siv.add_global_callback('2', move |s| {
+        if ENTER_URL_OPEN.load(Ordering::Relaxed) {
+            s.pop_layer();
+            ENTER_URL_OPEN.store(false, Ordering::Relaxed);
+        }
+
+        s.set_screen(1);
+    });
+
+    siv.add_global_callback('3', move |s| {
+        if ENTER_URL_OPEN.load(Ordering::Relaxed) {
+            s.pop_layer();
+            ENTER_URL_OPEN.store(false, Ordering::Relaxed);
+        }
+
+        s.set_screen(2);
+    });
+
+    siv.add_global_callback('6', move |s| {
+        if ENTER_URL_OPEN.load(Ordering::Relaxed) {
+            s.pop_layer();
+            ENTER_URL_OPEN.store(false, Ordering::Relaxed);
+        }
+
+        s.set_screen(3);
+    });
+
+    siv.add_global_callback('7', move |s| {
+        if ENTER_URL_OPEN.load(Ordering::Relaxed) {
+            s.pop_layer();
+            ENTER_URL_OPEN.store(false, Ordering::Relaxed);
+        }
+
+        s.set_screen(6);
+    });
+
+    siv.add_global_callback('V', move |s| {
+        if ENTER_URL_OPEN.load(Ordering::Relaxed) {
+            s.pop_layer();
+            ENTER_URL_OPEN.store(false, Ordering::Relaxed);
+        }
+
+        show_discover(s);
+    });
+
+    // The event log is meant for debugging and is not listed in the menu.
+    siv.add_global_callback('D', move |s| {
+        if ENTER_URL_OPEN.load(Ordering::Relaxed) {
+            s.pop_layer();
+            ENTER_URL_OPEN.store(false, Ordering::Relaxed);
+        }
+
+        s.set_screen(EVENT_LOG_SCREEN);
+        load_event_log(s);
+    });
+
+    siv.add_global_callback('5', move |s| {
+        if ENTER_URL_OPEN.load(Ordering::Relaxed) {
+            s.pop_layer();
+            ENTER_URL_OPEN.store(false, Ordering::Relaxed);
+        }
+
+        show_favorites(s);
+    });
+
+    siv.add_global_callback('8', move |s| {
+        if ENTER_URL_OPEN.load(Ordering::Relaxed) {
+            s.pop_layer();
+            ENTER_URL_OPEN.store(false, Ordering::Relaxed);
+        }
+
+        show_cached_tracks(s);
+    });
+
+    siv.add_global_callback('9', move |s| {
+        if ENTER_URL_OPEN.load(Ordering::Relaxed) {
+            s.pop_layer();
+            ENTER_URL_OPEN.store(false, Ordering::Relaxed);
+        }
+
+        show_release_calendar(s);
+    });
+
+    siv.add_global_callback('H', move |s| {
+        if ENTER_URL_OPEN.load(Ordering::Relaxed) {
+            s.pop_layer();
+            ENTER_URL_OPEN.store(false, Ordering::Relaxed);
+        }
+
+        show_history(s);
+    });
+
+    siv.add_global_callback('C', move |s| {
+        if let Some(result) = s.call_on_name("cached_only", |c: &mut Checkbox| c.toggle()) {
+            result.process(s);
+        }
+    });
+}
+
+fn change_volume(step: f64) {
+    tokio::spawn(async move {
+        if let Err(error) = player::change_volume(step).await {
+            debug!(?error);
+        }
+    });
+}
+
+fn toggle_shuffle() {
+    tokio::spawn(async {
+        if let Err(error) = player::toggle_shuffle().await {
+            debug!(?error);
+        }
+    });
+}
+
+/// The volume, when it's not at full, and shuffle as shown below the progress bar.
+/// `None` when neither applies.
+fn modes_label() -> Option<String> {
+    let volume = player::volume();
+    let mut modes = Vec::new();
+
+    if SHUFFLE.load(Ordering::Relaxed) {
+        modes.push(t!("shuffle-indicator"));
+    }
+    if autoplay::is_enabled() {
+        modes.push(t!("autoplay-indicator"));
+    }
+    if volume < 1.0 {
+        modes.push(t!(
+            "volume-indicator",
+            volume = (volume * 100.0).round() as u32
+        ));
+    }
+
+    (!modes.is_empty()).then(|| modes.join(" · "))
+}
+
+fn update_modes(s: &mut Cursive) {
+    let label = modes_label();
+
+    s.call_on_name("modes", |view: &mut TextView| {
+        view.set_content(label.clone().unwrap_or_default());
+    });
+    s.call_on_name(
+        "modes_row",
+        |view: &mut HideableView<NamedView<TextView>>| {
+            view.set_visible(label.is_some());
+        },
+    );
+}
+
+/// Keys for simple mode: play/pause, next and unlocking the full TUI with the PIN.
+fn install_simple_events(siv: &mut Cursive) {
+    siv.clear_global_callbacks(Event::CtrlChar('c'));
+    siv.set_on_pre_event(Event::CtrlChar('c'), move |s| {
+        s.add_layer(Dialog::text(t!("simple-mode-locked")).dismiss_button(t!("close")));
+    });
+
+    siv.add_global_callback(' ', move |_| {
+        tokio::spawn(async {
+            if let Err(error) = player::play_pause().await {
+                debug!(?error);
+            }
+        });
+    });
+
+    siv.add_global_callback('N', move |_| {
+        tokio::spawn(async {
+            if let Err(error) = player::next().await {
+                debug!(?error);
+            }
+        });
+    });
+
+    siv.add_global_callback('U', unlock_simple_mode);
+}
+
+/// Ask for the PIN to leave simple mode. The full TUI is set up once the player confirms it.
+fn unlock_simple_mode(s: &mut Cursive) {
+    let input = EditView::new()
+        .secret()
+        .on_submit(move |s: &mut Cursive, pin: &str| {
+            s.pop_layer();
+
+            let pin = pin.to_string();
+            tokio::spawn(async move {
+                if let Err(error) = player::unlock_simple_mode(&pin).await {
+                    SINK.get()
+                        .unwrap()
+                        .send(Box::new(move |s| {
+                            s.add_layer(
+                                Dialog::text(error.to_string()).dismiss_button(t!("close")),
+                            );
+                        }))
+                        .expect("failed to send update");
+                }
+            });
+        })
+        .fixed_width(12);
+
+    s.add_layer(
+        Dialog::around(input)
+            .title(t!("party-mode-pin"))
+            .dismiss_button(t!("cancel")),
+    );
+}
+
+/// Swap the simple mode keys for the full ones and go to the player screen.
+fn leave_simple_mode(s: &mut Cursive) {
+    s.clear_global_callbacks(' ');
+    s.clear_global_callbacks('N');
+    s.clear_global_callbacks('U');
+
+    install_global_events(s);
+    install_menubar(s);
+    s.set_screen(0);
+}
+
+/// The release notes of the versions since the last run, shown once after an upgrade.
+fn show_whats_new(s: &mut Cursive, releases: &[&Release]) {
+    let mut content = StyledString::new();
+
+    for release in releases {
+        content.append_styled(release.version, Effect::Bold);
+        content.append_plain("\n");
+
+        for note in release.notes {
+            content.append_plain(format!("• {note}\n"));
+        }
+
+        content.append_plain("\n");
+    }
+
+    let dialog = Dialog::around(TextView::new(content).scrollable())
+        .title(t!("whats-new"))
+        .dismiss_button(t!("close"))
+        .max_width(80)
+        .max_height(30);
+
+    s.add_layer(dialog);
+}
+
+/// Ask for the name of a new playlist and create it.
+fn new_playlist(s: &mut Cursive) {
+    let form = LinearLayout::vertical()
+        .child(TextView::new(t!("playlist-name")))
+        .child(EditView::new().with_name("playlist_name").fixed_width(32))
+        .child(
+            LinearLayout::horizontal()
+                .child(Checkbox::new().with_name("playlist_public"))
+                .child(TextView::new(format!(" {}", t!("playlist-public")))),
+        );
+
+    let dialog = Dialog::around(form)
+        .button(t!("create"), |s| {
+            let name = s
+                .call_on_name("playlist_name", |view: &mut EditView| view.get_content())
+                .map(|name| name.trim().to_string())
+                .unwrap_or_default();
+            let public = s
+                .call_on_name("playlist_public", |view: &mut Checkbox| view.is_checked())
+                .unwrap_or_default();
+
+            if name.is_empty() {
+                return;
+            }
+
+            s.pop_layer();
+
+            tokio::spawn(async move {
+                let message = match player::create_playlist(name, public).await {
+                    Ok(playlist) => t!("playlist-created", name = playlist.title, id = playlist.id),
+                    Err(error) => error.to_string(),
+                };
+
+                refresh_user_playlists(message).await;
+            });
+        })
+        .dismiss_button(t!("cancel"));
+
+    s.add_layer(dialog);
+}
+
+/// Ask whether to delete the selected playlist and delete it.
+fn delete_playlist(s: &mut Cursive) {
+    let Some(playlist_id) = s
+        .call_on_name("user_playlists", |view: &mut SelectView<u32>| {
+            view.selection()
+        })
+        .flatten()
+        .map(|id| *id)
+        .filter(|id| *id != 0)
+    else {
+        return;
+    };
+
+    tokio::spawn(async move {
+        let title = player::user_playlists()
+            .await
+            .into_iter()
+            .find(|p| p.id == playlist_id)
+            .map(|p| p.title)
+            .unwrap_or_else(|| playlist_id.to_string());
+
+        SINK.get()
+            .unwrap()
+            .send(Box::new(move |s| {
+                let dialog = Dialog::text(t!("delete-playlist-prompt", title = title))
+                    .button(t!("delete"), move |s| {
+                        s.pop_layer();
+
+                        tokio::spawn(async move {
+                            let message = match player::delete_playlist(playlist_id as i64).await {
+                                Ok(()) => {
+                                    // Close the playlist if it is still the one shown.
+                                    SINK.get()
+                                        .unwrap()
+                                        .send(Box::new(move |s| {
+                                            s.call_on_name(
+                                                "user_playlist_layout",
+                                                |l: &mut LinearLayout| {
+                                                    let name = user_playlist_name(playlist_id);
+
+                                                    if let Some(index) =
+                                                        l.find_child_from_name(&name)
+                                                    {
+                                                        l.remove_child(index);
+                                                    }
+                                                },
+                                            );
+                                        }))
+                                        .expect("failed to send update");
+
+                                    t!("playlist-deleted", id = playlist_id)
+                                }
+                                Err(error) => error.to_string(),
+                            };
+
+                            refresh_user_playlists(message).await;
+                        });
+                    })
+                    .dismiss_button(t!("cancel"));
+
+                s.add_layer(dialog);
+            }))
+            .expect("failed to send update");
+    });
+}
+
+/// The name of the view showing one of the user's playlists on the My Playlists screen.
+fn user_playlist_name(playlist_id: u32) -> String {
+    format!("user_playlist_{playlist_id}")
+}
+
+/// Load the user's playlists into the My Playlists screen again and show `message`.
+async fn refresh_user_playlists(message: String) {
+    let playlists = player::user_playlists().await;
+
+    SINK.get()
+        .unwrap()
+        .send(Box::new(move |s| {
+            s.call_on_name("user_playlists", |view: &mut SelectView<u32>| {
+                fill_user_playlists(view, playlists);
+            });
+
+            s.add_layer(Dialog::text(message).dismiss_button(t!("close")));
+        }))
+        .expect("failed to send update");
+}
+
+fn fill_user_playlists(view: &mut SelectView<u32>, mut playlists: Vec<Playlist>) {
+    view.clear();
+    view.add_item(t!("select-playlist"), 0);
+
+    playlists.sort_by(|a, b| b.updated_at.cmp(&a.updated_at));
+
+    playlists.iter().for_each(|p| {
+        let mut item = StyledString::plain(p.title.clone());
+        item.append_plain("  ");
+        item.append_styled(p.summary(), Effect::Dim);
+
+        view.add_item(item, p.id);
+    });
+}
+
+fn build_theme(theme: Theme) -> cursive::theme::Theme {
+    cursive::theme::Theme {
+        shadow: false,
+        borders: BorderStyle::Simple,
+        palette: Palette::terminal_default().with(|palette| {
+            use cursive::theme::BaseColor::*;
+
+            let accent = match theme {
+                Theme::Terminal => Cyan.dark(),
+                Theme::Monochrome => cursive::theme::Color::TerminalDefault,
+            };
+
+            {
+                use cursive::theme::Color::TerminalDefault;
+                use cursive::theme::PaletteColor::*;
+
+                palette[Background] = TerminalDefault;
+                palette[View] = TerminalDefault;
+                palette[Primary] = match theme {
+                    Theme::Terminal => White.dark(),
+                    Theme::Monochrome => TerminalDefault,
+                };
+                palette[Highlight] = accent;
+                palette[HighlightInactive] = Black.dark();
+                palette[HighlightText] = Black.dark();
+            }
+
+            {
+                use cursive::theme::Color::TerminalDefault;
+                use cursive::theme::Effect::*;
+                use cursive::theme::PaletteStyle::*;
+
+                palette[Highlight] = Style::from(accent)
+                    .combine(Underline)
+                    .combine(Reverse)
+                    .combine(Bold);
+                palette[HighlightInactive] = Style::from(TerminalDefault).combine(Reverse);
+                palette[TitlePrimary] = Style::from(accent).combine(Bold);
+            }
+        }),
+    }
+}
+
+type ResultsPanel = ScrollView<NamedView<SelectView<(i32, Option<String>)>>>;
+
+/// Search for the query in the search form, only fetching the selected kind of result.
+fn run_search(s: &mut Cursive) {
+    let query = s
+        .find_name::<EditView>("search_query")
+        .map(|view| view.get_content().trim().to_string())
+        .unwrap_or_default();
+
+    if query.is_empty() {
+        return;
+    }
+
+    let search_type = s
+        .find_name::<SelectView<SearchType>>("search_type")
+        .and_then(|view| view.selection())
+        .map(|search_type| *search_type)
+        .unwrap_or(SearchType::Albums);
+
+    tokio::spawn(async move {
+        let limits = SearchLimits::only(search_type, SearchLimits::DEFAULT_LIMIT);
+        let results = player::search(&query, limits).await;
+
+        SINK.get()
+            .unwrap()
+            .send(Box::new(move |s| {
+                s.set_user_data(results);
+                load_search_results(search_type, s);
+            }))
+            .expect("failed to send update");
+    });
+}
+
+/// The selected item of the search results and the kind of results shown.
+fn selected_search_result(s: &mut Cursive) -> Option<(SearchType, String)> {
+    let item = s
+        .find_name::<SelectView>("search_results")
+        .and_then(|view| view.selection())
+        .map(|item| item.to_string())
+        .filter(|item| item != UNSTREAMABLE)?;
+
+    let search_type = s
+        .find_name::<SelectView<SearchType>>("search_type")
+        .and_then(|view| view.selection())
+        .map(|search_type| *search_type)
+        .unwrap_or(SearchType::Albums);
+
+    Some((search_type, item))
+}
+
+/// Add the selected album or track of the search results to the end of the queue.
+fn enqueue_search_result(s: &mut Cursive) {
+    if let Some((search_type, item)) = selected_search_result(s) {
+        enqueue(search_type, item);
+    }
+}
+
+/// Add the selected album, track or artist of the search results to the favorites.
+fn favorite_search_result(s: &mut Cursive) {
+    if let Some(item) =
+        selected_search_result(s).and_then(|(search_type, item)| favorite_item(search_type, &item))
+    {
+        set_favorite(item, true);
+    }
+}
+
+/// Add an album or track, by the id in a result list, to the end of the queue.
+fn enqueue(search_type: SearchType, item: String) {
+    tokio::spawn(async move {
+        let result = match search_type {
+            SearchType::Albums => player::add_album_to_queue(&item).await,
+            SearchType::Tracks => match item.parse::<i32>() {
+                Ok(track_id) => player::add_track_to_queue(track_id).await,
+                Err(_) => return,
+            },
+            _ => return,
+        };
+
+        let message = match result {
+            Ok(()) => t!("added-to-queue"),
+            Err(error) => error.to_string(),
+        };
+
+        SINK.get()
+            .unwrap()
+            .send(Box::new(move |s| {
+                s.add_layer(Dialog::text(message).dismiss_button(t!("close")));
+            }))
+            .expect("failed to send update");
+    });
+}
+
+/// Pick what the search results are narrowed to. The results shown are filtered again
+/// without searching anew.
+fn search_filter(s: &mut Cursive) {
+    let filter = SEARCH_FILTER.lock().unwrap().clone();
+
+    let mut hires = Checkbox::new();
+    hires.set_checked(filter.hires_only);
+
+    let form = LinearLayout::vertical()
+        .child(
+            LinearLayout::horizontal()
+                .child(hires.with_name("search_filter_hires"))
+                .child(TextView::new(format!(" {}", t!("filter-hires")))),
+        )
+        .child(TextView::new(t!("filter-min-year")))
+        .child(
+            EditView::new()
+                .content(filter.min_year.map(|y| y.to_string()).unwrap_or_default())
+                .with_name("search_filter_year")
+                .fixed_width(8),
+        )
+        .child(TextView::new(t!("filter-genre")))
+        .child(
+            EditView::new()
+                .content(filter.genre.unwrap_or_default())
+                .with_name("search_filter_genre")
+                .fixed_width(32),
+        );
+
+    let dialog = Dialog::around(form)
+        .title(t!("search-filter"))
+        .button(t!("apply-filter"), |s| {
+            let hires_only = s
+                .call_on_name("search_filter_hires", |view: &mut Checkbox| {
+                    view.is_checked()
+                })
+                .unwrap_or_default();
+            let year = s
+                .call_on_name("search_filter_year", |view: &mut EditView| {
+                    view.get_content().trim().to_string()
+                })
+                .unwrap_or_default();
+            let genre = s
+                .call_on_name("search_filter_genre", |view: &mut EditView| {
+                    view.get_content().trim().to_string()
+                })
+                .unwrap_or_default();
+
+            let min_year = if year.is_empty() {
+                None
+            } else if let Ok(year) = year.parse::<u32>() {
+                Some(year)
+            } else {
+                s.add_layer(
+                    Dialog::text(t!("invalid-year", year = year)).dismiss_button(t!("close")),
+                );
+                return;
+            };
+
+            s.pop_layer();
+            set_search_filter(
+                s,
+                SearchFilter {
+                    hires_only,
+                    min_year,
+                    genre: Some(genre).filter(|g| !g.is_empty()),
+                },
+            );
+        })
+        .button(t!("clear-filter"), |s| {
+            s.pop_layer();
+            set_search_filter(s, SearchFilter::default());
+        })
+        .dismiss_button(t!("cancel"))
+        .wrap_with(OnEventView::new)
+        .on_event(Event::Key(Key::Esc), |s| {
+            s.pop_layer();
+        });
+
+    s.add_layer(dialog);
+}
+
+fn set_search_filter(s: &mut Cursive, filter: SearchFilter) {
+    *SEARCH_FILTER.lock().unwrap() = filter;
+
+    s.call_on_name("search_filter", |view: &mut TextView| {
+        view.set_content(search_filter_label());
+    });
+
+    let search_type = s
+        .find_name::<SelectView<SearchType>>("search_type")
+        .and_then(|view| view.selection())
+        .map(|search_type| *search_type)
+        .unwrap_or(SearchType::Albums);
+
+    load_search_results(search_type, s);
+}
+
+/// What the search results are filtered by, or nothing when they aren't.
+fn search_filter_label() -> String {
+    let filter = SEARCH_FILTER.lock().unwrap();
+
+    if !filter.is_active() {
+        return String::new();
+    }
+
+    let mut parts = vec![];
+
+    if filter.hires_only {
+        parts.push(t!("filter-hires"));
+    }
+
+    if let Some(year) = filter.min_year {
+        parts.push(t!("filter-since", year = year));
+    }
+
+    if let Some(genre) = &filter.genre {
+        parts.push(genre.clone());
+    }
+
+    t!("search-filtered", filters = parts.join(", "))
+}
+
+fn load_search_results(search_type: SearchType, s: &mut Cursive) {
+    if let Some(mut search_results) = s.find_name::<SelectView>("search_results") {
+        search_results.clear();
+
+        let filter = SEARCH_FILTER.lock().unwrap().clone();
+
+        if let Some(data) = s.user_data::<SearchResults>() {
+            match search_type {
+                SearchType::Albums => {
+                    for a in data.albums.iter().filter(|a| filter.album_matches(a)) {
+                        let id = if a.available || a.available_on().is_some() {
+                            a.id.clone()
+                        } else {
+                            UNSTREAMABLE.to_string()
+                        };
+
+                        search_results.add_item(a.list_item(), id);
+                    }
+
+                    search_results.set_on_submit(move |s: &mut Cursive, item: &String| {
+                        if item == UNSTREAMABLE {
+                            return;
+                        }
+
+                        let upcoming = s.user_data::<SearchResults>().and_then(|data| {
+                            data.albums
+                                .iter()
+                                .find(|a| &a.id == item && !a.available)
+                                .cloned()
+                        });
+
+                        if let Some(album) = upcoming {
+                            watch_release(s, album);
+                        } else {
+                            let item = item.clone();
+                            tokio::spawn(async move { player::play_album(&item).await });
+                        }
+                    });
+                }
+                SearchType::Artists => {
+                    for a in &data.artists {
+                        search_results.add_item(a.name.clone(), a.id.to_string());
+                    }
+
+                    search_results.set_on_submit(move |_s: &mut Cursive, item: &String| {
+                        submit_artist(item.parse::<i32>().expect("failed to parse string"));
+                    });
+                }
+                SearchType::Tracks => {
+                    for t in data.tracks.iter().filter(|t| filter.track_matches(t)) {
+                        let id = if t.available {
+                            t.id.to_string()
+                        } else {
+                            UNSTREAMABLE.to_string()
+                        };
+
+                        search_results.add_item(t.list_item(), id)
+                    }
+
+                    search_results.set_on_submit(move |s: &mut Cursive, item: &String| {
+                        if item != UNSTREAMABLE {
+                            submit_track(
+                                s,
+                                (item.parse::<i32>().expect("failed to parse string"), None),
+                            );
+                        }
+                    });
+                }
+                SearchType::Playlists => {
+                    for p in &data.playlists {
+                        search_results.add_item(p.title.clone(), p.id.to_string())
+                    }
+
+                    search_results.set_on_submit(move |_s: &mut Cursive, item: &String| {
+                        let item = item.parse::<u32>().expect("failed to parse string");
+
+                        submit_playlist(item, |s, layout| {
+                            let event_panel =
+                                OnEventView::new(layout).on_event(Event::Key(Key::Esc), move |s| {
+                                    s.screen_mut().pop_layer();
+                                });
+
+                            s.screen_mut().add_layer(Panel::new(event_panel));
+                        });
+                    });
+                }
+                _ => {}
+            }
+        }
+    }
+}
+
+fn favorite_item(search_type: SearchType, item: &str) -> Option<Favorite> {
+    match search_type {
+        SearchType::Albums => Some(Favorite::Album(item.to_string())),
+        SearchType::Tracks => item.parse().ok().map(Favorite::Track),
+        SearchType::Artists => item.parse().ok().map(Favorite::Artist),
+        _ => None,
+    }
+}
+
+/// Add `item` to the favorites, or remove it, and refresh the favorites screen.
+fn set_favorite(item: Favorite, favorite: bool) {
+    tokio::spawn(async move {
+        let message = match player::set_favorite(item, favorite).await {
+            Ok(()) if favorite => t!("favorite-added"),
+            Ok(()) => t!("favorite-removed"),
+            Err(error) => error.to_string(),
+        };
+
+        SINK.get()
+            .unwrap()
+            .send(Box::new(move |s| {
+                s.add_layer(Dialog::text(message).dismiss_button(t!("close")));
+            }))
+            .expect("failed to send update");
+
+        refresh_favorites();
+    });
+}
+
+fn favorites_type(s: &mut Cursive) -> SearchType {
+    s.find_name::<SelectView<SearchType>>("favorites_type")
+        .and_then(|view| view.selection())
+        .map(|search_type| *search_type)
+        .unwrap_or(SearchType::Albums)
+}
+
+/// The kind of favorites shown and the id of the selected one.
+fn selected_favorite(s: &mut Cursive) -> Option<(SearchType, String)> {
+    let search_type = favorites_type(s);
+    let item = s
+        .find_name::<SelectView>("favorites_list")
+        .and_then(|view| view.selection())
+        .map(|item| item.to_string())?;
+
+    Some((search_type, item))
+}
+
+/// Whether the favorite album or track with the id `item` can be played.
+fn favorite_available(search_type: SearchType, item: &str) -> bool {
+    let favorites = FAVORITES.lock().unwrap();
+
+    match search_type {
+        SearchType::Albums => favorites.albums.iter().any(|a| a.id == item && a.available),
+        SearchType::Tracks => favorites
+            .tracks
+            .iter()
+            .any(|t| t.id.to_string() == item && t.available),
+        _ => true,
+    }
+}
+
+fn submit_favorite(s: &mut Cursive, item: &String) {
+    let search_type = favorites_type(s);
+
+    if !favorite_available(search_type, item) {
+        return;
+    }
+
+    match search_type {
+        SearchType::Albums => {
+            let item = item.clone();
+            tokio::spawn(async move { player::play_album(&item).await });
+
+            s.set_screen(0);
+        }
+        SearchType::Tracks => {
+            if let Ok(track_id) = item.parse::<i32>() {
+                submit_track(s, (track_id, None));
+            }
+        }
+        SearchType::Artists => {
+            if let Ok(artist_id) = item.parse::<i32>() {
+                submit_artist(artist_id);
+            }
+        }
+        _ => {}
+    }
+}
+
+/// Shows the favorites screen straight from the cache and refreshes it in the background.
+fn show_favorites(s: &mut Cursive) {
+    s.set_screen(4);
+    refresh_favorites();
+}
+
+fn refresh_favorites() {
+    tokio::spawn(async {
+        if let Some(favorites) = favorites::refresh().await {
+            *FAVORITES.lock().unwrap() = favorites;
+
+            SINK.get()
+                .unwrap()
+                .send(Box::new(load_favorites))
+                .expect("failed to send update");
+        }
+    });
+}
+
+/// Redraws the favorites list using the selected kind, sort and filters. The sort
+/// and filters only apply to albums.
+fn load_favorites(s: &mut Cursive) {
+    let search_type = favorites_type(s);
+
+    let sort = s
+        .find_name::<SelectView<FavoriteSort>>("favorites_sort")
+        .and_then(|view| view.selection())
+        .map(|sort| *sort)
+        .unwrap_or_default();
+
+    let filter = FavoriteFilter {
+        hires_only: s
+            .find_name::<Checkbox>("favorites_hires")
+            .map(|c| c.is_checked())
+            .unwrap_or_default(),
+        unplayed_only: s
+            .find_name::<Checkbox>("favorites_unplayed")
+            .map(|c| c.is_checked())
+            .unwrap_or_default(),
+    };
+
+    if let Some(mut list) = s.find_name::<SelectView>("favorites_list") {
+        let selected = list.selection();
+        list.clear();
+
+        let favorites = FAVORITES.lock().unwrap();
+
+        match search_type {
+            SearchType::Tracks => {
+                for t in &favorites.tracks {
+                    list.add_item(t.list_item(), t.id.to_string());
+                }
+            }
+            SearchType::Artists => {
+                for a in &favorites.artists {
+                    list.add_item(a.name.clone(), a.id.to_string());
+                }
+            }
+            _ => {
+                for a in favorites.arrange(sort, filter) {
+                    list.add_item(a.list_item(), a.id.clone());
+                }
+            }
+        }
+
+        if let Some(index) =
+            selected.and_then(|id| list.iter().position(|(_, value)| *value == *id))
+        {
+            list.set_selection(index);
+        }
+    }
+}
+
+/// Shows the cache browser and reloads the list of cached tracks.
+fn show_cached_tracks(s: &mut Cursive) {
+    s.set_screen(CACHED_TRACKS_SCREEN);
+
+    tokio::spawn(async {
+        let tracks = cache::cached_tracks().await;
+
+        SINK.get()
+            .unwrap()
+            .send(Box::new(move |s| {
+                if let Some(mut list) = s.find_name::<SelectView<i32>>("cached_tracks") {
+                    list.clear();
+
+                    for cached in tracks {
+                        list.add_item(cached.track.list_item(), cached.track.id as i32);
+                    }
+                }
+            }))
+            .expect("failed to send update");
+    });
+}
+
+/// Shows the tracks played last and the ones played most.
+fn show_history(s: &mut Cursive) {
+    s.set_screen(HISTORY_SCREEN);
+
+    tokio::spawn(async {
+        let recent = history::recent(history::DEFAULT_LIMIT).await;
+        let most_played = history::most_played(history::DEFAULT_LIMIT).await;
+
+        SINK.get()
+            .unwrap()
+            .send(Box::new(move |s| {
+                if let Some(mut list) = s.find_name::<SelectView<i32>>("history_recent") {
+                    list.clear();
+
+                    if recent.is_empty() {
+                        list.add_item(t!("history-empty"), -1);
+                    }
+
+                    for entry in &recent {
+                        list.add_item(entry.list_item(), entry.track_id as i32);
+                    }
+                }
+
+                if let Some(mut list) = s.find_name::<SelectView<i32>>("history_most_played") {
+                    list.clear();
+
+                    for track in &most_played {
+                        list.add_item(track.list_item(), track.track_id as i32);
+                    }
+                }
+            }))
+            .expect("failed to send update");
+    });
+}
+
+/// Shows the Discover screen, loading the featured playlists and the albums of the first
+/// section the first time.
+fn show_discover(s: &mut Cursive) {
+    s.set_screen(DISCOVER_SCREEN);
+
+    let loaded = s
+        .find_name::<SelectView<u32>>("discover_playlists")
+        .is_some_and(|list| !list.is_empty());
+
+    if loaded {
+        return;
+    }
+
+    load_discover_albums(DISCOVER_SECTIONS[0]);
+
+    tokio::spawn(async {
+        let playlists = player::featured_playlists().await.unwrap_or_default();
+
+        SINK.get()
+            .unwrap()
+            .send(Box::new(move |s| {
+                if let Some(mut list) = s.find_name::<SelectView<u32>>("discover_playlists") {
+                    list.clear();
+
+                    for playlist in playlists {
+                        list.add_item(playlist.title, playlist.id);
+                    }
+                }
+            }))
+            .expect("failed to send update");
+    });
+}
+
+/// Fetches the first page of a featured section for the Discover screen.
+fn load_discover_albums(category: BrowseCategory) {
+    tokio::spawn(async move {
+        let Some(page) = player::browse(category, None, 0).await else {
+            return;
+        };
+
+        SINK.get()
+            .unwrap()
+            .send(Box::new(move |s| {
+                s.call_on_name(
+                    "discover_albums_panel",
+                    |panel: &mut Panel<ResizedView<ScrollView<NamedView<SelectView>>>>| {
+                        panel.set_title(category.title());
+                    },
+                );
+
+                if let Some(mut list) = s.find_name::<SelectView>("discover_albums") {
+                    list.clear();
+
+                    for a in &page.albums {
+                        let id = if a.available {
+                            a.id.clone()
+                        } else {
+                            UNSTREAMABLE.to_string()
+                        };
+
+                        list.add_item(a.list_item(), id);
+                    }
+                }
+            }))
+            .expect("failed to send update");
+    });
+}
+
+/// Shows the release calendar from the last refresh, refreshing it when there is none.
+fn show_release_calendar(s: &mut Cursive) {
+    s.set_screen(RELEASE_CALENDAR_SCREEN);
+
+    tokio::spawn(async {
+        let albums = releases::calendar().await;
+
+        if albums.is_empty() {
+            refresh_release_calendar();
+            return;
+        }
+
+        SINK.get()
+            .unwrap()
+            .send(Box::new(move |s| fill_release_calendar(s, albums)))
+            .expect("failed to send update");
+    });
+}
+
+/// Fetches the releases of the followed artists again.
+fn refresh_release_calendar() {
+    tokio::spawn(async {
+        let service = player::service().await;
+        let albums = releases::refresh_calendar(service.as_ref()).await;
+
+        SINK.get()
+            .unwrap()
+            .send(Box::new(move |s| match albums {
+                Some(albums) => fill_release_calendar(s, albums),
+                None => s.add_layer(
+                    Dialog::text(t!("calendar-refresh-failed")).dismiss_button(t!("close")),
+                ),
+            }))
+            .expect("failed to send update");
+    });
+}
+
+fn fill_release_calendar(s: &mut Cursive, albums: Vec<Album>) {
+    if let Some(mut list) = s.find_name::<SelectView>("release_calendar") {
+        list.clear();
+
+        if albums.is_empty() {
+            list.add_item(t!("calendar-empty"), UNSTREAMABLE.to_string());
+        }
+
+        for album in &albums {
+            let date = releases::release_timestamp(album)
+                .map(releases::format_date)
+                .unwrap_or_default();
+
+            let mut item = StyledString::styled(format!("{date:<10}  "), Effect::Dim);
+            item.append(album.list_item());
+
+            list.add_item(item, album.id.clone());
+        }
+    }
+
+    *CALENDAR.lock().unwrap() = albums;
+}
+
+/// Fetches a page of a browse category, narrowed to a genre when one is given, and shows
+/// it once it arrives.
+fn load_browse_page(category: BrowseCategory, genre: Option<u32>, offset: u32) {
+    tokio::spawn(async move {
+        if let Some(page) = player::browse(category, genre, offset).await {
+            SINK.get()
+                .unwrap()
+                .send(Box::new(move |s| {
+                    show_browse_page(s, &page);
+                    *BROWSE.lock().unwrap() = page;
+                }))
+                .expect("failed to send update");
+        }
+    });
+}
+
+fn show_browse_page(s: &mut Cursive, page: &BrowsePage) {
+    let genre = page.genre.and_then(|id| {
+        s.find_name::<SelectView<Option<u32>>>("browse_genre")
+            .and_then(|view| {
+                view.iter()
+                    .find(|(_, genre)| **genre == Some(id))
+                    .map(|(name, _)| name.to_string())
+            })
+    });
+
+    s.call_on_name(
+        "browse_panel",
+        |panel: &mut Panel<ResizedView<ScrollView<NamedView<SelectView>>>>| {
+            panel.set_title(match &genre {
+                Some(genre) => format!("{genre} · {}", page.category.title()),
+                None => page.category.title(),
+            });
+        },
+    );
+
+    if let Some(mut album_list) = s.find_name::<SelectView>("browse_albums") {
+        album_list.clear();
+
+        for a in &page.albums {
+            let id = if a.available {
+                a.id.clone()
+            } else {
+                UNSTREAMABLE.to_string()
+            };
+
+            album_list.add_item(a.list_item(), id);
+        }
+    }
+
+    let pages = page.total.div_ceil(player::BROWSE_PAGE_SIZE).max(1);
+    let current = page.offset / player::BROWSE_PAGE_SIZE + 1;
+
+    s.call_on_name("browse_page", |view: &mut TextView| {
+        view.set_content(t!("browse-page", page = current, pages = pages));
+    });
+}
+
+/// Keeps the event log up to date while its screen is visible.
+fn tail_event_log() {
+    tokio::spawn(async {
+        let mut interval = tokio::time::interval(std::time::Duration::from_millis(500));
+
+        loop {
+            interval.tick().await;
+
+            let sent = SINK.get().unwrap().send(Box::new(|s| {
+                if s.active_screen() == EVENT_LOG_SCREEN {
+                    load_event_log(s);
+                }
+            }));
+
+            if sent.is_err() {
+                break;
+            }
+        }
+    });
+}
+
+fn load_event_log(s: &mut Cursive) {
+    let filter = s
+        .find_name::<EditView>("event_filter")
+        .map(|view| view.get_content())
+        .unwrap_or_default();
+
+    let lines = event_log::events(&filter)
+        .iter()
+        .map(|e| e.to_string())
+        .collect::<Vec<String>>()
+        .join("\n");
+
+    s.call_on_name("event_log", |view: &mut TextView| {
+        view.set_content(lines);
+    });
+
+    let stats = transition::gapless_stats();
+
+    s.call_on_name("gapless_stats", |view: &mut TextView| {
+        view.set_content(t!(
+            "gapless-stats",
+            prepared = stats.prepared,
+            retried = stats.retried,
+            prefetched = stats.prefetched,
+            failed = stats.failed
+        ));
+    });
+
+    // The slowest endpoints on average, the full list is served at /metrics.
+    let latency = metrics::summary()
+        .into_iter()
+        .take(API_LATENCY_ROWS)
+        .map(|l| {
+            t!(
+                "api-latency",
+                endpoint = l.endpoint,
+                calls = l.calls,
+                errors = l.errors,
+                p50 = l.p50_ms,
+                p95 = l.p95_ms,
+                max = l.max_ms
+            )
+        })
+        .collect::<Vec<String>>();
+
+    s.call_on_name("api_latency", |view: &mut TextView| {
+        view.set_content(latency.join("\n"));
+    });
+}
+
+fn dump_event_log(s: &mut Cursive) {
+    let mut path = dirs::data_local_dir().unwrap_or_else(std::env::temp_dir);
+    path.push("hifi-rs");
+    path.push(format!(
+        "events-{}.log",
+        chrono::Local::now().format("%Y%m%d-%H%M%S")
+    ));
+
+    let message = match event_log::dump(&path) {
+        Ok(count) => t!(
+            "event-dump-saved",
+            count = count,
+            path = path.to_string_lossy()
+        ),
+        Err(error) => t!("event-dump-failed", error = error),
+    };
+
+    s.add_layer(Dialog::text(message).dismiss_button(t!("close")));
+}
+
+/// Load a playlist in the background and hand its layout to `show` once it is there.
+fn submit_playlist<F>(item: u32, show: F)
+where
+    F: FnOnce(&mut Cursive, LinearLayout) + Send + 'static,
+{
+    tokio::spawn(async move {
+        let playlist = player::playlist(item as i64).await;
+
+        SINK.get()
+            .unwrap()
+            .send(Box::new(move |s| {
+                let layout = playlist_layout(item, playlist);
+                show(s, layout);
+            }))
+            .expect("failed to send update");
+    });
+}
+
+fn playlist_layout(item: u32, playlist: Option<Playlist>) -> LinearLayout {
+    let mut layout = LinearLayout::vertical();
+
+    let description = playlist.as_ref().and_then(|p| p.description.clone());
+    let playlist_tracks = playlist
+        .map(|p| p.tracks.into_values().collect::<Vec<Track>>())
+        .unwrap_or_default();
+
+    let mut list = CursiveUI::results_list("playlist_items");
+    let mut playlist_items = list.get_inner_mut().get_mut();
+
+    let entries: PlaylistEntries = Arc::new(Mutex::new(
+        playlist_tracks
+            .iter()
+            .map(|t| (t.playlist_track_id, t.list_item()))
+            .collect(),
+    ));
+
+    for t in &playlist_tracks {
+        let mut row = StyledString::plain(format!("{:02} ", t.position));
+
+        row.append(t.list_item());
+
+        let track_id = if t.available { t.id as i32 } else { -1 };
+
+        let value = if let Some(album) = &t.album {
+            let album_id = if album.available {
+                album.id.clone()
+            } else {
+                UNSTREAMABLE.to_string()
+            };
+
+            (track_id, Some(album_id))
+        } else {
+            (track_id, None)
+        };
+
+        playlist_items.add_item(row, value);
+    }
+
+    playlist_items.set_on_submit(move |s, item| {
+        submit_track(s, item.clone());
+    });
+
+    let removed = entries.clone();
+    let moved_up = entries.clone();
+    let moved_down = entries;
+
+    let list = OnEventView::new(list)
+        .on_event_inner('d', move |panel: &mut ResultsPanel, _| {
+            remove_playlist_entry(item, &removed, &mut panel.get_inner_mut().get_mut())
+        })
+        .on_event_inner('K', move |panel: &mut ResultsPanel, _| {
+            move_playlist_entry(item, &moved_up, &mut panel.get_inner_mut().get_mut(), true)
+        })
+        .on_event_inner('J', move |panel: &mut ResultsPanel, _| {
+            move_playlist_entry(
+                item,
+                &moved_down,
+                &mut panel.get_inner_mut().get_mut(),
+                false,
+            )
+        });
+
+    let meta = LinearLayout::horizontal()
+        .child(Button::new(t!("play"), move |_s| {
+            tokio::spawn(async move { player::play_playlist(item as i64).await });
+        }))
+        .child(TextView::new(" "))
+        .child(Button::new(t!("audition"), move |s| {
+            s.set_screen(0);
+
+            tokio::spawn(async move {
+                if let Err(error) = audition::start(item as i64).await {
+                    debug!(?error);
+                }
+            });
+        }))
+        .child(
+            TextView::new(t!("total-tracks", count = playlist_tracks.len()))
+                .h_align(HAlign::Right)
+                .full_width(),
+        );
+
+    layout.add_child(meta);
+
+    if let Some(description) = description {
+        layout.add_child(description_view(description, None));
+    }
+
+    layout.add_child(list);
+    layout.add_child(TextView::new(t!("playlist-keys")));
+
+    layout
+}
+
+/// The tracks of a playlist made for the user. Unlike the user's own playlists it can't be
+/// edited.
+fn personal_playlist_layout(kind: PersonalPlaylist, playlist: Option<Playlist>) -> LinearLayout {
+    let mut layout = LinearLayout::vertical();
+
+    let Some(playlist) = playlist else {
+        layout.add_child(TextView::new(t!("personal-playlist-unavailable")));
+        return layout;
+    };
+
+    let mut list = CursiveUI::results_list("playlist_items");
+    let mut playlist_items = list.get_inner_mut().get_mut();
+
+    for t in playlist.tracks.values() {
+        let mut row = StyledString::plain(format!("{:02} ", t.position));
+        row.append(t.list_item());
+
+        let track_id = if t.available { t.id as i32 } else { -1 };
+        let album_id = t.album.as_ref().map(|album| {
+            if album.available {
+                album.id.clone()
+            } else {
+                UNSTREAMABLE.to_string()
+            }
+        });
+
+        playlist_items.add_item(row, (track_id, album_id));
+    }
+
+    playlist_items.set_on_submit(move |s, item| {
+        submit_track(s, item.clone());
+    });
+
+    let meta = LinearLayout::horizontal()
+        .child(Button::new(t!("play"), move |_s| {
+            tokio::spawn(async move { player::play_personal_playlist(kind).await });
+        }))
+        .child(
+            TextView::new(playlist.summary())
+                .h_align(HAlign::Right)
+                .full_width(),
+        );
+
+    layout.add_child(meta);
+
+    if let Some(description) = playlist.description.clone() {
+        layout.add_child(description_view(description, None));
+    }
+
+    layout.add_child(list);
+
+    layout
+}
+
+/// The entries of a playlist as shown, in order: the playlist track id of each and the
+/// line for it without its number.
+type PlaylistEntries = Arc<Mutex<Vec<(Option<i64>, StyledString)>>>;
+
+/// Remove the selected entry from the playlist `playlist_id`. The list changes right away,
+/// the service is told in the background.
+fn remove_playlist_entry(
+    playlist_id: u32,
+    entries: &PlaylistEntries,
+    list: &mut SelectView<(i32, Option<String>)>,
+) -> Option<EventResult> {
+    let index = list.selected_id()?;
+    let mut entries = entries.lock().unwrap();
+    let playlist_track_id = entries.get(index)?.0?;
+
+    entries.remove(index);
+    list.remove_item(index);
+    number_playlist_entries(&entries, list);
+
+    tokio::spawn(async move {
+        let result =
+            player::remove_playlist_tracks(playlist_id as i64, vec![playlist_track_id]).await;
+        playlist_edited(playlist_id, result).await;
+    });
+
+    Some(EventResult::Consumed(None))
+}
+
+/// Move the selected entry of the playlist `playlist_id` one place up or down.
+fn move_playlist_entry(
+    playlist_id: u32,
+    entries: &PlaylistEntries,
+    list: &mut SelectView<(i32, Option<String>)>,
+    up: bool,
+) -> Option<EventResult> {
+    let index = list.selected_id()?;
+    let mut entries = entries.lock().unwrap();
+
+    let target = if up { index.checked_sub(1)? } else { index + 1 };
+
+    if target >= entries.len() {
+        return None;
+    }
+
+    let playlist_track_id = entries[index].0?;
+    // The service places the entry before the one at `insert_before`, counted before the move.
+    let insert_before = if up { target } else { target + 1 };
+
+    let (label, value) = list
+        .get_item(index)
+        .map(|(label, value)| (label.to_string(), value.clone()))?;
+
+    entries.swap(index, target);
+    list.remove_item(index);
+    list.insert_item(target, label, value);
+    list.set_selection(target);
+    number_playlist_entries(&entries, list);
+
+    tokio::spawn(async move {
+        let result =
+            player::move_playlist_track(playlist_id as i64, playlist_track_id, insert_before).await;
+        playlist_edited(playlist_id, result).await;
+    });
+
+    Some(EventResult::Consumed(None))
+}
+
+/// Number the rows of a playlist again after an entry was removed or moved.
+fn number_playlist_entries(
+    entries: &[(Option<i64>, StyledString)],
+    list: &mut SelectView<(i32, Option<String>)>,
+) {
+    for (index, (_, line)) in entries.iter().enumerate() {
+        if let Some((label, _)) = list.get_item_mut(index) {
+            let mut row = StyledString::plain(format!("{:02} ", index + 1));
+            row.append(line.clone());
+
+            *label = row;
+        }
+    }
+}
+
+/// Index the edited playlist again, or tell why the edit failed. The list shown is
+/// only right again once the playlist is opened anew.
+async fn playlist_edited(playlist_id: u32, result: player::Result<()>) {
+    match result {
+        Ok(()) => {
+            if let Some(playlist) = player::playlist(playlist_id as i64).await {
+                library::index_playlist(&playlist).await;
+            }
+        }
+        Err(error) => {
+            let message = error.to_string();
+
+            SINK.get()
+                .unwrap()
+                .send(Box::new(move |s| {
+                    s.add_layer(Dialog::text(message).dismiss_button(t!("close")));
+                }))
+                .expect("failed to send update");
+        }
+    }
+}
+
+/// A scrollable block of text for album and playlist descriptions.
+fn description_view(description: String, catchline: Option<String>) -> impl View {
+    let mut content = StyledString::new();
+
+    if let Some(catchline) = catchline {
+        content.append_styled(catchline, Effect::Italic);
+        content.append_plain("\n\n");
+    }
+
+    content.append_plain(description);
+
+    TextView::new(content)
+        .scrollable()
+        .scroll_y(true)
+        .max_height(DESCRIPTION_HEIGHT)
+}
+
+/// Shows the description of whatever is playing, the album or the playlist.
+fn show_description() {
+    tokio::spawn(async {
+        let list = player::current_tracklist().await;
+
+        let (title, catchline, description) = match list.list_type() {
+            TrackListType::Playlist => match list.get_playlist() {
+                Some(playlist) => (playlist.title.clone(), None, playlist.description.clone()),
+                None => return,
+            },
+            _ => {
+                let Some(id) = list.get_album().map(|a| a.id.clone()) else {
+                    return;
+                };
+
+                match player::album(&id).await {
+                    Some(album) => (album.display_title(), album.catchline, album.description),
+                    None => return,
+                }
+            }
+        };
+
+        SINK.get()
+            .unwrap()
+            .send(Box::new(move |s| {
+                let content: Box<dyn View> = match description {
+                    Some(description) => Box::new(
+                        description_view(description, catchline).max_width(DESCRIPTION_WIDTH),
+                    ),
+                    None => Box::new(TextView::new(t!("no-description"))),
+                };
+
+                let mut dialog = Dialog::around(BoxedView::new(content))
+                    .title(title.trim())
+                    .dismiss_button(t!("close"))
+                    .wrap_with(OnEventView::new);
+
+                dialog.set_on_pre_event(Event::Key(Key::Esc), |s| {
+                    s.pop_layer();
+                });
+
+                s.add_layer(dialog);
+            }))
+            .expect("failed to send update");
+    });
+}
+
+/// Load an artist's albums in the background and show them as a menu. The albums
+/// are then fetched in one batch, so picking one of them starts right away.
+fn submit_artist(item: i32) {
+    tokio::spawn(async move {
+        let artist_albums = player::artist_albums(item).await;
+        let album_ids = artist_albums
+            .iter()
+            .filter(|a| a.available)
+            .take(ARTIST_PREFETCH)
+            .map(|a| a.id.clone())
+            .collect::<Vec<String>>();
+
+        tokio::spawn(async move { player::albums(album_ids).await });
+
+        SINK.get()
+            .unwrap()
+            .send(Box::new(move |s| show_artist_albums(s, artist_albums)))
+            .expect("failed to send update");
+    });
+}
+
+fn show_artist_albums(s: &mut Cursive, artist_albums: Vec<Album>) {
+    if !artist_albums.is_empty() {
+        let mut tree = cursive::menu::Tree::new();
+
+        for a in artist_albums {
+            if !a.available {
+                if a.available_on().is_some() {
+                    tree.add_leaf(a.list_item(), move |s: &mut Cursive| {
+                        s.screen_mut().pop_layer();
+                        watch_release(s, a.clone());
+                    });
+                }
+
+                continue;
+            }
+
+            tree.add_leaf(a.list_item(), move |s: &mut Cursive| {
+                let id = a.id.clone();
+                tokio::spawn(async move { player::play_album(&id).await });
+
+                s.call_on_name(
+                    "screens",
+                    |screens: &mut ScreensView<ResizedView<LinearLayout>>| {
+                        screens.set_active_screen(0);
+                    },
+                );
+            });
+        }
+
+        let album_list: MenuPopup = MenuPopup::new(Rc::new(tree));
+
+        let events = album_list
+            .scrollable()
+            .resized(SizeConstraint::Full, SizeConstraint::Free);
+
+        s.screen_mut().add_layer(events);
+    }
+}
+
+/// Offers to add an upcoming album to the release watch list.
+fn watch_release(s: &mut Cursive, album: Album) {
+    let mut dialog = Dialog::text(t!("watch-release-prompt", title = album.title))
+        .button(t!("yes"), move |s: &mut Cursive| {
+            s.screen_mut().pop_layer();
+
+            let album = album.clone();
+            tokio::spawn(async move { releases::watch(&album).await });
+        })
+        .dismiss_button(t!("no"))
+        .wrap_with(OnEventView::new);
+
+    dialog.set_on_pre_event(Event::Key(Key::Esc), |s| {
+        s.screen_mut().pop_layer();
+    });
+
+    s.screen_mut().add_layer(dialog);
+}
+
+/// Pick the quality of the next album, playlist or track, leaving the session's as it is.
+fn pick_next_quality(s: &mut Cursive) {
+    let mut qualities = SelectView::new();
+    qualities.add_item(t!("session-quality"), None);
+    qualities.add_item(service::format_label(0, 0.), Some(AudioQuality::Mp3));
+    qualities.add_item(service::format_label(16, 44.1), Some(AudioQuality::CD));
+    qualities.add_item(service::format_label(24, 96.), Some(AudioQuality::HIFI96));
+    qualities.add_item(service::format_label(24, 192.), Some(AudioQuality::HIFI192));
+
+    qualities.set_on_submit(|s: &mut Cursive, quality: &Option<AudioQuality>| {
+        s.pop_layer();
+
+        let quality = quality.clone();
+        tokio::spawn(async move { player::set_next_quality(quality).await });
+    });
+
+    let layout = LinearLayout::vertical()
+        .child(PaddedView::lrtb(
+            0,
+            0,
+            0,
+            1,
+            TextView::new(t!("next-quality-help")).style(Style::from(Effect::Dim)),
+        ))
+        .child(qualities);
+
+    let mut dialog = Dialog::around(layout)
+        .title(t!("next-quality"))
+        .dismiss_button(t!("cancel"))
+        .wrap_with(OnEventView::new);
+
+    dialog.set_on_pre_event(Event::Key(Key::Esc), |s| {
+        s.pop_layer();
+    });
+
+    s.add_layer(dialog);
+}
+
+fn switch_quality(s: &mut Cursive) {
+    let mut qualities = SelectView::new();
+    qualities.add_item(service::format_label(0, 0.), AudioQuality::Mp3);
+    qualities.add_item(service::format_label(16, 44.1), AudioQuality::CD);
+    qualities.add_item(service::format_label(24, 96.), AudioQuality::HIFI96);
+    qualities.add_item(service::format_label(24, 192.), AudioQuality::HIFI192);
+
+    qualities.set_on_submit(|s: &mut Cursive, quality: &AudioQuality| {
+        s.pop_layer();
+
+        let quality = quality.clone();
+        tokio::spawn(async move { player::switch_quality(quality).await });
+    });
+
+    let mut dialog = Dialog::around(qualities)
+        .title(t!("switch-quality"))
+        .dismiss_button(t!("cancel"))
+        .wrap_with(OnEventView::new);
+
+    dialog.set_on_pre_event(Event::Key(Key::Esc), |s| {
+        s.pop_layer();
+    });
+
+    s.add_layer(dialog);
+}
+
+/// Save the queue under a name, or switch to one saved before.
+async fn saved_queues() {
+    let snapshots = db::get_queue_snapshots().await;
+
+    SINK.get()
+        .unwrap()
+        .send(Box::new(move |s| {
+            let mut layout = LinearLayout::vertical()
+                .child(TextView::new(t!("queue-name")))
+                .child(
+                    EditView::new()
+                        .on_submit(|s, _| save_queue(s))
+                        .with_name("queue_name"),
+                )
+                .child(TextView::new(" "));
+
+            if snapshots.is_empty() {
+                layout.add_child(TextView::new(t!("no-saved-queues")));
+            } else {
+                let mut list: SelectView<String> = SelectView::new();
+
+                for snapshot in snapshots {
+                    let label = format!(
+                        "{}  {} · {}",
+                        snapshot.name,
+                        snapshot.current_title(),
+                        snapshot.saved_at_label()
+                    );
+                    list.add_item(label, snapshot.name);
+                }
+
+                list.set_on_submit(|s: &mut Cursive, name: &String| {
+                    s.pop_layer();
+
+                    let name = name.clone();
+                    tokio::spawn(async move {
+                        if let Err(error) = player::load_queue(&name).await {
+                            show_message(error.to_string());
+                        }
+                    });
+                });
+
+                layout.add_child(list.scrollable());
+            }
+
+            let mut dialog = Dialog::around(layout)
+                .title(t!("saved-queues"))
+                .button(t!("save-queue"), save_queue)
+                .dismiss_button(t!("cancel"))
+                .min_width(40)
+                .wrap_with(OnEventView::new);
+
+            dialog.set_on_pre_event(Event::Key(Key::Esc), |s| {
+                s.pop_layer();
+            });
 
-        self.menubar();
-        self.global_events();
-        self.root.run();
-    }
+            s.add_layer(dialog);
+        }))
+        .expect("failed to send update");
+}
 
-    pub async fn sink(&self) -> &CbSink {
-        self.root.cb_sink()
+fn save_queue(s: &mut Cursive) {
+    let name = s
+        .call_on_name("queue_name", |view: &mut EditView| {
+            view.get_content().trim().to_string()
+        })
+        .unwrap_or_default();
+
+    if name.is_empty() {
+        return;
     }
+
+    s.pop_layer();
+
+    tokio::spawn(async move {
+        match player::save_queue(&name).await {
+            Ok(()) => show_message(t!("queue-saved", name = name)),
+            Err(error) => show_message(error.to_string()),
+        }
+    });
 }
 
-impl Default for CursiveUI {
-    fn default() -> Self {
-        Self::new()
-    }
+fn show_message(message: String) {
+    SINK.get()
+        .unwrap()
+        .send(Box::new(move |s| {
+            s.add_layer(Dialog::text(message).dismiss_button(t!("close")));
+        }))
+        .expect("failed to send update");
 }
 
-type ResultsPanel = ScrollView<NamedView<SelectView<(i32, Option<String>)>>>;
+async fn output_profiles() {
+    let profiles = db::get_output_profiles().await;
 
-fn load_search_results(item: &str, s: &mut Cursive) {
-    if let Some(mut search_results) = s.find_name::<SelectView>("search_results") {
-        search_results.clear();
+    SINK.get()
+        .unwrap()
+        .send(Box::new(move |s| {
+            if profiles.is_empty() {
+                s.add_layer(Dialog::text(t!("no-output-profiles")).dismiss_button(t!("close")));
+                return;
+            }
 
-        if let Some(data) = s.user_data::<SearchResults>() {
-            match item {
-                "Albums" => {
-                    for a in &data.albums {
-                        let id = if a.available {
-                            a.id.clone()
-                        } else {
-                            UNSTREAMABLE.to_string()
-                        };
+            let mut list: SelectView<OutputProfile> = SelectView::new();
 
-                        search_results.add_item(a.list_item(), id);
-                    }
+            for profile in profiles {
+                list.add_item(profile.name.clone(), profile);
+            }
 
-                    search_results.set_on_submit(move |_s: &mut Cursive, item: &String| {
-                        if item != UNSTREAMABLE {
-                            let item = item.clone();
-                            tokio::spawn(async move { player::play_album(&item).await });
-                        }
-                    });
-                }
-                "Artists" => {
-                    for a in &data.artists {
-                        search_results.add_item(a.name.clone(), a.id.to_string());
-                    }
+            list.set_on_submit(|s: &mut Cursive, profile: &OutputProfile| {
+                s.pop_layer();
 
-                    search_results.set_on_submit(move |s: &mut Cursive, item: &String| {
-                        submit_artist(s, item.parse::<i32>().expect("failed to parse string"));
-                    });
-                }
-                "Tracks" => {
-                    for t in &data.tracks {
-                        let id = if t.available {
-                            t.id.to_string()
-                        } else {
-                            UNSTREAMABLE.to_string()
-                        };
+                let profile = profile.clone();
+                tokio::spawn(async move {
+                    let name = profile.name.clone();
 
-                        search_results.add_item(t.list_item(), id)
+                    if player::set_output_profile(profile).await.is_ok() {
+                        db::set_config_value(ConfigKey::OutputProfile, Some(name)).await;
                     }
+                });
+            });
 
-                    search_results.set_on_submit(move |s: &mut Cursive, item: &String| {
-                        if item != UNSTREAMABLE {
-                            submit_track(
-                                s,
-                                (item.parse::<i32>().expect("failed to parse string"), None),
-                            );
-                        }
-                    });
+            let mut dialog = Dialog::around(list.scrollable())
+                .title(t!("output-profiles"))
+                .dismiss_button(t!("cancel"))
+                .wrap_with(OnEventView::new);
+
+            dialog.set_on_pre_event(Event::Key(Key::Esc), |s| {
+                s.pop_layer();
+            });
+
+            s.add_layer(dialog);
+        }))
+        .expect("failed to send update");
+}
+
+/// Pick the device to play through. The choice is saved, like the output profile.
+async fn output_devices() {
+    let devices = tokio::task::spawn_blocking(output::devices)
+        .await
+        .unwrap_or_default();
+    let active = config::settings().await.output_device;
+
+    SINK.get()
+        .unwrap()
+        .send(Box::new(move |s| {
+            if devices.is_empty() {
+                s.add_layer(Dialog::text(t!("no-output-devices")).dismiss_button(t!("close")));
+                return;
+            }
+
+            let mut list: SelectView<Option<String>> = SelectView::new();
+            list.add_item(t!("system-default"), None);
+
+            for device in devices {
+                let selected = active.as_ref() == Some(&device.sink);
+                list.add_item(device.name, Some(device.sink));
+
+                if selected {
+                    list.set_selection(list.len() - 1);
                 }
-                "Playlists" => {
-                    for p in &data.playlists {
-                        search_results.add_item(p.title.clone(), p.id.to_string())
+            }
+
+            list.set_on_submit(|s: &mut Cursive, device: &Option<String>| {
+                s.pop_layer();
+
+                let device = device.clone();
+                tokio::spawn(async move {
+                    if player::set_output_device(device.clone()).await.is_ok() {
+                        db::set_config_value(ConfigKey::OutputDevice, device).await;
                     }
+                });
+            });
 
-                    search_results.set_on_submit(move |s: &mut Cursive, item: &String| {
-                        let layout = submit_playlist(
-                            s,
-                            item.parse::<u32>().expect("failed to parse string"),
-                        );
+            let mut dialog = Dialog::around(list.scrollable())
+                .title(t!("output-devices"))
+                .dismiss_button(t!("cancel"))
+                .wrap_with(OnEventView::new);
 
-                        let event_panel =
-                            OnEventView::new(layout).on_event(Event::Key(Key::Esc), move |s| {
-                                s.screen_mut().pop_layer();
-                            });
+            dialog.set_on_pre_event(Event::Key(Key::Esc), |s| {
+                s.pop_layer();
+            });
 
-                        s.screen_mut().add_layer(Panel::new(event_panel));
-                    });
-                }
-                _ => {}
+            s.add_layer(dialog);
+        }))
+        .expect("failed to send update");
+}
+
+async fn reload_config() {
+    let message = match player::reload_config().await {
+        Ok(changed) if changed.is_empty() => t!("config-unchanged"),
+        Ok(changed) => {
+            let list = |keys: Vec<&ConfigKey>| {
+                keys.iter()
+                    .map(|k| k.to_string())
+                    .collect::<Vec<String>>()
+                    .join(", ")
+            };
+
+            let restart = changed
+                .iter()
+                .filter(|k| k.needs_restart())
+                .collect::<Vec<&ConfigKey>>();
+            let mut message = t!("config-reloaded", changed = list(changed.iter().collect()));
+
+            if !restart.is_empty() {
+                message.push('\n');
+                message.push_str(&t!("config-needs-restart", changed = list(restart)));
             }
+
+            message
         }
-    }
+        Err(error) => t!("config-reload-failed", error = error),
+    };
+
+    SINK.get()
+        .unwrap()
+        .send(Box::new(move |s| {
+            s.add_layer(Dialog::text(message).dismiss_button(t!("close")));
+        }))
+        .expect("failed to send update");
 }
 
-fn submit_playlist(_s: &mut Cursive, item: u32) -> LinearLayout {
-    let mut layout = LinearLayout::vertical();
+/// What picking a playlist in the track's playlists popup does.
+#[derive(Debug, Clone, Copy)]
+enum PlaylistEdit {
+    Remove(i64),
+    Add(i64),
+}
 
-    let playlist_tracks = block_on(async { player::playlist_tracks(item as i64).await });
+/// A popup with the user's playlists that contain the current track, to remove it from
+/// them or add it to another. It opens with what the index knows and is filled again
+/// once the playlists have been indexed anew, when they are due for it.
+fn track_playlists(s: &mut Cursive) {
+    let Some(track) = snapshot::track_list().current_track().cloned() else {
+        return;
+    };
 
-    let mut list = CursiveUI::results_list("playlist_items");
-    let mut playlist_items = list.get_inner_mut().get_mut();
+    let mut playlists = SelectView::<PlaylistEdit>::new();
 
-    for t in &playlist_tracks {
-        let mut row = StyledString::plain(format!("{:02} ", t.position));
+    let track_id = track.id;
+    playlists.set_on_submit(move |s: &mut Cursive, edit: &PlaylistEdit| {
+        s.pop_layer();
 
-        row.append(t.list_item());
+        let edit = *edit;
+        tokio::spawn(async move { edit_track_playlist(track_id, edit).await });
+    });
 
-        let track_id = if t.available { t.id as i32 } else { -1 };
+    let mut dialog = Dialog::around(playlists.with_name("track_playlists").scrollable())
+        .title(t!("track-playlists", title = track.display_title()))
+        .dismiss_button(t!("close"))
+        .wrap_with(OnEventView::new);
 
-        let value = if let Some(album) = &t.album {
-            let album_id = if album.available {
-                album.id.clone()
-            } else {
-                UNSTREAMABLE.to_string()
-            };
+    dialog.set_on_pre_event(Event::Key(Key::Esc), |s| {
+        s.pop_layer();
+    });
 
-            (track_id, Some(album_id))
-        } else {
-            (track_id, None)
-        };
+    s.add_layer(dialog);
 
-        playlist_items.add_item(row, value);
-    }
+    tokio::spawn(async move {
+        fill_track_playlists(track_id).await;
 
-    playlist_items.set_on_submit(move |s, item| {
-        submit_track(s, item.clone());
+        if library::refresh_playlists().await {
+            fill_track_playlists(track_id).await;
+        }
     });
+}
 
-    let meta = LinearLayout::horizontal()
-        .child(Button::new("play", move |_s| {
-            tokio::spawn(async move { player::play_playlist(item as i64).await });
+async fn fill_track_playlists(track_id: u32) {
+    let containing = library::playlists_containing(track_id).await;
+    let all = player::user_playlists().await;
+
+    SINK.get()
+        .unwrap()
+        .send(Box::new(move |s| {
+            s.call_on_name("track_playlists", |view: &mut SelectView<PlaylistEdit>| {
+                view.clear();
+
+                for entry in &containing {
+                    if let Ok(id) = entry.id.parse::<i64>() {
+                        view.add_item(
+                            t!("track-playlist-remove", title = entry.title.clone()),
+                            PlaylistEdit::Remove(id),
+                        );
+                    }
+                }
+
+                for playlist in &all {
+                    if !containing.iter().any(|e| e.id == playlist.id.to_string()) {
+                        view.add_item(
+                            t!("track-playlist-add", title = playlist.title.clone()),
+                            PlaylistEdit::Add(playlist.id as i64),
+                        );
+                    }
+                }
+            });
         }))
-        .child(
-            TextView::new(format!("total tracks: {}", playlist_tracks.len()))
-                .h_align(HAlign::Right)
-                .full_width(),
-        );
+        .expect("failed to send update");
+}
 
-    layout.add_child(meta);
-    layout.add_child(list);
+async fn edit_track_playlist(track_id: u32, edit: PlaylistEdit) {
+    let result = match edit {
+        PlaylistEdit::Add(playlist_id) => {
+            player::add_playlist_tracks(playlist_id, vec![track_id]).await
+        }
+        PlaylistEdit::Remove(playlist_id) => {
+            let entries = player::playlist(playlist_id)
+                .await
+                .map(|p| {
+                    p.tracks
+                        .values()
+                        .filter(|t| t.id == track_id)
+                        .filter_map(|t| t.playlist_track_id)
+                        .collect::<Vec<i64>>()
+                })
+                .unwrap_or_default();
 
-    layout
+            player::remove_playlist_tracks(playlist_id, entries).await
+        }
+    };
+
+    let playlist_id = match edit {
+        PlaylistEdit::Add(id) | PlaylistEdit::Remove(id) => id,
+    };
+
+    if let Some(playlist) = player::playlist(playlist_id).await {
+        library::index_playlist(&playlist).await;
+    }
+
+    let message = match result {
+        Ok(()) => t!("track-playlist-updated"),
+        Err(error) => error.to_string(),
+    };
+
+    SINK.get()
+        .unwrap()
+        .send(Box::new(move |s| {
+            s.add_layer(Dialog::text(message).dismiss_button(t!("close")));
+        }))
+        .expect("failed to send update");
 }
 
-fn submit_artist(s: &mut Cursive, item: i32) {
-    let artist_albums = block_on(async { player::artist_albums(item).await });
+/// Turn party mode on, or ask for the PIN to turn it off.
+fn party_mode(s: &mut Cursive) {
+    let show_error = |error: player::error::Error| {
+        SINK.get()
+            .unwrap()
+            .send(Box::new(move |s| {
+                s.add_layer(Dialog::text(error.to_string()).dismiss_button(t!("close")));
+            }))
+            .expect("failed to send update");
+    };
 
-    if !artist_albums.is_empty() {
-        let mut tree = cursive::menu::Tree::new();
+    if !party::is_enabled() {
+        let dialog = Dialog::text(t!("party-mode-prompt"))
+            .button(t!("yes"), move |s: &mut Cursive| {
+                s.pop_layer();
 
-        for a in artist_albums {
-            if !a.available {
-                continue;
-            }
+                tokio::spawn(async move {
+                    if let Err(error) = player::enable_party_mode().await {
+                        show_error(error);
+                    }
+                });
+            })
+            .dismiss_button(t!("no"));
 
-            tree.add_leaf(a.list_item(), move |s: &mut Cursive| {
-                let id = a.id.clone();
-                tokio::spawn(async move { player::play_album(&id).await });
+        s.add_layer(dialog);
+        return;
+    }
 
-                s.call_on_name(
-                    "screens",
-                    |screens: &mut ScreensView<ResizedView<LinearLayout>>| {
-                        screens.set_active_screen(0);
-                    },
-                );
+    let input = EditView::new()
+        .secret()
+        .on_submit(move |s: &mut Cursive, pin: &str| {
+            s.pop_layer();
+
+            let pin = pin.to_string();
+            tokio::spawn(async move {
+                if let Err(error) = player::disable_party_mode(&pin).await {
+                    show_error(error);
+                }
+            });
+        })
+        .fixed_width(12);
+
+    s.add_layer(
+        Dialog::around(input)
+            .title(t!("party-mode-pin"))
+            .dismiss_button(t!("cancel")),
+    );
+}
+
+/// A popup searching the favorites, playlists and history while typing.
+fn library_search(s: &mut Cursive) {
+    let results = SelectView::<Option<LibraryEntry>>::new()
+        .on_submit(|s: &mut Cursive, entry: &Option<LibraryEntry>| {
+            if let Some(entry) = entry.clone() {
+                s.pop_layer();
+                submit_library_entry(s, entry);
+            }
+        })
+        .with_name("library_results")
+        .scrollable()
+        .max_height(20);
+
+    let input = EditView::new()
+        .on_edit(|_, query: &str, _| {
+            let query = query.to_string();
+
+            tokio::spawn(async move {
+                let entries = library::search(&query).await;
+
+                SINK.get()
+                    .unwrap()
+                    .send(Box::new(move |s| fill_library_results(s, &query, entries)))
+                    .expect("failed to send update");
             });
+        })
+        .on_submit(|s: &mut Cursive, _: &str| {
+            s.focus_name("library_results").ok();
+        })
+        .with_name("library_query");
+
+    let layout = LinearLayout::new(Orientation::Vertical)
+        .child(input)
+        .child(results)
+        .fixed_width(72);
+
+    let mut dialog = Dialog::around(layout)
+        .title(t!("library-search"))
+        .dismiss_button(t!("close"))
+        .wrap_with(OnEventView::new);
+
+    dialog.set_on_pre_event(Event::Key(Key::Esc), |s| {
+        s.pop_layer();
+    });
+
+    s.add_layer(dialog);
+}
+
+fn fill_library_results(s: &mut Cursive, query: &str, entries: Vec<LibraryEntry>) {
+    // Answers to earlier keystrokes can arrive late, only show the latest.
+    let current = s.call_on_name("library_query", |view: &mut EditView| view.get_content());
+
+    if current.as_deref().map(|c| c.as_str()) != Some(query) {
+        return;
+    }
+
+    s.call_on_name(
+        "library_results",
+        |view: &mut SelectView<Option<LibraryEntry>>| {
+            view.clear();
+
+            if entries.is_empty() && !query.trim().is_empty() {
+                view.add_item(t!("library-no-results"), None);
+            }
+
+            for entry in entries {
+                let kind = match entry.kind {
+                    LibraryKind::Album => t!("library-album"),
+                    LibraryKind::Track => t!("library-track"),
+                    LibraryKind::Playlist => t!("library-playlist"),
+                };
+
+                let mut label = StyledString::styled(format!("{kind:<9}"), Effect::Dim);
+                label.append_plain(entry.label());
+
+                view.add_item(label, Some(entry));
+            }
+        },
+    );
+}
+
+fn submit_library_entry(s: &mut Cursive, entry: LibraryEntry) {
+    match entry.kind {
+        LibraryKind::Album => {
+            tokio::spawn(async move { player::play_album(&entry.id).await });
+        }
+        LibraryKind::Playlist => {
+            if let Ok(id) = entry.id.parse::<i64>() {
+                tokio::spawn(async move { player::play_playlist(id).await });
+            }
+        }
+        LibraryKind::Track => {
+            if let Ok(id) = entry.id.parse::<i32>() {
+                submit_track(s, (id, entry.album_id));
+            }
+            return;
         }
+    }
 
-        let album_list: MenuPopup = MenuPopup::new(Rc::new(tree));
+    s.call_on_name(
+        "screens",
+        |screens: &mut ScreensView<ResizedView<LinearLayout>>| {
+            screens.set_active_screen(0);
+        },
+    );
+}
 
-        let events = album_list
-            .scrollable()
-            .resized(SizeConstraint::Full, SizeConstraint::Free);
+/// Keep or remove the track being auditioned. Does nothing outside of an audition.
+fn audition_decide(keep: bool) {
+    if !audition::is_active() {
+        return;
+    }
 
-        s.screen_mut().add_layer(events);
+    tokio::spawn(async move {
+        if let Err(error) = audition::decide(keep).await {
+            debug!(?error);
+        }
+    });
+}
+
+/// Asks whether to remove the tracks picked during an audition from the playlist.
+fn confirm_audition(s: &mut Cursive, audition: Audition) {
+    let show_result = |message: String| {
+        SINK.get()
+            .unwrap()
+            .send(Box::new(move |s| {
+                s.add_layer(Dialog::text(message).dismiss_button(t!("close")));
+            }))
+            .expect("failed to send update");
+    };
+
+    if audition.removals.is_empty() {
+        tokio::spawn(audition::cancel());
+        s.add_layer(Dialog::text(t!("audition-all-kept")).dismiss_button(t!("close")));
+        return;
+    }
+
+    let mut content = StyledString::plain(t!("audition-confirm", count = audition.removals.len()));
+    content.append_plain("\n\n");
+
+    for track in &audition.removals {
+        content.append(track.list_item());
+        content.append_plain("\n");
     }
+
+    let dialog = Dialog::around(TextView::new(content).scrollable())
+        .title(t!("audition"))
+        .button(t!("audition-remove"), move |s: &mut Cursive| {
+            s.pop_layer();
+
+            tokio::spawn(async move {
+                match audition::apply().await {
+                    Ok(count) => show_result(t!("audition-removed", count = count)),
+                    Err(error) => show_result(error.to_string()),
+                }
+            });
+        })
+        .button(t!("cancel"), |s: &mut Cursive| {
+            s.pop_layer();
+            tokio::spawn(audition::cancel());
+        });
+
+    s.add_layer(dialog);
 }
 
 fn submit_track(s: &mut Cursive, item: (i32, Option<String>)) {
@@ -721,12 +3841,28 @@ fn submit_track(s: &mut Cursive, item: (i32, Option<String>)) {
         );
     };
 
-    let album = move |s: &mut Cursive| {
+    let album = move |s: &mut Cursive| {
+        s.screen_mut().pop_layer();
+
+        if let Some(album_id) = &item.1 {
+            let id = album_id.clone();
+            tokio::spawn(async move { player::play_album(&id).await });
+
+            s.call_on_name(
+                "screens",
+                |screens: &mut ScreensView<ResizedView<LinearLayout>>| {
+                    screens.set_active_screen(0);
+                },
+            );
+        }
+    };
+
+    let album_from_track = move |s: &mut Cursive| {
         s.screen_mut().pop_layer();
 
         if let Some(album_id) = &item.1 {
             let id = album_id.clone();
-            tokio::spawn(async move { player::play_album(&id).await });
+            tokio::spawn(async move { player::play_album_from_track(&id, item.0).await });
 
             s.call_on_name(
                 "screens",
@@ -737,10 +3873,11 @@ fn submit_track(s: &mut Cursive, item: (i32, Option<String>)) {
         }
     };
 
-    let mut album_or_track = Dialog::text("Track or album?")
-        .button("Track", track)
-        .button("Album", album)
-        .dismiss_button("Cancel")
+    let mut album_or_track = Dialog::text(t!("track-or-album"))
+        .button(t!("track"), track)
+        .button(t!("album"), album)
+        .button(t!("album-from-track"), album_from_track)
+        .dismiss_button(t!("cancel"))
         .wrap_with(OnEventView::new);
 
     album_or_track.set_on_pre_event(Event::Key(Key::Esc), |s| {
@@ -750,6 +3887,56 @@ fn submit_track(s: &mut Cursive, item: (i32, Option<String>)) {
     s.screen_mut().add_layer(album_or_track);
 }
 
+fn fill_track_list(s: &mut Cursive, list: &TrackListValue) {
+    QUEUE_GENERATION.store(list.generation(), Ordering::Relaxed);
+
+    if let Some(mut list_view) = s.find_name::<ScrollView<SelectView<usize>>>("current_track_list")
+    {
+        list_view.get_inner_mut().clear();
+
+        list.unplayed_tracks().iter().for_each(|i| {
+            list_view.get_inner_mut().add_item(
+                i.track_list_item(list.list_type(), false),
+                i.position as usize,
+            );
+        });
+
+        list.played_tracks().iter().for_each(|i| {
+            list_view.get_inner_mut().add_item(
+                i.track_list_item(list.list_type(), true),
+                i.position as usize,
+            );
+        });
+    }
+}
+
+/// Reflow the views that depend on the terminal size.
+fn on_resize(s: &mut Cursive) {
+    s.clear();
+    layout::set_screen_width(s.screen_size().x);
+
+    for name in ["current_track_title_scroll", "entity_title_scroll"] {
+        s.call_on_name(name, |view: &mut ScrollView<NamedView<TextView>>| {
+            view.scroll_to_left();
+        });
+    }
+
+    // Dialogs keep the position they were added at, put them back in the middle.
+    let screen = s.screen_mut();
+    let first = if ENTER_URL_OPEN.load(Ordering::Relaxed) {
+        2
+    } else {
+        1
+    };
+
+    for i in first..screen.len() {
+        screen.reposition_layer(LayerPosition::FromBack(i), Position::center());
+    }
+
+    // The track list rows are padded to the screen width, rebuild them.
+    fill_track_list(s, &snapshot::track_list());
+}
+
 fn set_current_track(s: &mut Cursive, track: &Track, lt: &TrackListType) {
     if let (Some(mut track_num), Some(mut track_title), Some(mut progress)) = (
         s.find_name::<TextView>("current_track_number"),
@@ -771,14 +3958,21 @@ fn set_current_track(s: &mut Cursive, track: &Track, lt: &TrackListType) {
             }
         };
 
-        track_title.set_content(track.title.trim());
+        track_title.set_content(track.display_title());
         progress.set_max(track.duration_seconds as usize);
     }
 
+    s.call_on_name("simple_title", |view: &mut TextView| {
+        view.set_content(track.display_title());
+    });
+
     if let Some(artist) = &track.artist {
         s.call_on_name("artist_name", |view: &mut TextView| {
             view.set_content(artist.name.clone());
         });
+        s.call_on_name("simple_artist", |view: &mut TextView| {
+            view.set_content(artist.name.clone());
+        });
     }
 
     if let (Some(mut bit_depth), Some(mut sample_rate)) = (
@@ -788,6 +3982,272 @@ fn set_current_track(s: &mut Cursive, track: &Track, lt: &TrackListType) {
         bit_depth.set_content(format!("{} bits", track.bit_depth));
         sample_rate.set_content(format!("{} kHz", track.sampling_rate));
     }
+
+    // The service streams the track below the quality asked for.
+    let fallback = track
+        .fallback_quality
+        .as_ref()
+        .and_then(|q| q.to_possible_value());
+
+    if let Some(quality) = &fallback {
+        s.call_on_name("fallback_quality", |view: &mut TextView| {
+            view.set_content(t!("quality-fallback", quality = quality.get_name()));
+        });
+    }
+
+    s.call_on_name(
+        "fallback_quality_row",
+        |view: &mut HideableView<NamedView<TextView>>| {
+            view.set_visible(fallback.is_some());
+        },
+    );
+
+    let cover_art = track
+        .cover_art
+        .clone()
+        .or_else(|| track.album.as_ref().map(|a| a.cover_art.clone()))
+        .filter(|url| !url.is_empty());
+
+    show_cover(s, cover_art);
+    show_lyrics(s, track);
+}
+
+/// Show the cover at `url` next to the track, downloading it in the background.
+fn show_cover(s: &mut Cursive, url: Option<String>) {
+    if artwork::protocol() == Protocol::None || url == cover::url() {
+        return;
+    }
+
+    cover::set(url.clone(), None);
+    s.call_on_name(
+        "cover_row",
+        |view: &mut HideableView<PaddedView<CoverView>>| {
+            view.set_visible(false);
+        },
+    );
+
+    let Some(url) = url else {
+        return;
+    };
+
+    tokio::spawn(async move {
+        let image = artwork::load(&url).await;
+
+        SINK.get()
+            .unwrap()
+            .send(Box::new(move |s| {
+                // Another track may have started while this one loaded.
+                if cover::url().as_ref() != Some(&url) {
+                    return;
+                }
+
+                cover::set(Some(url), image);
+                let loaded = cover::is_loaded();
+
+                s.call_on_name(
+                    "cover_row",
+                    |view: &mut HideableView<PaddedView<CoverView>>| {
+                        view.set_visible(loaded);
+                    },
+                );
+            }))
+            .expect("failed to send update");
+    });
+}
+
+/// Show the lyrics of `track` next to the track list, looking them up in the background.
+fn show_lyrics(s: &mut Cursive, track: &Track) {
+    {
+        let mut lyrics = LYRICS.lock().expect("failed to lock lyrics");
+
+        if lyrics.0 == Some(track.id) {
+            return;
+        }
+
+        *lyrics = (Some(track.id), None);
+    }
+
+    s.call_on_name(
+        "lyrics_panel",
+        |view: &mut HideableView<Panel<NamedView<ScrollView<NamedView<SelectView<usize>>>>>>| {
+            view.set_visible(false);
+        },
+    );
+
+    let track = track.clone();
+
+    tokio::spawn(async move {
+        let Some(found) = lyrics::lyrics(&track).await else {
+            return;
+        };
+
+        SINK.get()
+            .unwrap()
+            .send(Box::new(move |s| {
+                {
+                    let mut lyrics = LYRICS.lock().expect("failed to lock lyrics");
+
+                    // Another track may have started while these were looked up.
+                    if lyrics.0 != Some(track.id) {
+                        return;
+                    }
+
+                    lyrics.1 = Some(found.clone());
+                }
+
+                s.call_on_name("lyrics", |view: &mut SelectView<usize>| {
+                    view.clear();
+
+                    if found == Lyrics::Instrumental {
+                        view.add_item(t!("lyrics-instrumental"), 0);
+                    }
+
+                    for (index, line) in found.lines().into_iter().enumerate() {
+                        view.add_item(line, index);
+                    }
+                });
+                s.call_on_name(
+                    "lyrics_panel",
+                    |view: &mut HideableView<
+                        Panel<NamedView<ScrollView<NamedView<SelectView<usize>>>>>,
+                    >| {
+                        view.set_visible(true);
+                    },
+                );
+            }))
+            .expect("failed to send update");
+    });
+}
+
+/// Select the line of the synced lyrics being sung at `position` and scroll it into view.
+fn follow_lyrics(s: &mut Cursive, position: ClockTime) {
+    let line = LYRICS
+        .lock()
+        .expect("failed to lock lyrics")
+        .1
+        .as_ref()
+        .and_then(|lyrics| lyrics.current_line(Duration::from_nanos(position.nseconds())));
+
+    let Some(line) = line else {
+        return;
+    };
+
+    let moved = s
+        .call_on_name("lyrics", |view: &mut SelectView<usize>| {
+            if view.selected_id() == Some(line) {
+                return false;
+            }
+
+            view.set_selection(line);
+            true
+        })
+        .unwrap_or_default();
+
+    if moved {
+        s.call_on_name(
+            "lyrics_scroll",
+            |view: &mut ScrollView<NamedView<SelectView<usize>>>| {
+                view.scroll_to_important_area();
+            },
+        );
+    }
+}
+
+fn format_remote_clients(clients: &[RemoteClient]) -> StyledString {
+    if clients.is_empty() {
+        return StyledString::plain(t!("no-remote-clients"));
+    }
+
+    let mut list = StyledString::new();
+
+    for client in clients {
+        let connected_at = chrono::DateTime::from_timestamp(client.connected_at, 0)
+            .map(|d| {
+                d.with_timezone(&chrono::Local)
+                    .format("%H:%M:%S")
+                    .to_string()
+            })
+            .unwrap_or_default();
+
+        list.append_styled(
+            client
+                .name
+                .clone()
+                .unwrap_or_else(|| format!("#{}", client.id)),
+            Effect::Bold,
+        );
+        list.append_plain(format!(" {} ", client.address));
+        list.append_styled(connected_at, Effect::Dim);
+
+        if !client.topics.is_empty() {
+            list.append_styled(format!(" [{}]", client.topics.join(", ")), Effect::Dim);
+        }
+
+        list.append_plain("\n");
+    }
+
+    list
+}
+
+/// Draws one meter per channel, scaled from -60dB to 0dB.
+fn vu_meter(rms: &[f64], peak: &[f64], width: usize) -> StyledString {
+    let mut meter = StyledString::new();
+    let labels = ["L", "R"];
+
+    for (channel, value) in rms.iter().enumerate() {
+        let label = labels.get(channel).unwrap_or(&"-");
+        let scale = |db: f64| (((db + 60.) / 60.).clamp(0., 1.) * width as f64) as usize;
+
+        let filled = scale(*value);
+        let peak_at = peak.get(channel).map(|p| scale(*p)).unwrap_or(filled);
+
+        if channel > 0 {
+            meter.append_plain("\n");
+        }
+
+        meter.append_styled(format!("{label} "), Effect::Dim);
+        meter.append_plain("\u{2588}".repeat(filled));
+
+        if peak_at > filled {
+            meter.append_plain(" ".repeat(peak_at - filled - 1));
+            meter.append_styled("|", Effect::Bold);
+        }
+    }
+
+    meter
+}
+
+/// Draws one bar per band using block characters.
+fn spectrum_bars(magnitudes: &[f32]) -> String {
+    let threshold = player::visualization::SPECTRUM_THRESHOLD as f32;
+    let steps = (SPECTRUM_BLOCKS.len() - 1) as f32;
+
+    magnitudes
+        .iter()
+        .map(|m| {
+            let level = ((m - threshold) / -threshold).clamp(0., 1.);
+            let c = SPECTRUM_BLOCKS[(level * steps).round() as usize];
+
+            format!("{c}{c}")
+        })
+        .collect::<String>()
+}
+
+/// The bitrate, buffer fill and data used, on one line.
+fn stats_line(stats: &StreamStats) -> String {
+    let bitrate = stats
+        .bitrate
+        .map(|bitrate| t!("stats-bitrate", kbps = bitrate / 1000))
+        .unwrap_or_else(|| "-".to_string());
+
+    t!(
+        "stats-line",
+        bitrate = bitrate,
+        buffer = stats.buffer_percent,
+        rate = stats.download_rate / 1000,
+        track = session::format_bytes(stats.track_bytes),
+        session = session::format_bytes(stats.session_bytes)
+    )
 }
 
 fn get_state_icon(state: GstState) -> String {
@@ -809,11 +4269,22 @@ fn get_state_icon(state: GstState) -> String {
 }
 
 pub async fn receive_notifications() {
-    let mut receiver = player::notify_receiver();
+    let mut receiver = player::notify_receiver().merge(player::visualization_receiver());
+    // The status of an outage and when the service is checked on next, counted down here as
+    // the player only tells when that changes.
+    let mut outage: Option<(u16, Instant)> = None;
+    let mut countdown = tokio::time::interval(Duration::from_secs(1));
 
     loop {
         select! {
+            _ = countdown.tick(), if outage.is_some() => {
+                if let Some((status, retry_at)) = outage {
+                    show_outage(status, retry_at);
+                }
+            }
             Some(notification) = receiver.next() => {
+                snapshot::update(&notification);
+
                 match notification {
                     Notification::Quit => {
                         debug!("exiting tui notification thread");
@@ -834,6 +4305,10 @@ pub async fn receive_notifications() {
                         SINK.get()
                             .unwrap()
                             .send(Box::new(move |s| {
+                                s.call_on_name("simple_status", |view: &mut TextView| {
+                                    view.set_content(get_state_icon(status));
+                                });
+
                                 if let Some(mut view) = s.find_name::<TextView>("player_status") {
                                     view.set_content(get_state_icon(status));
                                     match status {
@@ -860,36 +4335,49 @@ pub async fn receive_notifications() {
                                 if let Some(mut progress) = s.find_name::<ProgressBar>("progress") {
                                     progress.set_value(clock.seconds() as usize);
                                 }
+
+                                follow_lyrics(s, clock);
                             }))
                             .expect("failed to send update");
                     }
+                    Notification::ListProgress { progress } => {
+                        SINK.get().unwrap().send(Box::new(move |s| {
+                            s.call_on_name("list_progress", |view: &mut TextView| {
+                                view.set_content(t!(
+                                    "list-progress",
+                                    track = progress.track,
+                                    total = progress.total_tracks,
+                                    elapsed = service::format_seconds(progress.elapsed_seconds),
+                                    duration = service::format_seconds(progress.duration_seconds)
+                                ));
+                            });
+                            s.call_on_name(
+                                "list_progress_row",
+                                |view: &mut HideableView<NamedView<TextView>>| {
+                                    view.set_visible(true);
+                                },
+                            );
+                        })).expect("failed to send update");
+                    }
                     Notification::CurrentTrackList { list } => {
+                        let show_progress =
+                            matches!(list.list_type(), TrackListType::Album | TrackListType::Playlist);
+
+                        SINK.get().unwrap().send(Box::new(move |s| {
+                            s.call_on_name(
+                                "list_progress_row",
+                                |view: &mut HideableView<NamedView<TextView>>| {
+                                    view.set_visible(show_progress);
+                                },
+                            );
+                        })).expect("failed to send update");
+
                         match list.list_type() {
                             TrackListType::Album => {
                                 SINK.get()
                                     .unwrap()
                                     .send(Box::new(move |s| {
-                                        if let Some(mut list_view) = s
-                                            .find_name::<ScrollView<SelectView<usize>>>(
-                                                "current_track_list",
-                                            )
-                                        {
-                                            list_view.get_inner_mut().clear();
-
-                                            list.unplayed_tracks().iter().for_each(|i| {
-                                                list_view.get_inner_mut().add_item(
-                                                    i.track_list_item(list.list_type(), false),
-                                                    i.position as usize,
-                                                );
-                                            });
-
-                                            list.played_tracks().iter().for_each(|i| {
-                                                list_view.get_inner_mut().add_item(
-                                                    i.track_list_item(list.list_type(), true),
-                                                    i.position as usize,
-                                                );
-                                            });
-                                        }
+                                        fill_track_list(s, &list);
                                         if let (
                                             Some(album),
                                             Some(mut entity_title),
@@ -899,7 +4387,7 @@ pub async fn receive_notifications() {
                                             s.find_name::<TextView>("entity_title"),
                                             s.find_name::<TextView>("total_tracks"),
                                         ) {
-                                            let mut title = StyledString::plain(album.title.clone());
+                                            let mut title = StyledString::plain(album.display_title());
                                             title.append_plain(" ");
                                             title.append_styled(
                                                 format!("({})", album.release_year),
@@ -924,27 +4412,7 @@ pub async fn receive_notifications() {
                                 SINK.get()
                                     .unwrap()
                                     .send(Box::new(move |s| {
-                                        if let Some(mut list_view) = s
-                                            .find_name::<ScrollView<SelectView<usize>>>(
-                                                "current_track_list",
-                                            )
-                                        {
-                                            list_view.get_inner_mut().clear();
-
-                                            list.unplayed_tracks().iter().for_each(|i| {
-                                                list_view.get_inner_mut().add_item(
-                                                    i.track_list_item(list.list_type(), false),
-                                                    i.position as usize,
-                                                );
-                                            });
-
-                                            list.played_tracks().iter().for_each(|i| {
-                                                list_view.get_inner_mut().add_item(
-                                                    i.track_list_item(list.list_type(), true),
-                                                    i.position as usize,
-                                                );
-                                            });
-                                        }
+                                        fill_track_list(s, &list);
                                         if let (
                                             Some(playlist),
                                             Some(mut entity_title),
@@ -986,7 +4454,7 @@ pub async fn receive_notifications() {
                                         if let (Some(album), Some(mut entity_title)) =
                                             (list.get_album(), s.find_name::<TextView>("entity_title"))
                                         {
-                                            entity_title.set_content(album.title.trim());
+                                            entity_title.set_content(album.display_title());
                                         }
                                         if let Some(mut total_tracks) =
                                             s.find_name::<TextView>("total_tracks")
@@ -1038,12 +4506,247 @@ pub async fn receive_notifications() {
                         })).expect("failed to send update");
                     }
                     Notification::Error { error: _ } => {}
+                    Notification::ClientConnected { client: _ } | Notification::ClientDisconnected { client: _ } => {
+                        let clients = websocket::clients().await;
+
+                        SINK.get().unwrap().send(Box::new(move |s| {
+                            s.call_on_name("remote_clients", |view: &mut TextView| {
+                                view.set_content(format_remote_clients(&clients));
+                            });
+                        })).expect("failed to send update");
+                    }
+                    Notification::ReleaseAvailable { album } => {
+                        SINK.get().unwrap().send(Box::new(move |s| {
+                            let message = t!(
+                                "release-available",
+                                title = album.title,
+                                artist = album.artist.name
+                            );
+                            let id = album.id.clone();
+
+                            let dialog = Dialog::text(message)
+                                .button(t!("play"), move |s: &mut Cursive| {
+                                    s.pop_layer();
+
+                                    let id = id.clone();
+                                    tokio::spawn(async move { player::play_album(&id).await });
+
+                                    s.set_screen(0);
+                                })
+                                .dismiss_button(t!("close"));
+
+                            s.add_layer(dialog);
+                        })).expect("failed to send update");
+                    }
+                    Notification::TrackAbandoned { track, attempts, errors, abandoned } => {
+                        SINK.get().unwrap().send(Box::new(move |s| {
+                            let message = t!(
+                                "track-abandoned",
+                                title = track.title,
+                                attempts = attempts,
+                                errors = errors,
+                                abandoned = abandoned
+                            );
+
+                            s.add_layer(Dialog::text(message).dismiss_button(t!("close")));
+                        })).expect("failed to send update");
+                    }
+                    Notification::TrackUnavailable { track } => {
+                        SINK.get().unwrap().send(Box::new(move |s| {
+                            let message = if track.status == TrackStatus::Unavailable {
+                                t!("track-blocked", title = track.title)
+                            } else {
+                                t!("track-unavailable", title = track.title)
+                            };
+
+                            s.add_layer(Dialog::text(message).dismiss_button(t!("close")));
+                        })).expect("failed to send update");
+                    }
+                    Notification::OutputProfileChanged { profile } => {
+                        SINK.get().unwrap().send(Box::new(move |s| {
+                            s.call_on_name("player_panel", |panel: &mut Panel<LinearLayout>| {
+                                panel.set_title(format!("{} · {}", t!("player-panel"), profile.name));
+                            });
+                        })).expect("failed to send update");
+                    }
+                    Notification::Volume { .. } => {
+                        SINK.get().unwrap().send(Box::new(update_modes)).expect("failed to send update");
+                    }
+                    Notification::Shuffle { enabled } => {
+                        SHUFFLE.store(enabled, Ordering::Relaxed);
+                        SINK.get().unwrap().send(Box::new(update_modes)).expect("failed to send update");
+                    }
+                    Notification::GaplessPrepFailed { .. } => {}
+                    // The TUI is already gone when the player quits, the summary is printed after it.
+                    Notification::SessionSummary { .. } => {}
+                    Notification::NetworkChanged { .. } => {}
+                    Notification::Crossfade { .. } => {}
+                    Notification::SimpleMode { enabled } => {
+                        if !enabled {
+                            SINK.get()
+                                .unwrap()
+                                .send(Box::new(leave_simple_mode))
+                                .expect("failed to send update");
+                        }
+                    }
+                    Notification::PartyMode { enabled } => {
+                        SINK.get().unwrap().send(Box::new(move |s| {
+                            s.call_on_name("party_row", |view: &mut HideableView<TextView>| {
+                                view.set_visible(enabled);
+                            });
+                        })).expect("failed to send update");
+                    }
+                    Notification::Audition { audition } => {
+                        SINK.get().unwrap().send(Box::new(move |s| {
+                            let visible = audition.is_some();
+
+                            if let Some(audition) = audition {
+                                s.call_on_name("audition", |view: &mut TextView| {
+                                    view.set_content(t!(
+                                        "audition-indicator",
+                                        count = audition.removals.len()
+                                    ));
+                                });
+                            }
+
+                            s.call_on_name(
+                                "audition_row",
+                                |view: &mut HideableView<NamedView<TextView>>| {
+                                    view.set_visible(visible);
+                                },
+                            );
+                        })).expect("failed to send update");
+                    }
+                    Notification::AuditionFinished { audition } => {
+                        SINK.get().unwrap().send(Box::new(move |s| {
+                            confirm_audition(s, audition);
+                        })).expect("failed to send update");
+                    }
+                    Notification::ConfigReloaded { changed, .. } => {
+                        if changed.contains(&ConfigKey::Theme) {
+                            let theme = config::settings().await.theme;
+
+                            SINK.get().unwrap().send(Box::new(move |s| {
+                                s.set_theme(build_theme(theme));
+                            })).expect("failed to send update");
+                        }
+
+                        if changed.contains(&ConfigKey::Autoplay) {
+                            SINK.get().unwrap().send(Box::new(update_modes)).expect("failed to send update");
+                        }
+                    }
+                    Notification::AutoplayAppended { .. } => {}
+                    Notification::AccountLimited { limited_to } => {
+                        SINK.get().unwrap().send(Box::new(move |s| {
+                            let visible = limited_to.is_some();
+
+                            if let Some(quality) = limited_to.and_then(|q| q.to_possible_value()) {
+                                s.call_on_name("account", |view: &mut TextView| {
+                                    view.set_content(t!(
+                                        "account-limited",
+                                        quality = quality.get_name()
+                                    ));
+                                });
+                            }
+
+                            s.call_on_name(
+                                "account_row",
+                                |view: &mut HideableView<NamedView<TextView>>| {
+                                    view.set_visible(visible);
+                                },
+                            );
+                        })).expect("failed to send update");
+                    }
+                    Notification::ServiceOutage { status, retry_in } => {
+                        outage = status
+                            .map(|status| (status, Instant::now() + Duration::from_secs(retry_in)));
+
+                        if let Some((status, retry_at)) = outage {
+                            show_outage(status, retry_at);
+                        }
+
+                        SINK.get().unwrap().send(Box::new(move |s| {
+                            s.call_on_name(
+                                "outage_row",
+                                |view: &mut HideableView<NamedView<TextView>>| {
+                                    view.set_visible(status.is_some());
+                                },
+                            );
+                        })).expect("failed to send update");
+                    }
+                    Notification::SignedIn => {
+                        let playlists = player::user_playlists().await;
+
+                        SINK.get().unwrap().send(Box::new(move |s| {
+                            s.call_on_name("user_playlists", |view: &mut SelectView<u32>| {
+                                fill_user_playlists(view, playlists);
+                            });
+                        })).expect("failed to send update");
+                    }
+                    Notification::Offline { offline } => {
+                        SINK.get().unwrap().send(Box::new(move |s| {
+                            s.call_on_name("offline_row", |view: &mut HideableView<TextView>| {
+                                view.set_visible(offline);
+                            });
+                        })).expect("failed to send update");
+                    }
+                    Notification::Level { rms, peak } => {
+                        if !SHOW_VISUALIZATION.load(Ordering::Relaxed) {
+                            continue;
+                        }
+
+                        SINK.get().unwrap().send(Box::new(move |s| {
+                            let width = s.screen_size().x.saturating_sub(6);
+
+                            s.call_on_name("visualization_row", |view: &mut HideableView<NamedView<TextView>>| {
+                                view.set_visible(true);
+                                view.get_inner_mut().get_mut().set_content(vu_meter(&rms, &peak, width));
+                            });
+                        })).expect("failed to send update");
+                    }
+                    Notification::Stats { stats } => {
+                        if !SHOW_STATS.load(Ordering::Relaxed) {
+                            continue;
+                        }
+
+                        SINK.get().unwrap().send(Box::new(move |s| {
+                            s.call_on_name("stats", |view: &mut TextView| {
+                                view.set_content(stats_line(&stats));
+                            });
+                        })).expect("failed to send update");
+                    }
+                    Notification::Spectrum { magnitudes } => {
+                        if !SHOW_VISUALIZATION.load(Ordering::Relaxed) {
+                            continue;
+                        }
+
+                        SINK.get().unwrap().send(Box::new(move |s| {
+                            s.call_on_name("visualization_row", |view: &mut HideableView<NamedView<TextView>>| {
+                                view.set_visible(true);
+                                view.get_inner_mut().get_mut().set_content(spectrum_bars(&magnitudes));
+                            });
+                        })).expect("failed to send update");
+                    }
                 }
             }
         }
     }
 }
 
+/// Show the outage with the seconds left until the service is checked on again.
+fn show_outage(status: u16, retry_at: Instant) {
+    let seconds = retry_at.saturating_duration_since(Instant::now()).as_secs();
+
+    SINK.get()
+        .unwrap()
+        .send(Box::new(move |s| {
+            s.call_on_name("outage", |view: &mut TextView| {
+                view.set_content(t!("service-outage", status = status, seconds = seconds));
+            });
+        }))
+        .expect("failed to send update");
+}
+
 pub trait CursiveFormat {
     fn list_item(&self) -> StyledString;
     fn track_list_item(&self, _list_type: &TrackListType, _inactive: bool) -> StyledString {
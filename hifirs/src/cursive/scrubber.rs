@@ -0,0 +1,129 @@
+use crate::player;
+use cursive::{
+    direction::Direction,
+    event::{Event, EventResult, Key, MouseButton, MouseEvent},
+    view::{CannotFocus, View, ViewWrapper},
+    wrap_impl, Vec2,
+};
+use gstreamer::ClockTime;
+use std::sync::{Arc, Mutex};
+
+/// Seconds an arrow key moves the seek target.
+const STEP_SECONDS: u64 = 5;
+
+/// Where a seek would go, shown in the progress bar until it is confirmed or cancelled.
+#[derive(Debug, Clone, Default)]
+pub struct Preview(Arc<Mutex<Option<ClockTime>>>);
+
+impl Preview {
+    pub fn get(&self) -> Option<ClockTime> {
+        *self.0.lock().unwrap()
+    }
+
+    fn set(&self, target: Option<ClockTime>) {
+        *self.0.lock().unwrap() = target;
+    }
+
+    fn take(&self) -> Option<ClockTime> {
+        self.0.lock().unwrap().take()
+    }
+
+    /// The label of the progress bar: position and duration, and the target while picking one.
+    pub fn label(&self, value: usize, max: usize) -> String {
+        let time = |seconds: u64| ClockTime::from_seconds(seconds).to_string()[2..7].to_string();
+        let label = format!("{} / {}", time(value as u64), time(max as u64));
+
+        match self.get() {
+            Some(target) => format!("{label}  → {}", time(target.seconds())),
+            None => label,
+        }
+    }
+}
+
+/// Makes the progress bar it wraps seekable. The arrow keys, Home, End or a click pick a
+/// target, Enter seeks there and Esc forgets it.
+pub struct Scrubber<V> {
+    view: V,
+    preview: Preview,
+    width: usize,
+}
+
+impl<V> Scrubber<V> {
+    pub fn new(view: V, preview: Preview) -> Self {
+        Self {
+            view,
+            preview,
+            width: 0,
+        }
+    }
+
+    /// Show `target` as the seek target, kept within the track.
+    fn pick(&self, target: ClockTime, duration: ClockTime) -> EventResult {
+        self.preview.set(Some(target.min(duration)));
+        EventResult::Consumed(None)
+    }
+}
+
+impl<V: View> ViewWrapper for Scrubber<V> {
+    wrap_impl!(self.view: V);
+
+    fn wrap_take_focus(&mut self, _source: Direction) -> Result<EventResult, CannotFocus> {
+        Ok(EventResult::Consumed(None))
+    }
+
+    fn wrap_layout(&mut self, size: Vec2) {
+        self.width = size.x;
+        self.view.layout(size);
+    }
+
+    fn wrap_on_event(&mut self, event: Event) -> EventResult {
+        let Some(duration) = player::duration() else {
+            return EventResult::Ignored;
+        };
+        let current = self
+            .preview
+            .get()
+            .or_else(player::position)
+            .unwrap_or_default();
+        let step = ClockTime::from_seconds(STEP_SECONDS);
+
+        match event {
+            Event::Key(Key::Left) => self.pick(current.saturating_sub(step), duration),
+            Event::Key(Key::Right) => self.pick(current + step, duration),
+            Event::Key(Key::Home) => self.pick(ClockTime::ZERO, duration),
+            Event::Key(Key::End) => self.pick(duration, duration),
+            Event::Key(Key::Enter) => match self.preview.take() {
+                Some(target) => {
+                    tokio::spawn(async move {
+                        if let Err(error) = player::seek_to(target).await {
+                            debug!(?error);
+                        }
+                    });
+
+                    EventResult::Consumed(None)
+                }
+                None => EventResult::Ignored,
+            },
+            Event::Key(Key::Esc) => match self.preview.take() {
+                Some(_) => EventResult::Consumed(None),
+                None => EventResult::Ignored,
+            },
+            Event::Mouse {
+                offset,
+                position,
+                event: MouseEvent::Press(MouseButton::Left),
+            } => match position.checked_sub(offset) {
+                Some(click) if self.width > 0 && click.x < self.width => {
+                    let fraction = click.x as f64 / (self.width - 1).max(1) as f64;
+
+                    self.pick(
+                        ClockTime::from_nseconds((duration.nseconds() as f64 * fraction) as u64),
+                        duration,
+                    )
+                }
+                _ => EventResult::Ignored,
+            },
+            _ => EventResult::Ignored,
+        }
+    }
+}
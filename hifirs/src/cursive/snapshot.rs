@@ -0,0 +1,57 @@
+use crate::player::{self, notification::Notification, queue::TrackListValue};
+use gstreamer::State as GstState;
+use once_cell::sync::Lazy;
+use std::sync::RwLock;
+
+static SNAPSHOT: Lazy<RwLock<Snapshot>> = Lazy::new(|| RwLock::new(Snapshot::default()));
+
+/// The TUI's copy of the player state, kept current from the notifications so views
+/// can read it while they draw without waiting on the queue or the database.
+#[derive(Debug, Clone)]
+struct Snapshot {
+    status: GstState,
+    list: TrackListValue,
+}
+
+impl Default for Snapshot {
+    fn default() -> Self {
+        Self {
+            status: GstState::Null,
+            list: TrackListValue::new(None),
+        }
+    }
+}
+
+/// Fill the snapshot before the TUI starts, later changes arrive through [`update`].
+pub async fn init() {
+    let list = player::current_tracklist().await;
+    let mut snapshot = SNAPSHOT.write().expect("failed to lock snapshot");
+
+    snapshot.status = player::current_state();
+    snapshot.list = list;
+}
+
+/// Apply a notification to the snapshot.
+pub fn update(notification: &Notification) {
+    match notification {
+        Notification::Status { status } => {
+            SNAPSHOT.write().expect("failed to lock snapshot").status = *status;
+        }
+        Notification::CurrentTrackList { list } => {
+            SNAPSHOT.write().expect("failed to lock snapshot").list = list.clone();
+        }
+        _ => {}
+    }
+}
+
+pub fn status() -> GstState {
+    SNAPSHOT.read().expect("failed to lock snapshot").status
+}
+
+pub fn track_list() -> TrackListValue {
+    SNAPSHOT
+        .read()
+        .expect("failed to lock snapshot")
+        .list
+        .clone()
+}
@@ -0,0 +1,107 @@
+use crate::{
+    library, player,
+    service::{Album, Artist, Track},
+    sql::db,
+};
+use clap::ValueEnum;
+use serde::{Deserialize, Serialize};
+use std::{collections::HashSet, fmt::Display};
+
+/// How the favorite albums are ordered.
+#[derive(Default, Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, ValueEnum)]
+#[serde(rename_all = "kebab-case")]
+pub enum FavoriteSort {
+    /// Most recently favorited first.
+    #[default]
+    RecentlyAdded,
+    /// Alphabetically by artist, then by release year.
+    Artist,
+    /// Newest releases first.
+    ReleaseYear,
+}
+
+impl Display for FavoriteSort {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            FavoriteSort::RecentlyAdded => f.write_str("recently-added"),
+            FavoriteSort::Artist => f.write_str("artist"),
+            FavoriteSort::ReleaseYear => f.write_str("release-year"),
+        }
+    }
+}
+
+#[derive(Default, Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct FavoriteFilter {
+    pub hires_only: bool,
+    pub unplayed_only: bool,
+}
+
+/// The user's favorite albums, tracks and artists along with the ids of albums
+/// that have been played.
+#[derive(Default, Debug, Clone)]
+pub struct Favorites {
+    pub albums: Vec<Album>,
+    pub tracks: Vec<Track>,
+    pub artists: Vec<Artist>,
+    pub played: HashSet<String>,
+}
+
+impl Favorites {
+    /// Returns the albums matching `filter`, ordered by `sort`.
+    pub fn arrange(&self, sort: FavoriteSort, filter: FavoriteFilter) -> Vec<&Album> {
+        let mut albums = self
+            .albums
+            .iter()
+            .filter(|a| !filter.hires_only || a.hires_available)
+            .filter(|a| !filter.unplayed_only || !self.played.contains(&a.id))
+            .collect::<Vec<&Album>>();
+
+        match sort {
+            FavoriteSort::RecentlyAdded => {
+                albums.sort_by_key(|a| std::cmp::Reverse(a.favorited_at.unwrap_or_default()))
+            }
+            FavoriteSort::Artist => albums.sort_by(|a, b| {
+                a.artist
+                    .name
+                    .to_lowercase()
+                    .cmp(&b.artist.name.to_lowercase())
+                    .then(a.release_year.cmp(&b.release_year))
+            }),
+            FavoriteSort::ReleaseYear => albums.sort_by_key(|a| std::cmp::Reverse(a.release_year)),
+        }
+
+        albums
+    }
+}
+
+/// Loads the favorite albums stored in the local cache. Tracks and artists are
+/// only known after a [`refresh`].
+pub async fn cached() -> Favorites {
+    Favorites {
+        albums: db::get_favorite_albums().await,
+        played: db::played_album_ids().await,
+        ..Default::default()
+    }
+}
+
+/// Fetches the favorites from the service and replaces the local cache.
+/// Returns `None` when the service could not be reached.
+pub async fn refresh() -> Option<Favorites> {
+    let (albums, tracks, artists) = futures::join!(
+        player::favorite_albums(),
+        player::favorite_tracks(),
+        player::favorite_artists()
+    );
+    let albums = albums?;
+
+    db::set_favorite_albums(&albums).await;
+    library::index_favorites(&albums).await;
+
+    Some(Favorites {
+        albums,
+        tracks: tracks.unwrap_or_default(),
+        artists: artists.unwrap_or_default(),
+        played: db::played_album_ids().await,
+    })
+}
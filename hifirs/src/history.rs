@@ -0,0 +1,123 @@
+use crate::{
+    play_tracker::{PlayTracker, TrackPlay},
+    player::{self, notification::Notification},
+    service::Track,
+    sql::db,
+};
+use futures::StreamExt;
+use serde::{Deserialize, Serialize};
+
+/// How many entries the history screen and `hifi-rs history` show by default.
+pub const DEFAULT_LIMIT: u32 = 50;
+
+/// A track that was played, and for how long.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, sqlx::FromRow)]
+#[serde(rename_all = "camelCase")]
+pub struct HistoryEntry {
+    pub track_id: i64,
+    pub title: String,
+    pub artist: Option<String>,
+    pub album: Option<String>,
+    pub album_id: Option<String>,
+    /// When the track started playing, as a unix timestamp.
+    pub played_at: i64,
+    /// Seconds of the track that were played.
+    pub duration_played: i64,
+    /// The length of the track in seconds.
+    pub duration: i64,
+}
+
+impl HistoryEntry {
+    fn new(track: &Track, played_at: i64, duration_played: u64) -> Self {
+        Self {
+            track_id: track.id as i64,
+            title: track.display_title(),
+            artist: track.artist.as_ref().map(|a| a.name.clone()),
+            album: track.album.as_ref().map(|a| a.title.clone()),
+            album_id: track.album.as_ref().map(|a| a.id.clone()),
+            played_at,
+            duration_played: duration_played as i64,
+            duration: track.duration_seconds as i64,
+        }
+    }
+
+    /// When the track was played, in local time.
+    pub fn played_at_label(&self) -> String {
+        chrono::DateTime::from_timestamp(self.played_at, 0)
+            .map(|date| {
+                date.with_timezone(&chrono::Local)
+                    .format("%Y-%m-%d %H:%M")
+                    .to_string()
+            })
+            .unwrap_or_default()
+    }
+
+    /// The track as a line in a list: when, artist and title.
+    pub fn list_item(&self) -> String {
+        let played_at = self.played_at_label();
+
+        match &self.artist {
+            Some(artist) => format!("{played_at}  {artist} - {}", self.title),
+            None => format!("{played_at}  {}", self.title),
+        }
+    }
+}
+
+/// How often a track was played in total.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, sqlx::FromRow)]
+#[serde(rename_all = "camelCase")]
+pub struct MostPlayed {
+    pub track_id: i64,
+    pub title: String,
+    pub artist: Option<String>,
+    pub album: Option<String>,
+    pub plays: i64,
+    /// Seconds of the track that were played over all plays.
+    pub seconds_played: i64,
+}
+
+impl MostPlayed {
+    /// The track as a line in a list: plays, artist and title.
+    pub fn list_item(&self) -> String {
+        match &self.artist {
+            Some(artist) => format!("{:>4}x  {artist} - {}", self.plays, self.title),
+            None => format!("{:>4}x  {}", self.plays, self.title),
+        }
+    }
+}
+
+/// The tracks played last, newest first.
+pub async fn recent(limit: u32) -> Vec<HistoryEntry> {
+    db::recent_tracks(limit).await
+}
+
+/// The tracks played most often, most plays first.
+pub async fn most_played(limit: u32) -> Vec<MostPlayed> {
+    db::most_played(limit).await
+}
+
+/// Follows the notifications and adds every track that was played to the
+/// history until the player quits.
+pub async fn record_loop() {
+    let mut receiver = player::notify_receiver();
+    let mut tracker = PlayTracker::default();
+
+    while let Some(notification) = receiver.next().await {
+        if let Notification::Quit = notification {
+            record(tracker.finish()).await;
+            break;
+        }
+
+        if let Some(finished) = tracker.update(&notification) {
+            record(finished).await;
+        }
+    }
+}
+
+async fn record(play: Option<TrackPlay>) {
+    if let Some(play) = play.filter(|play| play.heard_seconds > 0) {
+        let entry = HistoryEntry::new(&play.track, play.started_at.timestamp(), play.heard_seconds);
+
+        db::add_history_entry(&entry).await;
+    }
+}
@@ -0,0 +1,142 @@
+use clap::ValueEnum;
+use include_dir::{include_dir, Dir};
+use once_cell::sync::{Lazy, OnceCell};
+use serde::{Deserialize, Serialize};
+use std::{collections::HashMap, fmt::Display};
+
+static LOCALES: Dir = include_dir!("$CARGO_MANIFEST_DIR/locales");
+
+static FALLBACK: Lazy<HashMap<String, String>> = Lazy::new(|| load_catalog(Language::En));
+static CATALOG: OnceCell<HashMap<String, String>> = OnceCell::new();
+static LANGUAGE: OnceCell<Language> = OnceCell::new();
+
+/// Languages with a bundled catalog.
+#[derive(Default, Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, ValueEnum)]
+#[serde(rename_all = "lowercase")]
+pub enum Language {
+    #[default]
+    En,
+    Fr,
+    De,
+}
+
+impl Language {
+    /// The value sent to Qobuz in the `Accept-Language` header.
+    pub fn accept_language(&self) -> &'static str {
+        match self {
+            Language::En => "en,en-US;q=0.8",
+            Language::Fr => "fr,fr-FR;q=0.8,en;q=0.6",
+            Language::De => "de,de-DE;q=0.8,en;q=0.6",
+        }
+    }
+
+    /// Pick a language from the `LANG` environment variable.
+    pub fn from_env() -> Option<Self> {
+        let lang = std::env::var("LANG").ok()?;
+        lang.get(0..2).and_then(|code| code.parse().ok())
+    }
+}
+
+impl Display for Language {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Language::En => f.write_str("en"),
+            Language::Fr => f.write_str("fr"),
+            Language::De => f.write_str("de"),
+        }
+    }
+}
+
+impl std::str::FromStr for Language {
+    type Err = ();
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_lowercase().as_str() {
+            "en" => Ok(Language::En),
+            "fr" => Ok(Language::Fr),
+            "de" => Ok(Language::De),
+            _ => Err(()),
+        }
+    }
+}
+
+/// Select the catalog used for the rest of the session.
+/// Falls back to `LANG`, then English.
+pub fn init(language: Option<Language>) {
+    let language = language.or_else(Language::from_env).unwrap_or_default();
+    debug!("using language {}", language);
+
+    LANGUAGE.set(language).ok();
+    CATALOG.set(load_catalog(language)).ok();
+}
+
+/// The language selected at startup.
+pub fn language() -> Language {
+    LANGUAGE.get().copied().unwrap_or_default()
+}
+
+/// Look up a message by its id, falling back to English and then the id itself.
+pub fn tr(id: &str) -> String {
+    CATALOG
+        .get()
+        .and_then(|catalog| catalog.get(id))
+        .or_else(|| FALLBACK.get(id))
+        .cloned()
+        .unwrap_or_else(|| id.to_string())
+}
+
+/// Look up a message and replace its `{ $name }` placeables.
+pub fn tr_args(id: &str, args: &[(&str, String)]) -> String {
+    let mut message = tr(id);
+
+    for (name, value) in args {
+        message = message.replace(&format!("{{ ${name} }}"), value);
+    }
+
+    message
+}
+
+/// Parses the small subset of the fluent syntax used by the bundled catalogs:
+/// `key = value` pairs, indented continuation lines and `#` comments.
+fn load_catalog(language: Language) -> HashMap<String, String> {
+    let mut catalog = HashMap::new();
+
+    let Some(contents) = LOCALES
+        .get_file(format!("{language}.ftl"))
+        .and_then(|file| file.contents_utf8())
+    else {
+        return catalog;
+    };
+
+    let mut current: Option<String> = None;
+
+    for line in contents.lines() {
+        if line.trim().is_empty() || line.trim_start().starts_with('#') {
+            continue;
+        }
+
+        if line.starts_with(char::is_whitespace) {
+            if let Some(message) = current.as_ref().and_then(|key| catalog.get_mut(key)) {
+                message.push('\n');
+                message.push_str(line.trim());
+            }
+        } else if let Some((key, value)) = line.split_once('=') {
+            let key = key.trim().to_string();
+
+            catalog.insert(key.clone(), value.trim().to_string());
+            current = Some(key);
+        }
+    }
+
+    catalog
+}
+
+#[macro_export]
+macro_rules! t {
+    ($id:expr) => {
+        $crate::i18n::tr($id)
+    };
+    ($id:expr, $($name:ident = $value:expr),+ $(,)?) => {
+        $crate::i18n::tr_args($id, &[$((stringify!($name), $value.to_string())),+])
+    };
+}
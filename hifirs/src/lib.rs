@@ -2,17 +2,37 @@
 #[macro_use]
 extern crate tracing;
 
+pub mod artwork;
+pub mod changelog;
 #[macro_use]
 pub mod cli;
+pub mod config;
+pub mod ctl;
+#[macro_use]
+pub mod i18n;
+pub mod library;
+pub mod local;
+pub mod lyrics;
+#[cfg(any(target_os = "windows", target_os = "macos"))]
+mod media_controls;
+pub mod media_keys;
 #[cfg(target_os = "linux")]
 mod mpris;
 #[macro_use]
-mod player;
+pub mod player;
 pub mod cursive;
+pub mod favorites;
+pub mod history;
+pub mod network;
+pub mod play_log;
+pub mod play_tracker;
 mod qobuz;
+pub mod releases;
+pub mod scrobble;
 pub mod service;
 #[macro_use]
 pub mod sql;
+pub mod tls;
 pub mod websocket;
 
 const REFRESH_RESOLUTION: u64 = 250;
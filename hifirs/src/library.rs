@@ -0,0 +1,389 @@
+use crate::{
+    player::{self, cache, notification::Notification},
+    service::{Album, Playlist, Track, TrackStatus},
+    sql::db,
+};
+use clap::ValueEnum;
+use futures::StreamExt;
+use once_cell::sync::Lazy;
+use serde::{Deserialize, Serialize};
+use std::{
+    collections::{BTreeMap, HashSet},
+    fmt::Display,
+    sync::Mutex,
+    time::{Duration, Instant},
+};
+
+/// The most results a library search returns.
+pub const SEARCH_LIMIT: u32 = 100;
+/// How long the contents of the user's playlists are trusted before they are indexed again.
+const PLAYLIST_REFRESH_INTERVAL: Duration = Duration::from_secs(5 * 60);
+
+/// When every playlist of the user was last indexed, see [`refresh_playlists`].
+static PLAYLISTS_REFRESHED: Lazy<Mutex<Option<Instant>>> = Lazy::new(|| Mutex::new(None));
+
+/// What a library entry points to.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize, ValueEnum)]
+#[serde(rename_all = "lowercase")]
+pub enum LibraryKind {
+    Album,
+    Track,
+    Playlist,
+}
+
+/// Where a library entry was indexed from.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, ValueEnum)]
+#[serde(rename_all = "lowercase")]
+pub enum LibrarySource {
+    Favorite,
+    Playlist,
+    History,
+}
+
+impl Display for LibraryKind {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            LibraryKind::Album => f.write_str("album"),
+            LibraryKind::Track => f.write_str("track"),
+            LibraryKind::Playlist => f.write_str("playlist"),
+        }
+    }
+}
+
+impl Display for LibrarySource {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            LibrarySource::Favorite => f.write_str("favorite"),
+            LibrarySource::Playlist => f.write_str("playlist"),
+            LibrarySource::History => f.write_str("history"),
+        }
+    }
+}
+
+/// An album, track or playlist in the local search index.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct LibraryEntry {
+    pub kind: LibraryKind,
+    pub id: String,
+    pub title: String,
+    pub artist: Option<String>,
+    pub album: Option<String>,
+    pub album_id: Option<String>,
+    pub source: LibrarySource,
+    /// The playlist a track was indexed from.
+    pub source_id: Option<String>,
+}
+
+impl LibraryEntry {
+    fn album(album: &Album, source: LibrarySource) -> Self {
+        Self {
+            kind: LibraryKind::Album,
+            id: album.id.clone(),
+            title: album.title.clone(),
+            artist: Some(album.artist.name.clone()),
+            album: None,
+            album_id: Some(album.id.clone()),
+            source,
+            source_id: None,
+        }
+    }
+
+    fn track(track: &Track, source: LibrarySource, source_id: Option<String>) -> Self {
+        Self {
+            kind: LibraryKind::Track,
+            id: track.id.to_string(),
+            title: track.title.clone(),
+            artist: track
+                .artist
+                .as_ref()
+                .or(track.album.as_ref().map(|a| &a.artist))
+                .map(|a| a.name.clone()),
+            album: track.album.as_ref().map(|a| a.title.clone()),
+            album_id: track.album.as_ref().map(|a| a.id.clone()),
+            source,
+            source_id,
+        }
+    }
+
+    fn playlist(playlist: &Playlist) -> Self {
+        Self {
+            kind: LibraryKind::Playlist,
+            id: playlist.id.to_string(),
+            title: playlist.title.clone(),
+            artist: None,
+            album: None,
+            album_id: None,
+            source: LibrarySource::Playlist,
+            source_id: Some(playlist.id.to_string()),
+        }
+    }
+
+    fn parts(&self) -> Vec<&str> {
+        [
+            Some(self.title.as_str()),
+            self.artist.as_deref(),
+            self.album.as_deref(),
+        ]
+        .into_iter()
+        .flatten()
+        .collect()
+    }
+
+    /// Everything a search is matched against.
+    fn haystack(&self) -> String {
+        self.parts().join(" ")
+    }
+
+    /// A single line for result lists, e.g. `Title · Artist · Album`.
+    pub fn label(&self) -> String {
+        self.parts().join(" · ")
+    }
+}
+
+/// Replace the indexed favorites.
+pub async fn index_favorites(albums: &[Album]) {
+    let entries = albums
+        .iter()
+        .map(|a| LibraryEntry::album(a, LibrarySource::Favorite))
+        .collect::<Vec<LibraryEntry>>();
+
+    db::set_library_entries(LibrarySource::Favorite, None, &entries).await;
+}
+
+/// Replace the indexed titles of the user's playlists, keeping the tracks of each.
+pub async fn index_playlists(playlists: &[Playlist]) {
+    let entries = playlists
+        .iter()
+        .map(LibraryEntry::playlist)
+        .collect::<Vec<LibraryEntry>>();
+
+    db::set_library_playlists(&entries).await;
+}
+
+/// Replace the indexed contents of a playlist that was loaded.
+pub async fn index_playlist(playlist: &Playlist) {
+    let source_id = playlist.id.to_string();
+
+    let mut entries = vec![LibraryEntry::playlist(playlist)];
+    entries.extend(
+        playlist
+            .tracks
+            .values()
+            .map(|t| LibraryEntry::track(t, LibrarySource::Playlist, Some(source_id.clone()))),
+    );
+
+    db::set_library_entries(LibrarySource::Playlist, Some(&source_id), &entries).await;
+}
+
+/// Drop a deleted playlist and its tracks from the index.
+pub async fn forget_playlist(playlist_id: i64) {
+    let source_id = playlist_id.to_string();
+
+    db::set_library_entries(LibrarySource::Playlist, Some(&source_id), &[]).await;
+}
+
+/// Add a played track and its album to the history.
+pub async fn index_played(track: &Track) {
+    let mut entries = vec![LibraryEntry::track(track, LibrarySource::History, None)];
+
+    if let Some(album) = &track.album {
+        entries.push(LibraryEntry::album(album, LibrarySource::History));
+    }
+
+    db::add_library_entries(&entries).await;
+}
+
+/// The user's playlists as far as they are in the index, without their tracks.
+/// Stands in for the list from the service while offline.
+pub async fn indexed_playlists() -> Vec<Playlist> {
+    let entries = db::get_library_entries().await;
+
+    entries
+        .iter()
+        .filter(|e| e.kind == LibraryKind::Playlist)
+        .filter_map(|e| {
+            let tracks_count = entries
+                .iter()
+                .filter(|t| t.kind == LibraryKind::Track && t.source_id.as_ref() == Some(&e.id))
+                .count() as u32;
+
+            Some(Playlist {
+                title: e.title.clone(),
+                id: e.id.parse().ok()?,
+                tracks_count,
+                ..Default::default()
+            })
+        })
+        .collect()
+}
+
+/// The user's playlists that contain `track_id`, as far as their contents are in the index.
+pub async fn playlists_containing(track_id: u32) -> Vec<LibraryEntry> {
+    let entries = db::get_library_entries().await;
+    let track_id = track_id.to_string();
+
+    let sources = entries
+        .iter()
+        .filter(|e| e.kind == LibraryKind::Track && e.source == LibrarySource::Playlist)
+        .filter(|e| e.id == track_id)
+        .filter_map(|e| e.source_id.clone())
+        .collect::<HashSet<String>>();
+
+    entries
+        .into_iter()
+        .filter(|e| e.kind == LibraryKind::Playlist && sources.contains(&e.id))
+        .collect()
+}
+
+/// Index the contents of every playlist of the user again, so [`playlists_containing`]
+/// also knows about playlists that were never opened or changed elsewhere. Only done
+/// once in [`PLAYLIST_REFRESH_INTERVAL`], returns whether it was done now.
+pub async fn refresh_playlists() -> bool {
+    {
+        let mut refreshed = PLAYLISTS_REFRESHED.lock().unwrap();
+
+        if refreshed.is_some_and(|at| at.elapsed() < PLAYLIST_REFRESH_INTERVAL) {
+            return false;
+        }
+
+        *refreshed = Some(Instant::now());
+    }
+
+    let service = player::service().await;
+    let Some(playlists) = service.user_playlists().await else {
+        // Tried again the next time.
+        *PLAYLISTS_REFRESHED.lock().unwrap() = None;
+        return false;
+    };
+
+    index_playlists(&playlists).await;
+
+    for playlist in playlists {
+        if let Some(playlist) = service.playlist(playlist.id as i64).await {
+            index_playlist(&playlist).await;
+        }
+    }
+
+    true
+}
+
+/// A playlist rebuilt from the index with only the tracks that are in the track cache,
+/// so it can be played while offline.
+pub async fn cached_playlist(playlist_id: i64) -> Option<Playlist> {
+    let source_id = playlist_id.to_string();
+    let entries = db::get_library_entries()
+        .await
+        .into_iter()
+        .filter(|e| e.source == LibrarySource::Playlist && e.source_id == Some(source_id.clone()))
+        .collect::<Vec<LibraryEntry>>();
+
+    let title = entries
+        .iter()
+        .find(|e| e.kind == LibraryKind::Playlist)?
+        .title
+        .clone();
+
+    let mut tracks = BTreeMap::new();
+
+    for entry in entries.iter().filter(|e| e.kind == LibraryKind::Track) {
+        let Ok(track_id) = entry.id.parse::<i32>() else {
+            continue;
+        };
+
+        if cache::cached_url(track_id).await.is_none() {
+            continue;
+        }
+
+        if let Some(mut track) = cache::cached_track(track_id).await {
+            let position = tracks.len() as u32 + 1;
+            track.position = position;
+            track.status = TrackStatus::Unplayed;
+
+            tracks.insert(position, track);
+        }
+    }
+
+    Some(Playlist {
+        title,
+        duration_seconds: tracks.values().map(|t: &Track| t.duration_seconds).sum(),
+        tracks_count: tracks.len() as u32,
+        id: playlist_id as u32,
+        tracks,
+        ..Default::default()
+    })
+}
+
+/// Search the favorites, playlists and history. Every word of `query` has to appear somewhere
+/// in the title, artist or album. When nothing matches, the letters of the query only have to
+/// appear in order, so abbreviations and missing letters still find something.
+pub async fn search(query: &str) -> Vec<LibraryEntry> {
+    let query = query.trim();
+
+    if query.is_empty() {
+        return Vec::new();
+    }
+
+    let mut entries = db::search_library(query, SEARCH_LIMIT).await;
+
+    if entries.is_empty() {
+        let mut scored = db::get_library_entries()
+            .await
+            .into_iter()
+            .filter_map(|e| fuzzy_score(query, &e.haystack()).map(|score| (score, e)))
+            .collect::<Vec<(u32, LibraryEntry)>>();
+
+        scored.sort_by_key(|(score, _)| *score);
+        entries = scored.into_iter().map(|(_, e)| e).collect();
+    }
+
+    let mut seen = HashSet::new();
+    entries.retain(|e| seen.insert((e.kind, e.id.clone())));
+    entries.truncate(SEARCH_LIMIT as usize);
+
+    entries
+}
+
+/// How loosely `haystack` contains the letters of `query` in order, lower is closer.
+/// `None` when some letter is missing.
+fn fuzzy_score(query: &str, haystack: &str) -> Option<u32> {
+    let haystack = haystack.to_lowercase().chars().collect::<Vec<char>>();
+    let mut position = 0;
+    let mut first = None;
+    let mut gaps = 0;
+
+    for c in query.to_lowercase().chars().filter(|c| !c.is_whitespace()) {
+        let found = haystack[position..].iter().position(|h| *h == c)?;
+
+        if first.is_none() {
+            first = Some(position + found);
+        } else {
+            gaps += found as u32;
+        }
+
+        position += found + 1;
+    }
+
+    Some(gaps * 2 + first.unwrap_or_default() as u32)
+}
+
+/// Follows the notifications and adds every track that starts playing to the history.
+pub async fn index_loop() {
+    let mut receiver = player::notify_receiver();
+    let mut last = None;
+
+    while let Some(notification) = receiver.next().await {
+        match notification {
+            Notification::CurrentTrackList { list } => {
+                if let Some(track) = list.current_track() {
+                    if last != Some(track.id) {
+                        last = Some(track.id);
+                        index_played(track).await;
+                    }
+                }
+            }
+            Notification::Quit => break,
+            _ => {}
+        }
+    }
+}
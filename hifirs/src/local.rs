@@ -0,0 +1,658 @@
+use crate::{
+    config,
+    service::{
+        self, Album, Artist, BrowseCategory, BrowsePage, Credentials, Favorite, Genre,
+        MusicService, Playlist, SearchLimits, SearchResults, ServiceUri, StreamUrl, Track,
+        TrackStatus,
+    },
+    sql::db,
+};
+use async_trait::async_trait;
+use futures::{future::BoxFuture, FutureExt};
+use hifirs_qobuz_api::client::AudioQuality;
+use lofty::{Accessor, AudioFile, ItemKey, TaggedFileExt};
+use once_cell::sync::OnceCell;
+use rand::seq::SliceRandom;
+use std::{
+    collections::{BTreeMap, HashMap},
+    path::{Path, PathBuf},
+    sync::{Arc, RwLock},
+};
+
+/// Local tracks get ids from here on, so they are never mistaken for tracks of a
+/// streaming service and can share a queue with them.
+pub const TRACK_ID_BASE: u32 = 2_000_000_000;
+/// Local album ids start with this.
+pub const ALBUM_PREFIX: &str = "local-";
+const EXTENSIONS: &[&str] = &["flac", "mp3", "m4a", "ogg", "opus", "wav", "aif", "aiff"];
+const COVERS: &[&str] = &[
+    "cover.jpg",
+    "cover.png",
+    "folder.jpg",
+    "folder.png",
+    "front.jpg",
+];
+
+static LIBRARY: OnceCell<Arc<LocalService>> = OnceCell::new();
+
+/// Plays the music files in a directory, going by their tags.
+#[derive(Debug)]
+pub struct LocalService {
+    dir: PathBuf,
+    index: RwLock<Index>,
+    scanned: tokio::sync::Mutex<bool>,
+}
+
+#[derive(Debug, Default)]
+struct Index {
+    tracks: HashMap<u32, LocalTrack>,
+    /// The tracks of each album, in disc and track order.
+    albums: BTreeMap<String, Vec<u32>>,
+}
+
+#[derive(Debug, Clone)]
+struct LocalTrack {
+    path: PathBuf,
+    track: Track,
+}
+
+/// What the tags and properties of a file say about it.
+#[derive(Debug, Clone, Default)]
+struct Tags {
+    title: Option<String>,
+    artist: Option<String>,
+    album: Option<String>,
+    album_artist: Option<String>,
+    number: u32,
+    disc: u32,
+    year: u32,
+    duration_seconds: u32,
+    /// kHz, like the tracks of the streaming services.
+    sampling_rate: f32,
+    bit_depth: u32,
+    replay_gain: Option<f64>,
+    replay_peak: Option<f64>,
+}
+
+/// The library of the music directory, set up on first use.
+pub fn open(dir: PathBuf) -> Arc<LocalService> {
+    LIBRARY
+        .get_or_init(|| {
+            Arc::new(LocalService {
+                dir,
+                index: RwLock::new(Index::default()),
+                scanned: tokio::sync::Mutex::new(false),
+            })
+        })
+        .clone()
+}
+
+/// The library, when a music directory is set up.
+pub fn library() -> Option<Arc<LocalService>> {
+    LIBRARY.get().cloned()
+}
+
+pub fn is_local_track(track_id: i32) -> bool {
+    track_id >= TRACK_ID_BASE as i32
+}
+
+pub fn is_local_album(album_id: &str) -> bool {
+    album_id.starts_with(ALBUM_PREFIX)
+}
+
+/// Sets up the library for the service registry, see [`service::register`].
+pub fn build_service(
+    _credentials: Credentials,
+) -> BoxFuture<'static, service::Result<(Arc<dyn MusicService>, bool)>> {
+    async move {
+        let dir = config::settings()
+            .await
+            .music_dir
+            .ok_or_else(|| service::Error::Setup {
+                message: "set a music directory first with `hifi-rs config set music-dir`"
+                    .to_string(),
+            })?;
+
+        let library = open(dir);
+        library.scan_once().await;
+
+        Ok((library as Arc<dyn MusicService>, true))
+    }
+    .boxed()
+}
+
+impl LocalService {
+    /// Scan the music directory, unless that happened already.
+    pub async fn scan_once(&self) {
+        let mut scanned = self.scanned.lock().await;
+
+        if !*scanned {
+            self.scan().await;
+            *scanned = true;
+        }
+    }
+
+    /// Read the tags of every music file in the directory and rebuild the index.
+    pub async fn scan(&self) {
+        info!(dir = ?self.dir, "scanning music directory");
+
+        let dir = self.dir.clone();
+        let files = tokio::task::spawn_blocking(move || {
+            let mut paths = Vec::new();
+            collect_files(&dir, &mut paths);
+            paths.sort();
+
+            paths
+                .into_iter()
+                .filter_map(|path| read_tags(&path).map(|tags| (path, tags)))
+                .collect::<Vec<(PathBuf, Tags)>>()
+        })
+        .await
+        .unwrap_or_default();
+
+        // Tracks keep their id across scans, it is stored with their path.
+        let mut tracks = Vec::with_capacity(files.len());
+        for (path, tags) in files {
+            if let Some(id) = db::local_track_id(&path.to_string_lossy()).await {
+                tracks.push((TRACK_ID_BASE + id as u32, path, tags));
+            }
+        }
+
+        let index = build_index(tracks);
+        info!(
+            tracks = index.tracks.len(),
+            albums = index.albums.len(),
+            "music directory scanned"
+        );
+
+        *self.index.write().expect("failed to lock local library") = index;
+    }
+
+    fn local_track(&self, track_id: i32) -> Option<LocalTrack> {
+        self.index
+            .read()
+            .expect("failed to lock local library")
+            .tracks
+            .get(&(track_id as u32))
+            .cloned()
+    }
+
+    fn local_album(&self, album_id: &str) -> Option<Album> {
+        let index = self.index.read().expect("failed to lock local library");
+        let track_ids = index.albums.get(album_id)?;
+
+        let tracks = track_ids
+            .iter()
+            .filter_map(|id| index.tracks.get(id))
+            .map(|local| local.track.clone())
+            .collect::<Vec<Track>>();
+
+        let mut album = tracks.first()?.album.clone()?;
+        album.tracks = tracks
+            .into_iter()
+            .enumerate()
+            .map(|(i, mut track)| {
+                track.position = i as u32 + 1;
+                track.album = None;
+                (track.position, track)
+            })
+            .collect();
+
+        Some(album)
+    }
+
+    /// The album of the first track in `dir`, or the track itself for a file.
+    fn find_path(&self, path: &Path) -> Option<ServiceUri> {
+        let index = self.index.read().expect("failed to lock local library");
+
+        if let Some((id, _)) = index.tracks.iter().find(|(_, t)| t.path == path) {
+            return Some(ServiceUri::Track(*id as i32));
+        }
+
+        index
+            .tracks
+            .values()
+            .filter(|t| t.path.starts_with(path))
+            .min_by(|a, b| a.path.cmp(&b.path))
+            .and_then(|t| t.track.album.as_ref())
+            .map(|album| ServiceUri::Album(album.id.clone()))
+    }
+}
+
+#[async_trait]
+impl MusicService for LocalService {
+    fn name(&self) -> &'static str {
+        "local"
+    }
+
+    /// `file://` uris of a music file, or of a directory to play the album in it.
+    fn parse_uri(&self, uri: &str) -> Option<ServiceUri> {
+        let path = url::Url::parse(uri).ok()?.to_file_path().ok()?;
+
+        self.find_path(&path)
+    }
+
+    async fn login(&self, _username: &str, _password: &str) {}
+
+    async fn album(&self, album_id: &str) -> Option<Album> {
+        self.local_album(album_id)
+    }
+
+    async fn track(&self, track_id: i32) -> Option<Track> {
+        self.local_track(track_id).map(|local| local.track)
+    }
+
+    async fn albums_by_ids(&self, album_ids: &[String]) -> Vec<Album> {
+        album_ids
+            .iter()
+            .filter_map(|id| self.local_album(id))
+            .collect()
+    }
+
+    async fn tracks_by_ids(&self, track_ids: &[i32]) -> Vec<Track> {
+        track_ids
+            .iter()
+            .filter_map(|id| self.local_track(*id))
+            .map(|local| local.track)
+            .collect()
+    }
+
+    async fn artist(&self, _artist_id: i32) -> Option<Artist> {
+        None
+    }
+
+    /// Other tracks by the same artist, topped up with random tracks from the library.
+    async fn radio(&self, seed: &Track, limit: u32) -> Vec<Track> {
+        let artist = seed.artist.as_ref().map(|a| a.name.clone());
+        let (mut same_artist, mut others): (Vec<Track>, Vec<Track>) = self
+            .index
+            .read()
+            .expect("failed to lock local library")
+            .tracks
+            .values()
+            .map(|local| local.track.clone())
+            .filter(|track| track.id != seed.id)
+            .partition(|track| track.artist.as_ref().map(|a| &a.name) == artist.as_ref());
+
+        let mut rng = rand::thread_rng();
+        same_artist.shuffle(&mut rng);
+        others.shuffle(&mut rng);
+
+        same_artist
+            .into_iter()
+            .chain(others)
+            .take(limit as usize)
+            .collect()
+    }
+
+    async fn playlist(&self, _playlist_id: i64) -> Option<Playlist> {
+        None
+    }
+
+    /// Matches titles, artists and album titles, ignoring case.
+    async fn search(&self, query: &str, limits: SearchLimits) -> Option<SearchResults> {
+        let needle = query.to_lowercase();
+        let matches = |text: Option<&str>| {
+            text.map(|t| t.to_lowercase().contains(&needle))
+                .unwrap_or(false)
+        };
+
+        let album_ids = {
+            let index = self.index.read().expect("failed to lock local library");
+
+            index
+                .albums
+                .keys()
+                .filter(|id| {
+                    index.albums[*id]
+                        .first()
+                        .and_then(|track_id| index.tracks.get(track_id))
+                        .and_then(|local| local.track.album.as_ref())
+                        .map(|album| {
+                            matches(Some(album.title.as_str()))
+                                || matches(Some(album.artist.name.as_str()))
+                        })
+                        .unwrap_or(false)
+                })
+                .take(limits.albums as usize)
+                .cloned()
+                .collect::<Vec<String>>()
+        };
+
+        let mut tracks = self
+            .index
+            .read()
+            .expect("failed to lock local library")
+            .tracks
+            .values()
+            .map(|local| &local.track)
+            .filter(|track| {
+                matches(Some(track.title.as_str()))
+                    || matches(track.artist.as_ref().map(|a| a.name.as_str()))
+            })
+            .cloned()
+            .collect::<Vec<Track>>();
+        tracks.sort_by_key(|track| track.id);
+        tracks.truncate(limits.tracks as usize);
+
+        Some(SearchResults {
+            query: query.to_string(),
+            albums: self.albums_by_ids(&album_ids).await,
+            tracks,
+            artists: Vec::new(),
+            playlists: Vec::new(),
+        })
+    }
+
+    /// Files are played as they are.
+    fn quality(&self) -> AudioQuality {
+        AudioQuality::HIFI192
+    }
+
+    async fn track_url(&self, track_id: i32) -> Option<StreamUrl> {
+        self.track_url_with_quality(track_id, self.quality()).await
+    }
+
+    async fn track_url_with_quality(
+        &self,
+        track_id: i32,
+        quality: AudioQuality,
+    ) -> Option<StreamUrl> {
+        let local = self.local_track(track_id)?;
+        let url = url::Url::from_file_path(&local.path).ok()?;
+
+        Some(StreamUrl {
+            url: url.to_string(),
+            requested: quality.clone(),
+            delivered: quality,
+            limited: false,
+        })
+    }
+
+    async fn user_playlists(&self) -> Option<Vec<Playlist>> {
+        Some(Vec::new())
+    }
+
+    async fn remove_playlist_tracks(
+        &self,
+        _playlist_id: u32,
+        _playlist_track_ids: Vec<i64>,
+    ) -> Option<Playlist> {
+        None
+    }
+
+    async fn add_playlist_tracks(
+        &self,
+        _playlist_id: u32,
+        _track_ids: Vec<u32>,
+    ) -> Option<Playlist> {
+        None
+    }
+
+    async fn create_playlist(
+        &self,
+        _name: &str,
+        _public: bool,
+        _description: Option<&str>,
+    ) -> Option<Playlist> {
+        None
+    }
+
+    async fn delete_playlist(&self, _playlist_id: u32) -> bool {
+        false
+    }
+
+    async fn move_playlist_track(
+        &self,
+        _playlist_id: u32,
+        _playlist_track_id: i64,
+        _insert_before: usize,
+    ) -> Option<Playlist> {
+        None
+    }
+
+    fn reconnect(&self) {}
+
+    async fn health_check(&self) -> bool {
+        true
+    }
+
+    async fn favorite_albums(&self) -> Option<Vec<Album>> {
+        Some(Vec::new())
+    }
+
+    async fn favorite_artists(&self) -> Option<Vec<Artist>> {
+        Some(Vec::new())
+    }
+
+    async fn favorite_tracks(&self) -> Option<Vec<Track>> {
+        Some(Vec::new())
+    }
+
+    async fn set_favorite(&self, _item: Favorite, _favorite: bool) -> bool {
+        false
+    }
+
+    fn browse_categories(&self) -> Vec<BrowseCategory> {
+        Vec::new()
+    }
+
+    fn personal_playlists(&self) -> Vec<PersonalPlaylist> {
+        Vec::new()
+    }
+
+    async fn personal_playlist(&self, _kind: PersonalPlaylist) -> Option<Playlist> {
+        None
+    }
+
+    async fn featured_playlists(&self) -> Option<Vec<Playlist>> {
+        None
+    }
+
+    async fn genres(&self) -> Vec<Genre> {
+        Vec::new()
+    }
+
+    async fn browse(
+        &self,
+        _category: BrowseCategory,
+        _genre: Option<u32>,
+        _offset: u32,
+        _limit: u32,
+    ) -> Option<BrowsePage> {
+        None
+    }
+}
+
+fn collect_files(dir: &Path, paths: &mut Vec<PathBuf>) {
+    let Ok(entries) = std::fs::read_dir(dir) else {
+        warn!(?dir, "failed to read directory");
+        return;
+    };
+
+    for entry in entries.flatten() {
+        let path = entry.path();
+
+        if path.is_dir() {
+            collect_files(&path, paths);
+        } else if path
+            .extension()
+            .and_then(|e| e.to_str())
+            .map(|e| EXTENSIONS.contains(&e.to_lowercase().as_str()))
+            .unwrap_or(false)
+        {
+            paths.push(path);
+        }
+    }
+}
+
+fn read_tags(path: &Path) -> Option<Tags> {
+    let file = match lofty::read_from_path(path) {
+        Ok(file) => file,
+        Err(error) => {
+            debug!(?path, %error, "failed to read tags");
+            return None;
+        }
+    };
+
+    let properties = file.properties();
+    let mut tags = Tags {
+        duration_seconds: properties.duration().as_secs() as u32,
+        sampling_rate: properties.sample_rate().unwrap_or_default() as f32 / 1000.,
+        bit_depth: properties.bit_depth().unwrap_or(16) as u32,
+        ..Default::default()
+    };
+
+    if let Some(tag) = file.primary_tag().or_else(|| file.first_tag()) {
+        let gain = |key: &ItemKey| {
+            tag.get_string(key)
+                .and_then(|v| v.trim_end_matches("dB").trim().parse::<f64>().ok())
+        };
+
+        tags.title = tag.title().map(|t| t.to_string());
+        tags.artist = tag.artist().map(|a| a.to_string());
+        tags.album = tag.album().map(|a| a.to_string());
+        tags.album_artist = tag.get_string(&ItemKey::AlbumArtist).map(String::from);
+        tags.number = tag.track().unwrap_or_default();
+        tags.disc = tag.disk().unwrap_or(1);
+        tags.year = tag.year().unwrap_or_default();
+        tags.replay_gain = gain(&ItemKey::ReplayGainTrackGain);
+        tags.replay_peak = gain(&ItemKey::ReplayGainTrackPeak);
+    }
+
+    Some(tags)
+}
+
+/// Group the tracks into albums by album artist and album title, with the album named
+/// after the directory when the files have no album tag.
+fn build_index(tracks: Vec<(u32, PathBuf, Tags)>) -> Index {
+    let mut groups: BTreeMap<(String, String), Vec<(u32, PathBuf, Tags)>> = BTreeMap::new();
+
+    for (id, path, tags) in tracks {
+        let dir_name = path
+            .parent()
+            .and_then(|p| p.file_name())
+            .map(|n| n.to_string_lossy().to_string())
+            .unwrap_or_default();
+        let album_artist = tags
+            .album_artist
+            .clone()
+            .or_else(|| tags.artist.clone())
+            .unwrap_or_default();
+        let album_title = tags.album.clone().unwrap_or(dir_name);
+
+        groups
+            .entry((album_artist, album_title))
+            .or_default()
+            .push((id, path, tags));
+    }
+
+    let mut index = Index::default();
+
+    for ((album_artist, album_title), mut files) in groups {
+        files.sort_by_key(|(_, path, tags)| (tags.disc, tags.number, path.clone()));
+
+        // The album keeps the id of its lowest track, which only changes when that file goes.
+        let album_id = format!(
+            "{ALBUM_PREFIX}{}",
+            files
+                .iter()
+                .map(|(id, _, _)| id - TRACK_ID_BASE)
+                .min()
+                .unwrap_or_default()
+        );
+        let cover_art = files
+            .first()
+            .and_then(|(_, path, _)| cover_art(path))
+            .unwrap_or_default();
+        let (bit_depth, sampling_rate) = files
+            .iter()
+            .map(|(_, _, tags)| (tags.bit_depth, tags.sampling_rate))
+            .fold((0, 0.), |(b, s), (bit_depth, rate)| {
+                (b.max(bit_depth), f32::max(s, rate))
+            });
+
+        let album = Album {
+            id: album_id.clone(),
+            title: album_title,
+            artist: Artist {
+                id: 0,
+                name: album_artist,
+                albums: None,
+            },
+            release_year: files.first().map(|(_, _, t)| t.year).unwrap_or_default(),
+            hires_available: service::is_hires(bit_depth, sampling_rate),
+            explicit: false,
+            total_tracks: files.len() as u32,
+            tracks: BTreeMap::new(),
+            available: true,
+            cover_art: cover_art.clone(),
+            favorited_at: None,
+            streamable_at: None,
+            catchline: None,
+            description: None,
+            genre: None,
+            release_date: None,
+            stream_date: None,
+            version: None,
+        };
+
+        let mut album_tracks = Vec::with_capacity(files.len());
+
+        for (position, (id, path, tags)) in files.into_iter().enumerate() {
+            let title = tags.title.clone().unwrap_or_else(|| {
+                path.file_stem()
+                    .map(|s| s.to_string_lossy().to_string())
+                    .unwrap_or_default()
+            });
+
+            let track = Track {
+                id,
+                number: tags.number,
+                title,
+                album: Some(album.clone()),
+                artist: tags.artist.clone().map(|name| Artist {
+                    id: 0,
+                    name,
+                    albums: None,
+                }),
+                duration_seconds: tags.duration_seconds,
+                explicit: false,
+                hires_available: service::is_hires(tags.bit_depth, tags.sampling_rate),
+                sampling_rate: tags.sampling_rate,
+                bit_depth: tags.bit_depth,
+                status: TrackStatus::Unplayed,
+                track_url: None,
+                available: true,
+                cover_art: Some(cover_art.clone()).filter(|c| !c.is_empty()),
+                position: position as u32 + 1,
+                media_number: tags.disc,
+                url: None,
+                playlist_track_id: None,
+                version: None,
+                replay_gain: tags.replay_gain,
+                replay_peak: tags.replay_peak,
+                fallback_quality: None,
+            };
+
+            album_tracks.push(id);
+            index.tracks.insert(id, LocalTrack { path, track });
+        }
+
+        index.albums.insert(album_id, album_tracks);
+    }
+
+    index
+}
+
+/// A cover image next to the file, as a `file://` url.
+fn cover_art(path: &Path) -> Option<String> {
+    let dir = path.parent()?;
+
+    COVERS
+        .iter()
+        .map(|name| dir.join(name))
+        .find(|cover| cover.is_file())
+        .and_then(|cover| url::Url::from_file_path(cover).ok())
+        .map(|url| url.to_string())
+}
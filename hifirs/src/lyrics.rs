@@ -0,0 +1,202 @@
+use crate::{service::Track, sql::db};
+use serde::{Deserialize, Serialize};
+use std::time::Duration;
+
+const LRCLIB_URL: &str = "https://lrclib.net/api/get";
+/// lrclib asks clients to say who they are.
+const USER_AGENT: &str = concat!(
+    "hifi-rs/",
+    env!("CARGO_PKG_VERSION"),
+    " (https://github.com/iamdb/hifi.rs)"
+);
+/// Tracks lrclib had no lyrics for are asked about again after this many seconds.
+const MISSING_MAX_AGE: i64 = 7 * 24 * 60 * 60;
+
+/// The words to a track.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum Lyrics {
+    /// Lines with the time they are sung at, in order.
+    Synced(Vec<LyricLine>),
+    Plain(Vec<String>),
+    Instrumental,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct LyricLine {
+    pub start: Duration,
+    pub text: String,
+}
+
+impl Lyrics {
+    /// The lines to show, one per row.
+    pub fn lines(&self) -> Vec<&str> {
+        match self {
+            Lyrics::Synced(lines) => lines.iter().map(|line| line.text.as_str()).collect(),
+            Lyrics::Plain(lines) => lines.iter().map(String::as_str).collect(),
+            Lyrics::Instrumental => vec![],
+        }
+    }
+
+    /// The index of the line being sung at `position`, for synced lyrics.
+    pub fn current_line(&self, position: Duration) -> Option<usize> {
+        match self {
+            Lyrics::Synced(lines) => lines.iter().rposition(|line| line.start <= position),
+            _ => None,
+        }
+    }
+}
+
+/// Lyrics as kept in the database. Both kinds are `None` when lrclib had none.
+#[derive(Debug, Clone, Default, PartialEq, sqlx::FromRow)]
+pub struct CachedLyrics {
+    pub synced: Option<String>,
+    pub plain: Option<String>,
+    pub instrumental: bool,
+    pub fetched_at: i64,
+}
+
+impl CachedLyrics {
+    fn lyrics(&self) -> Option<Lyrics> {
+        if self.instrumental {
+            return Some(Lyrics::Instrumental);
+        }
+
+        if let Some(lines) = self.synced.as_deref().map(parse_lrc) {
+            if !lines.is_empty() {
+                return Some(Lyrics::Synced(lines));
+            }
+        }
+
+        self.plain
+            .as_deref()
+            .map(|plain| Lyrics::Plain(plain.lines().map(str::to_string).collect()))
+    }
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct LrclibLyrics {
+    #[serde(default)]
+    instrumental: bool,
+    plain_lyrics: Option<String>,
+    synced_lyrics: Option<String>,
+}
+
+/// The lyrics of `track`, from the database when they were looked up before.
+pub async fn lyrics(track: &Track) -> Option<Lyrics> {
+    let artist = track.artist.as_ref()?.name.clone();
+    let duration = track.duration_seconds;
+
+    if let Some(cached) = db::get_lyrics(&artist, &track.title, duration).await {
+        let lyrics = cached.lyrics();
+        let fresh = chrono::Utc::now().timestamp() - cached.fetched_at < MISSING_MAX_AGE;
+
+        if lyrics.is_some() || fresh {
+            return lyrics;
+        }
+    }
+
+    let album = track.album.as_ref().map(|album| album.title.clone());
+    let cached = match fetch(&artist, &track.title, album, duration).await {
+        Ok(cached) => cached,
+        Err(error) => {
+            debug!(?error, track.id, "failed to fetch lyrics");
+            return None;
+        }
+    };
+
+    db::save_lyrics(&artist, &track.title, duration, &cached).await;
+
+    cached.lyrics()
+}
+
+/// Ask lrclib for the lyrics. Not having any is not an error, it's cached like lyrics are.
+async fn fetch(
+    artist: &str,
+    title: &str,
+    album: Option<String>,
+    duration: u32,
+) -> reqwest::Result<CachedLyrics> {
+    let mut query = vec![
+        ("artist_name", artist.to_string()),
+        ("track_name", title.to_string()),
+        ("duration", duration.to_string()),
+    ];
+
+    if let Some(album) = album {
+        query.push(("album_name", album));
+    }
+
+    let response = reqwest::Client::new()
+        .get(LRCLIB_URL)
+        .header(reqwest::header::USER_AGENT, USER_AGENT)
+        .query(&query)
+        .send()
+        .await?;
+
+    let fetched_at = chrono::Utc::now().timestamp();
+
+    if response.status() == reqwest::StatusCode::NOT_FOUND {
+        return Ok(CachedLyrics {
+            fetched_at,
+            ..Default::default()
+        });
+    }
+
+    let found = response.error_for_status()?.json::<LrclibLyrics>().await?;
+
+    Ok(CachedLyrics {
+        synced: found.synced_lyrics.filter(|l| !l.trim().is_empty()),
+        plain: found.plain_lyrics.filter(|l| !l.trim().is_empty()),
+        instrumental: found.instrumental,
+        fetched_at,
+    })
+}
+
+/// Reads LRC lines like `[01:02.34] text`. A line may carry several times, and tags such
+/// as `[ar: artist]` are left out.
+fn parse_lrc(lrc: &str) -> Vec<LyricLine> {
+    let mut lines = vec![];
+
+    for line in lrc.lines() {
+        let mut rest = line.trim();
+        let mut starts = vec![];
+
+        while let Some((time, after)) = rest
+            .strip_prefix('[')
+            .and_then(|inner| inner.split_once(']'))
+        {
+            match parse_time(time) {
+                Some(start) => starts.push(start),
+                None => break,
+            }
+
+            rest = after;
+        }
+
+        let text = rest.trim();
+
+        lines.extend(starts.into_iter().map(|start| LyricLine {
+            start,
+            text: text.to_string(),
+        }));
+    }
+
+    lines.sort_by_key(|line| line.start);
+    lines
+}
+
+/// `mm:ss.xx` or `mm:ss`.
+fn parse_time(time: &str) -> Option<Duration> {
+    let (minutes, seconds) = time.split_once(':')?;
+    let minutes = minutes.trim().parse::<u64>().ok()?;
+    let seconds = seconds
+        .trim()
+        .parse::<f64>()
+        .ok()
+        .filter(|seconds| seconds.is_finite() && *seconds >= 0.)?;
+
+    Some(Duration::from_secs(minutes * 60) + Duration::from_secs_f64(seconds))
+}
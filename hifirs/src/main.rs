@@ -1,7 +1,31 @@
 use std::process;
 
+#[cfg(not(target_os = "macos"))]
 #[tokio::main]
 async fn main() {
+    run().await;
+}
+
+/// The Now Playing buttons of macOS are handled on the main thread's dispatch queue, so
+/// the main thread is given to that and hifi-rs runs on a runtime of its own.
+#[cfg(target_os = "macos")]
+fn main() {
+    extern "C" {
+        fn dispatch_main() -> !;
+    }
+
+    std::thread::spawn(|| {
+        tokio::runtime::Runtime::new()
+            .expect("failed to start the runtime")
+            .block_on(run());
+
+        process::exit(0);
+    });
+
+    unsafe { dispatch_main() }
+}
+
+async fn run() {
     match hifi_rs::cli::run().await {
         Ok(()) => {}
         Err(err) => {
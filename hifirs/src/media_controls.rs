@@ -0,0 +1,213 @@
+use crate::{
+    media_keys::MediaKeyAction,
+    player::{self, notification::Notification},
+    service::Track,
+};
+use futures::StreamExt;
+use gstreamer::{ClockTime, State as GstState};
+use souvlaki::{
+    MediaControlEvent, MediaControls, MediaMetadata, MediaPlayback, MediaPosition, PlatformConfig,
+    SeekDirection,
+};
+use std::{
+    ffi::c_void,
+    time::{Duration, Instant},
+};
+use tokio::select;
+
+/// Positions further than this from where playback should be by now are sent on, as
+/// after a seek. The system works out the rest from the playback state.
+const SEEK_TOLERANCE: Duration = Duration::from_secs(2);
+/// How often the window messages are handled while waiting for updates on Windows.
+#[cfg(target_os = "windows")]
+const PUMP_INTERVAL: Duration = Duration::from_millis(50);
+
+/// What the system's now playing overlay is told.
+enum Update {
+    Track(Option<Track>),
+    Playback {
+        status: GstState,
+        position: ClockTime,
+    },
+}
+
+/// Shows what plays in the system's media overlay and takes its buttons and the hardware
+/// media keys: the System Media Transport Controls on Windows and the Now Playing center on
+/// macOS. What MPRIS is on Linux.
+///
+/// The controls run on a thread of their own. On Windows that thread handles the window
+/// messages the controls answer through, on macOS the buttons come in on the main thread,
+/// which `main` leaves to the system for that.
+pub async fn receive_notifications() {
+    let (update_tx, update_rx) = flume::unbounded::<Update>();
+    let (event_tx, event_rx) = flume::unbounded::<MediaControlEvent>();
+
+    // The controls can't move between threads on every platform, so they get their own.
+    std::thread::spawn(move || run_controls(update_rx, event_tx));
+
+    let mut receiver = player::notify_receiver();
+    let mut events = event_rx.into_stream();
+    let mut status = GstState::Null;
+    // The position last sent and when, to tell seeks from playback moving on.
+    let mut sent = (ClockTime::default(), Instant::now());
+
+    loop {
+        select! {
+            Some(event) = events.next() => {
+                if let Err(error) = handle_event(event).await {
+                    debug!(?error);
+                }
+            }
+            Ok(notification) = receiver.recv() => match notification {
+                Notification::Quit => break,
+                Notification::Status { status: new_status } => {
+                    status = new_status;
+                    sent = (player::position().unwrap_or_default(), Instant::now());
+
+                    let _ = update_tx.send(Update::Playback { status, position: sent.0 });
+                }
+                Notification::Position { clock } => {
+                    let (position, at) = sent;
+                    let mut expected = position.nseconds();
+
+                    if status == GstState::Playing {
+                        expected += at.elapsed().as_nanos() as u64;
+                    }
+
+                    if clock.nseconds().abs_diff(expected) > SEEK_TOLERANCE.as_nanos() as u64 {
+                        sent = (clock, Instant::now());
+
+                        let _ = update_tx.send(Update::Playback { status, position: clock });
+                    }
+                }
+                Notification::CurrentTrackList { list } => {
+                    let _ = update_tx.send(Update::Track(list.current_track().cloned()));
+                }
+                _ => {}
+            }
+        }
+    }
+}
+
+async fn handle_event(event: MediaControlEvent) -> player::Result<()> {
+    debug!(?event, "media control");
+
+    match event {
+        MediaControlEvent::Play => MediaKeyAction::Play.run().await,
+        MediaControlEvent::Pause => MediaKeyAction::Pause.run().await,
+        MediaControlEvent::Toggle => MediaKeyAction::PlayPause.run().await,
+        MediaControlEvent::Next => MediaKeyAction::Next.run().await,
+        MediaControlEvent::Previous => MediaKeyAction::Previous.run().await,
+        MediaControlEvent::Stop => MediaKeyAction::Stop.run().await,
+        MediaControlEvent::Seek(SeekDirection::Forward) => MediaKeyAction::JumpForward.run().await,
+        MediaControlEvent::Seek(SeekDirection::Backward) => {
+            MediaKeyAction::JumpBackward.run().await
+        }
+        MediaControlEvent::SeekBy(direction, by) => {
+            let seconds = by.as_secs() as i64;
+
+            match direction {
+                SeekDirection::Forward => player::seek_seconds(seconds, true).await,
+                SeekDirection::Backward => player::seek_seconds(-seconds, true).await,
+            }
+        }
+        MediaControlEvent::SetPosition(MediaPosition(position)) => {
+            player::seek_to(ClockTime::from_nseconds(position.as_nanos() as u64)).await
+        }
+        MediaControlEvent::SetVolume(volume) => player::set_volume(volume).await,
+        _ => Ok(()),
+    }
+}
+
+fn run_controls(updates: flume::Receiver<Update>, events: flume::Sender<MediaControlEvent>) {
+    let config = PlatformConfig {
+        dbus_name: "hifirs",
+        display_name: "hifi-rs",
+        hwnd: window(),
+    };
+
+    let mut controls = match MediaControls::new(config) {
+        Ok(controls) => controls,
+        Err(error) => {
+            warn!(?error, "failed to set up the media controls");
+            return;
+        }
+    };
+
+    if let Err(error) = controls.attach(move |event| {
+        let _ = events.send(event);
+    }) {
+        warn!(?error, "failed to listen for media controls");
+        return;
+    }
+
+    // Ends when the notification loop is done and drops its sender.
+    while let Some(update) = next_update(&updates) {
+        let result = match update {
+            Update::Track(Some(track)) => controls.set_metadata(MediaMetadata {
+                title: Some(&track.title),
+                album: track.album.as_ref().map(|album| album.title.as_str()),
+                artist: track.artist.as_ref().map(|artist| artist.name.as_str()),
+                cover_url: track.cover_art.as_deref(),
+                duration: Some(Duration::from_secs(track.duration_seconds as u64)),
+            }),
+            Update::Track(None) => controls.set_metadata(MediaMetadata::default()),
+            Update::Playback { status, position } => {
+                let progress = Some(MediaPosition(Duration::from_nanos(position.nseconds())));
+
+                controls.set_playback(match status {
+                    GstState::Playing => MediaPlayback::Playing { progress },
+                    GstState::Paused => MediaPlayback::Paused { progress },
+                    _ => MediaPlayback::Stopped,
+                })
+            }
+        };
+
+        if let Err(error) = result {
+            debug!(?error, "failed to update the media controls");
+        }
+    }
+}
+
+/// Wait for the next update, handling the window messages in the meantime.
+#[cfg(target_os = "windows")]
+fn next_update(updates: &flume::Receiver<Update>) -> Option<Update> {
+    use windows_sys::Win32::UI::WindowsAndMessaging::{
+        DispatchMessageW, PeekMessageW, TranslateMessage, MSG, PM_REMOVE,
+    };
+
+    loop {
+        unsafe {
+            let mut msg: MSG = std::mem::zeroed();
+
+            while PeekMessageW(&mut msg, 0, 0, 0, PM_REMOVE) != 0 {
+                TranslateMessage(&msg);
+                DispatchMessageW(&msg);
+            }
+        }
+
+        match updates.recv_timeout(PUMP_INTERVAL) {
+            Ok(update) => return Some(update),
+            Err(flume::RecvTimeoutError::Timeout) => continue,
+            Err(flume::RecvTimeoutError::Disconnected) => return None,
+        }
+    }
+}
+
+#[cfg(not(target_os = "windows"))]
+fn next_update(updates: &flume::Receiver<Update>) -> Option<Update> {
+    updates.recv().ok()
+}
+
+/// The Windows controls belong to a window, for a terminal program that's the console's.
+#[cfg(target_os = "windows")]
+fn window() -> Option<*mut c_void> {
+    let hwnd = unsafe { windows_sys::Win32::System::Console::GetConsoleWindow() };
+
+    (hwnd != 0).then_some(hwnd as *mut c_void)
+}
+
+#[cfg(not(target_os = "windows"))]
+fn window() -> Option<*mut c_void> {
+    None
+}
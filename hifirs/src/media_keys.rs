@@ -0,0 +1,194 @@
+use crate::player;
+use clap::ValueEnum;
+use serde::{Deserialize, Serialize};
+use std::{fmt::Display, str::FromStr};
+
+/// What a media key does when it is pressed.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, ValueEnum)]
+#[serde(rename_all = "kebab-case")]
+pub enum MediaKeyAction {
+    PlayPause,
+    Play,
+    Pause,
+    Next,
+    Previous,
+    Stop,
+    JumpForward,
+    JumpBackward,
+    StartOver,
+    RestartList,
+}
+
+impl Display for MediaKeyAction {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self.to_possible_value() {
+            Some(value) => f.write_str(value.get_name()),
+            None => Ok(()),
+        }
+    }
+}
+
+impl MediaKeyAction {
+    pub async fn run(self) -> player::Result<()> {
+        match self {
+            MediaKeyAction::PlayPause => player::play_pause().await,
+            MediaKeyAction::Play => player::play().await,
+            MediaKeyAction::Pause => player::pause().await,
+            MediaKeyAction::Next => player::next().await,
+            MediaKeyAction::Previous => player::previous().await,
+            MediaKeyAction::Stop => player::stop().await,
+            MediaKeyAction::JumpForward => player::jump_forward().await,
+            MediaKeyAction::JumpBackward => player::jump_backward().await,
+            MediaKeyAction::StartOver => player::start_over().await,
+            MediaKeyAction::RestartList => player::restart_list().await,
+        }
+    }
+}
+
+/// Keys, named the way the kernel does (`KEY_PLAYPAUSE`), and what they do.
+/// Written as `KEY_PLAYPAUSE=play-pause,KEY_NEXTSONG=next`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct KeyMap(Vec<(String, MediaKeyAction)>);
+
+impl Default for KeyMap {
+    fn default() -> Self {
+        Self(vec![
+            ("KEY_PLAYPAUSE".to_string(), MediaKeyAction::PlayPause),
+            ("KEY_PLAYCD".to_string(), MediaKeyAction::Play),
+            ("KEY_PAUSECD".to_string(), MediaKeyAction::Pause),
+            ("KEY_NEXTSONG".to_string(), MediaKeyAction::Next),
+            ("KEY_PREVIOUSSONG".to_string(), MediaKeyAction::Previous),
+            ("KEY_STOPCD".to_string(), MediaKeyAction::Stop),
+            ("KEY_FASTFORWARD".to_string(), MediaKeyAction::JumpForward),
+            ("KEY_REWIND".to_string(), MediaKeyAction::JumpBackward),
+        ])
+    }
+}
+
+impl KeyMap {
+    pub fn action(&self, key: &str) -> Option<MediaKeyAction> {
+        self.0.iter().find(|(k, _)| k == key).map(|(_, a)| *a)
+    }
+}
+
+impl FromStr for KeyMap {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mut map = Vec::new();
+
+        for entry in s.split(',').map(str::trim).filter(|e| !e.is_empty()) {
+            let Some((key, action)) = entry.split_once('=') else {
+                return Err(format!("expected KEY_NAME=action, got {entry}"));
+            };
+
+            let key = key.trim().to_uppercase();
+
+            if !key.starts_with("KEY_") || key.len() == 4 {
+                return Err(format!("{key} is not a key name like KEY_PLAYPAUSE"));
+            }
+
+            let action = MediaKeyAction::from_str(action.trim(), true)?;
+
+            map.retain(|(k, _): &(String, MediaKeyAction)| *k != key);
+            map.push((key, action));
+        }
+
+        if map.is_empty() {
+            return Err("expected at least one KEY_NAME=action".to_string());
+        }
+
+        Ok(Self(map))
+    }
+}
+
+impl Display for KeyMap {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let entries = self
+            .0
+            .iter()
+            .map(|(key, action)| format!("{key}={action}"))
+            .collect::<Vec<String>>();
+
+        f.write_str(&entries.join(","))
+    }
+}
+
+/// Listens for media keys on every input device that has one of the mapped keys and runs
+/// their actions, for desktops without an MPRIS applet. Devices are read, not grabbed, so
+/// other programs still see the keys. Reading input devices usually needs the user to be
+/// in the `input` group.
+#[cfg(all(target_os = "linux", feature = "media-keys"))]
+pub async fn listen_loop(map: KeyMap) {
+    use crate::player::notification::Notification;
+    use evdev::InputEventKind;
+    use futures::StreamExt;
+
+    let devices = evdev::enumerate()
+        .map(|(_, device)| device)
+        .filter(|device| {
+            device.supported_keys().is_some_and(|supported| {
+                supported
+                    .iter()
+                    .any(|key| map.action(&format!("{key:?}")).is_some())
+            })
+        })
+        .collect::<Vec<evdev::Device>>();
+
+    if devices.is_empty() {
+        warn!("no readable input device has media keys, is the user in the input group?");
+        return;
+    }
+
+    let (tx, rx) = flume::unbounded::<MediaKeyAction>();
+
+    for device in devices {
+        debug!(
+            "listening for media keys on {}",
+            device.name().unwrap_or("unknown device")
+        );
+
+        let mut events = match device.into_event_stream() {
+            Ok(events) => events,
+            Err(error) => {
+                debug!(?error, "failed to read input device");
+                continue;
+            }
+        };
+        let tx = tx.clone();
+        let map = map.clone();
+
+        tokio::spawn(async move {
+            while let Ok(event) = events.next_event().await {
+                // 1 is a press, 0 a release and 2 a key held down.
+                if let (InputEventKind::Key(key), 1) = (event.kind(), event.value()) {
+                    if let Some(action) = map.action(&format!("{key:?}")) {
+                        if tx.send_async(action).await.is_err() {
+                            break;
+                        }
+                    }
+                }
+            }
+        });
+    }
+
+    let mut actions = rx.stream();
+    let mut receiver = player::notify_receiver();
+
+    loop {
+        tokio::select! {
+            Some(action) = actions.next() => {
+                debug!(%action, "media key pressed");
+
+                if let Err(error) = action.run().await {
+                    debug!(?error);
+                }
+            }
+            Some(notification) = receiver.next() => {
+                if notification == Notification::Quit {
+                    break;
+                }
+            }
+        }
+    }
+}
@@ -1,12 +1,49 @@
 use crate::{
-    player::{self, notification::Notification},
-    service::{Album, Track},
+    player::{self, notification::Notification, party, queue::TrackListValue},
+    service::{Album, Playlist, Track},
 };
 use chrono::{DateTime, Duration, Local};
-use futures::executor::block_on;
 use gstreamer::{ClockTime, State as GstState};
-use std::collections::HashMap;
-use zbus::{fdo::Result, interface, zvariant, Connection, ConnectionBuilder, SignalContext};
+use std::{collections::HashMap, str::FromStr};
+use zbus::{
+    fdo::Result,
+    interface,
+    zvariant::{self, ObjectPath, OwnedObjectPath},
+    Connection, ConnectionBuilder, SignalContext,
+};
+
+const TRACK_PATH: &str = "/org/hifirs/Player/TrackList";
+const NO_TRACK_PATH: &str = "/org/mpris/MediaPlayer2/TrackList/NoTrack";
+const PLAYLIST_PATH: &str = "/org/hifirs/Playlists";
+
+/// A playlist as MPRIS describes it: its id, name and icon.
+type MprisPlaylist = (OwnedObjectPath, String, String);
+
+fn object_path(path: String) -> OwnedObjectPath {
+    OwnedObjectPath::try_from(path).expect("invalid object path")
+}
+
+/// Tracks are identified by their position in the queue, which is unique unlike their id.
+fn track_path(position: u32) -> OwnedObjectPath {
+    object_path(format!("{TRACK_PATH}/{position}"))
+}
+
+/// The id at the end of an object path under `prefix`.
+fn path_id<T: FromStr>(path: &ObjectPath<'_>, prefix: &str) -> Option<T> {
+    path.as_str()
+        .strip_prefix(prefix)?
+        .strip_prefix('/')?
+        .parse()
+        .ok()
+}
+
+fn playlist_to_mpris(playlist: &Playlist) -> MprisPlaylist {
+    (
+        object_path(format!("{PLAYLIST_PATH}/{}", playlist.id)),
+        playlist.title.clone(),
+        playlist.cover_art.clone().unwrap_or_default(),
+    )
+}
 
 #[derive(Debug)]
 pub struct Mpris {}
@@ -21,10 +58,13 @@ pub async fn init() -> Connection {
         can_play: true,
         can_pause: true,
         can_stop: true,
-        can_next: true,
-        can_previous: true,
+        can_next: false,
+        can_previous: false,
+        can_seek: false,
     };
     let mpris_tracklist = MprisTrackList {};
+    let mpris_playlists = MprisPlaylists {};
+    let mpris_extensions = MprisExtensions {};
 
     let conn = ConnectionBuilder::session()
         .unwrap()
@@ -34,6 +74,10 @@ pub async fn init() -> Connection {
         .unwrap()
         .serve_at("/org/mpris/MediaPlayer2", mpris_tracklist)
         .unwrap()
+        .serve_at("/org/mpris/MediaPlayer2", mpris_playlists)
+        .unwrap()
+        .serve_at("/org/mpris/MediaPlayer2", mpris_extensions)
+        .unwrap()
         .name("org.mpris.MediaPlayer2.hifirs")
         .unwrap()
         .build()
@@ -64,7 +108,7 @@ pub async fn receive_notifications(conn: &Connection) {
                     target_state: _,
                 } => {}
                 Notification::Buffering {
-                    is_buffering: _,
+                    is_buffering,
                     target_state: _,
                     percent: _,
                 } => {
@@ -73,9 +117,16 @@ pub async fn receive_notifications(conn: &Connection) {
                         .await
                         .expect("failed to get object server");
 
-                    iface_ref
-                        .get_mut()
+                    let mut iface = iface_ref.get_mut().await;
+                    let can_seek =
+                        !is_buffering && !player::is_live() && iface.status != GstState::Null;
+
+                    iface
+                        .set_can_seek(iface_ref.signal_context(), can_seek)
                         .await
+                        .expect("failed to signal capability change");
+
+                    iface
                         .playback_status_changed(iface_ref.signal_context())
                         .await
                         .expect("failed to signal metadata change");
@@ -113,6 +164,14 @@ pub async fn receive_notifications(conn: &Connection) {
                         }
                     }
 
+                    let can_seek =
+                        status != GstState::Null && !player::is_live() && !player::is_buffering();
+
+                    iface
+                        .set_can_seek(iface_ref.signal_context(), can_seek)
+                        .await
+                        .expect("failed to signal capability change");
+
                     iface
                         .playback_status_changed(iface_ref.signal_context())
                         .await
@@ -156,26 +215,36 @@ pub async fn receive_notifications(conn: &Connection) {
                         player_iface.total_tracks = album.total_tracks;
                     }
 
-                    if let Some(current) = list.current_track() {
-                        player_iface.can_previous = current.position != 0;
-
-                        player_iface.can_next = !(player_iface.total_tracks != 0
-                            && current.position == player_iface.total_tracks - 1);
+                    player_iface
+                        .set_navigation(player_ref.signal_context(), &list)
+                        .await
+                        .expect("failed to signal capability change");
+
+                    let tracks = list
+                        .queue
+                        .keys()
+                        .map(|position| track_path(*position))
+                        .collect::<Vec<OwnedObjectPath>>();
+                    let current = list
+                        .current_track()
+                        .map(|t| track_path(t.position))
+                        .unwrap_or_else(|| object_path(NO_TRACK_PATH.to_string()));
+
+                    MprisTrackList::track_list_replaced(list_ref.signal_context(), tracks, current)
+                        .await
+                        .expect("failed to send track list replaced signal");
 
-                        let tracks = list
-                            .cursive_list()
-                            .iter()
-                            .map(|t| t.0)
-                            .collect::<Vec<&str>>();
+                    let playlists_ref = object_server
+                        .interface::<_, MprisPlaylists>("/org/mpris/MediaPlayer2")
+                        .await
+                        .expect("failed to get object server");
 
-                        MprisTrackList::track_list_replaced(
-                            list_ref.signal_context(),
-                            tracks,
-                            &current.title,
-                        )
+                    playlists_ref
+                        .get()
                         .await
-                        .expect("failed to send track list replaced signal");
-                    }
+                        .active_playlist_changed(playlists_ref.signal_context())
+                        .await
+                        .expect("failed to signal active playlist change");
 
                     player_iface
                         .metadata_changed(player_ref.signal_context())
@@ -187,6 +256,80 @@ pub async fn receive_notifications(conn: &Connection) {
                     bitdepth: _,
                     sampling_rate: _,
                 } => {}
+                Notification::Level { rms: _, peak: _ } => {}
+                Notification::Spectrum { magnitudes: _ } => {}
+                Notification::ClientConnected { client: _ } => {}
+                Notification::ClientDisconnected { client: _ } => {}
+                Notification::ReleaseAvailable { album: _ } => {}
+                Notification::TrackAbandoned { .. } => {}
+                Notification::TrackUnavailable { .. } => {}
+                Notification::SessionSummary { .. } => {}
+                Notification::Stats { .. } => {}
+                Notification::OutputProfileChanged { .. } => {}
+                Notification::Volume { .. } => {
+                    let player_ref = object_server
+                        .interface::<_, MprisPlayer>("/org/mpris/MediaPlayer2")
+                        .await
+                        .expect("failed to get object server");
+
+                    player_ref
+                        .get()
+                        .await
+                        .volume_changed(player_ref.signal_context())
+                        .await
+                        .expect("failed to signal volume change");
+                }
+                Notification::Offline { .. } => {}
+                Notification::SignedIn => {
+                    let playlists_ref = object_server
+                        .interface::<_, MprisPlaylists>("/org/mpris/MediaPlayer2")
+                        .await
+                        .expect("failed to get object server");
+
+                    playlists_ref
+                        .get()
+                        .await
+                        .playlist_count_changed(playlists_ref.signal_context())
+                        .await
+                        .expect("failed to signal playlist count change");
+                }
+                Notification::ConfigReloaded { .. } => {}
+                Notification::GaplessPrepFailed { .. } => {}
+                Notification::AutoplayAppended { .. } => {}
+                Notification::PartyMode { .. } => {
+                    let list_ref = object_server
+                        .interface::<_, MprisTrackList>("/org/mpris/MediaPlayer2")
+                        .await
+                        .expect("failed to get object server");
+
+                    list_ref
+                        .get()
+                        .await
+                        .can_edit_tracks_changed(list_ref.signal_context())
+                        .await
+                        .expect("failed to signal capability change");
+                }
+                Notification::Shuffle { .. } => {
+                    let player_ref = object_server
+                        .interface::<_, MprisPlayer>("/org/mpris/MediaPlayer2")
+                        .await
+                        .expect("failed to get object server");
+
+                    player_ref
+                        .get()
+                        .await
+                        .shuffle_changed(player_ref.signal_context())
+                        .await
+                        .expect("failed to signal shuffle change");
+                }
+                Notification::SimpleMode { .. } => {}
+                Notification::Crossfade { .. } => {}
+                Notification::AccountLimited { .. } => {}
+                Notification::ServiceOutage { .. } => {}
+                Notification::ListProgress { .. } => {}
+                Notification::Audition { .. } => {}
+                Notification::AuditionFinished { .. } => {}
+                Notification::NetworkChanged { .. } => {}
             }
         }
     }
@@ -195,6 +338,11 @@ pub async fn receive_notifications(conn: &Connection) {
 #[interface(name = "org.mpris.MediaPlayer2")]
 impl Mpris {
     async fn quit(&self) -> Result<()> {
+        if party::is_enabled() {
+            debug!("party mode is on, ignoring quit");
+            return Ok(());
+        }
+
         if let Err(error) = player::quit().await {
             debug!(?error);
         }
@@ -243,6 +391,49 @@ pub struct MprisPlayer {
     can_stop: bool,
     can_next: bool,
     can_previous: bool,
+    can_seek: bool,
+}
+
+impl MprisPlayer {
+    /// Work out whether there is a track to go to in either direction
+    /// and signal the properties that changed.
+    async fn set_navigation(
+        &mut self,
+        ctxt: &SignalContext<'_>,
+        list: &TrackListValue,
+    ) -> zbus::Result<()> {
+        let (can_next, can_previous) = match list.current_track() {
+            Some(current) => (
+                list.next_playable(current.position).is_some(),
+                list.queue
+                    .keys()
+                    .next()
+                    .is_some_and(|first| current.position > *first),
+            ),
+            None => (false, false),
+        };
+
+        if can_next != self.can_next {
+            self.can_next = can_next;
+            self.can_go_next_changed(ctxt).await?;
+        }
+
+        if can_previous != self.can_previous {
+            self.can_previous = can_previous;
+            self.can_go_previous_changed(ctxt).await?;
+        }
+
+        Ok(())
+    }
+
+    async fn set_can_seek(&mut self, ctxt: &SignalContext<'_>, can_seek: bool) -> zbus::Result<()> {
+        if can_seek != self.can_seek {
+            self.can_seek = can_seek;
+            self.can_seek_changed(ctxt).await?;
+        }
+
+        Ok(())
+    }
 }
 
 #[interface(name = "org.mpris.MediaPlayer2.Player")]
@@ -263,6 +454,11 @@ impl MprisPlayer {
         }
     }
     async fn stop(&self) {
+        if party::is_enabled() {
+            debug!("party mode is on, ignoring stop");
+            return;
+        }
+
         if let Err(error) = player::stop().await {
             debug!(?error);
         }
@@ -282,6 +478,28 @@ impl MprisPlayer {
             debug!(?error);
         }
     }
+    /// Seek by `offset` microseconds, backwards when negative.
+    async fn seek(&self, offset: i64) {
+        let current = player::position().unwrap_or_default().useconds() as i64;
+        let target = (current + offset).max(0) as u64;
+
+        if let Err(error) = player::seek_to(ClockTime::from_useconds(target)).await {
+            debug!(?error);
+        }
+    }
+    /// Seek to `position` microseconds. Ignored when `track_id` is no longer the current
+    /// track, so a late request doesn't seek in the next one.
+    async fn set_position(&self, track_id: ObjectPath<'_>, position: i64) {
+        let current = player::current_track().await.map(|track| track.position);
+
+        if position < 0 || path_id::<u32>(&track_id, TRACK_PATH) != current {
+            return;
+        }
+
+        if let Err(error) = player::seek_to(ClockTime::from_useconds(position as u64)).await {
+            debug!(?error);
+        }
+    }
     #[zbus(property, name = "PlaybackStatus")]
     async fn playback_status(&self) -> &str {
         match self.status {
@@ -301,8 +519,14 @@ impl MprisPlayer {
         1.0
     }
     #[zbus(property, name = "Shuffle")]
-    fn shuffle(&self) -> bool {
-        false
+    async fn shuffle(&self) -> bool {
+        player::is_shuffled().await
+    }
+    #[zbus(property, name = "Shuffle")]
+    async fn set_shuffle(&mut self, shuffle: bool) {
+        if let Err(error) = player::set_shuffle(shuffle).await {
+            debug!(?error);
+        }
     }
     #[zbus(property, name = "Metadata")]
     async fn metadata(&self) -> HashMap<&str, zvariant::Value> {
@@ -318,7 +542,13 @@ impl MprisPlayer {
     }
     #[zbus(property, name = "Volume")]
     fn volume(&self) -> f64 {
-        1.0
+        player::volume()
+    }
+    #[zbus(property, name = "Volume")]
+    async fn set_volume(&mut self, volume: f64) {
+        if let Err(error) = player::set_volume(volume).await {
+            debug!(?error);
+        }
     }
     #[zbus(property, name = "Position")]
     async fn position(&self) -> i64 {
@@ -359,7 +589,7 @@ impl MprisPlayer {
     }
     #[zbus(property, name = "CanSeek")]
     fn can_seek(&self) -> bool {
-        true
+        self.can_seek
     }
     #[zbus(property, name = "CanControl")]
     fn can_control(&self) -> bool {
@@ -367,6 +597,39 @@ impl MprisPlayer {
     }
 }
 
+/// Actions MPRIS has no method for, under hifi-rs' own interface name.
+#[derive(Debug)]
+pub struct MprisExtensions {}
+
+#[interface(name = "rs.hifi.Player")]
+impl MprisExtensions {
+    async fn start_over(&self) {
+        if let Err(error) = player::start_over().await {
+            debug!(?error);
+        }
+    }
+    async fn restart_list(&self) {
+        if let Err(error) = player::restart_list().await {
+            debug!(?error);
+        }
+    }
+    /// Move an upcoming track to the place of another one, MPRIS' track list can't reorder.
+    async fn move_track(&self, track_id: ObjectPath<'_>, to_track_id: ObjectPath<'_>) {
+        let (Some(from), Some(to)) = (
+            path_id::<u32>(&track_id, TRACK_PATH),
+            path_id::<u32>(&to_track_id, TRACK_PATH),
+        ) else {
+            return;
+        };
+
+        let generation = player::current_tracklist().await.generation();
+
+        if let Err(error) = player::move_in_queue(from, to, generation).await {
+            debug!(?error);
+        }
+    }
+}
+
 #[derive(Debug)]
 pub struct MprisTrackList {}
 
@@ -374,29 +637,54 @@ pub struct MprisTrackList {}
 impl MprisTrackList {
     async fn get_tracks_metadata(
         &self,
-        tracks: Vec<String>,
+        track_ids: Vec<OwnedObjectPath>,
     ) -> Vec<HashMap<&str, zvariant::Value>> {
         debug!("get tracks metadata");
 
-        player::current_tracklist()
-            .await
-            .all_tracks()
-            .into_iter()
-            .filter_map(|i| {
-                if tracks.contains(&i.position.to_string()) {
-                    let album =
-                        block_on(async { player::current_tracklist().await.get_album().cloned() });
-                    Some(track_to_meta(i.clone(), album))
-                } else {
-                    None
-                }
-            })
+        let list = player::current_tracklist().await;
+        let album = list.get_album().cloned();
+
+        track_ids
+            .iter()
+            .filter_map(|id| path_id::<u32>(id, TRACK_PATH))
+            .filter_map(|position| list.find_track_by_index(position))
+            .map(|track| track_to_meta(track.clone(), album.clone()))
             .collect::<Vec<HashMap<&str, zvariant::Value>>>()
     }
 
-    async fn go_to(&self, position: String) {
-        if let Ok(p) = position.parse::<u32>() {
-            if let Err(error) = player::skip(p, true).await {
+    /// Only appending is supported, `after_track` is ignored.
+    async fn add_track(&self, uri: &str, _after_track: ObjectPath<'_>, set_as_current: bool) {
+        let result = if set_as_current {
+            player::play_uri(uri).await
+        } else {
+            player::add_uri_to_queue(uri).await
+        };
+
+        if let Err(error) = result {
+            debug!(?error);
+        }
+    }
+
+    async fn remove_track(&self, track_id: ObjectPath<'_>) {
+        let Some(position) = path_id::<u32>(&track_id, TRACK_PATH) else {
+            return;
+        };
+
+        let generation = player::current_tracklist().await.generation();
+
+        if let Err(error) = player::remove_from_queue(position, generation).await {
+            debug!(?error);
+        }
+    }
+
+    async fn go_to(&self, track_id: ObjectPath<'_>) {
+        if party::is_enabled() {
+            debug!("party mode is on, ignoring go to");
+            return;
+        }
+
+        if let Some(position) = path_id::<u32>(&track_id, TRACK_PATH) {
+            if let Err(error) = player::skip(position, true).await {
                 debug!(?error);
             }
         }
@@ -405,23 +693,85 @@ impl MprisTrackList {
     #[zbus(signal, name = "TrackListReplaced")]
     pub async fn track_list_replaced(
         #[zbus(signal_context)] ctxt: &SignalContext<'_>,
-        tracks: Vec<&str>,
-        current: &str,
+        tracks: Vec<OwnedObjectPath>,
+        current_track: OwnedObjectPath,
     ) -> zbus::Result<()>;
 
     #[zbus(property, name = "Tracks")]
-    async fn tracks(&self) -> Vec<String> {
+    async fn tracks(&self) -> Vec<OwnedObjectPath> {
         player::current_tracklist()
             .await
-            .unplayed_tracks()
-            .iter()
-            .map(|i| i.position.to_string())
-            .collect::<Vec<String>>()
+            .queue
+            .keys()
+            .map(|position| track_path(*position))
+            .collect::<Vec<OwnedObjectPath>>()
     }
 
     #[zbus(property, name = "CanEditTracks")]
     async fn can_edit_tracks(&self) -> bool {
-        false
+        !party::is_enabled()
+    }
+}
+
+/// The user's Qobuz playlists, so they can be started from the desktop.
+#[derive(Debug)]
+pub struct MprisPlaylists {}
+
+#[interface(name = "org.mpris.MediaPlayer2.Playlists")]
+impl MprisPlaylists {
+    async fn activate_playlist(&self, playlist_id: ObjectPath<'_>) {
+        if let Some(id) = path_id::<i64>(&playlist_id, PLAYLIST_PATH) {
+            if let Err(error) = player::play_playlist(id).await {
+                debug!(?error);
+            }
+        }
+    }
+
+    async fn get_playlists(
+        &self,
+        index: u32,
+        max_count: u32,
+        order: &str,
+        reverse_order: bool,
+    ) -> Vec<MprisPlaylist> {
+        let mut playlists = player::user_playlists().await;
+
+        // Qobuz has no dates for playlists, so anything but alphabetical is the user's order.
+        if order == "Alphabetical" {
+            playlists.sort_by_key(|p| p.title.to_lowercase());
+        }
+
+        if reverse_order {
+            playlists.reverse();
+        }
+
+        playlists
+            .iter()
+            .skip(index as usize)
+            .take(max_count as usize)
+            .map(playlist_to_mpris)
+            .collect()
+    }
+
+    #[zbus(property, name = "PlaylistCount")]
+    async fn playlist_count(&self) -> u32 {
+        player::user_playlists().await.len() as u32
+    }
+
+    #[zbus(property, name = "Orderings")]
+    fn orderings(&self) -> Vec<&str> {
+        vec!["Alphabetical", "UserDefined"]
+    }
+
+    #[zbus(property, name = "ActivePlaylist")]
+    async fn active_playlist(&self) -> (bool, MprisPlaylist) {
+        match player::current_tracklist().await.get_playlist() {
+            Some(playlist) => (true, playlist_to_mpris(playlist)),
+            None => (
+                false,
+                (object_path("/".to_string()), String::new(), String::new()),
+            ),
+        }
     }
 }
 
@@ -433,10 +783,7 @@ fn track_to_meta<'a>(
 
     meta.insert(
         "mpris:trackid",
-        zvariant::Value::new(format!(
-            "/org/hifirs/Player/TrackList/{}",
-            playlist_track.id
-        )),
+        zvariant::Value::new(track_path(playlist_track.position)),
     );
     meta.insert(
         "xesam:title",
@@ -444,7 +791,11 @@ fn track_to_meta<'a>(
     );
     meta.insert(
         "xesam:trackNumber",
-        zvariant::Value::new(playlist_track.position as i32),
+        zvariant::Value::new(playlist_track.number as i32),
+    );
+    meta.insert(
+        "xesam:discNumber",
+        zvariant::Value::new(playlist_track.media_number as i32),
     );
 
     meta.insert(
@@ -454,14 +805,14 @@ fn track_to_meta<'a>(
         ),
     );
 
-    if let Some(artist) = &playlist_track.artist {
-        meta.insert(
-            "xesam:artist",
-            zvariant::Value::new(artist.name.trim().to_string()),
-        );
+    if let Some(url) = &playlist_track.url {
+        meta.insert("xesam:url", zvariant::Value::new(url.clone()));
     }
 
-    if let Some(album) = album {
+    // Tracks in a playlist carry their own album.
+    let album = album.or(playlist_track.album.clone());
+
+    if let Some(album) = &album {
         meta.insert(
             "mpris:artUrl",
             zvariant::Value::new(album.cover_art.clone()),
@@ -472,11 +823,27 @@ fn track_to_meta<'a>(
         );
         meta.insert(
             "xesam:albumArtist",
-            zvariant::Value::new(album.artist.name.trim().to_string()),
+            zvariant::Value::new(vec![album.artist.name.trim().to_string()]),
         );
+
+        if let Some(genre) = &album.genre {
+            meta.insert("xesam:genre", zvariant::Value::new(vec![genre.clone()]));
+        }
+
+        if let Some(date) = &album.release_date {
+            meta.insert("xesam:contentCreated", zvariant::Value::new(date.clone()));
+        }
+    }
+
+    let artist = playlist_track
+        .artist
+        .as_ref()
+        .or(album.as_ref().map(|a| &a.artist));
+
+    if let Some(artist) = artist {
         meta.insert(
             "xesam:artist",
-            zvariant::Value::new(album.artist.name.trim().to_string()),
+            zvariant::Value::new(vec![artist.name.trim().to_string()]),
         );
     }
 
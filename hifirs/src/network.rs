@@ -0,0 +1,221 @@
+use crate::{
+    player::{self, error::Error, notification::Notification},
+    service::{self, Credentials},
+};
+use futures::StreamExt;
+use hifirs_qobuz_api::client::{health, throttle};
+use std::{
+    net::{IpAddr, Ipv4Addr, Ipv6Addr, UdpSocket},
+    time::Duration,
+};
+use tokio::time::Instant;
+
+/// How often the local address is checked for a network change.
+pub const CHECK_INTERVAL: Duration = Duration::from_secs(5);
+/// The first wait before signing in again after starting offline. It doubles after every
+/// failed attempt, up to [`LOGIN_RETRY_MAX`].
+pub const LOGIN_RETRY_MIN: Duration = Duration::from_secs(5);
+pub const LOGIN_RETRY_MAX: Duration = Duration::from_secs(300);
+/// How often an outage of the service is looked at, to count down to the next check.
+const OUTAGE_TICK: Duration = Duration::from_secs(1);
+
+/// The address this machine currently uses to reach the internet.
+/// Connecting a UDP socket sends nothing, it only asks the OS for a route.
+pub fn local_address() -> Option<IpAddr> {
+    let route = |bind: IpAddr, remote: IpAddr| {
+        let socket = UdpSocket::bind((bind, 0)).ok()?;
+        socket.connect((remote, 53)).ok()?;
+        socket.local_addr().ok().map(|a| a.ip())
+    };
+
+    route(
+        Ipv4Addr::UNSPECIFIED.into(),
+        Ipv4Addr::new(1, 1, 1, 1).into(),
+    )
+    .or_else(|| {
+        route(
+            Ipv6Addr::UNSPECIFIED.into(),
+            Ipv6Addr::new(0x2606, 0x4700, 0x4700, 0, 0, 0, 0, 0x1111).into(),
+        )
+    })
+}
+
+/// Watches for the machine moving to another network, then drops the connections
+/// to the service and tells the remote interfaces to rebind.
+pub async fn watch_loop() {
+    let mut interval = tokio::time::interval(CHECK_INTERVAL);
+    let mut receiver = player::notify_receiver();
+    let mut current = local_address();
+
+    loop {
+        tokio::select! {
+            _ = interval.tick() => {
+                let address = local_address();
+
+                if address == current {
+                    continue;
+                }
+
+                info!(?current, ?address, "network changed");
+                current = address;
+
+                player::reconnect_service().await;
+
+                if let Err(error) = player::notify(Notification::NetworkChanged { address }).await {
+                    debug!(?error);
+                }
+            }
+            Some(notification) = receiver.next() => {
+                if notification == Notification::Quit {
+                    break;
+                }
+            }
+        }
+    }
+}
+
+/// Keeps trying to sign in after the player started offline, backing off between attempts
+/// and trying straight away when the network changes. Stops once signed in, or when the
+/// service answers but turns the credentials down.
+pub async fn login_loop(service_name: String, credentials: Credentials) {
+    let mut receiver = player::notify_receiver();
+    let mut delay = LOGIN_RETRY_MIN;
+    let mut next_attempt = Instant::now() + delay;
+
+    loop {
+        tokio::select! {
+            _ = tokio::time::sleep_until(next_attempt) => {}
+            Some(notification) = receiver.next() => {
+                match notification {
+                    Notification::Quit => break,
+                    Notification::NetworkChanged { .. } => delay = LOGIN_RETRY_MIN,
+                    _ => continue,
+                }
+            }
+        }
+
+        match service::build(&service_name, credentials.clone()).await {
+            Ok((service, true)) => {
+                info!("signed in");
+
+                if let Err(error) = player::sign_in(service).await {
+                    debug!(?error);
+                }
+
+                break;
+            }
+            Ok((_, false)) => {
+                delay = (delay * 2).min(LOGIN_RETRY_MAX);
+                next_attempt = Instant::now() + delay;
+                debug!(?delay, "still offline, retrying");
+            }
+            Err(error) => {
+                error!(%error, "failed to sign in");
+
+                if let Err(error) = player::notify(Notification::Error {
+                    error: error.into(),
+                })
+                .await
+                {
+                    debug!(?error);
+                }
+
+                break;
+            }
+        }
+    }
+}
+
+/// Follows an outage of the service: runs the health check when it is due, tells the
+/// front-ends when the outage starts and when the next check is, and resumes the playback
+/// that was held during the outage once the service is back. Calls that failed after all
+/// their retries are reported here too.
+pub async fn outage_loop() {
+    let mut interval = tokio::time::interval(OUTAGE_TICK);
+    let mut receiver = player::notify_receiver();
+    // The outage the front-ends were last told about.
+    let mut reported: Option<health::Outage> = None;
+
+    loop {
+        tokio::select! {
+            _ = interval.tick() => {}
+            Some(notification) = receiver.next() => {
+                if notification == Notification::Quit {
+                    break;
+                }
+
+                continue;
+            }
+        }
+
+        // Calls are retried quietly, only the ones that used up their retries are shown.
+        if let Some(gave_up) = throttle::take_gave_up() {
+            let error = Error::Client {
+                message: gave_up.to_string(),
+            };
+
+            if let Err(error) = player::notify(Notification::Error { error }).await {
+                debug!(?error);
+            }
+        }
+
+        let Some(mut outage) = health::outage() else {
+            // Any call that worked ends the outage, not only the health check.
+            if reported.take().is_some() {
+                recovered().await;
+            }
+
+            continue;
+        };
+
+        if reported.is_none() {
+            warn!(status = outage.status, "the service is unavailable");
+        }
+
+        if outage.retry_in() == 0 {
+            if player::service_healthy().await {
+                reported = None;
+                recovered().await;
+
+                continue;
+            }
+
+            match health::outage() {
+                Some(next) => outage = next,
+                None => continue,
+            }
+        }
+
+        if reported == Some(outage) {
+            continue;
+        }
+
+        reported = Some(outage);
+
+        let notification = Notification::ServiceOutage {
+            status: Some(outage.status),
+            retry_in: outage.retry_in(),
+        };
+
+        if let Err(error) = player::notify(notification).await {
+            debug!(?error);
+        }
+    }
+}
+
+async fn recovered() {
+    info!("the service is available again");
+
+    if let Err(error) = player::notify(Notification::ServiceOutage {
+        status: None,
+        retry_in: 0,
+    })
+    .await
+    {
+        debug!(?error);
+    }
+
+    if let Err(error) = player::resume_after_outage().await {
+        debug!(?error);
+    }
+}
@@ -0,0 +1,210 @@
+use crate::{
+    play_tracker::{PlayTracker, TrackPlay},
+    player::{self, notification::Notification},
+    service::{self, Track},
+};
+use chrono::{DateTime, Utc};
+use clap::ValueEnum;
+use futures::StreamExt;
+use serde::{Deserialize, Serialize};
+use std::{
+    fmt::Display,
+    fs::{self, OpenOptions},
+    io::Write,
+    path::{Path, PathBuf},
+};
+
+/// The log is rotated once it grows past this size.
+pub const PLAY_LOG_MAX_BYTES: u64 = 5 * 1024 * 1024;
+/// How many rotated logs are kept next to the current one.
+pub const PLAY_LOG_BACKUPS: u32 = 3;
+
+const TSV_HEADER: &str = "timestamp\tartist\ttitle\talbum\tduration_played\tquality";
+
+/// Whether plays are written to a local log, and in which format.
+#[derive(Default, Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, ValueEnum)]
+#[serde(rename_all = "lowercase")]
+pub enum PlayLogFormat {
+    /// Nothing is written.
+    #[default]
+    Off,
+    /// Tab separated values with a header line.
+    Tsv,
+    /// One JSON object per line.
+    Jsonl,
+}
+
+impl Display for PlayLogFormat {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            PlayLogFormat::Off => f.write_str("off"),
+            PlayLogFormat::Tsv => f.write_str("tsv"),
+            PlayLogFormat::Jsonl => f.write_str("jsonl"),
+        }
+    }
+}
+
+/// A single entry in the play log.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Play {
+    pub timestamp: DateTime<Utc>,
+    pub artist: String,
+    pub title: String,
+    pub album: String,
+    /// Seconds of the track that were played.
+    pub duration_played: u64,
+    pub quality: String,
+}
+
+impl Play {
+    fn new(track: &Track, timestamp: DateTime<Utc>, duration_played: u64, quality: &str) -> Self {
+        Self {
+            timestamp,
+            artist: track
+                .artist
+                .as_ref()
+                .map(|a| a.name.clone())
+                .unwrap_or_default(),
+            title: track.title.trim().to_string(),
+            album: track
+                .album
+                .as_ref()
+                .map(|a| a.title.clone())
+                .unwrap_or_default(),
+            duration_played,
+            quality: if quality.is_empty() {
+                track.format_label()
+            } else {
+                quality.to_string()
+            },
+        }
+    }
+
+    fn to_tsv(&self) -> String {
+        let clean = |value: &str| value.replace(['\t', '\n', '\r'], " ");
+
+        format!(
+            "{}\t{}\t{}\t{}\t{}\t{}",
+            self.timestamp.to_rfc3339(),
+            clean(&self.artist),
+            clean(&self.title),
+            clean(&self.album),
+            self.duration_played,
+            clean(&self.quality)
+        )
+    }
+}
+
+/// Where the play log is written for `format`, if anywhere.
+pub fn path(format: PlayLogFormat) -> Option<PathBuf> {
+    let file_name = match format {
+        PlayLogFormat::Off => return None,
+        PlayLogFormat::Tsv => "plays.tsv",
+        PlayLogFormat::Jsonl => "plays.jsonl",
+    };
+
+    let mut path = dirs::data_local_dir()?;
+    path.push("hifi-rs");
+    path.push(file_name);
+
+    Some(path)
+}
+
+fn backup_path(path: &Path, index: u32) -> PathBuf {
+    let mut name = path.as_os_str().to_owned();
+    name.push(format!(".{index}"));
+
+    PathBuf::from(name)
+}
+
+/// Move the log out of the way once it is too big, keeping `PLAY_LOG_BACKUPS` old logs.
+fn rotate(path: &Path) -> std::io::Result<()> {
+    match fs::metadata(path) {
+        Ok(metadata) if metadata.len() >= PLAY_LOG_MAX_BYTES => {}
+        _ => return Ok(()),
+    }
+
+    for index in (1..PLAY_LOG_BACKUPS).rev() {
+        let from = backup_path(path, index);
+
+        if from.exists() {
+            fs::rename(&from, backup_path(path, index + 1))?;
+        }
+    }
+
+    fs::rename(path, backup_path(path, 1))
+}
+
+/// Append a play to the log at `path`.
+pub fn append(path: &Path, format: PlayLogFormat, play: &Play) -> std::io::Result<()> {
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent)?;
+    }
+
+    rotate(path)?;
+
+    let is_new = fs::metadata(path).map(|m| m.len() == 0).unwrap_or(true);
+    let mut file = OpenOptions::new().create(true).append(true).open(path)?;
+
+    match format {
+        PlayLogFormat::Off => {}
+        PlayLogFormat::Tsv => {
+            if is_new {
+                writeln!(file, "{TSV_HEADER}")?;
+            }
+
+            writeln!(file, "{}", play.to_tsv())?;
+        }
+        PlayLogFormat::Jsonl => {
+            let line = serde_json::to_string(play).map_err(std::io::Error::other)?;
+            writeln!(file, "{line}")?;
+        }
+    }
+
+    Ok(())
+}
+
+/// Follows the notifications and writes every track that was played
+/// to the play log until the player quits.
+pub async fn record_loop(format: PlayLogFormat) {
+    let Some(path) = path(format) else {
+        return;
+    };
+
+    let mut receiver = player::notify_receiver();
+    let mut tracker = PlayTracker::default();
+    let mut quality = String::new();
+
+    let write = |finished: Option<TrackPlay>, quality: &str| {
+        if let Some(finished) = finished.filter(|play| play.heard_seconds > 0) {
+            let play = Play::new(
+                &finished.track,
+                finished.started_at,
+                finished.heard_seconds,
+                quality,
+            );
+
+            if let Err(error) = append(&path, format, &play) {
+                debug!(?error, "failed to write to the play log");
+            }
+        }
+    };
+
+    while let Some(notification) = receiver.next().await {
+        match notification {
+            Notification::AudioQuality {
+                bitdepth,
+                sampling_rate,
+            } => quality = service::format_label(bitdepth, sampling_rate as f32 / 1000.),
+            Notification::Quit => {
+                write(tracker.finish(), &quality);
+                break;
+            }
+            notification => {
+                if let Some(finished) = tracker.update(&notification) {
+                    write(finished, &quality);
+                }
+            }
+        }
+    }
+}
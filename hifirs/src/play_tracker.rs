@@ -0,0 +1,194 @@
+use crate::{player::notification::Notification, service::Track};
+use chrono::{DateTime, Utc};
+
+/// The position moving on by more than this between two notifications is a seek, not
+/// playback.
+const MAX_STEP_SECONDS: u64 = 2;
+/// A track going back to its start from this close to its end is played again, as on repeat.
+const REPEAT_WINDOW_SECONDS: u64 = 5;
+
+/// A track as it was played.
+#[derive(Debug, Clone, PartialEq)]
+pub struct TrackPlay {
+    pub track: Track,
+    pub started_at: DateTime<Utc>,
+    /// Seconds of the track that were heard. Seeks are left out.
+    pub heard_seconds: u64,
+}
+
+impl TrackPlay {
+    fn new(track: Track) -> Self {
+        Self {
+            track,
+            started_at: Utc::now(),
+            heard_seconds: 0,
+        }
+    }
+}
+
+/// Follows the notifications to tell which track plays and how much of it was heard,
+/// counted the way [`session::record_second`](crate::player::session::record_second) counts
+/// the listening time of the session. The history, the play log and the scrobblers go by it.
+#[derive(Debug, Default)]
+pub struct PlayTracker {
+    current: Option<TrackPlay>,
+    /// Where the current track is in the queue, to tell a track queued twice from itself.
+    queue_position: u32,
+    last_position: u64,
+}
+
+impl PlayTracker {
+    /// The track playing now.
+    pub fn current(&self) -> Option<&TrackPlay> {
+        self.current.as_ref()
+    }
+
+    /// Follow `notification`. Returns `Some` when another play started with it, holding
+    /// the one that ended if there was one. The new one is [`PlayTracker::current`].
+    pub fn update(&mut self, notification: &Notification) -> Option<Option<TrackPlay>> {
+        match notification {
+            Notification::Position { clock } => self.moved_to(clock.seconds()),
+            Notification::CurrentTrackList { list } => {
+                let playing = list.current_track();
+                let key = playing.map(|track| (track.id, track.position));
+                let current = self
+                    .current
+                    .as_ref()
+                    .map(|p| (p.track.id, self.queue_position));
+
+                if key == current {
+                    return None;
+                }
+
+                self.queue_position = playing.map(|track| track.position).unwrap_or_default();
+                self.last_position = 0;
+
+                Some(std::mem::replace(
+                    &mut self.current,
+                    playing.cloned().map(TrackPlay::new),
+                ))
+            }
+            _ => None,
+        }
+    }
+
+    /// The play that was going on, e.g. when the player quits.
+    pub fn finish(&mut self) -> Option<TrackPlay> {
+        self.current.take()
+    }
+
+    fn moved_to(&mut self, position: u64) -> Option<Option<TrackPlay>> {
+        let last_position = std::mem::replace(&mut self.last_position, position);
+        let play = self.current.as_mut()?;
+
+        if position > last_position && position - last_position <= MAX_STEP_SECONDS {
+            play.heard_seconds += position - last_position;
+            return None;
+        }
+
+        let duration = play.track.duration_seconds as u64;
+
+        if position < last_position
+            && position <= MAX_STEP_SECONDS
+            && last_position + REPEAT_WINDOW_SECONDS >= duration
+        {
+            let again = TrackPlay {
+                heard_seconds: position,
+                ..TrackPlay::new(play.track.clone())
+            };
+
+            return Some(self.current.replace(again));
+        }
+
+        None
+    }
+}
+
+#[cfg(test)]
+fn track(id: u32, position: u32) -> Track {
+    Track {
+        id,
+        number: position,
+        title: format!("track {id}"),
+        album: None,
+        artist: None,
+        duration_seconds: 200,
+        explicit: false,
+        hires_available: false,
+        sampling_rate: 44.1,
+        bit_depth: 16,
+        status: crate::service::TrackStatus::Playing,
+        track_url: None,
+        available: true,
+        cover_art: None,
+        position,
+        media_number: 1,
+        url: None,
+        playlist_track_id: None,
+        version: None,
+        replay_gain: None,
+        replay_peak: None,
+        fallback_quality: None,
+    }
+}
+
+#[cfg(test)]
+fn playing(track: Track) -> Notification {
+    let queue = [(track.position, track)].into_iter().collect();
+
+    Notification::CurrentTrackList {
+        list: crate::player::queue::TrackListValue::new(Some(&queue)),
+    }
+}
+
+#[cfg(test)]
+fn at(seconds: u64) -> Notification {
+    Notification::Position {
+        clock: gstreamer::ClockTime::from_seconds(seconds),
+    }
+}
+
+#[test]
+fn counts_only_heard_seconds() {
+    let mut tracker = PlayTracker::default();
+
+    assert_eq!(tracker.update(&playing(track(1, 0))), Some(None));
+
+    for seconds in 1..=10 {
+        tracker.update(&at(seconds));
+    }
+
+    // A seek ahead isn't heard, playing on from there is.
+    tracker.update(&at(150));
+    tracker.update(&at(151));
+
+    let finished = tracker
+        .update(&playing(track(2, 1)))
+        .flatten()
+        .expect("the first track wasn't finished");
+
+    assert_eq!(finished.track.id, 1);
+    assert_eq!(finished.heard_seconds, 11);
+    assert_eq!(tracker.current().map(|play| play.track.id), Some(2));
+}
+
+#[test]
+fn counts_a_repeated_track_again() {
+    let mut tracker = PlayTracker::default();
+
+    tracker.update(&playing(track(1, 0)));
+    tracker.update(&at(197));
+    tracker.update(&at(198));
+    assert_eq!(tracker.update(&playing(track(1, 0))), None);
+
+    let finished = tracker
+        .update(&at(0))
+        .flatten()
+        .expect("the repeat wasn't noticed");
+
+    assert_eq!(finished.heard_seconds, 1);
+    assert_eq!(tracker.current().map(|play| play.heard_seconds), Some(0));
+
+    // The same track queued again is another play too.
+    assert!(tracker.update(&playing(track(1, 1))).is_some());
+}
@@ -0,0 +1,46 @@
+use crate::{player::notification::Notification, service::StreamUrl};
+use hifirs_qobuz_api::client::AudioQuality;
+use once_cell::sync::Lazy;
+use std::sync::Mutex;
+
+static LIMITED_TO: Lazy<Mutex<Option<AudioQuality>>> = Lazy::new(|| Mutex::new(None));
+
+/// The best quality the account's subscription delivers, once a track came back below the
+/// requested quality because of it.
+pub fn limited_to() -> Option<AudioQuality> {
+    LIMITED_TO.lock().unwrap().clone()
+}
+
+/// Compare the quality of a fetched track url with the one requested. A limit stays until a
+/// track is delivered at the requested quality again, since tracks that are simply not
+/// available in hi-res say nothing about the account. Returns the notification for the
+/// player to send when the limit changed.
+pub fn record(url: &StreamUrl) -> Option<Notification> {
+    let limited_to = if url.limited {
+        Some(url.delivered.clone())
+    } else if url.delivered == url.requested {
+        None
+    } else {
+        return None;
+    };
+
+    let changed = {
+        let mut current = LIMITED_TO.lock().unwrap();
+        let changed = *current != limited_to;
+        *current = limited_to.clone();
+
+        changed
+    };
+
+    if !changed {
+        return None;
+    }
+
+    info!(
+        requested = ?url.requested,
+        delivered = ?url.delivered,
+        "account quality limit changed"
+    );
+
+    Some(Notification::AccountLimited { limited_to })
+}
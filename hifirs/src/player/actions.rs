@@ -1,3 +1,6 @@
+use crate::service::{BrowseCategory, SearchType};
+use gstreamer::ClockTime;
+use hifirs_qobuz_api::client::AudioQuality;
 use serde::{Deserialize, Serialize};
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -8,17 +11,181 @@ pub enum Action {
     PlayPause,
     Next,
     Previous,
+    StartOver,
+    RestartList,
     Stop,
     Quit,
-    SkipTo { num: u32 },
+    SkipTo {
+        num: u32,
+        generation: u64,
+    },
     JumpForward,
     JumpBackward,
-    PlayAlbum { album_id: String },
-    PlayTrack { track_id: i32 },
-    PlayUri { uri: String },
-    PlayPlaylist { playlist_id: i64 },
-    Search { query: String },
-    FetchArtistAlbums { artist_id: i32 },
-    FetchPlaylistTracks { playlist_id: i64 },
+    Seek {
+        seconds: i64,
+        #[serde(default)]
+        relative: bool,
+    },
+    /// Seek to a position in the current track, in nanoseconds.
+    SeekTo {
+        position: ClockTime,
+    },
+    /// Seek to a percentage of the current track, between 0 and 100.
+    SeekPercent(f32),
+    SetVolume {
+        volume: f64,
+    },
+    ToggleShuffle,
+    Status,
+    FetchTrackList,
+    RemoveFromQueue {
+        position: u32,
+        generation: u64,
+    },
+    MoveInQueue {
+        from: u32,
+        to: u32,
+        generation: u64,
+    },
+    SetQuality {
+        quality: AudioQuality,
+    },
+    /// Play the next album, playlist or track at `quality`, or at the session's with none.
+    SetNextQuality {
+        quality: Option<AudioQuality>,
+    },
+    SetOutputProfile {
+        name: String,
+    },
+    PlayAlbum {
+        album_id: String,
+        /// Play it at this quality instead of the session's.
+        #[serde(default)]
+        quality: Option<AudioQuality>,
+    },
+    PlayTrack {
+        track_id: i32,
+    },
+    PlayAlbumFromTrack {
+        album_id: String,
+        track_id: i32,
+    },
+    AddTrackToQueue {
+        track_id: i32,
+    },
+    AddAlbumToQueue {
+        album_id: String,
+    },
+    PlayUri {
+        uri: String,
+    },
+    PlayPlaylist {
+        playlist_id: i64,
+    },
+    Search {
+        query: String,
+        search_type: Option<SearchType>,
+        limit: Option<u32>,
+    },
+    FetchArtistAlbums {
+        artist_id: i32,
+    },
+    FetchPlaylistTracks {
+        playlist_id: i64,
+    },
+    FetchPlaylist {
+        playlist_id: i64,
+    },
+    FetchAlbum {
+        album_id: String,
+    },
+    FetchAlbums {
+        album_ids: Vec<String>,
+    },
+    FetchTracks {
+        track_ids: Vec<i32>,
+    },
     FetchUserPlaylists,
+    FetchOutputProfiles,
+    ReloadConfig,
+    FetchBrowseCategories,
+    FetchGenres,
+    FetchFeaturedPlaylists,
+    Browse {
+        kind: BrowseCategory,
+        /// Only albums of this genre.
+        #[serde(default)]
+        genre: Option<u32>,
+        offset: u32,
+    },
+    Identify {
+        name: String,
+    },
+    Subscribe {
+        topics: Vec<String>,
+    },
+    Clients,
+    SetPartyMode {
+        enabled: bool,
+        pin: Option<String>,
+    },
+    /// Save the queue under a name, replacing a queue saved under it before.
+    SaveQueue {
+        name: String,
+    },
+    /// Replace the queue with a saved one.
+    LoadQueue {
+        name: String,
+    },
+}
+
+impl Action {
+    /// Actions that can't wreck the session: basic transport controls, the volume and
+    /// lookups.
+    pub fn allowed_in_party_mode(&self) -> bool {
+        matches!(
+            self,
+            Action::Play
+                | Action::Pause
+                | Action::PlayPause
+                | Action::Next
+                | Action::SetVolume { .. }
+                | Action::Search { .. }
+                | Action::FetchArtistAlbums { .. }
+                | Action::FetchPlaylistTracks { .. }
+                | Action::FetchPlaylist { .. }
+                | Action::FetchAlbum { .. }
+                | Action::FetchAlbums { .. }
+                | Action::FetchTracks { .. }
+                | Action::FetchUserPlaylists
+                | Action::FetchOutputProfiles
+                | Action::FetchBrowseCategories
+                | Action::FetchGenres
+                | Action::FetchFeaturedPlaylists
+                | Action::Browse { .. }
+                | Action::Identify { .. }
+                | Action::Subscribe { .. }
+                | Action::Clients
+                | Action::Status
+                | Action::FetchTrackList
+                | Action::SetPartyMode { .. }
+        )
+    }
+
+    /// Actions left in simple mode. Playlists are checked against the allowlist when started.
+    pub fn allowed_in_simple_mode(&self) -> bool {
+        matches!(
+            self,
+            Action::Play
+                | Action::Pause
+                | Action::PlayPause
+                | Action::Next
+                | Action::PlayPlaylist { .. }
+                | Action::FetchPlaylist { .. }
+                | Action::Identify { .. }
+                | Action::Subscribe { .. }
+                | Action::Status
+                | Action::FetchTrackList
+        )
+    }
 }
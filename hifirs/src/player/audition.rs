@@ -0,0 +1,170 @@
+use crate::{
+    player::{
+        self,
+        notification::Notification,
+        party,
+        trim::{self, Trim, TrimKind},
+        Result,
+    },
+    service::Track,
+};
+use once_cell::sync::Lazy;
+use serde::{Deserialize, Serialize};
+use std::sync::Mutex;
+
+/// Seconds played from the middle of each track.
+pub const CLIP_SECS: u64 = 20;
+
+static SESSION: Lazy<Mutex<Option<Audition>>> = Lazy::new(|| Mutex::new(None));
+
+/// A pass through a playlist, hearing a clip of every track and picking the ones to remove.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Audition {
+    pub playlist_id: i64,
+    /// Tracks marked for removal, in the order they were heard.
+    pub removals: Vec<Track>,
+}
+
+/// The audition in progress, if any.
+pub fn current() -> Option<Audition> {
+    SESSION.lock().unwrap().clone()
+}
+
+pub fn is_active() -> bool {
+    SESSION.lock().unwrap().is_some()
+}
+
+async fn set_session(audition: Option<Audition>) -> Result<()> {
+    *SESSION.lock().unwrap() = audition.clone();
+
+    player::notify(Notification::Audition { audition }).await
+}
+
+/// Start auditioning a playlist from its first track.
+pub async fn start(playlist_id: i64) -> Result<()> {
+    party::check()?;
+
+    set_session(Some(Audition {
+        playlist_id,
+        removals: Vec::new(),
+    }))
+    .await?;
+
+    player::play_playlist(playlist_id).await
+}
+
+/// The part of a track to play while auditioning, as a trim around its middle.
+/// Ends the audition when something other than the auditioned playlist is playing.
+pub async fn clip(track: &Track) -> Option<Trim> {
+    let playlist_id = SESSION.lock().unwrap().as_ref()?.playlist_id;
+
+    let playing = player::current_tracklist()
+        .await
+        .get_playlist()
+        .map(|p| p.id as i64);
+
+    if playing != Some(playlist_id) {
+        debug!(playlist_id, "auditioned playlist is no longer playing");
+
+        if let Err(error) = set_session(None).await {
+            debug!(?error);
+        }
+
+        return None;
+    }
+
+    let duration = track.duration_seconds as u64;
+
+    if duration <= CLIP_SECS {
+        return None;
+    }
+
+    let start_secs = (duration - CLIP_SECS) / 2;
+
+    Some(Trim {
+        kind: TrimKind::Track,
+        id: track.id.to_string(),
+        start_secs,
+        end_secs: duration - start_secs - CLIP_SECS,
+    })
+}
+
+/// Keep or remove the track being auditioned, then move on to the next one.
+pub async fn decide(keep: bool) -> Result<()> {
+    party::check()?;
+
+    let Some(track) = player::current_track().await else {
+        return Ok(());
+    };
+
+    let audition = {
+        let mut session = SESSION.lock().unwrap();
+
+        let Some(audition) = session.as_mut() else {
+            return Ok(());
+        };
+
+        audition
+            .removals
+            .retain(|t| t.playlist_track_id != track.playlist_track_id);
+
+        if !keep {
+            audition.removals.push(track);
+        }
+
+        audition.clone()
+    };
+
+    player::notify(Notification::Audition {
+        audition: Some(audition),
+    })
+    .await?;
+
+    trim::clear();
+    player::end_trimmed_track().await
+}
+
+/// The last clip is over. Playback stops and the front-ends confirm the removals.
+pub(super) async fn finish() -> Result<()> {
+    let Some(audition) = current() else {
+        return Ok(());
+    };
+
+    player::pause().await?;
+    player::notify(Notification::AuditionFinished { audition }).await
+}
+
+/// Remove the tracks marked for removal from the playlist and end the audition.
+/// Returns how many tracks were removed.
+pub async fn apply() -> Result<usize> {
+    party::check()?;
+
+    let Some(audition) = current() else {
+        return Ok(0);
+    };
+
+    let playlist_track_ids = audition
+        .removals
+        .iter()
+        .filter_map(|t| t.playlist_track_id)
+        .collect::<Vec<i64>>();
+    let count = playlist_track_ids.len();
+
+    if count > 0 {
+        player::remove_playlist_tracks(audition.playlist_id, playlist_track_ids).await?;
+    }
+
+    set_session(None).await?;
+
+    Ok(count)
+}
+
+/// End the audition without changing the playlist.
+pub async fn cancel() -> Result<()> {
+    if is_active() {
+        set_session(None).await?;
+    }
+
+    Ok(())
+}
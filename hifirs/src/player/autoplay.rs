@@ -0,0 +1,84 @@
+use crate::{
+    player::{self, channels, notification::Notification, queue, Result},
+    service::Track,
+};
+use futures::StreamExt;
+use std::sync::atomic::{AtomicBool, Ordering};
+
+/// Tracks added each time the queue is about to run out.
+const AUTOPLAY_TRACKS: u32 = 10;
+
+static ENABLED: AtomicBool = AtomicBool::new(false);
+
+pub fn set_enabled(enabled: bool) {
+    ENABLED.store(enabled, Ordering::Relaxed);
+}
+
+pub fn is_enabled() -> bool {
+    ENABLED.load(Ordering::Relaxed)
+}
+
+/// Once the last track of the queue starts, add tracks by its artist and similar ones,
+/// so playback goes on without end.
+pub async fn watch_loop() {
+    let mut receiver = player::notify_receiver();
+    // The track the queue was last extended after, so it's only done once for each.
+    let mut extended_after = None;
+
+    while let Some(notification) = receiver.next().await {
+        match notification {
+            Notification::CurrentTrackList { list } => {
+                if !is_enabled() {
+                    continue;
+                }
+
+                let Some(current) = list.current_track() else {
+                    continue;
+                };
+
+                if list.unplayed_tracks().iter().any(|t| t.available)
+                    || extended_after == Some(current.id)
+                {
+                    continue;
+                }
+
+                extended_after = Some(current.id);
+
+                let seed = current.clone();
+                tokio::spawn(async move {
+                    if let Err(error) = extend(&seed).await {
+                        debug!(?error, "autoplay failed");
+                    }
+                });
+            }
+            Notification::Quit => break,
+            _ => {}
+        }
+    }
+}
+
+async fn extend(seed: &Track) -> Result<()> {
+    let service = queue().read().await.track_service(seed.id as i32);
+    let tracks = service.radio(seed, AUTOPLAY_TRACKS).await;
+
+    let mut state = queue().write().await;
+    let tracks = state.append_autoplay(tracks);
+
+    if tracks.is_empty() {
+        debug!(seed.id, "autoplay found nothing to add");
+        return Ok(());
+    }
+
+    let list = state.track_list();
+    drop(state);
+
+    info!(seed.id, added = tracks.len(), "autoplay extended the queue");
+
+    player::broadcast_track_list(&list).await?;
+    channels()
+        .tx
+        .broadcast(Notification::AutoplayAppended { tracks })
+        .await?;
+
+    Ok(())
+}
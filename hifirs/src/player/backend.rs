@@ -0,0 +1,209 @@
+use crate::player::{error::Error, Result};
+use futures::stream::BoxStream;
+use std::time::Duration;
+
+/// Where a backend is, from stopped to playing. Each state includes the ones before it,
+/// so `Paused` means loaded and ready to play.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum PlaybackState {
+    Stopped,
+    Ready,
+    Paused,
+    Playing,
+}
+
+/// How a change of state went.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum StateChange {
+    /// The backend is in the new state.
+    Done,
+    /// The backend is getting there, [`BackendEvent::Prerolled`] follows once it is.
+    Pending,
+    /// The stream is live, it plays as it comes and can't be paused or seeked.
+    Live,
+}
+
+/// How closely a seek has to land on the position asked for.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SeekMode {
+    /// Close by, wherever the stream can start quickest. For skipping around a track.
+    Fast,
+    /// Exactly on the position, e.g. to pick up where playback was.
+    Accurate,
+}
+
+/// What a backend reports while it plays.
+#[derive(Debug, Clone)]
+pub enum BackendEvent {
+    /// A new stream started playing.
+    StreamStart,
+    /// The last stream ended and nothing follows it.
+    EndOfStream,
+    /// The current stream is close to its end. A uri set now plays right after it.
+    AboutToFinish,
+    /// The backend, or one of its parts, moved to this state.
+    StateChanged(PlaybackState),
+    /// How full the buffer is, in percent.
+    Buffering(i32),
+    /// A pending state change or a seek finished, at this position when the backend knows it.
+    Prerolled(Option<Duration>),
+    /// The format the stream is decoded to.
+    AudioFormat { sampling_rate: u32, bit_depth: u32 },
+    /// The loudness of each channel, in dB, for the visualization.
+    Level { rms: Vec<f64>, peak: Vec<f64> },
+    /// The magnitude of each frequency band, in dB, for the visualization.
+    Spectrum { magnitudes: Vec<f32> },
+    /// The backend lost its clock and has to be restarted to play on.
+    ClockLost,
+    /// Playback failed. `url_rejected` when the server refused the uri, which it does once
+    /// a stream url expired.
+    Error { error: Error, url_rejected: bool },
+}
+
+/// Plays audio from a uri. The player drives whichever backend it was built with
+/// through this, the GStreamer one being [`PlaybinBackend`](super::playbin::PlaybinBackend).
+pub trait PlaybackBackend: Send + Sync {
+    fn set_uri(&self, uri: &str);
+
+    fn set_state(&self, state: PlaybackState) -> Result<StateChange>;
+
+    fn state(&self) -> PlaybackState;
+
+    fn seek(&self, position: Duration, mode: SeekMode) -> Result<()>;
+
+    fn position(&self) -> Option<Duration>;
+
+    fn duration(&self) -> Option<Duration>;
+
+    /// The volume, 1.0 being unchanged.
+    fn volume(&self) -> f64;
+
+    fn set_volume(&self, volume: f64);
+
+    /// Everything the backend reports, from the time this is called. Taken once, by the
+    /// player loop.
+    fn events(&self) -> BoxStream<'static, BackendEvent>;
+
+    fn play(&self) -> Result<StateChange> {
+        self.set_state(PlaybackState::Playing)
+    }
+
+    fn pause(&self) -> Result<StateChange> {
+        self.set_state(PlaybackState::Paused)
+    }
+
+    /// Whether [`PlaybackBackend::switch_uri`] changes streams without stopping first.
+    fn switches_instantly(&self) -> bool {
+        false
+    }
+
+    /// Change to `uri` while playing, right away where the backend can.
+    fn switch_uri(&self, uri: &str) {
+        self.set_uri(uri);
+    }
+}
+
+/// A backend that plays nothing, for tests. It goes through the states, positions and
+/// volumes it is told to and reports the events a test sends it. Clones share all of that,
+/// so a test can keep one to look at while the player drives another.
+#[cfg(test)]
+pub mod mock {
+    use super::{BackendEvent, PlaybackBackend, PlaybackState, SeekMode, StateChange};
+    use crate::player::Result;
+    use futures::{stream::BoxStream, StreamExt};
+    use std::{
+        sync::{Arc, Mutex},
+        time::Duration,
+    };
+
+    /// How long every track the mock plays is.
+    pub const DURATION: Duration = Duration::from_secs(300);
+
+    #[derive(Clone)]
+    pub struct MockBackend {
+        inner: Arc<Mutex<Inner>>,
+        events: (flume::Sender<BackendEvent>, flume::Receiver<BackendEvent>),
+    }
+
+    struct Inner {
+        uri: Option<String>,
+        state: PlaybackState,
+        position: Duration,
+        volume: f64,
+    }
+
+    impl Default for MockBackend {
+        fn default() -> Self {
+            Self {
+                inner: Arc::new(Mutex::new(Inner {
+                    uri: None,
+                    state: PlaybackState::Stopped,
+                    position: Duration::ZERO,
+                    volume: 1.0,
+                })),
+                events: flume::unbounded(),
+            }
+        }
+    }
+
+    impl MockBackend {
+        /// The uri the player loaded last.
+        pub fn uri(&self) -> Option<String> {
+            self.inner.lock().unwrap().uri.clone()
+        }
+
+        /// Report `event` to the player, as if it happened while playing.
+        pub fn send(&self, event: BackendEvent) {
+            self.events
+                .0
+                .send(event)
+                .expect("mock backend events are gone");
+        }
+    }
+
+    impl PlaybackBackend for MockBackend {
+        fn set_uri(&self, uri: &str) {
+            let mut inner = self.inner.lock().unwrap();
+            inner.uri = Some(uri.to_string());
+            inner.position = Duration::ZERO;
+        }
+
+        fn set_state(&self, state: PlaybackState) -> Result<StateChange> {
+            self.inner.lock().unwrap().state = state;
+
+            Ok(StateChange::Done)
+        }
+
+        fn state(&self) -> PlaybackState {
+            self.inner.lock().unwrap().state
+        }
+
+        fn seek(&self, position: Duration, _mode: SeekMode) -> Result<()> {
+            self.inner.lock().unwrap().position = position.min(DURATION);
+
+            Ok(())
+        }
+
+        fn position(&self) -> Option<Duration> {
+            let inner = self.inner.lock().unwrap();
+
+            (inner.state >= PlaybackState::Paused).then_some(inner.position)
+        }
+
+        fn duration(&self) -> Option<Duration> {
+            (self.state() >= PlaybackState::Paused).then_some(DURATION)
+        }
+
+        fn volume(&self) -> f64 {
+            self.inner.lock().unwrap().volume
+        }
+
+        fn set_volume(&self, volume: f64) {
+            self.inner.lock().unwrap().volume = volume;
+        }
+
+        fn events(&self) -> BoxStream<'static, BackendEvent> {
+            self.events.1.clone().into_stream().boxed()
+        }
+    }
+}
@@ -0,0 +1,390 @@
+use crate::{
+    player::{self, notification::Notification},
+    service::Track,
+    sql::db,
+};
+use chrono::{DateTime, Utc};
+use flume::{Receiver, Sender};
+use gstreamer::{
+    self as gst, glib, prelude::*, Element, PadProbeData, PadProbeReturn, PadProbeType,
+};
+use hifirs_qobuz_api::client::AudioQuality;
+use once_cell::sync::Lazy;
+use serde::{Deserialize, Serialize};
+use std::{
+    collections::{HashSet, VecDeque},
+    fs::File,
+    io::Write,
+    path::PathBuf,
+    sync::{
+        atomic::{AtomicBool, AtomicU64, Ordering},
+        Mutex,
+    },
+};
+
+/// The cache size limit in megabytes when none is configured.
+pub const DEFAULT_CACHE_SIZE_MB: u64 = 2048;
+/// How many stream urls are remembered until their download starts.
+const MAX_EXPECTED: usize = 16;
+
+static OFFLINE: AtomicBool = AtomicBool::new(false);
+static CACHED_ONLY: AtomicBool = AtomicBool::new(false);
+static SIZE_LIMIT_MB: AtomicU64 = AtomicU64::new(DEFAULT_CACHE_SIZE_MB);
+/// Stream urls handed to the player, with the track and quality id they are for.
+static EXPECTED: Lazy<Mutex<VecDeque<(String, i32, u32)>>> =
+    Lazy::new(|| Mutex::new(VecDeque::with_capacity(MAX_EXPECTED)));
+static DOWNLOADED: Lazy<(Sender<Download>, Receiver<Download>)> = Lazy::new(flume::unbounded);
+
+/// A track stored in the local track cache.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct CachedTrack {
+    pub track: Track,
+    /// The Qobuz quality id the track was downloaded at.
+    pub quality: u32,
+    pub path: PathBuf,
+    /// Size on disk, in bytes.
+    pub size: u64,
+    pub cached_at: DateTime<Utc>,
+}
+
+impl CachedTrack {
+    /// The file uri the player can open the track from.
+    pub fn uri(&self) -> Option<String> {
+        if !self.path.is_file() {
+            return None;
+        }
+
+        glib::filename_to_uri(&self.path, None)
+            .map(|uri| uri.to_string())
+            .ok()
+    }
+}
+
+/// Where cached tracks are stored.
+pub fn cache_dir() -> Option<PathBuf> {
+    let mut path = dirs::data_local_dir()?;
+    path.push("hifi-rs");
+    path.push("tracks");
+
+    Some(path)
+}
+
+/// True while tracks are being played from the cache because the service could not be reached.
+pub fn is_offline() -> bool {
+    OFFLINE.load(Ordering::Relaxed)
+}
+
+/// Record whether tracks are played from the cache. Returns the notification for the
+/// player to send when that changed.
+pub fn set_offline(offline: bool) -> Option<Notification> {
+    if OFFLINE.swap(offline, Ordering::Relaxed) == offline {
+        return None;
+    }
+
+    debug!(offline, "offline status changed");
+
+    Some(Notification::Offline { offline })
+}
+
+/// True when only cached tracks are played and shown in the queue.
+pub fn cached_only() -> bool {
+    CACHED_ONLY.load(Ordering::Relaxed)
+}
+
+pub fn set_cached_only(enabled: bool) {
+    CACHED_ONLY.store(enabled, Ordering::Relaxed);
+}
+
+/// Every cached track whose file is still on disk, most recently cached first.
+pub async fn cached_tracks() -> Vec<CachedTrack> {
+    db::get_cached_tracks()
+        .await
+        .into_iter()
+        .filter(|c| c.path.is_file())
+        .collect()
+}
+
+pub async fn cached_track_ids() -> HashSet<u32> {
+    cached_tracks().await.iter().map(|c| c.track.id).collect()
+}
+
+/// The track's metadata, as it was when the track was cached.
+pub async fn cached_track(track_id: i32) -> Option<Track> {
+    db::get_cached_track(track_id).await.map(|c| c.track)
+}
+
+/// A uri for the best quality copy of the track in the cache, if there is one.
+pub async fn cached_url(track_id: i32) -> Option<String> {
+    db::get_cached_track(track_id).await?.uri()
+}
+
+/// The cache size limit in megabytes, 0 turns caching streamed tracks off.
+pub fn set_size_limit(megabytes: u64) {
+    SIZE_LIMIT_MB.store(megabytes, Ordering::Relaxed);
+}
+
+fn size_limit() -> u64 {
+    SIZE_LIMIT_MB.load(Ordering::Relaxed) * 1024 * 1024
+}
+
+/// A uri for a copy of the track in the cache at `quality` or better, so it
+/// doesn't have to be streamed again.
+pub async fn cached_url_at(track_id: i32, quality: &AudioQuality) -> Option<String> {
+    let cached = db::get_cached_track(track_id).await?;
+
+    if cached.quality >= quality.clone() as u32 {
+        cached.uri()
+    } else {
+        None
+    }
+}
+
+/// Remember that `url` streams the track at `quality`, so the download can be
+/// saved to the cache once the player opens it.
+pub fn expect_stream(url: &str, track_id: i32, quality: &AudioQuality) {
+    if size_limit() == 0 {
+        return;
+    }
+
+    let mut expected = EXPECTED.lock().unwrap();
+
+    if expected.len() == MAX_EXPECTED {
+        expected.pop_front();
+    }
+
+    expected.push_back((url.to_string(), track_id, quality.clone() as u32));
+}
+
+/// A streamed track being written to the cache alongside playback.
+#[derive(Debug)]
+struct Download {
+    track_id: i32,
+    quality: u32,
+    path: PathBuf,
+    file: Option<File>,
+    size: u64,
+}
+
+impl Download {
+    fn start(track_id: i32, quality: u32) -> Option<Self> {
+        let dir = cache_dir()?;
+        std::fs::create_dir_all(&dir).ok()?;
+
+        let extension = if quality == AudioQuality::Mp3 as u32 {
+            "mp3"
+        } else {
+            "flac"
+        };
+        let path = dir.join(format!("{track_id}-{quality}.{extension}"));
+        let file = File::create(part_path(&path)).ok()?;
+
+        Some(Self {
+            track_id,
+            quality,
+            path,
+            file: Some(file),
+            size: 0,
+        })
+    }
+
+    /// Append a buffer. Only a download read from start to end in one go is kept,
+    /// anything else, like a seek ahead of the downloaded part, abandons it.
+    fn write(&mut self, offset: u64, data: &[u8]) {
+        let Some(file) = self.file.as_mut() else {
+            return;
+        };
+
+        if (offset != gst::BUFFER_OFFSET_NONE && offset != self.size)
+            || file.write_all(data).is_err()
+        {
+            self.abandon();
+            return;
+        }
+
+        self.size += data.len() as u64;
+    }
+
+    /// The download reached the end. `expected` is the size the server announced.
+    fn finish(&mut self, expected: Option<u64>) {
+        let Some(file) = self.file.take() else {
+            return;
+        };
+
+        if expected.is_some_and(|expected| expected != self.size) || file.sync_all().is_err() {
+            self.file = Some(file);
+            self.abandon();
+            return;
+        }
+
+        drop(file);
+
+        if std::fs::rename(part_path(&self.path), &self.path).is_ok() {
+            debug!(
+                self.track_id,
+                self.size, "streamed track saved to the cache"
+            );
+
+            DOWNLOADED
+                .0
+                .send(Self {
+                    track_id: self.track_id,
+                    quality: self.quality,
+                    path: self.path.clone(),
+                    file: None,
+                    size: self.size,
+                })
+                .expect("failed to send download");
+        }
+    }
+
+    fn abandon(&mut self) {
+        if self.file.take().is_some() {
+            debug!(self.track_id, "not caching a partial download");
+            std::fs::remove_file(part_path(&self.path)).ok();
+        }
+    }
+}
+
+impl Drop for Download {
+    fn drop(&mut self) {
+        self.abandon();
+    }
+}
+
+fn part_path(path: &std::path::Path) -> PathBuf {
+    path.with_extension("part")
+}
+
+/// Write what `source` downloads to the cache as well, when its url was expected
+/// with [`expect_stream`]. Called from the playbin's `source-setup`.
+pub fn tee_source(source: &Element) {
+    let location = source.property::<Option<String>>("location");
+
+    let expected = {
+        let mut expected = EXPECTED.lock().unwrap();
+        let index = expected
+            .iter()
+            .position(|(url, _, _)| Some(url) == location.as_ref());
+
+        index.and_then(|i| expected.remove(i))
+    };
+
+    let Some((_, track_id, quality)) = expected else {
+        return;
+    };
+    let (Some(pad), Some(download)) =
+        (source.static_pad("src"), Download::start(track_id, quality))
+    else {
+        return;
+    };
+
+    let download = Mutex::new(download);
+
+    pad.add_probe(
+        PadProbeType::BUFFER | PadProbeType::EVENT_DOWNSTREAM,
+        move |pad, info| {
+            let mut download = download.lock().unwrap();
+
+            match &info.data {
+                Some(PadProbeData::Buffer(buffer)) => {
+                    if let Ok(map) = buffer.map_readable() {
+                        download.write(buffer.offset(), map.as_slice());
+                    }
+                }
+                Some(PadProbeData::Event(event)) => match event.view() {
+                    gst::EventView::Eos(_) => {
+                        let expected = pad
+                            .query_duration::<gst::format::Bytes>()
+                            .map(|bytes| *bytes);
+                        download.finish(expected);
+
+                        return PadProbeReturn::Remove;
+                    }
+                    gst::EventView::FlushStart(_) => {
+                        download.abandon();
+
+                        return PadProbeReturn::Remove;
+                    }
+                    _ => {}
+                },
+                _ => {}
+            }
+
+            PadProbeReturn::Ok
+        },
+    );
+}
+
+/// Adds the streamed tracks saved by the player to the cache and keeps it under
+/// the size limit, until the player quits.
+pub async fn save_loop() {
+    while let Ok(download) = DOWNLOADED.1.recv_async().await {
+        let track = match player::current_tracklist()
+            .await
+            .queue
+            .values()
+            .find(|t| t.id as i32 == download.track_id)
+        {
+            Some(track) => Some(track.clone()),
+            None => player::tracks(vec![download.track_id])
+                .await
+                .into_iter()
+                .next(),
+        };
+
+        let Some(track) = track else {
+            std::fs::remove_file(&download.path).ok();
+            continue;
+        };
+
+        db::add_cached_track(&CachedTrack {
+            track,
+            quality: download.quality,
+            path: download.path.clone(),
+            size: download.size,
+            cached_at: Utc::now(),
+        })
+        .await;
+
+        enforce_size_limit().await;
+    }
+}
+
+/// Remove the tracks cached longest ago until the cache fits in its size limit.
+async fn enforce_size_limit() {
+    let limit = size_limit();
+    let mut cached = db::get_cached_tracks().await;
+    let mut total = cached.iter().map(|c| c.size).sum::<u64>();
+
+    while total > limit {
+        let Some(oldest) = cached.pop() else {
+            break;
+        };
+
+        remove(&oldest).await;
+        total = total.saturating_sub(oldest.size);
+    }
+}
+
+async fn remove(cached: &CachedTrack) {
+    if cached.path.is_file() {
+        if let Err(error) = std::fs::remove_file(&cached.path) {
+            debug!(?error, "failed to remove cached track");
+        }
+    }
+
+    db::remove_cached_track(cached.track.id as i32, cached.quality).await;
+}
+
+/// Remove every track from the cache. Returns how many were removed and the bytes freed.
+pub async fn purge() -> (usize, u64) {
+    let cached = db::get_cached_tracks().await;
+    let freed = cached.iter().map(|c| c.size).sum();
+
+    for track in &cached {
+        remove(track).await;
+    }
+
+    (cached.len(), freed)
+}
@@ -0,0 +1,182 @@
+use gstreamer::{prelude::*, ClockTime, Element, SeekFlags, State as GstState};
+use once_cell::sync::Lazy;
+use std::{
+    sync::{
+        atomic::{AtomicU32, Ordering},
+        Mutex,
+    },
+    time::Duration,
+};
+
+pub const MAX_CROSSFADE_SECS: u32 = 12;
+/// The end of the current track is loaded this long before the fade starts, so it is
+/// prerolled and already at the right position when it has to play.
+const PREPARE_LEAD_SECS: u64 = 5;
+/// How often the volumes are changed while fading.
+const FADE_STEP_MS: u64 = 50;
+
+static SECONDS: AtomicU32 = AtomicU32::new(0);
+/// The fade in of the current track, multiplied with the volume.
+static LEVEL: Lazy<Mutex<f64>> = Lazy::new(|| Mutex::new(1.0));
+/// A second pipeline playing the end of the previous track while the next one fades in.
+static TAIL: Lazy<Mutex<Option<Element>>> = Lazy::new(|| Mutex::new(None));
+static PREPARED_FOR: AtomicU32 = AtomicU32::new(0);
+static STARTED_FOR: AtomicU32 = AtomicU32::new(0);
+
+/// What the player has to do for the crossfade at the current position.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Phase {
+    /// Load the end of the current track into the second pipeline.
+    Prepare,
+    /// Play the end of the current track and fade in the next one.
+    Start,
+}
+
+/// Overlap the last `seconds` of each track with the next one, 0 turns it off.
+pub fn set_seconds(seconds: u32) {
+    SECONDS.store(seconds.min(MAX_CROSSFADE_SECS), Ordering::Relaxed);
+}
+
+pub fn seconds() -> u32 {
+    SECONDS.load(Ordering::Relaxed)
+}
+
+pub fn is_enabled() -> bool {
+    seconds() > 0
+}
+
+/// The fade in level of the current track, between 0.0 and 1.0.
+pub fn level() -> f64 {
+    *LEVEL.lock().unwrap()
+}
+
+/// Where the crossfade of a track of `duration` starts. Tracks shorter than
+/// twice the crossfade are not faded.
+pub fn start_at(duration: ClockTime) -> Option<ClockTime> {
+    let length = ClockTime::from_seconds(seconds() as u64);
+
+    if length.is_zero() || duration < length * 2 {
+        return None;
+    }
+
+    duration.checked_sub(length)
+}
+
+/// Which phase of the crossfade, if any, is due for `track_id` at `position`.
+/// Every phase is only returned once per track.
+pub fn due(track_id: u32, position: ClockTime, duration: ClockTime) -> Option<Phase> {
+    let start = start_at(duration)?;
+    let prepare = start.saturating_sub(ClockTime::from_seconds(PREPARE_LEAD_SECS));
+
+    if position >= start {
+        (STARTED_FOR.swap(track_id, Ordering::Relaxed) != track_id).then_some(Phase::Start)
+    } else if position >= prepare {
+        (PREPARED_FOR.swap(track_id, Ordering::Relaxed) != track_id).then_some(Phase::Prepare)
+    } else {
+        None
+    }
+}
+
+/// Load `uri` into the second pipeline, paused at `start`.
+pub async fn prepare_tail(uri: &str, start: ClockTime, volume: f64) {
+    stop_tail();
+
+    let tail = match gstreamer::ElementFactory::make("playbin3").build() {
+        Ok(tail) => tail,
+        Err(error) => {
+            warn!(?error, "failed to build the crossfade pipeline");
+            return;
+        }
+    };
+
+    tail.set_property_from_str("flags", "audio+buffering");
+    tail.set_property("uri", uri);
+    tail.set_property("volume", volume);
+
+    if let Err(error) = tail.set_state(GstState::Paused) {
+        warn!(?error, "failed to preroll the crossfade pipeline");
+        return;
+    }
+
+    let mut interval = tokio::time::interval(Duration::from_millis(100));
+
+    for _ in 0..PREPARE_LEAD_SECS * 10 {
+        if tail.current_state() == GstState::Paused {
+            break;
+        }
+
+        interval.tick().await;
+    }
+
+    if let Err(error) = tail.seek_simple(SeekFlags::FLUSH | SeekFlags::ACCURATE, start) {
+        warn!(?error, "failed to seek the crossfade pipeline");
+        let _ = tail.set_state(GstState::Null);
+        return;
+    }
+
+    debug!(?start, "crossfade prepared");
+    *TAIL.lock().unwrap() = Some(tail);
+}
+
+/// Play the prepared end of the track and fade it out. Returns false when nothing
+/// was prepared, the next track is then only faded in.
+pub fn start_tail() -> bool {
+    let Some(tail) = TAIL.lock().unwrap().clone() else {
+        return false;
+    };
+
+    if let Err(error) = tail.set_state(GstState::Playing) {
+        warn!(?error, "failed to play the crossfade pipeline");
+        stop_tail();
+        return false;
+    }
+
+    let volume = tail.property::<f64>("volume");
+    let steps = steps();
+
+    tokio::spawn(async move {
+        let mut interval = tokio::time::interval(Duration::from_millis(FADE_STEP_MS));
+
+        for step in (0..steps).rev() {
+            interval.tick().await;
+
+            // Replaced or stopped in the meantime.
+            if TAIL.lock().unwrap().as_ref() != Some(&tail) {
+                return;
+            }
+
+            tail.set_property("volume", volume * step as f64 / steps as f64);
+        }
+
+        stop_tail();
+    });
+
+    true
+}
+
+/// Stop the end of the previous track, e.g. when playback is paused during the fade.
+pub fn stop_tail() {
+    if let Some(tail) = TAIL.lock().unwrap().take() {
+        let _ = tail.set_state(GstState::Null);
+    }
+}
+
+/// Raise the level of the current track from silence to full over the crossfade,
+/// calling `apply` after every step.
+pub async fn fade_in(apply: impl Fn(f64)) {
+    let steps = steps();
+    let mut interval = tokio::time::interval(Duration::from_millis(FADE_STEP_MS));
+
+    // Never quite silent, so the volume can still be worked out from the pipeline.
+    for step in 1..=steps {
+        let level = step as f64 / steps as f64;
+        *LEVEL.lock().unwrap() = level;
+        apply(level);
+
+        interval.tick().await;
+    }
+}
+
+fn steps() -> u64 {
+    (seconds() as u64 * 1000 / FADE_STEP_MS).max(1)
+}
@@ -2,7 +2,7 @@ use gstreamer::{glib, StateChangeError};
 use serde::{Deserialize, Serialize};
 use snafu::prelude::*;
 
-use crate::player::notification::Notification;
+use crate::{player::notification::Notification, service};
 
 #[derive(Snafu, Debug, Clone, Serialize, Deserialize, PartialEq)]
 pub enum Error {
@@ -16,6 +16,37 @@ pub enum Error {
     Seek,
     #[snafu(display("sorry, could not resume previous session"))]
     Resume,
+    #[snafu(display("the queue has changed (version {current}, expected {expected})"))]
+    StaleQueue {
+        expected: u64,
+        current: u64,
+    },
+    #[snafu(display("only upcoming tracks can be moved or removed"))]
+    NotUpcoming,
+    #[snafu(display("could not be found on the service"))]
+    NotFound,
+    #[snafu(display("the service did not change the favorites"))]
+    FavoriteFailed,
+    #[snafu(display("not allowed while party mode is on"))]
+    PartyMode,
+    #[snafu(display("not allowed in simple mode"))]
+    SimpleMode,
+    #[snafu(display("set a party mode PIN first with `hifi-rs config set party-pin`"))]
+    PartyPinMissing,
+    #[snafu(display("wrong PIN"))]
+    WrongPin,
+    #[snafu(display("nothing is queued"))]
+    EmptyQueue,
+    #[snafu(display("there is no saved queue named {name}"))]
+    QueueNotFound {
+        name: String,
+    },
+    #[snafu(display("failed to update the playlist"))]
+    PlaylistUpdate,
+    #[snafu(display("there is no output device or sink called {name}"))]
+    UnknownOutput {
+        name: String,
+    },
     #[snafu(display("{message}"))]
     GStreamer {
         message: String,
@@ -52,6 +83,14 @@ impl From<StateChangeError> for Error {
     }
 }
 
+impl From<service::Error> for Error {
+    fn from(value: service::Error) -> Self {
+        Error::Client {
+            message: value.to_string(),
+        }
+    }
+}
+
 impl From<hifirs_qobuz_api::Error> for Error {
     fn from(value: hifirs_qobuz_api::Error) -> Self {
         Error::Client {
@@ -0,0 +1,179 @@
+use crate::player::{self, notification::Notification};
+use chrono::{DateTime, Local};
+use futures::StreamExt;
+use gstreamer::{prelude::*, Message, MessageView};
+use once_cell::sync::Lazy;
+use serde::{Deserialize, Serialize};
+use std::{collections::VecDeque, fmt::Display, io::Write, path::Path, sync::Mutex};
+
+/// The number of events kept in memory.
+pub const EVENT_LOG_SIZE: usize = 500;
+const MAX_DETAIL_LENGTH: usize = 240;
+
+static EVENTS: Lazy<Mutex<VecDeque<Event>>> =
+    Lazy::new(|| Mutex::new(VecDeque::with_capacity(EVENT_LOG_SIZE)));
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum EventSource {
+    Notification,
+    Gstreamer,
+}
+
+impl Display for EventSource {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            EventSource::Notification => f.write_str("notification"),
+            EventSource::Gstreamer => f.write_str("gstreamer"),
+        }
+    }
+}
+
+/// Something that happened on the notification bus or the GStreamer bus.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Event {
+    pub timestamp: DateTime<Local>,
+    pub source: EventSource,
+    pub kind: String,
+    pub detail: String,
+}
+
+impl Event {
+    /// True when the filter is empty or matches the source or kind of the event.
+    pub fn matches(&self, filter: &str) -> bool {
+        let filter = filter.trim().to_lowercase();
+
+        filter.is_empty()
+            || self.source.to_string() == filter
+            || self.kind.to_lowercase().contains(&filter)
+    }
+}
+
+impl Display for Event {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "{} {:<12} {:<18} {}",
+            self.timestamp.format("%H:%M:%S%.3f"),
+            self.source,
+            self.kind,
+            self.detail
+        )
+    }
+}
+
+fn record(source: EventSource, kind: String, mut detail: String) {
+    if detail.len() > MAX_DETAIL_LENGTH {
+        let mut end = MAX_DETAIL_LENGTH;
+        while !detail.is_char_boundary(end) {
+            end -= 1;
+        }
+
+        detail.truncate(end);
+        detail.push('…');
+    }
+
+    let mut events = EVENTS.lock().unwrap();
+
+    if events.len() == EVENT_LOG_SIZE {
+        events.pop_front();
+    }
+
+    events.push_back(Event {
+        timestamp: Local::now(),
+        source,
+        kind,
+        detail,
+    });
+}
+
+/// Records a message from the GStreamer bus. The level and spectrum elements post several
+/// times a second for the visualization, those are skipped like the notifications made of
+/// them in [`record_notifications`].
+pub fn record_message(msg: &Message) {
+    if let MessageView::Element(element) = msg.view() {
+        if element
+            .structure()
+            .is_some_and(|s| s.has_name("level") || s.has_name("spectrum"))
+        {
+            return;
+        }
+    }
+
+    let source = msg
+        .src()
+        .map(|s| s.path_string().to_string())
+        .unwrap_or_default();
+    let structure = msg.structure().map(|s| s.to_string()).unwrap_or_default();
+
+    // The message view's debug output starts with the variant name, e.g. `StateChanged(..)`.
+    let kind = format!("{:?}", msg.view())
+        .split('(')
+        .next()
+        .unwrap_or_default()
+        .to_string();
+
+    record(
+        EventSource::Gstreamer,
+        kind,
+        format!("{source} {structure}"),
+    );
+}
+
+/// Records every notification until the player quits.
+/// Visualization data and stream stats are skipped so they do not push everything else out
+/// of the log.
+pub async fn record_notifications() {
+    let mut receiver = player::notify_receiver();
+
+    while let Some(notification) = receiver.next().await {
+        match &notification {
+            Notification::Level { .. }
+            | Notification::Spectrum { .. }
+            | Notification::Stats { .. } => continue,
+            Notification::Quit => {
+                record(
+                    EventSource::Notification,
+                    notification.topic(),
+                    String::new(),
+                );
+                break;
+            }
+            _ => {}
+        }
+
+        let detail = match serde_json::to_value(&notification) {
+            Ok(serde_json::Value::Object(map)) => map
+                .values()
+                .next()
+                .map(|v| v.to_string())
+                .unwrap_or_default(),
+            _ => String::new(),
+        };
+
+        record(EventSource::Notification, notification.topic(), detail);
+    }
+}
+
+/// Returns the recorded events matching `filter`, oldest first.
+pub fn events(filter: &str) -> Vec<Event> {
+    EVENTS
+        .lock()
+        .unwrap()
+        .iter()
+        .filter(|e| e.matches(filter))
+        .cloned()
+        .collect()
+}
+
+/// Writes every recorded event to `path`, returning the number written.
+pub fn dump(path: &Path) -> std::io::Result<usize> {
+    let events = events("");
+    let mut file = std::fs::File::create(path)?;
+
+    for event in &events {
+        writeln!(file, "{event}")?;
+    }
+
+    Ok(events.len())
+}
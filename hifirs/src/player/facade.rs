@@ -0,0 +1,400 @@
+//! The player the binary installs, driven from the front-ends, the remote control and the
+//! other loops without a handle to it. Each function calls the method of the same name on
+//! the [`Player`](super::Player) set up by [`init`](super::init).
+
+use crate::{
+    config::{ConfigKey, Settings},
+    player::{
+        notification::{BroadcastReceiver, Notification},
+        output::OutputProfile,
+        player,
+        queue::{ListProgress, TrackListValue},
+        transition::FormatChangePolicy,
+        visualization::Visualization,
+        Result, SeekMode,
+    },
+    service::{MusicService, PersonalPlaylist, Track},
+};
+use gstreamer::{ClockTime, State as GstState};
+use hifirs_qobuz_api::client::AudioQuality;
+use std::sync::Arc;
+
+/// See [`Player::notify`](super::Player::notify).
+pub async fn notify(notification: Notification) -> Result<()> {
+    player().notify(notification).await
+}
+
+/// See [`Player::notify_receiver`](super::Player::notify_receiver).
+pub fn notify_receiver() -> BroadcastReceiver {
+    player().notify_receiver()
+}
+
+/// See [`Player::visualization_receiver`](super::Player::visualization_receiver).
+pub fn visualization_receiver() -> BroadcastReceiver {
+    player().visualization_receiver()
+}
+
+/// See [`Player::set_visualization`](super::Player::set_visualization).
+pub fn set_visualization(kind: Visualization, interval_ms: u64) {
+    player().set_visualization(kind, interval_ms)
+}
+
+/// See [`Player::set_format_change_policy`](super::Player::set_format_change_policy).
+pub fn set_format_change_policy(policy: FormatChangePolicy, resample_rate: u32) {
+    player().set_format_change_policy(policy, resample_rate)
+}
+
+/// See [`Player::set_settings`](super::Player::set_settings).
+pub fn set_settings(settings: Settings) {
+    player().set_settings(settings)
+}
+
+/// See [`Player::toggle_visualization`](super::Player::toggle_visualization).
+pub fn toggle_visualization() -> bool {
+    player().toggle_visualization()
+}
+
+/// See [`Player::play`](super::Player::play).
+pub async fn play() -> Result<()> {
+    player().play().await
+}
+
+/// See [`Player::pause`](super::Player::pause).
+pub async fn pause() -> Result<()> {
+    player().pause().await
+}
+
+/// See [`Player::ready`](super::Player::ready).
+pub async fn ready() -> Result<()> {
+    player().ready().await
+}
+
+/// See [`Player::stop`](super::Player::stop).
+pub async fn stop() -> Result<()> {
+    player().stop().await
+}
+
+/// See [`Player::set_player_state`](super::Player::set_player_state).
+pub async fn set_player_state(state: GstState) -> Result<()> {
+    player().set_player_state(state).await
+}
+
+/// See [`Player::broadcast_track_list`](super::Player::broadcast_track_list).
+pub(crate) async fn broadcast_track_list(list: &TrackListValue) -> Result<()> {
+    player().broadcast_track_list(list).await
+}
+
+/// See [`Player::play_pause`](super::Player::play_pause).
+pub async fn play_pause() -> Result<()> {
+    player().play_pause().await
+}
+
+/// See [`Player::is_paused`](super::Player::is_paused).
+pub fn is_paused() -> bool {
+    player().is_paused()
+}
+
+/// See [`Player::is_playing`](super::Player::is_playing).
+pub fn is_playing() -> bool {
+    player().is_playing()
+}
+
+/// See [`Player::is_ready`](super::Player::is_ready).
+pub fn is_ready() -> bool {
+    player().is_ready()
+}
+
+/// See [`Player::current_state`](super::Player::current_state).
+pub fn current_state() -> GstState {
+    player().current_state()
+}
+
+/// See [`Player::position`](super::Player::position).
+pub fn position() -> Option<ClockTime> {
+    player().position()
+}
+
+/// See [`Player::duration`](super::Player::duration).
+pub fn duration() -> Option<ClockTime> {
+    player().duration()
+}
+
+/// See [`Player::list_progress`](super::Player::list_progress).
+pub async fn list_progress(position: ClockTime) -> Option<ListProgress> {
+    player().list_progress(position).await
+}
+
+/// See [`Player::seek`](super::Player::seek).
+pub async fn seek(time: ClockTime, mode: SeekMode) -> Result<()> {
+    player().seek(time, mode).await
+}
+
+/// See [`Player::resume`](super::Player::resume).
+pub async fn resume(autoplay: bool) -> Result<()> {
+    player().resume(autoplay).await
+}
+
+/// See [`Player::enable_party_mode`](super::Player::enable_party_mode).
+pub async fn enable_party_mode() -> Result<()> {
+    player().enable_party_mode().await
+}
+
+/// See [`Player::disable_party_mode`](super::Player::disable_party_mode).
+pub async fn disable_party_mode(pin: &str) -> Result<()> {
+    player().disable_party_mode(pin).await
+}
+
+/// See [`Player::unlock_simple_mode`](super::Player::unlock_simple_mode).
+pub async fn unlock_simple_mode(pin: &str) -> Result<()> {
+    player().unlock_simple_mode(pin).await
+}
+
+/// See [`Player::save_queue`](super::Player::save_queue).
+pub async fn save_queue(name: &str) -> Result<()> {
+    player().save_queue(name).await
+}
+
+/// See [`Player::load_queue`](super::Player::load_queue).
+pub async fn load_queue(name: &str) -> Result<()> {
+    player().load_queue(name).await
+}
+
+/// See [`Player::switch_quality`](super::Player::switch_quality).
+pub async fn switch_quality(quality: AudioQuality) -> Result<()> {
+    player().switch_quality(quality).await
+}
+
+/// See [`Player::set_output_device`](super::Player::set_output_device).
+pub async fn set_output_device(device: Option<String>) -> Result<()> {
+    player().set_output_device(device).await
+}
+
+/// See [`Player::set_output_profile`](super::Player::set_output_profile).
+pub async fn set_output_profile(profile: OutputProfile) -> Result<()> {
+    player().set_output_profile(profile).await
+}
+
+/// See [`Player::reload_config`](super::Player::reload_config).
+pub async fn reload_config() -> Result<Vec<ConfigKey>> {
+    player().reload_config().await
+}
+
+/// See [`Player::jump_forward`](super::Player::jump_forward).
+pub async fn jump_forward() -> Result<()> {
+    player().jump_forward().await
+}
+
+/// See [`Player::jump_backward`](super::Player::jump_backward).
+pub async fn jump_backward() -> Result<()> {
+    player().jump_backward().await
+}
+
+/// See [`Player::seek_seconds`](super::Player::seek_seconds).
+pub async fn seek_seconds(seconds: i64, relative: bool) -> Result<()> {
+    player().seek_seconds(seconds, relative).await
+}
+
+/// See [`Player::seek_to`](super::Player::seek_to).
+pub async fn seek_to(position: ClockTime) -> Result<()> {
+    player().seek_to(position).await
+}
+
+/// See [`Player::seek_percent`](super::Player::seek_percent).
+pub async fn seek_percent(percent: f32) -> Result<()> {
+    player().seek_percent(percent).await
+}
+
+/// See [`Player::volume`](super::Player::volume).
+pub fn volume() -> f64 {
+    player().volume()
+}
+
+/// See [`Player::set_volume`](super::Player::set_volume).
+pub async fn set_volume(volume: f64) -> Result<()> {
+    player().set_volume(volume).await
+}
+
+/// See [`Player::change_volume`](super::Player::change_volume).
+pub async fn change_volume(step: f64) -> Result<()> {
+    player().change_volume(step).await
+}
+
+/// See [`Player::skip`](super::Player::skip).
+pub async fn skip(new_position: u32, force: bool) -> Result<()> {
+    player().skip(new_position, force).await
+}
+
+/// See [`Player::skip_to`](super::Player::skip_to).
+pub async fn skip_to(new_position: u32, generation: u64) -> Result<()> {
+    player().skip_to(new_position, generation).await
+}
+
+/// See [`Player::add_track_to_queue`](super::Player::add_track_to_queue).
+pub async fn add_track_to_queue(track_id: i32) -> Result<()> {
+    player().add_track_to_queue(track_id).await
+}
+
+/// See [`Player::add_album_to_queue`](super::Player::add_album_to_queue).
+pub async fn add_album_to_queue(album_id: &str) -> Result<()> {
+    player().add_album_to_queue(album_id).await
+}
+
+/// See [`Player::remove_from_queue`](super::Player::remove_from_queue).
+pub async fn remove_from_queue(position: u32, generation: u64) -> Result<()> {
+    player().remove_from_queue(position, generation).await
+}
+
+/// See [`Player::move_in_queue`](super::Player::move_in_queue).
+pub async fn move_in_queue(from: u32, to: u32, generation: u64) -> Result<()> {
+    player().move_in_queue(from, to, generation).await
+}
+
+/// See [`Player::is_shuffled`](super::Player::is_shuffled).
+pub async fn is_shuffled() -> bool {
+    player().is_shuffled().await
+}
+
+/// See [`Player::set_shuffle`](super::Player::set_shuffle).
+pub async fn set_shuffle(enabled: bool) -> Result<()> {
+    player().set_shuffle(enabled).await
+}
+
+/// See [`Player::toggle_shuffle`](super::Player::toggle_shuffle).
+pub async fn toggle_shuffle() -> Result<bool> {
+    player().toggle_shuffle().await
+}
+
+/// See [`Player::next`](super::Player::next).
+pub async fn next() -> Result<()> {
+    player().next().await
+}
+
+/// See [`Player::start_over`](super::Player::start_over).
+pub async fn start_over() -> Result<()> {
+    player().start_over().await
+}
+
+/// See [`Player::restart_list`](super::Player::restart_list).
+pub async fn restart_list() -> Result<()> {
+    player().restart_list().await
+}
+
+/// See [`Player::previous`](super::Player::previous).
+pub async fn previous() -> Result<()> {
+    player().previous().await
+}
+
+/// See [`Player::play_track`](super::Player::play_track).
+pub async fn play_track(track_id: i32) -> Result<()> {
+    player().play_track(track_id).await
+}
+
+/// See [`Player::play_album`](super::Player::play_album).
+pub async fn play_album(album_id: &str) -> Result<()> {
+    player().play_album(album_id).await
+}
+
+/// See [`Player::play_album_with_quality`](super::Player::play_album_with_quality).
+pub async fn play_album_with_quality(album_id: &str, quality: AudioQuality) -> Result<()> {
+    player().play_album_with_quality(album_id, quality).await
+}
+
+/// See [`Player::set_next_quality`](super::Player::set_next_quality).
+pub async fn set_next_quality(quality: Option<AudioQuality>) -> Result<()> {
+    player().set_next_quality(quality).await
+}
+
+/// See [`Player::play_album_from_track`](super::Player::play_album_from_track).
+pub async fn play_album_from_track(album_id: &str, track_id: i32) -> Result<()> {
+    player().play_album_from_track(album_id, track_id).await
+}
+
+/// See [`Player::play_playlist`](super::Player::play_playlist).
+pub async fn play_playlist(playlist_id: i64) -> Result<()> {
+    player().play_playlist(playlist_id).await
+}
+
+/// See [`Player::play_personal_playlist`](super::Player::play_personal_playlist).
+pub async fn play_personal_playlist(kind: PersonalPlaylist) -> Result<()> {
+    player().play_personal_playlist(kind).await
+}
+
+/// See [`Player::play_cached`](super::Player::play_cached).
+pub async fn play_cached() -> Result<()> {
+    player().play_cached().await
+}
+
+/// See [`Player::set_cached_only`](super::Player::set_cached_only).
+pub async fn set_cached_only(enabled: bool) -> Result<()> {
+    player().set_cached_only(enabled).await
+}
+
+/// See [`Player::play_uri`](super::Player::play_uri).
+pub async fn play_uri(uri: &str) -> Result<()> {
+    player().play_uri(uri).await
+}
+
+/// See [`Player::add_uri_to_queue`](super::Player::add_uri_to_queue).
+pub async fn add_uri_to_queue(uri: &str) -> Result<()> {
+    player().add_uri_to_queue(uri).await
+}
+
+/// See [`Player::switch_service`](super::Player::switch_service).
+pub async fn switch_service(name: &str) -> Result<()> {
+    player().switch_service(name).await
+}
+
+/// See [`Player::current_tracklist`](super::Player::current_tracklist).
+pub async fn current_tracklist() -> TrackListValue {
+    player().current_tracklist().await
+}
+
+/// See [`Player::current_track`](super::Player::current_track).
+pub async fn current_track() -> Option<Track> {
+    player().current_track().await
+}
+
+/// See [`Player::is_live`](super::Player::is_live).
+pub fn is_live() -> bool {
+    player().is_live()
+}
+
+/// See [`Player::is_buffering`](super::Player::is_buffering).
+pub fn is_buffering() -> bool {
+    player().is_buffering()
+}
+
+/// See [`Player::resume_after_outage`](super::Player::resume_after_outage).
+pub async fn resume_after_outage() -> Result<()> {
+    player().resume_after_outage().await
+}
+
+/// See [`Player::sign_in`](super::Player::sign_in).
+pub async fn sign_in(service: Arc<dyn MusicService>) -> Result<()> {
+    player().sign_in(service).await
+}
+
+/// See [`Player::service`](super::Player::service).
+pub async fn service() -> Arc<dyn MusicService> {
+    player().service().await
+}
+
+/// See [`Player::clock_loop`](super::Player::clock_loop).
+pub async fn clock_loop() {
+    player().clock_loop().await
+}
+
+/// See [`Player::end_trimmed_track`](super::Player::end_trimmed_track).
+pub(crate) async fn end_trimmed_track() -> Result<()> {
+    player().end_trimmed_track().await
+}
+
+/// See [`Player::quit`](super::Player::quit).
+pub async fn quit() -> Result<()> {
+    player().quit().await
+}
+
+/// See [`Player::player_loop`](super::Player::player_loop).
+pub async fn player_loop() -> Result<()> {
+    player().player_loop().await
+}
@@ -0,0 +1,260 @@
+use crate::{
+    player::{self, notification::Notification, queue, visualization},
+    service::Track,
+    sql::db,
+};
+use futures::StreamExt;
+use gstreamer::{self as gst, prelude::*, ClockTime, Element, MessageView, Structure};
+use once_cell::sync::Lazy;
+use serde::{Deserialize, Serialize};
+use std::{
+    collections::{HashMap, HashSet},
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Mutex,
+    },
+    time::{Duration, Instant},
+};
+
+/// The loudness tracks are brought to, the ReplayGain 2.0 reference level.
+const REFERENCE_LUFS: f64 = -18.;
+/// Seconds at the start of a track that are measured.
+const SCAN_SECONDS: u64 = 30;
+/// A scan that hasn't finished by then is given up.
+const SCAN_TIMEOUT: Duration = Duration::from_secs(60);
+const MIN_GAIN_DB: f64 = -20.;
+const MAX_GAIN_DB: f64 = 12.;
+
+static ENABLED: AtomicBool = AtomicBool::new(false);
+static SCANNED: Lazy<Mutex<HashMap<u32, Gain>>> = Lazy::new(|| Mutex::new(HashMap::new()));
+
+/// A track gain in dB and the track's sample peak, 1.0 being full scale.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct Gain {
+    pub db: f64,
+    pub peak: f64,
+}
+
+impl Gain {
+    /// The volume factor for the gain, lowered when it would push the peak past full scale.
+    pub fn factor(&self) -> f64 {
+        let factor = 10_f64.powf(self.db.clamp(MIN_GAIN_DB, MAX_GAIN_DB) / 20.);
+
+        if self.peak > 0. {
+            factor.min(1. / self.peak)
+        } else {
+            factor
+        }
+    }
+}
+
+pub fn set_enabled(enabled: bool) {
+    ENABLED.store(enabled, Ordering::Relaxed);
+}
+
+pub fn is_enabled() -> bool {
+    ENABLED.load(Ordering::Relaxed)
+}
+
+/// The gain for a track, from its ReplayGain tags or an earlier scan.
+pub async fn gain_for(track: &Track) -> Option<Gain> {
+    if let Some(db) = track.replay_gain {
+        return Some(Gain {
+            db,
+            peak: track.replay_peak.unwrap_or(1.),
+        });
+    }
+
+    if let Some(gain) = SCANNED.lock().unwrap().get(&track.id) {
+        return Some(*gain);
+    }
+
+    let gain = db::get_loudness(track.id).await?;
+    SCANNED.lock().unwrap().insert(track.id, gain);
+
+    Some(gain)
+}
+
+/// Measures the loudness of the current and the next track when they have no ReplayGain
+/// tags, one at a time in a pipeline of their own, while normalization is on. The rest of
+/// the queue waits its turn, a long one would otherwise be streamed all at once.
+pub async fn scan_loop() {
+    let (tx, rx) = flume::unbounded::<Track>();
+
+    tokio::spawn(async move {
+        while let Ok(track) = rx.recv_async().await {
+            if gain_for(&track).await.is_some() {
+                continue;
+            }
+
+            let fetch = queue().read().await.track_url(track.id);
+            let Some(url) = fetch.await else {
+                continue;
+            };
+
+            match tokio::task::spawn_blocking(move || scan(&url)).await {
+                Ok(Some(gain)) => {
+                    debug!(track.id, ?gain, "measured loudness");
+                    SCANNED.lock().unwrap().insert(track.id, gain);
+                    db::save_loudness(track.id, gain).await;
+                }
+                _ => debug!(track.id, "loudness scan failed"),
+            }
+        }
+    });
+
+    let mut receiver = player::notify_receiver();
+    let mut queued = HashSet::new();
+
+    while let Some(notification) = receiver.next().await {
+        match notification {
+            Notification::CurrentTrackList { list } => {
+                if !is_enabled() {
+                    continue;
+                }
+
+                let tracks = list
+                    .current_track()
+                    .into_iter()
+                    .chain(list.unplayed_tracks().into_iter().take(1))
+                    .filter(|t| t.available && t.replay_gain.is_none());
+
+                for track in tracks {
+                    if queued.insert(track.id) && tx.send(track.clone()).is_err() {
+                        return;
+                    }
+                }
+            }
+            Notification::Quit => break,
+            _ => {}
+        }
+    }
+}
+
+/// Play the first seconds of `url` into a fakesink as fast as it decodes and measure
+/// them. Uses BS.1770 loudness from `ebur128level` when the plugin is installed, or
+/// the plain rms from `level`, which is close enough for most music.
+fn scan(url: &str) -> Option<Gain> {
+    let meter = if gst::ElementFactory::find("ebur128level").is_some() {
+        "ebur128level"
+    } else {
+        "level"
+    };
+
+    let pipeline = gst::parse::launch(&format!(
+        "uridecodebin name=source ! audioconvert ! {meter} post-messages=true ! fakesink sync=false"
+    ))
+    .ok()?;
+
+    pipeline
+        .downcast_ref::<gst::Bin>()?
+        .by_name("source")?
+        .set_property("uri", url);
+
+    let measurement = measure(&pipeline);
+
+    if let Err(error) = pipeline.set_state(gst::State::Null) {
+        debug!(?error, "failed to stop the loudness pipeline");
+    }
+
+    measurement?.gain()
+}
+
+fn measure(pipeline: &Element) -> Option<Measurement> {
+    pipeline.set_state(gst::State::Playing).ok()?;
+
+    let bus = pipeline.bus()?;
+    let started = Instant::now();
+    let mut measurement = Measurement::default();
+
+    while started.elapsed() < SCAN_TIMEOUT {
+        let Some(msg) = bus.timed_pop(ClockTime::from_seconds(1)) else {
+            continue;
+        };
+
+        match msg.view() {
+            MessageView::Element(element) => {
+                if let Some(structure) = element.structure() {
+                    measurement.add(structure);
+                }
+
+                if pipeline
+                    .query_position::<ClockTime>()
+                    .is_some_and(|p| p.seconds() >= SCAN_SECONDS)
+                {
+                    break;
+                }
+            }
+            MessageView::Eos(_) => break,
+            MessageView::Error(error) => {
+                debug!(error = %error.error(), "loudness scan error");
+                return None;
+            }
+            _ => {}
+        }
+    }
+
+    Some(measurement)
+}
+
+#[derive(Debug, Default)]
+struct Measurement {
+    /// Integrated loudness so far, from `ebur128level`.
+    integrated: Option<f64>,
+    /// Summed mean square of each `level` interval.
+    power: f64,
+    intervals: u32,
+    peak: f64,
+}
+
+impl Measurement {
+    fn add(&mut self, structure: &Structure) {
+        match structure.name().as_str() {
+            "ebur128-level" => {
+                if let Ok(loudness) = structure.get::<f64>("global-loudness") {
+                    if loudness.is_finite() {
+                        self.integrated = Some(loudness);
+                    }
+                }
+
+                if let Ok(peaks) = structure.get::<gst::Array>("sample-peak") {
+                    for peak in peaks.iter().filter_map(|v| v.get::<f64>().ok()) {
+                        self.peak = self.peak.max(peak);
+                    }
+                }
+            }
+            "level" => {
+                if let Some((rms, peak)) = visualization::parse_level(structure) {
+                    if rms.is_empty() {
+                        return;
+                    }
+
+                    let power = rms.iter().map(|db| 10_f64.powf(db / 10.)).sum::<f64>();
+
+                    self.power += power / rms.len() as f64;
+                    self.intervals += 1;
+
+                    for db in peak {
+                        self.peak = self.peak.max(10_f64.powf(db / 20.));
+                    }
+                }
+            }
+            _ => {}
+        }
+    }
+
+    fn gain(&self) -> Option<Gain> {
+        let loudness = match self.integrated {
+            Some(loudness) => loudness,
+            None if self.intervals > 0 && self.power > 0. => {
+                10. * (self.power / self.intervals as f64).log10()
+            }
+            None => return None,
+        };
+
+        Some(Gain {
+            db: REFERENCE_LUFS - loudness,
+            peak: self.peak,
+        })
+    }
+}
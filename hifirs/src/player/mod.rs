@@ -1,572 +1,2453 @@
 use crate::{
+    config::{self, ConfigKey, Settings},
+    library, local,
     player::{
+        crossfade::Phase,
         error::Error,
         notification::{BroadcastReceiver, BroadcastSender, Notification},
+        output::OutputProfile,
+        playbin::PlaybinBackend,
         queue::{
-            controls::{PlayerState, SafePlayerState},
-            TrackListValue,
+            controls::{self, PlayerState, SafePlayerState},
+            ListProgress, TrackListValue,
         },
+        recovery::{Recovery, RecoveryPolicy},
+        transition::FormatChangePolicy,
+        visualization::Visualization,
     },
-    service::{Album, Playlist, SearchResults, Track},
+    service::{
+        self, Album, Artist, BrowseCategory, BrowsePage, Credentials, Favorite, Genre,
+        MusicService, PersonalPlaylist, Playlist, SearchLimits, SearchResults, ServiceUri, Track,
+        TrackStatus,
+    },
+    sql::db,
     REFRESH_RESOLUTION,
 };
-use cached::proc_macro::cached;
-use flume::{Receiver, Sender};
+use cached::{proc_macro::cached, Cached};
 use futures::prelude::*;
-use gst::{
-    prelude::*, Caps, ClockTime, Element, Message, MessageType, MessageView, SeekFlags,
-    State as GstState, StateChangeSuccess, Structure,
-};
+use gst::{prelude::*, ClockTime, Element, State as GstState};
 use gstreamer as gst;
-use hifirs_qobuz_api::client::{self, UrlType};
-use once_cell::sync::{Lazy, OnceCell};
+use hifirs_qobuz_api::client::{health, AudioQuality};
+use once_cell::sync::OnceCell;
 use std::{
-    str::FromStr,
     sync::{
         atomic::{AtomicBool, AtomicU32, Ordering},
-        Arc,
+        Arc, Mutex,
     },
-    time::Duration,
+    time::{Duration, Instant},
 };
 use tokio::{select, sync::RwLock};
 
-#[macro_use]
+pub(crate) mod account;
 pub mod actions;
+pub(crate) mod audition;
+pub(crate) mod autoplay;
+pub mod backend;
+pub(crate) mod cache;
+pub(crate) mod crossfade;
 pub mod error;
+pub(crate) mod event_log;
+mod facade;
+pub(crate) mod loudness;
 pub mod notification;
+pub mod output;
+pub(crate) mod party;
+pub mod playbin;
 #[macro_use]
 pub mod queue;
+pub mod recovery;
+pub mod schedule;
+pub(crate) mod session;
+pub(crate) mod simple;
+pub(crate) mod stats;
+pub mod sync;
+pub mod transition;
+pub mod trim;
+pub mod visualization;
+
+pub use backend::{BackendEvent, PlaybackBackend, PlaybackState, SeekMode, StateChange};
+pub use facade::*;
 
 pub type Result<T, E = Error> = std::result::Result<T, E>;
 
-static VERSION: Lazy<(u32, u32, u32, u32)> = Lazy::new(gstreamer::version);
+/// Positions closer than this to the start of the track are not restored after reloading it.
+const RESTORE_TOLERANCE_MS: u64 = 250;
+/// Pauses longer than this are assumed to have outlived the stream url and the
+/// pipeline clock, so the track is reloaded before playing again.
+const STALE_PAUSE_SECS: u64 = 20 * 60;
+/// How many levels or spectrums wait for a slow listener before the oldest are dropped.
+const VISUALIZATION_BACKLOG: usize = 4;
+/// How often the prefetched url of the next track is checked for expiry.
+const URL_CHECK_INTERVAL: Duration = Duration::from_secs(30);
+/// How much the volume keys raise or lower the volume.
+pub const VOLUME_STEP: f64 = 0.05;
+
+struct Broadcast {
+    tx: BroadcastSender,
+    rx: BroadcastReceiver,
+}
+
+impl Broadcast {
+    fn new() -> Self {
+        Self::with_capacity(20)
+    }
 
-static PLAYBIN: Lazy<Element> = Lazy::new(|| {
-    gst::init().expect("error initializing gstreamer");
+    /// Once `capacity` notifications are waiting, the oldest ones make room for new ones.
+    fn with_capacity(capacity: usize) -> Self {
+        let (mut tx, rx) = async_broadcast::broadcast(capacity);
+        tx.set_overflow(true);
 
-    let playbin = gst::ElementFactory::make("playbin3")
-        .build()
-        .expect("error building playbin element");
+        Broadcast { rx, tx }
+    }
+}
 
-    playbin.set_property_from_str("flags", "audio+buffering");
+/// A playback backend, the queue it plays, the channels its notifications go out on and
+/// everything the player keeps track of while it plays.
+///
+/// The binary runs one player, set up by [`init`] and driven through the functions of
+/// this module, which call the methods of the installed player. When hifi-rs is used as a
+/// library, or in tests, a player can be built around any backend with
+/// [`Player::with_backend`] and driven through its methods directly.
+pub struct Player {
+    backend: Box<dyn PlaybackBackend>,
+    /// The GStreamer pipeline of a player built with [`Player::new`], for output sinks,
+    /// visualization and synced playback. Other backends go without those.
+    pipeline: Option<Element>,
+    queue: SafePlayerState,
+    channels: Broadcast,
+    /// Levels and spectrums come several times a second, on a channel of their own so they
+    /// don't push the other notifications out before the listeners get to them.
+    visualization: Broadcast,
+    quit_when_done: AtomicBool,
+    is_buffering: AtomicBool,
+    is_live: AtomicBool,
+    visualization_enabled: AtomicBool,
+    format_change_policy: Mutex<FormatChangePolicy>,
+    resample_rate: AtomicU32,
+    /// Position of the next track when playback was stopped to insert a gap, or 0.
+    pending_gap: AtomicU32,
+    /// The track whose refused url was last replaced, see [`Player::refresh_rejected_url`].
+    refreshed_url_for: AtomicU32,
+    /// Set while the stream starting next is the replacement of a refused url.
+    url_refreshing: AtomicBool,
+    sampling_rate: AtomicU32,
+    bit_depth: AtomicU32,
+    output_profile: Mutex<Option<OutputProfile>>,
+    /// The sink to play through when the output profile doesn't name a device.
+    output_device: Mutex<Option<String>>,
+    /// The settings currently in effect, compared against when the config is reloaded.
+    settings: Mutex<Settings>,
+    /// The normalization gain of the current track, applied on top of the volume.
+    gain_factor: Mutex<f64>,
+    /// When the player was paused, cleared once it plays or is stopped.
+    paused_at: Mutex<Option<Instant>>,
+    /// What the player signed in with, to set up another service a uri asks for.
+    credentials: Mutex<Credentials>,
+    /// Playback was paused because it failed while the service was down.
+    held_for_outage: AtomicBool,
+}
 
-    if VERSION.1 >= 22 {
-        playbin.connect("element-setup", false, |value| {
-            let element = &value[1].get::<gst::Element>().unwrap();
+static PLAYER: OnceCell<Player> = OnceCell::new();
 
-            if element.name().contains("urisourcebin") {
-                element.set_property("parse-streams", true);
-            }
+impl Player {
+    /// A player streaming through GStreamer's playbin3.
+    pub fn new(state: PlayerState) -> Self {
+        let backend = PlaybinBackend::new();
+        let pipeline = backend.pipeline().clone();
 
-            None
-        });
+        Self {
+            pipeline: Some(pipeline),
+            ..Self::with_backend(backend, state)
+        }
     }
 
-    playbin.connect("source-setup", false, |value| {
-        let element = &value[1].get::<gst::Element>().unwrap();
+    /// A player on `backend`, e.g. a mock one in tests.
+    pub fn with_backend(backend: impl PlaybackBackend + 'static, mut state: PlayerState) -> Self {
+        let channels = Broadcast::new();
+        state.set_notifier(channels.tx.clone());
+
+        Self {
+            backend: Box::new(backend),
+            pipeline: None,
+            queue: Arc::new(RwLock::new(state)),
+            channels,
+            visualization: Broadcast::with_capacity(VISUALIZATION_BACKLOG),
+            quit_when_done: AtomicBool::new(false),
+            is_buffering: AtomicBool::new(false),
+            is_live: AtomicBool::new(false),
+            visualization_enabled: AtomicBool::new(true),
+            format_change_policy: Mutex::new(FormatChangePolicy::default()),
+            resample_rate: AtomicU32::new(0),
+            pending_gap: AtomicU32::new(0),
+            refreshed_url_for: AtomicU32::new(0),
+            url_refreshing: AtomicBool::new(false),
+            sampling_rate: AtomicU32::new(44100),
+            bit_depth: AtomicU32::new(16),
+            output_profile: Mutex::new(None),
+            output_device: Mutex::new(None),
+            settings: Mutex::new(Settings::default()),
+            gain_factor: Mutex::new(1.0),
+            paused_at: Mutex::new(None),
+            credentials: Mutex::new(Credentials::default()),
+            held_for_outage: AtomicBool::new(false),
+        }
+    }
 
-        if element.name().contains("souphttpsrc") {
-            debug!("new source, changing settings");
-            let ua = if rand::random() {
-                USER_AGENTS[0]
-            } else {
-                USER_AGENTS[1]
-            };
-            element.set_property("user-agent", ua);
-            element.set_property("compress", true);
-            element.set_property("retries", 10);
-            element.set_property("timeout", 30_u32);
-            element.set_property(
-                "extra-headers",
-                Structure::from_str("a-structure, DNT=1, Pragma=no-cache, Cache-Control=no-cache")
-                    .expect("failed to make structure from string"),
-            )
-        }
-
-        None
-    });
-
-    playbin.add_property_deep_notify_watch(Some("caps"), true);
-
-    // Connects to the `about-to-finish` signal so the player
-    // can setup the next track to play. Enables gapless playback.
-    playbin.connect("about-to-finish", false, move |_| {
-        debug!("about to finish");
-        ABOUT_TO_FINISH
-            .tx
-            .send(true)
-            .expect("failed to send about to finish message");
+    /// Make this the player the functions of this module drive. There can only be one.
+    pub fn install(self) {
+        if PLAYER.set(self).is_err() {
+            panic!("error setting player state");
+        }
+    }
+
+    pub fn backend(&self) -> &dyn PlaybackBackend {
+        self.backend.as_ref()
+    }
 
-        None
-    });
+    pub fn queue(&self) -> &SafePlayerState {
+        &self.queue
+    }
 
-    playbin
-});
+    /// The GStreamer pipeline, when the player has one.
+    fn playbin(&self) -> Option<&Element> {
+        self.pipeline.as_ref()
+    }
 
-struct Broadcast {
-    tx: BroadcastSender,
-    rx: BroadcastReceiver,
-}
+    /// Get a notification channel receiver
+    pub fn notify_receiver(&self) -> BroadcastReceiver {
+        self.channels.rx.clone()
+    }
 
-static BROADCAST_CHANNELS: Lazy<Broadcast> = Lazy::new(|| {
-    let (mut tx, rx) = async_broadcast::broadcast(20);
-    tx.set_overflow(true);
+    /// Get a receiver for the `Level` and `Spectrum` notifications, which don't go out with
+    /// the others.
+    pub fn visualization_receiver(&self) -> BroadcastReceiver {
+        self.visualization.rx.clone()
+    }
 
-    Broadcast { rx, tx }
-});
+    /// Send a notification to every listener.
+    pub async fn notify(&self, notification: Notification) -> Result<()> {
+        self.channels.tx.broadcast(notification).await?;
 
-struct AboutToFinish {
-    tx: Sender<bool>,
-    rx: Receiver<bool>,
-}
+        Ok(())
+    }
 
-static ABOUT_TO_FINISH: Lazy<AboutToFinish> = Lazy::new(|| {
-    let (tx, rx) = flume::bounded::<bool>(1);
+    /// Send the notification a change of state came back with, if it did.
+    async fn notify_change(&self, notification: Option<Notification>) -> Result<()> {
+        match notification {
+            Some(notification) => self.notify(notification).await,
+            None => Ok(()),
+        }
+    }
 
-    AboutToFinish { tx, rx }
-});
-static QUIT_WHEN_DONE: AtomicBool = AtomicBool::new(false);
-static IS_BUFFERING: AtomicBool = AtomicBool::new(false);
-static IS_LIVE: AtomicBool = AtomicBool::new(false);
-static SAMPLING_RATE: AtomicU32 = AtomicU32::new(44100);
-static BIT_DEPTH: AtomicU32 = AtomicU32::new(16);
-static QUEUE: OnceCell<SafePlayerState> = OnceCell::new();
-static USER_AGENTS: &[&str] = &[
-    "Mozilla/5.0 (Windows NT 10.0; Win64; x64) AppleWebKit/537.36 (KHTML, like Gecko) Chrome/114.0.0.0 Safari/537.36",
-    "Mozilla/5.0 (Macintosh; Intel Mac OS X 13_4) AppleWebKit/537.36 (KHTML, like Gecko) Chrome/114.0.0.0 Safari/537.36"
-];
+    #[instrument(skip(self))]
+    /// Attach a visualization element to the pipeline, posting
+    /// level or spectrum messages every `interval_ms` milliseconds.
+    pub fn set_visualization(&self, kind: Visualization, interval_ms: u64) {
+        if let Some(filter) = visualization::build_filter(kind, interval_ms) {
+            if let Some(playbin) = self.playbin() {
+                playbin.set_property("audio-filter", &filter);
+            }
+        }
+    }
 
-#[instrument]
-pub async fn init(
-    username: Option<&str>,
-    password: Option<&str>,
-    quit_when_done: bool,
-) -> Result<()> {
-    let state = Arc::new(RwLock::new(PlayerState::new(username, password).await));
-    let version = gstreamer::version();
-    debug!(?version);
+    #[instrument(skip(self))]
+    /// Set how the player handles sample rate or bit depth changes between tracks.
+    pub fn set_format_change_policy(&self, policy: FormatChangePolicy, resample_rate: u32) {
+        if policy == FormatChangePolicy::Resample {
+            if let Some(sink) = transition::build_resample_sink(resample_rate) {
+                if let Some(playbin) = self.playbin() {
+                    playbin.set_property("audio-sink", &sink);
+                }
+            }
+        }
 
-    QUEUE.set(state).expect("error setting player state");
-    QUIT_WHEN_DONE.store(quit_when_done, Ordering::Relaxed);
+        *self.format_change_policy.lock().unwrap() = policy;
+        self.resample_rate.store(resample_rate, Ordering::Relaxed);
+    }
 
-    Ok(())
-}
-#[instrument]
-/// Play the player.
-pub async fn play() -> Result<()> {
-    set_player_state(gst::State::Playing).await?;
-    Ok(())
-}
-#[instrument]
-/// Pause the player.
-pub async fn pause() -> Result<()> {
-    set_player_state(gst::State::Paused).await?;
-    Ok(())
-}
-#[instrument]
-/// Ready the player.
-pub async fn ready() -> Result<()> {
-    set_player_state(gst::State::Ready).await?;
-    Ok(())
-}
-#[instrument]
-/// Stop the player.
-pub async fn stop() -> Result<()> {
-    set_player_state(gst::State::Null).await?;
-    Ok(())
-}
-#[instrument]
-/// Sets the player to a specific state.
-pub async fn set_player_state(state: gst::State) -> Result<()> {
-    let ret = PLAYBIN.set_state(state)?;
+    fn format_change_policy(&self) -> FormatChangePolicy {
+        *self.format_change_policy.lock().unwrap()
+    }
+
+    #[instrument(skip(self))]
+    /// Remember the settings the player was started with.
+    pub fn set_settings(&self, settings: Settings) {
+        *self.settings.lock().unwrap() = settings;
+    }
 
-    match ret {
-        StateChangeSuccess::Success => {
-            debug!("*** successful state change ***");
+    #[instrument(skip(self))]
+    /// Turn the visualization messages on or off. Returns the new state.
+    pub fn toggle_visualization(&self) -> bool {
+        let enabled = !self.visualization_enabled.load(Ordering::Relaxed);
+        self.visualization_enabled.store(enabled, Ordering::Relaxed);
+
+        if let Some(filter) = self
+            .playbin()
+            .and_then(|playbin| playbin.property::<Option<Element>>("audio-filter"))
+        {
+            filter.set_property("post-messages", enabled);
         }
-        StateChangeSuccess::Async => {
-            debug!("*** async state change ***");
 
-            BROADCAST_CHANNELS
-                .tx
-                .broadcast(Notification::Loading {
-                    is_loading: true,
-                    target_state: state,
-                })
-                .await?;
+        enabled
+    }
+
+    #[instrument(skip(self))]
+    /// Play the player.
+    pub async fn play(&self) -> Result<()> {
+        if let Some(paused_for) = self.stale_pause() {
+            return self.resume_stale(paused_for).await;
         }
-        StateChangeSuccess::NoPreroll => {
-            debug!("*** stream is live ***");
-            IS_LIVE.store(true, Ordering::Relaxed);
+
+        self.set_player_state(GstState::Playing).await?;
+        Ok(())
+    }
+
+    /// How long the player has been paused, if it was long enough for the stream to go stale.
+    fn stale_pause(&self) -> Option<Duration> {
+        if !self.is_paused() {
+            return None;
         }
+
+        self.paused_at
+            .lock()
+            .unwrap()
+            .map(|paused_at| paused_at.elapsed())
+            .filter(|paused_for| paused_for.as_secs() >= STALE_PAUSE_SECS)
     }
 
-    Ok(())
-}
-async fn broadcast_track_list<'a>(list: &TrackListValue) -> Result<()> {
-    BROADCAST_CHANNELS
-        .tx
-        .broadcast(Notification::CurrentTrackList { list: list.clone() })
-        .await?;
-    Ok(())
-}
-#[instrument]
-/// Toggle play and pause.
-pub async fn play_pause() -> Result<()> {
-    let mut state = QUEUE.get().unwrap().write().await;
-
-    if is_playing() {
-        state.set_target_status(GstState::Paused);
-        pause().await?;
-    } else if is_paused() || is_ready() {
-        state.set_target_status(GstState::Playing);
-        play().await?;
+    /// Resume after a long pause. The url of the current track has most likely expired,
+    /// so a new one is fetched and the track is prepared again at the saved position.
+    async fn resume_stale(&self, paused_for: Duration) -> Result<()> {
+        let last_position = self.position().unwrap_or_default();
+        let url = self.queue.write().await.refresh_current_url().await;
+
+        match url {
+            Some(url) => {
+                info!(
+                    paused_secs = paused_for.as_secs(),
+                    ?last_position,
+                    "long pause, resuming with a fresh track url"
+                );
+
+                self.ready().await?;
+                self.backend.set_uri(&url);
+                self.restore_position(last_position, GstState::Playing)
+                    .await
+            }
+            None => {
+                warn!(
+                    paused_secs = paused_for.as_secs(),
+                    "long pause, could not refresh the track url, resuming with the old one"
+                );
+
+                self.paused_at.lock().unwrap().take();
+                self.set_player_state(GstState::Playing).await
+            }
+        }
     }
 
-    Ok(())
-}
-#[instrument]
-/// Is the player paused?
-pub fn is_paused() -> bool {
-    PLAYBIN.current_state() == gst::State::Paused
-}
-#[instrument]
-/// Is the player playing?
-pub fn is_playing() -> bool {
-    PLAYBIN.current_state() == gst::State::Playing
-}
-#[instrument]
-/// Is the player ready?
-pub fn is_ready() -> bool {
-    PLAYBIN.current_state() == gst::State::Ready
-}
-#[instrument]
-/// Current player state
-pub fn current_state() -> GstState {
-    PLAYBIN.current_state()
-}
-#[instrument]
-/// Current track position.
-pub fn position() -> Option<ClockTime> {
-    PLAYBIN.query_position::<ClockTime>()
-}
-#[instrument]
-/// Current track duraiton.
-pub fn duration() -> Option<ClockTime> {
-    PLAYBIN.query_duration::<ClockTime>()
-}
-#[instrument]
-/// Seek to a specified time in the current track.
-pub async fn seek(time: ClockTime, flags: Option<SeekFlags>) -> Result<()> {
-    let flags = flags.unwrap_or(SeekFlags::FLUSH | SeekFlags::TRICKMODE_KEY_UNITS);
+    #[instrument(skip(self))]
+    /// Pause the player.
+    pub async fn pause(&self) -> Result<()> {
+        crossfade::stop_tail();
+        self.set_player_state(GstState::Paused).await?;
+        Ok(())
+    }
 
-    PLAYBIN.seek_simple(flags, time)?;
-    Ok(())
-}
-#[instrument]
-/// Load the previous player state and seek to the last known position.
-pub async fn resume(autoplay: bool) -> Result<()> {
-    let mut state = QUEUE.get().unwrap().write().await;
+    #[instrument(skip(self))]
+    /// Ready the player.
+    pub async fn ready(&self) -> Result<()> {
+        self.set_player_state(GstState::Ready).await?;
+        Ok(())
+    }
+
+    #[instrument(skip(self))]
+    /// Stop the player.
+    pub async fn stop(&self) -> Result<()> {
+        crossfade::stop_tail();
+        self.set_player_state(GstState::Null).await?;
+        Ok(())
+    }
 
-    if let Some(last_position) = state.load_last_state().await {
-        state.set_resume(true);
+    #[instrument(skip(self))]
+    /// Sets the player to a specific state.
+    pub async fn set_player_state(&self, state: GstState) -> Result<()> {
+        let ret = self.backend.set_state(state.into())?;
 
-        let list = state.track_list();
-        BROADCAST_CHANNELS
+        let mut paused_at = self.paused_at.lock().unwrap();
+        if state == GstState::Paused {
+            paused_at.get_or_insert_with(Instant::now);
+        } else {
+            *paused_at = None;
+        }
+        drop(paused_at);
+
+        match ret {
+            StateChange::Done => {
+                debug!("*** successful state change ***");
+            }
+            StateChange::Pending => {
+                debug!("*** async state change ***");
+
+                self.channels
+                    .tx
+                    .broadcast(Notification::Loading {
+                        is_loading: true,
+                        target_state: state,
+                    })
+                    .await?;
+            }
+            StateChange::Live => {
+                debug!("*** stream is live ***");
+                self.is_live.store(true, Ordering::Relaxed);
+            }
+        }
+
+        Ok(())
+    }
+
+    async fn broadcast_track_list(&self, list: &TrackListValue) -> Result<()> {
+        self.channels
             .tx
             .broadcast(Notification::CurrentTrackList { list: list.clone() })
             .await?;
+        Ok(())
+    }
 
-        if autoplay {
-            state.set_target_status(GstState::Playing);
-        } else {
+    /// Tell the front-ends about queued tracks the service no longer offers.
+    async fn broadcast_withdrawn(&self, tracks: Vec<Track>) -> Result<()> {
+        for track in tracks {
+            self.channels
+                .tx
+                .broadcast(Notification::TrackUnavailable { track })
+                .await?;
+        }
+
+        Ok(())
+    }
+
+    #[instrument(skip(self))]
+    /// Toggle play and pause.
+    pub async fn play_pause(&self) -> Result<()> {
+        let mut state = self.queue.write().await;
+
+        if self.is_playing() {
             state.set_target_status(GstState::Paused);
+            self.pause().await?;
+        } else if self.is_paused() || self.is_ready() {
+            state.set_target_status(GstState::Playing);
+            // Resuming after a long pause reloads the track, which needs the state.
+            drop(state);
+            self.play().await?;
         }
 
-        if let Some(track) = state.current_track() {
-            if let Some(url) = &track.track_url {
-                PLAYBIN.set_property("uri", url);
+        Ok(())
+    }
+
+    #[instrument(skip(self))]
+    /// Is the player paused?
+    pub fn is_paused(&self) -> bool {
+        self.backend.state() == PlaybackState::Paused
+    }
+
+    #[instrument(skip(self))]
+    /// Is the player playing?
+    pub fn is_playing(&self) -> bool {
+        self.backend.state() == PlaybackState::Playing
+    }
 
-                ready().await?;
-                pause().await?;
+    #[instrument(skip(self))]
+    /// Is the player ready?
+    pub fn is_ready(&self) -> bool {
+        self.backend.state() == PlaybackState::Ready
+    }
 
-                let mut interval = tokio::time::interval(Duration::from_millis(100));
+    #[instrument(skip(self))]
+    /// Current player state
+    pub fn current_state(&self) -> GstState {
+        self.backend.state().into()
+    }
 
-                while !is_paused() {
-                    debug!("wait for paused state");
-                    interval.tick().await;
-                }
+    #[instrument(skip(self))]
+    /// Current track position.
+    pub fn position(&self) -> Option<ClockTime> {
+        self.backend.position().map(clock_time)
+    }
 
-                seek(last_position, None).await?;
+    #[instrument(skip(self))]
+    /// Current track duraiton.
+    pub fn duration(&self) -> Option<ClockTime> {
+        self.backend.duration().map(clock_time)
+    }
 
-                return Ok(());
+    #[instrument(skip(self))]
+    /// Progress through the album or playlist at `position` in the current track.
+    pub async fn list_progress(&self, position: ClockTime) -> Option<ListProgress> {
+        self.queue.read().await.list_progress(position)
+    }
+
+    #[instrument(skip(self))]
+    /// Seek to a specified time in the current track.
+    pub async fn seek(&self, time: ClockTime, mode: SeekMode) -> Result<()> {
+        self.backend
+            .seek(Duration::from_nanos(time.nseconds()), mode)?;
+        Ok(())
+    }
+
+    #[instrument(skip(self))]
+    /// Load the previous player state and seek to the last known position.
+    pub async fn resume(&self, autoplay: bool) -> Result<()> {
+        let mut state = self.queue.write().await;
+
+        if let Some(last_position) = state.load_last_state().await {
+            state.set_resume(true);
+
+            let list = state.track_list();
+            self.channels
+                .tx
+                .broadcast(Notification::CurrentTrackList { list: list.clone() })
+                .await?;
+
+            if autoplay {
+                state.set_target_status(GstState::Playing);
+            } else {
+                state.set_target_status(GstState::Paused);
+            }
+
+            if let Some(track) = state.current_track() {
+                if let Some(url) = &track.track_url {
+                    self.backend.set_uri(url);
+
+                    self.ready().await?;
+                    self.pause().await?;
+
+                    let mut interval = tokio::time::interval(Duration::from_millis(100));
+
+                    while !self.is_paused() {
+                        debug!("wait for paused state");
+                        interval.tick().await;
+                    }
+
+                    self.seek(last_position, SeekMode::Fast).await?;
+
+                    return Ok(());
+                } else {
+                    return Err(Error::Resume);
+                }
             } else {
                 return Err(Error::Resume);
             }
-        } else {
-            return Err(Error::Resume);
         }
+
+        Ok(())
     }
 
-    Ok(())
-}
-#[instrument]
-/// Jump forward in the currently playing track +10 seconds.
-pub async fn jump_forward() -> Result<()> {
-    if let (Some(current_position), Some(duration)) = (
-        PLAYBIN.query_position::<ClockTime>(),
-        PLAYBIN.query_duration::<ClockTime>(),
-    ) {
-        let ten_seconds = ClockTime::from_seconds(10);
-        let next_position = current_position + ten_seconds;
-
-        if next_position < duration {
-            seek(next_position, None).await?;
+    #[instrument(skip(self))]
+    /// Turn party mode on, see [`party::enable`].
+    pub async fn enable_party_mode(&self) -> Result<()> {
+        self.notify_change(party::enable().await?).await
+    }
+
+    #[instrument(skip(self, pin))]
+    /// Turn party mode off if `pin` is the configured PIN.
+    pub async fn disable_party_mode(&self, pin: &str) -> Result<()> {
+        self.notify_change(party::disable(pin).await?).await
+    }
+
+    #[instrument(skip(self, pin))]
+    /// Leave simple mode for the rest of the session if `pin` is the party mode PIN.
+    pub async fn unlock_simple_mode(&self, pin: &str) -> Result<()> {
+        self.notify_change(simple::unlock(pin).await?).await
+    }
+
+    #[instrument(skip(self))]
+    /// Save the queue as `name`, to switch back to it with [`load_queue`].
+    pub async fn save_queue(&self, name: &str) -> Result<()> {
+        party::check()?;
+
+        let position = self.position().unwrap_or_default();
+
+        if self.queue.read().await.save_queue(name, position).await {
+            Ok(())
         } else {
-            seek(duration, None).await?;
+            Err(Error::EmptyQueue)
         }
     }
 
-    Ok(())
-}
-#[instrument]
-/// Jump forward in the currently playing track -10 seconds.
-pub async fn jump_backward() -> Result<()> {
-    if let Some(current_position) = PLAYBIN.query_position::<ClockTime>() {
-        if current_position.seconds() < 10 {
-            seek(ClockTime::default(), None).await?;
-        } else {
-            let ten_seconds = ClockTime::from_seconds(10);
-            let seek_position = current_position - ten_seconds;
+    #[instrument(skip(self))]
+    /// Replace the queue with the one saved as `name` and continue where it was left.
+    pub async fn load_queue(&self, name: &str) -> Result<()> {
+        party::check()?;
+
+        let snapshot = db::get_queue_snapshot(name)
+            .await
+            .ok_or_else(|| Error::QueueNotFound {
+                name: name.to_string(),
+            })?;
+        let position = ClockTime::from_mseconds(snapshot.position);
+
+        self.ready().await?;
+
+        let mut state = self.queue.write().await;
+        let url = state.load_queue(snapshot).await;
+        state.set_target_status(GstState::Playing);
 
-            seek(seek_position, None).await?;
+        let list = state.track_list();
+        self.broadcast_track_list(&list).await?;
+        drop(state);
+
+        match url {
+            Some(url) => {
+                self.backend.set_uri(&url);
+                self.restore_position(position, GstState::Playing).await
+            }
+            None => Err(Error::TrackURL),
         }
     }
 
-    Ok(())
-}
-#[instrument]
-/// Skip to a specific track in the playlist.
-pub async fn skip(new_position: u32, force: bool) -> Result<()> {
-    let mut state = QUEUE.get().unwrap().write().await;
-    let current_position = state.current_track_position();
-    let total_tracks = state.track_list().total();
-
-    // Typical previous skip functionality where if,
-    // the track is greater than 1 second into playing,
-    // then it goes to the beginning. If triggered again
-    // within a second after playing, it will skip to the previous track.
-    // Ignore if going from the last track to the first (EOS).
-    if !force
-        && new_position < current_position
-        && total_tracks != current_position
-        && new_position != 1
-    {
-        if let Some(current_player_position) = position() {
-            if current_player_position.seconds() > 1 {
-                debug!("current track position >1s, seeking to start of track");
+    #[instrument(skip(self))]
+    /// Reload the current track at a different quality and return to the same position.
+    /// The new quality is used for every following track as well.
+    pub async fn switch_quality(&self, quality: AudioQuality) -> Result<()> {
+        party::check()?;
+
+        let last_position = self.position().unwrap_or_default();
+
+        let mut state = self.queue.write().await;
+        let target_status = state.target_status();
+        let has_track = state.current_track().is_some();
+
+        let url = state.switch_quality(quality).await;
+        drop(state);
+
+        if let Some(url) = url {
+            self.ready().await?;
+            self.backend.set_uri(&url);
+            self.restore_position(last_position, target_status).await?;
+        } else if has_track {
+            return Err(Error::TrackURL);
+        }
+
+        Ok(())
+    }
+
+    /// Preroll the current track, seek back to `last_position` and continue in `target_status`.
+    async fn restore_position(
+        &self,
+        last_position: ClockTime,
+        target_status: GstState,
+    ) -> Result<()> {
+        self.pause().await?;
+
+        let mut interval = tokio::time::interval(Duration::from_millis(100));
+
+        while !self.is_paused() {
+            debug!("wait for paused state");
+            interval.tick().await;
+        }
+
+        if last_position.mseconds() > RESTORE_TOLERANCE_MS {
+            self.seek(last_position, SeekMode::Accurate).await?;
+        }
+
+        self.set_player_state(target_status).await?;
+
+        Ok(())
+    }
+
+    #[instrument(skip(self))]
+    /// Play through another sink, `None` for the system default. An output profile with a device
+    /// of its own still uses that one.
+    pub async fn set_output_device(&self, device: Option<String>) -> Result<()> {
+        party::check()?;
+
+        *self.output_device.lock().unwrap() = device.clone();
+        self.settings.lock().unwrap().output_device = device;
+
+        self.rebuild_sink().await
+    }
+
+    #[instrument(skip(self))]
+    /// Switch to another output profile. The sink can only be swapped while the
+    /// pipeline is stopped, so the current track is reloaded at the same position.
+    pub async fn set_output_profile(&self, profile: OutputProfile) -> Result<()> {
+        party::check()?;
+
+        *self.output_profile.lock().unwrap() = Some(profile.clone());
+        self.settings.lock().unwrap().output_profile = Some(profile.name.clone());
+
+        self.rebuild_sink().await?;
+
+        if profile.bit_perfect {
+            *self.gain_factor.lock().unwrap() = 1.0;
+        }
+
+        let volume = profile.effective_volume();
+        self.backend.set_volume(volume * self.gain_factor());
+
+        self.channels
+            .tx
+            .broadcast(Notification::OutputProfileChanged { profile })
+            .await?;
+        self.notify(Notification::Volume { volume }).await?;
+
+        Ok(())
+    }
+
+    /// Build the audio sink for the active output profile and format change policy
+    /// and swap it in, reloading the current track at the same position.
+    async fn rebuild_sink(&self) -> Result<()> {
+        let last_position = self.position().unwrap_or_default();
+        let target_status = self.queue.read().await.target_status();
+        let was_loaded = self.current_state() >= GstState::Paused;
+
+        if was_loaded {
+            self.ready().await?;
+        }
+
+        let resample_rate = (self.format_change_policy() == FormatChangePolicy::Resample)
+            .then(|| self.resample_rate.load(Ordering::Relaxed));
+        let device = self.output_device.lock().unwrap().clone();
+        let profile = self
+            .output_profile
+            .lock()
+            .unwrap()
+            .clone()
+            .map(|mut profile| {
+                profile.device = profile.device.or_else(|| device.clone());
+                profile
+            });
+
+        let sink = match (profile, resample_rate, device) {
+            (Some(profile), _, _) => output::build_sink(&profile, resample_rate),
+            (None, _, Some(device)) => {
+                output::build_sink(&OutputProfile::for_device(device), resample_rate)
+            }
+            (None, Some(rate), None) => Ok(transition::build_resample_sink(rate)),
+            (None, None, None) => Ok(None),
+        };
+        // Playing on through the default sink beats stopping, but the front-ends are told.
+        let sink = match sink {
+            Ok(sink) => sink,
+            Err(error) => {
+                warn!(
+                    ?error,
+                    "failed to build the output sink, using the default one"
+                );
+                self.notify(Notification::Error { error }).await?;
+
+                None
+            }
+        };
+        if let Some(playbin) = self.playbin() {
+            playbin.set_property("audio-sink", sink);
+        }
+
+        if was_loaded {
+            self.restore_position(last_position, target_status).await?;
+        }
+
+        Ok(())
+    }
+
+    #[instrument(skip(self))]
+    /// Read the settings again and apply the ones that changed, without stopping playback.
+    /// Returns the keys that changed.
+    pub async fn reload_config(&self) -> Result<Vec<ConfigKey>> {
+        party::check()?;
+
+        let settings = config::settings().await;
+        let previous = std::mem::replace(&mut *self.settings.lock().unwrap(), settings.clone());
+        let changed = previous.changed(&settings);
+
+        if changed.is_empty() {
+            info!("config reloaded, nothing changed");
+            return Ok(changed);
+        }
+
+        recovery::set_policy(settings.recovery);
+        service::set_edition_hints(settings.edition_hints);
+        loudness::set_enabled(settings.normalization);
+        autoplay::set_enabled(settings.autoplay);
+        crossfade::set_seconds(settings.crossfade);
+        cache::set_size_limit(settings.cache_size);
+        simple::set_playlists(settings.simple_playlists.clone());
+
+        let resample_before = (previous.format_change == FormatChangePolicy::Resample)
+            .then_some(previous.resample_rate);
+        let resample_after = (settings.format_change == FormatChangePolicy::Resample)
+            .then_some(settings.resample_rate);
+
+        *self.format_change_policy.lock().unwrap() = settings.format_change;
+        self.resample_rate
+            .store(settings.resample_rate, Ordering::Relaxed);
+
+        let device_changed = changed.contains(&ConfigKey::OutputDevice);
+
+        if device_changed {
+            *self.output_device.lock().unwrap() = settings.output_device.clone();
+        }
+
+        let sink_changed = resample_before != resample_after || device_changed;
+
+        if changed.contains(&ConfigKey::OutputProfile) {
+            let profile = match &settings.output_profile {
+                Some(name) => match db::get_output_profile(name).await {
+                    Some(profile) => Some(profile),
+                    None => {
+                        warn!(name, "output profile in the config does not exist");
+                        self.output_profile.lock().unwrap().clone()
+                    }
+                },
+                None => None,
+            };
+
+            match profile {
+                Some(profile) => self.set_output_profile(profile).await?,
+                None => {
+                    *self.output_profile.lock().unwrap() = None;
+                    self.backend.set_volume(self.gain_factor());
+                    self.rebuild_sink().await?;
+                }
+            }
+        } else if sink_changed {
+            self.rebuild_sink().await?;
+        }
+
+        if changed.contains(&ConfigKey::Normalization) {
+            if let Some(track) = self.current_track().await {
+                self.apply_normalization(&track).await;
+            }
+        }
+
+        let needs_restart = changed
+            .iter()
+            .copied()
+            .filter(ConfigKey::needs_restart)
+            .collect::<Vec<ConfigKey>>();
+
+        info!(?changed, ?needs_restart, "config reloaded");
+
+        self.channels
+            .tx
+            .broadcast(Notification::ConfigReloaded {
+                changed: changed.clone(),
+                needs_restart,
+            })
+            .await?;
+
+        Ok(changed)
+    }
+
+    #[instrument(skip(self))]
+    /// Jump forward in the currently playing track +10 seconds.
+    pub async fn jump_forward(&self) -> Result<()> {
+        party::check()?;
+
+        if let (Some(current_position), Some(duration)) = (self.position(), self.duration()) {
+            let ten_seconds = ClockTime::from_seconds(10);
+            let next_position = current_position + ten_seconds;
+
+            if next_position < duration {
+                self.seek(next_position, SeekMode::Fast).await?;
+            } else {
+                self.seek(duration, SeekMode::Fast).await?;
+            }
+        }
+
+        Ok(())
+    }
+
+    #[instrument(skip(self))]
+    /// Jump forward in the currently playing track -10 seconds.
+    pub async fn jump_backward(&self) -> Result<()> {
+        party::check()?;
+
+        if let Some(current_position) = self.position() {
+            if current_position.seconds() < 10 {
+                self.seek(ClockTime::default(), SeekMode::Fast).await?;
+            } else {
+                let ten_seconds = ClockTime::from_seconds(10);
+                let seek_position = current_position - ten_seconds;
+
+                self.seek(seek_position, SeekMode::Fast).await?;
+            }
+        }
+
+        Ok(())
+    }
+
+    #[instrument(skip(self))]
+    /// Seek to `seconds` into the current track, or by `seconds` from the current
+    /// position when `relative`. The target is kept within the track.
+    pub async fn seek_seconds(&self, seconds: i64, relative: bool) -> Result<()> {
+        party::check()?;
+
+        let (Some(current_position), Some(duration)) = (self.position(), self.duration()) else {
+            return Ok(());
+        };
+
+        let target = if relative {
+            current_position.seconds() as i64 + seconds
+        } else {
+            seconds
+        };
+        let target = target.clamp(0, duration.seconds() as i64) as u64;
+
+        self.seek(ClockTime::from_seconds(target), SeekMode::Fast)
+            .await
+    }
+
+    #[instrument(skip(self))]
+    /// Seek to `position` in the current track, or to its end when `position` is past it.
+    pub async fn seek_to(&self, position: ClockTime) -> Result<()> {
+        party::check()?;
+
+        let Some(duration) = self.duration() else {
+            return Ok(());
+        };
+
+        self.seek(position.min(duration), SeekMode::Accurate).await
+    }
+
+    #[instrument(skip(self))]
+    /// Seek to `percent` of the current track, between 0 and 100.
+    pub async fn seek_percent(&self, percent: f32) -> Result<()> {
+        let Some(duration) = self.duration() else {
+            return Ok(());
+        };
+
+        let fraction = (percent as f64 / 100.).clamp(0., 1.);
+
+        self.seek_to(ClockTime::from_nseconds(
+            (duration.nseconds() as f64 * fraction) as u64,
+        ))
+        .await
+    }
+
+    #[instrument(skip(self))]
+    /// The playback volume, between 0.0 and 1.0, without the normalization gain.
+    pub fn volume(&self) -> f64 {
+        (self.backend.volume() / self.output_gain()).clamp(0.0, 1.0)
+    }
+
+    #[instrument(skip(self))]
+    /// Set the playback volume, between 0.0 and 1.0. A bit perfect output profile
+    /// always plays at full volume.
+    pub async fn set_volume(&self, volume: f64) -> Result<()> {
+        // Party mode leaves the volume to the guests, simple mode doesn't.
+        simple::check()?;
+
+        if self.is_bit_perfect() {
+            debug!("ignoring volume change for a bit perfect output profile");
+            return Ok(());
+        }
+
+        let volume = volume.clamp(0.0, 1.0);
+        self.backend.set_volume(volume * self.output_gain());
+
+        self.notify(Notification::Volume { volume }).await
+    }
+
+    #[instrument(skip(self))]
+    /// Raise or lower the volume by `step`, e.g. `-VOLUME_STEP`.
+    pub async fn change_volume(&self, step: f64) -> Result<()> {
+        self.set_volume(self.volume() + step).await
+    }
+
+    fn is_bit_perfect(&self) -> bool {
+        self.output_profile
+            .lock()
+            .unwrap()
+            .as_ref()
+            .is_some_and(|p| p.bit_perfect)
+    }
+
+    fn gain_factor(&self) -> f64 {
+        *self.gain_factor.lock().unwrap()
+    }
+
+    /// Everything the volume is multiplied with: the normalization gain and the crossfade.
+    fn output_gain(&self) -> f64 {
+        self.gain_factor() * crossfade::level()
+    }
+
+    /// Scale the volume by the normalization gain of `track`. The gain is removed when
+    /// normalization is off, the track's loudness isn't known yet or the output is bit perfect.
+    async fn apply_normalization(&self, track: &Track) {
+        let factor = if loudness::is_enabled() && !self.is_bit_perfect() {
+            loudness::gain_for(track)
+                .await
+                .map(|gain| gain.factor())
+                .unwrap_or(1.0)
+        } else {
+            1.0
+        };
+
+        let volume = self.volume();
+        *self.gain_factor.lock().unwrap() = factor;
+        self.backend.set_volume(volume * self.output_gain());
+
+        debug!(track.id, factor, "normalization gain applied");
+    }
+
+    #[instrument(skip(self))]
+    /// Skip to a specific track in the playlist.
+    pub async fn skip(&self, new_position: u32, force: bool) -> Result<()> {
+        self.skip_checked(new_position, force, None).await
+    }
+
+    #[instrument(skip(self))]
+    /// Skip to a specific track, but only if the queue is still at `generation`.
+    /// Used by remote clients, whose view of the queue may be out of date.
+    pub async fn skip_to(&self, new_position: u32, generation: u64) -> Result<()> {
+        party::check()?;
+
+        self.skip_checked(new_position, true, Some(generation))
+            .await
+    }
+
+    #[instrument(skip(self))]
+    /// Add a track to the end of the queue without interrupting playback, or play it
+    /// when nothing is queued.
+    pub async fn add_track_to_queue(&self, track_id: i32) -> Result<()> {
+        party::check()?;
+
+        let state = self.queue.read().await;
+
+        if state.track_list().queue.is_empty() {
+            drop(state);
+            return self.play_track(track_id).await;
+        }
+
+        let service = state.track_service(track_id);
+        drop(state);
+
+        // Fetched without holding the queue, the player goes on while the service answers.
+        let track = service.track(track_id).await.ok_or(Error::NotFound)?;
+
+        let mut state = self.queue.write().await;
+        state.add_track_to_queue(track);
+
+        let list = state.track_list();
+        drop(state);
+
+        self.broadcast_track_list(&list).await
+    }
+
+    #[instrument(skip(self))]
+    /// Add a whole album to the end of the queue without interrupting playback, or play
+    /// it when nothing is queued.
+    pub async fn add_album_to_queue(&self, album_id: &str) -> Result<()> {
+        party::check()?;
+
+        let state = self.queue.read().await;
+
+        if state.track_list().queue.is_empty() {
+            drop(state);
+            return self.play_album(album_id).await;
+        }
+
+        let service = state.album_service(album_id);
+        drop(state);
+
+        let album = service.album(album_id).await.ok_or(Error::NotFound)?;
+
+        let mut state = self.queue.write().await;
+        state.add_album_to_queue(album);
+
+        let list = state.track_list();
+        drop(state);
+
+        self.broadcast_track_list(&list).await
+    }
+
+    #[instrument(skip(self))]
+    /// Remove an upcoming track from the queue, if the queue is still at `generation`.
+    pub async fn remove_from_queue(&self, position: u32, generation: u64) -> Result<()> {
+        party::check()?;
+
+        let mut state = self.queue.write().await;
+        let current = state.generation();
+
+        if generation != current {
+            debug!(generation, current, "rejecting removal from a stale queue");
+            return Err(Error::StaleQueue {
+                expected: generation,
+                current,
+            });
+        }
+
+        if state.remove_track(position).is_none() {
+            return Err(Error::NotUpcoming);
+        }
+
+        let list = state.track_list();
+        drop(state);
+
+        self.broadcast_track_list(&list).await
+    }
+
+    #[instrument(skip(self))]
+    /// Move an upcoming track to another upcoming position, if the queue is still at `generation`.
+    pub async fn move_in_queue(&self, from: u32, to: u32, generation: u64) -> Result<()> {
+        party::check()?;
+
+        let mut state = self.queue.write().await;
+        let current = state.generation();
+
+        if generation != current {
+            debug!(generation, current, "rejecting a move in a stale queue");
+            return Err(Error::StaleQueue {
+                expected: generation,
+                current,
+            });
+        }
+
+        if !state.move_track(from, to) {
+            return Err(Error::NotUpcoming);
+        }
+
+        let list = state.track_list();
+        drop(state);
+
+        self.broadcast_track_list(&list).await
+    }
+
+    #[instrument(skip(self))]
+    /// Whether the upcoming tracks are shuffled.
+    pub async fn is_shuffled(&self) -> bool {
+        self.queue.read().await.shuffle()
+    }
+
+    #[instrument(skip(self))]
+    /// Turn shuffle on or off for the upcoming tracks and every list played after them.
+    pub async fn set_shuffle(&self, enabled: bool) -> Result<()> {
+        party::check()?;
+
+        let mut state = self.queue.write().await;
+        state.set_shuffle(enabled);
+
+        let list = state.track_list();
+        drop(state);
+
+        self.broadcast_track_list(&list).await?;
+        self.notify(Notification::Shuffle { enabled }).await
+    }
+
+    #[instrument(skip(self))]
+    /// Turn shuffle on when it is off and off when it is on. Returns whether it is on now.
+    pub async fn toggle_shuffle(&self) -> Result<bool> {
+        let enabled = !self.is_shuffled().await;
+        self.set_shuffle(enabled).await?;
+
+        Ok(enabled)
+    }
+
+    async fn skip_checked(
+        &self,
+        new_position: u32,
+        force: bool,
+        generation: Option<u64>,
+    ) -> Result<()> {
+        let mut state = self.queue.write().await;
+
+        if let Some(expected) = generation {
+            let current = state.generation();
+
+            if expected != current {
+                debug!(expected, current, "rejecting skip against a stale queue");
+                return Err(Error::StaleQueue { expected, current });
+            }
+        }
+
+        let current_position = state.current_track_position();
+        let total_tracks = state.track_list().total();
+
+        // Typical previous skip functionality where if,
+        // the track is greater than 1 second into playing,
+        // then it goes to the beginning. If triggered again
+        // within a second after playing, it will skip to the previous track.
+        // Ignore if going from the last track to the first (EOS).
+        if !force
+            && new_position < current_position
+            && total_tracks != current_position
+            && new_position != 1
+        {
+            if let Some(current_player_position) = self.position() {
+                if current_player_position.seconds() > 1 {
+                    debug!("current track position >1s, seeking to start of track");
+
+                    let zero_clock = ClockTime::default();
+
+                    self.seek(zero_clock, SeekMode::Fast).await?;
+
+                    return Ok(());
+                }
+            }
+        }
+
+        // While playing, playbin3 can switch straight to the next uri and keep the current
+        // track going until it's ready, instead of stopping first.
+        let instant = self.backend.switches_instantly() && self.is_playing();
+
+        if !instant {
+            self.ready().await?;
+        }
+
+        let next_track_to_play = state.skip_track(new_position).await;
+        let withdrawn = state.take_withdrawn();
+
+        if instant && next_track_to_play.is_none() {
+            self.ready().await?;
+        }
+
+        if let Some(next_track_to_play) = next_track_to_play {
+            let list = state.track_list();
+            let target_status = state.target_status();
+
+            drop(state);
+
+            self.broadcast_withdrawn(withdrawn).await?;
+            self.broadcast_track_list(&list).await?;
+            self.channels
+                .tx
+                .broadcast(Notification::Position {
+                    clock: ClockTime::default(),
+                })
+                .await?;
+
+            debug!(instant, "skipping to next track");
+
+            if instant {
+                self.backend.switch_uri(&next_track_to_play);
+            } else {
+                self.backend.set_uri(&next_track_to_play);
+            }
+
+            self.set_player_state(target_status).await?;
+        } else if !withdrawn.is_empty() {
+            let list = state.track_list();
+            drop(state);
+
+            self.broadcast_withdrawn(withdrawn).await?;
+            self.broadcast_track_list(&list).await?;
+        }
+
+        Ok(())
+    }
+
+    pub async fn next(&self) -> Result<()> {
+        let state = self.queue.read().await;
+
+        let current_position = state.current_track_position();
+        let next_position = state
+            .track_list()
+            .next_playable(current_position)
+            .unwrap_or(current_position + 1);
+        drop(state);
+        self.skip(next_position, true).await?;
+
+        Ok(())
+    }
+
+    #[instrument(skip(self))]
+    /// Play the current track again from the start, however far into it the player is.
+    pub async fn start_over(&self) -> Result<()> {
+        party::check()?;
+
+        self.seek(ClockTime::default(), SeekMode::Accurate).await?;
+
+        Ok(())
+    }
+
+    #[instrument(skip(self))]
+    /// Play the album or playlist again from its first playable track.
+    pub async fn restart_list(&self) -> Result<()> {
+        party::check()?;
+
+        let first = self.current_tracklist().await.next_playable(0);
+
+        match first {
+            Some(position) => self.skip(position, true).await,
+            None => Ok(()),
+        }
+    }
+
+    pub async fn previous(&self) -> Result<()> {
+        party::check()?;
+
+        let state = self.queue.read().await;
+
+        let current_position = state.current_track_position();
+        drop(state);
+        self.skip(current_position - 1, false).await?;
+
+        Ok(())
+    }
+
+    #[instrument(skip(self))]
+    /// Plays a single track.
+    pub async fn play_track(&self, track_id: i32) -> Result<()> {
+        party::check()?;
+
+        self.ready().await?;
+
+        let mut state = self.queue.write().await;
+
+        let track_url = state.play_track(track_id).await;
+        let withdrawn = state.take_withdrawn();
+        let list = state.track_list();
+        drop(state);
+
+        self.broadcast_withdrawn(withdrawn).await?;
+
+        if let Some(track_url) = track_url {
+            self.broadcast_track_list(&list).await?;
+
+            self.backend.set_uri(track_url.as_str());
+
+            self.play().await?;
+        }
+
+        Ok(())
+    }
+
+    #[instrument(skip(self))]
+    /// Plays a full album.
+    pub async fn play_album(&self, album_id: &str) -> Result<()> {
+        party::check()?;
+
+        self.start_album(album_id, None).await
+    }
+
+    #[instrument(skip(self))]
+    /// Plays a full album at `quality`, leaving the quality of the session as it is.
+    pub async fn play_album_with_quality(
+        &self,
+        album_id: &str,
+        quality: AudioQuality,
+    ) -> Result<()> {
+        party::check()?;
+
+        self.start_album(album_id, Some(quality)).await
+    }
+
+    #[instrument(skip(self))]
+    /// Play an album, whether party or simple mode lets a listener pick one or not. For
+    /// what the player starts on its own, like schedules.
+    async fn start_album(&self, album_id: &str, quality: Option<AudioQuality>) -> Result<()> {
+        let started = Instant::now();
+
+        // The current stream is torn down while the album and the url of its first
+        // track are fetched, rather than before.
+        let (ready, (track_url, withdrawn, list)) = tokio::join!(self.ready(), async {
+            let mut state = self.queue.write().await;
+
+            let track_url = match quality {
+                Some(quality) => state.play_album_with_quality(album_id, quality).await,
+                None => state.play_album(album_id).await,
+            };
+
+            (track_url, state.take_withdrawn(), state.track_list())
+        });
+        ready?;
+
+        self.broadcast_withdrawn(withdrawn).await?;
+
+        if let Some(track_url) = track_url {
+            self.broadcast_track_list(&list).await?;
+
+            self.backend.set_uri(&track_url);
+            debug!(
+                elapsed_ms = started.elapsed().as_millis() as u64,
+                "first track of the album is ready"
+            );
+
+            self.play().await?;
+        }
+
+        Ok(())
+    }
+
+    #[instrument(skip(self))]
+    /// Play the next album, playlist or track at `quality`, or at the session's quality with `None`.
+    pub async fn set_next_quality(&self, quality: Option<AudioQuality>) -> Result<()> {
+        party::check()?;
+
+        self.queue.write().await.set_next_quality(quality);
+
+        Ok(())
+    }
+
+    #[instrument(skip(self))]
+    /// Plays a full album, starting from the given track.
+    pub async fn play_album_from_track(&self, album_id: &str, track_id: i32) -> Result<()> {
+        party::check()?;
+
+        self.ready().await?;
+
+        let mut state = self.queue.write().await;
+
+        let track_url = state.play_album_from_track(album_id, track_id).await;
+        let withdrawn = state.take_withdrawn();
+        let list = state.track_list();
+        drop(state);
+
+        self.broadcast_withdrawn(withdrawn).await?;
+
+        if let Some(track_url) = track_url {
+            self.broadcast_track_list(&list).await?;
+
+            self.backend.set_uri(&track_url);
+
+            self.play().await?;
+        }
+
+        Ok(())
+    }
+
+    #[instrument(skip(self))]
+    /// Plays all tracks in a playlist.
+    pub async fn play_playlist(&self, playlist_id: i64) -> Result<()> {
+        party::check_playlist(playlist_id)?;
+
+        self.start_playlist(playlist_id).await
+    }
+
+    /// Play a playlist, whether party or simple mode lets a listener pick it or not. For
+    /// what the player starts on its own, like schedules.
+    async fn start_playlist(&self, playlist_id: i64) -> Result<()> {
+        self.ready().await?;
+
+        let mut state = self.queue.write().await;
+        let track_url = state.play_playlist(playlist_id).await;
+        let withdrawn = state.take_withdrawn();
+        let list = state.track_list();
+        drop(state);
+
+        self.broadcast_withdrawn(withdrawn).await?;
+
+        if let Some(track_url) = track_url {
+            self.broadcast_track_list(&list).await?;
+
+            self.backend.set_uri(track_url.as_str());
+
+            self.play().await?;
+        }
+
+        Ok(())
+    }
+
+    #[instrument(skip(self))]
+    /// Plays one of the playlists the service made for the user, like Weekly Q.
+    pub async fn play_personal_playlist(&self, kind: PersonalPlaylist) -> Result<()> {
+        party::check()?;
+
+        self.ready().await?;
+
+        let mut state = self.queue.write().await;
+        let track_url = state.play_personal_playlist(kind).await;
+        let withdrawn = state.take_withdrawn();
+        let list = state.track_list();
+        drop(state);
+
+        self.broadcast_withdrawn(withdrawn).await?;
+
+        if let Some(track_url) = track_url {
+            self.broadcast_track_list(&list).await?;
+
+            self.backend.set_uri(track_url.as_str());
+
+            self.play().await?;
+        }
+
+        Ok(())
+    }
+
+    #[instrument(skip(self))]
+    /// Plays every track in the local cache.
+    pub async fn play_cached(&self) -> Result<()> {
+        party::check()?;
+
+        self.ready().await?;
+
+        let mut state = self.queue.write().await;
+        if let Some(track_url) = state.play_cached().await {
+            let list = state.track_list();
+            self.broadcast_track_list(&list).await?;
+
+            drop(state);
+
+            self.backend.set_uri(track_url.as_str());
+
+            self.play().await?;
+        }
+
+        Ok(())
+    }
+
+    /// Only play, and show in the queue, tracks that are in the local cache.
+    pub async fn set_cached_only(&self, enabled: bool) -> Result<()> {
+        party::check()?;
+
+        let mut state = self.queue.write().await;
+        state.set_cached_only(enabled).await;
+
+        let list = state.track_list();
+        drop(state);
+
+        self.broadcast_track_list(&list).await
+    }
+
+    #[instrument(skip(self))]
+    /// Play an item from a web url or uri of the service. A uri with the scheme of another
+    /// registered service, e.g. `file://`, switches to that service first.
+    pub async fn play_uri(&self, uri: &str) -> Result<()> {
+        party::check()?;
+
+        if let Some(name) = service::for_uri(uri) {
+            if name != self.service().await.name() {
+                self.switch_service(name).await?;
+            }
+        }
+
+        match self.service().await.parse_uri(uri) {
+            Some(ServiceUri::Album(id)) => self.play_album(&id).await,
+            Some(ServiceUri::Playlist(id)) => self.play_playlist(id).await,
+            Some(ServiceUri::Track(id)) => self.play_track(id).await,
+            None => Err(Error::FailedToPlay {
+                message: format!("Failed to play item. {uri} is not a supported uri."),
+            }),
+        }
+    }
+
+    #[instrument(skip(self))]
+    /// Add the album or track behind a url or uri of the service, or a local file, to the end
+    /// of the queue.
+    pub async fn add_uri_to_queue(&self, uri: &str) -> Result<()> {
+        let mut service = self.service().await;
+
+        if let Some(name) = service::for_uri(uri) {
+            match local::library() {
+                // Local files can share the queue with tracks of any service.
+                Some(library) if name == library.name() => service = library,
+                _ if name != service.name() => {
+                    return Err(Error::FailedToPlay {
+                        message: format!(
+                            "Only items from {} can be added to the queue.",
+                            service.name()
+                        ),
+                    })
+                }
+                _ => {}
+            }
+        }
+
+        match service.parse_uri(uri) {
+            Some(ServiceUri::Album(id)) => self.add_album_to_queue(&id).await,
+            Some(ServiceUri::Track(id)) => self.add_track_to_queue(id).await,
+            Some(ServiceUri::Playlist(_)) => Err(Error::FailedToPlay {
+                message: "Only albums and tracks can be added to the queue.".to_string(),
+            }),
+            None => Err(Error::FailedToPlay {
+                message: format!("Failed to add item. {uri} is not a supported uri."),
+            }),
+        }
+    }
+
+    #[instrument(skip(self))]
+    /// Set up the registered service `name` with the credentials the player started with and
+    /// use it from now on. The queue is stopped, since its tracks belong to the old service.
+    pub async fn switch_service(&self, name: &str) -> Result<()> {
+        let credentials = self.credentials.lock().unwrap().clone();
+        let (service, signed_in) = service::build(name, credentials).await?;
+
+        if !signed_in {
+            return Err(Error::Client {
+                message: format!("{name} can't be reached"),
+            });
+        }
+
+        info!(name, "switching service");
+        self.stop().await?;
+        self.sign_in(service).await
+    }
+
+    /// Fetch the url of the next track, which starts in about `starts_in`, while the current
+    /// one plays, so it is ready when `about-to-finish` comes, even on a slow network.
+    async fn prefetch_next_track(&self, starts_in: Duration) {
+        let Some(prefetch) = self.queue.read().await.prefetch_next(starts_in) else {
+            return;
+        };
+
+        if let Some(prefetched) = prefetch.await {
+            self.queue.write().await.set_prefetched(prefetched);
+        }
+    }
+
+    /// Fetch a new url for the next track whenever the prefetched one would expire before
+    /// the current track ends, e.g. on long tracks. [`PlayerState::prefetch_next`] waits
+    /// until the end is close enough for a new url to last.
+    async fn url_refresh_loop(&'static self) {
+        let mut interval = tokio::time::interval(URL_CHECK_INTERVAL);
+
+        loop {
+            interval.tick().await;
+
+            if self.current_state() != GstState::Playing {
+                continue;
+            }
+
+            if let (Some(position), Some(duration)) = (self.position(), self.duration()) {
+                let remaining = duration.saturating_sub(position);
+
+                self.prefetch_next_track(Duration::from_nanos(remaining.nseconds()))
+                    .await;
+            }
+        }
+    }
+
+    /// Play on from a new url after the service refused the current track's, most likely
+    /// because it expired. Done once for each track, so a url that keeps being refused goes
+    /// through the usual recovery. Returns false when nothing was done.
+    async fn refresh_rejected_url(&self) -> Result<bool> {
+        let Some(track) = self.current_track().await else {
+            return Ok(false);
+        };
+
+        if self.refreshed_url_for.swap(track.id, Ordering::Relaxed) == track.id {
+            return Ok(false);
+        }
+
+        if let Some(url) = &track.track_url {
+            controls::forget_url(url);
+        }
+
+        let last_position = self.position().unwrap_or_default();
+        let mut state = self.queue.write().await;
+        let target_status = state.target_status();
+        let url = state.refresh_current_url().await;
+        drop(state);
+
+        let Some(url) = url else {
+            return Ok(false);
+        };
+
+        info!(
+            track.id,
+            ?last_position,
+            "track url refused, playing on from a new one"
+        );
+
+        self.ready().await?;
+        self.url_refreshing.store(true, Ordering::Relaxed);
+        self.backend.set_uri(&url);
+        self.restore_position(last_position, target_status).await?;
+
+        Ok(true)
+    }
+
+    #[instrument(skip(self))]
+    /// In response to the about-to-finish signal, set up the next track to play gaplessly,
+    /// with the url prefetched while the current one played when that still works.
+    async fn prep_next_track(&self) -> Result<()> {
+        let mut state = self.queue.write().await;
+
+        let total_tracks = state.track_list().total();
+        let current_position = state.current_track_position();
+
+        let next_position = match state.track_list().next_playable(current_position) {
+            Some(position) if total_tracks != current_position => position,
+            _ => {
+                debug!("no more tracks left");
+                return Ok(());
+            }
+        };
+
+        if trim::has_end() {
+            debug!("current track is trimmed, the next track is loaded when it ends");
+            return Ok(());
+        }
+
+        if self.crossfade_allowed() && self.duration().and_then(crossfade::start_at).is_some() {
+            debug!("the next track is loaded by the crossfade");
+            return Ok(());
+        }
+
+        let policy = self.format_change_policy();
+
+        if policy == FormatChangePolicy::Gap {
+            let list = state.track_list();
+
+            if let (Some(current), Some(next)) = (
+                state.current_track(),
+                list.find_track_by_index(next_position),
+            ) {
+                if transition::format_changes(current, next) {
+                    debug!(
+                        "format changes from {}/{} to {}/{}, inserting gap",
+                        current.bit_depth,
+                        current.sampling_rate,
+                        next.bit_depth,
+                        next.sampling_rate
+                    );
+                    self.pending_gap.store(next_position, Ordering::Relaxed);
+                    return Ok(());
+                }
+            }
+        }
+
+        let next_track_url = state.skip_track(next_position).await;
+        let withdrawn = state.take_withdrawn();
+
+        if let Some(next_track_url) = next_track_url {
+            drop(state);
+
+            self.broadcast_withdrawn(withdrawn).await?;
+            transition::record_prepared(false);
+            self.backend.set_uri(&next_track_url);
+
+            return Ok(());
+        }
+
+        // The rest of the queue can't be streamed anymore, retrying won't change that.
+        if !withdrawn.is_empty() {
+            let list = state.track_list();
+            drop(state);
+
+            self.broadcast_withdrawn(withdrawn).await?;
+            self.broadcast_track_list(&list).await?;
+
+            return Ok(());
+        }
+
+        let generation = state.generation();
+        let track = state
+            .track_list()
+            .find_track_by_index(next_position)
+            .cloned();
+        drop(state);
+
+        debug!(next_position, "failed to prepare the next track, retrying");
+        tokio::time::sleep(Duration::from_millis(transition::PREP_RETRY_BACKOFF_MS)).await;
+
+        let mut state = self.queue.write().await;
+
+        // Something else moved the queue on while waiting, so there is nothing left to prepare.
+        if state.generation() != generation {
+            debug!("queue changed before the retry, skipping it");
+            return Ok(());
+        }
+
+        let next_track_url = state.skip_track(next_position).await;
+        let withdrawn = state.take_withdrawn();
+        drop(state);
+
+        self.broadcast_withdrawn(withdrawn).await?;
+
+        let recovered = next_track_url.is_some();
+        let stats = match next_track_url {
+            Some(url) => {
+                self.backend.set_uri(&url);
+                transition::record_prepared(true)
+            }
+            None => transition::record_failed(),
+        };
+
+        warn!(next_position, recovered, ?stats, "gapless prep failed");
+
+        if let Some(track) = track {
+            self.channels
+                .tx
+                .broadcast(Notification::GaplessPrepFailed {
+                    track,
+                    recovered,
+                    stats,
+                })
+                .await?;
+        }
+
+        Ok(())
+    }
+
+    #[instrument(skip(self))]
+    /// Returns the current track list loaded in the player.
+    pub async fn current_tracklist(&self) -> TrackListValue {
+        self.queue.read().await.track_list()
+    }
+
+    #[instrument(skip(self))]
+    /// Returns the current track loaded in the player.
+    pub async fn current_track(&self) -> Option<Track> {
+        self.queue.read().await.current_track().cloned()
+    }
+
+    #[instrument(skip(self))]
+    /// Returns true if the player is currently playing a live stream, which can't be seeked.
+    pub fn is_live(&self) -> bool {
+        self.is_live.load(Ordering::Relaxed)
+    }
+
+    #[instrument(skip(self))]
+    /// Returns true if the player is currently buffering data.
+    pub fn is_buffering(&self) -> bool {
+        self.is_buffering.load(Ordering::Relaxed)
+    }
+
+    #[instrument(skip(self))]
+    /// Continue playback that was held because it failed during an outage of the service.
+    pub async fn resume_after_outage(&self) -> Result<()> {
+        if !self.held_for_outage.swap(false, Ordering::Relaxed) {
+            return Ok(());
+        }
+
+        info!("resuming playback after the service outage");
+        self.ready().await?;
+        self.pause().await?;
+        self.play().await
+    }
+
+    #[instrument(skip(self, service))]
+    /// Use a service that signed in after the player started offline, or one switched to. Lists
+    /// fetched before are forgotten so they are loaded again.
+    pub async fn sign_in(&self, service: Arc<dyn MusicService>) -> Result<()> {
+        self.queue.write().await.set_service(service);
+
+        PLAYLIST.lock().await.cache_clear();
+        PLAYLIST_TRACKS.lock().await.cache_clear();
+        USER_PLAYLISTS.lock().await.cache_clear();
+
+        self.notify_change(cache::set_offline(false)).await?;
+        self.notify(Notification::SignedIn).await
+    }
+
+    #[instrument(skip(self))]
+    /// The music service the player uses, for work that doesn't touch the queue.
+    pub async fn service(&self) -> Arc<dyn MusicService> {
+        self.queue.read().await.service()
+    }
+
+    /// Inserts the most recent position into the state at a set interval.
+    #[instrument(skip(self))]
+    pub async fn clock_loop(&'static self) {
+        debug!("starting clock loop");
+
+        let mut interval = tokio::time::interval(Duration::from_millis(REFRESH_RESOLUTION));
+        let mut last_position = ClockTime::default();
+
+        loop {
+            interval.tick().await;
+
+            if self.current_state() == GstState::Playing {
+                if let Some(position) = self.position() {
+                    if let Some(duration) = self.duration() {
+                        if trim::end_reached(position, duration) {
+                            debug!("reached the trimmed end of the track");
+
+                            tokio::spawn(async move {
+                                if let Err(error) = self.end_trimmed_track().await {
+                                    debug!(?error);
+                                }
+                            });
+                        }
+                    }
+
+                    if self.crossfade_allowed() {
+                        if let (Some(track), Some(duration)) =
+                            (self.current_track().await, self.duration())
+                        {
+                            if let Some(phase) = crossfade::due(track.id, position, duration) {
+                                tokio::spawn(async move {
+                                    if let Err(error) = self.run_crossfade(phase).await {
+                                        debug!(?error);
+                                    }
+                                });
+                            }
+                        }
+                    }
+
+                    if position.seconds() != last_position.seconds() {
+                        // Seeks jump further, only count the time that was actually heard.
+                        if position > last_position
+                            && position.seconds() - last_position.seconds() <= 2
+                        {
+                            session::record_second();
+                        }
+
+                        last_position = position;
+
+                        self.channels
+                            .tx
+                            .broadcast(Notification::Position { clock: position })
+                            .await
+                            .expect("failed to send notification");
+
+                        if let Some(progress) = self.list_progress(position).await {
+                            self.channels
+                                .tx
+                                .broadcast(Notification::ListProgress { progress })
+                                .await
+                                .expect("failed to send notification");
+                        }
+                    }
+                }
+            }
+        }
+    }
+
+    /// Crossfading needs a second sink next to the player's, which bit perfect output can't have.
+    /// Trimmed tracks and auditions end on their own terms.
+    fn crossfade_allowed(&self) -> bool {
+        crossfade::is_enabled()
+            && !self.is_bit_perfect()
+            && !trim::has_end()
+            && !audition::is_active()
+    }
+
+    /// Load the end of the current track into the second pipeline, or play it while the
+    /// next track fades in.
+    async fn run_crossfade(&self, phase: Phase) -> Result<()> {
+        let state = self.queue.read().await;
+        let current_position = state.current_track_position();
+        let next_position = state.track_list().next_playable(current_position);
+        let uri = state.current_track().and_then(|t| t.track_url.clone());
+        drop(state);
+
+        let Some(next_position) = next_position else {
+            debug!("no next track to crossfade into");
+            return Ok(());
+        };
+
+        match phase {
+            Phase::Prepare => {
+                if let (Some(uri), Some(start)) =
+                    (uri, self.duration().and_then(crossfade::start_at))
+                {
+                    crossfade::prepare_tail(&uri, start, self.backend.volume()).await;
+                }
+            }
+            Phase::Start => {
+                let seconds = crossfade::seconds();
+                let volume = self.volume();
+                let overlapped = crossfade::start_tail();
+                debug!(next_position, overlapped, "crossfading");
+
+                self.channels
+                    .tx
+                    .broadcast(Notification::Crossfade {
+                        active: true,
+                        seconds,
+                    })
+                    .await?;
+
+                self.skip(next_position, true).await?;
+                crossfade::fade_in(|level| {
+                    self.backend.set_volume(volume * self.gain_factor() * level);
+                })
+                .await;
+
+                self.channels
+                    .tx
+                    .broadcast(Notification::Crossfade {
+                        active: false,
+                        seconds,
+                    })
+                    .await?;
+            }
+        }
+
+        Ok(())
+    }
+
+    /// The end of the last track that was queued up.
+    async fn end_of_stream(&self) -> Result<()> {
+        let pending_gap = self.pending_gap.swap(0, Ordering::Relaxed);
+
+        if pending_gap != 0 {
+            debug!("format change gap before track {}", pending_gap);
+            self.ready().await?;
+
+            tokio::time::sleep(Duration::from_millis(transition::FORMAT_CHANGE_GAP_MS)).await;
+
+            self.queue
+                .write()
+                .await
+                .set_target_status(GstState::Playing);
+
+            self.skip(pending_gap, true).await?;
+        } else if self.quit_when_done.load(Ordering::Relaxed) {
+            let position = self.position().unwrap_or_default();
+            self.queue.read().await.quit(position);
+        } else {
+            let mut q = self.queue.write().await;
+            q.set_target_status(GstState::Paused);
+            drop(q);
+
+            self.skip(1, true).await?;
+        }
+
+        Ok(())
+    }
+
+    /// Move on from a track that was cut short by its trim, as if its stream had ended.
+    /// Trimmed tracks are not prepared gaplessly, so this loads the next track itself.
+    async fn end_trimmed_track(&self) -> Result<()> {
+        let state = self.queue.read().await;
+        let next_position = state
+            .track_list()
+            .next_playable(state.current_track_position());
+        drop(state);
+
+        match next_position {
+            Some(position) => self.skip(position, true).await,
+            None if audition::is_active() => audition::finish().await,
+            None => self.end_of_stream().await,
+        }
+    }
+
+    /// Seek past the trimmed start of the current track, once it can be seeked.
+    async fn apply_trim_start(&self) -> Result<()> {
+        let Some(track) = self.current_track().await else {
+            return Ok(());
+        };
+
+        if let Some(start) = trim::take_start(track.id) {
+            if self.position().unwrap_or_default() < start {
+                debug!(?start, "skipping trimmed start");
+                self.seek(start, SeekMode::Accurate).await?;
+            }
+        }
+
+        Ok(())
+    }
+
+    pub async fn quit(&self) -> Result<()> {
+        debug!("stopping player");
+
+        let position = self.position().unwrap_or_default();
+        self.queue.read().await.quit(position);
+
+        if self.is_playing() {
+            debug!("pausing player");
+            self.pause().await?;
+        }
+
+        if self.is_paused() {
+            debug!("readying player");
+            self.ready().await?;
+        }
+
+        if self.is_ready() {
+            debug!("stopping player");
+            self.stop().await?;
+        }
+
+        let stats = session::stats();
+        info!(?stats, "session summary");
+
+        self.channels
+            .tx
+            .broadcast(Notification::SessionSummary { stats })
+            .await
+            .expect("error sending broadcast");
+
+        self.channels
+            .tx
+            .broadcast(Notification::Quit)
+            .await
+            .expect("error sending broadcast");
+
+        Ok(())
+    }
+
+    /// Handles events from the playback backend, receives player actions from external
+    /// controls and takes necessary action.
+    #[instrument(skip(self))]
+    pub async fn player_loop(&'static self) -> Result<()> {
+        let mut events = self.backend.events();
+
+        let mut quitter = self.queue.read().await.quitter();
+
+        let clock_handle = tokio::spawn(async move { self.clock_loop().await });
+        let url_handle = tokio::spawn(async move { self.url_refresh_loop().await });
+
+        loop {
+            select! {
+                Ok(should_quit)= quitter.recv() => {
+                    if should_quit {
+                        clock_handle.abort();
+                        url_handle.abort();
+                        break;
+                    }
+                }
+                Some(event) = events.next() => {
+                    if matches!(event, BackendEvent::Buffering(_)) {
+                        match self.handle_event(event).await {
+                            Ok(_) => {},
+                            Err(error) => debug!(?error),
+                        };
+                    } else {
+                        tokio::spawn(async move { match self.handle_event(event).await {
+                                Ok(()) => {}
+                                Err(error) => {debug!(?error);}
+                            }
+                        });
+                    }
+                }
+            }
+        }
+
+        Ok(())
+    }
+
+    async fn handle_event(&'static self, event: BackendEvent) -> Result<()> {
+        match event {
+            BackendEvent::AboutToFinish => self.prep_next_track().await?,
+            BackendEvent::EndOfStream => {
+                debug!("END OF STREAM");
+
+                if trim::has_end() || audition::is_active() {
+                    trim::clear();
+                    self.end_trimmed_track().await?;
+                } else {
+                    self.end_of_stream().await?;
+                }
+            }
+            BackendEvent::StreamStart => {
+                // Any stream but the replacement of a refused url may have its url
+                // refreshed again, the same track played again too.
+                if !self.url_refreshing.swap(false, Ordering::Relaxed) {
+                    self.refreshed_url_for.store(0, Ordering::Relaxed);
+                }
+
+                stats::stream_started();
+
+                if let Some(track) = self.current_track().await {
+                    recovery::record_success(track.id);
+                    session::record_track(&track);
+                    trim::load(&track).await;
+                    self.apply_normalization(&track).await;
+                }
+
+                let track_duration = self
+                    .current_track()
+                    .await
+                    .map(|track| Duration::from_secs(track.duration_seconds as u64))
+                    .unwrap_or_default();
+
+                tokio::spawn(self.prefetch_next_track(track_duration));
+
+                if self.is_playing() {
+                    self.apply_trim_start().await?;
+
+                    let list = self.queue.read().await.track_list();
+                    self.broadcast_track_list(&list).await?;
+                }
+            }
+            BackendEvent::Buffering(percent) => {
+                if self.is_live.load(Ordering::Relaxed) {
+                    debug!("stream is live, ignore buffering");
+                    return Ok(());
+                }
+                stats::record_buffering(percent);
+
+                let target_status = self.queue.read().await.target_status();
+
+                if percent < 100 && !self.is_paused() && !self.is_buffering.load(Ordering::Relaxed)
+                {
+                    self.pause().await?;
+                    self.is_buffering.store(true, Ordering::Relaxed);
+                    session::record_rebuffer();
+                } else if percent > 99
+                    && self.is_buffering.load(Ordering::Relaxed)
+                    && self.is_paused()
+                {
+                    self.set_player_state(target_status).await?;
+                    self.is_buffering.store(false, Ordering::Relaxed);
+                }
+
+                if percent.rem_euclid(10) == 0 {
+                    debug!("buffering {}%", percent);
+                    self.channels
+                        .tx
+                        .broadcast(Notification::Buffering {
+                            is_buffering: percent < 99,
+                            target_state: target_status,
+                            percent: percent as u32,
+                        })
+                        .await?;
+                }
+            }
+            BackendEvent::StateChanged(state) => {
+                let current_state = GstState::from(state);
+                let mut q = self.queue.write().await;
+
+                if q.status() != current_state && q.target_status() == current_state {
+                    debug!("player state changed {:?}", current_state);
+                    q.set_status(current_state);
+                    drop(q);
+
+                    self.channels
+                        .tx
+                        .broadcast(Notification::Status {
+                            status: current_state,
+                        })
+                        .await?;
+                }
+            }
+            BackendEvent::Prerolled(running_time) => {
+                debug!("ASYNC DONE");
+                self.apply_trim_start().await?;
 
-                let zero_clock = ClockTime::default();
+                self.channels
+                    .tx
+                    .broadcast(Notification::Loading {
+                        is_loading: false,
+                        target_state: self.queue.read().await.target_status(),
+                    })
+                    .await?;
 
-                seek(zero_clock, None).await?;
+                let position = running_time
+                    .map(clock_time)
+                    .or_else(|| self.position())
+                    .unwrap_or_default();
 
-                return Ok(());
+                self.channels
+                    .tx
+                    .broadcast(Notification::Position { clock: position })
+                    .await?;
             }
-        }
-    }
+            BackendEvent::AudioFormat {
+                sampling_rate,
+                bit_depth,
+            } => {
+                let previous_bits = self.bit_depth.swap(bit_depth, Ordering::SeqCst);
+                let previous_rate = self.sampling_rate.swap(sampling_rate, Ordering::SeqCst);
+
+                if previous_rate != sampling_rate || previous_bits != bit_depth {
+                    if let Err(err) = self.channels.tx.try_broadcast(Notification::AudioQuality {
+                        bitdepth: bit_depth,
+                        sampling_rate,
+                    }) {
+                        debug!(?err);
+                    }
+                }
+            }
+            BackendEvent::Level { rms, peak } => {
+                self.visualize(Notification::Level { rms, peak });
+            }
+            BackendEvent::Spectrum { magnitudes } => {
+                self.visualize(Notification::Spectrum { magnitudes });
+            }
+            BackendEvent::ClockLost => {
+                debug!("clock lost, restarting playback");
+                self.pause().await?;
+                self.play().await?;
+            }
+            BackendEvent::Error {
+                error,
+                url_rejected,
+            } => {
+                if url_rejected && self.refresh_rejected_url().await? {
+                    return Ok(());
+                }
 
-    ready().await?;
+                debug!(?error, "playback failed");
 
-    if let Some(next_track_to_play) = state.skip_track(new_position).await {
-        let list = state.track_list();
-        let target_status = state.target_status();
+                self.channels
+                    .tx
+                    .broadcast(Notification::Error { error })
+                    .await?;
 
-        drop(state);
+                self.recover().await?;
+            }
+        }
 
-        broadcast_track_list(&list).await?;
-        BROADCAST_CHANNELS
-            .tx
-            .broadcast(Notification::Position {
-                clock: ClockTime::default(),
-            })
-            .await?;
+        Ok(())
+    }
 
-        debug!("skipping to next track");
+    /// Pass on levels or a spectrum while the visualization is on.
+    fn visualize(&self, notification: Notification) {
+        if !self.visualization_enabled.load(Ordering::Relaxed) {
+            return;
+        }
 
-        PLAYBIN.set_property("uri", next_track_to_play);
-        set_player_state(target_status).await?;
+        if let Err(err) = self.visualization.tx.try_broadcast(notification) {
+            debug!(?err);
+        }
     }
 
-    Ok(())
-}
-
-pub async fn next() -> Result<()> {
-    let state = QUEUE.get().unwrap().read().await;
+    /// Retry the current track according to the recovery policy, or skip it
+    /// and mark it as failed once it has used up its retries.
+    async fn recover(&'static self) -> Result<()> {
+        let Some(track) = self.current_track().await else {
+            return Ok(());
+        };
+
+        // Failures while the service is down say nothing about the track, so it is kept
+        // and played again once the service is back.
+        if let Some(outage) = health::outage() {
+            warn!(
+                status = outage.status,
+                "playback failed during a service outage, holding"
+            );
+            self.held_for_outage.store(true, Ordering::Relaxed);
 
-    let current_position = state.current_track_position();
-    drop(state);
-    skip(current_position + 1, true).await?;
+            return self.pause().await;
+        }
 
-    Ok(())
-}
+        match recovery::record_failure(track.id) {
+            Recovery::Retry { attempt, delay } => {
+                debug!(
+                    "retrying track {} (attempt {}) in {:?}",
+                    track.id, attempt, delay
+                );
 
-pub async fn previous() -> Result<()> {
-    let state = QUEUE.get().unwrap().read().await;
+                // Waited out on the side, so the events keep coming in meanwhile.
+                tokio::spawn(async move {
+                    tokio::time::sleep(delay).await;
 
-    let current_position = state.current_track_position();
-    drop(state);
-    skip(current_position - 1, false).await?;
+                    if let Err(error) = self.retry(track.id).await {
+                        debug!(?error, "retry failed");
+                    }
+                });
+            }
+            Recovery::Abandon { attempts } => {
+                debug!("abandoning track {} after {} attempts", track.id, attempts);
+                let is_last = self.current_tracklist().await.total() <= track.position;
 
-    Ok(())
-}
+                if is_last {
+                    self.stop().await?;
+                } else {
+                    self.next().await?;
+                }
 
-#[instrument]
-/// Plays a single track.
-pub async fn play_track(track_id: i32) -> Result<()> {
-    ready().await?;
+                let mut state = self.queue.write().await;
+                state.set_track_status(track.position, TrackStatus::Unplayable);
+                let list = state.track_list();
+                drop(state);
 
-    let mut state = QUEUE.get().unwrap().write().await;
+                self.broadcast_track_list(&list).await?;
 
-    if let Some(track_url) = state.play_track(track_id).await {
-        let list = state.track_list();
-        broadcast_track_list(&list).await?;
+                let (errors, abandoned) = recovery::totals();
+                self.channels
+                    .tx
+                    .broadcast(Notification::TrackAbandoned {
+                        track,
+                        attempts,
+                        errors,
+                        abandoned,
+                    })
+                    .await?;
+            }
+        }
 
-        drop(state);
+        Ok(())
+    }
 
-        PLAYBIN.set_property("uri", Some(track_url.as_str()));
+    /// Load the current track again, unless another one was played in the meantime.
+    async fn retry(&self, track_id: u32) -> Result<()> {
+        if self.current_track().await.map(|track| track.id) != Some(track_id) {
+            return Ok(());
+        }
 
-        play().await?;
+        self.ready().await?;
+        self.pause().await?;
+        self.play().await
     }
-
-    Ok(())
 }
-#[instrument]
-/// Plays a full album.
-pub async fn play_album(album_id: &str) -> Result<()> {
-    ready().await?;
-
-    let mut state = QUEUE.get().unwrap().write().await;
 
-    if let Some(track_url) = state.play_album(album_id).await {
-        let list = state.track_list();
-        broadcast_track_list(&list).await?;
+/// The installed player.
+fn player() -> &'static Player {
+    PLAYER.get().expect("player is not set up")
+}
 
-        drop(state);
+/// The GStreamer pipeline, when the player has one.
+fn playbin() -> Option<&'static Element> {
+    player().playbin()
+}
 
-        PLAYBIN.set_property("uri", Some(track_url));
+fn queue() -> &'static SafePlayerState {
+    &player().queue
+}
 
-        play().await?;
-    }
+fn channels() -> &'static Broadcast {
+    &player().channels
+}
 
-    Ok(())
+/// A position or duration from the backend, as the notifications carry it.
+fn clock_time(duration: Duration) -> ClockTime {
+    ClockTime::from_nseconds(duration.as_nanos() as u64)
 }
-#[instrument]
-/// Plays all tracks in a playlist.
-pub async fn play_playlist(playlist_id: i64) -> Result<()> {
-    ready().await?;
 
-    let mut state = QUEUE.get().unwrap().write().await;
-    if let Some(track_url) = state.play_playlist(playlist_id).await {
-        let list = state.track_list();
-        broadcast_track_list(&list).await?;
+#[instrument]
+/// Returns false when the service couldn't be reached and the player started offline.
+/// Signing in is then retried with [`sign_in`].
+pub async fn init(
+    service_name: &str,
+    credentials: Credentials,
+    quit_when_done: bool,
+) -> Result<bool> {
+    let (service, signed_in) = service::build(service_name, credentials.clone()).await?;
 
-        drop(state);
+    let version = gstreamer::version();
+    debug!(?version);
 
-        PLAYBIN.set_property("uri", Some(track_url.as_str()));
+    let player = Player::new(PlayerState::new(service));
+    *player.credentials.lock().unwrap() = credentials;
+    player
+        .quit_when_done
+        .store(quit_when_done, Ordering::Relaxed);
 
-        play().await?;
+    if !signed_in {
+        player.notify_change(cache::set_offline(true)).await?;
     }
 
-    Ok(())
-}
-#[instrument]
-/// Play an item from Qobuz web uri
-pub async fn play_uri(uri: &str) -> Result<()> {
-    match client::parse_url(uri) {
-        Ok(url) => match url {
-            UrlType::Album { id } => {
-                play_album(&id).await?;
-            }
-            UrlType::Playlist { id } => {
-                play_playlist(id).await?;
-            }
-            UrlType::Track { id } => {
-                play_track(id).await?;
-            }
-        },
-        Err(err) => {
-            return Err(Error::FailedToPlay {
-                message: format!("Failed to play item. {err}"),
-            })
-        }
-    }
+    player.install();
 
-    Ok(())
+    Ok(signed_in)
 }
 #[instrument]
-/// In response to the about-to-finish signal,
-/// prepare the next track by downloading the stream url.
-async fn prep_next_track() -> Result<()> {
-    let mut state = QUEUE.get().unwrap().write().await;
-
-    let total_tracks = state.track_list().total();
-    let current_position = state.current_track_position();
-
-    if total_tracks == current_position {
-        debug!("no more tracks left");
-    } else if let Some(next_track_url) = state.skip_track(current_position + 1).await {
-        drop(state);
-
-        PLAYBIN.set_property("uri", next_track_url);
-    }
-
-    Ok(())
+/// Set how the player recovers from playback errors.
+pub fn set_recovery_policy(policy: RecoveryPolicy) {
+    recovery::set_policy(policy);
 }
 #[instrument]
-/// Get a notification channel receiver
-pub fn notify_receiver() -> BroadcastReceiver {
-    BROADCAST_CHANNELS.rx.clone()
+/// Start in simple mode, where only the playlists in `playlists` can be started.
+pub fn set_simple_mode(enabled: bool, playlists: Vec<i64>) {
+    simple::init(enabled, playlists);
 }
 #[instrument]
-/// Returns the current track list loaded in the player.
-pub async fn current_tracklist() -> TrackListValue {
-    QUEUE.get().unwrap().read().await.track_list()
+/// Overlap the last `seconds` of each track with the next one, 0 turns it off.
+pub fn set_crossfade(seconds: u32) {
+    crossfade::set_seconds(seconds);
 }
 #[instrument]
-/// Returns the current track loaded in the player.
-pub async fn current_track() -> Option<Track> {
-    QUEUE.get().unwrap().read().await.current_track().cloned()
+/// Add similar tracks whenever the queue is about to run out.
+pub fn set_autoplay(enabled: bool) {
+    autoplay::set_enabled(enabled);
 }
 #[instrument]
-/// Returns true if the player is currently buffering data.
-pub fn is_buffering() -> bool {
-    IS_BUFFERING.load(Ordering::Relaxed)
+/// Even out the loudness of tracks from the next one on.
+pub fn set_normalization(enabled: bool) {
+    loudness::set_enabled(enabled);
 }
 #[instrument]
-/// Search the service.
-pub async fn search(query: &str) -> SearchResults {
-    QUEUE
-        .get()
-        .unwrap()
+/// Search the service, returning up to `limits` results of each kind.
+pub async fn search(query: &str, limits: SearchLimits) -> SearchResults {
+    queue()
         .read()
         .await
-        .search_all(query)
+        .search_all(query, limits)
         .await
         .unwrap_or_default()
 }
-
 #[instrument]
 #[cached(size = 10, time = 600)]
 /// Fetch the albums for a specific artist.
 pub async fn artist_albums(artist_id: i32) -> Vec<Album> {
-    if let Some(mut albums) = QUEUE
-        .get()
-        .unwrap()
-        .read()
-        .await
-        .fetch_artist_albums(artist_id)
-        .await
-    {
+    if let Some(mut albums) = queue().read().await.fetch_artist_albums(artist_id).await {
         albums.sort_by_key(|a| a.release_year);
 
         albums
@@ -574,14 +2455,11 @@ pub async fn artist_albums(artist_id: i32) -> Vec<Album> {
         Vec::new()
     }
 }
-
 #[instrument]
 #[cached(size = 10, time = 600)]
 /// Fetch the tracks for a specific playlist.
 pub async fn playlist_tracks(playlist_id: i64) -> Vec<Track> {
-    if let Some(tracks) = QUEUE
-        .get()
-        .unwrap()
+    if let Some(tracks) = queue()
         .read()
         .await
         .fetch_playlist_tracks(playlist_id)
@@ -592,289 +2470,214 @@ pub async fn playlist_tracks(playlist_id: i64) -> Vec<Track> {
         Vec::new()
     }
 }
-
+#[instrument]
+#[cached(size = 10, time = 600, option = true)]
+/// Fetch a playlist, including its tracks and description.
+pub async fn playlist(playlist_id: i64) -> Option<Playlist> {
+    let playlist = queue().read().await.fetch_playlist(playlist_id).await;
+
+    match playlist {
+        Some(playlist) => {
+            library::index_playlist(&playlist).await;
+            Some(playlist)
+        }
+        None => library::cached_playlist(playlist_id).await,
+    }
+}
 #[instrument]
 #[cached(size = 1, time = 600)]
 /// Fetch the current user's list of playlists.
 pub async fn user_playlists() -> Vec<Playlist> {
-    if let Some(playlists) = QUEUE
-        .get()
-        .unwrap()
-        .read()
-        .await
-        .fetch_user_playlists()
-        .await
-    {
+    if let Some(playlists) = queue().read().await.fetch_user_playlists().await {
+        library::index_playlists(&playlists).await;
+
         playlists
     } else {
-        Vec::new()
+        library::indexed_playlists().await
     }
 }
-
-/// Inserts the most recent position into the state at a set interval.
 #[instrument]
-pub async fn clock_loop() {
-    debug!("starting clock loop");
+/// Remove entries from one of the user's playlists and forget the cached copies of it.
+pub async fn remove_playlist_tracks(playlist_id: i64, playlist_track_ids: Vec<i64>) -> Result<()> {
+    party::check()?;
 
-    let mut interval = tokio::time::interval(Duration::from_millis(REFRESH_RESOLUTION));
-    let mut last_position = ClockTime::default();
-
-    loop {
-        interval.tick().await;
-
-        if current_state() == GstState::Playing {
-            if let Some(position) = position() {
-                if position.seconds() != last_position.seconds() {
-                    last_position = position;
+    let playlist = queue()
+        .read()
+        .await
+        .remove_playlist_tracks(playlist_id as u32, playlist_track_ids)
+        .await;
 
-                    BROADCAST_CHANNELS
-                        .tx
-                        .broadcast(Notification::Position { clock: position })
-                        .await
-                        .expect("failed to send notification");
-                }
-            }
-        }
-    }
+    playlist_updated(playlist_id, playlist.is_some()).await
 }
+#[instrument]
+/// Add tracks to one of the user's playlists and forget the cached copies of it.
+pub async fn add_playlist_tracks(playlist_id: i64, track_ids: Vec<u32>) -> Result<()> {
+    party::check()?;
 
-pub async fn quit() -> Result<()> {
-    debug!("stopping player");
-
-    QUEUE.get().unwrap().read().await.quit();
-
-    if is_playing() {
-        debug!("pausing player");
-        pause().await?;
-    }
-
-    if is_paused() {
-        debug!("readying player");
-        ready().await?;
-    }
-
-    if is_ready() {
-        debug!("stopping player");
-        stop().await?;
-    }
-
-    BROADCAST_CHANNELS
-        .tx
-        .broadcast(Notification::Quit)
+    let playlist = queue()
+        .read()
         .await
-        .expect("error sending broadcast");
+        .add_playlist_tracks(playlist_id as u32, track_ids)
+        .await;
 
-    Ok(())
+    playlist_updated(playlist_id, playlist.is_some()).await
 }
-
-/// Handles messages from GStreamer, receives player actions from external controls
-/// receives the about-to-finish event and takes necessary action.
 #[instrument]
-pub async fn player_loop() -> Result<()> {
-    let mut messages = PLAYBIN.bus().unwrap().stream();
-    let mut about_to_finish = ABOUT_TO_FINISH.rx.stream();
+/// Create a playlist for the user and add it to the library index.
+pub async fn create_playlist(name: String, public: bool) -> Result<Playlist> {
+    party::check()?;
 
-    let mut quitter = QUEUE.get().unwrap().read().await.quitter();
+    let playlist = queue().read().await.create_playlist(&name, public).await;
 
-    let clock_handle = tokio::spawn(async { clock_loop().await });
+    USER_PLAYLISTS.lock().await.cache_clear();
 
-    loop {
-        select! {
-            Ok(should_quit)= quitter.recv() => {
-                if should_quit {
-                    clock_handle.abort();
-                    break;
-                }
-            }
-            Some(almost_done) = about_to_finish.next() => {
-                if almost_done {
-                    tokio::spawn(async { prep_next_track().await });
-                }
-            }
-            Some(msg) = messages.next() => {
-                if msg.type_() == MessageType::Buffering {
-                    match handle_message(&msg).await {
-                        Ok(_) => {},
-                        Err(error) => debug!(?error),
-                    };
-                } else {
-                    tokio::spawn(async move { match handle_message(&msg).await {
-                            Ok(()) => {}
-                            Err(error) => {debug!(?error);}
-                        }
-                    });
-                }
-            }
-        }
-    }
+    let playlist = playlist.ok_or(Error::PlaylistUpdate)?;
+    library::index_playlist(&playlist).await;
 
-    Ok(())
+    Ok(playlist)
 }
+#[instrument]
+/// Delete one of the user's playlists and forget everything known about it.
+pub async fn delete_playlist(playlist_id: i64) -> Result<()> {
+    party::check()?;
 
-async fn handle_message(msg: &Message) -> Result<()> {
-    match msg.view() {
-        MessageView::Eos(_) => {
-            debug!("END OF STREAM");
-            if QUIT_WHEN_DONE.load(Ordering::Relaxed) {
-                QUEUE.get().unwrap().read().await.quit();
-            } else {
-                let mut q = QUEUE.get().unwrap().write().await;
-                q.set_target_status(GstState::Paused);
-                drop(q);
-
-                skip(1, true).await?;
-            }
-        }
-        MessageView::StreamStart(_) => {
-            if is_playing() {
-                let list = QUEUE.get().unwrap().read().await.track_list();
-                broadcast_track_list(&list).await?;
-            }
-        }
-        MessageView::AsyncDone(msg) => {
-            debug!("ASYNC DONE");
-            BROADCAST_CHANNELS
-                .tx
-                .broadcast(Notification::Loading {
-                    is_loading: false,
-                    target_state: QUEUE.get().unwrap().read().await.target_status(),
-                })
-                .await?;
-
-            let position = if let Some(p) = msg.running_time() {
-                p
-            } else if let Some(p) = position() {
-                p
-            } else {
-                ClockTime::default()
-            };
-
-            BROADCAST_CHANNELS
-                .tx
-                .broadcast(Notification::Position { clock: position })
-                .await?;
-        }
-        MessageView::PropertyNotify(el) => {
-            let (_, prop_name, value) = el.get();
-
-            if let Some(v) = value {
-                if prop_name == "caps" {
-                    if let Ok(caps) = v.get::<&Caps>() {
-                        if !caps.is_empty() {
-                            if let Some(structure) = caps.structure(0) {
-                                let rate: u32 = structure.get("rate").unwrap_or_default();
-                                let format: &str = structure.get("format").unwrap_or_default();
-                                let bits = if format.starts_with("S24") {
-                                    24_u32
-                                } else if format.starts_with("S16") {
-                                    16_u32
-                                } else {
-                                    0
-                                };
-
-                                if rate == 0 || bits == 0 {
-                                    return Ok(());
-                                }
-
-                                let previous_bits = BIT_DEPTH.swap(bits, Ordering::SeqCst);
-                                let previous_rate = SAMPLING_RATE.swap(rate, Ordering::SeqCst);
-
-                                if previous_rate != rate || previous_bits != bits {
-                                    match BROADCAST_CHANNELS.tx.try_broadcast(
-                                        Notification::AudioQuality {
-                                            bitdepth: bits,
-                                            sampling_rate: rate,
-                                        },
-                                    ) {
-                                        Ok(_) => {}
-                                        Err(err) => {
-                                            debug!(?err);
-                                        }
-                                    }
-                                }
-                            }
-                        }
-                    }
-                }
-            }
-        }
-        MessageView::Buffering(buffering) => {
-            if IS_LIVE.load(Ordering::Relaxed) {
-                debug!("stream is live, ignore buffering");
-                return Ok(());
-            }
-            let percent = buffering.percent();
-
-            let target_status = QUEUE.get().unwrap().read().await.target_status();
-
-            if percent < 100 && !is_paused() && !IS_BUFFERING.load(Ordering::Relaxed) {
-                pause().await?;
-                IS_BUFFERING.store(true, Ordering::Relaxed);
-            } else if percent > 99 && IS_BUFFERING.load(Ordering::Relaxed) && is_paused() {
-                set_player_state(target_status).await?;
-                IS_BUFFERING.store(false, Ordering::Relaxed);
-            }
+    let deleted = queue()
+        .read()
+        .await
+        .delete_playlist(playlist_id as u32)
+        .await;
 
-            if percent.rem_euclid(10) == 0 {
-                debug!("buffering {}%", percent);
-                BROADCAST_CHANNELS
-                    .tx
-                    .broadcast(Notification::Buffering {
-                        is_buffering: percent < 99,
-                        target_state: target_status,
-                        percent: percent as u32,
-                    })
-                    .await?;
-            }
-        }
-        MessageView::StateChanged(state_changed) => {
-            let current_state = state_changed
-                .current()
-                .to_value()
-                .get::<GstState>()
-                .unwrap();
+    if deleted {
+        library::forget_playlist(playlist_id).await;
+    }
 
-            let mut q = QUEUE.get().unwrap().write().await;
+    playlist_updated(playlist_id, deleted).await
+}
+#[instrument]
+/// Move an entry of one of the user's playlists so it comes before the entry at
+/// `insert_before`, counted from 0, and forget the cached copies of it.
+pub async fn move_playlist_track(
+    playlist_id: i64,
+    playlist_track_id: i64,
+    insert_before: usize,
+) -> Result<()> {
+    party::check()?;
 
-            if q.status() != current_state && q.target_status() == current_state {
-                debug!("player state changed {:?}", current_state);
-                q.set_status(current_state);
-                drop(q);
+    let playlist = queue()
+        .read()
+        .await
+        .move_playlist_track(playlist_id as u32, playlist_track_id, insert_before)
+        .await;
 
-                BROADCAST_CHANNELS
-                    .tx
-                    .broadcast(Notification::Status {
-                        status: current_state,
-                    })
-                    .await?;
-            }
-        }
-        MessageView::ClockLost(_) => {
-            debug!("clock lost, restarting playback");
-            pause().await?;
-            play().await?;
-        }
-        MessageView::Error(err) => {
-            BROADCAST_CHANNELS
-                .tx
-                .broadcast(Notification::Error { error: err.into() })
-                .await?;
+    playlist_updated(playlist_id, playlist.is_some()).await
+}
+/// Forget the cached copies of a playlist that was just edited, whether or not the edit
+/// worked.
+async fn playlist_updated(playlist_id: i64, updated: bool) -> Result<()> {
+    PLAYLIST.lock().await.cache_remove(&playlist_id);
+    PLAYLIST_TRACKS.lock().await.cache_remove(&playlist_id);
+    USER_PLAYLISTS.lock().await.cache_clear();
+
+    if updated {
+        Ok(())
+    } else {
+        Err(Error::PlaylistUpdate)
+    }
+}
+#[instrument]
+/// Drop the open connections to the service so the next request connects again.
+pub async fn reconnect_service() {
+    queue().read().await.reconnect();
+}
+#[instrument]
+/// Ask the service whether it is back after an outage.
+pub async fn service_healthy() -> bool {
+    queue().read().await.health_check().await
+}
+/// The number of albums in a page of a browse category.
+pub const BROWSE_PAGE_SIZE: u32 = 50;
 
-            ready().await?;
-            pause().await?;
-            play().await?;
+#[instrument]
+/// The catalog sections the service can browse.
+pub async fn browse_categories() -> Vec<BrowseCategory> {
+    queue().read().await.browse_categories()
+}
+#[instrument]
+/// The playlists the service makes for the user.
+pub async fn personal_playlists() -> Vec<PersonalPlaylist> {
+    queue().read().await.personal_playlists()
+}
+#[instrument]
+#[cached(size = 4, time = 600, option = true)]
+/// Fetch one of the playlists the service made for the user.
+pub async fn personal_playlist(kind: PersonalPlaylist) -> Option<Playlist> {
+    queue().read().await.fetch_personal_playlist(kind).await
+}
+#[instrument]
+#[cached(size = 1, time = 600, option = true)]
+/// Fetch the playlists picked by the service's editors.
+pub async fn featured_playlists() -> Option<Vec<Playlist>> {
+    queue().read().await.fetch_featured_playlists().await
+}
+#[instrument]
+/// The genres browse categories can be narrowed to.
+pub async fn genres() -> Vec<Genre> {
+    queue().read().await.fetch_genres().await
+}
+#[instrument]
+#[cached(size = 20, time = 600, option = true)]
+/// Fetch a page of albums from a browse category, only of `genre` when one is given.
+pub async fn browse(
+    category: BrowseCategory,
+    genre: Option<u32>,
+    offset: u32,
+) -> Option<BrowsePage> {
+    queue()
+        .read()
+        .await
+        .fetch_browse_page(category, genre, offset, BROWSE_PAGE_SIZE)
+        .await
+}
+#[instrument]
+/// Fetch a single album.
+pub async fn album(album_id: &str) -> Option<Album> {
+    queue().read().await.fetch_album(album_id).await
+}
+#[instrument]
+/// Fetch several albums at once, e.g. everything shown in a list.
+pub async fn albums(album_ids: Vec<String>) -> Vec<Album> {
+    queue().read().await.fetch_albums(&album_ids).await
+}
+#[instrument]
+/// Fetch several tracks at once.
+pub async fn tracks(track_ids: Vec<i32>) -> Vec<Track> {
+    queue().read().await.fetch_tracks(&track_ids).await
+}
+#[instrument]
+/// Fetch the current user's favorite albums, `None` if the service could not be reached.
+pub async fn favorite_albums() -> Option<Vec<Album>> {
+    queue().read().await.fetch_favorite_albums().await
+}
+#[instrument]
+/// Fetch the current user's favorite tracks, `None` if the service could not be reached.
+pub async fn favorite_tracks() -> Option<Vec<Track>> {
+    queue().read().await.fetch_favorite_tracks().await
+}
+#[instrument]
+/// Fetch the current user's favorite artists, `None` if the service could not be reached.
+pub async fn favorite_artists() -> Option<Vec<Artist>> {
+    queue().read().await.fetch_favorite_artists().await
+}
+#[instrument]
+/// Add an album, track or artist to the favorites, or remove it when `favorite` is false.
+pub async fn set_favorite(item: Favorite, favorite: bool) -> Result<()> {
+    party::check()?;
 
-            debug!(
-                "Error from {:?}: {} ({:?})",
-                err.src().map(|s| s.path_string()),
-                err.error(),
-                err.debug()
-            );
-        }
-        _ => (),
+    if queue().read().await.set_favorite(item, favorite).await {
+        Ok(())
+    } else {
+        Err(Error::FavoriteFailed)
     }
-
-    Ok(())
 }
 
 #[macro_export]
@@ -894,3 +2697,121 @@ macro_rules! action_blocking {
         }
     };
 }
+
+#[tokio::test]
+async fn drives_a_player_on_a_mock_backend() {
+    use backend::mock::{MockBackend, DURATION};
+
+    let backend = MockBackend::default();
+    let service = local::open(std::path::PathBuf::from(crate::TEST_TEMP_PATH));
+    let player = Player::with_backend(backend.clone(), PlayerState::new(service));
+    let mut notifications = player.notify_receiver();
+
+    player.play().await.expect("failed to play");
+    assert!(player.is_playing());
+
+    player
+        .seek_to(ClockTime::from_seconds(30))
+        .await
+        .expect("failed to seek");
+    assert_eq!(player.position(), Some(ClockTime::from_seconds(30)));
+
+    player
+        .seek_seconds(600, true)
+        .await
+        .expect("failed to seek");
+    assert_eq!(player.position(), Some(clock_time(DURATION)));
+
+    player
+        .set_volume(0.5)
+        .await
+        .expect("failed to set the volume");
+    assert_eq!(backend.volume(), 0.5);
+    assert!(matches!(
+        notifications.recv().await,
+        Ok(Notification::Volume { volume }) if volume == 0.5
+    ));
+
+    player.pause().await.expect("failed to pause");
+    assert!(player.is_paused());
+}
+
+#[tokio::test]
+async fn handles_the_events_of_a_mock_backend() {
+    use backend::mock::MockBackend;
+
+    let service = local::open(std::path::PathBuf::from(crate::TEST_TEMP_PATH));
+    let player: &'static Player = Box::leak(Box::new(Player::with_backend(
+        MockBackend::default(),
+        PlayerState::new(service),
+    )));
+    let mut notifications = player.notify_receiver();
+
+    player
+        .queue()
+        .write()
+        .await
+        .set_target_status(GstState::Playing);
+    player
+        .handle_event(BackendEvent::StateChanged(PlaybackState::Playing))
+        .await
+        .expect("failed to handle the state change");
+    assert!(matches!(
+        notifications.recv().await,
+        Ok(Notification::Status {
+            status: GstState::Playing
+        })
+    ));
+
+    let format = BackendEvent::AudioFormat {
+        sampling_rate: 96000,
+        bit_depth: 24,
+    };
+    player
+        .handle_event(format.clone())
+        .await
+        .expect("failed to handle the audio format");
+    assert!(matches!(
+        notifications.recv().await,
+        Ok(Notification::AudioQuality {
+            bitdepth: 24,
+            sampling_rate: 96000
+        })
+    ));
+
+    // Neither an unchanged format nor levels with the visualization off are passed on.
+    player
+        .handle_event(format)
+        .await
+        .expect("failed to handle the audio format");
+    player.toggle_visualization();
+    player
+        .handle_event(BackendEvent::Level {
+            rms: vec![-20.0],
+            peak: vec![-10.0],
+        })
+        .await
+        .expect("failed to handle the levels");
+    assert!(notifications.try_recv().is_err());
+}
+
+#[tokio::test]
+async fn runs_without_being_installed() {
+    use backend::mock::MockBackend;
+
+    let service = local::open(std::path::PathBuf::from(crate::TEST_TEMP_PATH));
+    let player = Player::with_backend(MockBackend::default(), PlayerState::new(service.clone()));
+    let mut notifications = player.notify_receiver();
+
+    assert_eq!(player.save_queue("later").await, Err(Error::EmptyQueue));
+
+    player.sign_in(service).await.expect("failed to sign in");
+
+    loop {
+        match notifications.recv().await {
+            Ok(Notification::SignedIn) => break,
+            Ok(_) => continue,
+            Err(error) => panic!("no sign in notification: {error}"),
+        }
+    }
+}
@@ -1,7 +1,21 @@
 use gstreamer::{ClockTime, State};
+use hifirs_qobuz_api::client::AudioQuality;
 use serde::{Deserialize, Serialize, Serializer};
+use std::net::{IpAddr, SocketAddr};
 
-use crate::{player, player::queue::TrackListValue};
+use crate::{
+    config::ConfigKey,
+    player,
+    player::{
+        audition::Audition,
+        output::OutputProfile,
+        queue::{ListProgress, TrackListValue},
+        session::SessionStats,
+        stats::StreamStats,
+        transition::GaplessStats,
+    },
+    service::{Album, Track},
+};
 
 pub type BroadcastReceiver = async_broadcast::Receiver<Notification>;
 pub type BroadcastSender = async_broadcast::Sender<Notification>;
@@ -13,6 +27,24 @@ where
     clock.seconds().serialize(s)
 }
 
+/// A remote client connected to the websocket.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct RemoteClient {
+    pub id: u64,
+    pub name: Option<String>,
+    pub address: SocketAddr,
+    pub connected_at: i64,
+    /// Notification topics this client wants to receive. Empty means everything.
+    pub topics: Vec<String>,
+}
+
+impl RemoteClient {
+    pub fn wants(&self, topic: &str) -> bool {
+        self.topics.is_empty() || self.topics.iter().any(|t| t == topic)
+    }
+}
+
 #[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
 #[serde(rename_all = "camelCase")]
 pub enum Notification {
@@ -31,6 +63,10 @@ pub enum Notification {
     CurrentTrackList {
         list: TrackListValue,
     },
+    /// Sent with every position while an album or playlist is playing.
+    ListProgress {
+        progress: ListProgress,
+    },
     AudioQuality {
         bitdepth: u32,
         sampling_rate: u32,
@@ -43,4 +79,122 @@ pub enum Notification {
     Error {
         error: player::error::Error,
     },
+    Level {
+        rms: Vec<f64>,
+        peak: Vec<f64>,
+    },
+    Spectrum {
+        magnitudes: Vec<f32>,
+    },
+    ClientConnected {
+        client: RemoteClient,
+    },
+    ClientDisconnected {
+        client: RemoteClient,
+    },
+    ReleaseAvailable {
+        album: Album,
+    },
+    OutputProfileChanged {
+        profile: OutputProfile,
+    },
+    /// A track was skipped after running out of retries. `errors` and
+    /// `abandoned` are the totals for the session so far.
+    TrackAbandoned {
+        track: Track,
+        attempts: u32,
+        errors: u32,
+        abandoned: u32,
+    },
+    /// Sent right before `Quit` with what was played this session.
+    SessionSummary {
+        stats: SessionStats,
+    },
+    /// A queued track could no longer be streamed when its turn came, so it was skipped.
+    /// Its status is `Unavailable` when the service still lists it but refused a url.
+    TrackUnavailable {
+        track: Track,
+    },
+    /// Tracks are being played from the local cache because the service could not be reached.
+    Offline {
+        offline: bool,
+    },
+    /// The player started offline and has now signed in to the service.
+    SignedIn,
+    /// The next track could not be prepared in time for a gapless transition.
+    /// `recovered` is true when the retry worked, otherwise playback stops between the tracks.
+    GaplessPrepFailed {
+        track: Track,
+        recovered: bool,
+        stats: GaplessStats,
+    },
+    /// The machine moved to another network. Remote clients should reconnect.
+    NetworkChanged {
+        address: Option<IpAddr>,
+    },
+    /// The service answers with server errors, e.g. during maintenance. Sent when the outage
+    /// starts and after every failed check, with the seconds until the next check, and with
+    /// `status: None` once it is back.
+    ServiceOutage {
+        status: Option<u16>,
+        retry_in: u64,
+    },
+    /// The account's subscription keeps tracks below the requested quality. `None` once a
+    /// track is delivered as requested again.
+    AccountLimited {
+        limited_to: Option<AudioQuality>,
+    },
+    /// The volume was changed, between 0.0 and 1.0.
+    Volume {
+        volume: f64,
+    },
+    /// Shuffle was turned on or off.
+    Shuffle {
+        enabled: bool,
+    },
+    /// Party mode was turned on or off.
+    PartyMode {
+        enabled: bool,
+    },
+    /// The next track started fading in over the end of the previous one, or finished doing so.
+    Crossfade {
+        active: bool,
+        seconds: u32,
+    },
+    /// Simple mode was unlocked with the PIN.
+    SimpleMode {
+        enabled: bool,
+    },
+    /// An audition started, a track was kept or removed, or the audition ended (`None`).
+    Audition {
+        audition: Option<Audition>,
+    },
+    /// The last track of an audition was heard. The removals still need to be confirmed.
+    AuditionFinished {
+        audition: Audition,
+    },
+    /// Autoplay added these tracks to the end of the queue as it was about to run out.
+    AutoplayAppended {
+        tracks: Vec<Track>,
+    },
+    /// Sent every second with the bitrate, buffer fill and data downloaded.
+    Stats {
+        stats: StreamStats,
+    },
+    /// The config was reloaded. `needs_restart` lists the changes that only apply after a restart.
+    ConfigReloaded {
+        changed: Vec<ConfigKey>,
+        needs_restart: Vec<ConfigKey>,
+    },
+}
+
+impl Notification {
+    /// The name a notification is serialized under, e.g. `position`.
+    pub fn topic(&self) -> String {
+        match serde_json::to_value(self) {
+            Ok(serde_json::Value::Object(map)) => map.keys().next().cloned().unwrap_or_default(),
+            Ok(serde_json::Value::String(name)) => name,
+            _ => String::new(),
+        }
+    }
 }
@@ -0,0 +1,212 @@
+use crate::player::{error::Error, Result};
+use clap::ValueEnum;
+use gstreamer::{self as gst, prelude::*, Element};
+use serde::{Deserialize, Serialize};
+use std::fmt::Display;
+
+/// Band gains, in dB, for the `equalizer-10bands` element.
+#[derive(Default, Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, ValueEnum)]
+#[serde(rename_all = "kebab-case")]
+pub enum EqPreset {
+    #[default]
+    Flat,
+    BassBoost,
+    TrebleBoost,
+    Vocal,
+    Loudness,
+}
+
+impl EqPreset {
+    /// Gains for the ten bands, from 29 Hz up to 15 kHz.
+    pub fn bands(&self) -> [f64; 10] {
+        match self {
+            EqPreset::Flat => [0.; 10],
+            EqPreset::BassBoost => [6., 5., 4., 2., 0., 0., 0., 0., 0., 0.],
+            EqPreset::TrebleBoost => [0., 0., 0., 0., 0., 0., 2., 4., 5., 6.],
+            EqPreset::Vocal => [-2., -2., 0., 2., 4., 4., 2., 0., -1., -2.],
+            EqPreset::Loudness => [5., 4., 2., 0., -1., -1., 0., 2., 4., 5.],
+        }
+    }
+}
+
+impl Display for EqPreset {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self.to_possible_value() {
+            Some(value) => f.write_str(value.get_name()),
+            None => f.write_str("flat"),
+        }
+    }
+}
+
+/// A named set of output settings, e.g. "speakers", "headphones" or "DAC".
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct OutputProfile {
+    pub name: String,
+    /// A GStreamer sink description, e.g. `alsasink device=hw:1,0`.
+    /// The system default output is used when this is empty.
+    pub device: Option<String>,
+    /// Send audio to the device untouched: no resampling, EQ or volume changes.
+    pub bit_perfect: bool,
+    /// Volume between 0.0 and 1.0.
+    pub volume: f64,
+    pub eq_preset: EqPreset,
+}
+
+impl OutputProfile {
+    /// Plays through `device` with the default settings, for when no profile is active.
+    pub fn for_device(device: String) -> Self {
+        Self {
+            name: String::new(),
+            device: Some(device),
+            bit_perfect: false,
+            volume: 1.0,
+            eq_preset: EqPreset::Flat,
+        }
+    }
+
+    /// The volume to apply, always full volume for bit perfect output.
+    pub fn effective_volume(&self) -> f64 {
+        if self.bit_perfect {
+            1.0
+        } else {
+            self.volume.clamp(0.0, 1.0)
+        }
+    }
+}
+
+/// An audio output found by GStreamer, e.g. an ALSA device, PulseAudio sink or JACK server.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct OutputDevice {
+    /// The name the system shows for it, e.g. `Built-in Audio Analog Stereo`.
+    pub name: String,
+    /// The sink description that plays through it, e.g. `pulsesink device="alsa_output.usb"`.
+    pub sink: String,
+}
+
+/// The sink properties that pick a device, for ALSA and PulseAudio, PipeWire and JACK.
+const DEVICE_PROPERTIES: &[&str] = &["device", "target-object", "port-pattern"];
+
+/// The audio outputs the device monitor finds.
+pub fn devices() -> Vec<OutputDevice> {
+    if let Err(error) = gst::init() {
+        debug!(?error, "failed to initialize gstreamer");
+        return Vec::new();
+    }
+
+    let monitor = gst::DeviceMonitor::new();
+    monitor.add_filter(Some("Audio/Sink"), None);
+
+    if let Err(error) = monitor.start() {
+        debug!(?error, "failed to start the device monitor");
+        return Vec::new();
+    }
+
+    let devices = monitor
+        .devices()
+        .into_iter()
+        .filter_map(|device| {
+            Some(OutputDevice {
+                name: device.display_name().to_string(),
+                sink: sink_description(&device)?,
+            })
+        })
+        .collect();
+
+    monitor.stop();
+
+    devices
+}
+
+/// The sink of the device called `name`, or `name` itself when it is no device name but
+/// already a sink description like `alsasink device=hw:1,0`. An error when it is neither.
+pub fn resolve_device(name: &str) -> Result<String> {
+    let name = name.trim();
+
+    if let Some(device) = devices()
+        .into_iter()
+        .find(|device| device.name.eq_ignore_ascii_case(name))
+    {
+        return Ok(device.sink);
+    }
+
+    let is_sink = name
+        .split_whitespace()
+        .next()
+        .is_some_and(|factory| gst::ElementFactory::find(factory).is_some());
+
+    if is_sink {
+        Ok(name.to_string())
+    } else {
+        Err(Error::UnknownOutput {
+            name: name.to_string(),
+        })
+    }
+}
+
+fn sink_description(device: &gst::Device) -> Option<String> {
+    let element = device.create_element(None).ok()?;
+    let factory = element.factory()?.name();
+
+    let property = DEVICE_PROPERTIES.iter().find_map(|property| {
+        if !element.has_property(property, None) {
+            return None;
+        }
+
+        element
+            .property_value(property)
+            .get::<Option<String>>()
+            .ok()
+            .flatten()
+            .map(|value| format!("{property}=\"{value}\""))
+    });
+
+    match property {
+        Some(property) => Some(format!("{factory} {property}")),
+        None => Some(factory.to_string()),
+    }
+}
+
+/// Builds the audio sink for `profile`, resampling to `resample_rate` when given.
+/// Returns `None` when the default sink would do the same job.
+pub fn build_sink(profile: &OutputProfile, resample_rate: Option<u32>) -> Result<Option<Element>> {
+    let mut elements = Vec::new();
+
+    if !profile.bit_perfect {
+        if profile.eq_preset != EqPreset::Flat {
+            let bands = profile
+                .eq_preset
+                .bands()
+                .iter()
+                .enumerate()
+                .map(|(i, gain)| format!("band{i}={gain}"))
+                .collect::<Vec<String>>()
+                .join(" ");
+
+            elements.push("audioconvert".to_string());
+            elements.push(format!("equalizer-10bands {bands}"));
+        }
+
+        if let Some(rate) = resample_rate {
+            elements.push("audioconvert ! audioresample".to_string());
+            elements.push(format!("audio/x-raw,rate={rate}"));
+        }
+    }
+
+    let device = profile
+        .device
+        .as_deref()
+        .map(str::trim)
+        .filter(|d| !d.is_empty());
+
+    if elements.is_empty() && device.is_none() {
+        return Ok(None);
+    }
+
+    elements.push(device.unwrap_or("autoaudiosink").to_string());
+
+    let bin = gst::parse::bin_from_description(&elements.join(" ! "), true)?;
+
+    Ok(Some(bin.upcast()))
+}
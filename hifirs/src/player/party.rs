@@ -0,0 +1,60 @@
+use crate::{
+    config::ConfigKey,
+    player::{error::Error, notification::Notification, simple, Result},
+    sql::db,
+};
+use std::sync::atomic::{AtomicBool, Ordering};
+
+static ENABLED: AtomicBool = AtomicBool::new(false);
+
+/// True while party mode is on. Only play, pause, next and the volume are allowed then,
+/// along with anything that just looks things up.
+pub fn is_enabled() -> bool {
+    ENABLED.load(Ordering::Relaxed)
+}
+
+/// Refuse an action that party mode or simple mode locks.
+pub fn check() -> Result<()> {
+    if is_enabled() {
+        Err(Error::PartyMode)
+    } else {
+        simple::check()
+    }
+}
+
+/// Like [`check`], but simple mode lets the playlists on its allowlist be started.
+pub fn check_playlist(playlist_id: i64) -> Result<()> {
+    if is_enabled() {
+        Err(Error::PartyMode)
+    } else {
+        simple::check_playlist(playlist_id)
+    }
+}
+
+/// Turn party mode on. A PIN has to be set first, otherwise there would be no way to turn it off.
+/// Returns the notification for the player to send when it wasn't on.
+pub async fn enable() -> Result<Option<Notification>> {
+    if db::get_config_value(ConfigKey::PartyPin).await.is_none() {
+        return Err(Error::PartyPinMissing);
+    }
+
+    Ok(set_enabled(true))
+}
+
+/// Turn party mode off if `pin` matches the configured PIN.
+pub async fn disable(pin: &str) -> Result<Option<Notification>> {
+    match db::get_config_value(ConfigKey::PartyPin).await {
+        Some(expected) if expected == pin.trim() => Ok(set_enabled(false)),
+        _ => Err(Error::WrongPin),
+    }
+}
+
+fn set_enabled(enabled: bool) -> Option<Notification> {
+    if ENABLED.swap(enabled, Ordering::Relaxed) == enabled {
+        return None;
+    }
+
+    info!(enabled, "party mode changed");
+
+    Some(Notification::PartyMode { enabled })
+}
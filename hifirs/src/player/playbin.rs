@@ -0,0 +1,308 @@
+use crate::player::{
+    backend::{BackendEvent, PlaybackBackend, PlaybackState, SeekMode, StateChange},
+    cache,
+    error::Error,
+    event_log, stats, visualization, Result,
+};
+use flume::{Receiver, Sender};
+use futures::{prelude::*, stream::BoxStream};
+use gst::{
+    prelude::*, Caps, ClockTime, Element, Message, MessageType, MessageView, SeekFlags,
+    State as GstState, StateChangeSuccess, Structure,
+};
+use gstreamer as gst;
+use once_cell::sync::Lazy;
+use std::{str::FromStr, time::Duration};
+
+static VERSION: Lazy<(u32, u32, u32, u32)> = Lazy::new(gstreamer::version);
+static USER_AGENTS: &[&str] = &[
+    "Mozilla/5.0 (Windows NT 10.0; Win64; x64) AppleWebKit/537.36 (KHTML, like Gecko) Chrome/114.0.0.0 Safari/537.36",
+    "Mozilla/5.0 (Macintosh; Intel Mac OS X 13_4) AppleWebKit/537.36 (KHTML, like Gecko) Chrome/114.0.0.0 Safari/537.36"
+];
+
+/// Plays through GStreamer's playbin3.
+pub struct PlaybinBackend {
+    playbin: Element,
+    about_to_finish: (Sender<()>, Receiver<()>),
+}
+
+impl PlaybinBackend {
+    pub fn new() -> Self {
+        let playbin = build_playbin();
+        let about_to_finish = flume::bounded::<()>(1);
+        let tx = about_to_finish.0.clone();
+
+        // Connects to the `about-to-finish` signal so the player
+        // can setup the next track to play. Enables gapless playback.
+        playbin.connect("about-to-finish", false, move |_| {
+            debug!("about to finish");
+            tx.send(()).expect("failed to send about to finish message");
+
+            None
+        });
+
+        Self {
+            playbin,
+            about_to_finish,
+        }
+    }
+
+    /// The playbin3 element, for what only GStreamer can do: output sinks, visualization
+    /// and synced playback.
+    pub fn pipeline(&self) -> &Element {
+        &self.playbin
+    }
+}
+
+impl Default for PlaybinBackend {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl PlaybackBackend for PlaybinBackend {
+    fn set_uri(&self, uri: &str) {
+        self.playbin.set_property("uri", uri);
+    }
+
+    fn set_state(&self, state: PlaybackState) -> Result<StateChange> {
+        Ok(match self.playbin.set_state(state.into())? {
+            StateChangeSuccess::Success => StateChange::Done,
+            StateChangeSuccess::Async => StateChange::Pending,
+            StateChangeSuccess::NoPreroll => StateChange::Live,
+        })
+    }
+
+    fn state(&self) -> PlaybackState {
+        self.playbin.current_state().into()
+    }
+
+    fn seek(&self, position: Duration, mode: SeekMode) -> Result<()> {
+        let flags = match mode {
+            SeekMode::Fast => SeekFlags::FLUSH | SeekFlags::TRICKMODE_KEY_UNITS,
+            SeekMode::Accurate => SeekFlags::FLUSH | SeekFlags::ACCURATE,
+        };
+
+        self.playbin
+            .seek_simple(flags, ClockTime::from_nseconds(position.as_nanos() as u64))?;
+
+        Ok(())
+    }
+
+    fn position(&self) -> Option<Duration> {
+        self.playbin
+            .query_position::<ClockTime>()
+            .map(|position| Duration::from_nanos(position.nseconds()))
+    }
+
+    fn duration(&self) -> Option<Duration> {
+        self.playbin
+            .query_duration::<ClockTime>()
+            .map(|duration| Duration::from_nanos(duration.nseconds()))
+    }
+
+    fn volume(&self) -> f64 {
+        self.playbin.property::<f64>("volume")
+    }
+
+    fn set_volume(&self, volume: f64) {
+        self.playbin.set_property("volume", volume);
+    }
+
+    fn events(&self) -> BoxStream<'static, BackendEvent> {
+        let playbin = self.playbin.clone();
+        let messages = self
+            .playbin
+            .bus()
+            .expect("pipeline has no bus")
+            .stream()
+            .filter_map(move |msg| {
+                if msg.type_() != MessageType::StateChanged
+                    || msg.src() == Some(playbin.upcast_ref::<gst::Object>())
+                {
+                    event_log::record_message(&msg);
+                }
+
+                future::ready(to_event(&msg))
+            });
+        let about_to_finish = self
+            .about_to_finish
+            .1
+            .clone()
+            .into_stream()
+            .map(|_| BackendEvent::AboutToFinish);
+
+        stream::select(messages, about_to_finish).boxed()
+    }
+
+    /// playbin3 can from GStreamer 1.22.
+    fn switches_instantly(&self) -> bool {
+        self.playbin.find_property("instant-uri").is_some()
+    }
+
+    /// `instant-uri` is only switched on for this, as a uri set in `about-to-finish`
+    /// must wait for the current track to end to be gapless.
+    fn switch_uri(&self, uri: &str) {
+        if self.switches_instantly() {
+            self.playbin.set_property("instant-uri", true);
+            self.set_uri(uri);
+            self.playbin.set_property("instant-uri", false);
+        } else {
+            self.set_uri(uri);
+        }
+    }
+}
+
+/// What the player needs to hear of a message from the bus. Tags only go into the stream
+/// stats and everything else is left to the event log.
+fn to_event(msg: &Message) -> Option<BackendEvent> {
+    match msg.view() {
+        MessageView::Eos(_) => Some(BackendEvent::EndOfStream),
+        MessageView::StreamStart(_) => Some(BackendEvent::StreamStart),
+        MessageView::Buffering(buffering) => Some(BackendEvent::Buffering(buffering.percent())),
+        MessageView::StateChanged(state_changed) => {
+            Some(BackendEvent::StateChanged(state_changed.current().into()))
+        }
+        MessageView::AsyncDone(msg) => Some(BackendEvent::Prerolled(
+            msg.running_time()
+                .map(|position| Duration::from_nanos(position.nseconds())),
+        )),
+        MessageView::PropertyNotify(el) => {
+            let (_, prop_name, value) = el.get();
+
+            if prop_name != "caps" {
+                return None;
+            }
+
+            let caps = value?.get::<&Caps>().ok()?;
+            let (sampling_rate, bit_depth) = audio_format(caps)?;
+
+            Some(BackendEvent::AudioFormat {
+                sampling_rate,
+                bit_depth,
+            })
+        }
+        MessageView::Element(element) => {
+            let structure = element.structure()?;
+
+            match structure.name().as_str() {
+                "level" => visualization::parse_level(structure)
+                    .map(|(rms, peak)| BackendEvent::Level { rms, peak }),
+                "spectrum" => visualization::parse_spectrum(structure)
+                    .map(|magnitudes| BackendEvent::Spectrum { magnitudes }),
+                _ => None,
+            }
+        }
+        MessageView::Tag(tag) => {
+            stats::record_tags(&tag.tags());
+
+            None
+        }
+        MessageView::ClockLost(_) => Some(BackendEvent::ClockLost),
+        MessageView::Error(err) => Some(BackendEvent::Error {
+            error: Error::from(err),
+            url_rejected: is_url_rejected(err),
+        }),
+        _ => None,
+    }
+}
+
+/// The sample rate and bit depth in `caps`, when they are complete.
+fn audio_format(caps: &Caps) -> Option<(u32, u32)> {
+    let structure = caps.structure(0)?;
+    let rate: u32 = structure.get("rate").unwrap_or_default();
+    let format: &str = structure.get("format").unwrap_or_default();
+    let bits = if format.starts_with("S24") {
+        24_u32
+    } else if format.starts_with("S16") {
+        16_u32
+    } else {
+        0
+    };
+
+    (rate != 0 && bits != 0).then_some((rate, bits))
+}
+
+/// True for errors of the http source when the service refused the url, which it does
+/// once the url expired.
+fn is_url_rejected(err: &gst::message::Error) -> bool {
+    err.error().matches(gst::ResourceError::NotAuthorized)
+        || err.debug().is_some_and(|debug| debug.contains("(403)"))
+}
+
+/// The player's notifications speak GStreamer's states.
+impl From<PlaybackState> for GstState {
+    fn from(state: PlaybackState) -> Self {
+        match state {
+            PlaybackState::Stopped => GstState::Null,
+            PlaybackState::Ready => GstState::Ready,
+            PlaybackState::Paused => GstState::Paused,
+            PlaybackState::Playing => GstState::Playing,
+        }
+    }
+}
+
+impl From<GstState> for PlaybackState {
+    fn from(state: GstState) -> Self {
+        match state {
+            GstState::Ready => PlaybackState::Ready,
+            GstState::Paused => PlaybackState::Paused,
+            GstState::Playing => PlaybackState::Playing,
+            _ => PlaybackState::Stopped,
+        }
+    }
+}
+
+/// Builds the playbin3 pipeline the player streams through.
+fn build_playbin() -> Element {
+    gst::init().expect("error initializing gstreamer");
+
+    let playbin = gst::ElementFactory::make("playbin3")
+        .build()
+        .expect("error building playbin element");
+
+    playbin.set_property_from_str("flags", "audio+buffering");
+
+    if VERSION.1 >= 22 {
+        playbin.connect("element-setup", false, |value| {
+            let element = &value[1].get::<gst::Element>().unwrap();
+
+            if element.name().contains("urisourcebin") {
+                element.set_property("parse-streams", true);
+            }
+
+            None
+        });
+    }
+
+    playbin.connect("source-setup", false, |value| {
+        let element = &value[1].get::<gst::Element>().unwrap();
+
+        if element.name().contains("souphttpsrc") {
+            debug!("new source, changing settings");
+            let ua = if rand::random() {
+                USER_AGENTS[0]
+            } else {
+                USER_AGENTS[1]
+            };
+            element.set_property("user-agent", ua);
+            element.set_property("compress", true);
+            element.set_property("retries", 10);
+            element.set_property("timeout", 30_u32);
+            element.set_property(
+                "extra-headers",
+                Structure::from_str("a-structure, DNT=1, Pragma=no-cache, Cache-Control=no-cache")
+                    .expect("failed to make structure from string"),
+            );
+
+            cache::tee_source(element);
+            stats::count_source(element);
+        }
+
+        None
+    });
+
+    playbin.add_property_deep_notify_watch(Some("caps"), true);
+
+    playbin
+}
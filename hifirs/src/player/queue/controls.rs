@@ -1,18 +1,40 @@
 use crate::{
-    player,
-    player::queue::{TrackListType, TrackListValue},
-    qobuz,
-    service::{Album, MusicService, Playlist, SearchResults, Track, TrackStatus},
+    library, local,
+    player::{
+        account, cache,
+        notification::{BroadcastSender as NotificationSender, Notification},
+        queue::{ListProgress, TrackListType, TrackListValue},
+        transition,
+    },
+    service::{
+        Album, Artist, BrowseCategory, BrowsePage, Favorite, Genre, MusicService, PersonalPlaylist,
+        Playlist, SearchLimits, SearchResults, Track, TrackStatus,
+    },
     sql::db,
 };
 use futures::executor;
 use gstreamer::{ClockTime, State as GstState};
-use std::{collections::BTreeMap, sync::Arc};
+use hifirs_qobuz_api::client::{health, AudioQuality};
+use once_cell::sync::Lazy;
+use serde::{Deserialize, Serialize};
+use std::{
+    collections::{BTreeMap, HashMap, HashSet},
+    future::Future,
+    sync::{Arc, Mutex},
+    time::{Duration, Instant},
+};
 use tokio::sync::{
     broadcast::{Receiver as BroadcastReceiver, Sender as BroadcastSender},
     RwLock,
 };
 
+/// A url is only used when it works for at least this much longer than needed.
+const URL_MARGIN: Duration = Duration::from_secs(60);
+/// How long a url that doesn't say when it expires is trusted for.
+const URL_MAX_AGE: Duration = Duration::from_secs(30 * 60);
+
+static TRACK_URLS: Lazy<Mutex<UrlManager>> = Lazy::new(Default::default);
+
 #[derive(Debug, Clone)]
 pub struct PlayerState {
     service: Arc<dyn MusicService>,
@@ -21,7 +43,25 @@ pub struct PlayerState {
     status: GstState,
     resume: bool,
     target_status: GstState,
+    quality: Option<AudioQuality>,
+    /// The quality the next album, playlist or track is played at instead of the session's.
+    next_quality: Option<AudioQuality>,
+    /// The quality the current queue was started at, used over the session's until
+    /// something else is played.
+    queue_quality: Option<AudioQuality>,
     quit_sender: BroadcastSender<bool>,
+    /// Queued tracks the service stopped offering, waiting to be broadcast.
+    withdrawn: Vec<Track>,
+    /// Shuffle the upcoming tracks of every list that is played.
+    shuffle: bool,
+    /// The url of the next track, fetched ahead of time.
+    prefetched: Option<Prefetched>,
+    /// The tracks marked unplayable for not being cached, by id, to put back when everything
+    /// is played again.
+    cache_filtered: HashSet<u32>,
+    /// Where the notifications of the player this state belongs to go, for what changes
+    /// while urls are fetched.
+    notifier: Option<NotificationSender>,
 }
 
 pub type SafePlayerState = Arc<RwLock<PlayerState>>;
@@ -36,12 +76,13 @@ pub struct SavedState {
     pub playback_entity_type: String,
 }
 
-impl From<PlayerState> for SavedState {
-    fn from(state: PlayerState) -> Self {
+impl SavedState {
+    /// What plays in `state`, `position` into the current track.
+    pub fn new(state: &PlayerState, position: ClockTime) -> Self {
         if let Some(current_track) = state.current_track() {
             let playback_track_index = current_track.position as i64;
             let playback_track_id = current_track.id as i64;
-            let playback_position = player::position().unwrap_or_default().mseconds() as i64;
+            let playback_position = position.mseconds() as i64;
             let playback_entity_type = state.list_type();
             let playback_entity_id = match playback_entity_type {
                 TrackListType::Album => state.album().expect("failed to get album id").id.clone(),
@@ -68,40 +109,358 @@ impl From<PlayerState> for SavedState {
     }
 }
 
+/// A queue saved under a name, to switch back to later.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct QueueSnapshot {
+    pub name: String,
+    pub tracklist: TrackListValue,
+    /// Milliseconds into the current track.
+    pub position: u64,
+    /// Unix timestamp of when it was saved.
+    pub saved_at: i64,
+}
+
+impl QueueSnapshot {
+    pub fn saved_at_label(&self) -> String {
+        chrono::DateTime::from_timestamp(self.saved_at, 0)
+            .map(|date| {
+                date.with_timezone(&chrono::Local)
+                    .format("%Y-%m-%d %H:%M")
+                    .to_string()
+            })
+            .unwrap_or_default()
+    }
+
+    /// The title of the track it was left at.
+    pub fn current_title(&self) -> String {
+        self.tracklist
+            .current_track()
+            .map(|t| t.title.clone())
+            .unwrap_or_default()
+    }
+}
+
+/// A url to play a track from.
+#[derive(Debug, Clone)]
+struct FetchedUrl {
+    url: String,
+    /// The lower quality the service streams the track at, when it isn't offered at the
+    /// one asked for.
+    fallback: Option<AudioQuality>,
+}
+
+impl FetchedUrl {
+    fn new(url: String) -> Self {
+        Self {
+            url,
+            fallback: None,
+        }
+    }
+
+    /// Attach the url to `track`, which then plays from it.
+    fn attach(self, track: &mut Track) -> String {
+        track.track_url = Some(self.url.clone());
+        track.fallback_quality = self.fallback;
+
+        self.url
+    }
+}
+
+/// The url of the next track, fetched while the current one plays so it's ready for a
+/// gapless transition.
+#[derive(Debug, Clone)]
+pub struct Prefetched {
+    track_id: u32,
+    quality: Option<AudioQuality>,
+    fetched: FetchedUrl,
+}
+
+/// Keeps track of when each stream url was fetched and when it stops working, so urls
+/// can be replaced before they are needed.
+#[derive(Debug, Default)]
+struct UrlManager {
+    urls: HashMap<String, UrlRecord>,
+    /// How long the last url that said when it expires worked for, from when it was fetched.
+    lifetime: Option<Duration>,
+}
+
+#[derive(Debug, Clone, Copy)]
+struct UrlRecord {
+    fetched_at: Instant,
+    /// Unix timestamp, from the url's `etsp` parameter. Qobuz urls have one.
+    expires_at: Option<i64>,
+}
+
+impl UrlRecord {
+    fn expires_within(&self, within: Duration) -> bool {
+        match self.expires_at {
+            Some(expires_at) => {
+                chrono::Utc::now().timestamp() + within.as_secs() as i64 >= expires_at
+            }
+            None => self.fetched_at.elapsed() + within >= URL_MAX_AGE,
+        }
+    }
+}
+
+impl UrlManager {
+    fn record(&mut self, url: &str) {
+        // Drop the ones that stopped working, they are of no use anymore.
+        self.urls
+            .retain(|_, record| !record.expires_within(Duration::ZERO));
+
+        let expires_at = url::Url::parse(url).ok().and_then(|url| {
+            url.query_pairs()
+                .find(|(key, _)| key == "etsp")
+                .and_then(|(_, value)| value.parse::<i64>().ok())
+        });
+
+        if let Some(expires_at) = expires_at {
+            let left = expires_at - chrono::Utc::now().timestamp();
+            self.lifetime = Some(Duration::from_secs(left.max(0) as u64));
+        }
+
+        self.urls.insert(
+            url.to_string(),
+            UrlRecord {
+                fetched_at: Instant::now(),
+                expires_at,
+            },
+        );
+    }
+
+    fn expires_within(&self, url: &str, within: Duration) -> bool {
+        // Local files and cached copies don't expire.
+        if url.starts_with("file:") {
+            return false;
+        }
+
+        match self.urls.get(url) {
+            Some(record) => record.expires_within(within + URL_MARGIN),
+            None => true,
+        }
+    }
+
+    fn lifetime(&self) -> Duration {
+        self.lifetime.unwrap_or(URL_MAX_AGE)
+    }
+}
+
+/// Whether `url` stops working within `within`, give or take [`URL_MARGIN`]. Stream urls
+/// that weren't fetched by the player or were rejected count as expiring.
+pub fn url_expires_within(url: &str, within: Duration) -> bool {
+    TRACK_URLS
+        .lock()
+        .expect("failed to lock track urls")
+        .expires_within(url, within)
+}
+
+/// How long a url fetched now works for, going by the last one fetched.
+pub fn url_lifetime() -> Duration {
+    TRACK_URLS
+        .lock()
+        .expect("failed to lock track urls")
+        .lifetime()
+}
+
+/// Stop trusting `url`, e.g. after the service refused it.
+pub fn forget_url(url: &str) {
+    TRACK_URLS
+        .lock()
+        .expect("failed to lock track urls")
+        .urls
+        .remove(url);
+}
+
+/// Fetch a track url, at `quality` when one was chosen for the session.
+/// Falls back to the track cache when the service can't provide one. Going offline or
+/// the account's limit changing is told through `notifier`.
+async fn fetch_track_url(
+    service: &dyn MusicService,
+    quality: Option<&AudioQuality>,
+    track_id: i32,
+    notifier: Option<&NotificationSender>,
+) -> Option<FetchedUrl> {
+    if let Some(library) = local::library().filter(|_| local::is_local_track(track_id)) {
+        return library
+            .track_url(track_id)
+            .await
+            .map(|url| FetchedUrl::new(url.url));
+    }
+
+    if cache::cached_only() {
+        return cache::cached_url(track_id).await.map(FetchedUrl::new);
+    }
+
+    let wanted = quality.cloned().unwrap_or_else(|| service.quality());
+
+    if let Some(cached) = cache::cached_url_at(track_id, &wanted).await {
+        debug!(track_id, "playing the cached copy instead of streaming");
+        return Some(FetchedUrl::new(cached));
+    }
+
+    let url = match quality {
+        Some(quality) => {
+            service
+                .track_url_with_quality(track_id, quality.clone())
+                .await
+        }
+        None => service.track_url(track_id).await,
+    };
+
+    if let Some(url) = url {
+        notify(notifier, cache::set_offline(false)).await;
+        notify(notifier, account::record(&url)).await;
+        TRACK_URLS
+            .lock()
+            .expect("failed to lock track urls")
+            .record(&url.url);
+        cache::expect_stream(&url.url, track_id, &url.delivered);
+
+        let fallback = ((url.delivered.clone() as u32) < (url.requested.clone() as u32))
+            .then_some(url.delivered);
+
+        return Some(FetchedUrl {
+            url: url.url,
+            fallback,
+        });
+    }
+
+    let cached = cache::cached_url(track_id).await;
+
+    if cached.is_some() {
+        notify(notifier, cache::set_offline(true)).await;
+    }
+
+    cached.map(FetchedUrl::new)
+}
+
+async fn notify(notifier: Option<&NotificationSender>, notification: Option<Notification>) {
+    if let (Some(notifier), Some(notification)) = (notifier, notification) {
+        if let Err(error) = notifier.broadcast(notification).await {
+            debug!(?error);
+        }
+    }
+}
+
 impl PlayerState {
+    #[instrument(skip(self))]
     pub async fn play_album(&mut self, album_id: &str) -> Option<String> {
         debug!("setting up album to play");
+        self.start_queue();
+
+        let album = self.album_service(album_id).album(album_id).await?;
+        let first_track_id = album.tracks.values().next().map(|t| t.id as i32);
+
+        let track_url = match first_track_id {
+            Some(id) => {
+                fetch_track_url(
+                    self.service.as_ref(),
+                    self.stream_quality(),
+                    id,
+                    self.notifier.as_ref(),
+                )
+                .await
+            }
+            None => None,
+        };
+
+        let mut tracklist = TrackListValue::new(Some(&album.tracks));
+        tracklist.set_album(album);
+        tracklist.set_list_type(TrackListType::Album);
+        tracklist.set_track_status(1, TrackStatus::Playing);
+
+        let mut first_url = None;
+
+        if let Some(mut entry) = tracklist.queue.first_entry() {
+            let first_track = entry.get_mut();
+            first_url = track_url.map(|fetched| fetched.attach(first_track));
+
+            self.set_current_track(first_track.clone());
+            self.set_target_status(GstState::Playing);
+        }
+
+        self.replace_list(tracklist);
+
+        // The first track may be blocked, then the album starts from the next one that plays.
+        if first_url.is_none() && first_track_id.is_some() {
+            return self.skip_track(1).await;
+        }
+
+        first_url
+    }
+    /// Play a full album at `quality`, without changing the quality of the session.
+    pub async fn play_album_with_quality(
+        &mut self,
+        album_id: &str,
+        quality: AudioQuality,
+    ) -> Option<String> {
+        self.next_quality = Some(quality);
+
+        self.play_album(album_id).await
+    }
+
+    /// Play the next album, playlist or track at `quality`, `None` for the session's.
+    pub fn set_next_quality(&mut self, quality: Option<AudioQuality>) {
+        self.next_quality = quality;
+    }
+
+    /// A new queue is played, at the quality picked for it if there is one.
+    fn start_queue(&mut self) {
+        self.queue_quality = self.next_quality.take();
+
+        if let Some(quality) = &self.queue_quality {
+            debug!(?quality, "playing at a quality picked for this queue");
+        }
+    }
+
+    /// The quality tracks are fetched at: the one picked for the queue, else the session's.
+    fn stream_quality(&self) -> Option<&AudioQuality> {
+        self.queue_quality.as_ref().or(self.quality.as_ref())
+    }
+
+    /// Queue up a full album but start playing it from `track_id`.
+    pub async fn play_album_from_track(&mut self, album_id: &str, track_id: i32) -> Option<String> {
+        debug!("setting up album to play from track");
+        self.start_queue();
+
+        if let Some(album) = self.album_service(album_id).album(album_id).await {
+            let position = album
+                .tracks
+                .values()
+                .find(|t| t.id as i32 == track_id)
+                .map(|t| t.position)
+                .unwrap_or(1);
 
-        if let Some(album) = self.service.album(album_id).await {
             let mut tracklist = TrackListValue::new(Some(&album.tracks));
             tracklist.set_album(album);
             tracklist.set_list_type(TrackListType::Album);
-            tracklist.set_track_status(1, TrackStatus::Playing);
-
-            self.replace_list(tracklist.clone());
-
-            if let Some(mut entry) = tracklist.queue.first_entry() {
-                let first_track = entry.get_mut();
+            // Marked first, so shuffle keeps it in place and only mixes up what follows.
+            tracklist.set_track_status(position, TrackStatus::Playing);
 
-                self.attach_track_url(first_track).await;
-                self.set_current_track(first_track.clone());
-                self.set_target_status(GstState::Playing);
+            self.replace_list(tracklist);
+            self.set_target_status(GstState::Playing);
 
-                first_track.track_url.clone()
-            } else {
-                None
-            }
+            self.skip_track(position).await
         } else {
             None
         }
     }
     pub async fn play_track(&mut self, track_id: i32) -> Option<String> {
         debug!("setting up track to play");
+        self.start_queue();
+
+        let track = match self.track_service(track_id).track(track_id).await {
+            Some(track) => Some(track),
+            None => cache::cached_track(track_id).await,
+        };
 
-        if let Some(mut track) = self.service.track(track_id).await {
+        if let Some(mut track) = track {
             track.status = TrackStatus::Playing;
             track.number = 1;
 
+            self.attach_track_url(&mut track).await;
+
             let mut queue = BTreeMap::new();
             queue.entry(track.position).or_insert_with(|| track.clone());
 
@@ -109,12 +468,13 @@ impl PlayerState {
             tracklist.set_list_type(TrackListType::Track);
 
             self.replace_list(tracklist.clone());
-
-            self.attach_track_url(&mut track).await;
             self.set_current_track(track.clone());
             self.set_target_status(GstState::Playing);
 
-            track.track_url.clone()
+            match track.track_url.clone() {
+                Some(url) => Some(url),
+                None => self.skip_track(track.position).await,
+            }
         } else {
             None
         }
@@ -122,28 +482,121 @@ impl PlayerState {
     pub async fn play_playlist(&mut self, playlist_id: i64) -> Option<String> {
         debug!("setting up playlist to play");
 
-        if let Some(playlist) = self.service.playlist(playlist_id).await {
-            let mut tracklist = TrackListValue::new(Some(&playlist.tracks));
+        let playlist = match self.service.playlist(playlist_id).await {
+            Some(playlist) => Some(playlist),
+            None => library::cached_playlist(playlist_id).await,
+        };
 
-            tracklist.set_playlist(playlist);
-            tracklist.set_list_type(TrackListType::Playlist);
-            tracklist.set_track_status(1, TrackStatus::Playing);
+        self.play_loaded_playlist(playlist?).await
+    }
 
-            self.replace_list(tracklist.clone());
+    /// Play one of the playlists the service made for the user.
+    pub async fn play_personal_playlist(&mut self, kind: PersonalPlaylist) -> Option<String> {
+        debug!(?kind, "setting up personal playlist to play");
 
-            if let Some(mut entry) = tracklist.queue.first_entry() {
-                let first_track = entry.get_mut();
+        let playlist = self.service.personal_playlist(kind).await?;
 
-                self.attach_track_url(first_track).await;
-                self.set_current_track(first_track.clone());
-                self.set_target_status(GstState::Playing);
+        self.play_loaded_playlist(playlist).await
+    }
 
-                first_track.track_url.clone()
-            } else {
-                None
-            }
+    async fn play_loaded_playlist(&mut self, playlist: Playlist) -> Option<String> {
+        self.start_queue();
+
+        let mut tracklist = TrackListValue::new(Some(&playlist.tracks));
+
+        tracklist.set_playlist(playlist);
+        tracklist.set_list_type(TrackListType::Playlist);
+        tracklist.set_track_status(1, TrackStatus::Playing);
+
+        let Some(mut entry) = tracklist.queue.first_entry() else {
+            self.replace_list(tracklist);
+            return None;
+        };
+
+        let first_track = entry.get_mut();
+        self.attach_track_url(first_track).await;
+
+        let first_track = first_track.clone();
+        self.replace_list(tracklist);
+        self.set_current_track(first_track.clone());
+        self.set_target_status(GstState::Playing);
+
+        match first_track.track_url {
+            Some(url) => Some(url),
+            None => self.skip_track(first_track.position).await,
+        }
+    }
+
+    /// Queue up every track in the cache.
+    pub async fn play_cached(&mut self) -> Option<String> {
+        debug!("setting up cached tracks to play");
+        self.start_queue();
+
+        let queue = cache::cached_tracks()
+            .await
+            .into_iter()
+            .enumerate()
+            .map(|(i, cached)| {
+                let position = i as u32 + 1;
+                let mut track = cached.track;
+                track.position = position;
+                track.number = position;
+                track.status = TrackStatus::Unplayed;
+
+                (position, track)
+            })
+            .collect::<BTreeMap<u32, Track>>();
+
+        if queue.is_empty() {
+            return None;
+        }
+
+        let mut tracklist = TrackListValue::new(Some(&queue));
+        tracklist.set_list_type(TrackListType::Track);
+
+        self.replace_list(tracklist);
+        self.set_target_status(GstState::Playing);
+
+        self.skip_track(1).await
+    }
+
+    /// Only play tracks that are in the cache, or go back to playing everything.
+    pub async fn set_cached_only(&mut self, enabled: bool) {
+        cache::set_cached_only(enabled);
+
+        if enabled {
+            self.apply_cache_filter().await;
         } else {
-            None
+            let position = self.current_track_position();
+            let filtered = std::mem::take(&mut self.cache_filtered);
+
+            for t in self.tracklist.queue.values_mut() {
+                if t.position > position
+                    && t.status == TrackStatus::Unplayable
+                    && filtered.contains(&t.id)
+                {
+                    t.status = TrackStatus::Unplayed;
+                }
+            }
+        }
+
+        self.bump_generation();
+    }
+
+    /// Mark the tracks that aren't in the cache as unplayable
+    /// while only cached tracks are played.
+    async fn apply_cache_filter(&mut self) {
+        if !cache::cached_only() {
+            return;
+        }
+
+        let cached = cache::cached_track_ids().await;
+
+        for t in self.tracklist.queue.values_mut() {
+            if t.status == TrackStatus::Unplayed && !cached.contains(&t.id) {
+                t.status = TrackStatus::Unplayable;
+                self.cache_filtered.insert(t.id);
+            }
         }
     }
 
@@ -201,7 +654,57 @@ impl PlayerState {
 
     pub fn replace_list(&mut self, tracklist: TrackListValue) {
         debug!("replacing tracklist");
+        let generation = self.tracklist.generation();
+
         self.tracklist = tracklist;
+        self.tracklist.set_generation(generation + 1);
+        self.cache_filtered.clear();
+
+        if self.shuffle {
+            self.tracklist.shuffle(self.playing_position());
+        }
+    }
+
+    /// The position of the track playing in the queue, 0 when none is.
+    fn playing_position(&self) -> u32 {
+        self.tracklist
+            .current_track()
+            .map(|t| t.position)
+            .unwrap_or_default()
+    }
+
+    pub fn shuffle(&self) -> bool {
+        self.shuffle
+    }
+
+    /// Turn shuffle on or off. The tracks after the current one are shuffled, or put
+    /// back in their original order.
+    pub fn set_shuffle(&mut self, enabled: bool) {
+        self.shuffle = enabled;
+
+        let position = self.playing_position();
+
+        if enabled {
+            self.tracklist.shuffle(position);
+        } else {
+            self.tracklist.unshuffle(position);
+        }
+
+        self.bump_generation();
+    }
+
+    /// The version of the queue, see [`TrackListValue::generation`].
+    pub fn generation(&self) -> u64 {
+        self.tracklist.generation()
+    }
+
+    fn bump_generation(&mut self) {
+        self.tracklist
+            .set_generation(self.tracklist.generation() + 1);
+    }
+
+    pub fn list_progress(&self, position: ClockTime) -> Option<ListProgress> {
+        self.tracklist.progress(position)
     }
 
     pub fn track_list(&self) -> TrackListValue {
@@ -210,6 +713,77 @@ impl PlayerState {
 
     pub fn set_track_status(&mut self, position: u32, status: TrackStatus) {
         self.tracklist.set_track_status(position, status);
+        self.bump_generation();
+    }
+
+    /// Add a track to the end of the queue.
+    pub fn add_track_to_queue(&mut self, track: Track) {
+        self.tracklist.append_tracks(vec![track]);
+        self.bump_generation();
+    }
+
+    /// Add the tracks autoplay picked to the end of the queue, leaving out the ones already
+    /// in it. Returns the tracks that were added.
+    pub fn append_autoplay(&mut self, tracks: Vec<Track>) -> Vec<Track> {
+        let tracks = tracks
+            .into_iter()
+            .filter(|t| {
+                !self
+                    .tracklist
+                    .queue
+                    .values()
+                    .any(|queued| queued.id == t.id)
+            })
+            .collect::<Vec<Track>>();
+
+        if !tracks.is_empty() {
+            self.tracklist.append_tracks(tracks.clone());
+            self.bump_generation();
+        }
+
+        tracks
+    }
+
+    /// Add every track of an album to the end of the queue.
+    pub fn add_album_to_queue(&mut self, mut album: Album) {
+        // The tracks of an album don't know which album they are on, which the queue
+        // needs once it holds tracks from more than one.
+        let tracks = std::mem::take(&mut album.tracks)
+            .into_values()
+            .map(|mut track| {
+                track.cover_art = Some(album.cover_art.clone());
+                track.album = Some(album.clone());
+                track
+            })
+            .collect::<Vec<Track>>();
+
+        self.tracklist.append_tracks(tracks);
+        self.bump_generation();
+    }
+
+    /// Move an upcoming track to another upcoming position.
+    pub fn move_track(&mut self, from: u32, to: u32) -> bool {
+        let current = self.current_track_position();
+
+        if from <= current || to <= current || !self.tracklist.move_track(from, to) {
+            return false;
+        }
+
+        self.bump_generation();
+
+        true
+    }
+
+    /// Remove an upcoming track from the queue.
+    pub fn remove_track(&mut self, position: u32) -> Option<Track> {
+        if position <= self.current_track_position() {
+            return None;
+        }
+
+        let track = self.tracklist.remove_track(position)?;
+        self.bump_generation();
+
+        Some(track)
     }
 
     pub fn target_status(&self) -> GstState {
@@ -220,44 +794,279 @@ impl PlayerState {
         self.target_status = target;
     }
 
+    /// Switch to `quality` for the rest of the session and fetch a new url
+    /// for the current track at that quality.
+    pub async fn switch_quality(&mut self, quality: AudioQuality) -> Option<String> {
+        self.quality = Some(quality);
+        self.queue_quality = None;
+
+        self.refresh_current_url().await
+    }
+
+    /// Fetch a new url for the current track at the session's quality,
+    /// replacing one that may have expired.
+    pub async fn refresh_current_url(&mut self) -> Option<String> {
+        let position = self.current_track()?.position;
+        let quality = self.stream_quality().cloned();
+        let track = self.tracklist.queue.get_mut(&position)?;
+        let url = fetch_track_url(
+            self.service.as_ref(),
+            quality.as_ref(),
+            track.id as i32,
+            self.notifier.as_ref(),
+        )
+        .await?
+        .attach(track);
+
+        self.current_track = Some(track.clone());
+
+        Some(url)
+    }
+
     /// Attach a `TrackURL` to the given track.
     pub async fn attach_track_url(&mut self, track: &mut Track) {
         debug!("fetching track url");
-        if let Some(track_url) = self.service.track_url(track.id as i32).await {
+        if let Some(track_url) = fetch_track_url(
+            self.service.as_ref(),
+            self.stream_quality(),
+            track.id as i32,
+            self.notifier.as_ref(),
+        )
+        .await
+        {
             debug!("attaching url information to track");
-            track.track_url = Some(track_url);
+            track_url.attach(track);
+        }
+    }
+
+    /// Fetch a url for any track, at the session's quality. The returned future
+    /// doesn't borrow the state, so the lock can be released while it runs.
+    pub fn track_url(&self, track_id: u32) -> impl Future<Output = Option<String>> + Send {
+        let service = self.service.clone();
+        let quality = self.stream_quality().cloned();
+        let notifier = self.notifier.clone();
+
+        async move {
+            fetch_track_url(
+                service.as_ref(),
+                quality.as_ref(),
+                track_id as i32,
+                notifier.as_ref(),
+            )
+            .await
+            .map(|fetched| fetched.url)
+        }
+    }
+
+    /// Fetch the url of the track after the current one, which starts in about `starts_in`,
+    /// unless there is one already that works until then. Nothing is fetched while the track
+    /// is further away than a url lasts, it would expire before it's needed all the same.
+    /// Like [`PlayerState::track_url`], the future doesn't borrow the state. Hand the result
+    /// to [`PlayerState::set_prefetched`].
+    pub fn prefetch_next(
+        &self,
+        starts_in: Duration,
+    ) -> Option<impl Future<Output = Option<Prefetched>> + Send> {
+        if starts_in + URL_MARGIN >= url_lifetime() {
+            return None;
+        }
+
+        let next = self
+            .tracklist
+            .next_playable(self.current_track_position())?;
+        let track_id = self.tracklist.queue.get(&next)?.id;
+        let quality = self.stream_quality().cloned();
+
+        if self.prefetched.as_ref().is_some_and(|p| {
+            p.track_id == track_id
+                && p.quality == quality
+                && !url_expires_within(&p.fetched.url, starts_in)
+        }) {
+            return None;
+        }
+
+        let service = self.service.clone();
+        let notifier = self.notifier.clone();
+
+        Some(async move {
+            let fetched = fetch_track_url(
+                service.as_ref(),
+                quality.as_ref(),
+                track_id as i32,
+                notifier.as_ref(),
+            )
+            .await?;
+
+            Some(Prefetched {
+                track_id,
+                quality,
+                fetched,
+            })
+        })
+    }
+
+    /// Keep a prefetched url, if its track is still the next one.
+    pub fn set_prefetched(&mut self, prefetched: Prefetched) {
+        let next = self
+            .tracklist
+            .next_playable(self.current_track_position())
+            .and_then(|position| self.tracklist.queue.get(&position));
+
+        if next.is_some_and(|track| track.id == prefetched.track_id) {
+            debug!(prefetched.track_id, "next track url prefetched");
+            self.prefetched = Some(prefetched);
         }
     }
 
+    /// The prefetched url for the track at `index`, if it's still good to play.
+    fn take_prefetched(&mut self, index: u32) -> Option<FetchedUrl> {
+        let prefetched = self.prefetched.take()?;
+        let track_id = self.tracklist.queue.get(&index)?.id;
+
+        if prefetched.track_id != track_id || prefetched.quality.as_ref() != self.stream_quality() {
+            return None;
+        }
+
+        if url_expires_within(&prefetched.fetched.url, Duration::ZERO) {
+            debug!(
+                track_id,
+                "prefetched url expires too soon, fetching a new one"
+            );
+            return None;
+        }
+
+        transition::record_prefetched();
+
+        Some(prefetched.fetched)
+    }
+
+    /// Skip to the track at `index`. When its url can't be fetched because the service
+    /// no longer offers the track or won't stream it here, it is marked and the following
+    /// tracks are tried.
     pub async fn skip_track(&mut self, index: u32) -> Option<String> {
+        let mut index = index;
+
+        loop {
+            if let Some(url) = self.skip_to(index).await {
+                return Some(url);
+            }
+
+            if !self.refresh_availability(index).await {
+                return None;
+            }
+
+            index = self.tracklist.next_playable(index)?;
+        }
+    }
+
+    /// Look the track at `index` up again after its url couldn't be fetched. Returns true
+    /// when the service answered and the track was marked, as [`TrackStatus::Unplayable`]
+    /// when it can no longer be streamed, or as [`TrackStatus::Unavailable`] when the service
+    /// still offers it but refused the url.
+    async fn refresh_availability(&mut self, index: u32) -> bool {
+        if cache::is_offline() || cache::cached_only() || health::outage().is_some() {
+            return false;
+        }
+
+        let Some(track_id) = self.tracklist.queue.get(&index).map(|t| t.id as i32) else {
+            return false;
+        };
+
+        let Some(fresh) = self.track_service(track_id).track(track_id).await else {
+            return false;
+        };
+
+        let Some(track) = self.tracklist.queue.get_mut(&index) else {
+            return false;
+        };
+
+        if fresh.available {
+            debug!(track_id, "the service refused a url for the track");
+            track.status = TrackStatus::Unavailable;
+        } else {
+            debug!(track_id, "track is no longer streamable");
+            track.available = false;
+            track.status = TrackStatus::Unplayable;
+        }
+
+        self.withdrawn.push(track.clone());
+        self.bump_generation();
+
+        true
+    }
+
+    /// The tracks found to be unavailable since the last call.
+    pub fn take_withdrawn(&mut self) -> Vec<Track> {
+        std::mem::take(&mut self.withdrawn)
+    }
+
+    async fn skip_to(&mut self, index: u32) -> Option<String> {
         let mut track_url = None;
+        let quality = self.stream_quality().cloned();
+        let mut prefetched = self.take_prefetched(index);
 
         for t in self.tracklist.queue.values_mut() {
+            // Tracks the service refused stay marked, unless asked for again.
+            if t.status == TrackStatus::Unavailable && t.position != index {
+                continue;
+            }
+
             match t.position.cmp(&index) {
                 std::cmp::Ordering::Less => {
                     t.status = TrackStatus::Played;
                 }
                 std::cmp::Ordering::Equal => {
-                    if let Some(url) = self.service.track_url(t.id as i32).await {
+                    let fetched = match prefetched.take() {
+                        Some(fetched) => Some(fetched),
+                        None => {
+                            fetch_track_url(
+                                self.service.as_ref(),
+                                quality.as_ref(),
+                                t.id as i32,
+                                self.notifier.as_ref(),
+                            )
+                            .await
+                        }
+                    };
+
+                    if let Some(fetched) = fetched {
                         t.status = TrackStatus::Playing;
-                        t.track_url = Some(url.clone());
-                        track_url = Some(url);
+                        track_url = Some(fetched.attach(t));
                         self.current_track = Some(t.clone());
                     } else {
                         t.status = TrackStatus::Unplayable;
                     }
                 }
                 std::cmp::Ordering::Greater => {
-                    t.status = TrackStatus::Unplayed;
+                    t.status = if t.available {
+                        TrackStatus::Unplayed
+                    } else {
+                        TrackStatus::Unplayable
+                    };
                 }
             }
         }
 
+        self.apply_cache_filter().await;
+        self.bump_generation();
+
         track_url
     }
 
-    pub async fn search_all(&self, query: &str) -> Option<SearchResults> {
-        self.service.search(query).await
+    /// Search the service, with matching local files added after its results.
+    pub async fn search_all(&self, query: &str, limits: SearchLimits) -> Option<SearchResults> {
+        let mut results = self.service.search(query, limits).await;
+
+        if let Some(library) = local::library().filter(|_| self.service.name() != "local") {
+            if let (Some(results), Some(local)) =
+                (results.as_mut(), library.search(query, limits).await)
+            {
+                results.albums.extend(local.albums);
+                results.tracks.extend(local.tracks);
+            }
+        }
+
+        results
     }
 
     pub async fn fetch_artist_albums(&self, artist_id: i32) -> Option<Vec<Album>> {
@@ -267,6 +1076,10 @@ impl PlayerState {
         }
     }
 
+    pub async fn fetch_playlist(&self, playlist_id: i64) -> Option<Playlist> {
+        self.service.playlist(playlist_id).await
+    }
+
     pub async fn fetch_playlist_tracks(&self, playlist_id: i64) -> Option<Vec<Track>> {
         match self.service.playlist(playlist_id).await {
             Some(results) => Some(results.tracks.values().cloned().collect::<Vec<Track>>()),
@@ -274,16 +1087,143 @@ impl PlayerState {
         }
     }
 
+    pub fn reconnect(&self) {
+        self.service.reconnect();
+    }
+
+    pub async fn health_check(&self) -> bool {
+        self.service.health_check().await
+    }
+
     pub async fn fetch_user_playlists(&self) -> Option<Vec<Playlist>> {
         self.service.user_playlists().await
     }
 
+    pub async fn remove_playlist_tracks(
+        &self,
+        playlist_id: u32,
+        playlist_track_ids: Vec<i64>,
+    ) -> Option<Playlist> {
+        self.service
+            .remove_playlist_tracks(playlist_id, playlist_track_ids)
+            .await
+    }
+
+    pub async fn add_playlist_tracks(
+        &self,
+        playlist_id: u32,
+        track_ids: Vec<u32>,
+    ) -> Option<Playlist> {
+        self.service
+            .add_playlist_tracks(playlist_id, track_ids)
+            .await
+    }
+
+    pub async fn create_playlist(&self, name: &str, public: bool) -> Option<Playlist> {
+        self.service.create_playlist(name, public, None).await
+    }
+
+    pub async fn delete_playlist(&self, playlist_id: u32) -> bool {
+        self.service.delete_playlist(playlist_id).await
+    }
+
+    pub async fn move_playlist_track(
+        &self,
+        playlist_id: u32,
+        playlist_track_id: i64,
+        insert_before: usize,
+    ) -> Option<Playlist> {
+        self.service
+            .move_playlist_track(playlist_id, playlist_track_id, insert_before)
+            .await
+    }
+
+    pub async fn fetch_album(&self, album_id: &str) -> Option<Album> {
+        self.album_service(album_id).album(album_id).await
+    }
+
+    pub async fn fetch_albums(&self, album_ids: &[String]) -> Vec<Album> {
+        self.service.albums_by_ids(album_ids).await
+    }
+
+    pub async fn fetch_tracks(&self, track_ids: &[i32]) -> Vec<Track> {
+        self.service.tracks_by_ids(track_ids).await
+    }
+
+    pub fn browse_categories(&self) -> Vec<BrowseCategory> {
+        self.service.browse_categories()
+    }
+
+    pub fn personal_playlists(&self) -> Vec<PersonalPlaylist> {
+        self.service.personal_playlists()
+    }
+
+    pub async fn fetch_personal_playlist(&self, kind: PersonalPlaylist) -> Option<Playlist> {
+        self.service.personal_playlist(kind).await
+    }
+
+    pub async fn fetch_featured_playlists(&self) -> Option<Vec<Playlist>> {
+        self.service.featured_playlists().await
+    }
+
+    pub async fn fetch_genres(&self) -> Vec<Genre> {
+        self.service.genres().await
+    }
+
+    pub async fn fetch_browse_page(
+        &self,
+        category: BrowseCategory,
+        genre: Option<u32>,
+        offset: u32,
+        limit: u32,
+    ) -> Option<BrowsePage> {
+        self.service.browse(category, genre, offset, limit).await
+    }
+
+    pub async fn fetch_favorite_albums(&self) -> Option<Vec<Album>> {
+        self.service.favorite_albums().await
+    }
+
+    pub async fn fetch_favorite_tracks(&self) -> Option<Vec<Track>> {
+        self.service.favorite_tracks().await
+    }
+
+    pub async fn fetch_favorite_artists(&self) -> Option<Vec<Artist>> {
+        self.service.favorite_artists().await
+    }
+
+    pub async fn set_favorite(&self, item: Favorite, favorite: bool) -> bool {
+        self.service.set_favorite(item, favorite).await
+    }
+
+    pub fn service(&self) -> Arc<dyn MusicService> {
+        self.service.clone()
+    }
+
+    /// The service a track is fetched from. Local files can share the queue with tracks of
+    /// the player's service, so they are looked up in the local library.
+    pub fn track_service(&self, track_id: i32) -> Arc<dyn MusicService> {
+        match local::library() {
+            Some(library) if local::is_local_track(track_id) => library,
+            _ => self.service.clone(),
+        }
+    }
+
+    /// The service an album is fetched from, see [`PlayerState::track_service`].
+    pub fn album_service(&self, album_id: &str) -> Arc<dyn MusicService> {
+        match local::library() {
+            Some(library) if local::is_local_album(album_id) => library,
+            _ => self.service.clone(),
+        }
+    }
+
     pub fn quitter(&self) -> BroadcastReceiver<bool> {
         self.quit_sender.subscribe()
     }
 
-    pub fn quit(&self) {
-        executor::block_on(self.persist());
+    /// Save what plays, `position` into the current track, and tell the player to quit.
+    pub fn quit(&self, position: ClockTime) {
+        executor::block_on(self.persist(position));
 
         self.quit_sender
             .send(true)
@@ -292,46 +1232,102 @@ impl PlayerState {
 
     pub fn reset(&mut self) {
         self.tracklist.clear();
+        self.bump_generation();
         self.current_track = None;
         self.status = gstreamer::State::Null;
         self.resume = false;
     }
 
-    pub async fn new(username: Option<&str>, password: Option<&str>) -> Self {
-        let client = Arc::new(
-            qobuz::make_client(username, password)
-                .await
-                .expect("error making client"),
-        );
-
+    /// A stopped player with an empty queue, using the service built from the registry.
+    pub fn new(service: Arc<dyn MusicService>) -> Self {
         let tracklist = TrackListValue::new(None);
         let (quit_sender, _) = tokio::sync::broadcast::channel::<bool>(1);
 
         Self {
             current_track: None,
-            service: client,
+            service,
             tracklist,
             status: gstreamer::State::Null,
             target_status: gstreamer::State::Null,
             resume: false,
+            quality: None,
+            next_quality: None,
+            queue_quality: None,
             quit_sender,
+            withdrawn: Vec::new(),
+            shuffle: false,
+            prefetched: None,
+            cache_filtered: HashSet::new(),
+            notifier: None,
         }
     }
 
-    pub async fn persist(&self) {
+    /// Send what changes while urls are fetched to the listeners of a player.
+    pub fn set_notifier(&mut self, notifier: NotificationSender) {
+        self.notifier = Some(notifier);
+    }
+
+    /// Swap in a service that has signed in since the player started offline, or another
+    /// registered service.
+    pub fn set_service(&mut self, service: Arc<dyn MusicService>) {
+        self.service = service;
+    }
+
+    /// Save what plays to resume it on the next start. Personal playlists can't be loaded
+    /// again, so they aren't saved.
+    pub async fn persist(&self, position: ClockTime) {
+        if self.list_type() == TrackListType::Playlist
+            && self.playlist().is_some_and(Playlist::is_personal)
+        {
+            debug!("not persisting a personal playlist");
+            return;
+        }
+
         debug!("persisting state to database");
         if self.current_track.is_some() {
-            db::persist_state(self.clone()).await;
+            db::persist_state(SavedState::new(self, position)).await;
         }
     }
 
+    /// Save the queue and the position in the current track as `name`, replacing a queue
+    /// saved under that name before. Returns false when nothing is queued.
+    pub async fn save_queue(&self, name: &str, position: ClockTime) -> bool {
+        if self.tracklist.queue.is_empty() {
+            return false;
+        }
+
+        let snapshot = QueueSnapshot {
+            name: name.to_string(),
+            tracklist: self.tracklist.clone(),
+            position: position.mseconds(),
+            saved_at: chrono::Utc::now().timestamp(),
+        };
+
+        db::save_queue_snapshot(&snapshot).await;
+        true
+    }
+
+    /// Replace the queue with a saved one and get the url of the track it was left at.
+    pub async fn load_queue(&mut self, snapshot: QueueSnapshot) -> Option<String> {
+        let index = snapshot
+            .tracklist
+            .current_track()
+            .or_else(|| snapshot.tracklist.queue.values().next())
+            .map(|t| t.position)?;
+
+        self.replace_list(snapshot.tracklist);
+        self.skip_track(index).await
+    }
+
     pub async fn load_last_state(&mut self) -> Option<ClockTime> {
         if let Some(last_state) = db::get_last_state().await {
             let entity_type: TrackListType = last_state.playback_entity_type.as_str().into();
 
             match entity_type {
                 TrackListType::Album => {
-                    if let Some(album) = self.service.album(&last_state.playback_entity_id).await {
+                    let album_id = &last_state.playback_entity_id;
+
+                    if let Some(album) = self.album_service(album_id).album(album_id).await {
                         self.replace_list(TrackListValue::new(Some(&album.tracks)));
                         self.tracklist.set_list_type(TrackListType::Album);
                         self.tracklist.set_album(album);
@@ -372,7 +1368,7 @@ impl PlayerState {
                         .playback_entity_id
                         .parse()
                         .expect("failed to parse track id");
-                    if let Some(mut track) = self.service.track(track_id).await {
+                    if let Some(mut track) = self.track_service(track_id).track(track_id).await {
                         track.status = TrackStatus::Playing;
                         track.number = 1;
 
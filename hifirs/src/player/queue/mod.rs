@@ -1,7 +1,9 @@
 pub mod controls;
 
 use crate::service::{Album, Playlist, Track, TrackStatus};
-use serde::{Deserialize, Serialize, Serializer};
+use gstreamer::ClockTime;
+use rand::seq::SliceRandom;
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
 use std::{collections::BTreeMap, fmt::Display};
 
 #[derive(Debug, Default, Clone, Serialize, Deserialize, PartialEq, Eq)]
@@ -43,14 +45,43 @@ where
     vec_values.serialize(s)
 }
 
+/// Reads the queue back from the list it is serialized as, keyed by position again.
+fn deserialize_btree<'de, D>(d: D) -> Result<BTreeMap<u32, Track>, D::Error>
+where
+    D: Deserializer<'de>,
+{
+    let tracks = Vec::<Track>::deserialize(d)?;
+    Ok(tracks.into_iter().map(|t| (t.position, t)).collect())
+}
+
+/// How far playback is through an album or playlist, e.g. track 5 of 12, 23:10 of 58:44.
+#[derive(Debug, Default, Clone, Copy, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub struct ListProgress {
+    /// The number of the current track in the queue, starting at 1.
+    pub track: u32,
+    pub total_tracks: u32,
+    pub elapsed_seconds: u64,
+    pub duration_seconds: u64,
+}
+
 /// A tracklist is a list of tracks.
 #[derive(Default, Debug, Clone, Serialize, Deserialize, PartialEq)]
 pub struct TrackListValue {
-    #[serde(serialize_with = "serialize_btree")]
+    #[serde(
+        serialize_with = "serialize_btree",
+        deserialize_with = "deserialize_btree"
+    )]
     pub queue: BTreeMap<u32, Track>,
     album: Option<Album>,
     playlist: Option<Playlist>,
     list_type: TrackListType,
+    /// Bumped on every change, so edits made against an older version of the queue can be rejected.
+    #[serde(default)]
+    generation: u64,
+    /// The ids of the shuffled tracks in their original order, `None` while not shuffled.
+    #[serde(default)]
+    unshuffled: Option<Vec<u32>>,
 }
 
 impl TrackListValue {
@@ -61,9 +92,19 @@ impl TrackListValue {
             album: None,
             playlist: None,
             list_type: TrackListType::Unknown,
+            generation: 0,
+            unshuffled: None,
         }
     }
 
+    pub fn generation(&self) -> u64 {
+        self.generation
+    }
+
+    pub fn set_generation(&mut self, generation: u64) {
+        self.generation = generation;
+    }
+
     pub fn total(&self) -> u32 {
         if let Some(album) = &self.album {
             album.total_tracks
@@ -79,6 +120,7 @@ impl TrackListValue {
         self.list_type = TrackListType::Unknown;
         self.album = None;
         self.playlist = None;
+        self.unshuffled = None;
         self.queue.clear();
     }
 
@@ -136,6 +178,137 @@ impl TrackListValue {
         }
     }
 
+    /// Take the track at `position` out of the queue and move the tracks after it up by one.
+    #[instrument(skip(self))]
+    pub fn remove_track(&mut self, position: u32) -> Option<Track> {
+        let removed = self.queue.remove(&position)?;
+
+        for (_, mut track) in self.queue.split_off(&position) {
+            track.position -= 1;
+            self.queue.insert(track.position, track);
+        }
+
+        if let Some(album) = self.album.as_mut() {
+            album.total_tracks = album.total_tracks.saturating_sub(1);
+        }
+        if let Some(playlist) = self.playlist.as_mut() {
+            playlist.tracks_count = playlist.tracks_count.saturating_sub(1);
+        }
+
+        Some(removed)
+    }
+
+    /// Move the track at `from` to `to`, shifting the tracks in between by one.
+    /// Returns false when either position is not in the queue.
+    #[instrument(skip(self))]
+    pub fn move_track(&mut self, from: u32, to: u32) -> bool {
+        if !self.queue.contains_key(&from) || !self.queue.contains_key(&to) {
+            return false;
+        }
+
+        let positions = self.queue.keys().copied().collect::<Vec<u32>>();
+        let mut tracks = std::mem::take(&mut self.queue)
+            .into_values()
+            .collect::<Vec<Track>>();
+
+        let from_index = positions
+            .iter()
+            .position(|p| *p == from)
+            .unwrap_or_default();
+        let to_index = positions.iter().position(|p| *p == to).unwrap_or_default();
+
+        let track = tracks.remove(from_index);
+        tracks.insert(to_index, track);
+
+        for (position, mut track) in positions.into_iter().zip(tracks) {
+            track.position = position;
+            self.queue.insert(position, track);
+        }
+
+        true
+    }
+
+    pub fn is_shuffled(&self) -> bool {
+        self.unshuffled.is_some()
+    }
+
+    /// Put the tracks after `position` in random order. Their original order is kept
+    /// until [`TrackListValue::unshuffle`], so shuffling again doesn't lose it.
+    #[instrument(skip(self))]
+    pub fn shuffle(&mut self, position: u32) {
+        let mut tracks = self
+            .queue
+            .split_off(&(position + 1))
+            .into_values()
+            .collect::<Vec<Track>>();
+
+        if self.unshuffled.is_none() {
+            self.unshuffled = Some(tracks.iter().map(|t| t.id).collect());
+        }
+
+        tracks.shuffle(&mut rand::thread_rng());
+        self.insert_from(position + 1, tracks);
+    }
+
+    /// Put the tracks after `position` back in the order they had before shuffling.
+    /// Tracks added since then keep their order and go last.
+    #[instrument(skip(self))]
+    pub fn unshuffle(&mut self, position: u32) {
+        let Some(order) = self.unshuffled.take() else {
+            return;
+        };
+
+        let mut tracks = self
+            .queue
+            .split_off(&(position + 1))
+            .into_values()
+            .collect::<Vec<Track>>();
+
+        tracks.sort_by_key(|track| {
+            order
+                .iter()
+                .position(|id| *id == track.id)
+                .unwrap_or(order.len())
+        });
+        self.insert_from(position + 1, tracks);
+    }
+
+    fn insert_from(&mut self, position: u32, tracks: Vec<Track>) {
+        for (position, mut track) in (position..).zip(tracks) {
+            track.position = position;
+            self.queue.insert(position, track);
+        }
+    }
+
+    /// Add `tracks` after the last track in the queue. Returns how many were added.
+    #[instrument(skip(self, tracks))]
+    pub fn append_tracks(&mut self, tracks: Vec<Track>) -> u32 {
+        let mut position = self.queue.keys().last().copied().unwrap_or(0);
+        let added = tracks.len() as u32;
+
+        for mut track in tracks {
+            position += 1;
+            track.position = position;
+            track.track_url = None;
+            track.fallback_quality = None;
+
+            if !track.status.is_skipped() {
+                track.status = TrackStatus::Unplayed;
+            }
+
+            self.queue.insert(position, track);
+        }
+
+        if let Some(album) = self.album.as_mut() {
+            album.total_tracks += added;
+        }
+        if let Some(playlist) = self.playlist.as_mut() {
+            playlist.tracks_count += added;
+        }
+
+        added
+    }
+
     #[instrument(skip(self))]
     pub fn all_tracks(&self) -> Vec<&Track> {
         self.queue.values().collect::<Vec<&Track>>()
@@ -182,16 +355,50 @@ impl TrackListValue {
         index
     }
 
+    /// The position of the first track after `position` that can be played.
+    #[instrument(skip(self))]
+    pub fn next_playable(&self, position: u32) -> Option<u32> {
+        self.queue
+            .range(position + 1..)
+            .find(|(_, t)| !t.status.is_skipped())
+            .map(|(i, _)| *i)
+    }
+
     pub fn current_track(&self) -> Option<&Track> {
         self.queue
             .values()
             .find(|&track| track.status == TrackStatus::Playing)
     }
 
-    pub fn cursive_list(&self) -> Vec<(&str, i32)> {
-        self.queue
+    /// Progress through the whole list at `position` in the current track.
+    /// `None` for single tracks or when nothing is playing.
+    pub fn progress(&self, position: ClockTime) -> Option<ListProgress> {
+        if !matches!(
+            self.list_type,
+            TrackListType::Album | TrackListType::Playlist
+        ) {
+            return None;
+        }
+
+        let index = self
+            .queue
             .values()
-            .map(|i| (i.title.as_str(), i.id as i32))
-            .collect::<Vec<(&str, i32)>>()
+            .position(|t| t.status == TrackStatus::Playing)?;
+
+        let durations = self
+            .queue
+            .values()
+            .map(|t| t.duration_seconds as u64)
+            .collect::<Vec<u64>>();
+
+        let elapsed =
+            durations[..index].iter().sum::<u64>() + position.seconds().min(durations[index]);
+
+        Some(ListProgress {
+            track: index as u32 + 1,
+            total_tracks: self.queue.len() as u32,
+            elapsed_seconds: elapsed,
+            duration_seconds: durations.iter().sum(),
+        })
     }
 }
@@ -0,0 +1,100 @@
+use once_cell::sync::Lazy;
+use std::{collections::HashMap, sync::Mutex, time::Duration};
+
+pub const DEFAULT_MAX_RETRIES: u32 = 3;
+pub const DEFAULT_RETRY_BACKOFF_MS: u64 = 1000;
+
+static POLICY: Lazy<Mutex<RecoveryPolicy>> = Lazy::new(|| Mutex::new(RecoveryPolicy::default()));
+static FAILURES: Lazy<Mutex<SessionFailures>> =
+    Lazy::new(|| Mutex::new(SessionFailures::default()));
+
+/// How the player recovers from playback errors.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct RecoveryPolicy {
+    /// How many times a track is retried before it is skipped.
+    pub max_retries: u32,
+    /// Delay before the first retry, doubled for every retry after that.
+    pub backoff_ms: u64,
+}
+
+impl Default for RecoveryPolicy {
+    fn default() -> Self {
+        Self {
+            max_retries: DEFAULT_MAX_RETRIES,
+            backoff_ms: DEFAULT_RETRY_BACKOFF_MS,
+        }
+    }
+}
+
+impl RecoveryPolicy {
+    /// Time to wait before the given retry attempt, starting at 1.
+    pub fn backoff(&self, attempt: u32) -> Duration {
+        let factor = 2_u64.saturating_pow(attempt.saturating_sub(1));
+        Duration::from_millis(self.backoff_ms.saturating_mul(factor))
+    }
+}
+
+/// What to do after a playback error.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Recovery {
+    /// Retry the current track after waiting.
+    Retry { attempt: u32, delay: Duration },
+    /// Give up on the current track and move on.
+    Abandon { attempts: u32 },
+}
+
+/// Failure counters for the current session.
+#[derive(Debug, Default)]
+struct SessionFailures {
+    tracks: HashMap<u32, u32>,
+    errors: u32,
+    abandoned: u32,
+}
+
+/// Set the policy used for the rest of the session.
+pub fn set_policy(policy: RecoveryPolicy) {
+    *POLICY.lock().unwrap() = policy;
+}
+
+pub fn policy() -> RecoveryPolicy {
+    *POLICY.lock().unwrap()
+}
+
+/// Count a playback error for `track_id` and decide how to recover from it.
+pub fn record_failure(track_id: u32) -> Recovery {
+    let policy = policy();
+    let mut failures = FAILURES.lock().expect("failed to lock failure counters");
+
+    failures.errors += 1;
+
+    let attempts = failures.tracks.entry(track_id).or_default();
+    *attempts += 1;
+    let attempt = *attempts;
+
+    if attempt > policy.max_retries {
+        failures.abandoned += 1;
+
+        Recovery::Abandon { attempts: attempt }
+    } else {
+        Recovery::Retry {
+            attempt,
+            delay: policy.backoff(attempt),
+        }
+    }
+}
+
+/// The track played, it starts over with all of its retries when it fails again.
+pub fn record_success(track_id: u32) {
+    FAILURES
+        .lock()
+        .expect("failed to lock failure counters")
+        .tracks
+        .remove(&track_id);
+}
+
+/// Total errors and abandoned tracks so far this session.
+pub fn totals() -> (u32, u32) {
+    let failures = FAILURES.lock().expect("failed to lock failure counters");
+
+    (failures.errors, failures.abandoned)
+}
@@ -0,0 +1,283 @@
+use crate::{
+    player::{self, notification::Notification},
+    sql::db,
+};
+use chrono::{DateTime, Datelike, Duration as ChronoDuration, Local, Timelike};
+use clap::ValueEnum;
+use futures::StreamExt;
+use serde::{Deserialize, Serialize};
+use std::{fmt::Display, str::FromStr, time::Duration};
+use tokio::select;
+
+/// How often the schedules are checked. Well under a minute, so none is missed.
+const CHECK_INTERVAL: Duration = Duration::from_secs(15);
+/// How far ahead the next start of a schedule is looked for, in minutes.
+const LOOKAHEAD_MINUTES: i64 = 366 * 24 * 60;
+
+/// What a schedule starts playing.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, ValueEnum)]
+#[serde(rename_all = "lowercase")]
+pub enum ScheduleKind {
+    Album,
+    Playlist,
+}
+
+impl Display for ScheduleKind {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ScheduleKind::Album => f.write_str("album"),
+            ScheduleKind::Playlist => f.write_str("playlist"),
+        }
+    }
+}
+
+/// An album or playlist that starts playing whenever the time matches `spec`, e.g. a
+/// wake-up alarm.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Schedule {
+    pub name: String,
+    /// A cron spec: minute, hour, day of month, month and day of week.
+    pub spec: String,
+    pub kind: ScheduleKind,
+    /// The album or playlist id.
+    pub id: String,
+    /// When it last started playback, as a unix timestamp.
+    pub last_run: Option<i64>,
+}
+
+impl Schedule {
+    /// The next time this starts playback after now, in local time.
+    pub fn next_run(&self) -> Option<DateTime<Local>> {
+        let spec = self.spec.parse::<CronSpec>().ok()?;
+        let now = Local::now().with_second(0)?.with_nanosecond(0)?;
+
+        (1..=LOOKAHEAD_MINUTES)
+            .map(|minutes| now + ChronoDuration::minutes(minutes))
+            .find(|time| spec.matches(time))
+    }
+
+    /// Party and simple mode keep listeners from picking what plays, not the schedules
+    /// set up beforehand.
+    async fn start(&self) -> player::Result<()> {
+        match self.kind {
+            ScheduleKind::Album => player::player().start_album(&self.id, None).await,
+            ScheduleKind::Playlist => match self.id.parse::<i64>() {
+                Ok(id) => player::player().start_playlist(id).await,
+                Err(_) => {
+                    warn!(name = self.name, id = self.id, "not a playlist id");
+                    Ok(())
+                }
+            },
+        }
+    }
+}
+
+/// A parsed cron spec. Each field takes `*`, numbers, ranges like `1-5`, steps like `*/15`
+/// and lists of those separated by commas. Day of week counts from Sunday, 0 or 7.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CronSpec {
+    minutes: Vec<u32>,
+    hours: Vec<u32>,
+    days: Vec<u32>,
+    months: Vec<u32>,
+    weekdays: Vec<u32>,
+    /// Whether day of month and day of week were left as `*`. When both are given, either
+    /// one matching is enough, as with cron.
+    any_day: bool,
+    any_weekday: bool,
+}
+
+impl FromStr for CronSpec {
+    type Err = String;
+
+    fn from_str(spec: &str) -> Result<Self, Self::Err> {
+        let fields = spec.split_whitespace().collect::<Vec<&str>>();
+
+        let [minutes, hours, days, months, weekdays] = fields[..] else {
+            return Err(format!(
+                "expected 5 fields (minute hour day month weekday), got {}",
+                fields.len()
+            ));
+        };
+
+        Ok(Self {
+            minutes: parse_field(minutes, 0, 59)?,
+            hours: parse_field(hours, 0, 23)?,
+            days: parse_field(days, 1, 31)?,
+            months: parse_field(months, 1, 12)?,
+            weekdays: parse_field(weekdays, 0, 7)?
+                .into_iter()
+                .map(|day| day % 7)
+                .collect(),
+            any_day: days == "*",
+            any_weekday: weekdays == "*",
+        })
+    }
+}
+
+impl CronSpec {
+    pub fn matches(&self, time: &DateTime<Local>) -> bool {
+        let day = self.days.contains(&time.day());
+        let weekday = self
+            .weekdays
+            .contains(&time.weekday().num_days_from_sunday());
+
+        let day_matches = match (self.any_day, self.any_weekday) {
+            (false, false) => day || weekday,
+            _ => day && weekday,
+        };
+
+        day_matches
+            && self.minutes.contains(&time.minute())
+            && self.hours.contains(&time.hour())
+            && self.months.contains(&time.month())
+    }
+}
+
+fn parse_field(field: &str, min: u32, max: u32) -> Result<Vec<u32>, String> {
+    let mut values = vec![];
+
+    for part in field.split(',') {
+        let (range, step) = match part.split_once('/') {
+            Some((range, step)) => (
+                range,
+                step.parse::<u32>()
+                    .ok()
+                    .filter(|step| *step > 0)
+                    .ok_or_else(|| format!("{part} has an invalid step"))?,
+            ),
+            None => (part, 1),
+        };
+
+        let number = |value: &str| {
+            value
+                .parse::<u32>()
+                .ok()
+                .filter(|value| (min..=max).contains(value))
+                .ok_or_else(|| format!("{value} is not between {min} and {max}"))
+        };
+
+        let (start, end) = match range {
+            "*" => (min, max),
+            range => match range.split_once('-') {
+                Some((start, end)) => (number(start)?, number(end)?),
+                // `5/10` runs from 5 to the end.
+                None if step > 1 => (number(range)?, max),
+                None => (number(range)?, number(range)?),
+            },
+        };
+
+        if start > end {
+            return Err(format!("{range} runs backwards"));
+        }
+
+        values.extend((start..=end).step_by(step as usize));
+    }
+
+    values.sort_unstable();
+    values.dedup();
+
+    Ok(values)
+}
+
+/// Start the schedules whose time has come, until the player quits. Schedules are read
+/// from the database every time, so ones added from the command line are picked up.
+pub async fn run_loop() {
+    let mut receiver = player::notify_receiver();
+    let mut interval = tokio::time::interval(CHECK_INTERVAL);
+
+    loop {
+        select! {
+            _ = interval.tick() => {}
+            Some(notification) = receiver.next() => match notification {
+                Notification::Quit => break,
+                _ => continue,
+            }
+        }
+
+        let Some(now) = Local::now()
+            .with_second(0)
+            .and_then(|now| now.with_nanosecond(0))
+        else {
+            continue;
+        };
+
+        for schedule in db::get_schedules().await {
+            let Ok(spec) = schedule.spec.parse::<CronSpec>() else {
+                continue;
+            };
+
+            // Once for every matching minute.
+            if !spec.matches(&now) || schedule.last_run >= Some(now.timestamp()) {
+                continue;
+            }
+
+            info!(
+                name = schedule.name,
+                kind = %schedule.kind,
+                id = schedule.id,
+                "starting scheduled playback"
+            );
+
+            db::set_schedule_run(&schedule.name, Local::now().timestamp()).await;
+
+            if let Err(error) = schedule.start().await {
+                error!(?error, name = schedule.name, "scheduled playback failed");
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+fn at(day: u32, hour: u32, minute: u32) -> DateTime<Local> {
+    use chrono::TimeZone;
+
+    // February 2024 starts on a Thursday.
+    Local
+        .with_ymd_and_hms(2024, 2, day, hour, minute, 0)
+        .single()
+        .expect("not a local time")
+}
+
+#[test]
+fn parses_steps_from_a_start() {
+    let spec = "5/10 * * * *".parse::<CronSpec>().expect("failed to parse");
+
+    assert_eq!(spec.minutes, vec![5, 15, 25, 35, 45, 55]);
+    assert!(spec.matches(&at(1, 12, 25)));
+    assert!(!spec.matches(&at(1, 12, 20)));
+}
+
+#[test]
+fn counts_sunday_as_0_or_7() {
+    let spec = "0 8 * * 7".parse::<CronSpec>().expect("failed to parse");
+
+    assert_eq!(spec.weekdays, vec![0]);
+    assert!(spec.matches(&at(4, 8, 0)));
+    assert!(!spec.matches(&at(5, 8, 0)));
+}
+
+#[test]
+fn matches_day_of_month_or_day_of_week() {
+    // The 1st, a Thursday, and every Monday.
+    let spec = "0 9 1 * 1".parse::<CronSpec>().expect("failed to parse");
+
+    assert!(spec.matches(&at(1, 9, 0)));
+    assert!(spec.matches(&at(5, 9, 0)));
+    assert!(!spec.matches(&at(6, 9, 0)));
+
+    // With the day of month left open, only Mondays.
+    let spec = "0 9 * * 1".parse::<CronSpec>().expect("failed to parse");
+
+    assert!(!spec.matches(&at(1, 9, 0)));
+    assert!(spec.matches(&at(5, 9, 0)));
+}
+
+#[test]
+fn rejects_malformed_specs() {
+    assert!("* * * *".parse::<CronSpec>().is_err());
+    assert!("60 * * * *".parse::<CronSpec>().is_err());
+    assert!("5-1 * * * *".parse::<CronSpec>().is_err());
+    assert!("*/0 * * * *".parse::<CronSpec>().is_err());
+}
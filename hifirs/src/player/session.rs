@@ -0,0 +1,89 @@
+use crate::{player::recovery, service::Track};
+use once_cell::sync::Lazy;
+use serde::{Deserialize, Serialize};
+use std::sync::Mutex;
+
+/// Lossless streams are FLAC, which usually comes out at about this share of the raw PCM size.
+const FLAC_RATIO: f64 = 0.6;
+/// Bytes per second of a 320 kbps MP3, for tracks without a known bit depth.
+const MP3_BYTES_PER_SECOND: u64 = 40_000;
+
+static SESSION: Lazy<Mutex<Session>> = Lazy::new(|| Mutex::new(Session::default()));
+
+#[derive(Debug, Default)]
+struct Session {
+    stats: SessionStats,
+    bytes_per_second: u64,
+}
+
+/// What was played since hifi-rs started.
+#[derive(Default, Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SessionStats {
+    /// Tracks that started playing.
+    pub tracks_played: u32,
+    pub listening_seconds: u64,
+    /// An estimate from the format of each track, the size of the streams isn't known.
+    pub bytes_streamed: u64,
+    /// Times playback stopped to wait for the stream.
+    pub rebuffers: u32,
+    /// Playback errors, retried or not.
+    pub errors: u32,
+}
+
+/// Roughly how many bytes a second of `track` takes to stream.
+fn bytes_per_second(track: &Track) -> u64 {
+    if track.bit_depth == 0 || track.sampling_rate <= 0. {
+        return MP3_BYTES_PER_SECOND;
+    }
+
+    let pcm = track.bit_depth as f64 / 8. * track.sampling_rate as f64 * 1000. * 2.;
+
+    (pcm * FLAC_RATIO) as u64
+}
+
+/// Count a track that started playing.
+pub fn record_track(track: &Track) {
+    let mut session = SESSION.lock().expect("failed to lock session stats");
+
+    session.stats.tracks_played += 1;
+    session.bytes_per_second = bytes_per_second(track);
+}
+
+/// Count a second of playback of the current track.
+pub fn record_second() {
+    let mut session = SESSION.lock().expect("failed to lock session stats");
+
+    session.stats.listening_seconds += 1;
+    session.stats.bytes_streamed += session.bytes_per_second;
+}
+
+pub fn record_rebuffer() {
+    SESSION
+        .lock()
+        .expect("failed to lock session stats")
+        .stats
+        .rebuffers += 1;
+}
+
+pub fn stats() -> SessionStats {
+    let (errors, _) = recovery::totals();
+
+    SessionStats {
+        errors,
+        ..SESSION.lock().expect("failed to lock session stats").stats
+    }
+}
+
+/// `1.2 GB`, `340 MB` and so on.
+pub fn format_bytes(bytes: u64) -> String {
+    const MB: f64 = 1000. * 1000.;
+
+    let mb = bytes as f64 / MB;
+
+    if mb >= 1000. {
+        format!("{:.1} GB", mb / 1000.)
+    } else {
+        format!("{:.0} MB", mb)
+    }
+}
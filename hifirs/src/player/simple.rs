@@ -0,0 +1,70 @@
+use crate::{
+    config::ConfigKey,
+    player::{error::Error, notification::Notification, Result},
+    sql::db,
+};
+use once_cell::sync::Lazy;
+use std::sync::{
+    atomic::{AtomicBool, Ordering},
+    Mutex,
+};
+
+static ENABLED: AtomicBool = AtomicBool::new(false);
+static PLAYLISTS: Lazy<Mutex<Vec<i64>>> = Lazy::new(|| Mutex::new(Vec::new()));
+
+/// True while simple mode is on. Only play, pause, next and starting one of
+/// the allowed playlists work then.
+pub fn is_enabled() -> bool {
+    ENABLED.load(Ordering::Relaxed)
+}
+
+/// Start in simple mode when the config asks for it.
+pub fn init(enabled: bool, playlists: Vec<i64>) {
+    ENABLED.store(enabled, Ordering::Relaxed);
+    set_playlists(playlists);
+}
+
+/// Set the playlists that can be started in simple mode.
+pub fn set_playlists(playlists: Vec<i64>) {
+    *PLAYLISTS.lock().unwrap() = playlists;
+}
+
+pub fn playlists() -> Vec<i64> {
+    PLAYLISTS.lock().unwrap().clone()
+}
+
+/// Refuse an action that simple mode locks.
+pub fn check() -> Result<()> {
+    if is_enabled() {
+        Err(Error::SimpleMode)
+    } else {
+        Ok(())
+    }
+}
+
+/// Like [`check`], but lets the playlists on the allowlist be started.
+pub fn check_playlist(playlist_id: i64) -> Result<()> {
+    if is_enabled() && !PLAYLISTS.lock().unwrap().contains(&playlist_id) {
+        Err(Error::SimpleMode)
+    } else {
+        Ok(())
+    }
+}
+
+/// Leave simple mode for the rest of the session if `pin` matches the party mode PIN.
+/// Returns the notification for the player to send when it was on.
+pub async fn unlock(pin: &str) -> Result<Option<Notification>> {
+    match db::get_config_value(ConfigKey::PartyPin).await {
+        Some(expected) if expected == pin.trim() => {
+            if !ENABLED.swap(false, Ordering::Relaxed) {
+                return Ok(None);
+            }
+
+            info!("simple mode unlocked");
+
+            Ok(Some(Notification::SimpleMode { enabled: false }))
+        }
+        Some(_) => Err(Error::WrongPin),
+        None => Err(Error::PartyPinMissing),
+    }
+}
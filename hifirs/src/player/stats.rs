@@ -0,0 +1,153 @@
+use crate::player::{self, channels, notification::Notification, playbin};
+use futures::StreamExt;
+use gst::{prelude::*, Element, PadProbeData, PadProbeReturn, PadProbeType};
+use gstreamer as gst;
+use once_cell::sync::Lazy;
+use serde::{Deserialize, Serialize};
+use std::{
+    sync::{
+        atomic::{AtomicU32, AtomicU64, Ordering},
+        Arc, Mutex,
+    },
+    time::Duration,
+};
+use tokio::select;
+
+/// How often the stats are sampled and sent.
+const STATS_INTERVAL: Duration = Duration::from_secs(1);
+
+/// Bytes downloaded from every stream since hifi-rs started.
+static SESSION_BYTES: AtomicU64 = AtomicU64::new(0);
+/// Bytes downloaded of the stream that plays.
+static TRACK_BYTES: Lazy<Mutex<Arc<AtomicU64>>> = Lazy::new(Default::default);
+/// Bytes downloaded of the stream set up last, which is the next track's once the current
+/// one is about to finish. It is counted for the track when it starts.
+static NEXT_TRACK_BYTES: Lazy<Mutex<Option<Arc<AtomicU64>>>> = Lazy::new(Default::default);
+/// The bitrate last reported by the decoder, 0 when it is not known.
+static BITRATE: AtomicU32 = AtomicU32::new(0);
+/// The fill level of the last buffering message, for when the playbin can't be asked.
+static BUFFER_PERCENT: AtomicU32 = AtomicU32::new(100);
+
+/// What the player is streaming right now.
+#[derive(Default, Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct StreamStats {
+    /// Bits per second of the stream, as reported by the decoder.
+    pub bitrate: Option<u32>,
+    /// How full the playback buffer is, in percent.
+    pub buffer_percent: u32,
+    /// Bytes downloaded over the last second.
+    pub download_rate: u64,
+    /// Bytes downloaded of the current track.
+    pub track_bytes: u64,
+    /// Bytes downloaded since hifi-rs started.
+    pub session_bytes: u64,
+}
+
+/// Count what `source` downloads. Called from the playbin's `source-setup`.
+pub fn count_source(source: &Element) {
+    let Some(pad) = source.static_pad("src") else {
+        return;
+    };
+
+    let track_bytes = Arc::new(AtomicU64::new(0));
+    *NEXT_TRACK_BYTES
+        .lock()
+        .expect("failed to lock stream stats") = Some(track_bytes.clone());
+
+    pad.add_probe(PadProbeType::BUFFER, move |_, info| {
+        if let Some(PadProbeData::Buffer(buffer)) = &info.data {
+            let size = buffer.size() as u64;
+
+            track_bytes.fetch_add(size, Ordering::Relaxed);
+            SESSION_BYTES.fetch_add(size, Ordering::Relaxed);
+        }
+
+        PadProbeReturn::Ok
+    });
+}
+
+/// A stream started playing, count the track bytes of the source set up for it. Tracks
+/// played from the cache have none and start at zero.
+pub fn stream_started() {
+    let next = NEXT_TRACK_BYTES
+        .lock()
+        .expect("failed to lock stream stats")
+        .take();
+
+    *TRACK_BYTES.lock().expect("failed to lock stream stats") = next.unwrap_or_default();
+}
+
+/// Note the bitrate from a tag message.
+pub fn record_tags(tags: &gst::TagList) {
+    let bitrate = tags
+        .get::<gst::tags::Bitrate>()
+        .or_else(|| tags.get::<gst::tags::NominalBitrate>())
+        .map(|bitrate| bitrate.get());
+
+    if let Some(bitrate) = bitrate.filter(|b| *b > 0) {
+        BITRATE.store(bitrate, Ordering::Relaxed);
+    }
+}
+
+pub fn record_buffering(percent: i32) {
+    BUFFER_PERCENT.store(percent.clamp(0, 100) as u32, Ordering::Relaxed);
+}
+
+fn buffer_percent() -> u32 {
+    let mut query = gst::query::Buffering::new(gst::Format::Percent);
+
+    if playbin().is_some_and(|playbin| playbin.query(&mut query)) {
+        let (_, percent) = query.percent();
+
+        percent.clamp(0, 100) as u32
+    } else {
+        BUFFER_PERCENT.load(Ordering::Relaxed)
+    }
+}
+
+pub fn stats() -> StreamStats {
+    let bitrate = BITRATE.load(Ordering::Relaxed);
+
+    StreamStats {
+        bitrate: (bitrate > 0).then_some(bitrate),
+        buffer_percent: buffer_percent(),
+        download_rate: 0,
+        track_bytes: TRACK_BYTES
+            .lock()
+            .expect("failed to lock stream stats")
+            .load(Ordering::Relaxed),
+        session_bytes: SESSION_BYTES.load(Ordering::Relaxed),
+    }
+}
+
+/// Send the stream stats every [`STATS_INTERVAL`], until the player quits.
+pub async fn stats_loop() {
+    let mut receiver = player::notify_receiver();
+    let mut interval = tokio::time::interval(STATS_INTERVAL);
+    let mut last_bytes = SESSION_BYTES.load(Ordering::Relaxed);
+
+    loop {
+        select! {
+            _ = interval.tick() => {}
+            Some(notification) = receiver.next() => match notification {
+                Notification::Quit => break,
+                _ => continue,
+            }
+        }
+
+        let stats = stats();
+        let download_rate =
+            stats.session_bytes.saturating_sub(last_bytes) / STATS_INTERVAL.as_secs().max(1);
+        last_bytes = stats.session_bytes;
+
+        let stats = StreamStats {
+            download_rate,
+            ..stats
+        };
+
+        if let Err(error) = channels().tx.broadcast(Notification::Stats { stats }).await {
+            debug!(?error);
+        }
+    }
+}
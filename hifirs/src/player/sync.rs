@@ -0,0 +1,364 @@
+use crate::player::{
+    self,
+    error::Error,
+    notification::Notification,
+    playbin,
+    queue::{TrackListType, TrackListValue},
+    Result, SeekMode,
+};
+use futures::prelude::*;
+use gst::{prelude::*, ClockTime, State as GstState};
+use gstreamer as gst;
+use gstreamer_net as gst_net;
+use serde::{Deserialize, Serialize};
+use std::{net::Ipv4Addr, time::Duration};
+use tokio::{
+    io::{AsyncBufReadExt, AsyncWriteExt, BufReader},
+    net::{TcpListener, TcpStream},
+    select,
+    sync::broadcast::{self, error::RecvError},
+};
+
+/// The port followers connect to. The leader's clock is served on the one after it.
+pub const DEFAULT_SYNC_PORT: u16 = 9890;
+/// How far a follower may drift from the leader before it seeks back in step, when it
+/// stays that far off for two states in a row.
+const TOLERANCE: ClockTime = ClockTime::from_mseconds(50);
+/// How far a follower may drift from the leader before it seeks back in step right away.
+const MAX_DRIFT: ClockTime = ClockTime::from_mseconds(500);
+/// How long a follower waits for a pause or a seek to finish before giving up on it.
+const PREROLL_TIMEOUT: Duration = Duration::from_secs(10);
+/// Time a follower gives itself to buffer the new position before playing from it.
+const START_DELAY: ClockTime = ClockTime::from_seconds(1);
+/// How often the leader tells its followers where it is.
+const SYNC_INTERVAL: Duration = Duration::from_secs(2);
+/// How long a follower waits for its clock to agree with the leader's.
+const CLOCK_SYNC_TIMEOUT: ClockTime = ClockTime::from_seconds(10);
+const RECONNECT_DELAY: Duration = Duration::from_secs(5);
+
+/// What the leader plays, sent to its followers as a line of JSON.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct SyncState {
+    pub source: Option<SyncSource>,
+    /// Queue position of the current track.
+    pub track_position: Option<u32>,
+    pub track_id: Option<u32>,
+    pub playing: bool,
+    /// Position in the current track, in nanoseconds, when the shared clock read `clock`.
+    pub position: u64,
+    pub clock: u64,
+}
+
+/// What the leader's queue was started from, so followers can load the same one.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub enum SyncSource {
+    Album(String),
+    Playlist(i64),
+    Track(i32),
+}
+
+impl SyncSource {
+    fn matches(&self, list: &TrackListValue) -> bool {
+        match (self, list.list_type()) {
+            (SyncSource::Album(id), TrackListType::Album) => {
+                list.get_album().is_some_and(|album| &album.id == id)
+            }
+            (SyncSource::Playlist(id), TrackListType::Playlist) => list
+                .get_playlist()
+                .is_some_and(|playlist| playlist.id as i64 == *id),
+            (SyncSource::Track(id), TrackListType::Track) => list
+                .current_track()
+                .is_some_and(|track| track.id as i32 == *id),
+            _ => false,
+        }
+    }
+
+    async fn play(&self) -> Result<()> {
+        match self {
+            SyncSource::Album(id) => player::play_album(id).await,
+            SyncSource::Playlist(id) => player::play_playlist(*id).await,
+            SyncSource::Track(id) => player::play_track(*id).await,
+        }
+    }
+}
+
+/// Lead multi-room playback. The pipeline runs on the system clock, which is served on
+/// `port + 1`, and followers connecting on `port` are told what plays and where.
+pub async fn lead(port: u16) {
+    let Some(pipeline) = pipeline() else {
+        warn!("synced playback needs the GStreamer backend");
+        return;
+    };
+
+    let clock = gst::SystemClock::obtain();
+    pipeline.use_clock(Some(&clock));
+
+    // Followers slave their clocks to this one for as long as it lives.
+    let _provider = match gst_net::NetTimeProvider::new(&clock, None, port as i32 + 1) {
+        Ok(provider) => provider,
+        Err(error) => {
+            error!(?error, "failed to serve the sync clock");
+            return;
+        }
+    };
+
+    let listener = match TcpListener::bind((Ipv4Addr::UNSPECIFIED, port)).await {
+        Ok(listener) => listener,
+        Err(error) => {
+            error!(?error, port, "failed to listen for followers");
+            return;
+        }
+    };
+
+    info!(port, "leading multi-room playback");
+
+    let (sender, _) = broadcast::channel::<String>(16);
+    tokio::spawn(announce_loop(sender.clone(), clock.clone()));
+
+    while let Ok((stream, address)) = listener.accept().await {
+        info!(%address, "follower joined");
+
+        let receiver = sender.subscribe();
+        let clock = clock.clone();
+
+        tokio::spawn(async move {
+            serve(stream, receiver, &clock).await;
+            info!(%address, "follower left");
+        });
+    }
+}
+
+/// Send the state on every change and every [`SYNC_INTERVAL`], so followers that drift
+/// catch up.
+async fn announce_loop(sender: broadcast::Sender<String>, clock: gst::Clock) {
+    let mut receiver = player::notify_receiver();
+    let mut interval = tokio::time::interval(SYNC_INTERVAL);
+
+    loop {
+        select! {
+            _ = interval.tick() => {}
+            Some(notification) = receiver.next() => match notification {
+                Notification::Status { .. } | Notification::CurrentTrackList { .. } => {}
+                Notification::Quit => break,
+                _ => continue,
+            }
+        }
+
+        // Nobody listening is fine.
+        sender.send(line(&state(&clock).await)).ok();
+    }
+}
+
+async fn serve(
+    mut stream: TcpStream,
+    mut receiver: broadcast::Receiver<String>,
+    clock: &gst::Clock,
+) {
+    if stream
+        .write_all(line(&state(clock).await).as_bytes())
+        .await
+        .is_err()
+    {
+        return;
+    }
+
+    loop {
+        match receiver.recv().await {
+            Ok(line) => {
+                if stream.write_all(line.as_bytes()).await.is_err() {
+                    break;
+                }
+            }
+            Err(RecvError::Lagged(_)) => continue,
+            Err(RecvError::Closed) => break,
+        }
+    }
+}
+
+fn line(state: &SyncState) -> String {
+    let mut line = serde_json::to_string(state).unwrap_or_default();
+    line.push('\n');
+    line
+}
+
+async fn state(clock: &gst::Clock) -> SyncState {
+    let list = player::current_tracklist().await;
+    let track = list.current_track();
+
+    let source = match list.list_type() {
+        TrackListType::Album => list
+            .get_album()
+            .map(|album| SyncSource::Album(album.id.clone())),
+        // Personal playlists have no id to load them by, followers can't play along.
+        TrackListType::Playlist => list
+            .get_playlist()
+            .filter(|playlist| !playlist.is_personal())
+            .map(|playlist| SyncSource::Playlist(playlist.id as i64)),
+        TrackListType::Track => track.map(|track| SyncSource::Track(track.id as i32)),
+        _ => None,
+    };
+
+    SyncState {
+        source,
+        track_position: track.map(|track| track.position),
+        track_id: track.map(|track| track.id),
+        playing: player::is_playing(),
+        position: player::position().unwrap_or_default().nseconds(),
+        clock: clock.time().unwrap_or_default().nseconds(),
+    }
+}
+
+/// Follow the leader at `host`, playing what it plays in step with it. Connections that
+/// drop are retried.
+pub async fn follow(host: String, port: u16) {
+    let Some(pipeline) = pipeline() else {
+        warn!("synced playback needs the GStreamer backend");
+        return;
+    };
+
+    let clock = gst_net::NetClientClock::new(None, &host, port as i32 + 1, ClockTime::ZERO);
+
+    let waiting = clock.clone();
+    match tokio::task::spawn_blocking(move || waiting.wait_for_sync(Some(CLOCK_SYNC_TIMEOUT))).await
+    {
+        Ok(Ok(_)) => debug!("clock synced with the leader"),
+        _ => warn!(
+            host,
+            "clock not synced with the leader yet, playback may drift"
+        ),
+    }
+
+    let clock = clock.upcast::<gst::Clock>();
+    pipeline.use_clock(Some(&clock));
+    // The base time is set to line up with the leader, the pipeline must not pick its own.
+    pipeline.set_start_time(ClockTime::NONE);
+
+    loop {
+        match TcpStream::connect((host.as_str(), port)).await {
+            Ok(stream) => {
+                info!(host, port, "following");
+
+                let mut lines = BufReader::new(stream).lines();
+                let mut drifting = false;
+
+                while let Ok(Some(line)) = lines.next_line().await {
+                    match serde_json::from_str::<SyncState>(&line) {
+                        Ok(state) => {
+                            if let Err(error) = apply(&state, &clock, &mut drifting).await {
+                                debug!(?error, "failed to follow the leader");
+                            }
+                        }
+                        Err(error) => debug!(?error, "unreadable sync state"),
+                    }
+                }
+
+                info!(host, port, "lost the leader");
+            }
+            Err(error) => debug!(?error, host, port, "leader unreachable"),
+        }
+
+        tokio::time::sleep(RECONNECT_DELAY).await;
+    }
+}
+
+/// Load and play what the leader plays, and seek when drifted further than [`TOLERANCE`]
+/// twice in a row or further than [`MAX_DRIFT`] once. `drifting` holds whether the last
+/// state found the follower out of step, so one late reading doesn't cost a preroll.
+async fn apply(state: &SyncState, clock: &gst::Clock, drifting: &mut bool) -> Result<()> {
+    let Some(source) = &state.source else {
+        return Ok(());
+    };
+
+    let list = player::current_tracklist().await;
+    let current = list.current_track().map(|track| track.id);
+
+    if current != state.track_id {
+        if !source.matches(&list) {
+            source.play().await?;
+        }
+
+        let current = player::current_track().await.map(|track| track.id);
+
+        if let (Some(position), true) = (state.track_position, current != state.track_id) {
+            player::skip(position, true).await?;
+        }
+    }
+
+    if !state.playing {
+        if player::is_playing() {
+            player::pause().await?;
+        }
+
+        return Ok(());
+    }
+
+    let Some(now) = clock.time() else {
+        return Ok(());
+    };
+
+    let expected = ClockTime::from_nseconds(state.position)
+        + now.saturating_sub(ClockTime::from_nseconds(state.clock));
+    let drift = player::position()
+        .map(|position| position.nseconds().abs_diff(expected.nseconds()))
+        .map(ClockTime::from_nseconds);
+
+    match drift {
+        Some(drift) if player::is_playing() && drift <= TOLERANCE => {
+            *drifting = false;
+            Ok(())
+        }
+        Some(drift) if player::is_playing() && drift <= MAX_DRIFT && !*drifting => {
+            *drifting = true;
+            Ok(())
+        }
+        _ => {
+            debug!(?drift, "catching up with the leader");
+            *drifting = false;
+            align(state, clock).await
+        }
+    }
+}
+
+/// Preroll at where the leader will be [`START_DELAY`] from now and start playing right then.
+async fn align(state: &SyncState, clock: &gst::Clock) -> Result<()> {
+    player::pause().await?;
+    wait_for_preroll().await?;
+
+    let start = clock.time().unwrap_or_default() + START_DELAY;
+    let target = ClockTime::from_nseconds(state.position)
+        + start.saturating_sub(ClockTime::from_nseconds(state.clock));
+
+    player::seek(target, SeekMode::Accurate).await?;
+    wait_for_preroll().await?;
+
+    // The first buffer after a flushing seek plays at running time zero, so this is when
+    // `target` is heard.
+    if let Some(pipeline) = pipeline() {
+        pipeline.set_base_time(start);
+    }
+
+    player::set_player_state(GstState::Playing).await
+}
+
+fn pipeline() -> Option<&'static gst::Pipeline> {
+    playbin().and_then(|playbin| playbin.downcast_ref::<gst::Pipeline>())
+}
+
+/// Wait for the pipeline to finish changing state, up to [`PREROLL_TIMEOUT`], as a stream
+/// that stopped coming in never prerolls.
+async fn wait_for_preroll() -> Result<()> {
+    let mut interval = tokio::time::interval(Duration::from_millis(20));
+    let prerolled = async {
+        while pipeline().is_some_and(|pipeline| pipeline.pending_state() != GstState::VoidPending) {
+            interval.tick().await;
+        }
+    };
+
+    tokio::time::timeout(PREROLL_TIMEOUT, prerolled)
+        .await
+        .map_err(|_| Error::GStreamer {
+            message: "timed out waiting for the stream to preroll".to_string(),
+        })
+}
@@ -0,0 +1,105 @@
+use crate::service::Track;
+use clap::ValueEnum;
+use gstreamer::{self as gst, prelude::*, Element};
+use once_cell::sync::Lazy;
+use serde::{Deserialize, Serialize};
+use std::{fmt::Display, sync::Mutex};
+
+/// Time given to the output to renegotiate when a gap is inserted.
+pub const FORMAT_CHANGE_GAP_MS: u64 = 500;
+/// Time to wait before fetching the next track's url a second time.
+pub const PREP_RETRY_BACKOFF_MS: u64 = 750;
+
+static GAPLESS: Lazy<Mutex<GaplessStats>> = Lazy::new(|| Mutex::new(GaplessStats::default()));
+
+/// How often the next track was queued up in time for a gapless transition this session.
+#[derive(Default, Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct GaplessStats {
+    /// Next tracks that were ready before the current one ended.
+    pub prepared: u32,
+    /// Of those, the ones that only worked on the second attempt.
+    pub retried: u32,
+    /// Tracks that started from a url fetched while the previous one played.
+    #[serde(default)]
+    pub prefetched: u32,
+    /// Next tracks that could not be prepared, so playback stopped between tracks.
+    pub failed: u32,
+}
+
+/// What to do when the next track has a different sample rate or bit depth.
+#[derive(Default, Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, ValueEnum)]
+#[serde(rename_all = "lowercase")]
+pub enum FormatChangePolicy {
+    /// Keep playback gapless, the output renegotiates mid-stream.
+    #[default]
+    Gapless,
+    /// Stop between the tracks and insert a short silence so the DAC can switch cleanly.
+    Gap,
+    /// Resample everything to a fixed rate so the output format never changes.
+    Resample,
+}
+
+impl Display for FormatChangePolicy {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            FormatChangePolicy::Gapless => f.write_str("gapless"),
+            FormatChangePolicy::Gap => f.write_str("gap"),
+            FormatChangePolicy::Resample => f.write_str("resample"),
+        }
+    }
+}
+
+/// Count a next track that was prepared, after a retry or not.
+pub fn record_prepared(retried: bool) -> GaplessStats {
+    let mut stats = GAPLESS.lock().expect("failed to lock gapless stats");
+
+    stats.prepared += 1;
+
+    if retried {
+        stats.retried += 1;
+    }
+
+    *stats
+}
+
+/// Count a track that started from a prefetched url.
+pub fn record_prefetched() {
+    GAPLESS
+        .lock()
+        .expect("failed to lock gapless stats")
+        .prefetched += 1;
+}
+
+/// Count a next track that could not be prepared.
+pub fn record_failed() -> GaplessStats {
+    let mut stats = GAPLESS.lock().expect("failed to lock gapless stats");
+
+    stats.failed += 1;
+
+    *stats
+}
+
+pub fn gapless_stats() -> GaplessStats {
+    *GAPLESS.lock().expect("failed to lock gapless stats")
+}
+
+/// Returns true when going from `current` to `next` changes the output format.
+pub fn format_changes(current: &Track, next: &Track) -> bool {
+    current.bit_depth != next.bit_depth
+        || (current.sampling_rate - next.sampling_rate).abs() > f32::EPSILON
+}
+
+/// Builds an audio sink that resamples all audio to `rate` before output.
+pub fn build_resample_sink(rate: u32) -> Option<Element> {
+    let description =
+        format!("audioconvert ! audioresample ! audio/x-raw,rate={rate} ! autoaudiosink");
+
+    match gst::parse::bin_from_description(&description, true) {
+        Ok(bin) => Some(bin.upcast()),
+        Err(error) => {
+            debug!(?error, "failed to build resampling sink");
+            None
+        }
+    }
+}
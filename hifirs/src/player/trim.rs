@@ -0,0 +1,113 @@
+use crate::{player::audition, service::Track, sql::db};
+use clap::ValueEnum;
+use gstreamer::ClockTime;
+use once_cell::sync::Lazy;
+use serde::{Deserialize, Serialize};
+use std::{fmt::Display, sync::Mutex};
+
+static ACTIVE: Lazy<Mutex<Option<ActiveTrim>>> = Lazy::new(|| Mutex::new(None));
+
+/// Whether a trim applies to a single track or to every track on an album.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, ValueEnum)]
+#[serde(rename_all = "lowercase")]
+pub enum TrimKind {
+    Track,
+    Album,
+}
+
+impl Display for TrimKind {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            TrimKind::Track => f.write_str("track"),
+            TrimKind::Album => f.write_str("album"),
+        }
+    }
+}
+
+/// Seconds to skip at the start of a track and to cut from its end.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Trim {
+    pub kind: TrimKind,
+    /// The track or album id.
+    pub id: String,
+    pub start_secs: u64,
+    pub end_secs: u64,
+}
+
+/// The trim of the track that is playing.
+#[derive(Debug)]
+struct ActiveTrim {
+    track_id: u32,
+    trim: Trim,
+    started: bool,
+}
+
+/// The trim for a track, preferring one set for the track over one set for its album.
+pub async fn trim_for(track: &Track) -> Option<Trim> {
+    if let Some(trim) = db::get_trim(TrimKind::Track, &track.id.to_string()).await {
+        return Some(trim);
+    }
+
+    let album = track.album.as_ref()?;
+
+    db::get_trim(TrimKind::Album, &album.id).await
+}
+
+/// Look up the trim for a track that just started streaming. While auditioning,
+/// only a clip from the middle of the track is played instead.
+pub async fn load(track: &Track) {
+    let trim = match audition::clip(track).await {
+        Some(clip) => Some(clip),
+        None => trim_for(track).await,
+    };
+    debug!(track_id = track.id, ?trim, "loaded trim");
+
+    *ACTIVE.lock().unwrap() = trim.map(|trim| ActiveTrim {
+        track_id: track.id,
+        trim,
+        started: false,
+    });
+}
+
+/// Where the current track should start, the first time this is asked.
+pub fn take_start(track_id: u32) -> Option<ClockTime> {
+    let mut active = ACTIVE.lock().unwrap();
+
+    match active.as_mut() {
+        Some(a) if a.track_id == track_id && !a.started && a.trim.start_secs > 0 => {
+            a.started = true;
+            Some(ClockTime::from_seconds(a.trim.start_secs))
+        }
+        _ => None,
+    }
+}
+
+/// True when the current track stops before the end of its stream.
+pub fn has_end() -> bool {
+    ACTIVE
+        .lock()
+        .unwrap()
+        .as_ref()
+        .is_some_and(|a| a.trim.end_secs > 0)
+}
+
+/// True once playback reaches the trimmed end of the track. Only true once per track.
+pub fn end_reached(position: ClockTime, duration: ClockTime) -> bool {
+    let mut active = ACTIVE.lock().unwrap();
+
+    let reached = active.as_ref().is_some_and(|a| {
+        a.trim.end_secs > 0 && position + ClockTime::from_seconds(a.trim.end_secs) >= duration
+    });
+
+    if reached {
+        *active = None;
+    }
+
+    reached
+}
+
+/// Forget the trim of the current track.
+pub fn clear() {
+    *ACTIVE.lock().unwrap() = None;
+}
@@ -0,0 +1,89 @@
+use clap::ValueEnum;
+use gstreamer::{self as gst, glib, prelude::*, Element, Structure};
+use serde::{Deserialize, Serialize};
+
+/// Number of frequency bands reported by the spectrum element.
+pub const SPECTRUM_BANDS: u32 = 32;
+/// Anything quieter than this is treated as silence.
+pub const SPECTRUM_THRESHOLD: i32 = -80;
+
+/// The kind of visualization displayed in the now playing panel.
+#[derive(Default, Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, ValueEnum)]
+#[serde(rename_all = "camelCase")]
+pub enum Visualization {
+    #[default]
+    None,
+    /// Stereo VU meters driven by the `level` element.
+    Vu,
+    /// A bar spectrum driven by the `spectrum` element.
+    Spectrum,
+}
+
+/// Builds the element that is attached to playbin as the `audio-filter`.
+pub fn build_filter(kind: Visualization, interval_ms: u64) -> Option<Element> {
+    let interval = gst::ClockTime::from_mseconds(interval_ms).nseconds();
+
+    match kind {
+        Visualization::None => None,
+        Visualization::Vu => {
+            let level = gst::ElementFactory::make("level")
+                .property("post-messages", true)
+                .property("interval", interval)
+                .build();
+
+            match level {
+                Ok(level) => Some(level),
+                Err(error) => {
+                    debug!(?error, "level element unavailable");
+                    None
+                }
+            }
+        }
+        Visualization::Spectrum => {
+            let spectrum = gst::ElementFactory::make("spectrum")
+                .property("post-messages", true)
+                .property("message-magnitude", true)
+                .property("interval", interval)
+                .property("bands", SPECTRUM_BANDS)
+                .property("threshold", SPECTRUM_THRESHOLD)
+                .build();
+
+            match spectrum {
+                Ok(spectrum) => Some(spectrum),
+                Err(error) => {
+                    debug!(?error, "spectrum element unavailable");
+                    None
+                }
+            }
+        }
+    }
+}
+
+/// Reads the per channel rms and peak values, in dB, from a `level` message.
+pub fn parse_level(structure: &Structure) -> Option<(Vec<f64>, Vec<f64>)> {
+    let rms = structure.get::<glib::ValueArray>("rms").ok()?;
+    let peak = structure.get::<glib::ValueArray>("peak").ok()?;
+
+    let rms = rms
+        .iter()
+        .filter_map(|v| v.get::<f64>().ok())
+        .collect::<Vec<f64>>();
+    let peak = peak
+        .iter()
+        .filter_map(|v| v.get::<f64>().ok())
+        .collect::<Vec<f64>>();
+
+    Some((rms, peak))
+}
+
+/// Reads the band magnitudes, in dB, from a `spectrum` message.
+pub fn parse_spectrum(structure: &Structure) -> Option<Vec<f32>> {
+    let magnitude = structure.get::<gst::List>("magnitude").ok()?;
+
+    Some(
+        magnitude
+            .iter()
+            .filter_map(|v| v.get::<f32>().ok())
+            .collect::<Vec<f32>>(),
+    )
+}
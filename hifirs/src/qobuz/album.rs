@@ -1,7 +1,10 @@
 use hifirs_qobuz_api::client::album::Album as QobuzAlbum;
 use std::{collections::BTreeMap, str::FromStr};
 
-use crate::service::{Album, Track};
+use crate::{
+    qobuz,
+    service::{Album, Track},
+};
 
 impl From<QobuzAlbum> for Album {
     fn from(value: QobuzAlbum) -> Self {
@@ -48,10 +51,45 @@ impl From<QobuzAlbum> for Album {
             available: value.streamable,
             tracks,
             cover_art: value.image.large,
+            favorited_at: value.favorited_at,
+            streamable_at: value.streamable_at,
+            catchline: value.catchline.as_deref().and_then(qobuz::plain_text),
+            description: value.description.as_deref().and_then(qobuz::plain_text),
+            genre: Some(value.genre.name).filter(|name| !name.is_empty()),
+            version: edition(value.version.as_deref(), value.release_tags.as_deref()),
+            release_date: Some(value.release_date_original).filter(|date| !date.is_empty()),
+            stream_date: Some(value.release_date_stream).filter(|date| !date.is_empty()),
         }
     }
 }
 
+/// The edition of an album: its version when Qobuz has one, otherwise what its release tags say.
+fn edition(version: Option<&str>, release_tags: Option<&[String]>) -> Option<String> {
+    if let Some(version) = version.map(str::trim).filter(|v| !v.is_empty()) {
+        return Some(version.to_string());
+    }
+
+    let hints = release_tags
+        .unwrap_or_default()
+        .iter()
+        .filter_map(|tag| match tag.to_lowercase().as_str() {
+            "remaster" | "remastered" => Some("Remaster"),
+            "deluxe" => Some("Deluxe"),
+            "expanded" => Some("Expanded"),
+            "anniversary" => Some("Anniversary Edition"),
+            "live" => Some("Live"),
+            "mono" => Some("Mono"),
+            _ => None,
+        })
+        .collect::<Vec<&str>>();
+
+    if hints.is_empty() {
+        None
+    } else {
+        Some(hints.join(", "))
+    }
+}
+
 impl From<&QobuzAlbum> for Album {
     fn from(value: &QobuzAlbum) -> Self {
         value.clone().into()
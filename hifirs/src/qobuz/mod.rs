@@ -1,13 +1,28 @@
 use crate::{
-    service::{Album, Artist, MusicService, Playlist, SearchResults, Track},
-    sql::db::{self},
+    i18n::{self, Language},
+    service::{
+        self, Album, Artist, BrowseCategory, BrowsePage, Credentials, Favorite, Genre,
+        MusicService, Playlist, SearchLimits, SearchResults, SearchType, ServiceUri, StreamUrl,
+        Track,
+    },
+    sql::db::{self, MetadataKind},
 };
 use async_trait::async_trait;
+use cached::{Cached, TimedSizedCache};
+use clap::ValueEnum;
+use futures::{future::BoxFuture, FutureExt, StreamExt};
 use hifirs_qobuz_api::client::{
+    self,
+    album::FeaturedAlbumType,
     api::{self, Client as QobuzClient},
+    playlist::DynamicListType,
     search_results::SearchAllResults,
-    AudioQuality,
+    AudioQuality, UrlType,
 };
+use once_cell::sync::Lazy;
+use rand::seq::SliceRandom;
+use std::sync::{Arc, Mutex};
+use tokio::sync::watch;
 
 pub type Result<T, E = hifirs_qobuz_api::Error> = std::result::Result<T, E>;
 
@@ -16,15 +31,144 @@ pub mod artist;
 pub mod playlist;
 pub mod track;
 
+/// How many albums or tracks of a batch are fetched at once.
+const CONCURRENT_FETCHES: usize = 6;
+/// Artists similar to the seed artist that radio tracks are picked from.
+const RADIO_ARTISTS: i32 = 8;
+/// Tracks searched per artist for radio, of which the most popular few are kept.
+const RADIO_SEARCH_LIMIT: i32 = 25;
+const RADIO_TRACKS_PER_ARTIST: usize = 3;
+/// How long albums and tracks fetched in a batch are kept, in seconds.
+const BATCH_CACHE_LIFESPAN: u64 = 600;
+/// How long albums and tracks are answered from the database, in seconds. They rarely
+/// change once released. Only what can be streamed is kept, in here and in the batch caches,
+/// and a track withdrawn since is caught by the check before it plays.
+const METADATA_MAX_AGE: i64 = 3 * 24 * 60 * 60;
+/// How long playlists are answered from the database, in seconds, as their owners may change
+/// them anywhere. The user's own changes made here drop them right away.
+const PLAYLIST_MAX_AGE: i64 = 15 * 60;
+
+static ALBUMS: Lazy<Mutex<TimedSizedCache<String, Album>>> = Lazy::new(|| {
+    Mutex::new(TimedSizedCache::with_size_and_lifespan(
+        200,
+        BATCH_CACHE_LIFESPAN,
+    ))
+});
+static TRACKS: Lazy<Mutex<TimedSizedCache<i32, Track>>> = Lazy::new(|| {
+    Mutex::new(TimedSizedCache::with_size_and_lifespan(
+        500,
+        BATCH_CACHE_LIFESPAN,
+    ))
+});
+
+const ENTITIES: &[(&str, &str)] = &[
+    ("&nbsp;", " "),
+    ("&quot;", "\""),
+    ("&#39;", "'"),
+    ("&apos;", "'"),
+    ("&lt;", "<"),
+    ("&gt;", ">"),
+    ("&copy;", "©"),
+    ("&copy", "©"),
+    ("&amp;", "&"),
+];
+
+/// Turn the HTML used in Qobuz descriptions into plain text, or `None` if
+/// there is nothing left once the markup is gone.
+pub fn plain_text(html: &str) -> Option<String> {
+    let mut text = String::with_capacity(html.len());
+    let mut tag = String::new();
+    let mut in_tag = false;
+
+    for c in html.chars() {
+        match c {
+            '<' => {
+                in_tag = true;
+                tag.clear();
+            }
+            '>' if in_tag => {
+                in_tag = false;
+
+                let name = tag.trim_start_matches('/').to_lowercase();
+                if name.starts_with("br") || name == "p" {
+                    text.push('\n');
+                }
+            }
+            _ if in_tag => tag.push(c),
+            _ => text.push(c),
+        }
+    }
+
+    for (entity, replacement) in ENTITIES {
+        text = text.replace(entity, replacement);
+    }
+
+    let text = text
+        .lines()
+        .map(str::trim)
+        .collect::<Vec<&str>>()
+        .join("\n")
+        .trim()
+        .to_string();
+
+    if text.is_empty() {
+        None
+    } else {
+        Some(text)
+    }
+}
+
 #[async_trait]
 impl MusicService for QobuzClient {
+    fn name(&self) -> &'static str {
+        "qobuz"
+    }
+
+    /// Web urls and `qobuz://album/<id>` style uris.
+    fn parse_uri(&self, uri: &str) -> Option<ServiceUri> {
+        let url = match uri.strip_prefix("qobuz://") {
+            Some(path) => format!("https://play.qobuz.com/{path}"),
+            None => uri.to_string(),
+        };
+
+        match client::parse_url(&url) {
+            Ok(UrlType::Album { id }) => Some(ServiceUri::Album(id)),
+            Ok(UrlType::Playlist { id }) => Some(ServiceUri::Playlist(id)),
+            Ok(UrlType::Track { id }) => Some(ServiceUri::Track(id)),
+            Err(error) => {
+                debug!(%error, uri, "not a qobuz uri");
+                None
+            }
+        }
+    }
+
     async fn login(&self, username: &str, password: &str) {
         self.login(username, password).await;
     }
 
     async fn album(&self, album_id: &str) -> Option<Album> {
+        // Lists prefetch their albums, so opening one of them is instant.
+        if let Some(album) = ALBUMS.lock().unwrap().cache_get(album_id).cloned() {
+            return Some(album);
+        }
+
+        if let Some(album) =
+            db::get_cached_metadata(MetadataKind::Album, album_id, METADATA_MAX_AGE).await
+        {
+            return Some(album);
+        }
+
         match self.album(album_id).await {
-            Ok(album) => Some(album.into()),
+            Ok(album) => {
+                let album: Album = album.into();
+
+                // Upcoming releases are checked again until they can be streamed.
+                if album.available {
+                    db::set_cached_metadata(MetadataKind::Album, album_id, &album).await;
+                }
+
+                Some(album)
+            }
             Err(err) => {
                 error!("failed to get album: {}", err);
                 None
@@ -39,6 +183,62 @@ impl MusicService for QobuzClient {
         }
     }
 
+    async fn albums_by_ids(&self, album_ids: &[String]) -> Vec<Album> {
+        futures::stream::iter(album_ids)
+            .map(|id| async move {
+                if let Some(album) = ALBUMS.lock().unwrap().cache_get(id).cloned() {
+                    return Some(album);
+                }
+
+                let album = MusicService::album(self, id).await?;
+
+                // Upcoming releases are looked up again, to notice when they come out.
+                if album.available {
+                    ALBUMS.lock().unwrap().cache_set(id.clone(), album.clone());
+                }
+
+                Some(album)
+            })
+            .buffered(CONCURRENT_FETCHES)
+            .filter_map(futures::future::ready)
+            .collect()
+            .await
+    }
+
+    /// Unlike [`MusicService::track`], which is also used to check whether a track can
+    /// still be streamed, only batches are answered from the cache.
+    async fn tracks_by_ids(&self, track_ids: &[i32]) -> Vec<Track> {
+        futures::stream::iter(track_ids)
+            .map(|id| async move {
+                if let Some(track) = TRACKS.lock().unwrap().cache_get(id).cloned() {
+                    return Some(track);
+                }
+
+                let key = id.to_string();
+
+                if let Some(track) =
+                    db::get_cached_metadata::<Track>(MetadataKind::Track, &key, METADATA_MAX_AGE)
+                        .await
+                {
+                    TRACKS.lock().unwrap().cache_set(*id, track.clone());
+                    return Some(track);
+                }
+
+                let track = MusicService::track(self, *id).await?;
+
+                if track.available {
+                    TRACKS.lock().unwrap().cache_set(*id, track.clone());
+                    db::set_cached_metadata(MetadataKind::Track, &key, &track).await;
+                }
+
+                Some(track)
+            })
+            .buffered(CONCURRENT_FETCHES)
+            .filter_map(futures::future::ready)
+            .collect()
+            .await
+    }
+
     async fn artist(&self, artist_id: i32) -> Option<Artist> {
         match self.artist(artist_id, None).await {
             Ok(track) => Some(track.into()),
@@ -46,25 +246,188 @@ impl MusicService for QobuzClient {
         }
     }
 
+    /// The most popular tracks of the seed's artist and of the artists Qobuz finds similar,
+    /// in random order.
+    async fn radio(&self, seed: &Track, limit: u32) -> Vec<Track> {
+        let Some(artist) = &seed.artist else {
+            return Vec::new();
+        };
+
+        let mut artists = vec![(artist.id as i64, artist.name.clone())];
+
+        match self
+            .similar_artists(artist.id as i32, Some(RADIO_ARTISTS))
+            .await
+        {
+            Ok(similar) => {
+                artists.extend(similar.artists.items.into_iter().map(|a| (a.id, a.name)))
+            }
+            Err(error) => debug!(?error, artist.id, "failed to get similar artists"),
+        }
+
+        let mut tracks = Vec::new();
+
+        for (artist_id, name) in artists {
+            let Ok(results) = self.search_tracks(&name, Some(RADIO_SEARCH_LIMIT)).await else {
+                continue;
+            };
+
+            // Searching by name also finds tracks by other artists that mention it.
+            tracks.extend(
+                results
+                    .tracks
+                    .items
+                    .into_iter()
+                    .filter(|t| t.performer.as_ref().is_some_and(|p| p.id == artist_id))
+                    .filter(|t| t.id as u32 != seed.id)
+                    .take(RADIO_TRACKS_PER_ARTIST)
+                    .map(Track::from),
+            );
+        }
+
+        tracks.shuffle(&mut rand::thread_rng());
+        tracks.truncate(limit as usize);
+        tracks
+    }
+
     async fn playlist(&self, playlist_id: i64) -> Option<Playlist> {
+        let key = playlist_id.to_string();
+
+        if let Some(playlist) =
+            db::get_cached_metadata(MetadataKind::Playlist, &key, PLAYLIST_MAX_AGE).await
+        {
+            return Some(playlist);
+        }
+
         match self.playlist(playlist_id).await {
-            Ok(playlist) => Some(playlist.into()),
+            Ok(playlist) => {
+                let playlist: Playlist = playlist.into();
+                db::set_cached_metadata(MetadataKind::Playlist, &key, &playlist).await;
+
+                Some(playlist)
+            }
             Err(_) => None,
         }
     }
 
-    async fn search(&self, query: &str) -> Option<SearchResults> {
-        match self.search_all(query, 100).await {
-            Ok(results) => Some(results.into()),
-            Err(_) => None,
+    async fn search(&self, query: &str, limits: SearchLimits) -> Option<SearchResults> {
+        let mut results = SearchResults {
+            query: query.to_string(),
+            ..Default::default()
+        };
+
+        // Searching a single kind gives it the whole limit instead of sharing it.
+        match limits.search_type() {
+            SearchType::Albums => {
+                results.albums = self
+                    .search_albums(query, Some(limits.albums as i32))
+                    .await
+                    .ok()?
+                    .albums
+                    .items
+                    .into_iter()
+                    .map(|a| a.into())
+                    .collect();
+            }
+            SearchType::Tracks => {
+                results.tracks = self
+                    .search_tracks(query, Some(limits.tracks as i32))
+                    .await
+                    .ok()?
+                    .tracks
+                    .items
+                    .into_iter()
+                    .map(|t| t.into())
+                    .collect();
+            }
+            SearchType::Artists => {
+                results.artists = self
+                    .search_artists(query, Some(limits.artists as i32))
+                    .await
+                    .ok()?
+                    .artists
+                    .items
+                    .into_iter()
+                    .map(|a| a.into())
+                    .collect();
+            }
+            SearchType::Playlists => {
+                results.playlists = self
+                    .search_playlists(query, Some(limits.playlists as i32))
+                    .await
+                    .ok()?
+                    .playlists
+                    .items
+                    .into_iter()
+                    .map(|p| p.into())
+                    .collect();
+            }
+            SearchType::All => {
+                results = self
+                    .search_all(query, limits.max() as i32)
+                    .await
+                    .ok()?
+                    .into();
+
+                results.albums.truncate(limits.albums as usize);
+                results.tracks.truncate(limits.tracks as usize);
+                results.artists.truncate(limits.artists as usize);
+                results.playlists.truncate(limits.playlists as usize);
+            }
         }
+
+        Some(results)
     }
 
-    async fn track_url(&self, track_id: i32) -> Option<String> {
-        match self.track_url(track_id, None, None).await {
-            Ok(track_url) => Some(track_url.url),
-            Err(_) => None,
+    fn quality(&self) -> AudioQuality {
+        self.quality().clone()
+    }
+
+    async fn track_url(&self, track_id: i32) -> Option<StreamUrl> {
+        let quality = self.quality().clone();
+
+        self.track_url_with_quality(track_id, quality).await
+    }
+
+    async fn track_url_with_quality(
+        &self,
+        track_id: i32,
+        quality: AudioQuality,
+    ) -> Option<StreamUrl> {
+        // Tracks that can't be had at a quality are asked for again a step lower each time.
+        let mut attempt = Some(quality.clone());
+
+        while let Some(current) = attempt {
+            match self.track_url(track_id, Some(&current), None).await {
+                Ok(track_url) => {
+                    let delivered = track_url.delivered();
+
+                    if delivered != quality {
+                        info!(
+                            track_id,
+                            requested = ?quality,
+                            ?delivered,
+                            "track is not available at the requested quality"
+                        );
+                    }
+
+                    return Some(StreamUrl {
+                        limited: track_url.limited_by_account(&current),
+                        delivered,
+                        requested: quality,
+                        url: track_url.url,
+                    });
+                }
+                // Lower qualities won't do any better while the service is down.
+                Err(hifirs_qobuz_api::Error::Unavailable { .. }) => return None,
+                Err(error) => {
+                    debug!(track_id, quality = ?current, %error, "no track url at this quality");
+                    attempt = current.lower();
+                }
+            }
         }
+
+        None
     }
 
     async fn user_playlists(&self) -> Option<Vec<Playlist>> {
@@ -79,19 +442,361 @@ impl MusicService for QobuzClient {
             Err(_) => None,
         }
     }
+
+    async fn remove_playlist_tracks(
+        &self,
+        playlist_id: u32,
+        playlist_track_ids: Vec<i64>,
+    ) -> Option<Playlist> {
+        let playlist_track_ids = playlist_track_ids
+            .into_iter()
+            .map(|id| id.to_string())
+            .collect::<Vec<String>>();
+
+        let playlist = match self
+            .playlist_delete_track(playlist_id.to_string(), playlist_track_ids)
+            .await
+        {
+            Ok(playlist) => Some(playlist.into()),
+            Err(error) => {
+                debug!(?error, "failed to remove playlist tracks");
+                None
+            }
+        };
+
+        forget_playlist(playlist_id).await;
+
+        playlist
+    }
+
+    async fn add_playlist_tracks(&self, playlist_id: u32, track_ids: Vec<u32>) -> Option<Playlist> {
+        let track_ids = track_ids
+            .into_iter()
+            .map(|id| id.to_string())
+            .collect::<Vec<String>>();
+
+        let playlist = match self
+            .playlist_add_track(
+                &playlist_id.to_string(),
+                track_ids.iter().map(String::as_str).collect(),
+            )
+            .await
+        {
+            Ok(playlist) => Some(playlist.into()),
+            Err(error) => {
+                debug!(?error, "failed to add playlist tracks");
+                None
+            }
+        };
+
+        forget_playlist(playlist_id).await;
+
+        playlist
+    }
+
+    async fn create_playlist(
+        &self,
+        name: &str,
+        public: bool,
+        description: Option<&str>,
+    ) -> Option<Playlist> {
+        match self
+            .create_playlist(
+                name.to_string(),
+                public,
+                description.map(str::to_string),
+                None,
+            )
+            .await
+        {
+            Ok(playlist) => Some(playlist.into()),
+            Err(error) => {
+                debug!(?error, "failed to create playlist");
+                None
+            }
+        }
+    }
+
+    async fn delete_playlist(&self, playlist_id: u32) -> bool {
+        let deleted = match self.delete_playlist(playlist_id.to_string()).await {
+            Ok(response) => response.is_success(),
+            Err(error) => {
+                debug!(?error, "failed to delete playlist");
+                false
+            }
+        };
+
+        forget_playlist(playlist_id).await;
+
+        deleted
+    }
+
+    async fn move_playlist_track(
+        &self,
+        playlist_id: u32,
+        playlist_track_id: i64,
+        insert_before: usize,
+    ) -> Option<Playlist> {
+        let playlist = match self
+            .playlist_update_position(
+                insert_before,
+                &playlist_id.to_string(),
+                &playlist_track_id.to_string(),
+            )
+            .await
+        {
+            Ok(playlist) => Some(playlist.into()),
+            Err(error) => {
+                debug!(?error, "failed to move playlist track");
+                None
+            }
+        };
+
+        forget_playlist(playlist_id).await;
+
+        playlist
+    }
+
+    async fn favorite_albums(&self) -> Option<Vec<Album>> {
+        match self.favorite_albums().await {
+            Ok(favorites) => Some(
+                favorites
+                    .albums
+                    .map(|albums| albums.items.into_iter().map(|a| a.into()).collect())
+                    .unwrap_or_default(),
+            ),
+            Err(err) => {
+                error!("failed to get favorite albums: {}", err);
+                None
+            }
+        }
+    }
+
+    async fn favorite_artists(&self) -> Option<Vec<Artist>> {
+        match self.favorite_artists().await {
+            Ok(favorites) => Some(
+                favorites
+                    .artists
+                    .map(|artists| artists.items.into_iter().map(|a| a.into()).collect())
+                    .unwrap_or_default(),
+            ),
+            Err(err) => {
+                error!("failed to get favorite artists: {}", err);
+                None
+            }
+        }
+    }
+
+    async fn favorite_tracks(&self) -> Option<Vec<Track>> {
+        match self.favorite_tracks().await {
+            Ok(favorites) => Some(
+                favorites
+                    .tracks
+                    .map(|tracks| tracks.items.into_iter().map(|t| t.into()).collect())
+                    .unwrap_or_default(),
+            ),
+            Err(err) => {
+                error!("failed to get favorite tracks: {}", err);
+                None
+            }
+        }
+    }
+
+    async fn set_favorite(&self, item: Favorite, favorite: bool) -> bool {
+        let response = match (item, favorite) {
+            (Favorite::Album(id), true) => self.add_favorite_album(&id).await,
+            (Favorite::Album(id), false) => self.remove_favorite_album(&id).await,
+            (Favorite::Track(id), true) => self.add_favorite_track(id).await,
+            (Favorite::Track(id), false) => self.remove_favorite_track(id).await,
+            (Favorite::Artist(id), true) => self.add_favorite_artist(id).await,
+            (Favorite::Artist(id), false) => self.remove_favorite_artist(id).await,
+        };
+
+        match response {
+            Ok(response) => response.is_success(),
+            Err(err) => {
+                error!("failed to change favorites: {}", err);
+                false
+            }
+        }
+    }
+
+    fn reconnect(&self) {
+        self.reconnect();
+    }
+
+    async fn health_check(&self) -> bool {
+        self.health_check().await
+    }
+
+    fn browse_categories(&self) -> Vec<BrowseCategory> {
+        BrowseCategory::value_variants().to_vec()
+    }
+
+    fn personal_playlists(&self) -> Vec<PersonalPlaylist> {
+        vec![PersonalPlaylist::Weekly, PersonalPlaylist::Daily]
+    }
+
+    async fn personal_playlist(&self, kind: PersonalPlaylist) -> Option<Playlist> {
+        let list_type = match kind {
+            PersonalPlaylist::Weekly => DynamicListType::Weekly,
+            PersonalPlaylist::Daily => DynamicListType::Daily,
+        };
+
+        match self.dynamic_tracks(list_type, 50).await {
+            Ok(dynamic) => Some(playlist::from_dynamic(kind, dynamic)),
+            Err(err) => {
+                error!("failed to get personal playlist: {}", err);
+                None
+            }
+        }
+    }
+
+    async fn featured_playlists(&self) -> Option<Vec<Playlist>> {
+        match self.featured_playlists(50, 0).await {
+            Ok(featured) => Some(
+                featured
+                    .playlists
+                    .items
+                    .into_iter()
+                    .map(|p| p.into())
+                    .collect::<Vec<Playlist>>(),
+            ),
+            Err(err) => {
+                error!("failed to get featured playlists: {}", err);
+                None
+            }
+        }
+    }
+
+    async fn genres(&self) -> Vec<Genre> {
+        match self.genres(None).await {
+            Ok(list) => list
+                .genres
+                .items
+                .into_iter()
+                .map(|genre| Genre {
+                    id: genre.id as u32,
+                    name: genre.name,
+                })
+                .collect(),
+            Err(err) => {
+                error!("failed to get genres: {}", err);
+                Vec::new()
+            }
+        }
+    }
+
+    async fn browse(
+        &self,
+        category: BrowseCategory,
+        genre: Option<u32>,
+        offset: u32,
+        limit: u32,
+    ) -> Option<BrowsePage> {
+        let featured = match genre {
+            Some(genre) => {
+                self.genre_albums(genre as i64, category.into(), limit as i32, offset as i32)
+                    .await
+            }
+            None => {
+                self.featured_albums(category.into(), limit as i32, offset as i32)
+                    .await
+            }
+        };
+
+        match featured {
+            Ok(featured) => Some(BrowsePage {
+                category,
+                genre,
+                offset: featured.albums.offset as u32,
+                total: featured.albums.total as u32,
+                albums: featured
+                    .albums
+                    .items
+                    .into_iter()
+                    .map(|a| a.into())
+                    .collect::<Vec<Album>>(),
+            }),
+            Err(err) => {
+                error!("failed to get featured albums: {}", err);
+                None
+            }
+        }
+    }
 }
 
-pub async fn make_client(username: Option<&str>, password: Option<&str>) -> Result<QobuzClient> {
+impl From<BrowseCategory> for FeaturedAlbumType {
+    fn from(category: BrowseCategory) -> Self {
+        match category {
+            BrowseCategory::NewReleases => FeaturedAlbumType::NewReleases,
+            BrowseCategory::MostStreamed => FeaturedAlbumType::MostStreamed,
+            BrowseCategory::PressAwards => FeaturedAlbumType::PressAwards,
+            BrowseCategory::EditorPicks => FeaturedAlbumType::EditorPicks,
+            BrowseCategory::BestSellers => FeaturedAlbumType::BestSellers,
+        }
+    }
+}
+
+pub async fn make_client(
+    username: Option<&str>,
+    password: Option<&str>,
+    token: Option<&str>,
+) -> Result<QobuzClient> {
     let mut client = api::new(None, None, None, None).await?;
 
-    setup_client(&mut client, username, password).await
+    setup_client(&mut client, username, password, token).await
 }
 
-/// Setup app_id, secret and user credentials for authentication
+/// Like [`make_client`], but when Qobuz can't be reached the client is returned as far as it
+/// could be set up from the cache, along with `false`. Signing in has to be tried again
+/// once the network is back.
+pub async fn make_client_or_offline(
+    username: Option<&str>,
+    password: Option<&str>,
+    token: Option<&str>,
+) -> Result<(QobuzClient, bool)> {
+    let mut client = api::new(None, None, None, None).await?;
+
+    match setup_client(&mut client, username, password, token).await {
+        Ok(client) => Ok((client, true)),
+        Err(error) if !client.reachable().await => {
+            warn!(%error, "qobuz can't be reached, starting offline");
+            Ok((client, false))
+        }
+        Err(error) => Err(error),
+    }
+}
+
+/// Sets up the client for the service registry, see [`service::register`].
+pub fn build_service(
+    credentials: Credentials,
+) -> BoxFuture<'static, service::Result<(Arc<dyn MusicService>, bool)>> {
+    async move {
+        db::prune_metadata_cache(METADATA_MAX_AGE).await;
+
+        let (client, signed_in) = make_client_or_offline(
+            credentials.username.as_deref(),
+            credentials.password.as_deref(),
+            credentials.token.as_deref(),
+        )
+        .await
+        .map_err(|error| service::Error::Setup {
+            message: error.to_string(),
+        })?;
+
+        Ok((Arc::new(client) as Arc<dyn MusicService>, signed_in))
+    }
+    .boxed()
+}
+
+/// Setup app_id, secret and user credentials for authentication.
+/// A `token` skips the username and password login entirely.
 pub async fn setup_client(
     client: &mut QobuzClient,
     username: Option<&str>,
     password: Option<&str>,
+    token: Option<&str>,
 ) -> Result<QobuzClient> {
     info!("setting up the api client");
 
@@ -104,6 +809,13 @@ pub async fn setup_client(
             client.set_default_quality(quality);
         }
 
+        let language = config
+            .language
+            .and_then(|l| l.parse::<Language>().ok())
+            .unwrap_or_else(i18n::language);
+        client.set_accept_language(language.accept_language().to_string());
+        client.set_http_config(config.http_config());
+
         if let Some(app_id) = config.app_id {
             debug!("using app_id from cache");
             client.set_app_id(app_id);
@@ -132,9 +844,25 @@ pub async fn setup_client(
             }
         }
 
-        if let Some(token) = config.user_token {
+        if let Some(token) = token {
+            info!("setting auth using the provided token");
+            client.login_with_token(token).await?;
+
+            if let Some(id) = client.get_app_id() {
+                db::set_app_id(id).await;
+            }
+
+            if let Some(secret) = client.get_active_secret() {
+                db::set_active_secret(secret).await;
+            }
+        } else if let Some(token) = config.user_token {
             info!("using token from cache");
             client.set_token(token);
+
+            // Lets the client sign in again once the cached token expires.
+            if let (Some(username), Some(password)) = (config.username, config.password) {
+                client.set_credentials(username, password);
+            }
         } else {
             let (username, password): (Option<String>, Option<String>) =
                 if let (Some(u), Some(p)) = (username, password) {
@@ -151,7 +879,7 @@ pub async fn setup_client(
                 client.test_secrets().await?;
 
                 if let Some(token) = client.get_token() {
-                    db::set_user_token(token).await;
+                    db::set_user_token(&token).await;
                 }
 
                 if let Some(secret) = client.get_active_secret() {
@@ -161,9 +889,32 @@ pub async fn setup_client(
         }
     }
 
+    save_renewed_tokens(client.token_changes());
+
     Ok(client.clone())
 }
 
+/// Store the tokens the client gets by signing in again after a 401, so the next start
+/// uses the renewed one.
+fn save_renewed_tokens(mut tokens: watch::Receiver<Option<String>>) {
+    tokio::spawn(async move {
+        while tokens.changed().await.is_ok() {
+            let token = tokens.borrow_and_update().clone();
+
+            if let Some(token) = token {
+                info!("saving the renewed user token");
+                db::set_user_token(&token).await;
+            }
+        }
+    });
+}
+
+/// Drop a playlist from the database after changing it. Only once the change is made, as a
+/// read in the meantime would store the playlist from before it again.
+async fn forget_playlist(playlist_id: u32) {
+    db::remove_cached_metadata(MetadataKind::Playlist, &playlist_id.to_string()).await;
+}
+
 impl From<SearchAllResults> for SearchResults {
     fn from(s: SearchAllResults) -> Self {
         Self {
@@ -1,8 +1,11 @@
 use std::collections::BTreeMap;
 
-use hifirs_qobuz_api::client::playlist::Playlist as QobuzPlaylist;
+use hifirs_qobuz_api::client::playlist::{DynamicTracks, Playlist as QobuzPlaylist};
 
-use crate::service::{Playlist, Track};
+use crate::{
+    qobuz,
+    service::{PersonalPlaylist, Playlist, Track},
+};
 
 impl From<QobuzPlaylist> for Playlist {
     fn from(value: QobuzPlaylist) -> Self {
@@ -46,6 +49,42 @@ impl From<QobuzPlaylist> for Playlist {
             tracks_count: value.tracks_count as u32,
             cover_art,
             tracks,
+            description: qobuz::plain_text(&value.description),
+            updated_at: Some(value.updated_at).filter(|at| *at > 0),
         }
     }
 }
+
+/// A personalized playlist as a playlist of its own. It has no id, as it can't be edited or
+/// fetched by one.
+pub fn from_dynamic(kind: PersonalPlaylist, value: DynamicTracks) -> Playlist {
+    let tracks = value
+        .tracks
+        .items
+        .into_iter()
+        .filter(|t| t.streamable)
+        .enumerate()
+        .map(|(i, t)| {
+            let mut track: Track = t.into();
+            track.position = i as u32 + 1;
+
+            (track.position, track)
+        })
+        .collect::<BTreeMap<u32, Track>>();
+
+    let description = value
+        .description
+        .or(value.baseline)
+        .and_then(|d| qobuz::plain_text(&d));
+
+    Playlist {
+        id: 0,
+        title: value.title.unwrap_or_else(|| kind.title()),
+        duration_seconds: tracks.values().map(|t| t.duration_seconds).sum(),
+        tracks_count: tracks.len() as u32,
+        cover_art: None,
+        tracks,
+        description,
+        updated_at: None,
+    }
+}
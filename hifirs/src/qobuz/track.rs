@@ -44,6 +44,12 @@ impl From<QobuzTrack> for Track {
             position: value.position.unwrap_or(value.track_number as usize) as u32,
             cover_art,
             media_number: value.media_number as u32,
+            url: Some(format!("https://open.qobuz.com/track/{}", value.id)),
+            playlist_track_id: value.playlist_track_id,
+            version: value.version.filter(|v| !v.trim().is_empty()),
+            replay_gain: value.audio_info.replaygain_track_gain,
+            replay_peak: value.audio_info.replaygain_track_peak,
+            fallback_quality: None,
         }
     }
 }
@@ -0,0 +1,151 @@
+use crate::{
+    player::{self, notification::Notification},
+    service::{Album, MusicService},
+    sql::db,
+};
+use futures::StreamExt;
+use serde::{Deserialize, Serialize};
+use std::{collections::HashSet, time::Duration};
+
+/// How often the watched releases are checked against the service.
+const CHECK_INTERVAL: Duration = Duration::from_secs(60 * 60);
+/// Albums that came out up to this many days ago are still on the release calendar.
+const RECENT_DAYS: i64 = 30;
+/// How many artists' albums are fetched at once while refreshing the calendar.
+const CONCURRENT_ARTISTS: usize = 4;
+
+/// An upcoming album the user asked to be notified about.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, sqlx::FromRow)]
+#[serde(rename_all = "camelCase")]
+pub struct WatchedRelease {
+    pub album_id: String,
+    pub title: String,
+    pub artist: String,
+    pub streamable_at: Option<i64>,
+}
+
+impl From<&Album> for WatchedRelease {
+    fn from(album: &Album) -> Self {
+        Self {
+            album_id: album.id.clone(),
+            title: album.title.clone(),
+            artist: album.artist.name.clone(),
+            streamable_at: album.streamable_at,
+        }
+    }
+}
+
+/// Formats a unix timestamp as a local date.
+pub fn format_date(timestamp: i64) -> String {
+    chrono::DateTime::from_timestamp(timestamp, 0)
+        .map(|d| {
+            d.with_timezone(&chrono::Local)
+                .format("%Y-%m-%d")
+                .to_string()
+        })
+        .unwrap_or_default()
+}
+
+/// The day an album can be streamed from, as a unix timestamp.
+pub fn release_timestamp(album: &Album) -> Option<i64> {
+    album.streamable_at.or_else(|| {
+        chrono::NaiveDate::parse_from_str(album.stream_date.as_deref()?, "%Y-%m-%d")
+            .ok()?
+            .and_hms_opt(0, 0, 0)
+            .map(|date| date.and_utc().timestamp())
+    })
+}
+
+/// The release calendar as of the last refresh, oldest release first.
+pub async fn calendar() -> Vec<Album> {
+    db::get_release_calendar().await
+}
+
+/// Collects the upcoming releases and the ones from the last month by every artist the
+/// user follows, which Qobuz calls favorite artists, and replaces the cached calendar.
+/// Returns `None` when the service could not be reached.
+pub async fn refresh_calendar(service: &dyn MusicService) -> Option<Vec<Album>> {
+    let artists = service.favorite_artists().await?;
+    let since = chrono::Utc::now().timestamp() - RECENT_DAYS * 24 * 60 * 60;
+
+    let mut releases = futures::stream::iter(artists)
+        .map(|artist| async move {
+            service
+                .artist(artist.id as i32)
+                .await
+                .and_then(|artist| artist.albums)
+                .unwrap_or_default()
+        })
+        .buffer_unordered(CONCURRENT_ARTISTS)
+        .flat_map(futures::stream::iter)
+        .filter_map(|album| async move {
+            release_timestamp(&album)
+                .filter(|date| *date >= since)
+                .map(|date| (date, album))
+        })
+        .collect::<Vec<(i64, Album)>>()
+        .await;
+
+    // An album by several followed artists shows up once for each of them.
+    let mut seen = HashSet::new();
+    releases.retain(|(_, album)| seen.insert(album.id.clone()));
+    releases.sort_by_key(|(date, _)| *date);
+
+    db::set_release_calendar(&releases).await;
+
+    Some(releases.into_iter().map(|(_, album)| album).collect())
+}
+
+/// Adds an album to the release watch list.
+pub async fn watch(album: &Album) {
+    db::add_watched_release(album.into()).await;
+}
+
+pub async fn watched() -> Vec<WatchedRelease> {
+    db::get_watched_releases().await
+}
+
+/// Checks every watched release whose date has passed and sends a
+/// `ReleaseAvailable` notification for the ones that can now be streamed.
+pub async fn check() {
+    let now = chrono::Utc::now().timestamp();
+
+    let due = watched()
+        .await
+        .into_iter()
+        .filter(|release| !release.streamable_at.is_some_and(|at| at > now))
+        .map(|release| release.album_id)
+        .collect::<Vec<String>>();
+
+    if due.is_empty() {
+        return;
+    }
+
+    for album in player::albums(due).await {
+        if album.available {
+            debug!("watched release {} is now available", album.id);
+            db::remove_watched_release(&album.id).await;
+
+            if let Err(error) = player::notify(Notification::ReleaseAvailable { album }).await {
+                debug!(?error);
+            }
+        }
+    }
+}
+
+/// Periodically checks the watched releases until the player quits.
+pub async fn watch_loop() {
+    let mut interval = tokio::time::interval(CHECK_INTERVAL);
+    let mut receiver = player::notify_receiver();
+
+    loop {
+        tokio::select! {
+            _ = interval.tick() => check().await,
+            Some(notification) = receiver.next() => {
+                if notification == Notification::Quit {
+                    break;
+                }
+            }
+        }
+    }
+}
@@ -0,0 +1,488 @@
+use crate::{
+    config::ConfigKey,
+    play_tracker::{PlayTracker, TrackPlay},
+    player::{self, notification::Notification},
+    service::Track,
+    sql::db,
+};
+use clap::ValueEnum;
+use futures::StreamExt;
+use serde::{Deserialize, Serialize};
+use serde_json::json;
+use snafu::prelude::*;
+use std::{collections::BTreeMap, fmt::Display};
+
+const LASTFM_API_URL: &str = "https://ws.audioscrobbler.com/2.0/";
+const LASTFM_AUTH_URL: &str = "https://www.last.fm/api/auth/";
+const LISTENBRAINZ_API_URL: &str = "https://api.listenbrainz.org/1";
+/// Tracks shorter than this are never scrobbled.
+const MIN_TRACK_SECONDS: u64 = 30;
+/// A track is scrobbled once half of it or this many seconds were played, whichever is first.
+const SCROBBLE_AFTER_SECONDS: u64 = 240;
+
+/// The services plays can be submitted to.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, ValueEnum)]
+#[serde(rename_all = "lowercase")]
+pub enum ScrobbleService {
+    Lastfm,
+    Listenbrainz,
+}
+
+impl Display for ScrobbleService {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ScrobbleService::Lastfm => f.write_str("Last.fm"),
+            ScrobbleService::Listenbrainz => f.write_str("ListenBrainz"),
+        }
+    }
+}
+
+#[derive(Debug, Snafu)]
+pub enum Error {
+    #[snafu(display("{service} request failed: {message}"))]
+    Request {
+        service: ScrobbleService,
+        message: String,
+    },
+    #[snafu(display("{service} refused the request: {message}"))]
+    Refused {
+        service: ScrobbleService,
+        message: String,
+    },
+}
+
+pub type Result<T, E = Error> = std::result::Result<T, E>;
+
+/// What is sent for a play.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Listen {
+    pub artist: String,
+    pub title: String,
+    pub album: Option<String>,
+    /// The length of the track in seconds.
+    pub duration: u64,
+    /// When the track started playing, as a unix timestamp.
+    pub started_at: i64,
+}
+
+impl Listen {
+    fn new(track: &Track, started_at: i64) -> Option<Self> {
+        Some(Self {
+            artist: track.artist.as_ref()?.name.clone(),
+            title: track.title.trim().to_string(),
+            album: track.album.as_ref().map(|a| a.title.clone()),
+            duration: track.duration_seconds as u64,
+            started_at,
+        })
+    }
+
+    /// The listen of `play`, when enough of it was heard to scrobble it.
+    fn heard(play: &TrackPlay) -> Option<Self> {
+        Self::new(&play.track, play.started_at.timestamp())
+            .filter(|listen| listen.counts(play.heard_seconds))
+    }
+
+    /// True once enough of the track was played for a scrobble: half of it, or four minutes.
+    pub fn counts(&self, played: u64) -> bool {
+        self.duration > MIN_TRACK_SECONDS
+            && (played * 2 >= self.duration || played >= SCROBBLE_AFTER_SECONDS)
+    }
+}
+
+/// A Last.fm API account and the session a user gave it.
+#[derive(Debug, Clone)]
+pub struct LastFm {
+    api_key: String,
+    api_secret: String,
+    session: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+struct LastFmToken {
+    token: String,
+}
+
+#[derive(Debug, Deserialize)]
+struct LastFmSessionResponse {
+    session: LastFmSession,
+}
+
+#[derive(Debug, Deserialize)]
+struct LastFmSession {
+    name: String,
+    key: String,
+}
+
+/// Last.fm reports errors in the body, not always with an error status.
+#[derive(Debug, Deserialize)]
+struct LastFmError {
+    error: u32,
+    message: String,
+}
+
+impl LastFm {
+    pub fn new(api_key: String, api_secret: String) -> Self {
+        Self {
+            api_key,
+            api_secret,
+            session: None,
+        }
+    }
+
+    /// A token the user has to allow at `auth_url` before `session` can be called.
+    pub async fn token(&self) -> Result<String> {
+        let response = self
+            .call::<LastFmToken>("auth.getToken", BTreeMap::new())
+            .await?;
+
+        Ok(response.token)
+    }
+
+    pub fn auth_url(&self, token: &str) -> String {
+        format!("{LASTFM_AUTH_URL}?api_key={}&token={token}", self.api_key)
+    }
+
+    /// Trade an allowed token for a session, returning the user name.
+    pub async fn session(&mut self, token: &str) -> Result<String> {
+        let params = BTreeMap::from([("token", token.to_string())]);
+        let response = self
+            .call::<LastFmSessionResponse>("auth.getSession", params)
+            .await?;
+
+        self.session = Some(response.session.key);
+
+        Ok(response.session.name)
+    }
+
+    async fn now_playing(&self, listen: &Listen) -> Result<()> {
+        self.call::<serde_json::Value>("track.updateNowPlaying", self.track_params(listen))
+            .await
+            .map(|_| ())
+    }
+
+    async fn scrobble(&self, listen: &Listen) -> Result<()> {
+        let mut params = self.track_params(listen);
+        params.insert("timestamp", listen.started_at.to_string());
+
+        self.call::<serde_json::Value>("track.scrobble", params)
+            .await
+            .map(|_| ())
+    }
+
+    fn track_params(&self, listen: &Listen) -> BTreeMap<&'static str, String> {
+        let mut params = BTreeMap::from([
+            ("artist", listen.artist.clone()),
+            ("track", listen.title.clone()),
+            ("duration", listen.duration.to_string()),
+        ]);
+
+        if let Some(album) = &listen.album {
+            params.insert("album", album.clone());
+        }
+
+        params
+    }
+
+    /// Make a signed call: the parameters sorted by name, concatenated and hashed with
+    /// the secret.
+    async fn call<T: for<'de> Deserialize<'de>>(
+        &self,
+        method: &'static str,
+        mut params: BTreeMap<&'static str, String>,
+    ) -> Result<T> {
+        params.insert("method", method.to_string());
+        params.insert("api_key", self.api_key.clone());
+
+        if let Some(session) = &self.session {
+            params.insert("sk", session.clone());
+        }
+
+        let signature = params
+            .iter()
+            .fold(String::new(), |signature, (key, value)| {
+                format!("{signature}{key}{value}")
+            });
+        params.insert(
+            "api_sig",
+            format!("{:x}", md5::compute(signature + &self.api_secret)),
+        );
+        params.insert("format", "json".to_string());
+
+        let service = ScrobbleService::Lastfm;
+        let response = reqwest::Client::new()
+            .post(LASTFM_API_URL)
+            .form(&params)
+            .send()
+            .await
+            .map_err(|error| Error::Request {
+                service,
+                message: error.to_string(),
+            })?;
+
+        let status = response.status();
+        let body = response.text().await.map_err(|error| Error::Request {
+            service,
+            message: error.to_string(),
+        })?;
+
+        if let Ok(error) = serde_json::from_str::<LastFmError>(&body) {
+            return Err(Error::Refused {
+                service,
+                message: format!("{} ({})", error.message, error.error),
+            });
+        }
+
+        if !status.is_success() {
+            return Err(Error::Refused {
+                service,
+                message: status.to_string(),
+            });
+        }
+
+        serde_json::from_str(&body).map_err(|error| Error::Refused {
+            service,
+            message: error.to_string(),
+        })
+    }
+}
+
+/// A ListenBrainz user token.
+#[derive(Debug, Clone)]
+pub struct ListenBrainz {
+    token: String,
+}
+
+#[derive(Debug, Deserialize)]
+struct TokenValidation {
+    valid: bool,
+    user_name: Option<String>,
+    message: Option<String>,
+}
+
+impl ListenBrainz {
+    pub fn new(token: String) -> Self {
+        Self { token }
+    }
+
+    /// Check the token, returning the user name it belongs to.
+    pub async fn validate(&self) -> Result<String> {
+        let service = ScrobbleService::Listenbrainz;
+        let validation = reqwest::Client::new()
+            .get(format!("{LISTENBRAINZ_API_URL}/validate-token"))
+            .header("Authorization", format!("Token {}", self.token))
+            .send()
+            .await
+            .and_then(|r| r.error_for_status())
+            .map_err(|error| Error::Request {
+                service,
+                message: error.to_string(),
+            })?
+            .json::<TokenValidation>()
+            .await
+            .map_err(|error| Error::Request {
+                service,
+                message: error.to_string(),
+            })?;
+
+        match (validation.valid, validation.user_name) {
+            (true, Some(user_name)) => Ok(user_name),
+            _ => Err(Error::Refused {
+                service,
+                message: validation
+                    .message
+                    .unwrap_or_else(|| "invalid token".to_string()),
+            }),
+        }
+    }
+
+    async fn now_playing(&self, listen: &Listen) -> Result<()> {
+        self.submit("playing_now", json!({ "track_metadata": metadata(listen) }))
+            .await
+    }
+
+    async fn scrobble(&self, listen: &Listen) -> Result<()> {
+        self.submit(
+            "single",
+            json!({
+                "listened_at": listen.started_at,
+                "track_metadata": metadata(listen),
+            }),
+        )
+        .await
+    }
+
+    async fn submit(&self, listen_type: &str, listen: serde_json::Value) -> Result<()> {
+        let service = ScrobbleService::Listenbrainz;
+
+        reqwest::Client::new()
+            .post(format!("{LISTENBRAINZ_API_URL}/submit-listens"))
+            .header("Authorization", format!("Token {}", self.token))
+            .json(&json!({ "listen_type": listen_type, "payload": [listen] }))
+            .send()
+            .await
+            .map_err(|error| Error::Request {
+                service,
+                message: error.to_string(),
+            })?
+            .error_for_status()
+            .map_err(|error| Error::Refused {
+                service,
+                message: error.to_string(),
+            })?;
+
+        Ok(())
+    }
+}
+
+fn metadata(listen: &Listen) -> serde_json::Value {
+    json!({
+        "artist_name": listen.artist,
+        "track_name": listen.title,
+        "release_name": listen.album,
+        "additional_info": {
+            "duration_ms": listen.duration * 1000,
+            "submission_client": "hifi-rs",
+            "submission_client_version": env!("CARGO_PKG_VERSION"),
+        },
+    })
+}
+
+/// The accounts plays are submitted to, as linked with `hifi-rs scrobble link`.
+#[derive(Debug, Clone, Default)]
+pub struct Scrobblers {
+    pub lastfm: Option<LastFm>,
+    pub listenbrainz: Option<ListenBrainz>,
+}
+
+impl Scrobblers {
+    pub async fn load() -> Self {
+        let lastfm = match (
+            db::get_config_value(ConfigKey::LastfmApiKey).await,
+            db::get_config_value(ConfigKey::LastfmApiSecret).await,
+            db::get_config_value(ConfigKey::LastfmSession).await,
+        ) {
+            (Some(api_key), Some(api_secret), Some(session)) => Some(LastFm {
+                api_key,
+                api_secret,
+                session: Some(session),
+            }),
+            _ => None,
+        };
+
+        let listenbrainz = db::get_config_value(ConfigKey::ListenbrainzToken)
+            .await
+            .map(ListenBrainz::new);
+
+        Self {
+            lastfm,
+            listenbrainz,
+        }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.lastfm.is_none() && self.listenbrainz.is_none()
+    }
+
+    pub fn linked(&self, service: ScrobbleService) -> bool {
+        match service {
+            ScrobbleService::Lastfm => self.lastfm.is_some(),
+            ScrobbleService::Listenbrainz => self.listenbrainz.is_some(),
+        }
+    }
+
+    async fn now_playing(&self, listen: &Listen) {
+        if let Some(lastfm) = &self.lastfm {
+            if let Err(error) = lastfm.now_playing(listen).await {
+                debug!(%error, "failed to update now playing");
+            }
+        }
+
+        if let Some(listenbrainz) = &self.listenbrainz {
+            if let Err(error) = listenbrainz.now_playing(listen).await {
+                debug!(%error, "failed to update now playing");
+            }
+        }
+    }
+
+    async fn scrobble(&self, listen: &Listen) {
+        if let Some(lastfm) = &self.lastfm {
+            if let Err(error) = lastfm.scrobble(listen).await {
+                warn!(%error, "failed to scrobble");
+            }
+        }
+
+        if let Some(listenbrainz) = &self.listenbrainz {
+            if let Err(error) = listenbrainz.scrobble(listen).await {
+                warn!(%error, "failed to scrobble");
+            }
+        }
+    }
+}
+
+/// Save the session of a linked Last.fm account.
+pub async fn link_lastfm(lastfm: &LastFm) {
+    db::set_config_value(ConfigKey::LastfmApiKey, Some(lastfm.api_key.clone())).await;
+    db::set_config_value(ConfigKey::LastfmApiSecret, Some(lastfm.api_secret.clone())).await;
+    db::set_config_value(ConfigKey::LastfmSession, lastfm.session.clone()).await;
+}
+
+pub async fn link_listenbrainz(listenbrainz: &ListenBrainz) {
+    db::set_config_value(
+        ConfigKey::ListenbrainzToken,
+        Some(listenbrainz.token.clone()),
+    )
+    .await;
+}
+
+/// Forget the account, the Last.fm API key is kept for linking again.
+pub async fn unlink(service: ScrobbleService) {
+    match service {
+        ScrobbleService::Lastfm => db::set_config_value(ConfigKey::LastfmSession, None).await,
+        ScrobbleService::Listenbrainz => {
+            db::set_config_value(ConfigKey::ListenbrainzToken, None).await
+        }
+    }
+}
+
+/// Follows the notifications, telling the linked accounts what is playing and scrobbling
+/// each play once enough of it was heard, until the player quits.
+pub async fn scrobble_loop() {
+    let scrobblers = Scrobblers::load().await;
+
+    if scrobblers.is_empty() {
+        return;
+    }
+
+    let mut receiver = player::notify_receiver();
+    let mut tracker = PlayTracker::default();
+
+    while let Some(notification) = receiver.next().await {
+        if let Notification::Quit = notification {
+            if let Some(listen) = tracker.finish().and_then(|play| Listen::heard(&play)) {
+                scrobblers.scrobble(&listen).await;
+            }
+            break;
+        }
+
+        let Some(finished) = tracker.update(&notification) else {
+            continue;
+        };
+
+        let finished = finished.and_then(|play| Listen::heard(&play));
+        let started = tracker
+            .current()
+            .and_then(|play| Listen::new(&play.track, play.started_at.timestamp()));
+
+        // Submitting can be slow, so it doesn't hold up the notifications.
+        let scrobblers = scrobblers.clone();
+
+        tokio::spawn(async move {
+            if let Some(listen) = finished {
+                scrobblers.scrobble(&listen).await;
+            }
+            if let Some(listen) = started {
+                scrobblers.now_playing(&listen).await;
+            }
+        });
+    }
+}
@@ -1,23 +1,292 @@
-use crate::{cursive::CursiveFormat, player::queue::TrackListType};
+use crate::{
+    cursive::{layout, CursiveFormat},
+    local,
+    player::queue::TrackListType,
+    qobuz, releases, t,
+};
 use async_trait::async_trait;
+use clap::ValueEnum;
 use cursive::{
     theme::{Effect, Style},
     utils::markup::StyledString,
 };
+use futures::future::BoxFuture;
 use gstreamer::ClockTime;
+use hifirs_qobuz_api::client::AudioQuality;
+use once_cell::sync::Lazy;
 use serde::{Deserialize, Serialize};
-use std::{collections::BTreeMap, fmt::Debug};
+use snafu::prelude::*;
+use std::{
+    collections::BTreeMap,
+    fmt::Debug,
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Arc, RwLock,
+    },
+};
+
+static EDITION_HINTS: AtomicBool = AtomicBool::new(true);
+
+/// The services the player can use, Qobuz and the local music directory being built in.
+static REGISTRY: Lazy<RwLock<Vec<Registration>>> = Lazy::new(|| {
+    RwLock::new(vec![
+        Registration {
+            name: "qobuz",
+            schemes: &["qobuz"],
+            build: qobuz::build_service,
+        },
+        Registration {
+            name: "local",
+            schemes: &["file"],
+            build: local::build_service,
+        },
+    ])
+});
+
+/// The service used when none is picked with `--service`.
+pub const DEFAULT_SERVICE: &str = "qobuz";
+
+#[derive(Snafu, Debug)]
+pub enum Error {
+    #[snafu(display("no service called {name}, pick one of: {}", registered().join(", ")))]
+    Unknown { name: String },
+    #[snafu(display("{message}"))]
+    Setup { message: String },
+}
+
+pub type Result<T, E = Error> = std::result::Result<T, E>;
+
+/// What a service is given to sign in with.
+#[derive(Debug, Clone, Default)]
+pub struct Credentials {
+    pub username: Option<String>,
+    pub password: Option<String>,
+    pub token: Option<String>,
+}
+
+/// Sets a service up and signs in. Returns the service and whether signing in worked, a
+/// service that can't be reached yet is returned anyway so the player can start offline.
+pub type ServiceBuilder =
+    fn(Credentials) -> BoxFuture<'static, Result<(Arc<dyn MusicService>, bool)>>;
+
+struct Registration {
+    name: &'static str,
+    /// Uris starting with `<scheme>://` are played with this service.
+    schemes: &'static [&'static str],
+    build: ServiceBuilder,
+}
+
+/// Make a service available under `name`, replacing one registered under the same name.
+pub fn register(name: &'static str, schemes: &'static [&'static str], build: ServiceBuilder) {
+    let mut registry = REGISTRY.write().expect("failed to lock service registry");
+    registry.retain(|registration| registration.name != name);
+    registry.push(Registration {
+        name,
+        schemes,
+        build,
+    });
+}
+
+/// The names of the registered services.
+pub fn registered() -> Vec<&'static str> {
+    REGISTRY
+        .read()
+        .expect("failed to lock service registry")
+        .iter()
+        .map(|registration| registration.name)
+        .collect()
+}
+
+/// Set up the service registered under `name`.
+pub async fn build(name: &str, credentials: Credentials) -> Result<(Arc<dyn MusicService>, bool)> {
+    let build = REGISTRY
+        .read()
+        .expect("failed to lock service registry")
+        .iter()
+        .find(|registration| registration.name == name)
+        .map(|registration| registration.build)
+        .context(UnknownSnafu { name })?;
+
+    build(credentials).await
+}
+
+/// The name of the service whose scheme `uri` starts with, e.g. `qobuz://album/123`.
+pub fn for_uri(uri: &str) -> Option<&'static str> {
+    let (scheme, _) = uri.split_once("://")?;
+
+    REGISTRY
+        .read()
+        .expect("failed to lock service registry")
+        .iter()
+        .find(|registration| registration.schemes.contains(&scheme))
+        .map(|registration| registration.name)
+}
+
+/// The item a uri points to.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ServiceUri {
+    Album(String),
+    Playlist(i64),
+    Track(i32),
+}
 
 #[async_trait]
 pub trait MusicService: Send + Sync + Debug {
+    /// The name the service is registered under.
+    fn name(&self) -> &'static str;
+    /// The album, playlist or track behind a uri or web url of the service.
+    fn parse_uri(&self, uri: &str) -> Option<ServiceUri>;
     async fn login(&self, username: &str, password: &str);
     async fn album(&self, album_id: &str) -> Option<Album>;
     async fn track(&self, track_id: i32) -> Option<Track>;
+    /// Fetch several albums at once, in the given order. Albums that could not be
+    /// fetched are left out.
+    async fn albums_by_ids(&self, album_ids: &[String]) -> Vec<Album>;
+    /// Fetch several tracks at once, in the given order. Tracks that could not be
+    /// fetched are left out.
+    async fn tracks_by_ids(&self, track_ids: &[i32]) -> Vec<Track>;
     async fn artist(&self, artist_id: i32) -> Option<Artist>;
+    /// Tracks to keep playing after `seed` once the queue runs out, by its artist and
+    /// similar ones. Fewer than `limit` when there aren't enough.
+    async fn radio(&self, seed: &Track, limit: u32) -> Vec<Track>;
     async fn playlist(&self, playlist_id: i64) -> Option<Playlist>;
-    async fn search(&self, query: &str) -> Option<SearchResults>;
-    async fn track_url(&self, track_id: i32) -> Option<String>;
+    async fn search(&self, query: &str, limits: SearchLimits) -> Option<SearchResults>;
+    /// The quality tracks are streamed at unless another one is asked for.
+    fn quality(&self) -> AudioQuality;
+    async fn track_url(&self, track_id: i32) -> Option<StreamUrl>;
+    async fn track_url_with_quality(
+        &self,
+        track_id: i32,
+        quality: AudioQuality,
+    ) -> Option<StreamUrl>;
     async fn user_playlists(&self) -> Option<Vec<Playlist>>;
+    async fn remove_playlist_tracks(
+        &self,
+        playlist_id: u32,
+        playlist_track_ids: Vec<i64>,
+    ) -> Option<Playlist>;
+    async fn add_playlist_tracks(&self, playlist_id: u32, track_ids: Vec<u32>) -> Option<Playlist>;
+    /// Create a playlist for the user, visible to everyone when `public`.
+    async fn create_playlist(
+        &self,
+        name: &str,
+        public: bool,
+        description: Option<&str>,
+    ) -> Option<Playlist>;
+    /// Delete one of the user's playlists. Returns whether it worked.
+    async fn delete_playlist(&self, playlist_id: u32) -> bool;
+    /// Move an entry of a playlist so it comes before the entry at `insert_before`.
+    async fn move_playlist_track(
+        &self,
+        playlist_id: u32,
+        playlist_track_id: i64,
+        insert_before: usize,
+    ) -> Option<Playlist>;
+    /// Drop open connections to the service, e.g. after the network changed.
+    fn reconnect(&self);
+    /// Check whether the service is back after an outage.
+    async fn health_check(&self) -> bool;
+    async fn favorite_albums(&self) -> Option<Vec<Album>>;
+    async fn favorite_artists(&self) -> Option<Vec<Artist>>;
+    async fn favorite_tracks(&self) -> Option<Vec<Track>>;
+    /// Add something to the favorites, or remove it when `favorite` is false.
+    /// Returns false when the service refused.
+    async fn set_favorite(&self, item: Favorite, favorite: bool) -> bool;
+    fn browse_categories(&self) -> Vec<BrowseCategory>;
+    /// The playlists the service puts together for the user.
+    fn personal_playlists(&self) -> Vec<PersonalPlaylist>;
+    async fn personal_playlist(&self, kind: PersonalPlaylist) -> Option<Playlist>;
+    /// Playlists picked by the service's editors.
+    async fn featured_playlists(&self) -> Option<Vec<Playlist>>;
+    /// The genres browse categories can be narrowed to.
+    async fn genres(&self) -> Vec<Genre>;
+    /// A page of a browse category, only with albums of `genre` when one is given.
+    async fn browse(
+        &self,
+        category: BrowseCategory,
+        genre: Option<u32>,
+        offset: u32,
+        limit: u32,
+    ) -> Option<BrowsePage>;
+}
+
+/// Something that can be added to the user's favorites.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum Favorite {
+    Album(String),
+    Track(i32),
+    Artist(i32),
+}
+
+/// A url to stream a track from, with the quality that was asked for and the one delivered.
+#[derive(Debug, Clone, PartialEq)]
+pub struct StreamUrl {
+    pub url: String,
+    pub requested: AudioQuality,
+    pub delivered: AudioQuality,
+    /// True when the account's subscription, not the track, kept it below the requested quality.
+    pub limited: bool,
+}
+
+/// Curated sections of the catalog that can be browsed page by page.
+#[derive(Default, Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize, ValueEnum)]
+#[serde(rename_all = "camelCase")]
+pub enum BrowseCategory {
+    #[default]
+    NewReleases,
+    MostStreamed,
+    PressAwards,
+    EditorPicks,
+    BestSellers,
+}
+
+impl BrowseCategory {
+    pub fn title(&self) -> String {
+        match self {
+            BrowseCategory::NewReleases => t!("browse-new-releases"),
+            BrowseCategory::MostStreamed => t!("browse-most-streamed"),
+            BrowseCategory::PressAwards => t!("browse-press-awards"),
+            BrowseCategory::EditorPicks => t!("browse-editor-picks"),
+            BrowseCategory::BestSellers => t!("browse-best-sellers"),
+        }
+    }
+}
+
+/// Playlists made for the user that the service renews on its own, like Qobuz's Weekly Q.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum PersonalPlaylist {
+    Weekly,
+    Daily,
+}
+
+impl PersonalPlaylist {
+    pub fn title(&self) -> String {
+        match self {
+            PersonalPlaylist::Weekly => t!("personal-weekly"),
+            PersonalPlaylist::Daily => t!("personal-daily"),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub struct Genre {
+    pub id: u32,
+    pub name: String,
+}
+
+#[derive(Debug, Default, Clone, Serialize, Deserialize, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct BrowsePage {
+    pub category: BrowseCategory,
+    /// The genre the albums are narrowed to, if any.
+    #[serde(default)]
+    pub genre: Option<u32>,
+    pub albums: Vec<Album>,
+    pub offset: u32,
+    pub total: u32,
 }
 
 #[derive(Debug, Default, Clone, Serialize, Deserialize, PartialEq)]
@@ -27,6 +296,16 @@ pub enum TrackStatus {
     #[default]
     Unplayed,
     Unplayable,
+    /// The service lists the track but refused a url for it, e.g. because it is blocked
+    /// in this region.
+    Unavailable,
+}
+
+impl TrackStatus {
+    /// True for tracks that are passed over when the queue moves on.
+    pub fn is_skipped(&self) -> bool {
+        matches!(self, TrackStatus::Unplayable | TrackStatus::Unavailable)
+    }
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
@@ -49,8 +328,30 @@ pub struct Track {
     pub cover_art: Option<String>,
     pub position: u32,
     pub media_number: u32,
+    /// A public link to the track on the service.
+    #[serde(default)]
+    pub url: Option<String>,
+    /// Identifies this entry of a playlist, set when the track was loaded as part of one.
+    #[serde(default)]
+    pub playlist_track_id: Option<i64>,
+    /// Which recording this is when there are several, e.g. `2019 Remaster` or `Live`.
+    #[serde(default)]
+    pub version: Option<String>,
+    /// ReplayGain track gain in dB, when the service knows it.
+    #[serde(default)]
+    pub replay_gain: Option<f64>,
+    /// ReplayGain track peak, 1.0 being full scale.
+    #[serde(default)]
+    pub replay_peak: Option<f64>,
+    /// The lower quality the track streams at, once its url was fetched, when the service
+    /// doesn't offer it at the quality asked for.
+    #[serde(default)]
+    pub fallback_quality: Option<AudioQuality>,
 }
 
+/// Columns in a track list row besides the title: number, duration and format.
+const TRACK_ROW_FIXED: usize = 3 + 1 + 5 + 1 + 7;
+
 impl CursiveFormat for Track {
     fn list_item(&self) -> StyledString {
         let mut style = Style::none();
@@ -59,10 +360,10 @@ impl CursiveFormat for Track {
             style = style.combine(Effect::Dim).combine(Effect::Strikethrough);
         }
 
-        let mut title = StyledString::styled(self.title.trim(), style.combine(Effect::Bold));
+        let mut title = StyledString::styled(self.display_title(), style.combine(Effect::Bold));
 
         if let Some(artist) = &self.artist {
-            title.append_styled(" by ", style);
+            title.append_styled(format!(" {} ", t!("by")), style);
             title.append_styled(&artist.name, style);
         }
 
@@ -78,9 +379,7 @@ impl CursiveFormat for Track {
             title.append_styled("e", style.combine(Effect::Dim));
         }
 
-        if self.hires_available {
-            title.append_styled("*", style.combine(Effect::Dim));
-        }
+        title.append(self.format_badge(style));
 
         title
     }
@@ -102,7 +401,10 @@ impl CursiveFormat for Track {
         };
 
         let mut item = StyledString::styled(format!("{:02} ", num), style);
-        item.append_styled(self.title.trim(), style.combine(Effect::Simple));
+        item.append_styled(
+            layout::fit(&self.display_title(), layout::column_width(TRACK_ROW_FIXED)),
+            style.combine(Effect::Simple),
+        );
         item.append_plain(" ");
 
         let duration = ClockTime::from_seconds(self.duration_seconds as u64)
@@ -111,11 +413,89 @@ impl CursiveFormat for Track {
             .to_string();
 
         item.append_styled(duration, style.combine(Effect::Dim));
+        item.append_plain(" ");
+        item.append(self.format_badge(style));
 
         item
     }
 }
 
+impl Track {
+    /// The title with its version when edition hints are on, see [`set_edition_hints`].
+    pub fn display_title(&self) -> String {
+        with_edition(&self.title, self.version.as_deref())
+    }
+
+    /// Compact format label for the track, see [`format_label`].
+    pub fn format_label(&self) -> String {
+        format_label(self.bit_depth, self.sampling_rate)
+    }
+
+    pub fn is_hires(&self) -> bool {
+        is_hires(self.bit_depth, self.sampling_rate)
+    }
+
+    fn format_badge(&self, style: Style) -> StyledString {
+        let effect = if self.is_hires() {
+            Effect::Bold
+        } else {
+            Effect::Dim
+        };
+
+        StyledString::styled(self.format_label(), style.combine(effect))
+    }
+}
+
+/// Compact label for an audio format, e.g. `24/96`, `16/44.1` or `MP3` when
+/// the bit depth and sampling rate (in kHz) are unknown.
+pub fn format_label(bit_depth: u32, sampling_rate: f32) -> String {
+    if bit_depth == 0 || sampling_rate <= 0. {
+        "MP3".to_string()
+    } else {
+        format!("{bit_depth}/{sampling_rate}")
+    }
+}
+
+/// Show the edition of albums and tracks after their titles, e.g. `Abbey Road (2019 Remaster)`,
+/// or keep the titles clean.
+pub fn set_edition_hints(enabled: bool) {
+    EDITION_HINTS.store(enabled, Ordering::Relaxed);
+}
+
+/// `title (version)`, unless edition hints are off or the title already mentions the version.
+fn with_edition(title: &str, version: Option<&str>) -> String {
+    let title = title.trim();
+
+    match version.map(str::trim).filter(|v| !v.is_empty()) {
+        Some(version)
+            if EDITION_HINTS.load(Ordering::Relaxed)
+                && !title.to_lowercase().contains(&version.to_lowercase()) =>
+        {
+            format!("{title} ({version})")
+        }
+        _ => title.to_string(),
+    }
+}
+
+/// `mm:ss`, or `h:mm:ss` from an hour on.
+pub fn format_seconds(seconds: u64) -> String {
+    if seconds >= 3600 {
+        format!(
+            "{}:{:02}:{:02}",
+            seconds / 3600,
+            seconds % 3600 / 60,
+            seconds % 60
+        )
+    } else {
+        format!("{:02}:{:02}", seconds / 60, seconds % 60)
+    }
+}
+
+/// Anything above CD quality is considered hi-res.
+pub fn is_hires(bit_depth: u32, sampling_rate: f32) -> bool {
+    bit_depth > 16 || sampling_rate > 48.
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
 #[serde(rename_all = "camelCase")]
 pub struct Album {
@@ -129,19 +509,60 @@ pub struct Album {
     pub tracks: BTreeMap<u32, Track>,
     pub available: bool,
     pub cover_art: String,
+    #[serde(default)]
+    pub favorited_at: Option<i64>,
+    #[serde(default)]
+    pub streamable_at: Option<i64>,
+    #[serde(default)]
+    pub catchline: Option<String>,
+    #[serde(default)]
+    pub description: Option<String>,
+    #[serde(default)]
+    pub genre: Option<String>,
+    /// The original release date, formatted as YYYY-MM-DD.
+    #[serde(default)]
+    pub release_date: Option<String>,
+    /// The date the album can be streamed from, formatted as YYYY-MM-DD.
+    #[serde(default)]
+    pub stream_date: Option<String>,
+    /// Which edition this is when there are several, e.g. `2019 Remaster` or `Deluxe`.
+    #[serde(default)]
+    pub version: Option<String>,
+}
+
+impl Album {
+    /// The title with its edition when edition hints are on, see [`set_edition_hints`].
+    pub fn display_title(&self) -> String {
+        with_edition(&self.title, self.version.as_deref())
+    }
+
+    /// The date an album that cannot be streamed yet becomes available.
+    pub fn available_on(&self) -> Option<i64> {
+        if self.available {
+            return None;
+        }
+
+        self.streamable_at
+            .filter(|at| *at > chrono::Utc::now().timestamp())
+    }
 }
 
 impl CursiveFormat for Album {
     fn list_item(&self) -> StyledString {
         let mut style = Style::none();
+        let available_on = self.available_on();
 
         if !self.available {
-            style = style.combine(Effect::Dim).combine(Effect::Strikethrough);
+            style = style.combine(Effect::Dim);
+
+            if available_on.is_none() {
+                style = style.combine(Effect::Strikethrough);
+            }
         }
 
-        let mut title = StyledString::styled(self.title.as_str(), style.combine(Effect::Bold));
+        let mut title = StyledString::styled(self.display_title(), style.combine(Effect::Bold));
 
-        title.append_styled(" by ", style);
+        title.append_styled(format!(" {} ", t!("by")), style);
         title.append_styled(self.artist.name.as_str(), style);
         title.append_styled(" ", style);
 
@@ -156,6 +577,17 @@ impl CursiveFormat for Album {
             title.append_styled("*", style.combine(Effect::Dim));
         }
 
+        if let Some(date) = available_on {
+            title.append_plain(" ");
+            title.append_styled(
+                format!(
+                    "[{}]",
+                    t!("available-on", date = releases::format_date(date))
+                ),
+                Effect::Italic,
+            );
+        }
+
         title
     }
 }
@@ -169,6 +601,143 @@ pub struct SearchResults {
     pub playlists: Vec<Playlist>,
 }
 
+/// Narrows search results down after they arrive, as the search API has no such options.
+#[derive(Default, Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SearchFilter {
+    /// Only albums and tracks available in more than CD quality.
+    pub hires_only: bool,
+    /// Leave out albums, and tracks from albums, released before this year.
+    pub min_year: Option<u32>,
+    /// Only albums, and tracks from albums, whose genre contains this, ignoring case.
+    pub genre: Option<String>,
+}
+
+impl SearchFilter {
+    pub fn is_active(&self) -> bool {
+        self != &SearchFilter::default()
+    }
+
+    pub fn album_matches(&self, album: &Album) -> bool {
+        (!self.hires_only || album.hires_available) && self.release_matches(album)
+    }
+
+    /// Tracks without an album only match when no year or genre is asked for.
+    pub fn track_matches(&self, track: &Track) -> bool {
+        (!self.hires_only || track.hires_available)
+            && match &track.album {
+                Some(album) => self.release_matches(album),
+                None => self.min_year.is_none() && self.genre.is_none(),
+            }
+    }
+
+    fn release_matches(&self, album: &Album) -> bool {
+        let year = self
+            .min_year
+            .map_or(true, |year| album.release_year >= year);
+        let genre = self.genre.as_ref().map_or(true, |genre| {
+            album
+                .genre
+                .as_ref()
+                .is_some_and(|g| g.to_lowercase().contains(&genre.to_lowercase()))
+        });
+
+        year && genre
+    }
+
+    /// Leave out the albums and tracks that don't match. Artists and playlists are kept.
+    pub fn apply(&self, results: &mut SearchResults) {
+        results.albums.retain(|a| self.album_matches(a));
+        results.tracks.retain(|t| self.track_matches(t));
+    }
+}
+
+/// The kinds of results a search can be narrowed to.
+#[derive(Default, Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, ValueEnum)]
+#[serde(rename_all = "camelCase")]
+pub enum SearchType {
+    #[default]
+    All,
+    Albums,
+    Tracks,
+    Artists,
+    Playlists,
+}
+
+/// How many results of each kind a search returns. Kinds with a limit of zero are not searched.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SearchLimits {
+    pub albums: u32,
+    pub tracks: u32,
+    pub artists: u32,
+    pub playlists: u32,
+}
+
+impl SearchLimits {
+    pub const DEFAULT_LIMIT: u32 = 100;
+
+    /// The same limit for every kind of result.
+    pub fn all(limit: u32) -> Self {
+        Self {
+            albums: limit,
+            tracks: limit,
+            artists: limit,
+            playlists: limit,
+        }
+    }
+
+    /// Only search for one kind of result, giving it the whole limit.
+    pub fn only(search_type: SearchType, limit: u32) -> Self {
+        let none = Self::all(0);
+
+        match search_type {
+            SearchType::All => Self::all(limit),
+            SearchType::Albums => Self {
+                albums: limit,
+                ..none
+            },
+            SearchType::Tracks => Self {
+                tracks: limit,
+                ..none
+            },
+            SearchType::Artists => Self {
+                artists: limit,
+                ..none
+            },
+            SearchType::Playlists => Self {
+                playlists: limit,
+                ..none
+            },
+        }
+    }
+
+    /// The one kind of result being searched for, if there is just one.
+    pub fn search_type(&self) -> SearchType {
+        match (self.albums, self.tracks, self.artists, self.playlists) {
+            (_, 0, 0, 0) if self.albums > 0 => SearchType::Albums,
+            (0, _, 0, 0) if self.tracks > 0 => SearchType::Tracks,
+            (0, 0, _, 0) if self.artists > 0 => SearchType::Artists,
+            (0, 0, 0, _) if self.playlists > 0 => SearchType::Playlists,
+            _ => SearchType::All,
+        }
+    }
+
+    /// The largest limit of any kind of result.
+    pub fn max(&self) -> u32 {
+        self.albums
+            .max(self.tracks)
+            .max(self.artists)
+            .max(self.playlists)
+    }
+}
+
+impl Default for SearchLimits {
+    fn default() -> Self {
+        Self::all(Self::DEFAULT_LIMIT)
+    }
+}
+
 #[derive(Default, Debug, Clone, Serialize, Deserialize, PartialEq)]
 pub struct Artist {
     pub id: u32,
@@ -185,6 +754,45 @@ pub struct Playlist {
     pub id: u32,
     pub cover_art: Option<String>,
     pub tracks: BTreeMap<u32, Track>,
+    #[serde(default)]
+    pub description: Option<String>,
+    /// When the playlist was last changed, as a unix timestamp.
+    #[serde(default)]
+    pub updated_at: Option<i64>,
+}
+
+impl Playlist {
+    /// Whether the service made this for the user, like the [`PersonalPlaylist`]s. Those have
+    /// no id to load them by again, a new one is made each time.
+    pub fn is_personal(&self) -> bool {
+        self.id == 0
+    }
+
+    /// Track count, total length and last update, e.g. `42 tracks · 2:51 · 2024-10-01`.
+    pub fn summary(&self) -> String {
+        let hours = self.duration_seconds / 3600;
+        let minutes = self.duration_seconds % 3600 / 60;
+
+        let mut summary = format!(
+            "{} · {hours}:{minutes:02}",
+            t!("playlist-tracks", count = self.tracks_count)
+        );
+
+        if let Some(updated) = self
+            .updated_at
+            .and_then(|at| chrono::DateTime::from_timestamp(at, 0))
+        {
+            summary.push_str(" · ");
+            summary.push_str(
+                &updated
+                    .with_timezone(&chrono::Local)
+                    .format("%Y-%m-%d")
+                    .to_string(),
+            );
+        }
+
+        summary
+    }
 }
 
 impl CursiveFormat for Artist {
@@ -1,15 +1,37 @@
+use clap::ValueEnum;
 use hifirs_qobuz_api::client::{ApiConfig, AudioQuality};
 use once_cell::sync::OnceCell;
+use serde::{de::DeserializeOwned, Serialize};
 use sqlx::{sqlite::SqliteConnectOptions, Pool, Sqlite, SqlitePool};
-use std::path::PathBuf;
+use std::{
+    collections::HashSet,
+    path::PathBuf,
+    sync::atomic::{AtomicBool, Ordering},
+};
 
 use crate::{
-    acquire, get_one,
-    player::queue::controls::{PlayerState, SavedState},
+    acquire,
+    config::ConfigKey,
+    get_one,
+    history::{HistoryEntry, MostPlayed},
+    library::{LibraryEntry, LibraryKind, LibrarySource},
+    lyrics::CachedLyrics,
+    player::{
+        cache::CachedTrack,
+        loudness::Gain,
+        output::{EqPreset, OutputProfile},
+        queue::controls::{QueueSnapshot, SavedState},
+        schedule::{Schedule, ScheduleKind},
+        trim::{Trim, TrimKind},
+    },
     query,
+    releases::WatchedRelease,
+    service::Album,
 };
 
 static POOL: OnceCell<Pool<Sqlite>> = OnceCell::new();
+/// Whether this run created the database, rather than opening one from an earlier run.
+static CREATED: AtomicBool = AtomicBool::new(false);
 
 pub async fn init() {
     let database_url = if let Ok(url) = std::env::var("DATABASE_URL") {
@@ -28,6 +50,7 @@ pub async fn init() {
     };
 
     debug!("DATABASE_URL: {}", database_url.to_string_lossy());
+    CREATED.store(!database_url.exists(), Ordering::Relaxed);
 
     let options = SqliteConnectOptions::new()
         .journal_mode(sqlx::sqlite::SqliteJournalMode::Wal)
@@ -48,6 +71,11 @@ pub async fn init() {
     create_config().await;
 }
 
+/// True when the database was made by this run, i.e. on a new install.
+pub fn is_new() -> bool {
+    CREATED.load(Ordering::Relaxed)
+}
+
 pub async fn clear_state() {
     if let Ok(mut conn) = acquire!() {
         sqlx::query("DELETE FROM state WHERE state.key != 'active_screen'")
@@ -143,6 +171,20 @@ pub async fn set_default_quality(quality: AudioQuality) {
     }
 }
 
+pub async fn set_language(language: String) {
+    if let Ok(mut conn) = acquire!() {
+        query!(
+            r#"
+            UPDATE config
+            SET language=?1
+            WHERE ROWID = 1
+            "#,
+            conn,
+            language
+        );
+    }
+}
+
 pub async fn create_config() {
     if let Ok(mut conn) = acquire!() {
         let rowid = 1;
@@ -160,7 +202,10 @@ pub async fn get_config() -> Option<ApiConfig> {
     if let Ok(mut conn) = acquire!() {
         if let Ok(conf) = get_one!(
             r#"
-            SELECT * FROM config
+            SELECT username, password, default_quality, user_token, app_id, active_secret, language,
+                http2, http_pool_size, http_connect_timeout, http_timeout, http_keep_alive,
+                api_slow_threshold, api_rate_limit, api_retries
+            FROM config
             WHERE ROWID = 1;
             "#,
             ApiConfig,
@@ -175,9 +220,60 @@ pub async fn get_config() -> Option<ApiConfig> {
     }
 }
 
-pub async fn persist_state(state: PlayerState) {
+pub async fn get_config_value(key: ConfigKey) -> Option<String> {
+    if let Ok(mut conn) = acquire!() {
+        let sql = format!(
+            "SELECT CAST({} AS TEXT) FROM config WHERE ROWID = 1;",
+            key.column()
+        );
+
+        sqlx::query_scalar::<_, Option<String>>(&sql)
+            .fetch_one(&mut *conn)
+            .await
+            .ok()
+            .flatten()
+    } else {
+        None
+    }
+}
+
+pub async fn set_config_value(key: ConfigKey, value: Option<String>) {
+    if let Ok(mut conn) = acquire!() {
+        let sql = format!("UPDATE config SET {}=?1 WHERE ROWID = 1;", key.column());
+
+        sqlx::query(&sql)
+            .bind(value)
+            .execute(&mut *conn)
+            .await
+            .expect("database failure");
+    }
+}
+
+/// The version of hifi-rs that last opened the database.
+pub async fn get_last_version() -> Option<String> {
+    if let Ok(mut conn) = acquire!() {
+        sqlx::query_scalar::<_, Option<String>>("SELECT last_version FROM config WHERE ROWID = 1;")
+            .fetch_one(&mut *conn)
+            .await
+            .ok()
+            .flatten()
+    } else {
+        None
+    }
+}
+
+pub async fn set_last_version(version: &str) {
+    if let Ok(mut conn) = acquire!() {
+        sqlx::query("UPDATE config SET last_version=?1 WHERE ROWID = 1;")
+            .bind(version)
+            .execute(&mut *conn)
+            .await
+            .expect("database failure");
+    }
+}
+
+pub async fn persist_state(saved_state: SavedState) {
     if let Ok(mut conn) = acquire!() {
-        let saved_state: SavedState = state.into();
         let playback_entity_type = saved_state.playback_entity_type.to_string();
 
         sqlx::query!(
@@ -210,6 +306,945 @@ pub async fn get_last_state() -> Option<SavedState> {
     }
 }
 
+pub async fn get_favorite_albums() -> Vec<Album> {
+    if let Ok(mut conn) = acquire!() {
+        sqlx::query_scalar::<_, String>("SELECT album FROM favorite_albums ORDER BY position;")
+            .fetch_all(&mut *conn)
+            .await
+            .unwrap_or_default()
+            .iter()
+            .filter_map(|album| serde_json::from_str(album).ok())
+            .collect()
+    } else {
+        Vec::new()
+    }
+}
+
+pub async fn set_favorite_albums(albums: &[Album]) {
+    if let Ok(mut conn) = acquire!() {
+        let mut tx = sqlx::Connection::begin(&mut *conn)
+            .await
+            .expect("database failure");
+
+        sqlx::query("DELETE FROM favorite_albums;")
+            .execute(&mut *tx)
+            .await
+            .expect("database failure");
+
+        for (position, album) in albums.iter().enumerate() {
+            let json = serde_json::to_string(album).expect("failed to serialize album");
+
+            sqlx::query("INSERT OR REPLACE INTO favorite_albums VALUES(?1,?2,?3);")
+                .bind(&album.id)
+                .bind(position as i64)
+                .bind(json)
+                .execute(&mut *tx)
+                .await
+                .expect("database failure");
+        }
+
+        tx.commit().await.expect("database failure");
+    }
+}
+
+/// The cached release calendar, oldest release first.
+pub async fn get_release_calendar() -> Vec<Album> {
+    if let Ok(mut conn) = acquire!() {
+        sqlx::query_scalar::<_, String>("SELECT album FROM release_calendar ORDER BY date;")
+            .fetch_all(&mut *conn)
+            .await
+            .unwrap_or_default()
+            .iter()
+            .filter_map(|album| serde_json::from_str(album).ok())
+            .collect()
+    } else {
+        Vec::new()
+    }
+}
+
+/// Replaces the cached release calendar with `albums` and the date each comes out.
+pub async fn set_release_calendar(albums: &[(i64, Album)]) {
+    if let Ok(mut conn) = acquire!() {
+        let mut tx = sqlx::Connection::begin(&mut *conn)
+            .await
+            .expect("database failure");
+
+        sqlx::query("DELETE FROM release_calendar;")
+            .execute(&mut *tx)
+            .await
+            .expect("database failure");
+
+        for (date, album) in albums {
+            let json = serde_json::to_string(album).expect("failed to serialize album");
+
+            sqlx::query("INSERT OR REPLACE INTO release_calendar VALUES(?1,?2,?3);")
+                .bind(&album.id)
+                .bind(date)
+                .bind(json)
+                .execute(&mut *tx)
+                .await
+                .expect("database failure");
+        }
+
+        tx.commit().await.expect("database failure");
+    }
+}
+
+/// The ids of every album that has been played at least once.
+pub async fn played_album_ids() -> HashSet<String> {
+    if let Ok(mut conn) = acquire!() {
+        sqlx::query_scalar::<_, String>(
+            "SELECT DISTINCT playback_entity_id FROM player_state WHERE playback_entity_type = 'album';",
+        )
+        .fetch_all(&mut *conn)
+        .await
+        .unwrap_or_default()
+        .into_iter()
+        .collect()
+    } else {
+        HashSet::new()
+    }
+}
+
+pub async fn add_watched_release(release: WatchedRelease) {
+    if let Ok(mut conn) = acquire!() {
+        sqlx::query("INSERT OR REPLACE INTO release_watch VALUES(?1,?2,?3,?4);")
+            .bind(release.album_id)
+            .bind(release.title)
+            .bind(release.artist)
+            .bind(release.streamable_at)
+            .execute(&mut *conn)
+            .await
+            .expect("database failure");
+    }
+}
+
+pub async fn remove_watched_release(album_id: &str) {
+    if let Ok(mut conn) = acquire!() {
+        sqlx::query("DELETE FROM release_watch WHERE album_id = ?1;")
+            .bind(album_id)
+            .execute(&mut *conn)
+            .await
+            .expect("database failure");
+    }
+}
+
+pub async fn get_watched_releases() -> Vec<WatchedRelease> {
+    if let Ok(mut conn) = acquire!() {
+        sqlx::query_as::<_, WatchedRelease>(
+            "SELECT album_id, title, artist, streamable_at FROM release_watch ORDER BY streamable_at;",
+        )
+        .fetch_all(&mut *conn)
+        .await
+        .unwrap_or_default()
+    } else {
+        Vec::new()
+    }
+}
+
+type OutputProfileRow = (String, Option<String>, bool, f64, String);
+
+fn output_profile_from_row(row: OutputProfileRow) -> OutputProfile {
+    let (name, device, bit_perfect, volume, eq_preset) = row;
+
+    OutputProfile {
+        name,
+        device,
+        bit_perfect,
+        volume,
+        eq_preset: EqPreset::from_str(&eq_preset, true).unwrap_or_default(),
+    }
+}
+
+pub async fn get_output_profiles() -> Vec<OutputProfile> {
+    if let Ok(mut conn) = acquire!() {
+        sqlx::query_as::<_, OutputProfileRow>(
+            "SELECT name, device, bit_perfect, volume, eq_preset FROM output_profiles ORDER BY name;",
+        )
+        .fetch_all(&mut *conn)
+        .await
+        .unwrap_or_default()
+        .into_iter()
+        .map(output_profile_from_row)
+        .collect()
+    } else {
+        Vec::new()
+    }
+}
+
+pub async fn get_output_profile(name: &str) -> Option<OutputProfile> {
+    if let Ok(mut conn) = acquire!() {
+        sqlx::query_as::<_, OutputProfileRow>(
+            "SELECT name, device, bit_perfect, volume, eq_preset FROM output_profiles WHERE name = ?1;",
+        )
+        .bind(name)
+        .fetch_optional(&mut *conn)
+        .await
+        .ok()
+        .flatten()
+        .map(output_profile_from_row)
+    } else {
+        None
+    }
+}
+
+pub async fn save_output_profile(profile: &OutputProfile) {
+    if let Ok(mut conn) = acquire!() {
+        sqlx::query("INSERT OR REPLACE INTO output_profiles VALUES(?1,?2,?3,?4,?5);")
+            .bind(&profile.name)
+            .bind(&profile.device)
+            .bind(profile.bit_perfect)
+            .bind(profile.volume)
+            .bind(profile.eq_preset.to_string())
+            .execute(&mut *conn)
+            .await
+            .expect("database failure");
+    }
+}
+
+/// Removes a profile, returning false if there was no profile with that name.
+pub async fn remove_output_profile(name: &str) -> bool {
+    if let Ok(mut conn) = acquire!() {
+        sqlx::query("DELETE FROM output_profiles WHERE name = ?1;")
+            .bind(name)
+            .execute(&mut *conn)
+            .await
+            .map(|r| r.rows_affected() > 0)
+            .unwrap_or_default()
+    } else {
+        false
+    }
+}
+
+type CachedTrackRow = (i64, i64, String, String, i64, String);
+
+fn cached_track_from_row(row: CachedTrackRow) -> Option<CachedTrack> {
+    let (_, quality, path, track, size, cached_at) = row;
+
+    Some(CachedTrack {
+        track: serde_json::from_str(&track).ok()?,
+        quality: quality as u32,
+        path: PathBuf::from(path),
+        size: size as u64,
+        cached_at: cached_at.parse().ok()?,
+    })
+}
+
+pub async fn get_cached_tracks() -> Vec<CachedTrack> {
+    if let Ok(mut conn) = acquire!() {
+        sqlx::query_as::<_, CachedTrackRow>(
+            "SELECT track_id, quality, path, track, size, cached_at FROM cached_tracks ORDER BY cached_at DESC;",
+        )
+        .fetch_all(&mut *conn)
+        .await
+        .unwrap_or_default()
+        .into_iter()
+        .filter_map(cached_track_from_row)
+        .collect()
+    } else {
+        Vec::new()
+    }
+}
+
+/// The best quality copy of a track in the cache.
+pub async fn get_cached_track(track_id: i32) -> Option<CachedTrack> {
+    if let Ok(mut conn) = acquire!() {
+        sqlx::query_as::<_, CachedTrackRow>(
+            "SELECT track_id, quality, path, track, size, cached_at FROM cached_tracks WHERE track_id = ?1 ORDER BY quality DESC LIMIT 1;",
+        )
+        .bind(track_id)
+        .fetch_optional(&mut *conn)
+        .await
+        .ok()
+        .flatten()
+        .and_then(cached_track_from_row)
+    } else {
+        None
+    }
+}
+
+pub async fn add_cached_track(cached: &CachedTrack) {
+    if let Ok(mut conn) = acquire!() {
+        let track = serde_json::to_string(&cached.track).expect("error making json");
+
+        sqlx::query("INSERT OR REPLACE INTO cached_tracks VALUES(?1,?2,?3,?4,?5,?6);")
+            .bind(cached.track.id)
+            .bind(cached.quality)
+            .bind(cached.path.to_string_lossy().to_string())
+            .bind(track)
+            .bind(cached.size as i64)
+            .bind(cached.cached_at.to_rfc3339())
+            .execute(&mut *conn)
+            .await
+            .expect("database failure");
+    }
+}
+
+pub async fn remove_cached_track(track_id: i32, quality: u32) {
+    if let Ok(mut conn) = acquire!() {
+        sqlx::query("DELETE FROM cached_tracks WHERE track_id = ?1 AND quality = ?2;")
+            .bind(track_id)
+            .bind(quality)
+            .execute(&mut *conn)
+            .await
+            .expect("database failure");
+    }
+}
+
+type TrimRow = (String, String, i64, i64);
+
+fn trim_from_row(row: TrimRow) -> Option<Trim> {
+    let (kind, id, start_secs, end_secs) = row;
+
+    Some(Trim {
+        kind: TrimKind::from_str(&kind, true).ok()?,
+        id,
+        start_secs: start_secs as u64,
+        end_secs: end_secs as u64,
+    })
+}
+
+pub async fn get_trims() -> Vec<Trim> {
+    if let Ok(mut conn) = acquire!() {
+        sqlx::query_as::<_, TrimRow>(
+            "SELECT kind, id, start_secs, end_secs FROM trims ORDER BY kind, id;",
+        )
+        .fetch_all(&mut *conn)
+        .await
+        .unwrap_or_default()
+        .into_iter()
+        .filter_map(trim_from_row)
+        .collect()
+    } else {
+        Vec::new()
+    }
+}
+
+pub async fn get_trim(kind: TrimKind, id: &str) -> Option<Trim> {
+    if let Ok(mut conn) = acquire!() {
+        sqlx::query_as::<_, TrimRow>(
+            "SELECT kind, id, start_secs, end_secs FROM trims WHERE kind = ?1 AND id = ?2;",
+        )
+        .bind(kind.to_string())
+        .bind(id)
+        .fetch_optional(&mut *conn)
+        .await
+        .ok()
+        .flatten()
+        .and_then(trim_from_row)
+    } else {
+        None
+    }
+}
+
+pub async fn save_trim(trim: &Trim) {
+    if let Ok(mut conn) = acquire!() {
+        sqlx::query("INSERT OR REPLACE INTO trims VALUES(?1,?2,?3,?4);")
+            .bind(trim.kind.to_string())
+            .bind(&trim.id)
+            .bind(trim.start_secs as i64)
+            .bind(trim.end_secs as i64)
+            .execute(&mut *conn)
+            .await
+            .expect("database failure");
+    }
+}
+
+/// Removes a trim, returning false if there was none.
+pub async fn remove_trim(kind: TrimKind, id: &str) -> bool {
+    if let Ok(mut conn) = acquire!() {
+        sqlx::query("DELETE FROM trims WHERE kind = ?1 AND id = ?2;")
+            .bind(kind.to_string())
+            .bind(id)
+            .execute(&mut *conn)
+            .await
+            .map(|r| r.rows_affected() > 0)
+            .unwrap_or_default()
+    } else {
+        false
+    }
+}
+
+type ScheduleRow = (String, String, String, String, Option<i64>);
+
+fn schedule_from_row(row: ScheduleRow) -> Option<Schedule> {
+    let (name, spec, kind, id, last_run) = row;
+
+    Some(Schedule {
+        name,
+        spec,
+        kind: ScheduleKind::from_str(&kind, true).ok()?,
+        id,
+        last_run,
+    })
+}
+
+pub async fn get_schedules() -> Vec<Schedule> {
+    if let Ok(mut conn) = acquire!() {
+        sqlx::query_as::<_, ScheduleRow>(
+            "SELECT name, spec, kind, id, last_run FROM schedules ORDER BY name;",
+        )
+        .fetch_all(&mut *conn)
+        .await
+        .unwrap_or_default()
+        .into_iter()
+        .filter_map(schedule_from_row)
+        .collect()
+    } else {
+        Vec::new()
+    }
+}
+
+/// Saves a schedule, replacing one with the same name.
+pub async fn save_schedule(schedule: &Schedule) {
+    if let Ok(mut conn) = acquire!() {
+        sqlx::query("INSERT OR REPLACE INTO schedules VALUES(?1,?2,?3,?4,?5);")
+            .bind(&schedule.name)
+            .bind(&schedule.spec)
+            .bind(schedule.kind.to_string())
+            .bind(&schedule.id)
+            .bind(schedule.last_run)
+            .execute(&mut *conn)
+            .await
+            .expect("database failure");
+    }
+}
+
+pub async fn set_schedule_run(name: &str, timestamp: i64) {
+    if let Ok(mut conn) = acquire!() {
+        sqlx::query("UPDATE schedules SET last_run = ?1 WHERE name = ?2;")
+            .bind(timestamp)
+            .bind(name)
+            .execute(&mut *conn)
+            .await
+            .expect("database failure");
+    }
+}
+
+/// Removes a schedule, returning false if there was none.
+pub async fn remove_schedule(name: &str) -> bool {
+    if let Ok(mut conn) = acquire!() {
+        sqlx::query("DELETE FROM schedules WHERE name = ?1;")
+            .bind(name)
+            .execute(&mut *conn)
+            .await
+            .map(|r| r.rows_affected() > 0)
+            .unwrap_or_default()
+    } else {
+        false
+    }
+}
+
+pub async fn get_loudness(track_id: u32) -> Option<Gain> {
+    if let Ok(mut conn) = acquire!() {
+        sqlx::query_as::<_, (f64, f64)>("SELECT gain, peak FROM loudness WHERE track_id = ?1;")
+            .bind(track_id as i64)
+            .fetch_optional(&mut *conn)
+            .await
+            .ok()
+            .flatten()
+            .map(|(db, peak)| Gain { db, peak })
+    } else {
+        None
+    }
+}
+
+pub async fn save_loudness(track_id: u32, gain: Gain) {
+    if let Ok(mut conn) = acquire!() {
+        sqlx::query("INSERT OR REPLACE INTO loudness VALUES(?1,?2,?3);")
+            .bind(track_id as i64)
+            .bind(gain.db)
+            .bind(gain.peak)
+            .execute(&mut *conn)
+            .await
+            .expect("database failure");
+    }
+}
+
+pub async fn get_lyrics(artist: &str, title: &str, duration: u32) -> Option<CachedLyrics> {
+    if let Ok(mut conn) = acquire!() {
+        sqlx::query_as::<_, CachedLyrics>(
+            "SELECT synced, plain, instrumental, fetched_at FROM lyrics WHERE artist = ?1 AND title = ?2 AND duration = ?3;",
+        )
+        .bind(artist)
+        .bind(title)
+        .bind(duration as i64)
+        .fetch_optional(&mut *conn)
+        .await
+        .ok()
+        .flatten()
+    } else {
+        None
+    }
+}
+
+pub async fn save_lyrics(artist: &str, title: &str, duration: u32, lyrics: &CachedLyrics) {
+    if let Ok(mut conn) = acquire!() {
+        sqlx::query("INSERT OR REPLACE INTO lyrics VALUES(?1,?2,?3,?4,?5,?6,?7);")
+            .bind(artist)
+            .bind(title)
+            .bind(duration as i64)
+            .bind(&lyrics.synced)
+            .bind(&lyrics.plain)
+            .bind(lyrics.instrumental)
+            .bind(lyrics.fetched_at)
+            .execute(&mut *conn)
+            .await
+            .expect("database failure");
+    }
+}
+
+type LibraryRow = (
+    String,
+    String,
+    String,
+    Option<String>,
+    Option<String>,
+    String,
+    Option<String>,
+    Option<String>,
+);
+
+const LIBRARY_COLUMNS: &str = "kind, entity_id, source, source_id, album_id, title, artist, album";
+
+fn library_entry_from_row(row: LibraryRow) -> Option<LibraryEntry> {
+    let (kind, id, source, source_id, album_id, title, artist, album) = row;
+
+    Some(LibraryEntry {
+        kind: LibraryKind::from_str(&kind, true).ok()?,
+        id,
+        title,
+        artist,
+        album,
+        album_id,
+        source: LibrarySource::from_str(&source, true).ok()?,
+        source_id,
+    })
+}
+
+async fn insert_library_entries(
+    conn: &mut sqlx::SqliteConnection,
+    entries: &[LibraryEntry],
+) -> Result<(), sqlx::Error> {
+    for entry in entries {
+        sqlx::query(&format!(
+            "INSERT INTO library_index ({LIBRARY_COLUMNS}) VALUES(?1,?2,?3,?4,?5,?6,?7,?8);"
+        ))
+        .bind(entry.kind.to_string())
+        .bind(&entry.id)
+        .bind(entry.source.to_string())
+        .bind(&entry.source_id)
+        .bind(&entry.album_id)
+        .bind(&entry.title)
+        .bind(&entry.artist)
+        .bind(&entry.album)
+        .execute(&mut *conn)
+        .await?;
+    }
+
+    Ok(())
+}
+
+/// Replaces the library entries indexed from `source`, or only those of one playlist
+/// when `source_id` is given.
+pub async fn set_library_entries(
+    source: LibrarySource,
+    source_id: Option<&str>,
+    entries: &[LibraryEntry],
+) {
+    if let Ok(mut conn) = acquire!() {
+        let mut tx = sqlx::Connection::begin(&mut *conn)
+            .await
+            .expect("database failure");
+
+        sqlx::query(
+            "DELETE FROM library_index WHERE source = ?1 AND (?2 IS NULL OR source_id = ?2);",
+        )
+        .bind(source.to_string())
+        .bind(source_id)
+        .execute(&mut *tx)
+        .await
+        .expect("database failure");
+
+        insert_library_entries(&mut tx, entries)
+            .await
+            .expect("database failure");
+
+        tx.commit().await.expect("database failure");
+    }
+}
+
+/// Replaces the indexed playlist titles without touching the tracks indexed from them.
+pub async fn set_library_playlists(entries: &[LibraryEntry]) {
+    if let Ok(mut conn) = acquire!() {
+        let mut tx = sqlx::Connection::begin(&mut *conn)
+            .await
+            .expect("database failure");
+
+        sqlx::query("DELETE FROM library_index WHERE kind = 'playlist';")
+            .execute(&mut *tx)
+            .await
+            .expect("database failure");
+
+        insert_library_entries(&mut tx, entries)
+            .await
+            .expect("database failure");
+
+        tx.commit().await.expect("database failure");
+    }
+}
+
+/// Adds library entries, replacing earlier ones for the same item and source.
+pub async fn add_library_entries(entries: &[LibraryEntry]) {
+    if let Ok(mut conn) = acquire!() {
+        let mut tx = sqlx::Connection::begin(&mut *conn)
+            .await
+            .expect("database failure");
+
+        for entry in entries {
+            sqlx::query(
+                "DELETE FROM library_index WHERE kind = ?1 AND entity_id = ?2 AND source = ?3;",
+            )
+            .bind(entry.kind.to_string())
+            .bind(&entry.id)
+            .bind(entry.source.to_string())
+            .execute(&mut *tx)
+            .await
+            .expect("database failure");
+        }
+
+        insert_library_entries(&mut tx, entries)
+            .await
+            .expect("database failure");
+
+        tx.commit().await.expect("database failure");
+    }
+}
+
+pub async fn get_library_entries() -> Vec<LibraryEntry> {
+    if let Ok(mut conn) = acquire!() {
+        sqlx::query_as::<_, LibraryRow>(&format!("SELECT {LIBRARY_COLUMNS} FROM library_index;"))
+            .fetch_all(&mut *conn)
+            .await
+            .unwrap_or_default()
+            .into_iter()
+            .filter_map(library_entry_from_row)
+            .collect()
+    } else {
+        Vec::new()
+    }
+}
+
+/// Searches the library index for entries containing every word of `query`, best matches
+/// first. The trigram index needs words of three letters or more, shorter ones are
+/// matched with LIKE.
+pub async fn search_library(query: &str, limit: u32) -> Vec<LibraryEntry> {
+    let words = query
+        .split_whitespace()
+        .map(|w| w.replace('"', ""))
+        .filter(|w| !w.is_empty())
+        .collect::<Vec<String>>();
+
+    if words.is_empty() {
+        return Vec::new();
+    }
+
+    let (long, short): (Vec<String>, Vec<String>) =
+        words.into_iter().partition(|w| w.chars().count() >= 3);
+
+    let mut conditions = Vec::new();
+
+    if !long.is_empty() {
+        conditions.push("library_index MATCH ?".to_string());
+    }
+
+    for _ in &short {
+        conditions.push(
+            "(title || ' ' || IFNULL(artist, '') || ' ' || IFNULL(album, '')) LIKE ? ESCAPE '\\'"
+                .to_string(),
+        );
+    }
+
+    let order = if long.is_empty() {
+        "ROWID DESC"
+    } else {
+        "rank"
+    };
+    let sql = format!(
+        "SELECT {LIBRARY_COLUMNS} FROM library_index WHERE {} ORDER BY {order} LIMIT {limit};",
+        conditions.join(" AND ")
+    );
+
+    if let Ok(mut conn) = acquire!() {
+        let mut query = sqlx::query_as::<_, LibraryRow>(&sql);
+
+        if !long.is_empty() {
+            let expression = long
+                .iter()
+                .map(|w| format!("\"{w}\""))
+                .collect::<Vec<String>>()
+                .join(" ");
+
+            query = query.bind(expression);
+        }
+
+        for word in &short {
+            query = query.bind(format!("%{}%", escape_like(word)));
+        }
+
+        query
+            .fetch_all(&mut *conn)
+            .await
+            .unwrap_or_default()
+            .into_iter()
+            .filter_map(library_entry_from_row)
+            .collect()
+    } else {
+        Vec::new()
+    }
+}
+
+/// `word` taken literally in a LIKE pattern escaped with a backslash, so `%` and `_` in a
+/// search don't match anything.
+fn escape_like(word: &str) -> String {
+    word.replace('\\', "\\\\")
+        .replace('%', "\\%")
+        .replace('_', "\\_")
+}
+
+pub async fn add_history_entry(entry: &HistoryEntry) {
+    if let Ok(mut conn) = acquire!() {
+        sqlx::query(
+            "INSERT INTO history (track_id, title, artist, album, album_id, played_at, duration_played, duration) VALUES(?1,?2,?3,?4,?5,?6,?7,?8);",
+        )
+        .bind(entry.track_id)
+        .bind(&entry.title)
+        .bind(&entry.artist)
+        .bind(&entry.album)
+        .bind(&entry.album_id)
+        .bind(entry.played_at)
+        .bind(entry.duration_played)
+        .bind(entry.duration)
+        .execute(&mut *conn)
+        .await
+        .expect("database failure");
+    }
+}
+
+/// The last `limit` plays, newest first.
+pub async fn recent_tracks(limit: u32) -> Vec<HistoryEntry> {
+    if let Ok(mut conn) = acquire!() {
+        sqlx::query_as::<_, HistoryEntry>(
+            "SELECT track_id, title, artist, album, album_id, played_at, duration_played, duration FROM history ORDER BY played_at DESC LIMIT ?1;",
+        )
+        .bind(limit)
+        .fetch_all(&mut *conn)
+        .await
+        .unwrap_or_default()
+    } else {
+        Vec::new()
+    }
+}
+
+/// The `limit` tracks with the most plays, the one played longest first on a tie.
+pub async fn most_played(limit: u32) -> Vec<MostPlayed> {
+    if let Ok(mut conn) = acquire!() {
+        sqlx::query_as::<_, MostPlayed>(
+            "SELECT track_id, MAX(title) AS title, MAX(artist) AS artist, MAX(album) AS album, COUNT(*) AS plays, SUM(duration_played) AS seconds_played FROM history GROUP BY track_id ORDER BY plays DESC, seconds_played DESC LIMIT ?1;",
+        )
+        .bind(limit)
+        .fetch_all(&mut *conn)
+        .await
+        .unwrap_or_default()
+    } else {
+        Vec::new()
+    }
+}
+
+/// The id of the local file at `path`, given the next free one when it is new.
+pub async fn local_track_id(path: &str) -> Option<i64> {
+    let mut conn = acquire!().ok()?;
+
+    sqlx::query("INSERT OR IGNORE INTO local_tracks (path) VALUES(?1);")
+        .bind(path)
+        .execute(&mut *conn)
+        .await
+        .expect("database failure");
+
+    sqlx::query_scalar::<_, i64>("SELECT id FROM local_tracks WHERE path = ?1;")
+        .bind(path)
+        .fetch_optional(&mut *conn)
+        .await
+        .ok()
+        .flatten()
+}
+
+type QueueSnapshotRow = (String, String, i64, i64);
+
+fn queue_snapshot_from_row(row: QueueSnapshotRow) -> Option<QueueSnapshot> {
+    let (name, tracklist, position, saved_at) = row;
+
+    Some(QueueSnapshot {
+        name,
+        tracklist: serde_json::from_str(&tracklist).ok()?,
+        position: position as u64,
+        saved_at,
+    })
+}
+
+/// The saved queues, the most recently saved first.
+pub async fn get_queue_snapshots() -> Vec<QueueSnapshot> {
+    if let Ok(mut conn) = acquire!() {
+        sqlx::query_as::<_, QueueSnapshotRow>(
+            "SELECT name, tracklist, position, saved_at FROM queue_snapshots ORDER BY saved_at DESC;",
+        )
+        .fetch_all(&mut *conn)
+        .await
+        .unwrap_or_default()
+        .into_iter()
+        .filter_map(queue_snapshot_from_row)
+        .collect()
+    } else {
+        Vec::new()
+    }
+}
+
+pub async fn get_queue_snapshot(name: &str) -> Option<QueueSnapshot> {
+    if let Ok(mut conn) = acquire!() {
+        sqlx::query_as::<_, QueueSnapshotRow>(
+            "SELECT name, tracklist, position, saved_at FROM queue_snapshots WHERE name = ?1;",
+        )
+        .bind(name)
+        .fetch_optional(&mut *conn)
+        .await
+        .ok()
+        .flatten()
+        .and_then(queue_snapshot_from_row)
+    } else {
+        None
+    }
+}
+
+pub async fn save_queue_snapshot(snapshot: &QueueSnapshot) {
+    if let Ok(mut conn) = acquire!() {
+        let tracklist =
+            serde_json::to_string(&snapshot.tracklist).expect("failed to serialize the queue");
+
+        sqlx::query("INSERT OR REPLACE INTO queue_snapshots VALUES(?1,?2,?3,?4);")
+            .bind(&snapshot.name)
+            .bind(tracklist)
+            .bind(snapshot.position as i64)
+            .bind(snapshot.saved_at)
+            .execute(&mut *conn)
+            .await
+            .expect("database failure");
+    }
+}
+
+/// Removes a saved queue, returning false if there was none with that name.
+pub async fn remove_queue_snapshot(name: &str) -> bool {
+    if let Ok(mut conn) = acquire!() {
+        sqlx::query("DELETE FROM queue_snapshots WHERE name = ?1;")
+            .bind(name)
+            .execute(&mut *conn)
+            .await
+            .map(|r| r.rows_affected() > 0)
+            .unwrap_or_default()
+    } else {
+        false
+    }
+}
+
+/// What a metadata cache entry holds.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum MetadataKind {
+    Album,
+    Track,
+    Playlist,
+}
+
+impl MetadataKind {
+    fn as_str(&self) -> &'static str {
+        match self {
+            MetadataKind::Album => "album",
+            MetadataKind::Track => "track",
+            MetadataKind::Playlist => "playlist",
+        }
+    }
+}
+
+/// The cached album, track or playlist with `id`, if it was fetched less than `max_age`
+/// seconds ago.
+pub async fn get_cached_metadata<T: DeserializeOwned>(
+    kind: MetadataKind,
+    id: &str,
+    max_age: i64,
+) -> Option<T> {
+    let since = chrono::Utc::now().timestamp() - max_age;
+
+    if let Ok(mut conn) = acquire!() {
+        sqlx::query_scalar::<_, String>(
+            "SELECT data FROM metadata_cache WHERE kind = ?1 AND id = ?2 AND fetched_at > ?3;",
+        )
+        .bind(kind.as_str())
+        .bind(id)
+        .bind(since)
+        .fetch_optional(&mut *conn)
+        .await
+        .ok()
+        .flatten()
+        .and_then(|data| serde_json::from_str(&data).ok())
+    } else {
+        None
+    }
+}
+
+pub async fn set_cached_metadata<T: Serialize>(kind: MetadataKind, id: &str, item: &T) {
+    if let Ok(mut conn) = acquire!() {
+        let data = serde_json::to_string(item).expect("failed to serialize metadata");
+
+        sqlx::query("INSERT OR REPLACE INTO metadata_cache VALUES(?1,?2,?3,?4);")
+            .bind(kind.as_str())
+            .bind(id)
+            .bind(data)
+            .bind(chrono::Utc::now().timestamp())
+            .execute(&mut *conn)
+            .await
+            .expect("database failure");
+    }
+}
+
+pub async fn remove_cached_metadata(kind: MetadataKind, id: &str) {
+    if let Ok(mut conn) = acquire!() {
+        sqlx::query("DELETE FROM metadata_cache WHERE kind = ?1 AND id = ?2;")
+            .bind(kind.as_str())
+            .bind(id)
+            .execute(&mut *conn)
+            .await
+            .expect("database failure");
+    }
+}
+
+/// Drops every cache entry fetched more than `max_age` seconds ago.
+pub async fn prune_metadata_cache(max_age: i64) {
+    let before = chrono::Utc::now().timestamp() - max_age;
+
+    if let Ok(mut conn) = acquire!() {
+        sqlx::query("DELETE FROM metadata_cache WHERE fetched_at <= ?1;")
+            .bind(before)
+            .execute(&mut *conn)
+            .await
+            .expect("database failure");
+    }
+}
+
 pub async fn close() {
     POOL.get().unwrap().close().await;
 }
+
+#[test]
+fn like_wildcards_are_escaped() {
+    assert_eq!(escape_like("50%"), "50\\%");
+    assert_eq!(escape_like("a_b"), "a\\_b");
+    assert_eq!(escape_like("c\\d"), "c\\\\d");
+}
@@ -0,0 +1,146 @@
+use crate::network;
+use axum_server::tls_rustls::RustlsConfig;
+use rcgen::CertifiedKey;
+use rustls::{ClientConfig, RootCertStore};
+use snafu::prelude::*;
+use std::{
+    fs::File,
+    io::BufReader,
+    path::{Path, PathBuf},
+};
+
+#[derive(Debug, Snafu)]
+pub enum Error {
+    #[snafu(display("failed to generate a certificate: {message}"))]
+    Generate { message: String },
+    #[snafu(display("failed to read {}: {message}", path.display()))]
+    Read { path: PathBuf, message: String },
+    #[snafu(display("failed to write {}: {message}", path.display()))]
+    Write { path: PathBuf, message: String },
+}
+
+pub type Result<T, E = Error> = std::result::Result<T, E>;
+
+/// Where generated certificates are stored.
+pub fn tls_dir() -> Option<PathBuf> {
+    let mut path = dirs::data_local_dir()?;
+    path.push("hifi-rs");
+    path.push("tls");
+
+    Some(path)
+}
+
+/// The names a generated certificate is valid for when none are given:
+/// localhost, the loopback addresses and this machine's current address.
+pub fn default_hosts() -> Vec<String> {
+    let mut hosts = vec![
+        "localhost".to_string(),
+        "127.0.0.1".to_string(),
+        "::1".to_string(),
+    ];
+
+    if let Some(address) = network::local_address() {
+        hosts.push(address.to_string());
+    }
+
+    hosts
+}
+
+/// Generate a self-signed certificate for `hosts` and write it and its key to the tls
+/// directory. Returns the paths of the certificate and the key.
+pub fn generate(hosts: Vec<String>) -> Result<(PathBuf, PathBuf)> {
+    let CertifiedKey { cert, key_pair } =
+        rcgen::generate_simple_self_signed(hosts).map_err(|error| Error::Generate {
+            message: error.to_string(),
+        })?;
+
+    let dir = tls_dir().ok_or_else(|| Error::Generate {
+        message: "no data directory".to_string(),
+    })?;
+
+    std::fs::create_dir_all(&dir).map_err(|error| Error::Write {
+        path: dir.clone(),
+        message: error.to_string(),
+    })?;
+
+    let cert_path = dir.join("cert.pem");
+    let key_path = dir.join("key.pem");
+
+    write(&cert_path, cert.pem())?;
+    write_private(&key_path, key_pair.serialize_pem())?;
+
+    Ok((cert_path, key_path))
+}
+
+fn write(path: &Path, contents: String) -> Result<()> {
+    std::fs::write(path, contents).map_err(|error| Error::Write {
+        path: path.to_path_buf(),
+        message: error.to_string(),
+    })
+}
+
+/// Write a key only its owner can read. The file is created that way, so the key is never
+/// readable by others, not even for a moment. One from before is replaced, as opening it
+/// would keep its permissions.
+fn write_private(path: &Path, contents: String) -> Result<()> {
+    use std::io::Write;
+
+    let error = |error: std::io::Error| Error::Write {
+        path: path.to_path_buf(),
+        message: error.to_string(),
+    };
+
+    if let Err(removing) = std::fs::remove_file(path) {
+        if removing.kind() != std::io::ErrorKind::NotFound {
+            return Err(error(removing));
+        }
+    }
+
+    let mut options = std::fs::OpenOptions::new();
+    options.write(true).create_new(true);
+
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::OpenOptionsExt;
+
+        options.mode(0o600);
+    }
+
+    options
+        .open(path)
+        .and_then(|mut file| file.write_all(contents.as_bytes()))
+        .map_err(error)
+}
+
+/// The server side of the web server's TLS.
+pub async fn server_config(cert: &Path, key: &Path) -> Result<RustlsConfig> {
+    RustlsConfig::from_pem_file(cert, key)
+        .await
+        .map_err(|error| Error::Read {
+            path: cert.to_path_buf(),
+            message: error.to_string(),
+        })
+}
+
+/// A client config that trusts only the certificate at `cert`, for talking to
+/// a player that uses a self-signed certificate.
+pub fn client_config(cert: &Path) -> Result<ClientConfig> {
+    let read_error = |message: String| Error::Read {
+        path: cert.to_path_buf(),
+        message,
+    };
+
+    let file = File::open(cert).map_err(|error| read_error(error.to_string()))?;
+    let mut roots = RootCertStore::empty();
+
+    for cert in rustls_pemfile::certs(&mut BufReader::new(file)) {
+        let cert = cert.map_err(|error| read_error(error.to_string()))?;
+        roots
+            .add(cert)
+            .map_err(|error| read_error(error.to_string()))?;
+    }
+
+    Ok(ClientConfig::builder()
+        .with_root_certificates(roots)
+        .with_no_client_auth())
+}
@@ -1,48 +1,168 @@
 use axum::{
     body::Body,
-    extract::ws::{Message, WebSocket, WebSocketUpgrade},
+    extract::{
+        ws::{Message, WebSocket, WebSocketUpgrade},
+        ConnectInfo, Query,
+    },
     http::{header, Request, Response},
     response::IntoResponse,
     routing::get,
-    Router,
+    Json, Router,
 };
-use futures::{SinkExt, StreamExt};
+use futures::{stream, SinkExt, StreamExt};
+use hifirs_qobuz_api::client::metrics::{self, EndpointLatency};
 use include_dir::{include_dir, Dir};
 use mime_guess::{mime::HTML, MimeGuess};
+use once_cell::sync::Lazy;
 use serde_json::{json, Value};
-use std::{net::SocketAddr, path::PathBuf, str::FromStr};
-use tokio::select;
+use std::{
+    collections::{BTreeMap, HashMap},
+    net::SocketAddr,
+    path::PathBuf,
+    str::FromStr,
+    sync::{
+        atomic::{AtomicBool, AtomicU64, Ordering},
+        Arc,
+    },
+};
+use tokio::{select, sync::RwLock};
 
-use crate::player::{self, actions::Action, notification::Notification};
+use crate::{
+    config::ConfigKey,
+    ctl, network,
+    player::{
+        self,
+        actions::Action,
+        error::Error,
+        notification::{Notification, RemoteClient},
+        party, simple,
+    },
+    service::SearchLimits,
+    sql::db,
+    tls,
+};
 
 static SITE: Dir = include_dir!("$CARGO_MANIFEST_DIR/../www/build");
 
-pub async fn init(binding_interface: SocketAddr) {
+static NEXT_CLIENT_ID: AtomicU64 = AtomicU64::new(1);
+static CLIENTS: Lazy<RwLock<BTreeMap<u64, RemoteClient>>> =
+    Lazy::new(|| RwLock::new(BTreeMap::new()));
+
+/// Returns the list of currently connected remote clients.
+pub async fn clients() -> Vec<RemoteClient> {
+    CLIENTS.read().await.values().cloned().collect()
+}
+
+/// Serves the web UI and websocket until the player quits. When the network changes
+/// the listener is closed and bound again, so it doesn't stay on an address that is gone.
+/// With a certificate and key, everything is served over TLS. If they can't be loaded
+/// nothing is served, rather than falling back to plain HTTP.
+pub async fn init(binding_interface: SocketAddr, tls: Option<(PathBuf, PathBuf)>) {
     let app = Router::new()
         .route("/ws", get(ws_handler))
+        .route("/metrics", get(metrics_handler))
         .route("/*key", get(static_handler))
         .route("/", get(static_handler));
 
-    debug!("listening on {}", binding_interface);
+    let tls_config = match tls {
+        Some((cert, key)) => match tls::server_config(&cert, &key).await {
+            Ok(config) => Some(config),
+            Err(error) => {
+                error!(%error, "not starting the web server");
+                return;
+            }
+        },
+        None => None,
+    };
+
+    loop {
+        let Some(listener) = bind(binding_interface).await else {
+            break;
+        };
 
-    let listener = tokio::net::TcpListener::bind(&binding_interface)
-        .await
-        .unwrap();
+        debug!("listening on {}", binding_interface);
 
-    axum::serve(listener, app)
-        .with_graceful_shutdown(async {
+        let quitting = Arc::new(AtomicBool::new(false));
+        let shutdown = quitting.clone();
+        let shutdown_signal = async move {
             let mut broadcast_receiver = player::notify_receiver();
 
-            loop {
-                if let Some(message) = broadcast_receiver.next().await {
-                    if message == Notification::Quit {
+            while let Some(message) = broadcast_receiver.next().await {
+                match message {
+                    Notification::Quit => {
+                        shutdown.store(true, Ordering::Relaxed);
                         break;
                     }
+                    Notification::NetworkChanged { .. } => break,
+                    _ => {}
                 }
             }
-        })
-        .await
-        .unwrap();
+        };
+
+        let service = app
+            .clone()
+            .into_make_service_with_connect_info::<SocketAddr>();
+
+        match &tls_config {
+            Some(config) => {
+                let handle = axum_server::Handle::new();
+                let shutdown_handle = handle.clone();
+
+                tokio::spawn(async move {
+                    shutdown_signal.await;
+                    shutdown_handle.graceful_shutdown(None);
+                });
+
+                let listener = listener.into_std().expect("failed to convert the listener");
+
+                if let Err(error) = axum_server::from_tcp_rustls(listener, config.clone())
+                    .handle(handle)
+                    .serve(service)
+                    .await
+                {
+                    debug!(?error, "web server stopped");
+                }
+            }
+            None => {
+                axum::serve(listener, service)
+                    .with_graceful_shutdown(shutdown_signal)
+                    .await
+                    .unwrap();
+            }
+        }
+
+        if quitting.load(Ordering::Relaxed) {
+            break;
+        }
+
+        debug!("network changed, rebinding {}", binding_interface);
+    }
+}
+
+/// Bind the listener, retrying while the interface is unavailable. Returns None if the player quits first.
+async fn bind(binding_interface: SocketAddr) -> Option<tokio::net::TcpListener> {
+    let mut broadcast_receiver = player::notify_receiver();
+
+    loop {
+        match tokio::net::TcpListener::bind(&binding_interface).await {
+            Ok(listener) => return Some(listener),
+            Err(error) => debug!(?error, "failed to bind {}, retrying", binding_interface),
+        }
+
+        select! {
+            _ = tokio::time::sleep(network::CHECK_INTERVAL) => {}
+            Some(message) = broadcast_receiver.next() => {
+                if message == Notification::Quit {
+                    return None;
+                }
+            }
+        }
+    }
+}
+
+/// Latency of the Qobuz API calls per endpoint, as JSON.
+async fn metrics_handler() -> Json<Vec<EndpointLatency>> {
+    Json(metrics::summary())
 }
 
 async fn static_handler(req: Request<Body>) -> impl IntoResponse {
@@ -87,18 +207,52 @@ async fn static_handler(req: Request<Body>) -> impl IntoResponse {
         .expect("error making body")
 }
 
-async fn ws_handler(ws: WebSocketUpgrade) -> impl IntoResponse {
-    ws.on_upgrade(handle_connection)
+/// Tell the client an action failed, e.g. because party mode locks it.
+async fn send_error(rt_sender: &flume::Sender<Value>, error: Error) {
+    if let Err(error) = rt_sender
+        .send_async(json!({ "error": { "error": error } }))
+        .await
+    {
+        debug!("error sending response {}", error);
+    }
 }
 
-async fn handle_connection(socket: WebSocket) {
-    debug!("new websocket connection");
+async fn ws_handler(
+    ws: WebSocketUpgrade,
+    ConnectInfo(address): ConnectInfo<SocketAddr>,
+    Query(params): Query<HashMap<String, String>>,
+) -> impl IntoResponse {
+    let client = RemoteClient {
+        id: NEXT_CLIENT_ID.fetch_add(1, Ordering::Relaxed),
+        name: params.get("name").cloned(),
+        address,
+        connected_at: chrono::Utc::now().timestamp(),
+        topics: params
+            .get("topics")
+            .map(|t| t.split(',').map(|t| t.trim().to_string()).collect())
+            .unwrap_or_default(),
+    };
+
+    ws.on_upgrade(move |socket| handle_connection(socket, client))
+}
+
+async fn handle_connection(socket: WebSocket, client: RemoteClient) {
+    debug!("new websocket connection from {}", client.address);
+    let client_id = client.id;
+
+    CLIENTS.write().await.insert(client_id, client.clone());
+
+    if let Err(error) = player::notify(Notification::ClientConnected { client }).await {
+        debug!(?error);
+    }
+
     let (mut sender, mut receiver) = socket.split();
     let (rt_sender, rt_receiver) = flume::bounded::<Value>(1);
 
     let mut send_task = tokio::spawn(async move {
         debug!("spawning send task");
-        let mut broadcast_receiver = player::notify_receiver();
+        let mut broadcast_receiver =
+            stream::select(player::notify_receiver(), player::visualization_receiver());
 
         if let Ok(ct) = serde_json::to_string(&Notification::CurrentTrackList {
             list: player::current_tracklist().await,
@@ -110,6 +264,12 @@ async fn handle_connection(socket: WebSocket) {
             if let Ok(p) = serde_json::to_string(&Notification::Position { clock: position }) {
                 sender.send(Message::Text(p)).await.expect("error");
             }
+
+            if let Some(progress) = player::list_progress(position).await {
+                if let Ok(p) = serde_json::to_string(&Notification::ListProgress { progress }) {
+                    sender.send(Message::Text(p)).await.expect("error");
+                }
+            }
         }
 
         if let Ok(s) = serde_json::to_string(&Notification::Status {
@@ -123,6 +283,21 @@ async fn handle_connection(socket: WebSocket) {
         loop {
             select! {
                 Some(message) = broadcast_receiver.next() => {
+                    // The connection may be on an address that no longer exists, close it
+                    // so the client reconnects to the new listener.
+                    let network_changed = matches!(message, Notification::NetworkChanged { .. });
+
+                    let wanted = CLIENTS
+                        .read()
+                        .await
+                        .get(&client_id)
+                        .map(|c| c.wants(&message.topic()))
+                        .unwrap_or(true);
+
+                    if !wanted && !network_changed {
+                        continue;
+                    }
+
                     let json = serde_json::to_string(&message).expect("error making json");
                     match sender.send(Message::Text(json)).await {
                         Ok(()) => {}
@@ -130,6 +305,10 @@ async fn handle_connection(socket: WebSocket) {
                             debug!(?error)
                         }
                     }
+
+                    if network_changed {
+                        break;
+                    }
                 }
                 Some(response) = rt_stream.next() => {
                     let json = serde_json::to_string(&response).expect("error making json");
@@ -153,29 +332,283 @@ async fn handle_connection(socket: WebSocket) {
                     if let Message::Text(s) = message {
                         if let Ok(action) = serde_json::from_str::<Action>(&s) {
                             debug!(?action);
+
+                            let locked = if party::is_enabled() && !action.allowed_in_party_mode() {
+                                Some(Error::PartyMode)
+                            } else if simple::is_enabled() && !action.allowed_in_simple_mode() {
+                                Some(Error::SimpleMode)
+                            } else {
+                                None
+                            };
+
+                            // The player checks the modes again as it acts, in case one was
+                            // turned on since. Its errors are sent back the same way.
+                            if let Some(error) = locked {
+                                send_error(&rt_sender, error).await;
+                                continue;
+                            }
+
                             match action {
-                                Action::Play => player::play().await.expect(""),
-                                Action::Pause => player::pause().await.expect(""),
-                                Action::PlayPause => player::play_pause().await.expect(""),
-                                Action::Next => player::next().await.expect(""),
-                                Action::Previous => player::previous().await.expect(""),
-                                Action::Stop => player::stop().await.expect(""),
-                                Action::Quit => player::quit().await.expect(""),
-                                Action::SkipTo { num } => player::skip(num, true).await.expect(""),
-                                Action::JumpForward => player::jump_forward().await.expect(""),
-                                Action::JumpBackward => player::jump_backward().await.expect(""),
-                                Action::PlayAlbum { album_id } => {
-                                    player::play_album(&album_id).await.expect("")
+                                Action::Play => {
+                                    if let Err(error) = player::play().await {
+                                        send_error(&rt_sender, error).await;
+                                    }
+                                }
+                                Action::Pause => {
+                                    if let Err(error) = player::pause().await {
+                                        send_error(&rt_sender, error).await;
+                                    }
+                                }
+                                Action::PlayPause => {
+                                    if let Err(error) = player::play_pause().await {
+                                        send_error(&rt_sender, error).await;
+                                    }
+                                }
+                                Action::Next => {
+                                    if let Err(error) = player::next().await {
+                                        send_error(&rt_sender, error).await;
+                                    }
+                                }
+                                Action::Previous => {
+                                    if let Err(error) = player::previous().await {
+                                        send_error(&rt_sender, error).await;
+                                    }
+                                }
+                                Action::StartOver => {
+                                    if let Err(error) = player::start_over().await {
+                                        debug!(?error);
+                                    }
+                                }
+                                Action::RestartList => {
+                                    if let Err(error) = player::restart_list().await {
+                                        debug!(?error);
+                                    }
+                                }
+                                Action::Stop => {
+                                    if let Err(error) = player::stop().await {
+                                        send_error(&rt_sender, error).await;
+                                    }
+                                }
+                                Action::Quit => {
+                                    if let Err(error) = player::quit().await {
+                                        send_error(&rt_sender, error).await;
+                                    }
+                                }
+                                Action::SkipTo { num, generation } => {
+                                    if let Err(error) = player::skip_to(num, generation).await {
+                                        let list = player::current_tracklist().await;
+                                        match rt_sender
+                                            .send_async(json!({
+                                                "error": { "error": error },
+                                                "currentTrackList": { "list": list }
+                                            }))
+                                            .await
+                                        {
+                                            Ok(_) => {}
+                                            Err(error) => {
+                                                debug!("error sending response {}", error)
+                                            }
+                                        }
+                                    }
+                                }
+                                Action::JumpForward => {
+                                    if let Err(error) = player::jump_forward().await {
+                                        send_error(&rt_sender, error).await;
+                                    }
+                                }
+                                Action::JumpBackward => {
+                                    if let Err(error) = player::jump_backward().await {
+                                        send_error(&rt_sender, error).await;
+                                    }
+                                }
+                                Action::Seek { seconds, relative } => {
+                                    if let Err(error) =
+                                        player::seek_seconds(seconds, relative).await
+                                    {
+                                        debug!(?error);
+                                    }
+                                }
+                                Action::SeekTo { position } => {
+                                    if let Err(error) = player::seek_to(position).await {
+                                        debug!(?error);
+                                    }
+                                }
+                                Action::SeekPercent(percent) => {
+                                    if let Err(error) = player::seek_percent(percent).await {
+                                        debug!(?error);
+                                    }
+                                }
+                                Action::SetVolume { volume } => {
+                                    if let Err(error) = player::set_volume(volume).await {
+                                        debug!(?error);
+                                    }
+                                }
+                                Action::ToggleShuffle => {
+                                    if let Err(error) = player::toggle_shuffle().await {
+                                        debug!(?error);
+                                    }
+                                }
+                                Action::Status => {
+                                    let status = ctl::PlayerStatus::current().await;
+                                    match rt_sender
+                                        .send_async(json!({ "playerStatus": status }))
+                                        .await
+                                    {
+                                        Ok(_) => {}
+                                        Err(error) => debug!("error sending response {}", error),
+                                    }
+                                }
+                                Action::FetchTrackList => {
+                                    let list = player::current_tracklist().await;
+                                    match rt_sender
+                                        .send_async(json!({ "currentTrackList": { "list": list } }))
+                                        .await
+                                    {
+                                        Ok(_) => {}
+                                        Err(error) => debug!("error sending response {}", error),
+                                    }
+                                }
+                                Action::RemoveFromQueue {
+                                    position,
+                                    generation,
+                                } => {
+                                    if let Err(error) =
+                                        player::remove_from_queue(position, generation).await
+                                    {
+                                        let list = player::current_tracklist().await;
+                                        match rt_sender
+                                            .send_async(json!({
+                                                "error": { "error": error },
+                                                "currentTrackList": { "list": list }
+                                            }))
+                                            .await
+                                        {
+                                            Ok(_) => {}
+                                            Err(error) => {
+                                                debug!("error sending response {}", error)
+                                            }
+                                        }
+                                    }
+                                }
+                                Action::MoveInQueue {
+                                    from,
+                                    to,
+                                    generation,
+                                } => {
+                                    if let Err(error) =
+                                        player::move_in_queue(from, to, generation).await
+                                    {
+                                        let list = player::current_tracklist().await;
+                                        match rt_sender
+                                            .send_async(json!({
+                                                "error": { "error": error },
+                                                "currentTrackList": { "list": list }
+                                            }))
+                                            .await
+                                        {
+                                            Ok(_) => {}
+                                            Err(error) => {
+                                                debug!("error sending response {}", error)
+                                            }
+                                        }
+                                    }
+                                }
+                                Action::SetQuality { quality } => {
+                                    if let Err(error) = player::switch_quality(quality).await {
+                                        debug!(?error);
+                                    }
+                                }
+                                Action::SetNextQuality { quality } => {
+                                    if let Err(error) = player::set_next_quality(quality).await {
+                                        debug!(?error);
+                                    }
+                                }
+                                Action::SetOutputProfile { name } => {
+                                    if let Some(profile) = db::get_output_profile(&name).await {
+                                        match player::set_output_profile(profile).await {
+                                            Ok(()) => {
+                                                db::set_config_value(
+                                                    ConfigKey::OutputProfile,
+                                                    Some(name),
+                                                )
+                                                .await
+                                            }
+                                            Err(error) => debug!(?error),
+                                        }
+                                    }
+                                }
+                                Action::PlayAlbum { album_id, quality } => {
+                                    let result = match quality {
+                                        Some(quality) => {
+                                            player::play_album_with_quality(&album_id, quality)
+                                                .await
+                                        }
+                                        None => player::play_album(&album_id).await,
+                                    };
+
+                                    if let Err(error) = result {
+                                        debug!(?error);
+                                    }
                                 }
                                 Action::PlayTrack { track_id } => {
-                                    player::play_track(track_id).await.expect("")
+                                    if let Err(error) = player::play_track(track_id).await {
+                                        send_error(&rt_sender, error).await;
+                                    }
+                                }
+                                Action::PlayAlbumFromTrack { album_id, track_id } => {
+                                    if let Err(error) =
+                                        player::play_album_from_track(&album_id, track_id).await
+                                    {
+                                        debug!(?error);
+                                    }
+                                }
+                                Action::AddTrackToQueue { track_id } => {
+                                    if let Err(error) = player::add_track_to_queue(track_id).await {
+                                        match rt_sender
+                                            .send_async(json!({ "error": { "error": error } }))
+                                            .await
+                                        {
+                                            Ok(_) => {}
+                                            Err(error) => {
+                                                debug!("error sending response {}", error)
+                                            }
+                                        }
+                                    }
+                                }
+                                Action::AddAlbumToQueue { album_id } => {
+                                    if let Err(error) = player::add_album_to_queue(&album_id).await
+                                    {
+                                        match rt_sender
+                                            .send_async(json!({ "error": { "error": error } }))
+                                            .await
+                                        {
+                                            Ok(_) => {}
+                                            Err(error) => {
+                                                debug!("error sending response {}", error)
+                                            }
+                                        }
+                                    }
+                                }
+                                Action::PlayUri { uri } => {
+                                    if let Err(error) = player::play_uri(&uri).await {
+                                        send_error(&rt_sender, error).await;
+                                    }
                                 }
-                                Action::PlayUri { uri } => player::play_uri(&uri).await.expect(""),
                                 Action::PlayPlaylist { playlist_id } => {
-                                    player::play_playlist(playlist_id).await.expect("")
+                                    if let Err(error) = player::play_playlist(playlist_id).await {
+                                        send_error(&rt_sender, error).await;
+                                    }
                                 }
-                                Action::Search { query } => {
-                                    let results = player::search(&query).await;
+                                Action::Search {
+                                    query,
+                                    search_type,
+                                    limit,
+                                } => {
+                                    let limits = SearchLimits::only(
+                                        search_type.unwrap_or_default(),
+                                        limit.unwrap_or(SearchLimits::DEFAULT_LIMIT),
+                                    );
+                                    let results = player::search(&query, limits).await;
                                     match rt_sender
                                         .send_async(
                                             json!({ "searchResults": { "results": results }}),
@@ -212,6 +645,95 @@ async fn handle_connection(socket: WebSocket) {
                                         Err(error) => debug!("error sending response {}", error),
                                     }
                                 }
+                                Action::FetchPlaylist { playlist_id } => {
+                                    let result = player::playlist(playlist_id).await;
+                                    match rt_sender.send_async(json!({ "playlist": result })).await
+                                    {
+                                        Ok(_) => {}
+                                        Err(error) => debug!("error sending response {}", error),
+                                    }
+                                }
+                                Action::FetchAlbum { album_id } => {
+                                    let result = player::album(&album_id).await;
+                                    match rt_sender.send_async(json!({ "album": result })).await {
+                                        Ok(_) => {}
+                                        Err(error) => debug!("error sending response {}", error),
+                                    }
+                                }
+                                Action::FetchAlbums { album_ids } => {
+                                    let result = player::albums(album_ids).await;
+                                    match rt_sender.send_async(json!({ "albums": result })).await {
+                                        Ok(_) => {}
+                                        Err(error) => debug!("error sending response {}", error),
+                                    }
+                                }
+                                Action::FetchTracks { track_ids } => {
+                                    let result = player::tracks(track_ids).await;
+                                    match rt_sender.send_async(json!({ "tracks": result })).await {
+                                        Ok(_) => {}
+                                        Err(error) => debug!("error sending response {}", error),
+                                    }
+                                }
+                                Action::Identify { name } => {
+                                    if let Some(c) = CLIENTS.write().await.get_mut(&client_id) {
+                                        c.name = Some(name);
+                                    }
+                                }
+                                Action::Subscribe { topics } => {
+                                    if let Some(c) = CLIENTS.write().await.get_mut(&client_id) {
+                                        c.topics = topics;
+                                    }
+                                }
+                                Action::Clients => {
+                                    let results = clients().await;
+                                    match rt_sender.send_async(json!({ "clients": results })).await
+                                    {
+                                        Ok(_) => {}
+                                        Err(error) => debug!("error sending response {}", error),
+                                    }
+                                }
+                                Action::FetchBrowseCategories => {
+                                    let categories = player::browse_categories()
+                                        .await
+                                        .into_iter()
+                                        .map(|c| json!({ "category": c, "title": c.title() }))
+                                        .collect::<Vec<Value>>();
+                                    match rt_sender
+                                        .send_async(json!({ "browseCategories": categories }))
+                                        .await
+                                    {
+                                        Ok(_) => {}
+                                        Err(error) => debug!("error sending response {}", error),
+                                    }
+                                }
+                                Action::FetchGenres => {
+                                    let genres = player::genres().await;
+                                    match rt_sender.send_async(json!({ "genres": genres })).await {
+                                        Ok(_) => {}
+                                        Err(error) => debug!("error sending response {}", error),
+                                    }
+                                }
+                                Action::FetchFeaturedPlaylists => {
+                                    let playlists = player::featured_playlists().await;
+                                    match rt_sender
+                                        .send_async(json!({ "featuredPlaylists": playlists }))
+                                        .await
+                                    {
+                                        Ok(_) => {}
+                                        Err(error) => debug!("error sending response {}", error),
+                                    }
+                                }
+                                Action::Browse {
+                                    kind,
+                                    genre,
+                                    offset,
+                                } => {
+                                    let page = player::browse(kind, genre, offset).await;
+                                    match rt_sender.send_async(json!({ "browse": page })).await {
+                                        Ok(_) => {}
+                                        Err(error) => debug!("error sending response {}", error),
+                                    }
+                                }
                                 Action::FetchUserPlaylists => {
                                     let results = player::user_playlists().await;
                                     match rt_sender
@@ -222,6 +744,69 @@ async fn handle_connection(socket: WebSocket) {
                                         Err(error) => debug!("error sending response {}", error),
                                     }
                                 }
+                                Action::FetchOutputProfiles => {
+                                    let results = db::get_output_profiles().await;
+                                    match rt_sender
+                                        .send_async(json!({ "outputProfiles": results }))
+                                        .await
+                                    {
+                                        Ok(_) => {}
+                                        Err(error) => debug!("error sending response {}", error),
+                                    }
+                                }
+                                Action::SetPartyMode { enabled, pin } => {
+                                    let result = if enabled {
+                                        player::enable_party_mode().await
+                                    } else {
+                                        player::disable_party_mode(
+                                            pin.as_deref().unwrap_or_default(),
+                                        )
+                                        .await
+                                    };
+
+                                    if let Err(error) = result {
+                                        match rt_sender
+                                            .send_async(json!({ "error": { "error": error } }))
+                                            .await
+                                        {
+                                            Ok(_) => {}
+                                            Err(error) => {
+                                                debug!("error sending response {}", error)
+                                            }
+                                        }
+                                    }
+                                }
+                                Action::SaveQueue { name } => {
+                                    if let Err(error) = player::save_queue(&name).await {
+                                        match rt_sender
+                                            .send_async(json!({ "error": { "error": error } }))
+                                            .await
+                                        {
+                                            Ok(_) => {}
+                                            Err(error) => {
+                                                debug!("error sending response {}", error)
+                                            }
+                                        }
+                                    }
+                                }
+                                Action::LoadQueue { name } => {
+                                    if let Err(error) = player::load_queue(&name).await {
+                                        match rt_sender
+                                            .send_async(json!({ "error": { "error": error } }))
+                                            .await
+                                        {
+                                            Ok(_) => {}
+                                            Err(error) => {
+                                                debug!("error sending response {}", error)
+                                            }
+                                        }
+                                    }
+                                }
+                                Action::ReloadConfig => {
+                                    if let Err(error) = player::reload_config().await {
+                                        debug!(?error);
+                                    }
+                                }
                             }
                         };
                     }
@@ -237,4 +822,12 @@ async fn handle_connection(socket: WebSocket) {
         _ = (&mut send_task) => recv_task.abort(),
         _ = (&mut recv_task) => send_task.abort(),
     };
+
+    if let Some(client) = CLIENTS.write().await.remove(&client_id) {
+        debug!("websocket connection closed for {}", client.address);
+
+        if let Err(error) = player::notify(Notification::ClientDisconnected { client }).await {
+            debug!(?error);
+        }
+    }
 }
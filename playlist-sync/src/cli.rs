@@ -1,10 +1,18 @@
-use crate::{qobuz, spotify};
-use clap::Parser;
+use crate::{
+    config::{self, Conflict, Direction, Source, SyncPair},
+    qobuz::{self, Qobuz, QobuzPlaylist},
+    spotify::{self, Spotify},
+    state,
+    tidal::{self, Tidal},
+    Isrc, SourcePlaylist,
+};
+use clap::{Parser, Subcommand};
 use console::Term;
+use hifirs_qobuz_api::client::matching::TrackHints;
 use indicatif::{MultiProgress, ProgressBar, ProgressDrawTarget, ProgressStyle};
 use rspotify::model::PlaylistId;
 use snafu::Snafu;
-use std::time::Duration;
+use std::{collections::HashSet, path::PathBuf, time::Duration};
 
 const TITLE: &str = r#"
 ╔═╗ ┌─┐┌┐ ┬ ┬┌─┐
@@ -19,24 +27,42 @@ const TITLE: &str = r#"
 "#;
 
 #[derive(Parser)]
-#[clap(name = TITLE, about = "spotify to qobuz one-way sync", long_about = None, rename_all = "camelCase")]
+#[clap(name = TITLE, about = "spotify and qobuz playlist sync", long_about = None, rename_all = "camelCase", subcommand_negates_reqs = true)]
 struct Cli {
-    /// Spotify playlist to sync from
-    #[clap(short = 's', long = "spotify")]
-    pub spotify_playlist_id: String,
+    #[clap(subcommand)]
+    command: Option<Commands>,
+    /// Spotify playlist to sync from, or the playlist on the service given with --source
+    #[clap(short = 's', long = "spotify", required = true)]
+    pub spotify_playlist_id: Option<String>,
+    /// The service to sync from
+    #[clap(long, value_enum, default_value_t = Source::Spotify)]
+    pub source: Source,
     /// Qobuz client to sync to
-    #[clap(short = 'q', long = "qobuz")]
-    pub qobuz_playlist_id: i64,
+    #[clap(short = 'q', long = "qobuz", required = true)]
+    pub qobuz_playlist_id: Option<i64>,
     #[clap(short = 'c', long = "check")]
     pub check_existing: bool,
 }
 
+#[derive(Subcommand)]
+enum Commands {
+    /// Sync every playlist pair in a config file and print a summary
+    Run {
+        #[clap(long)]
+        config: PathBuf,
+    },
+}
+
 #[derive(Debug, Snafu)]
 pub enum Error {
     #[snafu(display("Client Error: {error}"))]
     QobuzError { error: hifirs_qobuz_api::Error },
     #[snafu(display("Client Error: {error}"))]
     SpotifyError { error: spotify::Error },
+    #[snafu(display("Client Error: {error}"))]
+    TidalError { error: tidal::Error },
+    #[snafu(display("Config Error: {error}"))]
+    ConfigError { error: config::Error },
 }
 
 impl From<spotify::Error> for Error {
@@ -45,15 +71,69 @@ impl From<spotify::Error> for Error {
     }
 }
 
+impl From<tidal::Error> for Error {
+    fn from(error: tidal::Error) -> Self {
+        Error::TidalError { error }
+    }
+}
+
 impl From<hifirs_qobuz_api::Error> for Error {
     fn from(error: hifirs_qobuz_api::Error) -> Self {
         Error::QobuzError { error }
     }
 }
 
+impl From<config::Error> for Error {
+    fn from(error: config::Error) -> Self {
+        Error::ConfigError { error }
+    }
+}
+
+/// What syncing one playlist pair did, or would have done in a dry run.
+#[derive(Debug, Default)]
+struct SyncReport {
+    label: String,
+    /// Tracks missing from the other playlist.
+    missing: usize,
+    /// Missing tracks that were found on Qobuz and added.
+    added: usize,
+    /// Tracks of the Qobuz playlist that were found on Spotify and added there.
+    pushed: usize,
+    /// Tracks removed from one playlist because they were removed from the other.
+    removed: usize,
+    /// Missing tracks that couldn't be found on Qobuz.
+    not_found: usize,
+    /// Repeated entries removed from the Qobuz playlist.
+    duplicates: usize,
+    /// Tracks that couldn't be streamed and were replaced.
+    replaced: usize,
+    dry_run: bool,
+    error: Option<String>,
+}
+
+impl SyncReport {
+    fn new(pair: &SyncPair) -> Self {
+        Self {
+            label: pair.label(),
+            dry_run: pair.dry_run,
+            ..Default::default()
+        }
+    }
+}
+
 pub async fn run() -> Result<(), Error> {
     let cli = Cli::parse();
 
+    let pairs = match &cli.command {
+        Some(Commands::Run { config }) => config::load(config)?.pairs,
+        None => vec![SyncPair::new(
+            cli.source,
+            cli.spotify_playlist_id.clone().unwrap_or_default(),
+            cli.qobuz_playlist_id.unwrap_or_default(),
+            cli.check_existing,
+        )],
+    };
+
     let term = Term::stdout();
     let draw_target = ProgressDrawTarget::term(term.clone(), 15);
     let prog = MultiProgress::with_draw_target(draw_target);
@@ -62,26 +142,27 @@ pub async fn run() -> Result<(), Error> {
 
     println!("{TITLE}");
 
-    let spotify_prog = ProgressBar::new_spinner().with_prefix("spotify");
-    spotify_prog.enable_steady_tick(Duration::from_secs(1));
-    spotify_prog.set_style(
-        ProgressStyle::default_spinner()
-            .template("{prefix} {spinner} {wide_msg}")
-            .unwrap(),
-    );
+    let spotify_prog = spinner("spotify");
+    let tidal_prog = spinner("tidal  ");
+    let qobuz_prog = spinner("qobuz  ");
 
-    prog.add(spotify_prog.clone());
+    let mut sources = Sources::default();
 
-    let mut spotify = spotify::new(&spotify_prog).await;
-    spotify.auth().await?;
+    if pairs.iter().any(|p| p.source == Source::Spotify) {
+        prog.add(spotify_prog.clone());
 
-    let qobuz_prog = ProgressBar::new_spinner().with_prefix("qobuz  ");
-    qobuz_prog.enable_steady_tick(Duration::from_secs(1));
-    qobuz_prog.set_style(
-        ProgressStyle::default_spinner()
-            .template("{prefix} {spinner} {wide_msg}")
-            .unwrap(),
-    );
+        let mut spotify = spotify::new(&spotify_prog).await;
+        spotify.auth().await?;
+        sources.spotify = Some(spotify);
+    }
+
+    if pairs.iter().any(|p| p.source == Source::Tidal) {
+        prog.add(tidal_prog.clone());
+
+        let mut tidal = tidal::new(&tidal_prog)?;
+        tidal.auth().await?;
+        sources.tidal = Some(tidal);
+    }
 
     prog.add(qobuz_prog.clone());
 
@@ -90,90 +171,373 @@ pub async fn run() -> Result<(), Error> {
         .auth(env!("QOBUZ_USERNAME"), env!("QOBUZ_PASSWORD"))
         .await?;
 
-    let spotify_playlist = spotify
-        .playlist(
-            PlaylistId::from_id(cli.spotify_playlist_id.as_str())
-                .expect("invalid spotify playlist id"),
-        )
-        .await?;
+    if cli.command.is_none() {
+        sync_pair(&sources, &qobuz, &pairs[0], &prog).await?;
+        spotify_prog.finish_and_clear();
+        tidal_prog.finish_and_clear();
+
+        return Ok(());
+    }
+
+    let mut reports = vec![];
 
-    let qobuz_playlist = qobuz.playlist(cli.qobuz_playlist_id).await?;
+    for pair in &pairs {
+        let report = match sync_pair(&sources, &qobuz, pair, &prog).await {
+            Ok(report) => report,
+            Err(error) => SyncReport {
+                error: Some(error.to_string()),
+                ..SyncReport::new(pair)
+            },
+        };
 
-    if cli.check_existing {
+        reports.push(report);
+    }
+
+    spotify_prog.finish_and_clear();
+    tidal_prog.finish_and_clear();
+    qobuz_prog.finish_and_clear();
+
+    print_summary(&reports);
+
+    Ok(())
+}
+
+/// The signed in services playlists are synced from.
+#[derive(Default)]
+struct Sources<'a> {
+    spotify: Option<Spotify<'a>>,
+    tidal: Option<Tidal<'a>>,
+}
+
+fn spinner(prefix: &'static str) -> ProgressBar {
+    let spinner = ProgressBar::new_spinner().with_prefix(prefix);
+    spinner.enable_steady_tick(Duration::from_secs(1));
+    spinner.set_style(
+        ProgressStyle::default_spinner()
+            .template("{prefix} {spinner} {wide_msg}")
+            .unwrap(),
+    );
+
+    spinner
+}
+
+/// With `conflict = "remove"`, the tracks that were in both playlists after the last sync
+/// and are now only in `present`: they were removed from `other` on purpose.
+fn removed_since(
+    pair: &SyncPair,
+    last_synced: &HashSet<Isrc>,
+    present: &HashSet<Isrc>,
+    other: &HashSet<Isrc>,
+) -> HashSet<Isrc> {
+    if pair.conflict != Conflict::Remove {
+        return HashSet::new();
+    }
+
+    last_synced
+        .iter()
+        .filter(|isrc| present.contains(*isrc) && !other.contains(*isrc))
+        .cloned()
+        .collect()
+}
+
+async fn sync_pair(
+    sources: &Sources<'_>,
+    qobuz: &Qobuz<'_>,
+    pair: &SyncPair,
+    prog: &MultiProgress,
+) -> Result<SyncReport, Error> {
+    let mut report = SyncReport::new(pair);
+
+    let resume_file = state::resume_path(pair.qobuz);
+
+    if resume_file.exists() && !pair.dry_run {
+        qobuz.resume(&resume_file).await?;
+    }
+
+    let qobuz_playlist = qobuz.playlist(pair.qobuz).await?;
+
+    if pair.dedupe {
+        let duplicates = qobuz_playlist.duplicates();
+        report.duplicates = duplicates.len();
+
+        if !duplicates.is_empty() && !pair.dry_run {
+            qobuz.delete_track(qobuz_playlist.id(), duplicates).await?;
+        }
+    }
+
+    if pair.check_existing {
         if let Some(tracks) = qobuz_playlist.tracks() {
             for existing_track in tracks.items {
                 if !existing_track.streamable {
+                    report.replaced += 1;
+
+                    if pair.dry_run {
+                        continue;
+                    }
+
+                    // Entries are removed by their playlist track id, not the catalog one.
+                    let Some(playlist_track_id) = existing_track.playlist_track_id else {
+                        continue;
+                    };
+
                     qobuz
-                        .delete_track(qobuz_playlist.id(), vec![existing_track.id.to_string()])
+                        .delete_track(qobuz_playlist.id(), vec![playlist_track_id.to_string()])
                         .await?;
 
+                    let hints = TrackHints {
+                        title: Some(existing_track.title.clone()),
+                        artist: existing_track.performer.as_ref().map(|p| p.name.clone()),
+                        duration: Some(existing_track.duration),
+                    };
+
                     if let (Some(isrc), Some(track_position)) =
                         (existing_track.isrc, existing_track.position)
                     {
-                        let results = qobuz.search(&isrc.to_lowercase()).await;
-                        if !results.is_empty() {
-                            if let Some(found) = results.first() {
-                                qobuz
-                                    .add_track(&qobuz_playlist.id(), &found.id.to_string())
-                                    .await;
-
-                                qobuz
-                                    .update_track_position(
-                                        &qobuz_playlist.id(),
-                                        &found.id.to_string(),
-                                        track_position,
-                                    )
-                                    .await?;
-                            }
+                        if let Some(found) = qobuz.find_track(&isrc, &hints).await {
+                            let mut transaction = qobuz.transaction(pair.qobuz).await?;
+                            transaction.add_tracks(vec![found.id.to_string()]);
+                            transaction.move_track(found.id.to_string(), track_position);
+                            transaction.commit().await?;
                         }
                     }
                 }
             }
         }
+    } else if pair.source == Source::Tidal {
+        if pair.direction != Direction::SpotifyToQobuz {
+            return Err(Error::TidalError {
+                error: tidal::Error::ClientError {
+                    error: "Tidal playlists only sync into Qobuz".to_string(),
+                },
+            });
+        }
+
+        let tidal = sources.tidal.as_ref().expect("not signed into tidal");
+        let tidal_playlist = tidal.playlist(&pair.source_playlist).await?;
+
+        add_missing_to_qobuz(
+            qobuz,
+            &qobuz_playlist,
+            &tidal_playlist,
+            pair,
+            &HashSet::new(),
+            &mut report,
+            prog,
+        )
+        .await?;
     } else {
+        let spotify = sources.spotify.as_ref().expect("not signed into spotify");
+
+        let playlist_id = PlaylistId::from_id(pair.source_playlist.as_str()).map_err(|_| {
+            spotify::Error::ClientError {
+                error: format!("invalid spotify playlist id: {}", pair.source_playlist),
+            }
+        })?;
+
+        let spotify_playlist = spotify.playlist(playlist_id.as_ref()).await?;
+
+        let spotify_isrcs = spotify_playlist.isrc_list();
         let qobuz_isrcs = qobuz_playlist.irsc_list();
-        let missing_tracks = spotify_playlist.missing_tracks(qobuz_isrcs.clone());
 
-        let progress = ProgressBar::new(missing_tracks.len() as u64).with_prefix("syncing");
-        progress.set_style(
-            ProgressStyle::default_bar()
-                .template("{prefix} {wide_bar:.cyan/blue} [{pos}/{len}]")
-                .unwrap(),
-        );
+        let two_way = pair.direction == Direction::Both;
+        let last_synced = if two_way {
+            state::load(pair)
+        } else {
+            HashSet::new()
+        };
 
-        prog.add(progress.clone());
+        let removed_from_qobuz = removed_since(pair, &last_synced, &spotify_isrcs, &qobuz_isrcs);
+        let removed_from_spotify = removed_since(pair, &last_synced, &qobuz_isrcs, &spotify_isrcs);
 
-        spotify_prog.finish_and_clear();
+        let mut synced = spotify_isrcs
+            .intersection(&qobuz_isrcs)
+            .cloned()
+            .collect::<HashSet<Isrc>>();
 
-        for missing in missing_tracks {
-            if let Some(isrc) = missing.track.external_ids.get("isrc") {
-                let results = qobuz.search(&isrc.to_lowercase()).await;
-                if !results.is_empty() {
-                    if let Some(found) = results.first() {
-                        qobuz
-                            .add_track(&qobuz_playlist.id(), &found.id.to_string())
-                            .await;
-
-                        if missing.index < qobuz_playlist.track_count() {
-                            qobuz
-                                .update_track_position(
-                                    &qobuz_playlist.id(),
-                                    &found.id.to_string(),
-                                    missing.index - 1,
-                                )
-                                .await?;
-                        }
+        if pair.direction.to_qobuz() {
+            let added = add_missing_to_qobuz(
+                qobuz,
+                &qobuz_playlist,
+                &spotify_playlist,
+                pair,
+                &removed_from_qobuz,
+                &mut report,
+                prog,
+            )
+            .await?;
+
+            synced.extend(added);
+
+            if !removed_from_qobuz.is_empty() {
+                report.removed += removed_from_qobuz.len();
+
+                if !pair.dry_run {
+                    spotify
+                        .remove_tracks(
+                            playlist_id.as_ref(),
+                            &spotify_playlist.track_ids(&removed_from_qobuz),
+                        )
+                        .await?;
+                }
+            }
+        }
+
+        if pair.direction.to_spotify() {
+            let mut missing_tracks = qobuz_playlist.missing_tracks(&spotify_isrcs);
+            let mut seen = HashSet::new();
+
+            missing_tracks.retain(|track| match &track.isrc {
+                Some(isrc) => {
+                    let isrc = Isrc::from(isrc);
+                    !removed_from_spotify.contains(&isrc) && (!pair.dedupe || seen.insert(isrc))
+                }
+                None => false,
+            });
+
+            report.missing += missing_tracks.len();
+
+            let mut track_ids = vec![];
+
+            for track in missing_tracks {
+                let Some(isrc) = &track.isrc else {
+                    continue;
+                };
+
+                match spotify.find_track(isrc).await {
+                    Some(found) => {
+                        report.pushed += 1;
+                        synced.insert(Isrc::from(isrc));
+                        track_ids.push(found);
                     }
+                    None => report.not_found += 1,
                 }
                 std::thread::sleep(Duration::from_millis(125));
             }
 
-            progress.inc(1);
+            if !track_ids.is_empty() && !pair.dry_run {
+                spotify.add_tracks(playlist_id.as_ref(), &track_ids).await?;
+            }
+
+            if !removed_from_spotify.is_empty() {
+                report.removed += removed_from_spotify.len();
+
+                if !pair.dry_run {
+                    qobuz
+                        .delete_track(
+                            qobuz_playlist.id(),
+                            qobuz_playlist.playlist_track_ids(&removed_from_spotify),
+                        )
+                        .await?;
+                }
+            }
         }
 
-        progress.set_style(ProgressStyle::default_bar().template("{msg}").unwrap());
-        progress.finish_with_message("complete!");
+        if two_way && !pair.dry_run {
+            state::save(pair, &synced);
+        }
     }
 
-    Ok(())
+    Ok(report)
+}
+
+/// Find the tracks of `source` that the Qobuz playlist is missing on Qobuz and add them,
+/// except for the ones in `skip`. Returns the ISRCs of the tracks that were found.
+async fn add_missing_to_qobuz(
+    qobuz: &Qobuz<'_>,
+    qobuz_playlist: &QobuzPlaylist,
+    source: &dyn SourcePlaylist,
+    pair: &SyncPair,
+    skip: &HashSet<Isrc>,
+    report: &mut SyncReport,
+    prog: &MultiProgress,
+) -> Result<HashSet<Isrc>, Error> {
+    let mut missing_tracks = source.missing_tracks(qobuz_playlist.irsc_list());
+    missing_tracks.retain(|missing| !skip.contains(&Isrc::from(&missing.isrc)));
+
+    if pair.dedupe {
+        let mut seen = HashSet::new();
+        missing_tracks.retain(|missing| seen.insert(Isrc::from(&missing.isrc)));
+    }
+
+    report.missing += missing_tracks.len();
+
+    let progress = ProgressBar::new(missing_tracks.len() as u64).with_prefix("syncing");
+    progress.set_style(
+        ProgressStyle::default_bar()
+            .template("{prefix} {wide_bar:.cyan/blue} [{pos}/{len}]")
+            .unwrap(),
+    );
+
+    prog.add(progress.clone());
+
+    let resume_file = state::resume_path(pair.qobuz);
+    let mut transaction = qobuz
+        .transaction(pair.qobuz)
+        .await?
+        .with_resume_file(resume_file);
+
+    let mut added = HashSet::new();
+    let mut found_isrcs = HashSet::new();
+
+    for missing in missing_tracks {
+        match qobuz.find_track(&missing.isrc, &missing.hints).await {
+            Some(found) if pair.dedupe && !added.insert(found.id) => {}
+            Some(found) => {
+                report.added += 1;
+                found_isrcs.insert(Isrc::from(&missing.isrc));
+
+                if !pair.dry_run {
+                    transaction.add_tracks(vec![found.id.to_string()]);
+
+                    if missing.index < qobuz_playlist.track_count() {
+                        transaction.move_track(found.id.to_string(), missing.index - 1);
+                    }
+                }
+            }
+            None => report.not_found += 1,
+        }
+        std::thread::sleep(Duration::from_millis(125));
+
+        progress.inc(1);
+    }
+
+    if !transaction.is_empty() {
+        qobuz.progress().set_message("applying changes");
+        transaction.commit().await?;
+    }
+
+    progress.set_style(ProgressStyle::default_bar().template("{msg}").unwrap());
+    progress.finish_with_message(format!("{} complete!", report.label));
+
+    Ok(found_isrcs)
+}
+
+fn print_summary(reports: &[SyncReport]) {
+    println!();
+
+    for report in reports {
+        let dry_run = if report.dry_run { " (dry run)" } else { "" };
+
+        match &report.error {
+            Some(error) => println!("{}{dry_run}: failed: {error}", report.label),
+            None => println!(
+                "{}{dry_run}: {} missing, {} added to Qobuz, {} added to Spotify, {} removed, {} not found, {} duplicates removed, {} replaced",
+                report.label,
+                report.missing,
+                report.added,
+                report.pushed,
+                report.removed,
+                report.not_found,
+                report.duplicates,
+                report.replaced
+            ),
+        }
+    }
+
+    let failed = reports.iter().filter(|r| r.error.is_some()).count();
+    println!(
+        "\n{} playlists synced, {} failed",
+        reports.len() - failed,
+        failed
+    );
 }
@@ -0,0 +1,150 @@
+use clap::ValueEnum;
+use serde::Deserialize;
+use snafu::prelude::*;
+use std::path::Path;
+
+/// The playlist pairs `playlist-sync run` works through, read from a TOML file:
+///
+/// ```toml
+/// [[pair]]
+/// name = "Running"
+/// spotify = "2IkvmS2LOZJCFa6n9yiA7Z"
+/// qobuz = 3551270
+/// dedupe = true
+/// dry_run = true
+///
+/// [[pair]]
+/// spotify = "37i9dQZF1DXcBWIGoYBM5M"
+/// qobuz = 4410371
+/// direction = "both"
+/// conflict = "remove"
+///
+/// [[pair]]
+/// source = "tidal"
+/// tidal = "36ea71a8-445e-41a4-82ab-6628c581535d"
+/// qobuz = 4410372
+/// ```
+#[derive(Debug, Clone, Deserialize)]
+pub struct SyncConfig {
+    #[serde(rename = "pair", default)]
+    pub pairs: Vec<SyncPair>,
+}
+
+/// A Spotify playlist and the Qobuz playlist it is synced with.
+#[derive(Debug, Clone, Deserialize)]
+pub struct SyncPair {
+    /// Shown in the summary instead of the playlist ids.
+    pub name: Option<String>,
+    #[serde(default)]
+    pub source: Source,
+    /// The id of the Spotify or Tidal playlist.
+    #[serde(rename = "spotify", alias = "tidal")]
+    pub source_playlist: String,
+    pub qobuz: i64,
+    #[serde(default)]
+    pub direction: Direction,
+    /// What to do with a track one playlist lost since the last two-way sync.
+    #[serde(default)]
+    pub conflict: Conflict,
+    /// Remove tracks that are in the Qobuz playlist more than once and never add a track twice.
+    #[serde(default)]
+    pub dedupe: bool,
+    /// Only report what would change.
+    #[serde(default)]
+    pub dry_run: bool,
+    /// Replace the tracks of the Qobuz playlist that can't be streamed instead of adding
+    /// the missing ones.
+    #[serde(default)]
+    pub check_existing: bool,
+}
+
+impl SyncPair {
+    /// A pair with the default options, as given on the command line.
+    pub fn new(source: Source, source_playlist: String, qobuz: i64, check_existing: bool) -> Self {
+        Self {
+            name: None,
+            source,
+            source_playlist,
+            qobuz,
+            direction: Direction::default(),
+            conflict: Conflict::default(),
+            dedupe: false,
+            dry_run: false,
+            check_existing,
+        }
+    }
+
+    pub fn label(&self) -> String {
+        self.name
+            .clone()
+            .unwrap_or_else(|| format!("{} → {}", self.source_playlist, self.qobuz))
+    }
+}
+
+/// The service the other playlist of a pair is on.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize, ValueEnum)]
+#[serde(rename_all = "kebab-case")]
+pub enum Source {
+    #[default]
+    Spotify,
+    /// Tidal playlists only sync into Qobuz.
+    Tidal,
+}
+
+/// Which way the tracks go.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum Direction {
+    /// Add the tracks of the Spotify playlist that are missing from the Qobuz playlist.
+    #[default]
+    #[serde(alias = "to-qobuz")]
+    SpotifyToQobuz,
+    /// Add the tracks of the Qobuz playlist that are missing from the Spotify playlist.
+    QobuzToSpotify,
+    /// Both ways, so each playlist ends up with the tracks added to the other.
+    Both,
+}
+
+impl Direction {
+    pub fn to_qobuz(&self) -> bool {
+        *self != Direction::QobuzToSpotify
+    }
+
+    pub fn to_spotify(&self) -> bool {
+        *self != Direction::SpotifyToQobuz
+    }
+}
+
+/// How a two-way sync treats a track that was in both playlists last time and is now
+/// missing from one of them.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum Conflict {
+    /// Add it back, so no track is ever lost.
+    #[default]
+    Keep,
+    /// Remove it from the other playlist too.
+    Remove,
+}
+
+#[derive(Snafu, Debug)]
+pub enum Error {
+    #[snafu(display("failed to read {path}: {error}"))]
+    Read { path: String, error: String },
+    #[snafu(display("failed to parse {path}: {error}"))]
+    Parse { path: String, error: String },
+}
+
+pub type Result<T, E = Error> = std::result::Result<T, E>;
+
+pub fn load(path: &Path) -> Result<SyncConfig> {
+    let contents = std::fs::read_to_string(path).map_err(|error| Error::Read {
+        path: path.to_string_lossy().to_string(),
+        error: error.to_string(),
+    })?;
+
+    toml::from_str(&contents).map_err(|error| Error::Parse {
+        path: path.to_string_lossy().to_string(),
+        error: error.to_string(),
+    })
+}
@@ -1,11 +1,16 @@
+use hifirs_qobuz_api::client::matching::TrackHints;
 use snafu::prelude::*;
+use std::collections::HashSet;
 extern crate pretty_env_logger;
 #[macro_use]
 extern crate log;
 
 pub mod cli;
+pub mod config;
 pub mod qobuz;
 pub mod spotify;
+pub mod state;
+pub mod tidal;
 
 #[derive(Hash, Clone, Eq, PartialEq)]
 pub struct Isrc(String);
@@ -16,6 +21,22 @@ impl From<&String> for Isrc {
     }
 }
 
+/// A playlist on a service that tracks are synced into Qobuz from.
+pub trait SourcePlaylist {
+    fn isrc_list(&self) -> HashSet<Isrc>;
+    /// The tracks with an ISRC that isn't in `isrcs`.
+    fn missing_tracks(&self, isrcs: HashSet<Isrc>) -> Vec<MissingTrack>;
+}
+
+/// A track of a source playlist that the Qobuz playlist doesn't have yet.
+pub struct MissingTrack {
+    pub isrc: String,
+    /// Metadata used to find the track on Qobuz when its ISRC doesn't match.
+    pub hints: TrackHints,
+    /// Where the track is in the source playlist.
+    pub index: usize,
+}
+
 #[derive(Snafu, Debug)]
 pub enum Error {
     ClientError { error: String },
@@ -29,6 +50,14 @@ impl From<spotify::Error> for Error {
     }
 }
 
+impl From<tidal::Error> for Error {
+    fn from(e: tidal::Error) -> Self {
+        Error::ClientError {
+            error: e.to_string(),
+        }
+    }
+}
+
 impl From<hifirs_qobuz_api::Error> for Error {
     fn from(e: hifirs_qobuz_api::Error) -> Self {
         Error::ClientError {
@@ -1,11 +1,12 @@
 use crate::Isrc;
 use hifirs_qobuz_api::client::{
     api::Client,
-    playlist::Playlist,
+    matching::TrackHints,
+    playlist::{Playlist, PlaylistTransaction},
     track::{Track, Tracks},
 };
 use indicatif::ProgressBar;
-use std::collections::HashSet;
+use std::{collections::HashSet, path::Path};
 
 pub struct Qobuz<'q> {
     client: Client,
@@ -35,6 +36,10 @@ impl<'q> Qobuz<'q> {
         Ok(())
     }
 
+    pub fn progress(&self) -> &ProgressBar {
+        self.progress
+    }
+
     pub async fn playlist(&self, playlist_id: i64) -> hifirs_qobuz_api::Result<QobuzPlaylist> {
         self.progress
             .set_message(format!("fetching playlist: {playlist_id}"));
@@ -45,17 +50,47 @@ impl<'q> Qobuz<'q> {
         Ok(QobuzPlaylist(playlist))
     }
 
-    pub async fn search(&self, query: &str) -> Vec<Track> {
-        self.progress.set_message(format!("{query} searching"));
-        let results = self.client.search_all(query, 100).await.unwrap();
+    /// Find the Qobuz track for an ISRC, falling back to the metadata in `hints`.
+    pub async fn find_track(&self, isrc: &str, hints: &TrackHints) -> Option<Track> {
+        self.progress.set_message(format!("{isrc} searching"));
 
-        if results.tracks.items.is_empty() {
-            self.progress.set_message(format!("{query} not found"));
-        } else {
-            self.progress.set_message(format!("{query} found"));
+        match self.client.find_track_by_isrc(isrc, Some(hints)).await {
+            Ok(Some(track)) => {
+                self.progress.set_message(format!("{isrc} found"));
+                Some(track)
+            }
+            Ok(None) => {
+                self.progress.set_message(format!("{isrc} not found"));
+                None
+            }
+            Err(error) => {
+                self.progress
+                    .set_message(format!("{isrc} search failed: {error}"));
+                None
+            }
         }
+    }
+
+    /// Start a batch of changes to a playlist that is rolled back if any step fails.
+    pub async fn transaction(
+        &self,
+        playlist_id: i64,
+    ) -> hifirs_qobuz_api::Result<PlaylistTransaction<'_>> {
+        PlaylistTransaction::begin(&self.client, playlist_id).await
+    }
+
+    /// Retry a batch of changes saved by a failed transaction.
+    pub async fn resume(&self, path: &Path) -> hifirs_qobuz_api::Result<()> {
+        self.progress
+            .set_message(format!("retrying changes from {}", path.to_string_lossy()));
+
+        PlaylistTransaction::resume(&self.client, path)
+            .await?
+            .commit()
+            .await?;
 
-        results.tracks.items
+        self.progress.set_message("changes applied");
+        Ok(())
     }
 
     pub async fn add_track(&self, playlist_id: &str, track_id: &str) {
@@ -73,19 +108,6 @@ impl<'q> Qobuz<'q> {
             .set_message(format!("added {track_id} to {playlist_id}"));
     }
 
-    pub async fn update_track_position(
-        &self,
-        playlist_id: &str,
-        track_id: &str,
-        index: usize,
-    ) -> hifirs_qobuz_api::Result<()> {
-        self.client
-            .playlist_track_position(index, playlist_id, track_id)
-            .await?;
-
-        Ok(())
-    }
-
     pub async fn delete_track(
         &self,
         playlist_id: String,
@@ -118,6 +140,53 @@ impl QobuzPlaylist {
         }
     }
 
+    /// The playlist track ids of the entries that repeat a track from earlier in the playlist.
+    pub fn duplicates(&self) -> Vec<String> {
+        let mut seen = HashSet::new();
+
+        self.0
+            .tracks
+            .iter()
+            .flat_map(|tracks| &tracks.items)
+            .filter(|track| !seen.insert(track.id))
+            .filter_map(|track| track.playlist_track_id)
+            .map(|id| id.to_string())
+            .collect()
+    }
+
+    /// The tracks with an ISRC that isn't in `isrcs`, in playlist order.
+    pub fn missing_tracks(&self, isrcs: &HashSet<Isrc>) -> Vec<Track> {
+        self.0
+            .tracks
+            .iter()
+            .flat_map(|tracks| &tracks.items)
+            .filter(|track| {
+                track
+                    .isrc
+                    .as_ref()
+                    .is_some_and(|isrc| !isrcs.contains(&Isrc::from(isrc)))
+            })
+            .cloned()
+            .collect()
+    }
+
+    /// The playlist track ids of the entries with one of `isrcs`.
+    pub fn playlist_track_ids(&self, isrcs: &HashSet<Isrc>) -> Vec<String> {
+        self.0
+            .tracks
+            .iter()
+            .flat_map(|tracks| &tracks.items)
+            .filter(|track| {
+                track
+                    .isrc
+                    .as_ref()
+                    .is_some_and(|isrc| isrcs.contains(&Isrc::from(isrc)))
+            })
+            .filter_map(|track| track.playlist_track_id)
+            .map(|id| id.to_string())
+            .collect()
+    }
+
     pub fn tracks(&self) -> Option<Tracks> {
         self.0.tracks.clone()
     }
@@ -1,9 +1,13 @@
-use crate::Isrc;
+use crate::{Isrc, MissingTrack, SourcePlaylist};
 use futures::stream::TryStreamExt;
+use hifirs_qobuz_api::client::matching::TrackHints;
 use indicatif::ProgressBar;
 use log::debug;
 use rspotify::{
-    model::{FullTrack, PlayableItem, PlaylistId, PlaylistItem, SimplifiedPlaylist},
+    model::{
+        FullTrack, PlayableId, PlayableItem, PlaylistId, PlaylistItem, SearchResult, SearchType,
+        SimplifiedPlaylist, TrackId,
+    },
     prelude::*,
     scopes, AuthCodeSpotify, Config, Credentials as SpotifyCredentials, OAuth,
 };
@@ -183,10 +187,96 @@ impl<'s> Spotify<'s> {
             }),
         }
     }
+
+    /// Find the Spotify track with an ISRC.
+    pub async fn find_track(&self, isrc: &str) -> Option<TrackId<'static>> {
+        self.progress.set_message(format!("{isrc} searching"));
+
+        let result = self
+            .client
+            .search(
+                &format!("isrc:{isrc}"),
+                SearchType::Track,
+                None,
+                None,
+                Some(1),
+                None,
+            )
+            .await;
+
+        match result {
+            Ok(SearchResult::Tracks(page)) => {
+                let found = page.items.into_iter().find_map(|track| track.id);
+
+                if found.is_some() {
+                    self.progress.set_message(format!("{isrc} found"));
+                } else {
+                    self.progress.set_message(format!("{isrc} not found"));
+                }
+
+                found
+            }
+            Ok(_) => None,
+            Err(error) => {
+                self.progress
+                    .set_message(format!("{isrc} search failed: {error}"));
+                None
+            }
+        }
+    }
+
+    /// Add tracks to the end of a playlist.
+    pub async fn add_tracks(
+        &self,
+        playlist_id: PlaylistId<'_>,
+        track_ids: &[TrackId<'static>],
+    ) -> Result<()> {
+        self.progress.set_message(format!(
+            "adding {} tracks to {playlist_id}",
+            track_ids.len()
+        ));
+
+        // Spotify takes at most 100 tracks per request.
+        for chunk in track_ids.chunks(100) {
+            self.client
+                .playlist_add_items(
+                    playlist_id.as_ref(),
+                    chunk.iter().map(|id| PlayableId::Track(id.as_ref())),
+                    None,
+                )
+                .await?;
+        }
+
+        Ok(())
+    }
+
+    /// Remove every occurrence of tracks from a playlist.
+    pub async fn remove_tracks(
+        &self,
+        playlist_id: PlaylistId<'_>,
+        track_ids: &[TrackId<'static>],
+    ) -> Result<()> {
+        self.progress.set_message(format!(
+            "removing {} tracks from {playlist_id}",
+            track_ids.len()
+        ));
+
+        for chunk in track_ids.chunks(100) {
+            self.client
+                .playlist_remove_all_occurrences_of_items(
+                    playlist_id.as_ref(),
+                    chunk.iter().map(|id| PlayableId::Track(id.as_ref())),
+                    None,
+                )
+                .await?;
+        }
+
+        Ok(())
+    }
 }
 
-impl SpotifyFullPlaylist {
-    pub fn isrc_list(&self) -> HashSet<Isrc> {
+impl SourcePlaylist for SpotifyFullPlaylist {
+    fn isrc_list(&self) -> HashSet<Isrc> {
         let mut set = HashSet::new();
 
         for track in &self.all_tracks {
@@ -199,31 +289,52 @@ impl SpotifyFullPlaylist {
         set
     }
 
-    pub fn missing_tracks(&self, isrcs: HashSet<Isrc>) -> Vec<MissingTrack> {
+    fn missing_tracks(&self, isrcs: HashSet<Isrc>) -> Vec<MissingTrack> {
         let spotify_isrcs = self.isrc_list();
         let diff = spotify_isrcs.difference(&isrcs).collect::<HashSet<_>>();
 
         self.all_tracks
             .iter()
-            .cloned()
             .enumerate()
             .filter_map(|(index, track)| {
-                if let Some(isrc) = track.external_ids.get("isrc") {
-                    if diff.contains::<Isrc>(&isrc.into()) {
-                        Some(MissingTrack { track, index })
-                    } else {
-                        None
-                    }
+                let isrc = track.external_ids.get("isrc")?;
+
+                if diff.contains::<Isrc>(&isrc.into()) {
+                    Some(MissingTrack {
+                        isrc: isrc.clone(),
+                        hints: TrackHints {
+                            title: Some(track.name.clone()),
+                            artist: track.artists.first().map(|a| a.name.clone()),
+                            duration: Some(track.duration.num_seconds()),
+                        },
+                        index,
+                    })
                 } else {
                     None
                 }
             })
             .collect::<Vec<MissingTrack>>()
     }
+}
 
+impl SpotifyFullPlaylist {
     pub fn track_count(&self) -> usize {
         self.all_tracks.len()
     }
+
+    /// The ids of the tracks with one of `isrcs`.
+    pub fn track_ids(&self, isrcs: &HashSet<Isrc>) -> Vec<TrackId<'static>> {
+        self.all_tracks
+            .iter()
+            .filter(|track| {
+                track
+                    .external_ids
+                    .get("isrc")
+                    .is_some_and(|isrc| isrcs.contains(&Isrc::from(isrc)))
+            })
+            .filter_map(|track| track.id.clone())
+            .collect()
+    }
 }
 
 #[derive(Snafu, Debug)]
@@ -240,8 +351,3 @@ impl From<rspotify::ClientError> for Error {
         }
     }
 }
-
-pub struct MissingTrack {
-    pub track: FullTrack,
-    pub index: usize,
-}
@@ -0,0 +1,49 @@
+use crate::{config::SyncPair, Isrc};
+use std::{collections::HashSet, path::PathBuf};
+
+/// The ISRCs that were in both playlists of a pair after its last two-way sync. A track
+/// missing from one side that is in here was removed there, anything else was added.
+pub fn load(pair: &SyncPair) -> HashSet<Isrc> {
+    match std::fs::read_to_string(path(pair)) {
+        Ok(contents) => contents
+            .lines()
+            .filter(|line| !line.is_empty())
+            .map(|line| Isrc(line.to_lowercase()))
+            .collect(),
+        Err(_) => HashSet::new(),
+    }
+}
+
+pub fn save(pair: &SyncPair, synced: &HashSet<Isrc>) {
+    let mut lines = synced
+        .iter()
+        .map(|isrc| isrc.0.clone())
+        .collect::<Vec<String>>();
+    lines.sort();
+
+    if let Err(error) = std::fs::write(path(pair), lines.join("\n")) {
+        error!("failed to save the sync state: {error}");
+    }
+}
+
+fn path(pair: &SyncPair) -> PathBuf {
+    PathBuf::from(format!(
+        "playlist-sync-{}-{}.txt",
+        pair.source_playlist, pair.qobuz
+    ))
+}
+
+/// Where the changes of a failed sync to the Qobuz playlist `qobuz` are kept, so they can
+/// be retried on the next run.
+pub fn resume_path(qobuz: i64) -> PathBuf {
+    let mut path = dirs::data_local_dir().unwrap_or_else(std::env::temp_dir);
+    path.push("hifi-rs");
+    path.push("playlist-sync");
+
+    if let Err(error) = std::fs::create_dir_all(&path) {
+        error!("failed to create {}: {error}", path.to_string_lossy());
+    }
+
+    path.push(format!("qobuz-sync-{qobuz}.json"));
+    path
+}
@@ -0,0 +1,382 @@
+use crate::{Isrc, MissingTrack, SourcePlaylist};
+use hifirs_qobuz_api::client::matching::TrackHints;
+use indicatif::ProgressBar;
+use log::debug;
+use serde::{Deserialize, Serialize};
+use snafu::prelude::*;
+use std::{
+    collections::HashSet,
+    time::{Duration, Instant, SystemTime, UNIX_EPOCH},
+};
+
+const AUTH_URL: &str = "https://auth.tidal.com/v1/oauth2";
+const API_URL: &str = "https://api.tidal.com/v1";
+const TOKEN_CACHE: &str = "/tmp/.tidal_token_cache.json";
+const SCOPE: &str = "r_usr w_usr";
+/// The most tracks Tidal returns per request.
+const PAGE_SIZE: usize = 100;
+
+pub struct Tidal<'t> {
+    client: reqwest::Client,
+    client_id: String,
+    client_secret: String,
+    token: Option<Token>,
+    progress: &'t ProgressBar,
+}
+
+pub fn new(progress: &'_ ProgressBar) -> Result<Tidal<'_>> {
+    Ok(Tidal {
+        client: reqwest::Client::new(),
+        client_id: env("TIDAL_CLIENT_ID")?,
+        client_secret: env("TIDAL_CLIENT_SECRET")?,
+        token: None,
+        progress,
+    })
+}
+
+fn env(name: &str) -> Result<String> {
+    std::env::var(name).map_err(|_| Error::ClientError {
+        error: format!("{name} is not set"),
+    })
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct Token {
+    access_token: String,
+    refresh_token: Option<String>,
+    /// Unix time the access token stops working.
+    expires_at: u64,
+    /// Tidal only returns what is available in the user's country.
+    country_code: String,
+}
+
+impl Token {
+    fn is_expired(&self) -> bool {
+        self.expires_at <= now() + 60
+    }
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct DeviceAuthorization {
+    device_code: String,
+    verification_uri_complete: String,
+    expires_in: u64,
+    interval: u64,
+}
+
+/// What the token endpoint answers while a device login isn't through.
+#[derive(Debug, Deserialize)]
+struct TokenError {
+    error: String,
+    error_description: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+struct TokenResponse {
+    access_token: String,
+    refresh_token: Option<String>,
+    expires_in: u64,
+    user: Option<TokenUser>,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct TokenUser {
+    country_code: String,
+}
+
+impl TokenResponse {
+    /// `previous` fills in what a refresh doesn't return again.
+    fn into_token(self, previous: Option<&Token>) -> Token {
+        Token {
+            access_token: self.access_token,
+            refresh_token: self
+                .refresh_token
+                .or_else(|| previous.and_then(|t| t.refresh_token.clone())),
+            expires_at: now() + self.expires_in,
+            country_code: self
+                .user
+                .map(|u| u.country_code)
+                .or_else(|| previous.map(|t| t.country_code.clone()))
+                .unwrap_or_else(|| "US".to_string()),
+        }
+    }
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct Page {
+    items: Vec<TidalTrack>,
+    total_number_of_items: usize,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct TidalTrack {
+    pub id: i64,
+    pub title: String,
+    /// Seconds.
+    pub duration: i64,
+    pub isrc: Option<String>,
+    pub artist: Option<TidalArtist>,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct TidalArtist {
+    pub name: String,
+}
+
+pub struct TidalPlaylist {
+    all_tracks: Vec<TidalTrack>,
+}
+
+impl<'t> Tidal<'t> {
+    /// Sign in with the cached token, refreshing it when it expired, or with a device login
+    /// in the browser.
+    pub async fn auth(&mut self) -> Result<()> {
+        self.progress.set_message("signing into Tidal");
+
+        if let Some(cached) = read_token_cache() {
+            if !cached.is_expired() {
+                debug!("found tidal token in cache");
+                self.token = Some(cached);
+            } else if let Some(refresh_token) = &cached.refresh_token {
+                match self.refresh(refresh_token, &cached).await {
+                    Ok(token) => {
+                        debug!("cached tidal token refreshed");
+                        self.token = Some(token);
+                    }
+                    Err(error) => error!("error refreshing tidal token {error}"),
+                }
+            }
+        }
+
+        if self.token.is_none() {
+            debug!("no cached tidal token, starting device login");
+            self.token = Some(self.device_login().await?);
+        }
+
+        if let Some(token) = &self.token {
+            write_token_cache(token);
+        }
+
+        self.progress.set_message("signed into Tidal");
+
+        Ok(())
+    }
+
+    async fn device_login(&self) -> Result<Token> {
+        let authorization = self
+            .client
+            .post(format!("{AUTH_URL}/device_authorization"))
+            .form(&[("client_id", self.client_id.as_str()), ("scope", SCOPE)])
+            .send()
+            .await?
+            .error_for_status()?
+            .json::<DeviceAuthorization>()
+            .await?;
+
+        let url = if authorization.verification_uri_complete.starts_with("http") {
+            authorization.verification_uri_complete.clone()
+        } else {
+            format!("https://{}", authorization.verification_uri_complete)
+        };
+
+        if webbrowser::open(&url).is_err() {
+            println!(
+                "There was a problem opening the browser, please open this url manually:\n{url}"
+            );
+        }
+
+        self.progress.set_message("waiting for authorization");
+
+        let deadline = Instant::now() + Duration::from_secs(authorization.expires_in);
+        let mut interval = Duration::from_secs(authorization.interval.max(1));
+
+        while Instant::now() < deadline {
+            tokio::time::sleep(interval).await;
+
+            let response = self
+                .client
+                .post(format!("{AUTH_URL}/token"))
+                .form(&[
+                    ("client_id", self.client_id.as_str()),
+                    ("client_secret", self.client_secret.as_str()),
+                    ("device_code", authorization.device_code.as_str()),
+                    ("grant_type", "urn:ietf:params:oauth:grant-type:device_code"),
+                    ("scope", SCOPE),
+                ])
+                .send()
+                .await?;
+
+            if response.status().is_success() {
+                self.progress.set_message("authorization received");
+
+                return Ok(response.json::<TokenResponse>().await?.into_token(None));
+            }
+
+            let status = response.status();
+            let answer = response.json::<TokenError>().await.ok();
+
+            // Tidal answers `authorization_pending` until the user signed in. Anything else,
+            // like `access_denied` or `expired_token`, ends the login.
+            match answer {
+                Some(answer) if answer.error == "authorization_pending" => {}
+                Some(answer) if answer.error == "slow_down" => interval += Duration::from_secs(5),
+                Some(answer) => {
+                    return Err(Error::ClientError {
+                        error: format!(
+                            "the Tidal login failed: {}",
+                            answer.error_description.unwrap_or(answer.error)
+                        ),
+                    })
+                }
+                None => {
+                    return Err(Error::ClientError {
+                        error: format!("the Tidal login failed: {status}"),
+                    })
+                }
+            }
+        }
+
+        Err(Error::ClientError {
+            error: "the Tidal login timed out".to_string(),
+        })
+    }
+
+    async fn refresh(&self, refresh_token: &str, previous: &Token) -> Result<Token> {
+        let response = self
+            .client
+            .post(format!("{AUTH_URL}/token"))
+            .form(&[
+                ("client_id", self.client_id.as_str()),
+                ("client_secret", self.client_secret.as_str()),
+                ("refresh_token", refresh_token),
+                ("grant_type", "refresh_token"),
+                ("scope", SCOPE),
+            ])
+            .send()
+            .await?
+            .error_for_status()?
+            .json::<TokenResponse>()
+            .await?;
+
+        Ok(response.into_token(Some(previous)))
+    }
+
+    pub async fn playlist(&self, playlist_id: &str) -> Result<TidalPlaylist> {
+        let token = self.token.as_ref().ok_or_else(|| Error::ClientError {
+            error: "not signed into Tidal".to_string(),
+        })?;
+
+        self.progress
+            .set_message(format!("fetching playlist: {playlist_id}"));
+
+        let mut all_tracks = vec![];
+
+        loop {
+            let offset = all_tracks.len().to_string();
+            let limit = PAGE_SIZE.to_string();
+
+            let page = self
+                .client
+                .get(format!("{API_URL}/playlists/{playlist_id}/tracks"))
+                .bearer_auth(&token.access_token)
+                .query(&[
+                    ("countryCode", token.country_code.as_str()),
+                    ("offset", offset.as_str()),
+                    ("limit", limit.as_str()),
+                ])
+                .send()
+                .await?
+                .error_for_status()?
+                .json::<Page>()
+                .await?;
+
+            let received = page.items.len();
+            all_tracks.extend(page.items);
+
+            if received == 0 || all_tracks.len() >= page.total_number_of_items {
+                break;
+            }
+        }
+
+        debug!("list size: {}", all_tracks.len());
+        self.progress.set_message("playlist tracks retrieved");
+
+        Ok(TidalPlaylist { all_tracks })
+    }
+}
+
+impl SourcePlaylist for TidalPlaylist {
+    fn isrc_list(&self) -> HashSet<Isrc> {
+        self.all_tracks
+            .iter()
+            .filter_map(|track| track.isrc.as_ref())
+            .map(Isrc::from)
+            .collect()
+    }
+
+    fn missing_tracks(&self, isrcs: HashSet<Isrc>) -> Vec<MissingTrack> {
+        self.all_tracks
+            .iter()
+            .enumerate()
+            .filter_map(|(index, track)| {
+                let isrc = track.isrc.as_ref()?;
+
+                if isrcs.contains(&Isrc::from(isrc)) {
+                    return None;
+                }
+
+                Some(MissingTrack {
+                    isrc: isrc.clone(),
+                    hints: TrackHints {
+                        title: Some(track.title.clone()),
+                        artist: track.artist.as_ref().map(|a| a.name.clone()),
+                        duration: Some(track.duration),
+                    },
+                    index,
+                })
+            })
+            .collect()
+    }
+}
+
+fn now() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_secs())
+        .unwrap_or_default()
+}
+
+fn read_token_cache() -> Option<Token> {
+    let contents = std::fs::read_to_string(TOKEN_CACHE).ok()?;
+    serde_json::from_str(&contents).ok()
+}
+
+fn write_token_cache(token: &Token) {
+    match serde_json::to_string(token) {
+        Ok(contents) => {
+            if let Err(error) = std::fs::write(TOKEN_CACHE, contents) {
+                error!("failed to cache the tidal token: {error}");
+            }
+        }
+        Err(error) => error!("failed to cache the tidal token: {error}"),
+    }
+}
+
+#[derive(Snafu, Debug)]
+pub enum Error {
+    #[snafu(display("{error}"))]
+    ClientError { error: String },
+}
+
+pub type Result<T, E = Error> = std::result::Result<T, E>;
+
+impl From<reqwest::Error> for Error {
+    fn from(error: reqwest::Error) -> Self {
+        Error::ClientError {
+            error: error.to_string(),
+        }
+    }
+}
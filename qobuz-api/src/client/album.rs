@@ -4,7 +4,9 @@ use crate::client::{
     track::Tracks,
     Composer, Image,
 };
+use clap::ValueEnum;
 use serde::{Deserialize, Serialize};
+use std::fmt::Display;
 
 #[derive(Default, Debug, Clone, PartialEq, Serialize, Deserialize)]
 pub struct Album {
@@ -18,6 +20,7 @@ pub struct Album {
     pub displayable: bool,
     pub downloadable: bool,
     pub duration: Option<i64>,
+    pub favorited_at: Option<i64>,
     pub genre: Genre,
     pub genres_list: Option<Vec<String>>,
     pub hires: bool,
@@ -78,6 +81,35 @@ pub struct AlbumSearchResults {
     pub albums: Albums,
 }
 
+/// The album sections featured by Qobuz.
+#[derive(Default, Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize, ValueEnum)]
+#[serde(rename_all = "kebab-case")]
+pub enum FeaturedAlbumType {
+    #[default]
+    NewReleases,
+    MostStreamed,
+    PressAwards,
+    EditorPicks,
+    BestSellers,
+}
+
+impl Display for FeaturedAlbumType {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            FeaturedAlbumType::NewReleases => f.write_str("new-releases"),
+            FeaturedAlbumType::MostStreamed => f.write_str("most-streamed"),
+            FeaturedAlbumType::PressAwards => f.write_str("press-awards"),
+            FeaturedAlbumType::EditorPicks => f.write_str("editor-picks"),
+            FeaturedAlbumType::BestSellers => f.write_str("best-sellers"),
+        }
+    }
+}
+
+#[derive(Default, Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct FeaturedAlbums {
+    pub albums: Albums,
+}
+
 #[derive(Default, Debug, Clone, PartialEq, Serialize, Deserialize)]
 pub struct Albums {
     pub limit: i64,
@@ -119,3 +151,27 @@ pub struct Genre {
     pub id: i64,
     pub slug: String,
 }
+
+#[derive(Default, Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct GenreList {
+    pub genres: Genres,
+}
+
+#[derive(Default, Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Genres {
+    pub limit: i64,
+    pub offset: i64,
+    pub total: i64,
+    pub items: Vec<Genre>,
+}
+
+#[test]
+fn reads_genre_list() {
+    let list: GenreList = serde_json::from_str(
+        r##"{"genres":{"limit":100,"offset":0,"total":1,"items":[{"id":112,"color":"#5eabc1","name":"Pop/Rock","path":[112],"slug":"pop-rock"}]}}"##,
+    )
+    .expect("failed to read genres");
+
+    assert_eq!(list.genres.items[0].id, 112);
+    assert_eq!(list.genres.items[0].name, "Pop/Rock");
+}
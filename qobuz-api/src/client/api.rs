@@ -1,23 +1,38 @@
 use crate::{
     client::{
-        album::{Album, AlbumSearchResults},
-        artist::{Artist, ArtistSearchResults},
-        playlist::{Playlist, UserPlaylistsResult},
+        album::{Album, AlbumSearchResults, FeaturedAlbumType, FeaturedAlbums, GenreList},
+        artist::{Artist, ArtistSearchResults, SimilarArtistsResults},
+        favorites::{FavoriteType, Favorites},
+        health,
+        matching::{self, TrackHints},
+        metrics,
+        playlist::{
+            DynamicListType, DynamicTracks, FeaturedPlaylists, Playlist, PlaylistSearchResults,
+            UserPlaylistsResult,
+        },
         search_results::SearchAllResults,
-        track::Track,
-        AudioQuality, TrackURL,
+        throttle::{self, GaveUp, RateLimiter},
+        track::{Track, TrackSearchResults},
+        AudioQuality, HttpConfig, TrackURL,
     },
     Error, Result,
 };
 use base64::{engine::general_purpose, Engine as _};
 use clap::ValueEnum;
 use reqwest::{
-    header::{HeaderMap, HeaderValue},
-    Method, Response, StatusCode,
+    header::{HeaderMap, HeaderValue, RETRY_AFTER},
+    Method, RequestBuilder, Response, StatusCode,
 };
 use serde::{Deserialize, Serialize};
 use serde_json::Value;
-use std::{collections::HashMap, fmt::Display};
+use std::{
+    collections::HashMap,
+    fmt::Display,
+    sync::{Arc, RwLock},
+    time::{Duration, Instant},
+};
+use tokio::sync::{watch, Mutex};
+use tracing::Instrument;
 
 const BUNDLE_REGEX: &str =
     r#"<script src="(/resources/\d+\.\d+\.\d+-[a-z0-9]\d{3}/bundle\.js)"></script>"#;
@@ -38,20 +53,24 @@ pub struct Client {
     active_secret: Option<String>,
     app_id: Option<String>,
     base_url: String,
-    client: reqwest::Client,
+    /// Shared between clones so [`Client::reconnect`] affects all of them.
+    client: Arc<RwLock<reqwest::Client>>,
+    http_config: HttpConfig,
+    limiter: RateLimiter,
     default_quality: AudioQuality,
-    user_token: Option<String>,
+    /// Shared between clones so a token renewed after a 401 is used by all of them.
+    user_token: Arc<watch::Sender<Option<String>>>,
+    /// Username and password to sign in again with once the token expires.
+    credentials: Option<(String, String)>,
+    /// Held while signing in again, so calls rejected at the same time share one login.
+    relogin: Arc<Mutex<()>>,
+    accept_language: String,
     bundle_regex: regex::Regex,
     app_id_regex: regex::Regex,
     seed_regex: regex::Regex,
 }
 
-pub async fn new(
-    active_secret: Option<String>,
-    app_id: Option<String>,
-    audio_quality: Option<AudioQuality>,
-    user_token: Option<String>,
-) -> Result<Client> {
+fn http_client(config: &HttpConfig) -> reqwest::Client {
     let mut headers = HeaderMap::new();
     headers.insert(
             "User-Agent",
@@ -61,11 +80,54 @@ pub async fn new(
             .unwrap(),
         );
 
-    let client = reqwest::Client::builder()
+    let builder = reqwest::Client::builder()
         .cookie_store(true)
         .default_headers(headers)
-        .build()
-        .unwrap();
+        .pool_max_idle_per_host(config.pool_size)
+        .pool_idle_timeout(config.keep_alive)
+        .tcp_keepalive(config.keep_alive)
+        .connect_timeout(config.connect_timeout)
+        .timeout(config.request_timeout);
+
+    let builder = if config.http2 {
+        builder
+            .http2_adaptive_window(true)
+            .http2_keep_alive_interval(config.keep_alive)
+            .http2_keep_alive_while_idle(true)
+    } else {
+        builder.http1_only()
+    };
+
+    builder.build().unwrap()
+}
+
+/// How long the API asked to wait before trying again, when it said so in seconds.
+fn retry_after(response: &Response) -> Option<Duration> {
+    response
+        .headers()
+        .get(RETRY_AFTER)
+        .and_then(|value| value.to_str().ok())
+        .and_then(|value| value.trim().parse::<u64>().ok())
+        .map(Duration::from_secs)
+}
+
+/// The token in the answer to a login call.
+fn user_auth_token(response: &str) -> Result<String> {
+    serde_json::from_str::<Value>(response)
+        .ok()
+        .and_then(|json| json["user_auth_token"].as_str().map(String::from))
+        .ok_or(Error::Login)
+}
+
+pub async fn new(
+    active_secret: Option<String>,
+    app_id: Option<String>,
+    audio_quality: Option<AudioQuality>,
+    user_token: Option<String>,
+) -> Result<Client> {
+    let http_config = HttpConfig::default();
+    let client = Arc::new(RwLock::new(http_client(&http_config)));
+    let limiter = RateLimiter::new(http_config.rate_limit);
 
     let default_quality = if let Some(quality) = audio_quality {
         quality
@@ -75,11 +137,16 @@ pub async fn new(
 
     Ok(Client {
         client,
+        http_config,
+        limiter,
         secrets: HashMap::new(),
         active_secret,
-        user_token,
+        user_token: Arc::new(watch::channel(user_token).0),
+        credentials: None,
+        relogin: Arc::new(Mutex::new(())),
         app_id,
         default_quality,
+        accept_language: "en,en-US;q=0.8,ko;q=0.6,zh;q=0.4,zh-CN;q=0.2".to_string(),
         base_url: "https://www.qobuz.com/api.json/0.2/".to_string(),
         bundle_regex: regex::Regex::new(BUNDLE_REGEX).unwrap(),
         app_id_regex: regex::Regex::new(APP_REGEX).unwrap(),
@@ -91,11 +158,21 @@ pub async fn new(
 enum Endpoint {
     Album,
     Artist,
+    DynamicTracks,
+    Favorites,
+    FavoriteCreate,
+    FavoriteDelete,
+    FeaturedAlbums,
+    FeaturedPlaylists,
+    Genres,
     Login,
     Track,
     UserPlaylist,
     SearchArtists,
     SearchAlbums,
+    SimilarArtists,
+    SearchTracks,
+    SearchPlaylists,
     TrackURL,
     Playlist,
     PlaylistCreate,
@@ -111,6 +188,13 @@ impl Display for Endpoint {
         let endpoint = match self {
             Endpoint::Album => "album/get",
             Endpoint::Artist => "artist/get",
+            Endpoint::DynamicTracks => "dynamic-tracks/get",
+            Endpoint::Favorites => "favorite/getUserFavorites",
+            Endpoint::FavoriteCreate => "favorite/create",
+            Endpoint::FavoriteDelete => "favorite/delete",
+            Endpoint::FeaturedAlbums => "album/getFeatured",
+            Endpoint::FeaturedPlaylists => "playlist/getFeatured",
+            Endpoint::Genres => "genre/list",
             Endpoint::Login => "user/login",
             Endpoint::Playlist => "playlist/get",
             Endpoint::PlaylistCreate => "playlist/create",
@@ -121,6 +205,9 @@ impl Display for Endpoint {
             Endpoint::Search => "catalog/search",
             Endpoint::SearchAlbums => "album/search",
             Endpoint::SearchArtists => "artist/search",
+            Endpoint::SearchPlaylists => "playlist/search",
+            Endpoint::SearchTracks => "track/search",
+            Endpoint::SimilarArtists => "artist/getSimilarArtists",
             Endpoint::Track => "track/get",
             Endpoint::TrackURL => "track/getFileUrl",
             Endpoint::UserPlaylist => "playlist/getUserPlaylists",
@@ -168,7 +255,43 @@ impl Client {
     }
 
     pub fn signed_in(&self) -> bool {
-        self.user_token.is_some()
+        self.user_token.borrow().is_some()
+    }
+
+    /// True when the API answers at all, even with an error status. False when it can't be
+    /// connected to, e.g. because the network is down.
+    pub async fn reachable(&self) -> bool {
+        match self.http().get(&self.base_url).send().await {
+            Ok(_) => true,
+            Err(error) => !(error.is_connect() || error.is_timeout()),
+        }
+    }
+
+    /// Check whether the API is back after an outage, by listing the genres as that needs
+    /// no account. Ends the outage when it answers with a success, otherwise pushes the next
+    /// check back.
+    pub async fn health_check(&self) -> bool {
+        let endpoint = format!("{}{}", self.base_url, Endpoint::Genres);
+        let request = self
+            .http()
+            .get(&endpoint)
+            .headers(self.client_headers())
+            .query(&[("limit", "1")]);
+
+        match request.send().await {
+            Ok(response) if response.status().is_success() => {
+                health::record_healthy();
+                true
+            }
+            Ok(response) => {
+                health::record_failed_check(retry_after(&response));
+                false
+            }
+            Err(_) => {
+                health::record_failed_check(None);
+                false
+            }
+        }
     }
 
     /// Login a user
@@ -189,13 +312,11 @@ impl Client {
 
             match self.make_get_call(&endpoint, Some(&params)).await {
                 Ok(response) => {
-                    let json: Value = serde_json::from_str(response.as_str()).unwrap();
+                    let token = user_auth_token(&response)?;
                     info!("Successfully logged in");
-                    debug!("{}", json);
-                    let mut token = json["user_auth_token"].to_string();
-                    token = token[1..token.len() - 1].to_string();
 
-                    self.user_token = Some(token);
+                    self.user_token.send_replace(Some(token));
+                    self.credentials = Some((username.to_string(), password.to_string()));
                     Ok(())
                 }
                 Err(err) => {
@@ -208,6 +329,26 @@ impl Client {
         }
     }
 
+    /// Use an existing user auth token instead of logging in with a username
+    /// and password. The token is checked by looking for a working secret.
+    pub async fn login_with_token(&mut self, token: &str) -> Result<()> {
+        self.user_token.send_replace(Some(token.trim().to_string()));
+
+        if self.secrets.is_empty() {
+            self.refresh().await?;
+        }
+
+        if self.test_secrets().await.is_err() {
+            error!("the user auth token was not accepted");
+            self.user_token.send_replace(None);
+
+            return Err(Error::Login);
+        }
+
+        info!("Successfully logged in with token");
+        Ok(())
+    }
+
     /// Retrieve a list of the user's playlists
     pub async fn user_playlists(&self) -> Result<UserPlaylistsResult> {
         let endpoint = format!("{}{}", self.base_url, Endpoint::UserPlaylist);
@@ -216,6 +357,104 @@ impl Client {
         get!(self, &endpoint, Some(&params))
     }
 
+    /// Retrieve everything the user has added to their favorites: albums, artists and tracks
+    pub async fn favorites(&self) -> Result<Favorites> {
+        let endpoint = format!("{}{}", self.base_url, Endpoint::Favorites);
+        let params = vec![("limit", "500"), ("offset", "0")];
+
+        get!(self, &endpoint, Some(&params))
+    }
+
+    /// Retrieve the albums the user has added to their favorites
+    pub async fn favorite_albums(&self) -> Result<Favorites> {
+        let endpoint = format!("{}{}", self.base_url, Endpoint::Favorites);
+        let params = vec![("type", "albums"), ("limit", "500"), ("offset", "0")];
+
+        get!(self, &endpoint, Some(&params))
+    }
+
+    /// Retrieve the artists the user has added to their favorites
+    pub async fn favorite_artists(&self) -> Result<Favorites> {
+        let endpoint = format!("{}{}", self.base_url, Endpoint::Favorites);
+        let params = vec![("type", "artists"), ("limit", "500"), ("offset", "0")];
+
+        get!(self, &endpoint, Some(&params))
+    }
+
+    /// Retrieve the tracks the user has added to their favorites
+    pub async fn favorite_tracks(&self) -> Result<Favorites> {
+        let endpoint = format!("{}{}", self.base_url, Endpoint::Favorites);
+        let params = vec![("type", "tracks"), ("limit", "500"), ("offset", "0")];
+
+        get!(self, &endpoint, Some(&params))
+    }
+
+    /// Add an album to the user's favorites
+    pub async fn add_favorite_album(&self, album_id: &str) -> Result<SuccessfulResponse> {
+        self.edit_favorites(Endpoint::FavoriteCreate, FavoriteType::Album, album_id)
+            .await
+    }
+
+    /// Add a track to the user's favorites
+    pub async fn add_favorite_track(&self, track_id: i32) -> Result<SuccessfulResponse> {
+        self.edit_favorites(
+            Endpoint::FavoriteCreate,
+            FavoriteType::Track,
+            &track_id.to_string(),
+        )
+        .await
+    }
+
+    /// Add an artist to the user's favorites
+    pub async fn add_favorite_artist(&self, artist_id: i32) -> Result<SuccessfulResponse> {
+        self.edit_favorites(
+            Endpoint::FavoriteCreate,
+            FavoriteType::Artist,
+            &artist_id.to_string(),
+        )
+        .await
+    }
+
+    /// Remove an album from the user's favorites
+    pub async fn remove_favorite_album(&self, album_id: &str) -> Result<SuccessfulResponse> {
+        self.edit_favorites(Endpoint::FavoriteDelete, FavoriteType::Album, album_id)
+            .await
+    }
+
+    /// Remove a track from the user's favorites
+    pub async fn remove_favorite_track(&self, track_id: i32) -> Result<SuccessfulResponse> {
+        self.edit_favorites(
+            Endpoint::FavoriteDelete,
+            FavoriteType::Track,
+            &track_id.to_string(),
+        )
+        .await
+    }
+
+    /// Remove an artist from the user's favorites
+    pub async fn remove_favorite_artist(&self, artist_id: i32) -> Result<SuccessfulResponse> {
+        self.edit_favorites(
+            Endpoint::FavoriteDelete,
+            FavoriteType::Artist,
+            &artist_id.to_string(),
+        )
+        .await
+    }
+
+    async fn edit_favorites(
+        &self,
+        endpoint: Endpoint,
+        favorite_type: FavoriteType,
+        id: &str,
+    ) -> Result<SuccessfulResponse> {
+        let endpoint = format!("{}{}", self.base_url, endpoint);
+
+        let mut form_data = HashMap::new();
+        form_data.insert(favorite_type.ids_param(), id);
+
+        post!(self, &endpoint, form_data)
+    }
+
     /// Retrieve a playlist
     pub async fn playlist(&self, playlist_id: i64) -> Result<Playlist> {
         let endpoint = format!("{}{}", self.base_url, Endpoint::Playlist);
@@ -272,6 +511,7 @@ impl Client {
         Ok(())
     }
 
+    /// Create a playlist for the user. Only public playlists can be collaborative.
     pub async fn create_playlist(
         &self,
         name: String,
@@ -307,6 +547,7 @@ impl Client {
         post!(self, &endpoint, form_data)
     }
 
+    /// Delete one of the user's playlists.
     pub async fn delete_playlist(&self, playlist_id: String) -> Result<SuccessfulResponse> {
         let endpoint = format!("{}{}", self.base_url, Endpoint::PlaylistDelete);
 
@@ -334,7 +575,8 @@ impl Client {
         post!(self, &endpoint, form_data)
     }
 
-    /// Add new track to playlist
+    /// Remove entries from a playlist. Entries are identified by their playlist track id,
+    /// so the same track can be removed from one spot and kept in another.
     pub async fn playlist_delete_track(
         &self,
         playlist_id: String,
@@ -351,12 +593,12 @@ impl Client {
         post!(self, &endpoint, form_data)
     }
 
-    /// Update track position in playlist
-    pub async fn playlist_track_position(
+    /// Move an entry of a playlist so it comes before the entry at `index`.
+    pub async fn playlist_update_position(
         &self,
         index: usize,
         playlist_id: &str,
-        track_id: &str,
+        playlist_track_id: &str,
     ) -> Result<Playlist> {
         let endpoint = format!("{}{}", self.base_url, Endpoint::PlaylistUpdatePosition);
 
@@ -364,7 +606,7 @@ impl Client {
 
         let mut form_data = HashMap::new();
         form_data.insert("playlist_id", playlist_id);
-        form_data.insert("playlist_track_ids", track_id);
+        form_data.insert("playlist_track_ids", playlist_track_id);
         form_data.insert("insert_before", index.as_str());
 
         post!(self, &endpoint, form_data)
@@ -433,6 +675,28 @@ impl Client {
         get!(self, &endpoint, Some(&params))
     }
 
+    /// Find the track with the given ISRC. When searching for the ISRC comes up
+    /// empty, the title, artist and duration in `hints` are searched for instead.
+    pub async fn find_track_by_isrc(
+        &self,
+        isrc: &str,
+        hints: Option<&TrackHints>,
+    ) -> Result<Option<Track>> {
+        let results = self.search_all(&isrc.to_lowercase(), 100).await?;
+
+        if let Some(track) = matching::match_isrc(&results.tracks.items, isrc, hints) {
+            return Ok(Some(track));
+        }
+
+        if let Some((hints, query)) = hints.and_then(|h| h.query().map(|q| (h, q))) {
+            let results = self.search_all(&query, 100).await?;
+
+            return Ok(matching::match_metadata(&results.tracks.items, hints));
+        }
+
+        Ok(None)
+    }
+
     // Retrieve information about an album
     pub async fn album(&self, album_id: &str) -> Result<Album> {
         let endpoint = format!("{}{}", self.base_url, Endpoint::Album);
@@ -446,6 +710,94 @@ impl Client {
         get!(self, &endpoint, Some(&params))
     }
 
+    // Retrieve a page of one of the featured album sections
+    pub async fn featured_albums(
+        &self,
+        featured_type: FeaturedAlbumType,
+        limit: i32,
+        offset: i32,
+    ) -> Result<FeaturedAlbums> {
+        let endpoint = format!("{}{}", self.base_url, Endpoint::FeaturedAlbums);
+        let featured_type = featured_type.to_string();
+        let limit = limit.to_string();
+        let offset = offset.to_string();
+        let params = vec![
+            ("type", featured_type.as_str()),
+            ("limit", limit.as_str()),
+            ("offset", offset.as_str()),
+        ];
+
+        get!(self, &endpoint, Some(&params))
+    }
+
+    // Retrieve a page of one of the featured album sections, narrowed to a genre
+    pub async fn genre_albums(
+        &self,
+        genre_id: i64,
+        featured_type: FeaturedAlbumType,
+        limit: i32,
+        offset: i32,
+    ) -> Result<FeaturedAlbums> {
+        let endpoint = format!("{}{}", self.base_url, Endpoint::FeaturedAlbums);
+        let genre_id = genre_id.to_string();
+        let featured_type = featured_type.to_string();
+        let limit = limit.to_string();
+        let offset = offset.to_string();
+        let params = vec![
+            ("type", featured_type.as_str()),
+            ("genre_id", genre_id.as_str()),
+            ("limit", limit.as_str()),
+            ("offset", offset.as_str()),
+        ];
+
+        get!(self, &endpoint, Some(&params))
+    }
+
+    // Retrieve a page of the playlists picked by Qobuz's editors
+    pub async fn featured_playlists(&self, limit: i32, offset: i32) -> Result<FeaturedPlaylists> {
+        let endpoint = format!("{}{}", self.base_url, Endpoint::FeaturedPlaylists);
+        let limit = limit.to_string();
+        let offset = offset.to_string();
+        let params = vec![
+            ("type", "editor-picks"),
+            ("limit", limit.as_str()),
+            ("offset", offset.as_str()),
+        ];
+
+        get!(self, &endpoint, Some(&params))
+    }
+
+    // Retrieve the tracks of one of the user's personalized playlists
+    pub async fn dynamic_tracks(
+        &self,
+        list_type: DynamicListType,
+        limit: i32,
+    ) -> Result<DynamicTracks> {
+        let endpoint = format!("{}{}", self.base_url, Endpoint::DynamicTracks);
+        let list_type = list_type.to_string();
+        let limit = limit.to_string();
+        let params = vec![
+            ("type", list_type.as_str()),
+            ("limit", limit.as_str()),
+            ("offset", "0"),
+        ];
+
+        get!(self, &endpoint, Some(&params))
+    }
+
+    // Retrieve the top level genres, or the ones below a parent genre
+    pub async fn genres(&self, parent_id: Option<i64>) -> Result<GenreList> {
+        let endpoint = format!("{}{}", self.base_url, Endpoint::Genres);
+        let parent_id = parent_id.map(|id| id.to_string());
+        let mut params = vec![("limit", "100"), ("offset", "0")];
+
+        if let Some(parent_id) = &parent_id {
+            params.push(("parent_id", parent_id.as_str()));
+        }
+
+        get!(self, &endpoint, Some(&params))
+    }
+
     // Search the database for albums
     pub async fn search_albums(
         &self,
@@ -489,6 +841,24 @@ impl Client {
         }
     }
 
+    // Retrieve the artists that are similar to an artist
+    pub async fn similar_artists(
+        &self,
+        artist_id: i32,
+        limit: Option<i32>,
+    ) -> Result<SimilarArtistsResults> {
+        let endpoint = format!("{}{}", self.base_url, Endpoint::SimilarArtists);
+        let artist_id = artist_id.to_string();
+        let limit = limit.unwrap_or(10).to_string();
+        let params = vec![
+            ("artist_id", artist_id.as_str()),
+            ("limit", limit.as_str()),
+            ("offset", "0"),
+        ];
+
+        get!(self, &endpoint, Some(&params))
+    }
+
     // Search the database for artists
     pub async fn search_artists(
         &self,
@@ -506,9 +876,45 @@ impl Client {
         get!(self, &endpoint, Some(&params))
     }
 
+    // Search the database for tracks
+    pub async fn search_tracks(
+        &self,
+        query: &str,
+        limit: Option<i32>,
+    ) -> Result<TrackSearchResults> {
+        let endpoint = format!("{}{}", self.base_url, Endpoint::SearchTracks);
+        let limit = limit.unwrap_or(100).to_string();
+        let params = vec![("query", query), ("limit", limit.as_str())];
+
+        get!(self, &endpoint, Some(&params))
+    }
+
+    // Search the database for playlists
+    pub async fn search_playlists(
+        &self,
+        query: &str,
+        limit: Option<i32>,
+    ) -> Result<PlaylistSearchResults> {
+        let endpoint = format!("{}{}", self.base_url, Endpoint::SearchPlaylists);
+        let limit = limit.unwrap_or(100).to_string();
+        let params = vec![("query", query), ("limit", limit.as_str())];
+
+        get!(self, &endpoint, Some(&params))
+    }
+
     // Set a user access token for authentication
     pub fn set_token(&mut self, token: String) {
-        self.user_token = Some(token);
+        self.user_token.send_replace(Some(token));
+    }
+
+    // Keep the username and password to sign in again with when the token is rejected
+    pub fn set_credentials(&mut self, username: String, password: String) {
+        self.credentials = Some((username, password));
+    }
+
+    /// Changes of the user token, e.g. after it was renewed because it had expired.
+    pub fn token_changes(&self) -> watch::Receiver<Option<String>> {
+        self.user_token.subscribe()
     }
 
     // Set an app_id for authentication
@@ -525,8 +931,13 @@ impl Client {
         self.default_quality = quality;
     }
 
-    pub fn get_token(&self) -> Option<&String> {
-        self.user_token.as_ref()
+    // Set the Accept-Language header so Qobuz returns localized metadata
+    pub fn set_accept_language(&mut self, accept_language: String) {
+        self.accept_language = accept_language;
+    }
+
+    pub fn get_token(&self) -> Option<String> {
+        self.user_token.borrow().clone()
     }
 
     pub fn get_active_secret(&self) -> Option<&String> {
@@ -547,7 +958,7 @@ impl Client {
             error!("no app_id");
         }
 
-        if let Some(token) = &self.user_token {
+        if let Some(token) = self.user_token.borrow().as_ref() {
             info!("adding token to request headers: {}", token);
             headers.insert(
                 "X-User-Auth-Token",
@@ -562,12 +973,34 @@ impl Client {
 
         headers.insert(
             "Accept-Language",
-            HeaderValue::from_str("en,en-US;q=0.8,ko;q=0.6,zh;q=0.4,zh-CN;q=0.2").unwrap(),
+            HeaderValue::from_str(self.accept_language.as_str()).unwrap(),
         );
 
         headers
     }
 
+    fn http(&self) -> reqwest::Client {
+        self.client
+            .read()
+            .expect("failed to lock http client")
+            .clone()
+    }
+
+    /// Drop every pooled connection, so the next request resolves the API host again
+    /// and connects over the current network.
+    pub fn reconnect(&self) {
+        debug!("replacing http client");
+        *self.client.write().expect("failed to lock http client") = http_client(&self.http_config);
+    }
+
+    /// Rebuild the HTTP client with new tuning. Open connections are dropped.
+    pub fn set_http_config(&mut self, config: HttpConfig) {
+        debug!(?config, "configuring http client");
+        *self.client.write().expect("failed to lock http client") = http_client(&config);
+        self.limiter = RateLimiter::new(config.rate_limit);
+        self.http_config = config;
+    }
+
     // Make a GET call to the API with the provided parameters
     async fn make_get_call(
         &self,
@@ -577,14 +1010,12 @@ impl Client {
         let headers = self.client_headers();
 
         debug!("calling {} endpoint, with params {params:?}", endpoint);
-        let request = self.client.request(Method::GET, endpoint).headers(headers);
+        let request = self.http().request(Method::GET, endpoint).headers(headers);
 
         if let Some(p) = params {
-            let response = request.query(&p).send().await?;
-            self.handle_response(response).await
+            self.send(endpoint, request.query(&p), true).await
         } else {
-            let response = request.send().await?;
-            self.handle_response(response).await
+            self.send(endpoint, request, true).await
         }
     }
 
@@ -593,22 +1024,164 @@ impl Client {
         let headers = self.client_headers();
 
         debug!("calling {} endpoint, with params {params:?}", endpoint);
-        let response = self
-            .client
+        let request = self
+            .http()
             .request(Method::POST, endpoint)
             .headers(headers)
-            .form(&params)
+            .form(&params);
+
+        // A POST that failed on the server may still have changed something, tracks added
+        // to a playlist or a playlist created, so it is not sent twice.
+        self.send(endpoint, request, false).await
+    }
+
+    // Send a request, recording its latency for the endpoint and logging it when it is slow.
+    // Requests wait for the rate limit, and rate limited calls are tried again after a
+    // backoff, as are server errors for `idempotent` requests. Errors carry the request id
+    // that the logs show for the call.
+    async fn send(
+        &self,
+        endpoint: &str,
+        request: RequestBuilder,
+        idempotent: bool,
+    ) -> Result<String> {
+        let id = metrics::next_request_id();
+        let name = endpoint.strip_prefix(&self.base_url).unwrap_or(endpoint);
+        let started = Instant::now();
+
+        let result = async {
+            let mut request = request;
+            let mut attempt = 0;
+
+            let mut signed_in_again = false;
+
+            loop {
+                // Requests with a streamed body can't be cloned and are only sent once.
+                let retry = request.try_clone();
+                let token = self.get_token();
+
+                self.limiter.acquire().await;
+                let response = request.send().await?;
+                let status = response.status().as_u16();
+
+                match retry {
+                    Some(retry)
+                        if response.status() == StatusCode::UNAUTHORIZED
+                            && !signed_in_again
+                            && self.credentials.is_some() =>
+                    {
+                        signed_in_again = true;
+
+                        if let Err(error) = self.sign_in_again(token.as_deref()).await {
+                            warn!(%error, "failed to sign in again");
+                            return self.handle_response(response).await;
+                        }
+
+                        request = retry.headers(self.client_headers());
+                    }
+                    Some(retry)
+                        if throttle::is_retryable(status, idempotent)
+                            && attempt < self.http_config.max_retries =>
+                    {
+                        let delay = throttle::backoff(attempt, retry_after(&response));
+                        debug!(status, attempt, ?delay, "retrying Qobuz API call");
+
+                        tokio::time::sleep(delay).await;
+                        request = retry;
+                        attempt += 1;
+                    }
+                    _ => {
+                        if throttle::is_retryable(status, idempotent) && attempt > 0 {
+                            warn!(
+                                status,
+                                attempts = attempt + 1,
+                                "giving up on Qobuz API call"
+                            );
+                            throttle::record_gave_up(GaveUp {
+                                endpoint: name.to_string(),
+                                status,
+                                attempts: attempt + 1,
+                            });
+                        }
+
+                        return self.handle_response(response).await;
+                    }
+                }
+            }
+        }
+        .instrument(debug_span!("qobuz_request", id, endpoint = name))
+        .await;
+
+        let elapsed = started.elapsed();
+        metrics::record(name, elapsed, result.is_ok());
+
+        if elapsed > self.http_config.slow_threshold {
+            warn!(
+                request_id = id,
+                endpoint = name,
+                elapsed_ms = elapsed.as_millis() as u64,
+                "slow Qobuz API call"
+            );
+        }
+
+        result.map_err(|error| match error {
+            Error::Api { message } => Error::Api {
+                message: format!("{message} (request {id})"),
+            },
+            error => error,
+        })
+    }
+
+    // Sign in again with the stored username and password after `rejected` was refused.
+    // Calls that were rejected at the same time find the new token and don't sign in again.
+    // This calls the API directly, as going through `send` would nest the retries.
+    async fn sign_in_again(&self, rejected: Option<&str>) -> Result<()> {
+        let _relogin = self.relogin.lock().await;
+
+        if self.user_token.borrow().as_deref() != rejected {
+            return Ok(());
+        }
+
+        let (Some((username, password)), Some(app_id)) = (&self.credentials, &self.app_id) else {
+            return Err(Error::Login);
+        };
+
+        info!("the user token expired, signing in again");
+
+        let endpoint = format!("{}{}", self.base_url, Endpoint::Login);
+        let params = [
+            ("email", username.as_str()),
+            ("password", password.as_str()),
+            ("app_id", app_id.as_str()),
+        ];
+
+        self.limiter.acquire().await;
+        let response = self
+            .http()
+            .get(&endpoint)
+            .headers(self.client_headers())
+            .query(&params)
             .send()
             .await?;
+        let token = user_auth_token(&self.handle_response(response).await?)?;
 
-        self.handle_response(response).await
+        self.user_token.send_replace(Some(token));
+
+        Ok(())
     }
 
     // Handle a response retrieved from the api
     async fn handle_response(&self, response: Response) -> Result<String> {
         if response.status() == StatusCode::OK {
+            health::record_healthy();
+
             let res = response.text().await.unwrap();
             Ok(res)
+        } else if response.status().is_server_error() {
+            let status = response.status().as_u16();
+            health::record_unavailable(status, retry_after(&response));
+
+            Err(Error::Unavailable { status })
         } else {
             Err(Error::Api {
                 message: response.status().to_string(),
@@ -621,14 +1194,14 @@ impl Client {
     pub async fn refresh(&mut self) -> Result<()> {
         debug!("fetching login page");
         let play_url = "https://play.qobuz.com";
-        let login_page = self.client.get(format!("{play_url}/login")).send().await?;
+        let login_page = self.http().get(format!("{play_url}/login")).send().await?;
 
         let contents = login_page.text().await.unwrap();
 
         if let Some(captures) = self.bundle_regex.captures(contents.as_str()) {
             let bundle_path = captures.get(1).map_or("", |m| m.as_str());
             let bundle_url = format!("{play_url}{bundle_path}");
-            if let Ok(bundle_page) = self.client.get(bundle_url).send().await {
+            if let Ok(bundle_page) = self.http().get(bundle_url).send().await {
                 if let Ok(bundle_contents) = bundle_page.text().await {
                     if let Some(captures) = self.app_id_regex.captures(bundle_contents.as_str()) {
                         let app_id = captures
@@ -721,12 +1294,69 @@ pub struct SuccessfulResponse {
     status: String,
 }
 
+impl SuccessfulResponse {
+    /// True when the API answered with `"status": "success"`.
+    pub fn is_success(&self) -> bool {
+        self.status == "success"
+    }
+}
+
 #[derive(Clone, Debug, Serialize, Deserialize, ValueEnum)]
 pub enum OutputFormat {
     Json,
     Tsv,
 }
 
+/// Answer every connection with `status` and an empty JSON body, for calls that must not
+/// reach the API.
+#[cfg(test)]
+async fn answer_with(status: &'static str) -> String {
+    use tokio::io::{AsyncReadExt, AsyncWriteExt};
+
+    let listener = tokio::net::TcpListener::bind("127.0.0.1:0")
+        .await
+        .expect("failed to bind");
+    let address = listener.local_addr().expect("no local address");
+
+    tokio::spawn(async move {
+        while let Ok((mut stream, _)) = listener.accept().await {
+            let mut request = [0; 4096];
+            let _ = stream.read(&mut request).await;
+            let response =
+                format!("HTTP/1.1 {status}\r\nContent-Length: 2\r\nConnection: close\r\n\r\n{{}}");
+            let _ = stream.write_all(response.as_bytes()).await;
+        }
+    });
+
+    format!("http://{address}/")
+}
+
+#[tokio::test]
+async fn send_records_metrics() {
+    let mut client = new(None, None, None, None)
+        .await
+        .expect("failed to create client");
+
+    for (status, endpoint, ok) in [
+        ("200 OK", "metrics-test/ok", true),
+        ("404 Not Found", "metrics-test/missing", false),
+    ] {
+        client.base_url = answer_with(status).await;
+
+        let url = format!("{}{endpoint}", client.base_url);
+        let result = client.send(&url, client.http().get(&url), true).await;
+        assert_eq!(result.is_ok(), ok);
+
+        let latency = metrics::summary()
+            .into_iter()
+            .find(|latency| latency.endpoint == endpoint)
+            .expect("the call wasn't recorded");
+
+        assert_eq!(latency.calls, 1);
+        assert_eq!(latency.errors, u64::from(!ok));
+    }
+}
+
 #[tokio::test]
 async fn can_use_methods() {
     //pretty_env_logger::init();
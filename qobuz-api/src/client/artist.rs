@@ -8,6 +8,11 @@ pub struct ArtistSearchResults {
     pub artists: Artists,
 }
 
+#[derive(Default, Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct SimilarArtistsResults {
+    pub artists: Artists,
+}
+
 #[derive(Default, Debug, Clone, PartialEq, Serialize, Deserialize)]
 pub struct Artists {
     pub limit: i64,
@@ -0,0 +1,41 @@
+use crate::client::{album::Albums, artist::Artists, track::Tracks};
+use serde::{Deserialize, Serialize};
+
+#[derive(Default, Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Favorites {
+    pub albums: Option<Albums>,
+    #[serde(default)]
+    pub artists: Option<Artists>,
+    #[serde(default)]
+    pub tracks: Option<Tracks>,
+}
+
+/// What can be added to or removed from the favorites.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum FavoriteType {
+    Album,
+    Artist,
+    Track,
+}
+
+impl FavoriteType {
+    /// The parameter of `favorite/create` and `favorite/delete` taking ids of this type.
+    pub fn ids_param(&self) -> &'static str {
+        match self {
+            FavoriteType::Album => "album_ids",
+            FavoriteType::Artist => "artist_ids",
+            FavoriteType::Track => "track_ids",
+        }
+    }
+}
+
+#[test]
+fn reads_each_type_on_its_own() {
+    let favorites: Favorites =
+        serde_json::from_str(r#"{"tracks":{"offset":0,"limit":500,"total":0,"items":[]}}"#)
+            .expect("failed to read favorites");
+
+    assert!(favorites.albums.is_none());
+    assert!(favorites.artists.is_none());
+    assert_eq!(favorites.tracks.map(|t| t.total), Some(0));
+}
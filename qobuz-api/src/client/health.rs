@@ -0,0 +1,105 @@
+use std::{
+    sync::Mutex,
+    time::{Duration, Instant},
+};
+
+/// The first wait before checking whether the API is back. It doubles after every failed
+/// check, up to [`RETRY_MAX`].
+pub const RETRY_MIN: Duration = Duration::from_secs(15);
+pub const RETRY_MAX: Duration = Duration::from_secs(300);
+
+static OUTAGE: Mutex<Option<Outage>> = Mutex::new(None);
+
+/// The API answered with a server error, e.g. during maintenance, and is taken to be down
+/// until a call or a health check works again.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Outage {
+    /// The HTTP status of the call that failed.
+    pub status: u16,
+    /// When to check on the API again.
+    pub retry_at: Instant,
+    delay: Duration,
+}
+
+impl Outage {
+    /// Whole seconds left until the next check.
+    pub fn retry_in(&self) -> u64 {
+        self.retry_at
+            .saturating_duration_since(Instant::now())
+            .as_secs()
+    }
+}
+
+/// Take the API to be down after a call ended with `status`. `retry_after` is how long
+/// the API asked to wait, if it did.
+pub fn record_unavailable(status: u16, retry_after: Option<Duration>) {
+    unavailable(
+        &mut OUTAGE.lock().expect("failed to lock api health"),
+        status,
+        retry_after,
+    );
+}
+
+/// The API is still down, wait twice as long before checking again.
+pub fn record_failed_check(retry_after: Option<Duration>) {
+    failed_check(
+        &mut OUTAGE.lock().expect("failed to lock api health"),
+        retry_after,
+    );
+}
+
+fn unavailable(outage: &mut Option<Outage>, status: u16, retry_after: Option<Duration>) {
+    match outage.as_mut() {
+        Some(outage) => outage.status = status,
+        None => {
+            *outage = Some(Outage {
+                status,
+                retry_at: Instant::now() + retry_after.unwrap_or(RETRY_MIN),
+                delay: RETRY_MIN,
+            })
+        }
+    }
+}
+
+fn failed_check(outage: &mut Option<Outage>, retry_after: Option<Duration>) {
+    if let Some(outage) = outage.as_mut() {
+        outage.delay = (outage.delay * 2).min(RETRY_MAX);
+        outage.retry_at = Instant::now() + retry_after.unwrap_or(outage.delay);
+    }
+}
+
+/// The API answered normally. Returns true when it was down until now.
+pub fn record_healthy() -> bool {
+    OUTAGE
+        .lock()
+        .expect("failed to lock api health")
+        .take()
+        .is_some()
+}
+
+/// The current outage, `None` while the API works.
+pub fn outage() -> Option<Outage> {
+    *OUTAGE.lock().expect("failed to lock api health")
+}
+
+// Works on an outage of its own, the global one is shared with every call the tests make.
+#[test]
+fn checks_back_off_until_healthy() {
+    let mut outage = None;
+
+    unavailable(&mut outage, 503, None);
+    let first = outage.expect("no outage recorded");
+    assert_eq!(first.status, 503);
+    assert_eq!(first.delay, RETRY_MIN);
+
+    unavailable(&mut outage, 502, None);
+    assert_eq!(outage.map(|o| (o.status, o.delay)), Some((502, RETRY_MIN)));
+
+    for _ in 0..10 {
+        failed_check(&mut outage, None);
+    }
+    assert_eq!(outage.map(|o| o.delay), Some(RETRY_MAX));
+
+    failed_check(&mut outage, Some(Duration::from_secs(30)));
+    assert!(outage.is_some_and(|o| o.retry_in() <= 30));
+}
@@ -0,0 +1,155 @@
+use crate::client::track::Track;
+
+/// Tracks whose durations are within this many seconds are considered the same recording.
+pub const DURATION_TOLERANCE: i64 = 3;
+
+/// What is known about a track from elsewhere, used to pick between several
+/// results or to find the track when the ISRC itself does not match.
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub struct TrackHints {
+    pub title: Option<String>,
+    pub artist: Option<String>,
+    /// Duration in seconds.
+    pub duration: Option<i64>,
+}
+
+impl TrackHints {
+    /// A query to search for the track by its metadata, if there is enough to go on.
+    pub fn query(&self) -> Option<String> {
+        match (&self.artist, &self.title) {
+            (Some(artist), Some(title)) => Some(format!("{artist} {title}")),
+            _ => None,
+        }
+    }
+
+    fn duration_matches(&self, track: &Track) -> bool {
+        self.duration
+            .map(|d| (d - track.duration).abs() <= DURATION_TOLERANCE)
+            .unwrap_or(true)
+    }
+}
+
+/// Lowercase `value` and strip everything that isn't a letter or a number,
+/// along with anything in brackets such as `(Remastered 2011)`.
+pub fn normalize(value: &str) -> String {
+    let mut depth = 0_u32;
+
+    value
+        .chars()
+        .filter(|c| match c {
+            '(' | '[' => {
+                depth += 1;
+                false
+            }
+            ')' | ']' => {
+                depth = depth.saturating_sub(1);
+                false
+            }
+            _ => depth == 0 && c.is_alphanumeric(),
+        })
+        .flat_map(char::to_lowercase)
+        .collect()
+}
+
+/// Prefer tracks that can be streamed, then the closest duration.
+fn best<'t>(
+    candidates: impl Iterator<Item = &'t Track>,
+    hints: Option<&TrackHints>,
+) -> Option<Track> {
+    candidates
+        .min_by_key(|t| {
+            let distance = hints
+                .and_then(|h| h.duration)
+                .map(|d| (d - t.duration).abs())
+                .unwrap_or_default();
+
+            (!t.streamable, distance)
+        })
+        .cloned()
+}
+
+/// Pick the track with the given ISRC out of `candidates`.
+pub fn match_isrc(candidates: &[Track], isrc: &str, hints: Option<&TrackHints>) -> Option<Track> {
+    best(
+        candidates.iter().filter(|t| {
+            t.isrc
+                .as_ref()
+                .is_some_and(|i| i.eq_ignore_ascii_case(isrc.trim()))
+        }),
+        hints,
+    )
+}
+
+/// Pick the track matching the title, artist and duration in `hints` out of `candidates`.
+pub fn match_metadata(candidates: &[Track], hints: &TrackHints) -> Option<Track> {
+    let (Some(title), Some(artist)) = (&hints.title, &hints.artist) else {
+        return None;
+    };
+
+    let title = normalize(title);
+    let artist = normalize(artist);
+
+    best(
+        candidates.iter().filter(|t| {
+            let performer = t
+                .performer
+                .as_ref()
+                .map(|p| normalize(&p.name))
+                .unwrap_or_default();
+
+            normalize(&t.title) == title
+                && !performer.is_empty()
+                && (performer.contains(&artist) || artist.contains(&performer))
+                && hints.duration_matches(t)
+        }),
+        Some(hints),
+    )
+}
+
+#[cfg(test)]
+fn track(id: i32, isrc: &str, title: &str, artist: &str, duration: i64, streamable: bool) -> Track {
+    use crate::client::track::Performer;
+
+    Track {
+        id,
+        isrc: Some(isrc.to_string()),
+        title: title.to_string(),
+        performer: Some(Performer {
+            id: 1,
+            name: artist.to_string(),
+        }),
+        duration,
+        streamable,
+        ..Default::default()
+    }
+}
+
+#[test]
+fn matches_isrc_and_prefers_streamable() {
+    let candidates = vec![
+        track(1, "USAT21001269", "Song", "Artist", 200, false),
+        track(2, "usat21001269", "Song", "Artist", 200, true),
+        track(3, "GBAYE0601498", "Song", "Artist", 200, true),
+    ];
+
+    let found = match_isrc(&candidates, "USAT21001269", None).map(|t| t.id);
+    assert_eq!(found, Some(2));
+    assert!(match_isrc(&candidates, "USXXX0000000", None).is_none());
+}
+
+#[test]
+fn falls_back_to_metadata() {
+    let candidates = vec![
+        track(1, "A", "Song (Live)", "Someone Else", 200, true),
+        track(2, "B", "Song - Remastered", "The Artist", 260, true),
+        track(3, "C", "Song (Remastered 2011)", "The Artist", 201, true),
+    ];
+    let hints = TrackHints {
+        title: Some("Song".to_string()),
+        artist: Some("The Artist".to_string()),
+        duration: Some(200),
+    };
+
+    assert_eq!(match_metadata(&candidates, &hints).map(|t| t.id), Some(3));
+    assert_eq!(normalize("Don't Stop (Remastered)"), "dontstop");
+}
@@ -0,0 +1,131 @@
+use serde::{Deserialize, Serialize};
+use std::{
+    collections::BTreeMap,
+    sync::{
+        atomic::{AtomicU64, Ordering},
+        Mutex,
+    },
+    time::Duration,
+};
+
+/// Upper bounds, in milliseconds, of the latency histogram buckets. Slower calls
+/// go in one more bucket at the end.
+pub const BUCKETS_MS: [u64; 7] = [50, 100, 250, 500, 1000, 2500, 5000];
+
+static NEXT_REQUEST_ID: AtomicU64 = AtomicU64::new(1);
+static ENDPOINTS: Mutex<BTreeMap<String, Histogram>> = Mutex::new(BTreeMap::new());
+
+#[derive(Debug, Default, Clone)]
+struct Histogram {
+    counts: [u64; BUCKETS_MS.len() + 1],
+    errors: u64,
+    total_ms: u64,
+    max_ms: u64,
+}
+
+impl Histogram {
+    fn add(&mut self, ms: u64, ok: bool) {
+        let bucket = BUCKETS_MS
+            .iter()
+            .position(|bound| ms <= *bound)
+            .unwrap_or(BUCKETS_MS.len());
+
+        self.counts[bucket] += 1;
+        self.total_ms += ms;
+        self.max_ms = self.max_ms.max(ms);
+
+        if !ok {
+            self.errors += 1;
+        }
+    }
+
+    fn count(&self) -> u64 {
+        self.counts.iter().sum()
+    }
+
+    /// The bucket bound below which `quantile` of the calls finished. Calls slower
+    /// than the last bound report the slowest call instead.
+    fn quantile(&self, quantile: f64) -> u64 {
+        let target = (self.count() as f64 * quantile).ceil() as u64;
+        let mut seen = 0;
+
+        for (index, count) in self.counts.iter().enumerate() {
+            seen += count;
+
+            if seen >= target && *count > 0 {
+                return BUCKETS_MS.get(index).copied().unwrap_or(self.max_ms);
+            }
+        }
+
+        self.max_ms
+    }
+}
+
+/// Latency of the calls to one endpoint since the client was created.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct EndpointLatency {
+    pub endpoint: String,
+    pub calls: u64,
+    pub errors: u64,
+    pub mean_ms: u64,
+    pub p50_ms: u64,
+    pub p95_ms: u64,
+    pub max_ms: u64,
+}
+
+/// A new id to tell a request apart in the logs and in the error it may end with.
+pub fn next_request_id() -> u64 {
+    NEXT_REQUEST_ID.fetch_add(1, Ordering::Relaxed)
+}
+
+/// Count a finished call to `endpoint`.
+pub fn record(endpoint: &str, elapsed: Duration, ok: bool) {
+    ENDPOINTS
+        .lock()
+        .expect("failed to lock api metrics")
+        .entry(endpoint.to_string())
+        .or_default()
+        .add(elapsed.as_millis() as u64, ok);
+}
+
+/// The latency of every endpoint that was called, slowest on average first.
+pub fn summary() -> Vec<EndpointLatency> {
+    let endpoints = ENDPOINTS.lock().expect("failed to lock api metrics");
+
+    let mut summary = endpoints
+        .iter()
+        .map(|(endpoint, histogram)| {
+            let calls = histogram.count();
+
+            EndpointLatency {
+                endpoint: endpoint.clone(),
+                calls,
+                errors: histogram.errors,
+                mean_ms: histogram.total_ms / calls.max(1),
+                p50_ms: histogram.quantile(0.5),
+                p95_ms: histogram.quantile(0.95),
+                max_ms: histogram.max_ms,
+            }
+        })
+        .collect::<Vec<EndpointLatency>>();
+
+    summary.sort_by_key(|latency| std::cmp::Reverse(latency.mean_ms));
+
+    summary
+}
+
+#[test]
+fn quantiles_come_from_bucket_bounds() {
+    let mut histogram = Histogram::default();
+
+    for ms in [20, 40, 80, 90, 300, 700, 900, 1200, 1800, 7000] {
+        histogram.add(ms, ms < 5000);
+    }
+
+    assert_eq!(histogram.count(), 10);
+    assert_eq!(histogram.counts, [2, 2, 0, 1, 2, 2, 0, 1]);
+    assert_eq!(histogram.errors, 1);
+    assert_eq!(histogram.quantile(0.5), 500);
+    assert_eq!(histogram.quantile(0.95), 7000);
+}
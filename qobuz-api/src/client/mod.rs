@@ -1,13 +1,18 @@
 use clap::ValueEnum;
 use serde::{Deserialize, Serialize};
 use snafu::prelude::*;
-use std::fmt::Display;
+use std::{fmt::Display, time::Duration};
 
 pub mod album;
 pub mod api;
 pub mod artist;
+pub mod favorites;
+pub mod health;
+pub mod matching;
+pub mod metrics;
 pub mod playlist;
 pub mod search_results;
+pub mod throttle;
 pub mod track;
 
 #[derive(Default, Debug)]
@@ -18,6 +23,93 @@ pub struct ApiConfig {
     pub user_token: Option<String>,
     pub app_id: Option<String>,
     pub active_secret: Option<String>,
+    pub language: Option<String>,
+    pub http2: Option<bool>,
+    pub http_pool_size: Option<i64>,
+    pub http_connect_timeout: Option<i64>,
+    pub http_timeout: Option<i64>,
+    pub http_keep_alive: Option<i64>,
+    pub api_slow_threshold: Option<i64>,
+    pub api_rate_limit: Option<i64>,
+    pub api_retries: Option<i64>,
+}
+
+impl ApiConfig {
+    /// The HTTP client tuning, with defaults for anything that is not set.
+    /// Timeouts and keep-alive are stored in seconds, the slow call threshold in milliseconds
+    /// and the rate limit in calls per second.
+    pub fn http_config(&self) -> HttpConfig {
+        let default = HttpConfig::default();
+        let secs = |value: Option<i64>, default: Duration| {
+            value
+                .filter(|secs| *secs > 0)
+                .map(|secs| Duration::from_secs(secs as u64))
+                .unwrap_or(default)
+        };
+
+        HttpConfig {
+            http2: self.http2.unwrap_or(default.http2),
+            pool_size: self
+                .http_pool_size
+                .filter(|size| *size >= 0)
+                .map(|size| size as usize)
+                .unwrap_or(default.pool_size),
+            connect_timeout: secs(self.http_connect_timeout, default.connect_timeout),
+            request_timeout: secs(self.http_timeout, default.request_timeout),
+            keep_alive: secs(self.http_keep_alive, default.keep_alive),
+            slow_threshold: self
+                .api_slow_threshold
+                .filter(|ms| *ms > 0)
+                .map(|ms| Duration::from_millis(ms as u64))
+                .unwrap_or(default.slow_threshold),
+            rate_limit: self
+                .api_rate_limit
+                .filter(|rate| *rate >= 0)
+                .map(|rate| rate as u32)
+                .unwrap_or(default.rate_limit),
+            max_retries: self
+                .api_retries
+                .filter(|retries| *retries >= 0)
+                .map(|retries| retries as u32)
+                .unwrap_or(default.max_retries),
+        }
+    }
+}
+
+/// Tuning for the HTTP client shared by every request, including track URL signing.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct HttpConfig {
+    /// Use HTTP/2 when the server offers it, so requests share one connection.
+    pub http2: bool,
+    /// Idle connections kept open per host.
+    pub pool_size: usize,
+    pub connect_timeout: Duration,
+    /// Limit for a whole request, from connecting to reading the response.
+    pub request_timeout: Duration,
+    /// How long idle connections stay open, and the interval of TCP keep-alive probes.
+    pub keep_alive: Duration,
+    /// Calls that take longer than this are logged as slow.
+    pub slow_threshold: Duration,
+    /// API calls started per second at most, 0 for no limit. Track URLs are signed
+    /// through the API too.
+    pub rate_limit: u32,
+    /// Times a call that was rate limited or met a server error is tried again.
+    pub max_retries: u32,
+}
+
+impl Default for HttpConfig {
+    fn default() -> Self {
+        Self {
+            http2: true,
+            pool_size: 8,
+            connect_timeout: Duration::from_secs(10),
+            request_timeout: Duration::from_secs(30),
+            keep_alive: Duration::from_secs(90),
+            slow_threshold: Duration::from_secs(1),
+            rate_limit: 10,
+            max_retries: 3,
+        }
+    }
 }
 
 #[derive(Default, Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
@@ -46,6 +138,44 @@ pub struct TrackURL {
     pub mime_type: String,
     pub sampling_rate: f64,
     pub bit_depth: i32,
+    /// True when only a short preview of the track is available.
+    #[serde(default)]
+    pub sample: bool,
+    /// Why the track was not delivered as requested.
+    #[serde(default)]
+    pub restrictions: Vec<Restriction>,
+}
+
+/// The track itself is not available in the requested format.
+const FORMAT_RESTRICTED_BY_AVAILABILITY: &str = "FormatRestrictedByFormatAvailability";
+
+impl TrackURL {
+    /// The format Qobuz actually delivered.
+    pub fn delivered(&self) -> AudioQuality {
+        AudioQuality::from(self.format_id as i64)
+    }
+
+    /// True when the account's subscription, rather than the track, kept it below `requested`.
+    pub fn limited_by_account(&self, requested: &AudioQuality) -> bool {
+        if self.sample {
+            return true;
+        }
+
+        if self.delivered() as i64 >= requested.clone() as i64 {
+            return false;
+        }
+
+        self.restrictions.is_empty()
+            || self
+                .restrictions
+                .iter()
+                .any(|r| r.code != FORMAT_RESTRICTED_BY_AVAILABILITY)
+    }
+}
+
+#[derive(Default, Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Restriction {
+    pub code: String,
 }
 
 #[derive(Default, Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
@@ -75,7 +205,7 @@ pub enum UrlTypeError {
 pub type ParseUrlResult<T, E = UrlTypeError> = std::result::Result<T, E>;
 
 /// The audio quality as defined by the Qobuz API.
-#[derive(Default, Clone, Debug, Serialize, Deserialize, ValueEnum)]
+#[derive(Default, Clone, Debug, PartialEq, Eq, Serialize, Deserialize, ValueEnum)]
 pub enum AudioQuality {
     #[default]
     Mp3 = 5,
@@ -84,6 +214,18 @@ pub enum AudioQuality {
     HIFI192 = 27,
 }
 
+impl AudioQuality {
+    /// The next quality down, `None` for MP3.
+    pub fn lower(&self) -> Option<AudioQuality> {
+        match self {
+            Self::HIFI192 => Some(Self::HIFI96),
+            Self::HIFI96 => Some(Self::CD),
+            Self::CD => Some(Self::Mp3),
+            Self::Mp3 => None,
+        }
+    }
+}
+
 impl From<i64> for AudioQuality {
     fn from(quality_id: i64) -> Self {
         match quality_id {
@@ -157,3 +299,28 @@ pub fn capitalize(s: &mut str) {
         r.make_ascii_uppercase();
     }
 }
+
+#[cfg(test)]
+fn track_url(format_id: i32, codes: &[&str]) -> TrackURL {
+    TrackURL {
+        format_id,
+        restrictions: codes
+            .iter()
+            .map(|code| Restriction {
+                code: code.to_string(),
+            })
+            .collect(),
+        ..Default::default()
+    }
+}
+
+#[test]
+fn detects_account_limits() {
+    let hires = AudioQuality::HIFI192;
+
+    assert!(!track_url(27, &[]).limited_by_account(&hires));
+    assert!(!track_url(6, &[FORMAT_RESTRICTED_BY_AVAILABILITY]).limited_by_account(&hires));
+    assert!(track_url(5, &["FormatRestrictedByUserRights"]).limited_by_account(&hires));
+    assert!(track_url(5, &[]).limited_by_account(&AudioQuality::CD));
+    assert!(!track_url(5, &[]).limited_by_account(&AudioQuality::Mp3));
+}
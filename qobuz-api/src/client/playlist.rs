@@ -1,5 +1,17 @@
-use crate::client::{track::Tracks, User};
+use crate::{
+    client::{
+        api::Client,
+        track::{Track, Tracks},
+        User,
+    },
+    Error, Result,
+};
 use serde::{Deserialize, Serialize};
+use std::{
+    collections::{HashMap, HashSet},
+    fmt::Display,
+    path::{Path, PathBuf},
+};
 
 #[derive(Default, Debug, Clone, PartialEq, Serialize, Deserialize)]
 pub struct UserPlaylistsResult {
@@ -74,6 +86,55 @@ impl Playlist {
     }
 }
 
+#[derive(Default, Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct PlaylistSearchResults {
+    pub query: String,
+    pub playlists: Playlists,
+}
+
+/// The playlists Qobuz puts together for each user and renews on its own.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum DynamicListType {
+    /// Weekly Q, new picks every week.
+    Weekly,
+    /// New picks every day.
+    Daily,
+}
+
+impl Display for DynamicListType {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            DynamicListType::Weekly => f.write_str("weekly"),
+            DynamicListType::Daily => f.write_str("daily"),
+        }
+    }
+}
+
+#[derive(Default, Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct DynamicTracks {
+    #[serde(default)]
+    pub title: Option<String>,
+    #[serde(default)]
+    pub baseline: Option<String>,
+    #[serde(default)]
+    pub description: Option<String>,
+    pub tracks: DynamicTrackItems,
+}
+
+#[derive(Default, Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct DynamicTrackItems {
+    #[serde(default)]
+    pub offset: i64,
+    #[serde(default)]
+    pub limit: i64,
+    pub items: Vec<Track>,
+}
+
+#[derive(Default, Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct FeaturedPlaylists {
+    pub playlists: Playlists,
+}
+
 #[derive(Default, Debug, Clone, PartialEq, Serialize, Deserialize)]
 pub struct Playlists {
     pub offset: i64,
@@ -81,3 +142,287 @@ pub struct Playlists {
     pub total: i64,
     pub items: Vec<Playlist>,
 }
+
+/// A single change made to a playlist as part of a [`PlaylistTransaction`].
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase", tag = "type")]
+pub enum PlaylistOperation {
+    AddTracks {
+        track_ids: Vec<String>,
+    },
+    /// Moves the entry the transaction added for the catalog track `track_id`. Its playlist
+    /// track id is only known once it has been added, so it is looked up when committing.
+    MoveTrack {
+        track_id: String,
+        index: usize,
+    },
+}
+
+/// The contents of a resume file, every operation of a transaction that did not complete.
+#[derive(Default, Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PlaylistResume {
+    pub playlist_id: i64,
+    pub operations: Vec<PlaylistOperation>,
+}
+
+impl PlaylistResume {
+    pub fn load(path: &Path) -> Result<Self> {
+        let contents = std::fs::read_to_string(path).map_err(|error| Error::ResumeFile {
+            message: error.to_string(),
+        })?;
+
+        serde_json::from_str(&contents).map_err(|error| Error::ResumeFile {
+            message: error.to_string(),
+        })
+    }
+
+    fn save(&self, path: &Path) -> Result<()> {
+        let contents = serde_json::to_string_pretty(self).map_err(|error| Error::ResumeFile {
+            message: error.to_string(),
+        })?;
+
+        std::fs::write(path, contents).map_err(|error| Error::ResumeFile {
+            message: error.to_string(),
+        })
+    }
+}
+
+/// Applies a batch of playlist changes as a unit. If any operation fails,
+/// the tracks added by the batch are removed again and the rest put back in
+/// order, so the playlist is left the way it was found.
+pub struct PlaylistTransaction<'c> {
+    client: &'c Client,
+    playlist_id: i64,
+    /// The playlist track ids of the entries the playlist had, in order.
+    existing: Vec<i64>,
+    operations: Vec<PlaylistOperation>,
+    resume_file: Option<PathBuf>,
+}
+
+impl<'c> PlaylistTransaction<'c> {
+    /// Starts a transaction, remembering the tracks currently in the playlist.
+    pub async fn begin(client: &'c Client, playlist_id: i64) -> Result<PlaylistTransaction<'c>> {
+        let playlist = client.playlist(playlist_id).await?;
+
+        Ok(Self {
+            client,
+            playlist_id,
+            existing: playlist_entries(&playlist)
+                .into_iter()
+                .map(|(_, playlist_track_id)| playlist_track_id)
+                .collect(),
+            operations: Vec::new(),
+            resume_file: None,
+        })
+    }
+
+    /// Starts a transaction with the operations stored in a resume file.
+    pub async fn resume(client: &'c Client, path: &Path) -> Result<PlaylistTransaction<'c>> {
+        let resume = PlaylistResume::load(path)?;
+
+        let mut transaction = Self::begin(client, resume.playlist_id).await?;
+        transaction.operations = resume.operations;
+        transaction.resume_file = Some(path.to_path_buf());
+
+        Ok(transaction)
+    }
+
+    /// Write the operations to `path` if the transaction fails, so it can be retried.
+    pub fn with_resume_file(mut self, path: PathBuf) -> Self {
+        self.resume_file = Some(path);
+        self
+    }
+
+    pub fn add_tracks(&mut self, track_ids: Vec<String>) {
+        self.operations
+            .push(PlaylistOperation::AddTracks { track_ids });
+    }
+
+    pub fn move_track(&mut self, track_id: String, index: usize) {
+        self.operations
+            .push(PlaylistOperation::MoveTrack { track_id, index });
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.operations.is_empty()
+    }
+
+    /// Applies every operation in order, rolling back on the first failure.
+    pub async fn commit(self) -> Result<()> {
+        let playlist_id = self.playlist_id.to_string();
+        // Catalog track id to playlist track id of the entries added so far, fetched for
+        // the first move after an add.
+        let mut added = None;
+
+        for (applied, operation) in self.operations.iter().enumerate() {
+            let result = match operation {
+                PlaylistOperation::AddTracks { track_ids } => {
+                    added = None;
+
+                    self.client
+                        .playlist_add_track(
+                            &playlist_id,
+                            track_ids.iter().map(|id| id.as_str()).collect(),
+                        )
+                        .await
+                        .map(|_| ())
+                }
+                PlaylistOperation::MoveTrack { track_id, index } => {
+                    if added.is_none() {
+                        added = self.added_entries().await.ok();
+                    }
+
+                    match added.as_ref().and_then(|added| added.get(track_id)) {
+                        Some(playlist_track_id) => self
+                            .client
+                            .playlist_update_position(
+                                *index,
+                                &playlist_id,
+                                &playlist_track_id.to_string(),
+                            )
+                            .await
+                            .map(|_| ()),
+                        None => Err(Error::Api {
+                            message: format!("track {track_id} was not added to the playlist"),
+                        }),
+                    }
+                }
+            };
+
+            if let Err(error) = result {
+                error!(
+                    "playlist operation {} of {} failed: {}",
+                    applied + 1,
+                    self.operations.len(),
+                    error
+                );
+
+                self.save_resume_file();
+
+                if let Err(rollback_error) = self.rollback().await {
+                    error!("failed to roll back playlist changes: {}", rollback_error);
+                }
+
+                return Err(error);
+            }
+        }
+
+        if let Some(path) = &self.resume_file {
+            if path.exists() {
+                std::fs::remove_file(path).ok();
+            }
+        }
+
+        Ok(())
+    }
+
+    /// The entries added to the playlist since the transaction began, by catalog track id.
+    async fn added_entries(&self) -> Result<HashMap<String, i64>> {
+        let playlist = self.client.playlist(self.playlist_id).await?;
+        let existing = self.existing.iter().collect::<HashSet<&i64>>();
+
+        Ok(playlist_entries(&playlist)
+            .into_iter()
+            .filter(|(_, playlist_track_id)| !existing.contains(playlist_track_id))
+            .map(|(track_id, playlist_track_id)| (track_id.to_string(), playlist_track_id))
+            .collect())
+    }
+
+    /// Removes every track added to the playlist since the transaction began and moves
+    /// the entries it had back to where they were.
+    pub async fn rollback(&self) -> Result<()> {
+        if self.operations.is_empty() {
+            return Ok(());
+        }
+
+        let playlist = self.client.playlist(self.playlist_id).await?;
+        let existing = self.existing.iter().collect::<HashSet<&i64>>();
+        let (kept, new_tracks): (Vec<i64>, Vec<i64>) = playlist_entries(&playlist)
+            .into_iter()
+            .map(|(_, playlist_track_id)| playlist_track_id)
+            .partition(|playlist_track_id| existing.contains(playlist_track_id));
+
+        let playlist_id = self.playlist_id.to_string();
+
+        if !new_tracks.is_empty() {
+            debug!("rolling back {} added tracks", new_tracks.len());
+            self.client
+                .playlist_delete_track(
+                    playlist_id.clone(),
+                    new_tracks.iter().map(|id| id.to_string()).collect(),
+                )
+                .await?;
+        }
+
+        for (index, playlist_track_id) in restore_moves(&kept, &self.existing) {
+            self.client
+                .playlist_update_position(index, &playlist_id, &playlist_track_id.to_string())
+                .await?;
+        }
+
+        Ok(())
+    }
+
+    fn save_resume_file(&self) {
+        if let Some(path) = &self.resume_file {
+            let resume = PlaylistResume {
+                playlist_id: self.playlist_id,
+                operations: self.operations.clone(),
+            };
+
+            match resume.save(path) {
+                Ok(()) => info!("saved resume file to {}", path.to_string_lossy()),
+                Err(error) => error!("{}", error),
+            }
+        }
+    }
+}
+
+/// The catalog and playlist track id of every entry of `playlist`, in order.
+fn playlist_entries(playlist: &Playlist) -> Vec<(i32, i64)> {
+    playlist
+        .tracks
+        .as_ref()
+        .map(|tracks| {
+            tracks
+                .items
+                .iter()
+                .filter_map(|t| t.playlist_track_id.map(|id| (t.id, id)))
+                .collect()
+        })
+        .unwrap_or_default()
+}
+
+/// The moves, each putting an entry before the one at an index, that turn `current` into
+/// `original`. Both hold the same entries.
+fn restore_moves(current: &[i64], original: &[i64]) -> Vec<(usize, i64)> {
+    let mut current = current.to_vec();
+    let mut moves = vec![];
+
+    for (index, entry) in original.iter().enumerate() {
+        if current.get(index) == Some(entry) {
+            continue;
+        }
+
+        if let Some(from) = current.iter().position(|id| id == entry) {
+            current.remove(from);
+            current.insert(index, *entry);
+            moves.push((index, *entry));
+        }
+    }
+
+    moves
+}
+
+#[test]
+fn restores_the_original_order() {
+    let original = [1, 2, 3, 4, 5];
+
+    assert!(restore_moves(&original, &original).is_empty());
+    assert_eq!(
+        restore_moves(&[1, 4, 2, 3, 5], &original),
+        vec![(1, 2), (2, 3)]
+    );
+    assert_eq!(restore_moves(&[5, 1, 2, 3, 4], &original).len(), 4);
+}
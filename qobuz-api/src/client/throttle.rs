@@ -0,0 +1,140 @@
+use std::{
+    fmt::Display,
+    sync::{Arc, Mutex},
+    time::Duration,
+};
+use tokio::time::Instant;
+
+/// The wait before the first retry. It doubles with every attempt, up to [`BACKOFF_MAX`].
+pub const BACKOFF_MIN: Duration = Duration::from_millis(500);
+pub const BACKOFF_MAX: Duration = Duration::from_secs(30);
+
+static GAVE_UP: Mutex<Option<GaveUp>> = Mutex::new(None);
+
+/// A call that was still rate limited or failing after all of its retries.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct GaveUp {
+    pub endpoint: String,
+    /// The HTTP status of the last attempt.
+    pub status: u16,
+    pub attempts: u32,
+}
+
+impl Display for GaveUp {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        if self.status == 429 {
+            write!(
+                f,
+                "Qobuz is limiting requests to {}, gave up after {} attempts.",
+                self.endpoint, self.attempts
+            )
+        } else {
+            write!(
+                f,
+                "Qobuz failed to answer {} ({}) after {} attempts.",
+                self.endpoint, self.status, self.attempts
+            )
+        }
+    }
+}
+
+/// Note a call that used up its retries, for [`take_gave_up`].
+pub fn record_gave_up(gave_up: GaveUp) {
+    *GAVE_UP.lock().expect("failed to lock api retries") = Some(gave_up);
+}
+
+/// The last call that used up its retries since this was asked last.
+pub fn take_gave_up() -> Option<GaveUp> {
+    GAVE_UP.lock().expect("failed to lock api retries").take()
+}
+
+/// True for responses worth trying again: rate limiting, and server errors when sending
+/// the request twice does no harm.
+pub fn is_retryable(status: u16, idempotent: bool) -> bool {
+    status == 429 || (idempotent && (500..600).contains(&status))
+}
+
+/// How long to wait before retry number `attempt`, counting from 0. The exponential
+/// delay is jittered between half and all of it, so clients don't retry in lockstep.
+/// When the API said how long to wait, that is used instead, within [`BACKOFF_MAX`].
+pub fn backoff(attempt: u32, retry_after: Option<Duration>) -> Duration {
+    if let Some(retry_after) = retry_after {
+        return retry_after.min(BACKOFF_MAX);
+    }
+
+    let delay = BACKOFF_MIN
+        .saturating_mul(2u32.saturating_pow(attempt))
+        .min(BACKOFF_MAX);
+    let half = delay / 2;
+
+    half + half.mul_f64(rand::random::<f64>())
+}
+
+/// Spaces requests out so no more than a set number start each second. Clones share
+/// the limit.
+#[derive(Debug, Clone)]
+pub struct RateLimiter {
+    interval: Option<Duration>,
+    /// When the next request may start.
+    next: Arc<Mutex<Instant>>,
+}
+
+impl RateLimiter {
+    /// A limit of `per_second` requests, 0 for no limit.
+    pub fn new(per_second: u32) -> Self {
+        Self {
+            interval: (per_second > 0).then(|| Duration::from_secs(1) / per_second),
+            next: Arc::new(Mutex::new(Instant::now())),
+        }
+    }
+
+    /// Wait for the next free slot and take it.
+    pub async fn acquire(&self) {
+        let Some(interval) = self.interval else {
+            return;
+        };
+
+        let slot = {
+            let mut next = self.next.lock().expect("failed to lock rate limiter");
+            let slot = (*next).max(Instant::now());
+            *next = slot + interval;
+
+            slot
+        };
+
+        tokio::time::sleep_until(slot).await;
+    }
+}
+
+#[test]
+fn backoff_doubles_within_bounds() {
+    for attempt in 0..10 {
+        let delay = backoff(attempt, None);
+        let full = BACKOFF_MIN
+            .saturating_mul(2u32.pow(attempt))
+            .min(BACKOFF_MAX);
+
+        assert!(delay >= full / 2 && delay <= full, "{attempt}: {delay:?}");
+    }
+
+    assert_eq!(
+        backoff(0, Some(Duration::from_secs(5))),
+        Duration::from_secs(5)
+    );
+    assert_eq!(backoff(0, Some(Duration::from_secs(600))), BACKOFF_MAX);
+}
+
+#[test]
+fn retries_rate_limits_and_server_errors() {
+    assert!(is_retryable(429, true));
+    assert!(is_retryable(503, true));
+    assert!(!is_retryable(401, true));
+    assert!(!is_retryable(404, true));
+}
+
+#[test]
+fn retries_only_rate_limits_when_not_idempotent() {
+    assert!(is_retryable(429, false));
+    assert!(!is_retryable(503, false));
+    assert!(!is_retryable(500, false));
+}
@@ -1,6 +1,12 @@
 use crate::client::album::Album;
 use serde::{Deserialize, Serialize};
 
+#[derive(Default, Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct TrackSearchResults {
+    pub query: String,
+    pub tracks: Tracks,
+}
+
 #[derive(Default, Debug, Clone, PartialEq, Serialize, Deserialize)]
 pub struct Tracks {
     pub offset: i64,
@@ -28,6 +34,7 @@ pub struct Track {
     pub parental_warning: bool,
     pub performer: Option<Performer>,
     pub performers: Option<String>,
+    pub playlist_track_id: Option<i64>,
     pub position: Option<usize>,
     pub previewable: bool,
     pub purchasable: bool,
@@ -33,8 +33,12 @@ pub enum Error {
     Create,
     #[snafu(display("{message}"))]
     Api { message: String },
+    #[snafu(display("Qobuz is unavailable right now ({status}), try again later."))]
+    Unavailable { status: u16 },
     #[snafu(display("Failed to deserialize json: {message}"))]
     DeserializeJSON { message: String },
+    #[snafu(display("Failed to use resume file: {message}"))]
+    ResumeFile { message: String },
 }
 
 impl From<reqwest::Error> for Error {
@@ -1,4 +1,4 @@
-import { derived, writable } from 'svelte/store';
+import { derived, get, writable } from 'svelte/store';
 
 export const currentStatus = writable('Stopped');
 export const connected = writable(false);
@@ -13,6 +13,7 @@ export const searchResults = writable({
 export const userPlaylists = writable([])
 
 export const position = writable(0);
+export const duration = writable(0);
 const currentTrackList = writable(null);
 export const currentTrack = derived(currentTrackList, (list) => {
   return list?.queue.find((l) => l.status === "Playing")
@@ -74,8 +75,9 @@ export const positionString = derived(position, (p) => {
 })
 
 export const durationString = derived(currentTrack, (d) => {
-  const durationMinutes = Math.floor(d.durationSeconds / 60);
-  const durationSeconds = d.durationSeconds - durationMinutes * 60;
+  const total = d?.durationSeconds ?? 0;
+  const durationMinutes = Math.floor(total / 60);
+  const durationSeconds = total - durationMinutes * 60;
 
   return `${durationMinutes.toString(10).padStart(2, 0)}:${durationSeconds.toString(10).padStart(2, 0)}`
 })
@@ -165,12 +167,17 @@ export class WS {
     this.ws.send(JSON.stringify({ previous: null }));
   }
 
+  seekPercent(percent) {
+    this.ws.send(JSON.stringify({ seekPercent: Math.min(Math.max(percent, 0), 100) }));
+  }
+
   close() {
     this.ws.close()
   }
 
   skipTo(num) {
-    this.ws.send(JSON.stringify({ skipTo: { num } }))
+    const generation = get(currentTrackList)?.generation ?? 0;
+    this.ws.send(JSON.stringify({ skipTo: { num, generation } }))
   }
 
   playAlbum(album_id) {